@@ -44,6 +44,7 @@ use super::Camera;
 use super::Decoder;
 use super::FormatHint;
 use super::RawDecodeParams;
+use super::PictureStyle;
 use super::RawMetadata;
 use super::Result;
 use super::WhiteLevel;
@@ -294,7 +295,10 @@ impl<'a> Decoder for Cr2Decoder<'a> {
 
   fn raw_metadata(&self, _file: &RawSource, _params: &RawDecodeParams) -> Result<RawMetadata> {
     let exif = Exif::new(self.tiff.root_ifd())?;
-    let mdata = RawMetadata::new_with_lens(&self.camera, exif, self.get_lens_description()?.cloned());
+    let mut mdata = RawMetadata::new_with_lens(&self.camera, exif, self.get_lens_description()?.cloned());
+    if let Some(Entry { value: Value::Short(info), .. }) = self.makernote.as_ref().and_then(|mn| mn.get_entry(Cr2MakernoteTag::ProcessingInfo)) {
+      mdata.picture_style = info.get(10).and_then(|style| PictureStyle::from_canon(*style));
+    }
     Ok(mdata)
   }
 
@@ -228,6 +228,9 @@ impl<'a> Decoder for Cr3Decoder<'a> {
     }
 
     let mut mdata = RawMetadata::new_with_lens(&self.camera, exif, cr3md.lens_description.cloned());
+    if let Some(Entry { value: Value::Short(info), .. }) = self.cmt3.get_entry(Cr3MakernoteTag::ProcessingInfo) {
+      mdata.picture_style = info.get(10).and_then(|style| PictureStyle::from_canon(*style));
+    }
 
     if let Some(unique_id) = &cr3md.image_unique_id {
       // For CR3, we use the already included Makernote tag with unique image ID
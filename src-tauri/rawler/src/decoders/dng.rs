@@ -3,6 +3,7 @@ use std::cmp;
 use image::EncodableLayout;
 use image::ImageBuffer;
 use image::Rgb;
+use rayon::prelude::*;
 
 use crate::RawImage;
 use crate::alloc_image_ok;
@@ -12,9 +13,11 @@ use crate::bits::LEu32;
 use crate::bits::LookupTable;
 use crate::cfa::*;
 use crate::decoders::*;
+use crate::decompressors::float::{PREDICTOR_NONE, SampleType, decode_samples, inflate};
 use crate::decompressors::ljpeg::*;
 use crate::formats::tiff::Entry;
 use crate::formats::tiff::Rational;
+use crate::formats::tiff::SampleFormat;
 use crate::formats::tiff::Value;
 use crate::imgop::Dim2;
 use crate::imgop::Point;
@@ -51,14 +54,23 @@ impl<'a> Decoder for DngDecoder<'a> {
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
     let cpp = fetch_tiff_tag!(raw, TiffCommonTag::SamplesPerPixel).force_usize(0);
     let bits = fetch_tiff_tag!(raw, TiffCommonTag::BitsPerSample).force_u32(0);
+    let sample_type = self.get_sample_type(raw, bits);
+
+    // Float and wide integer data skips the u16 buffer and is kept as f32, so
+    // HDR values above the white level survive.
+    let float_data = match sample_type {
+      Some(sample_type) if !dummy => Some(self.decode_float(file, raw, width, height, cpp, sample_type)?),
+      _ => None,
+    };
 
     let mut image = match fetch_tiff_tag!(raw, TiffCommonTag::Compression).force_u32(0) {
+      _ if sample_type.is_some() => alloc_image_plain!(width * cpp, height, true),
       1 => self.decode_uncompressed(file, raw, width * cpp, height, dummy)?,
       7 => self.decode_compressed(file, raw, width * cpp, height, cpp, dummy)?,
       c => return Err(RawlerError::DecoderFailed(format!("Don't know how to read DNGs with compression {}", c))),
     };
 
-    if let Some(lintable) = raw.get_entry(TiffCommonTag::Linearization) {
+    if let Some(lintable) = raw.get_entry(TiffCommonTag::Linearization).filter(|_| sample_type.is_none()) {
       if bits != 8 && fetch_tiff_tag!(raw, TiffCommonTag::Compression).force_u32(0) != 1 {
         // 8 bit uncompressed data is already read delinearized
         apply_linearization(&mut image, &lintable.value, bits);
@@ -75,7 +87,12 @@ impl<'a> Decoder for DngDecoder<'a> {
     }
 
     let blacklevel = self.get_blacklevels(raw)?;
-    let whitelevel = self.get_whitelevels(raw)?.or(Some(WhiteLevel::new_bits(bits, cpp)));
+    let whitelevel = self.get_whitelevels(raw)?.or(Some(match sample_type {
+      // Float data is nominally 0.0 .. 1.0
+      Some(SampleType::Float) => WhiteLevel::new(vec![1; cpp]),
+      Some(SampleType::Uint) if bits >= 32 => WhiteLevel::new(vec![u32::MAX; cpp]),
+      _ => WhiteLevel::new_bits(bits, cpp),
+    }));
 
     let photometric = match fetch_tiff_tag!(raw, TiffCommonTag::PhotometricInt).force_u32(0) {
       1 => RawPhotometricInterpretation::BlackIsZero,
@@ -84,8 +101,23 @@ impl<'a> Decoder for DngDecoder<'a> {
       _ => todo!(),
     };
 
-    let mut image = RawImage::new(cam, image, cpp, self.get_wb()?, photometric, blacklevel, whitelevel, dummy);
+    let mut image = match float_data {
+      Some(data) => {
+        // The placeholder buffer has no samples to measure black areas on.
+        let blacklevel = blacklevel.or_else(|| Some(BlackLevel::zero(1, 1, cpp)));
+        let mut image = RawImage::new(cam, image, cpp, self.get_wb()?, photometric, blacklevel, whitelevel, true);
+        image.data = RawImageData::Float(data);
+        image
+      }
+      None => RawImage::new(cam, image, cpp, self.get_wb()?, photometric, blacklevel, whitelevel, dummy || sample_type.is_some()),
+    };
     image.orientation = orientation;
+    // Corrections the maker left to the raw converter, applied while developing.
+    for tag in [DngTag::OpcodeList2, DngTag::OpcodeList3] {
+      if let Some(entry) = raw.get_entry(tag) {
+        image.add_dng_tag(tag, entry.value.clone());
+      }
+    }
 
     Ok(image)
   }
@@ -261,7 +293,7 @@ impl<'a> DngDecoder<'a> {
           Some(e) => e.force_u32(0) & 1 != 0,
           None => false,
         };
-        !subsampled && (compression == 7 || compression == 1 || compression == 0x884c)
+        !subsampled && (compression == 7 || compression == 1 || compression == 0x884c || compression == 8)
       })
       .collect::<Vec<&IFD>>();
     
@@ -448,6 +480,91 @@ impl<'a> DngDecoder<'a> {
     Ok(result)
   }
 
+  /// `Some` for samples that don't fit into u16: floats and integers wider than 16 bits.
+  fn get_sample_type(&self, raw: &IFD, bits: u32) -> Option<SampleType> {
+    let format = raw.get_entry(TiffCommonTag::SampleFormat).map(|entry| entry.force_u16(0));
+    if format == Some(SampleFormat::IEEEFP as u16) {
+      Some(SampleType::Float)
+    } else if bits > 16 {
+      Some(SampleType::Uint)
+    } else {
+      None
+    }
+  }
+
+  /// Decode float or wide integer samples, uncompressed or deflate
+  /// compressed, from strips or tiles.
+  fn decode_float(&self, file: &RawSource, raw: &IFD, width: usize, height: usize, cpp: usize, sample_type: SampleType) -> Result<Vec<f32>> {
+    let bits = fetch_tiff_tag!(raw, TiffCommonTag::BitsPerSample).force_u32(0);
+    let compression = fetch_tiff_tag!(raw, TiffCommonTag::Compression).force_u32(0);
+    let predictor = raw.get_entry(TiffCommonTag::Predictor).map(|entry| entry.force_u16(0)).unwrap_or(PREDICTOR_NONE);
+
+    let tiled = raw.has_entry(TiffCommonTag::TileOffsets);
+    let (block_width, block_height, offsets, counts) = if tiled {
+      (
+        fetch_tiff_tag!(raw, TiffCommonTag::TileWidth).force_usize(0),
+        fetch_tiff_tag!(raw, TiffCommonTag::TileLength).force_usize(0),
+        fetch_tiff_tag!(raw, TiffCommonTag::TileOffsets),
+        fetch_tiff_tag!(raw, TiffCommonTag::TileByteCounts),
+      )
+    } else {
+      let rows = raw.get_entry(TiffCommonTag::RowsPerStrip).map(|entry| entry.force_usize(0)).unwrap_or(height);
+      (
+        width,
+        rows.clamp(1, height.max(1)),
+        fetch_tiff_tag!(raw, TiffCommonTag::StripOffsets),
+        fetch_tiff_tag!(raw, TiffCommonTag::StripByteCounts),
+      )
+    };
+    if block_width == 0 || block_height == 0 {
+      return Err(RawlerError::DecoderFailed("DNG: invalid tile or strip size".to_string()));
+    }
+    let blocks_across = width.div_ceil(block_width);
+    let blocks_down = height.div_ceil(block_height);
+    if offsets.count() as usize != blocks_across * blocks_down || counts.count() != offsets.count() {
+      return Err(RawlerError::DecoderFailed(format!(
+        "DNG: expected {} tiles or strips, found {}",
+        blocks_across * blocks_down,
+        offsets.count()
+      )));
+    }
+
+    let blocks = (0..blocks_across * blocks_down)
+      .into_par_iter()
+      .map(|index| {
+        let src = file.subview(offsets.force_u64(index), counts.force_u64(index))?;
+        let inflated;
+        let data = match compression {
+          1 => src,
+          8 | 32946 => {
+            inflated = inflate(src)?;
+            &inflated
+          }
+          c => return Err(RawlerError::DecoderFailed(format!("DNG: Don't know how to read float data with compression {}", c))),
+        };
+        // Tiles always have the full tile size, only the last strip may be shorter.
+        let rows = if tiled {
+          block_height
+        } else {
+          block_height.min(height - (index / blocks_across) * block_height)
+        };
+        decode_samples(data, block_width * cpp, rows, bits, sample_type, predictor, cpp, raw.endian).map_err(RawlerError::DecoderFailed)
+      })
+      .collect::<Result<Vec<Vec<f32>>>>()?;
+
+    let row_samples = width * cpp;
+    let mut out = vec![0.0_f32; row_samples * height];
+    for (index, block) in blocks.iter().enumerate() {
+      let (x, y) = ((index % blocks_across) * block_width * cpp, (index / blocks_across) * block_height);
+      let copy_width = (block_width * cpp).min(row_samples - x);
+      for (row, samples) in block.chunks_exact(block_width * cpp).enumerate().take(height.saturating_sub(y)) {
+        let start = (y + row) * row_samples + x;
+        out[start..start + copy_width].copy_from_slice(&samples[..copy_width]);
+      }
+    }
+    Ok(out)
+  }
+
   pub fn decode_uncompressed(&self, file: &RawSource, raw: &IFD, width: usize, height: usize, dummy: bool) -> Result<PixU16> {
     let strips: Vec<&[u8]> = raw.strip_data_rawsource(file)?;
     let strips_continous: Vec<u8>;
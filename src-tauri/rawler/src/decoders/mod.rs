@@ -28,6 +28,8 @@ use crate::formats::jfif;
 use crate::formats::tiff::GenericTiffReader;
 use crate::formats::tiff::IFD;
 use crate::formats::tiff::reader::TiffReader;
+use crate::imgop::Point;
+use crate::imgop::pixel_shift::merge_pixel_shift;
 use crate::lens::LensDescription;
 use crate::pixarray::PixU16;
 use crate::rawsource::RawSource;
@@ -214,6 +216,99 @@ pub struct RawMetadata {
   pub lens: Option<LensDescription>,
   pub unique_image_id: Option<u128>,
   pub rating: Option<u32>,
+  pub picture_style: Option<PictureStyle>,
+}
+
+/// In-camera rendering style (Fujifilm film simulation, Canon picture style)
+/// selected when the image was taken. It only describes how the camera
+/// rendered its JPEG, the raw data itself is not affected.
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+pub enum PictureStyle {
+  Standard,
+  Portrait,
+  Landscape,
+  Neutral,
+  Faithful,
+  Monochrome,
+  Sepia,
+  Provia,
+  Velvia,
+  Astia,
+  ClassicChrome,
+  ProNegHi,
+  ProNegStd,
+  Eterna,
+  ClassicNeg,
+  BleachBypass,
+  NostalgicNeg,
+  RealaAce,
+  Acros,
+}
+
+impl PictureStyle {
+  /// Map Fujifilm makernote FilmMode (0x1401) and Saturation (0x1003).
+  /// Monochrome simulations have no FilmMode but are stored as saturation setting.
+  pub fn from_fuji(film_mode: Option<u32>, saturation: Option<u32>) -> Option<Self> {
+    match saturation {
+      Some(0x300..=0x303) => return Some(Self::Monochrome),
+      Some(0x310) => return Some(Self::Sepia),
+      Some(0x500..=0x503) => return Some(Self::Acros),
+      _ => {}
+    }
+    match film_mode? {
+      0x000 => Some(Self::Provia),
+      0x100 | 0x110 | 0x130 | 0x300 => Some(Self::Portrait),
+      0x120 => Some(Self::Astia),
+      0x200 | 0x400 => Some(Self::Velvia),
+      0x500 => Some(Self::ProNegStd),
+      0x501 => Some(Self::ProNegHi),
+      0x600 => Some(Self::ClassicChrome),
+      0x700 => Some(Self::Eterna),
+      0x800 => Some(Self::ClassicNeg),
+      0x900 => Some(Self::BleachBypass),
+      0xa00 => Some(Self::NostalgicNeg),
+      0xb00 => Some(Self::RealaAce),
+      _ => None,
+    }
+  }
+
+  /// Map the Canon picture style from makernote ProcessingInfo (0x00a0).
+  /// User defined and computer styles can't be reproduced and return `None`.
+  pub fn from_canon(style: u16) -> Option<Self> {
+    match style {
+      0x01 | 0x81 | 0x87 | 0x88 => Some(Self::Standard),
+      0x02 | 0x82 => Some(Self::Portrait),
+      0x03 | 0x83 => Some(Self::Landscape),
+      0x05 | 0x84 => Some(Self::Neutral),
+      0x85 => Some(Self::Faithful),
+      0x86 => Some(Self::Monochrome),
+      _ => None,
+    }
+  }
+
+  pub fn name(&self) -> &'static str {
+    match self {
+      Self::Standard => "Standard",
+      Self::Portrait => "Portrait",
+      Self::Landscape => "Landscape",
+      Self::Neutral => "Neutral",
+      Self::Faithful => "Faithful",
+      Self::Monochrome => "Monochrome",
+      Self::Sepia => "Sepia",
+      Self::Provia => "Provia",
+      Self::Velvia => "Velvia",
+      Self::Astia => "Astia",
+      Self::ClassicChrome => "Classic Chrome",
+      Self::ProNegHi => "Pro Neg. Hi",
+      Self::ProNegStd => "Pro Neg. Std",
+      Self::Eterna => "Eterna",
+      Self::ClassicNeg => "Classic Neg.",
+      Self::BleachBypass => "Eterna Bleach Bypass",
+      Self::NostalgicNeg => "Nostalgic Neg.",
+      Self::RealaAce => "Reala Ace",
+      Self::Acros => "Acros",
+    }
+  }
 }
 
 impl RawMetadata {
@@ -225,6 +320,7 @@ impl RawMetadata {
       unique_image_id: None,
       lens: None,
       rating: None,
+      picture_style: None,
     }
   }
 
@@ -239,6 +335,7 @@ impl RawMetadata {
       unique_image_id: None,
       lens,
       rating: None,
+      picture_style: None,
     }
   }
 
@@ -298,6 +395,12 @@ pub trait Decoder: Send {
     Ok(1)
   }
 
+  /// Sensor offsets of the raw images if they form a pixel shift burst,
+  /// one per raw image. See `imgop::pixel_shift`.
+  fn pixel_shift_offsets(&self) -> Option<Vec<Point>> {
+    None
+  }
+
   /// Gives the metadata for a Raw. This is not the original data but
   /// a generalized set of metadata attributes.
   fn raw_metadata(&self, file: &RawSource, params: &RawDecodeParams) -> Result<RawMetadata>;
@@ -737,6 +840,24 @@ impl RawLoader {
     self.decode(&rawfile, &RawDecodeParams::default(), false)
   }
 
+  /// Decodes all frames of a pixel shift file and merges them into one
+  /// full color image. Returns `None` for files that are no pixel shift burst.
+  pub fn decode_pixel_shift(&self, rawfile: &RawSource, dummy: bool) -> Result<Option<RawImage>> {
+    let decoder = self.get_decoder(rawfile)?;
+    let Some(offsets) = decoder.pixel_shift_offsets() else {
+      return Ok(None);
+    };
+    match panic::catch_unwind(AssertUnwindSafe(|| {
+      let frames = (0..offsets.len())
+        .map(|image_index| decoder.raw_image(rawfile, &RawDecodeParams { image_index }, dummy))
+        .collect::<Result<Vec<_>>>()?;
+      merge_pixel_shift(&frames, &offsets)
+    })) {
+      Ok(val) => val.map(Some),
+      Err(_) => Err(RawlerError::DecoderFailed(format!("Caught a panic while decoding.{}", BUG))),
+    }
+  }
+
   /// Decodes a file into a RawImage
   pub fn raw_image_count_file(&self, path: &Path) -> Result<usize> {
     let rawfile = RawSource::new(path).map_err(|err| RawlerError::with_io_error("raw_image_count_file()", path, err))?;
@@ -25,6 +25,8 @@ use crate::formats::tiff::IFD;
 use crate::formats::tiff::Value;
 use crate::formats::tiff::ifd::OffsetMode;
 use crate::formats::tiff::reader::TiffReader;
+use crate::imgop::Point;
+use crate::imgop::pixel_shift::four_shot_offsets;
 use crate::lens::LensDescription;
 use crate::lens::LensResolver;
 use crate::packed::*;
@@ -95,7 +97,19 @@ impl<'a> Decoder for PefDecoder<'a> {
     FormatDump::Pef(PefFormat { tiff: self.tiff.clone() })
   }
 
-  fn raw_image(&self, file: &RawSource, _params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
+  fn raw_image_count(&self) -> Result<usize> {
+    Ok(if self.is_pixel_shift() { self.raw_ifds().len() } else { 1 })
+  }
+
+  fn pixel_shift_offsets(&self) -> Option<Vec<Point>> {
+    if self.is_pixel_shift() && self.raw_ifds().len() == 4 {
+      Some(four_shot_offsets())
+    } else {
+      None
+    }
+  }
+
+  fn raw_image(&self, file: &RawSource, params: &RawDecodeParams, dummy: bool) -> Result<RawImage> {
     //for (i, ifd) in self.tiff.chains().iter().enumerate() {
     //  eprintln!("IFD {}", i);
     //  for line in ifd.dump::<crate::tags::LegacyTiffRootTag>(10) {
@@ -103,9 +117,9 @@ impl<'a> Decoder for PefDecoder<'a> {
     //  }
     //}
 
-    let raw = self
-      .tiff
-      .find_first_ifd_with_tag(TiffCommonTag::StripOffsets)
+    let raw = *self
+      .raw_ifds()
+      .get(params.image_index)
       .ok_or_else(|| RawlerError::unsupported(&self.camera, "Unable to find IFD"))?;
     let width = fetch_tiff_tag!(raw, TiffCommonTag::ImageWidth).force_usize(0);
     let height = fetch_tiff_tag!(raw, TiffCommonTag::ImageLength).force_usize(0);
@@ -198,6 +212,27 @@ impl<'a> Decoder for PefDecoder<'a> {
 }
 
 impl<'a> PefDecoder<'a> {
+  /// IFDs with sensor data, pixel shift files have one for each frame.
+  fn raw_ifds(&self) -> Vec<&IFD> {
+    let ifds = self.tiff.find_ifds_with_tag(TiffCommonTag::StripOffsets);
+    let size = |ifd: &IFD| {
+      (
+        ifd.get_entry(TiffCommonTag::ImageWidth).map(|entry| entry.force_usize(0)),
+        ifd.get_entry(TiffCommonTag::ImageLength).map(|entry| entry.force_usize(0)),
+      )
+    };
+    match ifds.first().map(|first| size(first)) {
+      Some(first_size) => ifds.into_iter().filter(|ifd| size(ifd) == first_size).collect(),
+      None => ifds,
+    }
+  }
+
+  /// The pixel shift info is only written by bodies with that mode,
+  /// which store the frames of a burst as separate raw IFDs.
+  fn is_pixel_shift(&self) -> bool {
+    self.makernote.get_entry(PefMakernote::PixelShiftInfo).is_some() && self.raw_ifds().len() > 1
+  }
+
   fn get_wb(&self) -> Result<[f32; 4]> {
     match self.makernote.get_entry(PefMakernote::WhitePoint) {
       Some(wb) => {
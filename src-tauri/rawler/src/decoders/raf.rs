@@ -41,6 +41,7 @@ use super::Camera;
 use super::Decoder;
 use super::FormatHint;
 use super::RawDecodeParams;
+use super::PictureStyle;
 use super::RawMetadata;
 
 mod dbp;
@@ -410,7 +411,11 @@ impl<'a> Decoder for RafDecoder<'a> {
         _ => None,
       });
     }
-    let mdata = RawMetadata::new(&self.camera, exif);
+    let mut mdata = RawMetadata::new(&self.camera, exif);
+    mdata.picture_style = PictureStyle::from_fuji(
+      self.makernotes.get_entry(RafMakernotes::FilmMode).map(|entry| entry.value.force_u32(0)),
+      self.makernotes.get_entry(RafMakernotes::Saturation).map(|entry| entry.value.force_u32(0)),
+    );
     Ok(mdata)
   }
 
@@ -0,0 +1,173 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Floating point and wide integer samples
+//!
+//! DNG 1.4 allows 16, 24 and 32 bit floating point samples, which tools use
+//! for merged HDR images. These are usually deflate compressed with the
+//! floating point predictor: every row is split into byte planes, most
+//! significant byte first, and the planes are delta coded as one stream.
+
+use std::io::Read;
+
+use libflate::zlib::Decoder;
+
+use crate::bits::Endian;
+
+pub const PREDICTOR_NONE: u16 = 1;
+pub const PREDICTOR_FLOATING_POINT: u16 = 3;
+pub const PREDICTOR_FLOATING_POINT_X2: u16 = 34894;
+pub const PREDICTOR_FLOATING_POINT_X4: u16 = 34895;
+
+/// Interpretation of the sample bits, from the TIFF SampleFormat tag
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SampleType {
+  Uint,
+  Float,
+}
+
+/// Convert an IEEE half precision float.
+pub fn f16_to_f32(bits: u16) -> f32 {
+  let sign = ((bits >> 15) as u32) << 31;
+  let exponent = ((bits >> 10) & 0x1f) as u32;
+  let mantissa = (bits & 0x3ff) as u32;
+  match exponent {
+    0 => {
+      // Zero or subnormal
+      let value = mantissa as f32 * 2.0_f32.powi(-24);
+      if sign != 0 {
+        -value
+      } else {
+        value
+      }
+    }
+    0x1f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 13)),
+    _ => f32::from_bits(sign | ((exponent + 127 - 15) << 23) | (mantissa << 13)),
+  }
+}
+
+/// Convert a 24 bit float as defined by DNG: 1 sign bit, 7 exponent bits with
+/// a bias of 63 and 16 mantissa bits.
+pub fn f24_to_f32(bits: u32) -> f32 {
+  let sign = ((bits >> 23) & 1) << 31;
+  let exponent = (bits >> 16) & 0x7f;
+  let mantissa = bits & 0xffff;
+  match exponent {
+    0 => {
+      // Zero or subnormal
+      let value = mantissa as f32 * 2.0_f32.powi(-78);
+      if sign != 0 {
+        -value
+      } else {
+        value
+      }
+    }
+    0x7f => f32::from_bits(sign | 0x7f80_0000 | (mantissa << 7)),
+    _ => f32::from_bits(sign | ((exponent + 127 - 63) << 23) | (mantissa << 7)),
+  }
+}
+
+/// Decompress a deflate (zlib) compressed strip or tile.
+pub fn inflate(src: &[u8]) -> std::io::Result<Vec<u8>> {
+  let mut decoder = Decoder::new(src)?;
+  let mut out = Vec::new();
+  decoder.read_to_end(&mut out)?;
+  Ok(out)
+}
+
+fn convert(value: u32, sample_type: SampleType, bytes: usize) -> f32 {
+  match (sample_type, bytes) {
+    (SampleType::Float, 2) => f16_to_f32(value as u16),
+    (SampleType::Float, 3) => f24_to_f32(value),
+    (SampleType::Float, _) => f32::from_bits(value),
+    (SampleType::Uint, _) => value as f32,
+  }
+}
+
+/// Decode `rows` rows of `row_samples` samples each from uncompressed (or
+/// already inflated) data. `cpp` is needed for the stride of the predictor.
+pub fn decode_samples(data: &[u8], row_samples: usize, rows: usize, bps: u32, sample_type: SampleType, predictor: u16, cpp: usize, endian: Endian) -> Result<Vec<f32>, String> {
+  let bytes = match (sample_type, bps) {
+    (SampleType::Float, 16 | 24 | 32) | (SampleType::Uint, 24 | 32) => bps as usize / 8,
+    _ => return Err(format!("Unsupported {:?} samples with {} bits", sample_type, bps)),
+  };
+  let row_len = row_samples * bytes;
+  if row_len == 0 || data.len() < row_len * rows {
+    return Err(format!("Sample data too short: {} bytes for {} rows of {} bytes", data.len(), rows, row_len));
+  }
+
+  let mut out = Vec::with_capacity(row_samples * rows);
+  match predictor {
+    PREDICTOR_NONE => {
+      for sample in data[..row_len * rows].chunks_exact(bytes) {
+        let value = match endian {
+          Endian::Big => sample.iter().fold(0_u32, |acc, b| (acc << 8) | *b as u32),
+          Endian::Little => sample.iter().rev().fold(0_u32, |acc, b| (acc << 8) | *b as u32),
+        };
+        out.push(convert(value, sample_type, bytes));
+      }
+    }
+    PREDICTOR_FLOATING_POINT | PREDICTOR_FLOATING_POINT_X2 | PREDICTOR_FLOATING_POINT_X4 if sample_type == SampleType::Float => {
+      let stride = cpp
+        * match predictor {
+          PREDICTOR_FLOATING_POINT_X2 => 2,
+          PREDICTOR_FLOATING_POINT_X4 => 4,
+          _ => 1,
+        };
+      let mut planes = vec![0_u8; row_len];
+      for row in data[..row_len * rows].chunks_exact(row_len) {
+        planes.copy_from_slice(row);
+        for i in stride..row_len {
+          planes[i] = planes[i].wrapping_add(planes[i - stride]);
+        }
+        for sample in 0..row_samples {
+          let value = (0..bytes).fold(0_u32, |acc, plane| (acc << 8) | planes[plane * row_samples + sample] as u32);
+          out.push(convert(value, sample_type, bytes));
+        }
+      }
+    }
+    _ => return Err(format!("Unsupported predictor {} for {:?} samples", predictor, sample_type)),
+  }
+  Ok(out)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn half_floats() {
+    assert_eq!(f16_to_f32(0x3c00), 1.0);
+    assert_eq!(f16_to_f32(0xc000), -2.0);
+    assert_eq!(f16_to_f32(0x7bff), 65504.0);
+    assert_eq!(f16_to_f32(0x0001), 2.0_f32.powi(-24));
+    assert!(f16_to_f32(0x7c00).is_infinite());
+  }
+
+  #[test]
+  fn dng_24_bit_floats() {
+    assert_eq!(f24_to_f32(0x3f_0000), 1.0);
+    assert_eq!(f24_to_f32(0xc0_8000), -3.0);
+    assert_eq!(f24_to_f32(0x3e_0000), 0.5);
+  }
+
+  #[test]
+  fn floating_point_predictor() {
+    let values = [1.0_f32, 2.5, -0.5, 100.0, 0.125, 7.0];
+    // Encode like a writer would: byte planes, most significant first, then delta coding.
+    let bytes: Vec<[u8; 4]> = values.iter().map(|v| v.to_be_bytes()).collect();
+    let mut planes: Vec<u8> = (0..4).flat_map(|plane| bytes.iter().map(move |b| b[plane])).collect();
+    for i in (1..planes.len()).rev() {
+      planes[i] = planes[i].wrapping_sub(planes[i - 1]);
+    }
+    let decoded = decode_samples(&planes, values.len(), 1, 32, SampleType::Float, PREDICTOR_FLOATING_POINT, 1, Endian::Little).unwrap();
+    assert_eq!(decoded, values);
+  }
+
+  #[test]
+  fn uncompressed_samples_follow_the_file_endianness() {
+    let data = [0x00, 0x3c, 0x00, 0x40];
+    let decoded = decode_samples(&data, 2, 1, 16, SampleType::Float, PREDICTOR_NONE, 1, Endian::Little).unwrap();
+    assert_eq!(decoded, [1.0, 2.0]);
+    assert!(decode_samples(&data, 2, 2, 16, SampleType::Float, PREDICTOR_NONE, 1, Endian::Little).is_err());
+  }
+}
@@ -2,5 +2,6 @@
 // Copyright 2021 Daniel Vogelbacher <daniel@chaospixel.com>
 
 pub mod crx;
+pub mod float;
 pub mod ljpeg;
 pub mod radc;
@@ -10,10 +10,10 @@ use image::DynamicImage;
 
 use crate::{
   RawImage,
-  decoders::{Decoder, RawDecodeParams, WellKnownIFD},
+  decoders::{Decoder, RawDecodeParams, RawMetadata, WellKnownIFD},
   dng::{DNG_VERSION_V1_4, PREVIEW_JPEG_QUALITY, original::OriginalCompressed, writer::DngWriter},
   formats::tiff::Entry,
-  imgop::develop::RawDevelop,
+  imgop::develop::{Intermediate, RawDevelop},
   rawsource::RawSource,
   tags::{DngTag, ExifTag, TiffCommonTag},
 };
@@ -214,6 +214,53 @@ where
   Ok(())
 }
 
+/// Write an already decoded raw image into DNG, e.g. a merged pixel shift burst
+/// that has no single source file to convert.
+pub fn write_raw_image<W>(rawimage: &RawImage, metadata: &RawMetadata, dng: &mut W, params: &ConvertParams) -> crate::Result<()>
+where
+  W: Write + Seek + Send,
+{
+  let mut dng = DngWriter::new(dng, DNG_VERSION_V1_4)?;
+
+  let mut raw = if params.thumbnail { dng.subframe(0) } else { dng.subframe_on_root(0) };
+  raw.raw_image(rawimage, params.crop, params.compression, params.photometric_conversion, params.predictor)?;
+  raw.finalize()?;
+
+  if params.preview || params.thumbnail {
+    match RawDevelop::default().develop_intermediate(rawimage).map(Intermediate::to_dynamic_image) {
+      Ok(Some(image)) => {
+        if params.preview {
+          let mut preview = dng.subframe(1);
+          preview.preview(&image, PREVIEW_JPEG_QUALITY)?;
+          preview.finalize()?;
+        }
+        if params.thumbnail {
+          dng.thumbnail(&image)?;
+        }
+      }
+      Ok(None) => log::warn!("Failed to convert developed preview, continue anyway"),
+      Err(err) => log::warn!("Failed to develop preview image, continue anyway: {:?}", err),
+    }
+  }
+
+  dng.load_base_tags(rawimage)?;
+  dng.load_metadata(metadata)?;
+  if !dng.root_ifd().contains(ExifTag::Orientation) {
+    dng.root_ifd_mut().add_tag(ExifTag::Orientation, rawimage.orientation.to_u16());
+  }
+  if let Some(artist) = &params.artist {
+    dng.root_ifd_mut().add_tag(TiffCommonTag::Artist, artist);
+  }
+  dng.root_ifd_mut().add_tag(TiffCommonTag::Software, &params.software);
+  dng
+    .root_ifd_mut()
+    .add_tag(ExifTag::ModifyDate, chrono::Local::now().format("%Y:%m:%d %H:%M:%S").to_string());
+
+  dng.close()?;
+
+  Ok(())
+}
+
 fn generate_preview(rawfile: &RawSource, decoder: &dyn Decoder, rawimage: &RawImage, params: &RawDecodeParams) -> crate::Result<DynamicImage> {
   match decoder.full_image(rawfile, params)? {
     Some(image) => Ok(image),
@@ -0,0 +1,357 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! DNG opcode lists
+//!
+//! Phones and drones write lens corrections as opcodes instead of applying
+//! them: OpcodeList2 is applied to the linearized raw data, OpcodeList3 to
+//! the demosaiced image. Opcode areas are relative to the active area.
+//! GainMap (vignetting and color shading) and WarpRectilinear (distortion
+//! and lateral CA) are supported, other opcodes are skipped.
+
+use rayon::prelude::*;
+
+const GAIN_MAP: u32 = 9;
+const WARP_RECTILINEAR: u32 = 1;
+
+/// Opcode may be skipped if it is not supported
+const FLAG_OPTIONAL: u32 = 1;
+
+/// Per plane gains on a regular grid, applied to a part of the image.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GainMap {
+  pub top: usize,
+  pub left: usize,
+  pub bottom: usize,
+  pub right: usize,
+  pub plane: usize,
+  pub planes: usize,
+  pub row_pitch: usize,
+  pub col_pitch: usize,
+  pub points_v: usize,
+  pub points_h: usize,
+  pub spacing_v: f64,
+  pub spacing_h: f64,
+  pub origin_v: f64,
+  pub origin_h: f64,
+  pub map_planes: usize,
+  /// Gains in row, column, plane order
+  pub gains: Vec<f32>,
+}
+
+/// Radial and tangential distortion around an optical center.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WarpRectilinear {
+  /// kr0..kr3, kt0, kt1 for each plane, or one set for all planes
+  pub coefficients: Vec<[f64; 6]>,
+  /// Optical center relative to the image, 0.5 is the middle
+  pub center_h: f64,
+  pub center_v: f64,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Opcode {
+  GainMap(GainMap),
+  WarpRectilinear(WarpRectilinear),
+}
+
+/// Big endian reader over the opcode parameters, as mandated by the spec.
+struct Reader<'a> {
+  data: &'a [u8],
+  pos: usize,
+}
+
+impl<'a> Reader<'a> {
+  fn bytes(&mut self, len: usize) -> Result<&'a [u8], String> {
+    let bytes = self.data.get(self.pos..self.pos + len).ok_or("Opcode list is truncated")?;
+    self.pos += len;
+    Ok(bytes)
+  }
+
+  fn u32(&mut self) -> Result<u32, String> {
+    Ok(u32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+  }
+
+  fn usize(&mut self) -> Result<usize, String> {
+    self.u32().map(|v| v as usize)
+  }
+
+  fn f32(&mut self) -> Result<f32, String> {
+    Ok(f32::from_be_bytes(self.bytes(4)?.try_into().unwrap()))
+  }
+
+  fn f64(&mut self) -> Result<f64, String> {
+    Ok(f64::from_be_bytes(self.bytes(8)?.try_into().unwrap()))
+  }
+}
+
+fn parse_gain_map(r: &mut Reader) -> Result<GainMap, String> {
+  let mut map = GainMap {
+    top: r.usize()?,
+    left: r.usize()?,
+    bottom: r.usize()?,
+    right: r.usize()?,
+    plane: r.usize()?,
+    planes: r.usize()?,
+    row_pitch: r.usize()?.max(1),
+    col_pitch: r.usize()?.max(1),
+    points_v: r.usize()?,
+    points_h: r.usize()?,
+    spacing_v: r.f64()?,
+    spacing_h: r.f64()?,
+    origin_v: r.f64()?,
+    origin_h: r.f64()?,
+    map_planes: r.usize()?,
+    gains: Vec::new(),
+  };
+  let count = map.points_v * map.points_h * map.map_planes;
+  if count == 0 {
+    return Err("GainMap without gains".into());
+  }
+  map.gains = (0..count).map(|_| r.f32()).collect::<Result<_, _>>()?;
+  Ok(map)
+}
+
+fn parse_warp_rectilinear(r: &mut Reader) -> Result<WarpRectilinear, String> {
+  let planes = r.usize()?;
+  if planes == 0 || planes > 4 {
+    return Err(format!("WarpRectilinear with {} planes", planes));
+  }
+  let coefficients = (0..planes).map(|_| Ok([r.f64()?, r.f64()?, r.f64()?, r.f64()?, r.f64()?, r.f64()?])).collect::<Result<_, String>>()?;
+  Ok(WarpRectilinear { coefficients, center_h: r.f64()?, center_v: r.f64()? })
+}
+
+/// Parse the content of an OpcodeList tag. Unsupported opcodes are skipped.
+pub fn parse_opcode_list(data: &[u8]) -> Result<Vec<Opcode>, String> {
+  let mut r = Reader { data, pos: 0 };
+  let count = r.u32()?;
+  let mut opcodes = Vec::new();
+  for _ in 0..count {
+    let id = r.u32()?;
+    let _version = r.u32()?;
+    let flags = r.u32()?;
+    let len = r.usize()?;
+    let mut params = Reader { data: r.bytes(len)?, pos: 0 };
+    match id {
+      GAIN_MAP => opcodes.push(Opcode::GainMap(parse_gain_map(&mut params)?)),
+      WARP_RECTILINEAR => opcodes.push(Opcode::WarpRectilinear(parse_warp_rectilinear(&mut params)?)),
+      _ if flags & FLAG_OPTIONAL != 0 => log::debug!("Skipping optional DNG opcode {}", id),
+      _ => log::warn!("Unsupported DNG opcode {}, the image may look different than intended", id),
+    }
+  }
+  Ok(opcodes)
+}
+
+/// Image data an opcode works on: `width` x `height` pixels of `cpp`
+/// samples, of which the area at `offset` with `bounds` size is the one the
+/// opcode coordinates refer to.
+pub struct OpcodeTarget<'a> {
+  pub data: &'a mut [f32],
+  pub width: usize,
+  pub height: usize,
+  pub cpp: usize,
+  pub offset: (usize, usize),
+  pub bounds: (usize, usize),
+}
+
+impl GainMap {
+  /// Bilinear gain at a position relative to the image bounds.
+  fn gain_at(&self, v: f64, h: f64, map_plane: usize) -> f32 {
+    let index = |pos: f64, origin: f64, spacing: f64, points: usize| {
+      let i = if spacing > 0.0 { (pos - origin) / spacing } else { 0.0 };
+      let i = i.clamp(0.0, (points - 1) as f64);
+      let i0 = (i.floor() as usize).min(points - 1);
+      (i0, (i0 + 1).min(points - 1), (i - i0 as f64) as f32)
+    };
+    let (r0, r1, fr) = index(v, self.origin_v, self.spacing_v, self.points_v);
+    let (c0, c1, fc) = index(h, self.origin_h, self.spacing_h, self.points_h);
+    let at = |r: usize, c: usize| self.gains[(r * self.points_h + c) * self.map_planes + map_plane];
+    let top = at(r0, c0) * (1.0 - fc) + at(r0, c1) * fc;
+    let bottom = at(r1, c0) * (1.0 - fc) + at(r1, c1) * fc;
+    top * (1.0 - fr) + bottom * fr
+  }
+
+  pub fn apply(&self, target: &mut OpcodeTarget) {
+    let (bounds_w, bounds_h) = target.bounds;
+    let (width, cpp) = (target.width, target.cpp);
+    let bottom = self.bottom.min(bounds_h).min(target.height.saturating_sub(target.offset.1));
+    let right = self.right.min(bounds_w).min(width.saturating_sub(target.offset.0));
+    let planes = self.plane..(self.plane + self.planes).min(cpp);
+    target.data.par_chunks_exact_mut(width * cpp).enumerate().for_each(|(y, line)| {
+      let Some(row) = y.checked_sub(target.offset.1).filter(|row| *row >= self.top && *row < bottom && (row - self.top) % self.row_pitch == 0) else {
+        return;
+      };
+      let v = row as f64 / bounds_h as f64;
+      for col in (self.left..right).step_by(self.col_pitch) {
+        let h = col as f64 / bounds_w as f64;
+        for plane in planes.clone() {
+          let map_plane = (plane - self.plane).min(self.map_planes - 1);
+          line[(col + target.offset.0) * cpp + plane] *= self.gain_at(v, h, map_plane);
+        }
+      }
+    });
+  }
+}
+
+impl WarpRectilinear {
+  /// Source position for the output pixel at `x`, `y` in the bounds.
+  fn source(&self, x: f64, y: f64, plane: usize, center: (f64, f64), m: f64) -> (f64, f64) {
+    let [kr0, kr1, kr2, kr3, kt0, kt1] = self.coefficients[plane.min(self.coefficients.len() - 1)];
+    let dx = (x - center.0) / m;
+    let dy = (y - center.1) / m;
+    let r2 = dx * dx + dy * dy;
+    let f = kr0 + r2 * (kr1 + r2 * (kr2 + r2 * kr3));
+    let dxt = kt0 * 2.0 * dx * dy + kt1 * (r2 + 2.0 * dx * dx);
+    let dyt = kt1 * 2.0 * dx * dy + kt0 * (r2 + 2.0 * dy * dy);
+    (center.0 + m * (dx * f + dxt), center.1 + m * (dy * f + dyt))
+  }
+
+  pub fn apply(&self, target: &mut OpcodeTarget) {
+    let (bounds_w, bounds_h) = target.bounds;
+    let (width, cpp, offset) = (target.width, target.cpp, target.offset);
+    if bounds_w < 2 || bounds_h < 2 {
+      return;
+    }
+    let center = (self.center_h * (bounds_w - 1) as f64, self.center_v * (bounds_h - 1) as f64);
+    // Distance from the center to the farthest corner
+    let m = [(0.0, 0.0), ((bounds_w - 1) as f64, 0.0), (0.0, (bounds_h - 1) as f64), ((bounds_w - 1) as f64, (bounds_h - 1) as f64)]
+      .iter()
+      .map(|(x, y)| ((x - center.0).powi(2) + (y - center.1).powi(2)).sqrt())
+      .fold(0.0, f64::max);
+    if m == 0.0 {
+      return;
+    }
+
+    let source = target.data.to_vec();
+    let sample = |x: f64, y: f64, plane: usize| -> f32 {
+      let x = x.clamp(0.0, (bounds_w - 1) as f64);
+      let y = y.clamp(0.0, (bounds_h - 1) as f64);
+      let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+      let (x1, y1) = ((x0 + 1).min(bounds_w - 1), (y0 + 1).min(bounds_h - 1));
+      let (fx, fy) = ((x - x0 as f64) as f32, (y - y0 as f64) as f32);
+      let at = |x: usize, y: usize| source[((y + offset.1) * width + x + offset.0) * cpp + plane];
+      let top = at(x0, y0) * (1.0 - fx) + at(x1, y0) * fx;
+      let bottom = at(x0, y1) * (1.0 - fx) + at(x1, y1) * fx;
+      top * (1.0 - fy) + bottom * fy
+    };
+
+    target.data.par_chunks_exact_mut(width * cpp).enumerate().for_each(|(y, line)| {
+      let Some(row) = y.checked_sub(offset.1).filter(|row| *row < bounds_h) else {
+        return;
+      };
+      for col in 0..bounds_w.min(width.saturating_sub(offset.0)) {
+        for plane in 0..cpp {
+          let (sx, sy) = self.source(col as f64, row as f64, plane, center, m);
+          line[(col + offset.0) * cpp + plane] = sample(sx, sy, plane);
+        }
+      }
+    });
+  }
+}
+
+impl Opcode {
+  /// The opcode for an image that was scaled by `sx`, `sy`, e.g. a quarter
+  /// resolution preview. Warps are relative to the image size already.
+  pub fn scaled(&self, sx: f64, sy: f64) -> Opcode {
+    match self {
+      Opcode::GainMap(map) => Opcode::GainMap(GainMap {
+        top: (map.top as f64 * sy) as usize,
+        left: (map.left as f64 * sx) as usize,
+        bottom: (map.bottom as f64 * sy).ceil() as usize,
+        right: (map.right as f64 * sx).ceil() as usize,
+        row_pitch: ((map.row_pitch as f64 * sy) as usize).max(1),
+        col_pitch: ((map.col_pitch as f64 * sx) as usize).max(1),
+        ..map.clone()
+      }),
+      Opcode::WarpRectilinear(_) => self.clone(),
+    }
+  }
+
+  pub fn apply(&self, target: &mut OpcodeTarget) {
+    match self {
+      Opcode::GainMap(map) => map.apply(target),
+      Opcode::WarpRectilinear(warp) => warp.apply(target),
+    }
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn opcode(id: u32, flags: u32, params: &[u8]) -> Vec<u8> {
+    [id, 3, flags, params.len() as u32].iter().flat_map(|v| v.to_be_bytes()).chain(params.iter().copied()).collect()
+  }
+
+  fn gain_map_params(gains: &[f32]) -> Vec<u8> {
+    let mut params: Vec<u8> = [0_u32, 0, 2, 4, 0, 1, 1, 1, 1, 2].iter().flat_map(|v| v.to_be_bytes()).collect();
+    params.extend([1.0_f64, 1.0, 0.0, 0.0].iter().flat_map(|v| v.to_be_bytes()));
+    params.extend(1_u32.to_be_bytes());
+    params.extend(gains.iter().flat_map(|v| v.to_be_bytes()));
+    params
+  }
+
+  #[test]
+  fn unknown_optional_opcodes_are_skipped() {
+    let mut list = 2_u32.to_be_bytes().to_vec();
+    list.extend(opcode(4, FLAG_OPTIONAL, &[0; 8]));
+    list.extend(opcode(GAIN_MAP, 0, &gain_map_params(&[1.0, 2.0])));
+    let opcodes = parse_opcode_list(&list).unwrap();
+    assert_eq!(opcodes.len(), 1);
+    assert!(matches!(&opcodes[0], Opcode::GainMap(map) if map.gains == [1.0, 2.0]));
+    assert!(parse_opcode_list(&list[..list.len() - 2]).is_err());
+  }
+
+  #[test]
+  fn gain_map_interpolates_across_the_area() {
+    let mut list = 1_u32.to_be_bytes().to_vec();
+    list.extend(opcode(GAIN_MAP, 0, &gain_map_params(&[1.0, 2.0])));
+    let opcodes = parse_opcode_list(&list).unwrap();
+    // A masked border of one pixel on the left, the map covers the 4x2 area next to it.
+    let mut data = vec![1.0; 5 * 2];
+    let mut target = OpcodeTarget {
+      data: &mut data,
+      width: 5,
+      height: 2,
+      cpp: 1,
+      offset: (1, 0),
+      bounds: (4, 2),
+    };
+    opcodes[0].apply(&mut target);
+    assert_eq!(data, [1.0, 1.0, 1.25, 1.5, 1.75, 1.0, 1.0, 1.25, 1.5, 1.75]);
+  }
+
+  #[test]
+  fn identity_warp_keeps_the_image() {
+    let warp = WarpRectilinear {
+      coefficients: vec![[1.0, 0.0, 0.0, 0.0, 0.0, 0.0]],
+      center_h: 0.5,
+      center_v: 0.5,
+    };
+    let mut data: Vec<f32> = (0..12).map(|v| v as f32).collect();
+    let original = data.clone();
+    warp.apply(&mut OpcodeTarget {
+      data: &mut data,
+      width: 4,
+      height: 3,
+      cpp: 1,
+      offset: (0, 0),
+      bounds: (4, 3),
+    });
+    for (a, b) in data.iter().zip(original.iter()) {
+      assert!((a - b).abs() < 1e-4);
+    }
+  }
+
+  #[test]
+  fn barrel_warp_samples_further_out() {
+    // kr1 > 0 reads from further out, which shrinks the content towards the center.
+    let warp = WarpRectilinear {
+      coefficients: vec![[1.0, 0.1, 0.0, 0.0, 0.0, 0.0]],
+      center_h: 0.5,
+      center_v: 0.5,
+    };
+    let (x, y) = warp.source(9.0, 4.5, 0, (4.5, 4.5), 4.5_f64.hypot(4.5));
+    assert!(x > 9.0);
+    assert!((y - 4.5).abs() < 1e-9);
+  }
+}
@@ -248,4 +248,36 @@ mod tests {
 
     Ok(())
   }
+
+  #[test]
+  fn rewrite_tiff_with_exif_keeps_strips() -> std::result::Result<(), Box<dyn std::error::Error>> {
+    let pixels: Vec<u8> = (0..48).collect();
+    let mut output = Cursor::new(Vec::new());
+    let mut tiff = TiffWriter::new(&mut output)?;
+    let offset = tiff.write_data(&pixels)?;
+    let mut dir = tiff.new_directory();
+    dir.add_tag(TiffCommonTag::ImageWidth, 4_u32);
+    dir.add_tag(TiffCommonTag::ImageLength, 4_u32);
+    dir.add_tag(TiffCommonTag::BitsPerSample, [8_u16, 8, 8]);
+    dir.add_tag(TiffCommonTag::SamplesPerPixel, 3_u16);
+    dir.add_tag(TiffCommonTag::StripOffsets, offset);
+    dir.add_tag(TiffCommonTag::StripByteCounts, pixels.len() as u32);
+    tiff.build(dir)?;
+
+    let exif = crate::exif::Exif {
+      artist: Some("Somebody".to_string()),
+      fnumber: Some(Rational::new(28, 10)),
+      ..Default::default()
+    };
+    let rewritten = writer::rewrite_tiff_with_exif(&output.into_inner(), &exif)?;
+
+    let reader = GenericTiffReader::new_with_buffer(&rewritten, 0, 0, Some(0))?;
+    assert_eq!(reader.root_ifd().strip_data(&mut Cursor::new(&rewritten))?, vec![pixels]);
+    assert!(reader.root_ifd().get_entry(TiffCommonTag::BitsPerSample).is_some());
+    let parsed = crate::exif::Exif::new(reader.root_ifd())?;
+    assert_eq!(parsed.artist, exif.artist);
+    assert_eq!(parsed.fnumber, exif.fnumber);
+
+    Ok(())
+  }
 }
@@ -3,7 +3,7 @@
 
 use std::{
   collections::BTreeMap,
-  io::{Seek, SeekFrom, Write},
+  io::{Cursor, Seek, SeekFrom, Write},
 };
 
 use byteorder::{LittleEndian, NativeEndian, WriteBytesExt};
@@ -14,10 +14,10 @@ use weezl::LzwError;
 use crate::{
   exif::Exif,
   imgop::Dim2,
-  tags::{ExifGpsTag, ExifTag, TiffTag},
+  tags::{ExifGpsTag, ExifTag, TiffCommonTag, TiffTag},
 };
 
-use super::{Entry, Result, TIFF_MAGIC, TiffError, Value};
+use super::{Entry, GenericTiffReader, Result, TIFF_MAGIC, TiffError, Value, reader::TiffReader};
 
 pub struct TiffWriter<W> {
   ifd_location: u64,
@@ -282,7 +282,7 @@ impl crate::decoders::RawMetadata {
   where
     W: Write + Seek,
   {
-    self.fill_exif_root(tiff, root_ifd)?;
+    Self::fill_exif_root(&self.exif, tiff, root_ifd)?;
     Self::fill_exif_ifd(&self.exif, exif_ifd)?;
     Ok(())
   }
@@ -333,16 +333,16 @@ impl crate::decoders::RawMetadata {
     Ok(())
   }
 
-  fn fill_exif_root<W>(&self, tiff: &mut TiffWriter<W>, root_ifd: &mut DirectoryWriter) -> Result<()>
+  pub fn fill_exif_root<W>(exif: &Exif, tiff: &mut TiffWriter<W>, root_ifd: &mut DirectoryWriter) -> Result<()>
   where
     W: Write + Seek,
   {
-    transfer_entry(root_ifd, ExifTag::Orientation, &self.exif.orientation)?;
-    transfer_entry(root_ifd, ExifTag::ModifyDate, &self.exif.modify_date)?;
-    transfer_entry(root_ifd, ExifTag::Copyright, &self.exif.copyright)?;
-    transfer_entry(root_ifd, ExifTag::Artist, &self.exif.artist)?;
+    transfer_entry(root_ifd, ExifTag::Orientation, &exif.orientation)?;
+    transfer_entry(root_ifd, ExifTag::ModifyDate, &exif.modify_date)?;
+    transfer_entry(root_ifd, ExifTag::Copyright, &exif.copyright)?;
+    transfer_entry(root_ifd, ExifTag::Artist, &exif.artist)?;
 
-    if let Some(gps) = &self.exif.gps {
+    if let Some(gps) = &exif.gps {
       let gps_offset = {
         let mut gps_ifd = DirectoryWriter::new();
         transfer_entry(&mut gps_ifd, ExifGpsTag::GPSVersionID, &gps.gps_version_id)?;
@@ -388,6 +388,53 @@ impl crate::decoders::RawMetadata {
   }
 }
 
+/// Rewrite a strip-based TIFF so that its first IFD carries the given EXIF data.
+///
+/// Only the first image is kept and strip data is copied unchanged, so the input
+/// must be in native byte order, which is what TIFF encoders on this machine write.
+/// Existing EXIF, GPS and SubIFD pointers are dropped.
+pub fn rewrite_tiff_with_exif(tiff_data: &[u8], exif: &Exif) -> Result<Vec<u8>> {
+  let reader = GenericTiffReader::new_with_buffer(tiff_data, 0, 0, Some(0))?;
+  if reader.little_endian() != cfg!(target_endian = "little") {
+    return Err(TiffError::General("Can't add EXIF to a TIFF in foreign byte order".to_string()));
+  }
+  let source_ifd = reader.root_ifd();
+  let strips = source_ifd.strip_data(&mut Cursor::new(tiff_data))?;
+
+  let mut output = Cursor::new(Vec::with_capacity(tiff_data.len() + 4096));
+  let mut tiff = TiffWriter::new(&mut output)?;
+  let mut root_ifd = tiff.new_directory();
+
+  let mut offsets = Vec::with_capacity(strips.len());
+  let mut byte_counts = Vec::with_capacity(strips.len());
+  for strip in &strips {
+    offsets.push(tiff.write_data(strip)?);
+    byte_counts.push(strip.len() as u32);
+  }
+
+  let dropped: [u16; 5] = [
+    TiffCommonTag::StripOffsets.into(),
+    TiffCommonTag::StripByteCounts.into(),
+    TiffCommonTag::ExifIFDPointer.into(),
+    TiffCommonTag::SubIFDs.into(),
+    ExifTag::GPSInfo.into(),
+  ];
+  root_ifd.copy(source_ifd.value_iter().filter(|(tag, _)| !dropped.contains(tag)));
+  root_ifd.add_tag(TiffCommonTag::StripOffsets, &offsets);
+  root_ifd.add_tag(TiffCommonTag::StripByteCounts, &byte_counts);
+
+  crate::decoders::RawMetadata::fill_exif_root(exif, &mut tiff, &mut root_ifd)?;
+  let mut exif_ifd = DirectoryWriter::new();
+  crate::decoders::RawMetadata::fill_exif_ifd(exif, &mut exif_ifd)?;
+  if !exif_ifd.is_empty() {
+    let exif_offset = exif_ifd.build(&mut tiff)?;
+    root_ifd.add_tag(TiffCommonTag::ExifIFDPointer, exif_offset);
+  }
+
+  tiff.build(root_ifd)?;
+  Ok(output.into_inner())
+}
+
 pub(crate) fn transfer_entry<T, V>(ifd: &mut DirectoryWriter, tag: T, entry: &Option<V>) -> Result<()>
 where
   T: TiffTag,
@@ -0,0 +1,122 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Bad pixel interpolation
+//!
+//! Stuck, dead or hot pixels that are known for a sensor are replaced by the
+//! average of the nearest samples of the same color. Other bad pixels are
+//! never used for the average, so clusters are filled from their border.
+
+use std::collections::HashSet;
+
+use rayon::prelude::*;
+
+use super::Point;
+use crate::rawimage::{RawImage, RawImageData, RawPhotometricInterpretation};
+
+/// Largest distance searched for same colored samples, enough for Bayer and
+/// X-Trans patterns even next to other bad pixels.
+const MAX_RADIUS: usize = 4;
+
+/// Replace the samples at `pixels` in the raw data of `raw`.
+///
+/// Positions are in raw sensor coordinates, including masked areas, as
+/// written by tools that map bad pixels. Positions outside the image are
+/// ignored.
+pub fn interpolate_bad_pixels(raw: &mut RawImage, pixels: &[Point]) {
+  let (width, height, cpp) = (raw.width, raw.height, raw.cpp);
+  let bad: HashSet<(usize, usize)> = pixels.iter().filter(|p| p.x < width && p.y < height).map(|p| (p.y, p.x)).collect();
+  if bad.is_empty() {
+    return;
+  }
+
+  let cfa = match &raw.photometric {
+    RawPhotometricInterpretation::Cfa(config) if cpp == 1 && config.cfa.width > 0 => Some(&config.cfa),
+    _ => None,
+  };
+  let data = raw.data.as_f32();
+
+  let replacements: Vec<(usize, f32)> = bad
+    .par_iter()
+    .flat_map_iter(|&(row, col)| {
+      let data = &data;
+      let bad = &bad;
+      (0..cpp).filter_map(move |component| {
+        let color = cfa.map(|cfa| cfa.color_at(row, col));
+        // Nearest ring that has usable samples.
+        (1..=MAX_RADIUS).find_map(|radius| {
+          let (mut sum, mut count) = (0.0, 0);
+          for y in row.saturating_sub(radius)..=(row + radius).min(height - 1) {
+            for x in col.saturating_sub(radius)..=(col + radius).min(width - 1) {
+              let on_ring = y.abs_diff(row) == radius || x.abs_diff(col) == radius;
+              let same_color = cfa.is_none_or(|cfa| Some(cfa.color_at(y, x)) == color);
+              if on_ring && same_color && !bad.contains(&(y, x)) {
+                sum += data[(y * width + x) * cpp + component];
+                count += 1;
+              }
+            }
+          }
+          (count > 0).then(|| ((row * width + col) * cpp + component, sum / count as f32))
+        })
+      })
+    })
+    .collect();
+
+  let mut samples = data.into_owned();
+  for (index, value) in replacements {
+    samples[index] = value;
+  }
+  raw.data = match raw.data {
+    RawImageData::Integer(_) => RawImageData::Integer(samples.into_iter().map(|p| p.round() as u16).collect()),
+    RawImageData::Float(_) => RawImageData::Float(samples),
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    cfa::{PlaneColor, CFA},
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, CFAConfig, WhiteLevel},
+  };
+
+  fn raw_with(data: Vec<u16>, width: usize, photometric: RawPhotometricInterpretation) -> RawImage {
+    let height = data.len() / width;
+    RawImage::new(Camera::default(), PixU16::new_with(data, width, height), 1, [1.0; 4], photometric, Some(BlackLevel::new(&[0_u32], 1, 1, 1)), Some(WhiteLevel::new(vec![4095])), false)
+  }
+
+  #[test]
+  fn bayer_pixel_uses_same_color_neighbours() {
+    // RGGB, red samples are 100, green 500, blue 900. The red sample at (2, 2) is hot.
+    let data = (0..36)
+      .map(|i| match ((i / 6) % 2, (i % 6) % 2) {
+        (0, 0) => {
+          if i == 14 {
+            4095
+          } else {
+            100
+          }
+        }
+        (1, 1) => 900,
+        _ => 500,
+      })
+      .collect();
+    let cfa = RawPhotometricInterpretation::Cfa(CFAConfig::new(&CFA::new("RGGB"), &PlaneColor::new("RGB")));
+    let mut raw = raw_with(data, 6, cfa);
+    interpolate_bad_pixels(&mut raw, &[Point { x: 2, y: 2 }]);
+    assert_eq!(raw.data.as_f32()[14], 100.0);
+    assert_eq!(raw.data.as_f32()[15], 500.0);
+  }
+
+  #[test]
+  fn neighbouring_bad_pixels_are_not_used() {
+    let mut data = vec![200; 25];
+    data[12] = 0;
+    data[13] = 4095;
+    let mut raw = raw_with(data, 5, RawPhotometricInterpretation::LinearRaw);
+    interpolate_bad_pixels(&mut raw, &[Point { x: 2, y: 2 }, Point { x: 3, y: 2 }, Point { x: 10, y: 10 }]);
+    assert_eq!(raw.data.as_f32()[12], 200.0);
+    assert_eq!(raw.data.as_f32()[13], 200.0);
+  }
+}
@@ -0,0 +1,158 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Banding correction
+//!
+//! Some sensors add a small offset to whole rows or columns, which shows as
+//! horizontal or vertical stripes at high ISO or with the electronic shutter.
+//! The offset of a row is estimated as the median difference of its samples
+//! to the samples of the same color a few rows above and below, so image
+//! detail cancels out and only the stripes are removed. Columns are handled
+//! the same way afterwards.
+
+use rayon::prelude::*;
+
+use crate::rawimage::{RawImage, RawImageData, RawPhotometricInterpretation};
+
+/// Same colored rows (or columns) on each side that form the reference.
+const REFERENCE_LINES: usize = 3;
+
+fn median(values: &mut [f32]) -> Option<f32> {
+  if values.is_empty() {
+    return None;
+  }
+  let mid = values.len() / 2;
+  let (_, median, _) = values.select_nth_unstable_by(mid, |a, b| a.total_cmp(b));
+  Some(*median)
+}
+
+/// Offsets of all rows of a `width` x `height` mosaic, where rows `period`
+/// apart have the same colors. Samples at or above `white` are not used.
+fn row_offsets(data: &[f32], width: usize, height: usize, period: usize, white: f32) -> Vec<f32> {
+  (0..height)
+    .into_par_iter()
+    .map(|row| {
+      // Lines on both sides average out gradients. At the borders the gradient
+      // is extrapolated from two lines on the same side instead.
+      let line = |distance: usize, below: bool| if below { Some(row + distance).filter(|r| *r < height) } else { row.checked_sub(distance) };
+      let pairs: Vec<(usize, usize, f32, f32)> = (1..=REFERENCE_LINES)
+        .filter_map(|i| match (line(i * period, false), line(i * period, true)) {
+          (Some(above), Some(below)) => Some((above, below, 0.5, 0.5)),
+          (Some(near), None) => line((i + 1) * period, false).map(|far| (near, far, (i + 1) as f32, -(i as f32))),
+          (None, Some(near)) => line((i + 1) * period, true).map(|far| (near, far, (i + 1) as f32, -(i as f32))),
+          (None, None) => None,
+        })
+        .collect();
+      if pairs.is_empty() {
+        return 0.0;
+      }
+      let mut reference = Vec::with_capacity(pairs.len());
+      let mut diffs: Vec<f32> = (0..width)
+        .filter_map(|col| {
+          let value = data[row * width + col];
+          if value >= white {
+            return None;
+          }
+          reference.clear();
+          reference.extend(pairs.iter().map(|(a, b, wa, wb)| (data[a * width + col], data[b * width + col], wa, wb)).filter(|(a, b, _, _)| *a < white && *b < white).map(|(a, b, wa, wb)| a * wa + b * wb));
+          median(&mut reference).map(|reference| value - reference)
+        })
+        .collect();
+      median(&mut diffs).unwrap_or(0.0)
+    })
+    .collect()
+}
+
+fn transpose(data: &[f32], width: usize, height: usize) -> Vec<f32> {
+  (0..width * height).map(|i| data[(i % height) * width + i / height]).collect()
+}
+
+/// Remove row and column offsets from the unscaled mosaic of `raw`.
+/// Data that is not a single channel mosaic is left alone.
+pub fn correct_banding(raw: &mut RawImage) {
+  let (width, height) = (raw.width, raw.height);
+  let (row_period, col_period) = match &raw.photometric {
+    RawPhotometricInterpretation::Cfa(config) if raw.cpp == 1 && config.cfa.width > 0 => (config.cfa.height, config.cfa.width),
+    _ if raw.cpp == 1 => (1, 1),
+    _ => {
+      log::debug!("Banding correction is only supported for single channel data");
+      return;
+    }
+  };
+  let white = raw.whitelevel.as_vec().into_iter().fold(f32::INFINITY, f32::min);
+
+  let mut samples = raw.data.as_f32().into_owned();
+  let rows = row_offsets(&samples, width, height, row_period, white);
+  samples.par_chunks_exact_mut(width).zip(rows.par_iter()).for_each(|(line, offset)| {
+    line.iter_mut().filter(|p| **p < white).for_each(|p| *p = (*p - offset).max(0.0));
+  });
+
+  let transposed = transpose(&samples, width, height);
+  let cols = row_offsets(&transposed, height, width, col_period, white);
+  samples.par_chunks_exact_mut(width).for_each(|line| {
+    line.iter_mut().zip(cols.iter()).filter(|(p, _)| **p < white).for_each(|(p, offset)| *p = (*p - offset).max(0.0));
+  });
+
+  raw.data = match raw.data {
+    RawImageData::Integer(_) => RawImageData::Integer(samples.into_iter().map(|p| p.round() as u16).collect()),
+    RawImageData::Float(_) => RawImageData::Float(samples),
+  };
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    cfa::{PlaneColor, CFA},
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, CFAConfig, WhiteLevel},
+  };
+
+  fn bayer(width: usize, height: usize, f: impl Fn(usize, usize) -> u16) -> RawImage {
+    let data = (0..height).flat_map(|row| (0..width).map(move |col| (row, col))).map(|(row, col)| f(row, col)).collect();
+    RawImage::new(
+      Camera::default(),
+      PixU16::new_with(data, width, height),
+      1,
+      [1.0; 4],
+      RawPhotometricInterpretation::Cfa(CFAConfig::new(&CFA::new("RGGB"), &PlaneColor::new("RGB"))),
+      Some(BlackLevel::new(&[0_u32], 1, 1, 1)),
+      Some(WhiteLevel::new(vec![4095])),
+      false,
+    )
+  }
+
+  // Red and blue samples are darker than green, like an unbalanced raw.
+  fn scene(row: usize, col: usize) -> u16 {
+    if (row + col) % 2 == 0 {
+      400 + col as u16
+    } else {
+      800 + col as u16
+    }
+  }
+
+  #[test]
+  fn row_stripes_are_removed() {
+    let mut raw = bayer(16, 16, |row, col| scene(row, col) + if row == 5 || row == 6 { 40 } else { 0 });
+    correct_banding(&mut raw);
+    let expected = bayer(16, 16, scene);
+    assert_eq!(raw.data.as_f32(), expected.data.as_f32());
+  }
+
+  #[test]
+  fn column_stripes_are_removed() {
+    let mut raw = bayer(16, 16, |row, col| scene(row, col) + if col == 9 { 25 } else { 0 });
+    correct_banding(&mut raw);
+    let expected = bayer(16, 16, scene);
+    assert_eq!(raw.data.as_f32(), expected.data.as_f32());
+  }
+
+  #[test]
+  fn clipped_samples_stay_clipped() {
+    let mut raw = bayer(8, 8, |row, col| if col == 3 { 4095 } else { scene(row, col) + if row == 2 { 30 } else { 0 } });
+    correct_banding(&mut raw);
+    let data = raw.data.as_f32();
+    assert_eq!(data[2 * 8 + 3], 4095.0);
+    assert_eq!(data[2 * 8 + 4], scene(2, 4) as f32);
+  }
+}
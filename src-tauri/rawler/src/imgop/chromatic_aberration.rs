@@ -0,0 +1,250 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Lateral chromatic aberration correction
+//!
+//! Lenses with lateral CA project the red and blue image at a slightly
+//! different magnification than the green one, which shows as colored
+//! fringes that grow towards the corners. The magnification of red and blue
+//! relative to green is estimated from the image itself and both channels
+//! are resampled to match green.
+
+use rayon::prelude::*;
+
+use crate::pixarray::Color2D;
+
+/// Largest relative magnification difference that is searched for.
+const MAX_SCALE_DEVIATION: f32 = 0.003;
+
+/// Step width of the search, about a tenth of a pixel at the corners of a 24 MP image.
+const SCALE_STEP: f32 = 0.000_05;
+
+/// Edge samples used for the estimation, more only cost time.
+const MAX_SAMPLES: usize = 50_000;
+
+/// Samples closer to the center carry no information about the magnification.
+const MIN_RADIUS_FRACTION: f32 = 0.2;
+
+/// Magnification of the red and blue channel relative to green.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LateralCa {
+  pub red: f32,
+  pub blue: f32,
+}
+
+impl Default for LateralCa {
+  fn default() -> Self {
+    Self { red: 1.0, blue: 1.0 }
+  }
+}
+
+impl LateralCa {
+  pub fn is_identity(&self) -> bool {
+    self.red == 1.0 && self.blue == 1.0
+  }
+}
+
+struct Sample {
+  /// Position relative to the image center
+  dx: f32,
+  dy: f32,
+  /// Unit vector pointing away from the center
+  nx: f32,
+  ny: f32,
+  /// Radial derivative of the green channel, normalized by its mean
+  green: f32,
+}
+
+/// Catmull-Rom weights for the four taps around a sample position.
+fn cubic_weights(t: f32) -> [f32; 4] {
+  let t2 = t * t;
+  let t3 = t2 * t;
+  [0.5 * (-t3 + 2.0 * t2 - t), 0.5 * (3.0 * t3 - 5.0 * t2 + 2.0), 0.5 * (-3.0 * t3 + 4.0 * t2 + t), 0.5 * (t3 - t2)]
+}
+
+/// Bicubic sample of channel `ch` at a position relative to the center.
+/// Bilinear sampling smooths depending on the sub-pixel position, which
+/// biases the estimation, so this is used for estimation and resampling.
+fn sample(pixels: &Color2D<f32, 3>, center: (f32, f32), x: f32, y: f32, ch: usize) -> f32 {
+  let x = (center.0 + x).clamp(0.0, (pixels.width - 1) as f32);
+  let y = (center.1 + y).clamp(0.0, (pixels.height - 1) as f32);
+  let (x0, y0) = (x.floor(), y.floor());
+  let (wx, wy) = (cubic_weights(x - x0), cubic_weights(y - y0));
+  let clamp_x = |i: isize| (x0 as isize + i).clamp(0, pixels.width as isize - 1) as usize;
+  let clamp_y = |i: isize| (y0 as isize + i).clamp(0, pixels.height as isize - 1) as usize;
+
+  let mut out = 0.0;
+  for (j, wy) in wy.iter().enumerate() {
+    let row = clamp_y(j as isize - 1);
+    let mut line = 0.0;
+    for (i, wx) in wx.iter().enumerate() {
+      line += pixels.at(row, clamp_x(i as isize - 1))[ch] * wx;
+    }
+    out += line * wy;
+  }
+  out
+}
+
+fn center_of(pixels: &Color2D<f32, 3>) -> (f32, f32) {
+  ((pixels.width as f32 - 1.0) / 2.0, (pixels.height as f32 - 1.0) / 2.0)
+}
+
+fn channel_means(pixels: &Color2D<f32, 3>) -> [f32; 3] {
+  let sum = pixels
+    .pixels()
+    .par_iter()
+    .fold(|| [0.0_f64; 3], |acc, p| [acc[0] + p[0] as f64, acc[1] + p[1] as f64, acc[2] + p[2] as f64])
+    .reduce(|| [0.0; 3], |a, b| [a[0] + b[0], a[1] + b[1], a[2] + b[2]]);
+  let n = pixels.pixels().len().max(1) as f64;
+  sum.map(|s| (s / n) as f32 + f32::MIN_POSITIVE)
+}
+
+/// Pick pixels with a strong radial green edge, lateral CA only shows up there.
+fn collect_samples(pixels: &Color2D<f32, 3>, center: (f32, f32), green_mean: f32) -> Vec<Sample> {
+  let max_radius = center.0.hypot(center.1);
+  let min_radius = max_radius * MIN_RADIUS_FRACTION;
+  let (w, h) = (pixels.width, pixels.height);
+
+  let mut candidates: Vec<Sample> = (2..h.saturating_sub(2))
+    .into_par_iter()
+    .flat_map_iter(|row| {
+      (2..w - 2).filter_map(move |col| {
+        let dx = col as f32 - center.0;
+        let dy = row as f32 - center.1;
+        let radius = dx.hypot(dy);
+        // Every scale of the search has to sample inside the image
+        let reach = 1.0 + MAX_SCALE_DEVIATION;
+        if radius < min_radius || (dx.abs() + 1.0) * reach > center.0 || (dy.abs() + 1.0) * reach > center.1 {
+          return None;
+        }
+        let (nx, ny) = (dx / radius, dy / radius);
+        let green = (sample(pixels, center, dx + nx, dy + ny, 1) - sample(pixels, center, dx - nx, dy - ny, 1)) / green_mean;
+        Some(Sample { dx, dy, nx, ny, green })
+      })
+    })
+    .collect();
+
+  if candidates.is_empty() {
+    return candidates;
+  }
+  // Keep the strongest edges
+  let keep = candidates.len().min(MAX_SAMPLES);
+  candidates.select_nth_unstable_by(keep - 1, |a, b| b.green.abs().total_cmp(&a.green.abs()));
+  candidates.truncate(keep);
+  // Flat images have no edges to measure
+  candidates.retain(|s| s.green.abs() > 0.02);
+  candidates
+}
+
+/// Find the magnification of `ch` whose radial edges correlate best with the green ones.
+/// Correlation instead of the difference keeps the estimate independent of the channel gain.
+fn estimate_channel(pixels: &Color2D<f32, 3>, center: (f32, f32), samples: &[Sample], ch: usize) -> f32 {
+  let steps = (MAX_SCALE_DEVIATION / SCALE_STEP).round() as i32;
+  (-steps..=steps)
+    .into_par_iter()
+    .map(|i| {
+      let scale = 1.0 + i as f32 * SCALE_STEP;
+      let (mut ee, mut eg) = (0.0_f64, 0.0_f64);
+      for s in samples {
+        let (x, y) = (s.dx * scale, s.dy * scale);
+        let edge = (sample(pixels, center, x + s.nx, y + s.ny, ch) - sample(pixels, center, x - s.nx, y - s.ny, ch)) as f64;
+        ee += edge * edge;
+        eg += edge * s.green as f64;
+      }
+      let correlation = if ee > 0.0 { eg / ee.sqrt() } else { 0.0 };
+      (i, scale, correlation)
+    })
+    // Prefer the smallest correction if several scales fit equally well
+    .max_by(|a, b| a.2.total_cmp(&b.2).then(b.0.abs().cmp(&a.0.abs())))
+    .map(|(_, scale, _)| scale)
+    .unwrap_or(1.0)
+}
+
+/// Estimate the lateral CA of demosaiced camera RGB data.
+pub fn estimate_lateral_ca(pixels: &Color2D<f32, 3>) -> LateralCa {
+  if pixels.width < 16 || pixels.height < 16 {
+    return LateralCa::default();
+  }
+  let center = center_of(pixels);
+  let means = channel_means(pixels);
+  let samples = collect_samples(pixels, center, means[1]);
+  if samples.is_empty() {
+    return LateralCa::default();
+  }
+  let ca = LateralCa {
+    red: estimate_channel(pixels, center, &samples, 0),
+    blue: estimate_channel(pixels, center, &samples, 2),
+  };
+  log::debug!("Estimated lateral CA from {} samples: {:?}", samples.len(), ca);
+  ca
+}
+
+/// Resample the red and blue channel so their magnification matches green.
+pub fn correct_lateral_ca(pixels: &mut Color2D<f32, 3>, ca: LateralCa) {
+  if ca.is_identity() {
+    return;
+  }
+  let center = center_of(pixels);
+  let source = pixels.clone();
+  let width = pixels.width;
+  pixels.data.par_chunks_exact_mut(width).enumerate().for_each(|(row, line)| {
+    let dy = row as f32 - center.1;
+    for (col, p) in line.iter_mut().enumerate() {
+      let dx = col as f32 - center.0;
+      p[0] = sample(&source, center, dx * ca.red, dy * ca.red, 0);
+      p[2] = sample(&source, center, dx * ca.blue, dy * ca.blue, 2);
+    }
+  });
+}
+
+/// Estimate and correct lateral CA in one step.
+pub fn auto_correct_lateral_ca(pixels: &mut Color2D<f32, 3>) -> LateralCa {
+  let ca = estimate_lateral_ca(pixels);
+  correct_lateral_ca(pixels, ca);
+  ca
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const SIZE: usize = 256;
+
+  fn pattern(x: f32, y: f32) -> f32 {
+    0.5 + 0.4 * (x * 0.15).sin() * (y * 0.15).cos()
+  }
+
+  /// Green is the pattern, red and blue are the pattern magnified by the given scales.
+  fn image_with_ca(red: f32, blue: f32) -> Color2D<f32, 3> {
+    let c = (SIZE as f32 - 1.0) / 2.0;
+    let data = (0..SIZE * SIZE)
+      .map(|i| {
+        let (dx, dy) = ((i % SIZE) as f32 - c, (i / SIZE) as f32 - c);
+        [pattern(c + dx / red, c + dy / red) * 0.8, pattern(c + dx, c + dy), pattern(c + dx / blue, c + dy / blue) * 0.6]
+      })
+      .collect();
+    Color2D::new_with(data, SIZE, SIZE)
+  }
+
+  #[test]
+  fn no_ca_is_not_corrected() {
+    let ca = estimate_lateral_ca(&image_with_ca(1.0, 1.0));
+    assert_eq!(ca, LateralCa::default());
+  }
+
+  #[test]
+  fn magnification_is_estimated_and_removed() {
+    let mut pixels = image_with_ca(1.002, 0.9985);
+    let ca = auto_correct_lateral_ca(&mut pixels);
+    assert!((ca.red - 1.002).abs() <= SCALE_STEP, "{:?}", ca);
+    assert!((ca.blue - 0.9985).abs() <= SCALE_STEP, "{:?}", ca);
+
+    // Away from the edges, red and blue follow green again
+    for row in 16..SIZE - 16 {
+      for col in 16..SIZE - 16 {
+        let p = pixels.at(row, col);
+        assert!((p[0] / 0.8 - p[1]).abs() < 0.02, "red at ({}, {})", row, col);
+        assert!((p[2] / 0.6 - p[1]).abs() < 0.02, "blue at ({}, {})", row, col);
+      }
+    }
+  }
+}
@@ -0,0 +1,100 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Dark frame subtraction
+//!
+//! A dark frame is taken with the lens cap on, at the same ISO, exposure time
+//! and ideally sensor temperature as the light frame. It records the thermal
+//! signal of the sensor (hot pixels, amp glow), which is removed from the raw
+//! data before any other processing.
+
+use rayon::prelude::*;
+
+use crate::rawimage::{RawImage, RawImageData, RawPhotometricInterpretation};
+
+/// Black level of the sample at `index`. Bayer data has one level per
+/// position in the 2x2 pattern, other data one level per component.
+pub(crate) fn black_level_at(raw: &RawImage, bayer: &[f32; 4], components: &[f32], index: usize) -> f32 {
+  match raw.photometric {
+    RawPhotometricInterpretation::Cfa(_) => {
+      let (row, col) = (index / raw.width, index % raw.width);
+      bayer[(row % 2) * 2 + col % 2]
+    }
+    _ => components[(index % raw.cpp) % components.len().max(1)],
+  }
+}
+
+/// Subtract `dark` from the raw data of `raw`.
+///
+/// Both images must be unscaled sensor data of the same size. The black level
+/// of the dark frame is kept out of the subtraction, so `raw` keeps its own
+/// black level. Samples at or above the white level stay clipped, otherwise
+/// blown highlights would no longer be detected as such.
+pub fn subtract_dark_frame(raw: &mut RawImage, dark: &RawImage) -> crate::Result<()> {
+  if raw.width != dark.width || raw.height != dark.height || raw.cpp != dark.cpp {
+    return Err(format!("Dark frame size {}x{}x{} does not match image size {}x{}x{}", dark.width, dark.height, dark.cpp, raw.width, raw.height, raw.cpp).into());
+  }
+
+  let dark_bayer = dark.blacklevel.as_bayer_array();
+  let dark_components = dark.blacklevel.as_vec();
+  let raw_bayer = raw.blacklevel.as_bayer_array();
+  let raw_components = raw.blacklevel.as_vec();
+  // The white level of `raw` may have been raised to keep headroom, the dark frame still has the sensor's one.
+  let white = raw.whitelevel.as_vec().into_iter().chain(dark.whitelevel.as_vec()).fold(f32::INFINITY, f32::min);
+
+  let dark_data = dark.data.as_f32();
+  let mut pixels = raw.data.as_f32().into_owned();
+  pixels.par_iter_mut().enumerate().for_each(|(i, p)| {
+    if *p >= white {
+      return;
+    }
+    let signal = (dark_data[i] - black_level_at(dark, &dark_bayer, &dark_components, i)).max(0.0);
+    // Never go below the black level, the scaling clips there anyway.
+    *p = (*p - signal).max(black_level_at(raw, &raw_bayer, &raw_components, i).min(*p));
+  });
+
+  raw.data = match raw.data {
+    RawImageData::Integer(_) => RawImageData::Integer(pixels.into_iter().map(|p| p.round() as u16).collect()),
+    RawImageData::Float(_) => RawImageData::Float(pixels),
+  };
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, WhiteLevel},
+  };
+
+  fn raw_with(data: Vec<u16>, black: u32) -> RawImage {
+    let height = data.len() / 2;
+    RawImage::new(
+      Camera::default(),
+      PixU16::new_with(data, 2, height),
+      1,
+      [1.0; 4],
+      RawPhotometricInterpretation::LinearRaw,
+      Some(BlackLevel::new(&[black], 1, 1, 1)),
+      Some(WhiteLevel::new(vec![1000])),
+      false,
+    )
+  }
+
+  #[test]
+  fn thermal_signal_is_removed() {
+    let mut raw = raw_with(vec![300, 500, 1000, 120], 100);
+    let dark = raw_with(vec![110, 400, 500, 90], 100);
+    subtract_dark_frame(&mut raw, &dark).unwrap();
+    // Clipped samples stay clipped, nothing goes below the black level
+    assert_eq!(raw.data.as_f32().as_slice(), &[290.0, 200.0, 1000.0, 120.0]);
+  }
+
+  #[test]
+  fn size_mismatch_is_an_error() {
+    let mut raw = raw_with(vec![300, 500, 1000, 120], 100);
+    let dark = raw_with(vec![110, 400], 100);
+    assert!(subtract_dark_frame(&mut raw, &dark).is_err());
+  }
+}
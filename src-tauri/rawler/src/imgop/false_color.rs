@@ -0,0 +1,106 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! False color suppression
+//!
+//! Sensors without an anti-aliasing filter record detail finer than the
+//! color filter pattern, which demosaicing turns into colored moiré and
+//! speckles. Like dcraw's median filter, the red-green and blue-green
+//! differences are median filtered while green keeps its detail, so
+//! isolated chroma errors are removed and luminance stays sharp.
+
+use rayon::prelude::*;
+
+use crate::pixarray::Color2D;
+
+/// Median of nine values, without allocating.
+fn median9(mut values: [f32; 9]) -> f32 {
+  values.sort_unstable_by(|a, b| a.total_cmp(b));
+  values[4]
+}
+
+/// 3x3 median of a plane, edges are clamped.
+fn median_filter(plane: &[f32], width: usize, height: usize) -> Vec<f32> {
+  let mut out = vec![0.0; plane.len()];
+  out.par_chunks_exact_mut(width).enumerate().for_each(|(row, line)| {
+    let rows = [row.saturating_sub(1), row, (row + 1).min(height - 1)];
+    for (col, out) in line.iter_mut().enumerate() {
+      let cols = [col.saturating_sub(1), col, (col + 1).min(width - 1)];
+      let mut window = [0.0; 9];
+      for (i, r) in rows.iter().enumerate() {
+        for (j, c) in cols.iter().enumerate() {
+          window[i * 3 + j] = plane[r * width + c];
+        }
+      }
+      *out = median9(window);
+    }
+  });
+  out
+}
+
+/// Suppress false colors of a demosaiced camera RGB image. The differences
+/// are taken after applying `wb`, so neutral detail has no chroma to begin
+/// with. Every pass removes more moiré but also some fine color detail.
+pub fn suppress_false_color(pixels: &mut Color2D<f32, 3>, wb: &[f32; 4], passes: usize) {
+  let (width, height) = (pixels.width, pixels.height);
+  if width == 0 || height == 0 {
+    return;
+  }
+  let wb = [wb[0], wb[1], wb[2]].map(|c| if c.is_finite() && c > 0.0 { c } else { 1.0 });
+
+  for _ in 0..passes {
+    let red_diff: Vec<f32> = pixels.data.par_iter().map(|p| p[0] * wb[0] - p[1] * wb[1]).collect();
+    let blue_diff: Vec<f32> = pixels.data.par_iter().map(|p| p[2] * wb[2] - p[1] * wb[1]).collect();
+    let red_diff = median_filter(&red_diff, width, height);
+    let blue_diff = median_filter(&blue_diff, width, height);
+
+    pixels.data.par_iter_mut().zip(red_diff.par_iter().zip(blue_diff.par_iter())).for_each(|(p, (red, blue))| {
+      let green = p[1] * wb[1];
+      p[0] = ((green + red) / wb[0]).max(0.0);
+      p[2] = ((green + blue) / wb[2]).max(0.0);
+    });
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  fn image(width: usize, height: usize, f: impl Fn(usize, usize) -> [f32; 3]) -> Color2D<f32, 3> {
+    let data = (0..height).flat_map(|row| (0..width).map(move |col| (row, col))).map(|(row, col)| f(row, col)).collect();
+    Color2D::new_with(data, width, height)
+  }
+
+  #[test]
+  fn isolated_color_speckle_is_removed() {
+    let mut pixels = image(5, 5, |row, col| if row == 2 && col == 2 { [0.9, 0.5, 0.1] } else { [0.5, 0.5, 0.5] });
+    suppress_false_color(&mut pixels, &[1.0, 1.0, 1.0, f32::NAN], 1);
+    let center = pixels.at(2, 2);
+    assert!((center[0] - 0.5).abs() < 1e-6);
+    assert!((center[2] - 0.5).abs() < 1e-6);
+  }
+
+  #[test]
+  fn luminance_detail_is_kept() {
+    // A neutral one pixel line has no chroma and must pass unchanged.
+    let mut pixels = image(6, 6, |_, col| if col == 3 { [0.8, 0.8, 0.8] } else { [0.2, 0.2, 0.2] });
+    let original = pixels.clone();
+    suppress_false_color(&mut pixels, &[1.0, 1.0, 1.0, f32::NAN], 2);
+    for (a, b) in pixels.data.iter().zip(original.data.iter()) {
+      for c in 0..3 {
+        assert!((a[c] - b[c]).abs() < 1e-6);
+      }
+    }
+  }
+
+  #[test]
+  fn neutral_depends_on_white_balance() {
+    // Camera RGB of a gray patch under tungsten light, with a green speckle.
+    let gray = [0.25, 0.5, 1.0];
+    let wb = [2.0, 1.0, 0.5, f32::NAN];
+    let mut pixels = image(3, 3, |row, col| if row == 1 && col == 1 { [0.1, 0.5, 0.2] } else { gray });
+    suppress_false_color(&mut pixels, &wb, 1);
+    let center = pixels.at(1, 1);
+    assert!((center[0] - gray[0]).abs() < 1e-6);
+    assert!((center[2] - gray[2]).abs() < 1e-6);
+  }
+}
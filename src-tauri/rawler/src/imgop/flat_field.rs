@@ -0,0 +1,199 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Flat-field correction
+//!
+//! A flat field is a frame of an evenly lit, featureless target (a diffuser
+//! or a white wall) taken with the same lens, aperture and filters as the
+//! image. Its falloff towards the corners records the vignetting of the lens
+//! and, with technical lenses on digital backs, a color cast that changes
+//! across the frame. Dividing the raw data by it removes both.
+
+use rayon::prelude::*;
+
+use super::dark_frame::black_level_at;
+use crate::rawimage::{RawImage, RawImageData, RawPhotometricInterpretation};
+
+/// Size of the blocks the flat field is averaged over, in CFA patterns.
+/// The falloff is smooth, averaging removes the noise of the flat field.
+const BLOCK_PATTERNS: usize = 32;
+
+/// Largest correction that is applied. Corners that dark in the flat field
+/// are mostly noise after the correction.
+const MAX_GAIN: f32 = 8.0;
+
+/// Block averages of one channel of the flat field, black level removed.
+struct ChannelGrid {
+  values: Vec<f32>,
+  /// Centers of the block columns and rows. The last block may be smaller.
+  x_centers: Vec<f32>,
+  y_centers: Vec<f32>,
+}
+
+/// Centers of the blocks covering `length` pixels.
+fn block_centers(length: usize, block: usize) -> Vec<f32> {
+  (0..length.div_ceil(block).max(1)).map(|i| (i * block + ((i + 1) * block).min(length)) as f32 / 2.0).collect()
+}
+
+/// The two blocks around `pos` and the weight of the second one. Positions
+/// outside the outer block centers are extrapolated, the falloff is steepest there.
+fn segment(centers: &[f32], pos: f32, block: usize) -> (usize, usize, f32) {
+  if centers.len() < 2 {
+    return (0, 0, 0.0);
+  }
+  let i = ((pos / block as f32 - 0.5).floor().max(0.0) as usize).min(centers.len() - 2);
+  (i, i + 1, (pos - centers[i]) / (centers[i + 1] - centers[i]))
+}
+
+impl ChannelGrid {
+  /// Bilinear interpolation between the block centers.
+  fn at(&self, x: f32, y: f32, block: usize) -> f32 {
+    let (x0, x1, tx) = segment(&self.x_centers, x, block);
+    let (y0, y1, ty) = segment(&self.y_centers, y, block);
+    let columns = self.x_centers.len();
+    let v = |col: usize, row: usize| self.values[row * columns + col];
+    let top = v(x0, y0) * (1.0 - tx) + v(x1, y0) * tx;
+    let bottom = v(x0, y1) * (1.0 - tx) + v(x1, y1) * tx;
+    top * (1.0 - ty) + bottom * ty
+  }
+}
+
+/// Size of the repeating pattern and the number of channels in it.
+fn pattern_of(raw: &RawImage) -> (usize, usize, usize) {
+  match &raw.photometric {
+    RawPhotometricInterpretation::Cfa(config) => (config.cfa.width.max(1), config.cfa.height.max(1), config.cfa.width.max(1) * config.cfa.height.max(1)),
+    _ => (1, 1, raw.cpp),
+  }
+}
+
+/// Pixel position and channel of the sample at `index`.
+fn locate(raw: &RawImage, pattern: (usize, usize), index: usize) -> (usize, usize, usize) {
+  let pixel = index / raw.cpp;
+  let (row, col) = (pixel / raw.width, pixel % raw.width);
+  let channel = if raw.cpp == 1 { (row % pattern.1) * pattern.0 + col % pattern.0 } else { index % raw.cpp };
+  (row, col, channel)
+}
+
+fn channel_grids(flat: &RawImage, block: usize) -> Vec<ChannelGrid> {
+  let (pattern_w, pattern_h, channels) = pattern_of(flat);
+  let columns = flat.width.div_ceil(block).max(1);
+  let rows = flat.height.div_ceil(block).max(1);
+  let bayer = flat.blacklevel.as_bayer_array();
+  let components = flat.blacklevel.as_vec();
+  let data = flat.data.as_f32();
+
+  let mut sums = vec![vec![0.0_f64; columns * rows]; channels];
+  let mut counts = vec![vec![0_u32; columns * rows]; channels];
+  for (i, value) in data.iter().enumerate() {
+    let (row, col, channel) = locate(flat, (pattern_w, pattern_h), i);
+    let cell = (row / block) * columns + col / block;
+    sums[channel][cell] += (value - black_level_at(flat, &bayer, &components, i)).max(0.0) as f64;
+    counts[channel][cell] += 1;
+  }
+
+  sums
+    .into_iter()
+    .zip(counts)
+    .map(|(sums, counts)| ChannelGrid {
+      values: sums.iter().zip(counts).map(|(s, n)| if n > 0 { (s / n as f64) as f32 } else { 0.0 }).collect(),
+      x_centers: block_centers(flat.width, block),
+      y_centers: block_centers(flat.height, block),
+    })
+    .collect()
+}
+
+/// Divide the raw data of `raw` by the flat field `flat`.
+///
+/// Both images must be unscaled sensor data of the same size. Each channel
+/// is normalized to its value in the image center, so the center keeps its
+/// brightness and color and the rest of the frame is matched to it. Clipped
+/// samples are left alone.
+pub fn divide_flat_field(raw: &mut RawImage, flat: &RawImage) -> crate::Result<()> {
+  if raw.width != flat.width || raw.height != flat.height || raw.cpp != flat.cpp {
+    return Err(format!("Flat field size {}x{}x{} does not match image size {}x{}x{}", flat.width, flat.height, flat.cpp, raw.width, raw.height, raw.cpp).into());
+  }
+
+  let (pattern_w, pattern_h, _) = pattern_of(raw);
+  let block = BLOCK_PATTERNS * pattern_w.max(pattern_h);
+  let grids = channel_grids(flat, block);
+  let (center_x, center_y) = (raw.width as f32 / 2.0, raw.height as f32 / 2.0);
+  let centers: Vec<f32> = grids.iter().map(|grid| grid.at(center_x, center_y, block)).collect();
+  if centers.iter().any(|c| *c <= 0.0) {
+    return Err("Flat field has no signal in the image center".into());
+  }
+
+  let bayer = raw.blacklevel.as_bayer_array();
+  let components = raw.blacklevel.as_vec();
+  // The white level of `raw` may have been raised to keep headroom, the flat field still has the sensor's one.
+  let white = raw.whitelevel.as_vec().into_iter().chain(flat.whitelevel.as_vec()).fold(f32::INFINITY, f32::min);
+
+  let mut pixels = raw.data.as_f32().into_owned();
+  let raw_ref = &*raw;
+  pixels.par_iter_mut().enumerate().for_each(|(i, p)| {
+    if *p >= white {
+      return;
+    }
+    let (row, col, channel) = locate(raw_ref, (pattern_w, pattern_h), i);
+    let falloff = grids[channel].at(col as f32 + 0.5, row as f32 + 0.5, block);
+    let gain = if falloff > 0.0 { (centers[channel] / falloff).min(MAX_GAIN) } else { 1.0 };
+    let black = black_level_at(raw_ref, &bayer, &components, i);
+    *p = black + (*p - black).max(0.0) * gain;
+  });
+
+  raw.data = match raw.data {
+    RawImageData::Integer(_) => RawImageData::Integer(pixels.into_iter().map(|p| p.round().min(u16::MAX as f32) as u16).collect()),
+    RawImageData::Float(_) => RawImageData::Float(pixels),
+  };
+  Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{
+    decoders::Camera,
+    pixarray::PixU16,
+    rawimage::{BlackLevel, WhiteLevel},
+  };
+
+  const SIZE: usize = 128;
+
+  fn raw_with(data: Vec<u16>) -> RawImage {
+    RawImage::new(
+      Camera::default(),
+      PixU16::new_with(data, SIZE, SIZE),
+      1,
+      [1.0; 4],
+      RawPhotometricInterpretation::LinearRaw,
+      Some(BlackLevel::new(&[100_u32], 1, 1, 1)),
+      Some(WhiteLevel::new(vec![4000])),
+      false,
+    )
+  }
+
+  /// Brightness falling off linearly from 1.0 on the left to 0.5 on the right.
+  fn falloff(col: usize) -> f32 {
+    1.0 - 0.5 * col as f32 / (SIZE - 1) as f32
+  }
+
+  #[test]
+  fn vignetting_is_removed() {
+    let flat = raw_with((0..SIZE * SIZE).map(|i| 100 + (2000.0 * falloff(i % SIZE)) as u16).collect());
+    let mut raw = raw_with((0..SIZE * SIZE).map(|i| 100 + (1000.0 * falloff(i % SIZE)) as u16).collect());
+    divide_flat_field(&mut raw, &flat).unwrap();
+
+    let center = 100.0 + 1000.0 * falloff(SIZE / 2);
+    let data = raw.data.as_f32();
+    for col in [0, 20, SIZE / 2, 100, SIZE - 1] {
+      let value = data[(SIZE / 2) * SIZE + col];
+      assert!((value - center).abs() / (center - 100.0) < 0.02, "column {}: {} vs {}", col, value, center);
+    }
+  }
+
+  #[test]
+  fn clipped_samples_stay_clipped() {
+    let flat = raw_with((0..SIZE * SIZE).map(|i| 100 + (2000.0 * falloff(i % SIZE)) as u16).collect());
+    let mut raw = raw_with(vec![4000; SIZE * SIZE]);
+    divide_flat_field(&mut raw, &flat).unwrap();
+    assert!(raw.data.as_f32().iter().all(|p| *p == 4000.0));
+  }
+}
@@ -0,0 +1,236 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Highlight recovery for demosaiced camera RGB data
+//!
+//! Sensor channels saturate at the same raw value, but white balance scales
+//! them differently. Without recovery, a blown area keeps the full red and
+//! blue values while green stops at the white balanced clip level, which
+//! renders as magenta.
+
+use rayon::prelude::*;
+
+use crate::pixarray::Color2D;
+
+/// How pixels with one or more saturated channels are treated.
+#[derive(PartialEq, Eq, Debug, Clone, Copy, Default)]
+pub enum HighlightMode {
+  /// Clip all channels at the level where the first one saturates. Blown
+  /// areas become neutral white, but some highlight detail is lost.
+  #[default]
+  Clip,
+  /// Keep the brightness of the unclipped data, but take the color from the
+  /// clipped data (like dcraw -H 2). Smooth transition into blown areas.
+  Blend,
+  /// Rebuild saturated channels from the unclipped ones, using the color
+  /// ratios of nearby unclipped pixels. Falls back to `Blend` where all
+  /// channels are saturated.
+  Reconstruct,
+}
+
+/// Channel values above this fraction of the saturation level are treated as clipped.
+/// Demosaicing smears the clip edge, so exact comparison does not work.
+const CLIPPED_THRESHOLD: f32 = 0.98;
+
+/// Size of the blocks in which the color of the surrounding is estimated.
+const BLOCK_SIZE: usize = 16;
+
+/// Recover highlights in camera RGB data (before white balance and color
+/// calibration).
+///
+/// `saturation` is the level at which the sensor clips, 1.0 for data that
+/// was rescaled to the white level. The output can exceed the white balanced
+/// clip level for `Blend` and `Reconstruct`.
+pub fn recover_highlights(pixels: &mut Color2D<f32, 3>, wb: &[f32; 4], saturation: f32, mode: HighlightMode) {
+  let wb = [wb[0], wb[1], wb[2]];
+  let clip = saturation * wb.iter().copied().fold(f32::INFINITY, f32::min);
+  let limit = wb.map(|c| c * saturation * CLIPPED_THRESHOLD);
+
+  match mode {
+    HighlightMode::Clip => pixels.data.par_iter_mut().for_each(|p| {
+      for c in 0..3 {
+        p[c] = p[c].min(clip / wb[c]);
+      }
+    }),
+    HighlightMode::Blend => pixels.data.par_iter_mut().for_each(|p| {
+      let v = [0, 1, 2].map(|c| p[c] * wb[c]);
+      if v.iter().any(|v| *v > clip) {
+        let blended = blend(v, clip);
+        *p = [0, 1, 2].map(|c| blended[c] / wb[c]);
+      }
+    }),
+    HighlightMode::Reconstruct => {
+      let ratios = RatioMap::new(pixels, &wb, clip, &limit);
+      let width = pixels.width;
+      pixels.data.par_chunks_exact_mut(width).enumerate().for_each(|(row, line)| {
+        for (col, p) in line.iter_mut().enumerate() {
+          let v = [0, 1, 2].map(|c| p[c] * wb[c]);
+          let clipped = [0, 1, 2].map(|c| v[c] >= limit[c]);
+          if !clipped.iter().any(|c| *c) {
+            continue;
+          }
+          let recovered = match ratios.at(row, col) {
+            Some(ratio) if !clipped.iter().all(|c| *c) => reconstruct(v, clipped, ratio),
+            _ => blend(v, clip),
+          };
+          *p = [0, 1, 2].map(|c| recovered[c] / wb[c]);
+        }
+      });
+    }
+  }
+}
+
+/// Take the luminance from the unclipped value and the chroma from the
+/// clipped value, in a space where both are separated.
+fn blend(v: [f32; 3], clip: f32) -> [f32; 3] {
+  const TRANS: [[f32; 3]; 3] = [[1.0, 1.0, 1.0], [1.732_050_8, -1.732_050_8, 0.0], [-1.0, -1.0, 2.0]];
+  const ITRANS: [[f32; 3]; 3] = [[1.0, 0.866_025_4, -0.5], [1.0, -0.866_025_4, -0.5], [1.0, 0.0, 1.0]];
+  let to_lab = |v: [f32; 3]| TRANS.map(|t| t[0] * v[0] + t[1] * v[1] + t[2] * v[2]);
+
+  let mut lab = to_lab(v);
+  let clipped = to_lab(v.map(|x| x.min(clip)));
+  let chroma = lab[1].powi(2) + lab[2].powi(2);
+  if chroma > 0.0 {
+    let ratio = ((clipped[1].powi(2) + clipped[2].powi(2)) / chroma).sqrt();
+    lab[1] *= ratio;
+    lab[2] *= ratio;
+  }
+  ITRANS.map(|t| ((t[0] * lab[0] + t[1] * lab[1] + t[2] * lab[2]) / 3.0).max(0.0))
+}
+
+/// Scale the color `ratio` to match the unclipped channels and use it for the clipped ones.
+fn reconstruct(v: [f32; 3], clipped: [bool; 3], ratio: [f32; 3]) -> [f32; 3] {
+  let (known, known_ratio) = (0..3).filter(|c| !clipped[*c]).fold((0.0, 0.0), |(k, r), c| (k + v[c], r + ratio[c]));
+  if known_ratio <= f32::EPSILON {
+    return v;
+  }
+  let scale = known / known_ratio;
+  [0, 1, 2].map(|c| if clipped[c] { v[c].max(scale * ratio[c]) } else { v[c] })
+}
+
+/// Normalized color (channels sum to one) of the bright, unclipped pixels in
+/// each block. Blocks without such pixels take the color of their neighbours.
+struct RatioMap {
+  blocks_w: usize,
+  blocks_h: usize,
+  ratios: Vec<Option<[f32; 3]>>,
+}
+
+impl RatioMap {
+  fn new(pixels: &Color2D<f32, 3>, wb: &[f32; 3], clip: f32, limit: &[f32; 3]) -> Self {
+    let blocks_w = pixels.width.div_ceil(BLOCK_SIZE);
+    let blocks_h = pixels.height.div_ceil(BLOCK_SIZE);
+
+    let mut ratios: Vec<Option<[f32; 3]>> = (0..blocks_w * blocks_h)
+      .into_par_iter()
+      .map(|block| {
+        let (by, bx) = (block / blocks_w, block % blocks_w);
+        let mut sum = [0.0_f32; 3];
+        for row in by * BLOCK_SIZE..((by + 1) * BLOCK_SIZE).min(pixels.height) {
+          for col in bx * BLOCK_SIZE..((bx + 1) * BLOCK_SIZE).min(pixels.width) {
+            let p = pixels.at(row, col);
+            let v = [0, 1, 2].map(|c| p[c] * wb[c]);
+            // Only the surrounding of highlights tells their color, dark pixels are too noisy.
+            if v.iter().zip(limit).all(|(v, l)| v < l) && v.iter().copied().fold(0.0, f32::max) > clip * 0.5 {
+              sum.iter_mut().zip(v).for_each(|(s, v)| *s += v);
+            }
+          }
+        }
+        let total: f32 = sum.iter().sum();
+        (total > 0.0).then(|| sum.map(|s| s / total))
+      })
+      .collect();
+
+    // Grow the known colors into blown areas, one ring of blocks per pass.
+    for _ in 0..blocks_w.max(blocks_h) {
+      if ratios.iter().all(Option::is_some) {
+        break;
+      }
+      let previous = ratios.clone();
+      let mut changed = false;
+      for by in 0..blocks_h {
+        for bx in 0..blocks_w {
+          if previous[by * blocks_w + bx].is_some() {
+            continue;
+          }
+          let mut sum = [0.0_f32; 3];
+          let mut count = 0;
+          for ny in by.saturating_sub(1)..(by + 2).min(blocks_h) {
+            for nx in bx.saturating_sub(1)..(bx + 2).min(blocks_w) {
+              if let Some(r) = previous[ny * blocks_w + nx] {
+                sum.iter_mut().zip(r).for_each(|(s, r)| *s += r);
+                count += 1;
+              }
+            }
+          }
+          if count > 0 {
+            ratios[by * blocks_w + bx] = Some(sum.map(|s| s / count as f32));
+            changed = true;
+          }
+        }
+      }
+      if !changed {
+        break;
+      }
+    }
+
+    Self { blocks_w, blocks_h, ratios }
+  }
+
+  /// Bilinear interpolation between the block centers
+  fn at(&self, row: usize, col: usize) -> Option<[f32; 3]> {
+    let fy = ((row as f32 + 0.5) / BLOCK_SIZE as f32 - 0.5).clamp(0.0, (self.blocks_h - 1) as f32);
+    let fx = ((col as f32 + 0.5) / BLOCK_SIZE as f32 - 0.5).clamp(0.0, (self.blocks_w - 1) as f32);
+    let (y0, x0) = (fy as usize, fx as usize);
+    let (y1, x1) = ((y0 + 1).min(self.blocks_h - 1), (x0 + 1).min(self.blocks_w - 1));
+    let (ty, tx) = (fy - y0 as f32, fx - x0 as f32);
+
+    let mut out = [0.0_f32; 3];
+    for (y, x, weight) in [(y0, x0, (1.0 - ty) * (1.0 - tx)), (y0, x1, (1.0 - ty) * tx), (y1, x0, ty * (1.0 - tx)), (y1, x1, ty * tx)] {
+      let ratio = self.ratios[y * self.blocks_w + x]?;
+      out.iter_mut().zip(ratio).for_each(|(o, r)| *o += r * weight);
+    }
+    Some(out)
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  const WB: [f32; 4] = [2.0, 1.0, 1.5, f32::NAN];
+
+  #[test]
+  fn clip_makes_blown_pixels_neutral() {
+    let mut pixels = Color2D::<f32, 3>::new_with(vec![[1.0, 1.0, 1.0], [0.2, 0.3, 0.25]], 2, 1);
+    recover_highlights(&mut pixels, &WB, 1.0, HighlightMode::Clip);
+    let p = pixels.at(0, 0);
+    let v = [p[0] * WB[0], p[1] * WB[1], p[2] * WB[2]];
+    assert!(v.iter().all(|v| (v - 1.0).abs() < 1.0e-6), "{:?}", v);
+    assert_eq!(pixels.at(0, 1), &[0.2, 0.3, 0.25]);
+  }
+
+  #[test]
+  fn blend_keeps_unclipped_pixels() {
+    let mut pixels = Color2D::<f32, 3>::new_with(vec![[0.4, 0.9, 0.5], [1.0, 1.0, 1.0]], 2, 1);
+    recover_highlights(&mut pixels, &WB, 1.0, HighlightMode::Blend);
+    assert_eq!(pixels.at(0, 0), &[0.4, 0.9, 0.5]);
+    let p = pixels.at(0, 1);
+    let v = [p[0] * WB[0], p[1] * WB[1], p[2] * WB[2]];
+    // Less saturated than the clipped input, which was (2.0, 1.0, 1.5) after white balance
+    assert!(v[0] / v[1] < 2.0 && v[2] / v[1] < 1.5, "{:?}", v);
+  }
+
+  #[test]
+  fn reconstruct_uses_surrounding_color() {
+    // Neutral surrounding (after white balance) with a pixel where only green clipped
+    let neutral = [0.35, 0.7, 0.7 / 1.5];
+    let mut data = vec![neutral; 32 * 32];
+    data[16 * 32 + 16] = [0.6, 1.0, 0.8];
+    let mut pixels = Color2D::<f32, 3>::new_with(data, 32, 32);
+    recover_highlights(&mut pixels, &WB, 1.0, HighlightMode::Reconstruct);
+    let p = pixels.at(16, 16);
+    // Red and blue are 1.2 after white balance, so green is rebuilt to the same level
+    assert!((p[1] * WB[1] - 1.2).abs() < 1.0e-3, "{:?}", p);
+    assert_eq!(pixels.at(0, 0), &neutral);
+  }
+}
@@ -1,13 +1,23 @@
 // SPDX-License-Identifier: LGPL-2.1
 // Copyright 2021 Daniel Vogelbacher <daniel@chaospixel.com>
 
+pub mod bad_pixels;
+pub mod banding;
+pub mod chromatic_aberration;
+pub mod dark_frame;
+pub mod false_color;
+pub mod flat_field;
 pub mod develop;
 pub mod gamma;
+pub mod highlights;
 pub mod matrix;
+pub mod pixel_shift;
 pub mod raw;
+pub mod rolloff;
 pub mod sensor;
 pub mod spline;
 pub mod srgb;
+pub mod white_balance;
 pub mod xyz;
 pub mod yuv;
 
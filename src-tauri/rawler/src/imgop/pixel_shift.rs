@@ -0,0 +1,150 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Pixel shift composites
+//!
+//! In pixel shift mode the camera moves the sensor by one pixel between the
+//! frames of a burst, so every scene point is captured through each color of
+//! the bayer pattern. Merging the frames gives a full color image without
+//! demosaic interpolation, as long as nothing moved between the frames.
+
+use rayon::prelude::*;
+
+use crate::rawimage::{BlackLevel, RawImage, RawImageData, RawPhotometricInterpretation, WhiteLevel};
+
+use super::Point;
+
+/// Sensor offsets of a four shot burst, relative to the first frame.
+/// The sensor moves one pixel right, then down, then back left.
+pub fn four_shot_offsets() -> Vec<Point> {
+  vec![Point::new(0, 0), Point::new(1, 0), Point::new(1, 1), Point::new(0, 1)]
+}
+
+/// Merge the frames of a pixel shift burst into a three component linear image.
+///
+/// `offsets[i]` is the position of the sensor for `frames[i]`: the scene
+/// point of pixel (x, y) was captured by the sensor pixel at
+/// (x + offset.x, y + offset.y). The frames must be unscaled bayer data of
+/// the same size. Black and white levels and the remaining metadata are taken
+/// from the first frame.
+pub fn merge_pixel_shift(frames: &[RawImage], offsets: &[Point]) -> crate::Result<RawImage> {
+  if frames.len() < 2 || frames.len() != offsets.len() {
+    return Err(format!("Pixel shift needs one offset per frame, got {} frames and {} offsets", frames.len(), offsets.len()).into());
+  }
+  let first = &frames[0];
+  let cfa = match &first.photometric {
+    RawPhotometricInterpretation::Cfa(config) if config.cfa.is_rgb() && config.cfa.width == 2 && config.cfa.height == 2 => config.cfa.clone(),
+    _ => return Err("Pixel shift is only supported for bayer sensors".to_string().into()),
+  };
+  if frames.iter().any(|frame| frame.dim() != first.dim() || frame.cpp != 1 || frame.photometric != first.photometric) {
+    return Err("Pixel shift frames differ in size or color filter".to_string().into());
+  }
+  let data: Vec<&[u16]> = frames
+    .iter()
+    .map(|frame| match &frame.data {
+      RawImageData::Integer(data) => Ok(data.as_slice()),
+      RawImageData::Float(_) => Err("Pixel shift frames must be integer sensor data".to_string()),
+    })
+    .collect::<std::result::Result<_, _>>()?;
+
+  let (width, height) = (first.width, first.height);
+  if width < 3 || height < 3 {
+    return Err("Pixel shift frames are too small".to_string().into());
+  }
+  // Shifted positions past the border use the pixel two steps back, which has the same color.
+  let inside = |pos: usize, len: usize| if pos < len { pos } else { pos - 2 };
+
+  let mut merged = vec![0_u16; width * height * 3];
+  merged.par_chunks_exact_mut(width * 3).enumerate().for_each(|(y, row)| {
+    for (x, pixel) in row.chunks_exact_mut(3).enumerate() {
+      let mut sums = [0_u32; 3];
+      let mut counts = [0_u32; 3];
+      for (frame, offset) in data.iter().zip(offsets) {
+        let sy = inside(y + offset.y, height);
+        let sx = inside(x + offset.x, width);
+        let color = cfa.color_at(sy, sx).min(2);
+        sums[color] += frame[sy * width + sx] as u32;
+        counts[color] += 1;
+      }
+      for c in 0..3 {
+        // Bursts with missing colors can only come from wrong offsets, the pixel stays black then.
+        pixel[c] = (sums[c] + counts[c] / 2).checked_div(counts[c]).unwrap_or(0) as u16;
+      }
+    }
+  });
+
+  // Levels of the first position in the pattern that has the color.
+  let black = first.blacklevel.as_bayer_array();
+  let white = first.whitelevel.as_bayer_array();
+  let level_of = |levels: &[f32; 4], color: usize| (0..4).find(|&i| cfa.color_at(i / 2, i % 2) == color).map_or(levels[0], |i| levels[i]).round() as u32;
+
+  let mut image = first.clone();
+  image.cpp = 3;
+  image.data = RawImageData::Integer(merged);
+  image.photometric = RawPhotometricInterpretation::LinearRaw;
+  image.blacklevel = BlackLevel::new(&[level_of(&black, 0), level_of(&black, 1), level_of(&black, 2)], 1, 1, 3);
+  image.whitelevel = WhiteLevel::new(vec![level_of(&white, 0), level_of(&white, 1), level_of(&white, 2)]);
+  image.blackareas.clear();
+  Ok(image)
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::{cfa::CFA, decoders::Camera, pixarray::PixU16, rawimage::CFAConfig};
+
+  fn bayer_frame(data: Vec<u16>, width: usize) -> RawImage {
+    let camera = Camera { cfa: CFA::new("RGGB"), ..Default::default() };
+    let height = data.len() / width;
+    let photometric = RawPhotometricInterpretation::Cfa(CFAConfig::new_from_camera(&camera));
+    RawImage::new(camera, PixU16::new_with(data, width, height), 1, [1.0; 4], photometric, Some(BlackLevel::new(&[64_u32, 64, 64, 64], 2, 2, 1)), Some(WhiteLevel::new(vec![4095])), false)
+  }
+
+  // Scene value of `color` at (x, y), the colors differ and every pixel is unique.
+  fn scene(color: usize, x: usize, y: usize) -> u16 {
+    (1000 * (color + 1) + 10 * y + x) as u16
+  }
+
+  // The scene as captured by an RGGB sensor moved to `offset`.
+  fn shifted_frame(offset: Point) -> RawImage {
+    let cfa = CFA::new("RGGB");
+    let data = (0..16)
+      .map(|i| {
+        let (sy, sx) = (i / 4, i % 4);
+        scene(cfa.color_at(sy, sx), sx.saturating_sub(offset.x), sy.saturating_sub(offset.y))
+      })
+      .collect();
+    bayer_frame(data, 4)
+  }
+
+  #[test]
+  fn four_shots_give_every_color_everywhere() {
+    let offsets = four_shot_offsets();
+    let frames: Vec<RawImage> = offsets.iter().map(|offset| shifted_frame(*offset)).collect();
+    let merged = merge_pixel_shift(&frames, &offsets).unwrap();
+    assert_eq!(merged.cpp, 3);
+    assert_eq!(merged.photometric, RawPhotometricInterpretation::LinearRaw);
+    // The last row and column have no shifted samples and borrow from their neighbours.
+    let pixels = merged.pixels_u16();
+    for y in 0..3 {
+      for x in 0..3 {
+        let i = (y * 4 + x) * 3;
+        assert_eq!(pixels[i..i + 3], [scene(0, x, y), scene(1, x, y), scene(2, x, y)]);
+      }
+    }
+    assert_eq!(merged.blacklevel.as_vec(), vec![64.0, 64.0, 64.0]);
+    assert_eq!(merged.whitelevel.0, vec![4095, 4095, 4095]);
+  }
+
+  #[test]
+  fn offsets_must_match_frames() {
+    let frames = vec![shifted_frame(Point::zero()), shifted_frame(Point::new(1, 0))];
+    assert!(merge_pixel_shift(&frames, &four_shot_offsets()).is_err());
+  }
+
+  #[test]
+  fn frames_must_have_the_same_size() {
+    let offsets = vec![Point::zero(), Point::new(1, 0)];
+    let frames = vec![shifted_frame(Point::zero()), bayer_frame(vec![0; 24], 4)];
+    assert!(merge_pixel_shift(&frames, &offsets).is_err());
+  }
+}
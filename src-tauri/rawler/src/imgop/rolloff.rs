@@ -0,0 +1,82 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Highlight rolloff
+//!
+//! Linear camera data has no upper bound after white balance and the color
+//! matrix, and whatever ends up above white is clipped hard later. Like the
+//! shoulder of a film curve, values above a knee are compressed so they
+//! approach white smoothly. The compression is applied to the largest
+//! channel and all channels are scaled by the same factor, so hues are kept.
+
+use rayon::prelude::*;
+
+use crate::pixarray::{Color2D, PixF32};
+
+/// Fraction of white where the shoulder starts. Values below are unchanged.
+const KNEE: f32 = 0.5;
+
+/// Compress `value` above `KNEE * white` towards `white`.
+///
+/// The curve continues the identity with the same slope at the knee and
+/// approaches `white` asymptotically, so highlights keep their gradation
+/// instead of being clipped.
+pub fn rolloff(value: f32, white: f32) -> f32 {
+  let knee = KNEE * white;
+  if value <= knee {
+    return value;
+  }
+  let range = white - knee;
+  knee + range * (1.0 - (-(value - knee) / range).exp())
+}
+
+/// Roll off the highlights of a RGB image, keeping the channel ratios.
+pub fn rolloff_rgb(pixels: &mut Color2D<f32, 3>, white: f32) {
+  pixels.data.par_iter_mut().for_each(|p| {
+    let max = p[0].max(p[1]).max(p[2]);
+    if max > KNEE * white {
+      let scale = rolloff(max, white) / max;
+      p.iter_mut().for_each(|c| *c *= scale);
+    }
+  });
+}
+
+/// Roll off the highlights of a single channel image.
+pub fn rolloff_mono(pixels: &mut PixF32, white: f32) {
+  pixels.data.par_iter_mut().for_each(|p| *p = rolloff(*p, white));
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  #[test]
+  fn values_below_the_knee_are_unchanged() {
+    for v in [0.0, 0.1, 0.25, 0.5] {
+      assert_eq!(rolloff(v, 1.0), v);
+    }
+  }
+
+  #[test]
+  fn curve_is_smooth_and_stays_below_white() {
+    let mut last = rolloff(0.5, 1.0);
+    for i in 1..300 {
+      let v = rolloff(0.5 + i as f32 * 0.01, 1.0);
+      assert!(v > last && v < 1.0, "{} after {}", v, last);
+      last = v;
+    }
+    assert!(rolloff(1000.0, 1.0) <= 1.0);
+    // Same slope as the identity just above the knee
+    assert!((rolloff(0.501, 1.0) - 0.501).abs() < 1e-5);
+    assert!((rolloff(8.0, 4.0) - 4.0 * rolloff(2.0, 1.0)).abs() < 1e-5);
+  }
+
+  #[test]
+  fn rgb_keeps_channel_ratios() {
+    let mut pixels = Color2D::new_with(vec![[2.0, 1.0, 0.5], [0.4, 0.3, 0.2]], 2, 1);
+    rolloff_rgb(&mut pixels, 1.0);
+    let [r, g, b] = pixels.data[0];
+    assert!(r < 1.0);
+    assert!((g / r - 0.5).abs() < 1e-6 && (b / r - 0.25).abs() < 1e-6);
+    assert_eq!(pixels.data[1], [0.4, 0.3, 0.2]);
+  }
+}
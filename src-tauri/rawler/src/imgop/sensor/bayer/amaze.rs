@@ -0,0 +1,350 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+use rayon::prelude::*;
+use std::time::Instant;
+
+use crate::{
+  cfa::{PlaneColor, CFA, CFA_COLOR_B, CFA_COLOR_G, CFA_COLOR_R},
+  imgop::Rect,
+  pixarray::{Color2D, PixF32, RgbF32},
+};
+
+use super::{compute_plane, ppg::interpolate_borders, Demosaic};
+
+/// Pixels on each border that are interpolated bilinear, because
+/// the AMaZE stages need up to 9 pixels of neighbourhood.
+const BORDER: usize = 9;
+
+/// Adaptive ratio estimates whose ratio is further away from 1.0 than
+/// this are not trusted and Hamilton-Adams is used instead.
+const ARTHRESH: f32 = 0.75;
+
+#[derive(Default)]
+pub struct AmazeDemosaic {
+  statistics: Option<Statistics>,
+}
+
+/// Channel balance and data range the algorithm adapts to.
+#[derive(Clone, Copy)]
+struct Statistics {
+  scale: [f32; 3],
+  max: f32,
+}
+
+impl AmazeDemosaic {
+  pub fn new() -> Self {
+    Self { statistics: None }
+  }
+
+  /// Use the statistics of the whole `roi` for every call, instead of the
+  /// statistics of the area that is demosaiced. Required when the image is
+  /// demosaiced in tiles, otherwise the tiles differ slightly.
+  pub fn for_image(pixels: &PixF32, cfa: &CFA, roi: Rect) -> Self {
+    Self {
+      statistics: Some(statistics(pixels, cfa, roi)),
+    }
+  }
+}
+
+/// AMaZE demosaic a raw image (f32 values)
+///
+/// AMaZE - Aliasing Minimization and Zipper Elimination - was developed by Emil Martinec
+/// for RawTherapee. This implementation follows the main stages of the original:
+/// green is interpolated from adaptive ratio and Hamilton-Adams estimates, blended
+/// between horizontal and vertical by the local variance of the color differences.
+/// Red and blue are interpolated as color differences along the smoother diagonal.
+/// The Nyquist texture and chromatic aberration stages are not included.
+///
+/// It is slower than PPG but produces far less maze and zipper artifacts in fine detail.
+///
+/// # Panics
+///
+/// This function panics for CFA pattern that are not RGGB or variants. You need
+/// to check the pattern before calling.
+impl Demosaic<f32, 3> for AmazeDemosaic {
+  fn demosaic(&self, pixels: &PixF32, cfa: &CFA, _colors: &PlaneColor, roi: Rect) -> Color2D<f32, 3> {
+    if !cfa.is_rgb() {
+      panic!("CFA pattern '{}' is not a RGB pattern, can not demosaic with AMaZE", cfa);
+    }
+    let now = Instant::now();
+
+    // The ROI changes the pattern if not perfectly aligned on the origin pattern
+    let cfa_roi = cfa.shift(roi.p.x, roi.p.y);
+
+    let mut rgb = super::expand_bayer_rgb(pixels.pixels(), pixels.dim(), cfa, roi);
+    let (w, h) = (rgb.width, rgb.height);
+
+    if w > 2 * BORDER && h > 2 * BORDER {
+      let raw: Vec<f32> = rgb.pixels().iter().enumerate().map(|(i, p)| p[cfa_roi.color_at(i / w, i % w)]).collect();
+
+      // The ratio estimates expect roughly balanced channels, but white balance
+      // is applied after demosaic. Scale red and blue to the green mean instead.
+      let Statistics { scale, max } = self.statistics.unwrap_or_else(|| statistics(pixels, cfa, roi));
+      let raw: Vec<f32> = raw.iter().enumerate().map(|(i, v)| v * scale[cfa_roi.color_at(i / w, i % w)]).collect();
+
+      // Input is not normalized, so the regularisation has to follow the data range.
+      let eps = max * 1.0e-5 + f32::MIN_POSITIVE;
+
+      let green = interpolate_green(&raw, w, h, &cfa_roi, eps);
+
+      let mut work = RgbF32::new(w, h);
+      work.pixels_mut().iter_mut().enumerate().for_each(|(i, p)| {
+        p[cfa_roi.color_at(i / w, i % w)] = raw[i];
+        p[CFA_COLOR_G] = green[i];
+      });
+      interpolate_rb_at_non_green(&mut work, &cfa_roi, eps);
+      interpolate_rb_at_green(&mut work, &cfa_roi, eps);
+
+      // Only the missing channels are taken over, the known sample stays untouched.
+      // Borders are overwritten below anyway.
+      rgb.pixels_mut().par_chunks_exact_mut(w).zip(work.pixels().par_chunks_exact(w)).enumerate().for_each(|(row, (out, line))| {
+        for (col, (o, p)) in out.iter_mut().zip(line).enumerate() {
+          let known = cfa_roi.color_at(row, col);
+          for ch in [CFA_COLOR_R, CFA_COLOR_G, CFA_COLOR_B] {
+            if ch != known {
+              o[ch] = p[ch] / scale[ch];
+            }
+          }
+        }
+      });
+    }
+    interpolate_borders(&mut rgb, &cfa_roi, BORDER);
+
+    log::debug!("AMaZE total debayer time: {:.5}s", now.elapsed().as_secs_f32());
+    rgb
+  }
+}
+
+/// Per channel factors that bring the red and blue means of `roi` to the green
+/// mean, and the largest sample after scaling.
+fn statistics(pixels: &PixF32, cfa: &CFA, roi: Rect) -> Statistics {
+  let mut sum = [(0.0_f64, 0_usize, 0.0_f32); 3];
+  for row in roi.y()..roi.y() + roi.height() {
+    for col in roi.x()..roi.x() + roi.width() {
+      let v = *pixels.at(row, col);
+      let ch = cfa.color_at(row, col);
+      sum[ch].0 += v as f64;
+      sum[ch].1 += 1;
+      sum[ch].2 = sum[ch].2.max(v);
+    }
+  }
+  let mean = sum.map(|(s, n, _)| if n > 0 { s / n as f64 } else { 0.0 });
+  let scale = mean.map(|m| if m > 0.0 && mean[CFA_COLOR_G] > 0.0 { (mean[CFA_COLOR_G] / m) as f32 } else { 1.0 });
+  let max = sum.iter().zip(scale).fold(0.0_f32, |m, ((_, _, max), scale)| m.max(max * scale));
+  Statistics { scale, max }
+}
+
+/// Prefer the adaptive ratio estimate, unless it overshoots the adjacent
+/// greens more than the Hamilton-Adams estimate does.
+fn pick_estimate(ratio: f32, ha: f32, g1: f32, g2: f32) -> f32 {
+  let (lo, hi) = (g1.min(g2), g1.max(g2));
+  let overshoot = |v: f32| (lo - v).max(v - hi).max(0.0);
+  if overshoot(ratio) > overshoot(ha) {
+    ha
+  } else {
+    ratio
+  }
+}
+
+/// Color difference variance of three samples (up to a constant factor)
+fn cd_variance(a: f32, b: f32, c: f32) -> f32 {
+  3.0 * (a * a + b * b + c * c) - (a + b + c) * (a + b + c)
+}
+
+/// AMaZE Demosaic: Interpolate missing G channels
+/// Returns a full green plane, valid for all pixels at least 7 pixels away from the edges.
+fn interpolate_green(raw: &[f32], w: usize, h: usize, cfa: &CFA, eps: f32) -> Vec<f32> {
+  let c = |row: usize, col: usize| raw[row * w + col];
+  let is_green = |row: usize, col: usize| cfa.color_at(row, col) == CFA_COLOR_G;
+
+  // Directional weights, large for strong gradients
+  let dirwts_v = compute_plane(w, h, 2, |row, col| eps + (c(row + 2, col) - c(row, col)).abs() + (c(row, col) - c(row - 2, col)).abs() + (c(row + 1, col) - c(row - 1, col)).abs());
+  let dirwts_h = compute_plane(w, h, 2, |row, col| eps + (c(row, col + 2) - c(row, col)).abs() + (c(row, col) - c(row, col - 2)).abs() + (c(row, col + 1) - c(row, col - 1)).abs());
+  let dv = |row: usize, col: usize| dirwts_v[row * w + col];
+  let dh = |row: usize, col: usize| dirwts_h[row * w + col];
+
+  // Ratio estimate of the green value next to (row, col) in direction (dr, dc),
+  // using the same color two pixels away.
+  let ratio = |row: usize, col: usize, near: (usize, usize), far: (usize, usize), dirwts: &dyn Fn(usize, usize) -> f32| {
+    let (wf, wc) = (dirwts(far.0, far.1), dirwts(row, col));
+    let cr = c(near.0, near.1) * (wf + wc) / (wf * (eps + c(row, col)) + wc * (eps + c(far.0, far.1)));
+    if (1.0 - cr).abs() < ARTHRESH {
+      Some(c(row, col) * cr)
+    } else {
+      None
+    }
+  };
+
+  // Vertical color differences (green - own color) at red and blue pixels
+  let vcd = compute_plane(w, h, 4, |row, col| {
+    if is_green(row, col) {
+      return 0.0;
+    }
+    let v = c(row, col);
+    let (n, s) = (c(row - 1, col), c(row + 1, col));
+    let gu_ha = n + 0.5 * (v - c(row - 2, col));
+    let gd_ha = s + 0.5 * (v - c(row + 2, col));
+    let gu = ratio(row, col, (row - 1, col), (row - 2, col), &dv).unwrap_or(gu_ha);
+    let gd = ratio(row, col, (row + 1, col), (row + 2, col), &dv).unwrap_or(gd_ha);
+    let vwt = dv(row - 1, col) / (dv(row - 1, col) + dv(row + 1, col));
+    let g_ar = vwt * gd + (1.0 - vwt) * gu;
+    let g_ha = vwt * gd_ha + (1.0 - vwt) * gu_ha;
+    pick_estimate(g_ar, g_ha, n, s) - v
+  });
+
+  // Horizontal color differences (green - own color) at red and blue pixels
+  let hcd = compute_plane(w, h, 4, |row, col| {
+    if is_green(row, col) {
+      return 0.0;
+    }
+    let v = c(row, col);
+    let (west, east) = (c(row, col - 1), c(row, col + 1));
+    let gl_ha = west + 0.5 * (v - c(row, col - 2));
+    let gr_ha = east + 0.5 * (v - c(row, col + 2));
+    let gl = ratio(row, col, (row, col - 1), (row, col - 2), &dh).unwrap_or(gl_ha);
+    let gr = ratio(row, col, (row, col + 1), (row, col + 2), &dh).unwrap_or(gr_ha);
+    let hwt = dh(row, col - 1) / (dh(row, col - 1) + dh(row, col + 1));
+    let g_ar = hwt * gr + (1.0 - hwt) * gl;
+    let g_ha = hwt * gr_ha + (1.0 - hwt) * gl_ha;
+    pick_estimate(g_ar, g_ha, west, east) - v
+  });
+  let vd = |row: usize, col: usize| vcd[row * w + col];
+  let hd = |row: usize, col: usize| hcd[row * w + col];
+
+  // Weight of the vertical estimate: the direction with the smoother color difference wins
+  let hvwt = compute_plane(w, h, 6, |row, col| {
+    if is_green(row, col) {
+      return 0.5;
+    }
+    let hvar = cd_variance(hd(row, col - 2), hd(row, col), hd(row, col + 2));
+    let vvar = cd_variance(vd(row - 2, col), vd(row, col), vd(row + 2, col));
+    if hvar + vvar > 0.0 {
+      hvar / (hvar + vvar)
+    } else {
+      0.5
+    }
+  });
+  let hv = |row: usize, col: usize| hvwt[row * w + col];
+
+  compute_plane(w, h, 7, |row, col| {
+    if is_green(row, col) {
+      return c(row, col);
+    }
+    // The diagonal neighbours are the other non-green color, their decision
+    // is used when it is more confident than the local one.
+    let local = hv(row, col);
+    let diag = 0.25 * (hv(row - 1, col - 1) + hv(row - 1, col + 1) + hv(row + 1, col - 1) + hv(row + 1, col + 1));
+    let wt = if (0.5 - local).abs() < (0.5 - diag).abs() { diag } else { local };
+    (c(row, col) + wt * vd(row, col) + (1.0 - wt) * hd(row, col)).max(0.0)
+  })
+}
+
+/// AMaZE Demosaic: Interpolate the missing R at B pixels and B at R pixels
+/// The color difference to green is taken from both diagonals, weighted by their gradients.
+fn interpolate_rb_at_non_green(img: &mut RgbF32, cfa: &CFA, eps: f32) {
+  let w = img.width;
+  let h = img.height;
+  let dataptr = img.data_ptr();
+  let margin = BORDER - 1;
+
+  img.pixels_mut().par_chunks_exact_mut(w).enumerate().skip(margin).take(h - 2 * margin).for_each(|(row, line)| {
+    for (col, pixel) in line.iter_mut().enumerate().skip(margin).take(w - 2 * margin) {
+      let ch = match cfa.color_at(row, col) {
+        CFA_COLOR_R => CFA_COLOR_B,
+        CFA_COLOR_B => CFA_COLOR_R,
+        _ => continue,
+      };
+      // The diagonal neighbours only get the channel they lack written,
+      // the values read here are never modified.
+      let (ne, sw, nw, se) = unsafe { (dataptr.at(row - 1, col + 1), dataptr.at(row + 1, col - 1), dataptr.at(row - 1, col - 1), dataptr.at(row + 1, col + 1)) };
+      let g = pixel[CFA_COLOR_G];
+      let grad_p = eps + (ne[ch] - sw[ch]).abs() + (ne[CFA_COLOR_G] - g).abs() + (g - sw[CFA_COLOR_G]).abs();
+      let grad_m = eps + (nw[ch] - se[ch]).abs() + (nw[CFA_COLOR_G] - g).abs() + (g - se[CFA_COLOR_G]).abs();
+      let cd_p = 0.5 * ((ne[CFA_COLOR_G] - ne[ch]) + (sw[CFA_COLOR_G] - sw[ch]));
+      let cd_m = 0.5 * ((nw[CFA_COLOR_G] - nw[ch]) + (se[CFA_COLOR_G] - se[ch]));
+      let pmwt = grad_m / (grad_p + grad_m);
+      pixel[ch] = (g - (pmwt * cd_p + (1.0 - pmwt) * cd_m)).max(0.0);
+    }
+  });
+}
+
+/// AMaZE Demosaic: Interpolate R and B at G pixels
+/// Each channel is interpolated from the color differences of the four direct
+/// neighbours, weighted by the inverse gradient towards them.
+fn interpolate_rb_at_green(img: &mut RgbF32, cfa: &CFA, eps: f32) {
+  let w = img.width;
+  let h = img.height;
+  let dataptr = img.data_ptr();
+
+  img.pixels_mut().par_chunks_exact_mut(w).enumerate().skip(BORDER).take(h - 2 * BORDER).for_each(|(row, line)| {
+    for (col, pixel) in line.iter_mut().enumerate().skip(BORDER).take(w - 2 * BORDER) {
+      if cfa.color_at(row, col) != CFA_COLOR_G {
+        continue;
+      }
+      // Neighbours are red or blue pixels which are not modified here
+      let (n, s, west, east) = unsafe { (dataptr.at(row - 1, col), dataptr.at(row + 1, col), dataptr.at(row, col - 1), dataptr.at(row, col + 1)) };
+      let g = pixel[CFA_COLOR_G];
+      for ch in [CFA_COLOR_R, CFA_COLOR_B] {
+        let mut sum = 0.0;
+        let mut wsum = 0.0;
+        for p in [n, s, west, east] {
+          let wt = 1.0 / (eps + (p[CFA_COLOR_G] - g).abs() + (p[ch] - g).abs().min((p[CFA_COLOR_G] - p[ch]).abs()));
+          sum += wt * (p[CFA_COLOR_G] - p[ch]);
+          wsum += wt;
+        }
+        pixel[ch] = (g - sum / wsum).max(0.0);
+      }
+    }
+  });
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::imgop::{Dim2, Point};
+
+  fn mosaic(w: usize, h: usize, cfa: &CFA, f: impl Fn(usize, usize, usize) -> f32) -> PixF32 {
+    let data = (0..w * h).map(|i| f(i / w, i % w, cfa.color_at(i / w, i % w))).collect();
+    PixF32::new_with(data, w, h)
+  }
+
+  #[test]
+  fn uniform_input_stays_uniform() {
+    let cfa = CFA::new("RGGB");
+    let pixels = mosaic(32, 32, &cfa, |_, _, ch| [0.2, 0.4, 0.1][ch]);
+    let roi = Rect::new(Point::new(0, 0), Dim2::new(32, 32));
+    let rgb = AmazeDemosaic::new().demosaic(&pixels, &cfa, &PlaneColor::new("RGB"), roi);
+    for p in rgb.pixels() {
+      assert!((p[0] - 0.2).abs() < 1.0e-5);
+      assert!((p[1] - 0.4).abs() < 1.0e-5);
+      assert!((p[2] - 0.1).abs() < 1.0e-5);
+    }
+  }
+
+  #[test]
+  fn grey_gradient_is_reconstructed() {
+    let cfa = CFA::new("GRBG");
+    let pixels = mosaic(40, 40, &cfa, |row, col, _| 0.1 + 0.01 * (row + col) as f32);
+    let roi = Rect::new(Point::new(0, 0), Dim2::new(40, 40));
+    let rgb = AmazeDemosaic::new().demosaic(&pixels, &cfa, &PlaneColor::new("RGB"), roi);
+    for row in BORDER..40 - BORDER {
+      for col in BORDER..40 - BORDER {
+        let expected = 0.1 + 0.01 * (row + col) as f32;
+        for v in rgb.at(row, col) {
+          assert!((v - expected).abs() < 1.0e-3, "({}, {}): {} != {}", row, col, v, expected);
+        }
+      }
+    }
+  }
+
+  #[test]
+  fn hard_edges_have_bounded_overshoot() {
+    let cfa = CFA::new("BGGR");
+    let pixels = mosaic(48, 48, &cfa, |row, col, ch| if (col / 3 + row / 5) % 2 == 0 { [0.9, 0.8, 0.7][ch] } else { 0.0 });
+    let roi = Rect::new(Point::new(0, 0), Dim2::new(48, 48));
+    let rgb = AmazeDemosaic::new().demosaic(&pixels, &cfa, &PlaneColor::new("RGB"), roi);
+    for p in rgb.pixels() {
+      assert!(p.iter().all(|v| v.is_finite() && (0.0..=1.2).contains(v)), "{:?}", p);
+    }
+  }
+}
@@ -1,4 +1,5 @@
 use multiversion::multiversion;
+use rayon::prelude::*;
 
 use crate::{
   CFA,
@@ -39,6 +40,9 @@ impl Bilinear4Channel {
 
     let pixels = pixels.crop(roi);
     let dim = pixels.dim();
+    if cfa.width != 2 || cfa.height != 2 || dim.w < 2 || dim.h < 2 {
+      return Bilinear4Channel::demosaic_any_pattern(&pixels, &cfa, &plane_map);
+    }
     let mut out = Color2D::new(pixels.dim().w, pixels.dim().h);
 
     // Process edges
@@ -110,4 +114,70 @@ impl Bilinear4Channel {
 
     out
   }
+
+  /// Patterns larger than 2x2, like the 2x8 CYGM pattern of some older
+  /// cameras, don't repeat a color on the direct neighbours. Missing colors
+  /// are averaged from the 3x3 neighbourhood, or 5x5 if it lacks the color.
+  fn demosaic_any_pattern(pixels: &PixF32, cfa: &CFA, plane_map: &[usize; 256]) -> Color2D<f32, 4> {
+    let dim = pixels.dim();
+    let mut out = Color2D::<f32, 4>::new(dim.w, dim.h);
+    out.pixels_mut().par_chunks_exact_mut(dim.w).enumerate().for_each(|(row, line)| {
+      for (col, pix) in line.iter_mut().enumerate() {
+        for radius in [1, 2] {
+          let mut sums = [0.0_f32; 4];
+          let mut counts = [0_u32; 4];
+          for y in row.saturating_sub(radius)..(row + radius + 1).min(dim.h) {
+            for x in col.saturating_sub(radius)..(col + radius + 1).min(dim.w) {
+              let plane = plane_map[cfa.color_at(y, x)].min(3);
+              sums[plane] += *pixels.at(y, x);
+              counts[plane] += 1;
+            }
+          }
+          for plane in 0..4 {
+            if counts[plane] > 0 {
+              pix[plane] = sums[plane] / counts[plane] as f32;
+            }
+          }
+          if counts.iter().all(|count| *count > 0) {
+            break;
+          }
+        }
+        pix[plane_map[cfa.color_at(row, col)].min(3)] = *pixels.at(row, col);
+      }
+    });
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::imgop::{Dim2, Point};
+
+  fn mosaic(w: usize, h: usize, cfa: &CFA, colors: &PlaneColor, values: [f32; 4]) -> PixF32 {
+    let plane_map = colors.plane_lookup_table();
+    let data = (0..w * h).map(|i| values[plane_map[cfa.color_at(i / w, i % w)]]).collect();
+    PixF32::new_with(data, w, h)
+  }
+
+  #[test]
+  fn uniform_cygm_stays_uniform() {
+    let cfa = CFA::new("GMYC");
+    let colors = PlaneColor::new("GMCY");
+    let values = [0.4, 0.3, 0.2, 0.1];
+    let pixels = mosaic(8, 8, &cfa, &colors, values);
+    let out = Bilinear4Channel::new().demosaic(&pixels, &cfa, &colors, Rect::new(Point::zero(), Dim2::new(8, 8)));
+    assert!(out.pixels().iter().all(|p| p.iter().zip(values).all(|(a, b)| (a - b).abs() < 1.0e-6)));
+  }
+
+  #[test]
+  fn uniform_large_pattern_stays_uniform() {
+    let cfa = CFA::new("EBGRBERGEBRGBEGR");
+    let colors = PlaneColor::new("RGBE");
+    let values = [0.4, 0.3, 0.2, 0.1];
+    let pixels = mosaic(6, 16, &cfa, &colors, values);
+    let out = Bilinear4Channel::new().demosaic(&pixels, &cfa, &colors, Rect::new(Point::new(1, 3), Dim2::new(4, 10)));
+    assert_eq!(out.dim(), Dim2::new(4, 10));
+    assert!(out.pixels().iter().all(|p| p.iter().zip(values).all(|(a, b)| (a - b).abs() < 1.0e-6)));
+  }
 }
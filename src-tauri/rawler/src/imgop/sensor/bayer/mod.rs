@@ -1,9 +1,12 @@
 // SPDX-License-Identifier: LGPL-2.1
 // Copyright 2021 Daniel Vogelbacher <daniel@chaospixel.com>
 
+pub mod amaze;
 pub mod bilinear;
+pub mod lmmse;
 pub mod ppg;
 pub mod superpixel;
+pub mod tiled;
 
 use multiversion::multiversion;
 use rayon::prelude::*;
@@ -38,6 +41,18 @@ fn expand_bayer_rgb(raw: &[f32], dim: Dim2, cfa: &CFA, roi: Rect) -> RgbF32 {
   out
 }
 
+/// Evaluate `f` for all pixels at least `margin` pixels away from the image edges.
+/// All other values are zero.
+pub(super) fn compute_plane(w: usize, h: usize, margin: usize, f: impl Fn(usize, usize) -> f32 + Sync) -> Vec<f32> {
+  let mut out = vec![0.0; w * h];
+  out.par_chunks_exact_mut(w).enumerate().skip(margin).take(h - 2 * margin).for_each(|(row, line)| {
+    for (col, v) in line.iter_mut().enumerate().skip(margin).take(w - 2 * margin) {
+      *v = f(row, col);
+    }
+  });
+  out
+}
+
 /// Bayer matrix pattern
 #[derive(Copy, Clone, Debug, PartialEq, Eq)]
 pub enum RgbBayerPattern {
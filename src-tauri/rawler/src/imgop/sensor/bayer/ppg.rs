@@ -53,7 +53,7 @@ impl Demosaic<f32, 3> for PPGDemosaic {
     let mut rgb = super::expand_bayer_rgb(pixels.pixels(), pixels.dim(), cfa, roi);
 
     // Now interpolate the missing channels
-    interpolate_borders(&mut rgb, &cfa_roi);
+    interpolate_borders(&mut rgb, &cfa_roi, 3);
     interpolate_green(&mut rgb, &cfa_roi);
     interpolate_rb_at_green(&mut rgb, &cfa_roi);
     interpolate_rb_at_non_green(&mut rgb, &cfa_roi);
@@ -64,7 +64,7 @@ impl Demosaic<f32, 3> for PPGDemosaic {
 }
 
 /// PPG Demosaic: Interpolate borders
-/// We take 3 pixels on each border and interpolate by bilinear interpolation.
+/// We take `border` pixels on each border and interpolate by bilinear interpolation.
 /// Bilinear interpolation is done by `(x1 + x2 + x3 + x4) / 4` or a
 /// reduced sum and count if not all samples are available like on borders.
 ///
@@ -72,16 +72,16 @@ impl Demosaic<f32, 3> for PPGDemosaic {
 /// all channel values. Then apply interpolation to calculate the missing
 /// two channel colors for the origin pixel position.
 #[multiversion(targets("x86_64+avx+avx2", "x86+sse", "aarch64+neon"))]
-fn interpolate_borders(input: &mut RgbF32, shifted: &CFA) {
+pub(super) fn interpolate_borders(input: &mut RgbF32, shifted: &CFA, border: usize) {
   let w = input.width;
   let h = input.height;
   // Iterate over all rows
   for row in 0..h {
     let mut col = 0;
     while col < w {
-      // Full process first 3 and last 3 rows, process only left and right 3 pixels for all other
-      if col == 3 && row >= 3 && row < h - 3 {
-        col = w - 3 // go to right border
+      // Full process first and last border rows, process only left and right border pixels for all other
+      if col == border && row >= border && row + border < h && col + border < w {
+        col = w - border // go to right border
       }
       // Store the pixel sum and count for each RGB channel (R=0, G=1, B=2)
       let mut sum = [(0.0, 0_usize); 3];
@@ -209,4 +209,39 @@ impl Demosaic<f32, 3> for SuperpixelQuarterRes3Channel {
 
     Color2D::new_with(out_data, roi.width() >> 2, roi.height() >> 2)
   }
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::imgop::Point;
+
+  // RGGB samples: red 0.1, green 0.5, blue 0.9
+  fn rggb(width: usize, height: usize) -> PixF32 {
+    let data = (0..width * height)
+      .map(|i| match ((i / width) % 2, (i % width) % 2) {
+        (0, 0) => 0.1,
+        (1, 1) => 0.9,
+        _ => 0.5,
+      })
+      .collect();
+    PixF32::new_with(data, width, height)
+  }
+
+  #[test]
+  fn half_resolution_output() {
+    let pixels = rggb(8, 6);
+    let out = Superpixel3Channel::new().demosaic(&pixels, &CFA::new("RGGB"), &PlaneColor::new("RGB"), Rect::new(Point::zero(), pixels.dim()));
+    assert_eq!((out.width, out.height), (4, 3));
+    assert!(out.data.iter().all(|p| *p == [0.1, 0.5, 0.9]));
+  }
+
+  #[test]
+  fn shifted_roi_keeps_colors() {
+    // Starting on a green/blue row changes the pattern phase, odd sizes are truncated.
+    let pixels = rggb(10, 10);
+    let roi = Rect::new(Point::new(1, 1), Dim2::new(7, 5));
+    let out = Superpixel3Channel::new().demosaic(&pixels, &CFA::new("RGGB"), &PlaneColor::new("RGB"), roi);
+    assert_eq!((out.width, out.height), (3, 2));
+    assert!(out.data.iter().all(|p| *p == [0.1, 0.5, 0.9]));
+  }
+}
@@ -0,0 +1,159 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! Tiled demosaicing
+//!
+//! The demosaic algorithms allocate several full size planes for their
+//! intermediate results, which adds up to many gigabytes for 100 MP files.
+//! This wrapper runs an algorithm on overlapping tiles of the mosaic instead
+//! and only keeps the final image at full size. The overlap is wide enough
+//! for the algorithms' neighbourhoods, so the seams are not visible.
+
+use rayon::prelude::*;
+
+use super::Demosaic;
+use crate::{
+  cfa::{PlaneColor, CFA},
+  imgop::{Dim2, Point, Rect},
+  pixarray::{Color2D, PixF32},
+};
+
+/// Tile edge length without the overlap
+const TILE_SIZE: usize = 512;
+
+/// Pixels added on each side of a tile, more than the widest algorithm reads.
+const OVERLAP: usize = 32;
+
+pub struct TiledDemosaic<D> {
+  inner: D,
+  tile_size: usize,
+  overlap: usize,
+}
+
+impl<D> TiledDemosaic<D> {
+  pub fn new(inner: D) -> Self {
+    Self::with_tile_size(inner, TILE_SIZE, OVERLAP)
+  }
+
+  pub fn with_tile_size(inner: D, tile_size: usize, overlap: usize) -> Self {
+    Self { inner, tile_size, overlap }
+  }
+}
+
+/// Round `value` up to a multiple of `multiple`.
+fn align(value: usize, multiple: usize) -> usize {
+  value.div_ceil(multiple) * multiple
+}
+
+/// Start and length of the tiles along one axis, and the padded span around each.
+fn spans(start: usize, len: usize, tile: usize, overlap: usize) -> Vec<((usize, usize), (usize, usize))> {
+  (0..len)
+    .step_by(tile)
+    .map(|offset| {
+      let inner = (start + offset, tile.min(len - offset));
+      let padded_start = inner.0.saturating_sub(overlap).max(start);
+      let padded_end = (inner.0 + inner.1 + overlap).min(start + len);
+      (inner, (padded_start, padded_end - padded_start))
+    })
+    .collect()
+}
+
+impl<D, const N: usize> Demosaic<f32, N> for TiledDemosaic<D>
+where
+  D: Demosaic<f32, N> + Sync,
+{
+  fn demosaic(&self, pixels: &PixF32, cfa: &CFA, colors: &PlaneColor, roi: Rect) -> Color2D<f32, N> {
+    // Tiles start on the pattern grid, so every tile sees the same pattern
+    // phase as the full image would at that position.
+    let pattern = cfa.width.max(cfa.height).max(1);
+    let tile = align(self.tile_size.max(1), pattern);
+    let overlap = align(self.overlap, pattern);
+    if roi.width() <= tile + overlap && roi.height() <= tile + overlap {
+      return self.inner.demosaic(pixels, cfa, colors, roi);
+    }
+
+    let columns = spans(roi.x(), roi.width(), tile, overlap);
+    let rows = spans(roi.y(), roi.height(), tile, overlap);
+    let width = roi.width();
+    let mut out = Color2D {
+      data: vec![[0.0; N]; width * roi.height()],
+      width,
+      height: roi.height(),
+    };
+
+    out.data.par_chunks_mut(tile * width).zip(rows.par_iter()).for_each(|(band, ((row_start, row_len), (padded_y, padded_h)))| {
+      for ((col_start, col_len), (padded_x, padded_w)) in &columns {
+        let padded = Rect::new(Point::new(*padded_x, *padded_y), Dim2::new(*padded_w, *padded_h));
+        let result = self.inner.demosaic(pixels, cfa, colors, padded);
+        let (skip_x, skip_y) = (col_start - padded_x, row_start - padded_y);
+        for row in 0..*row_len {
+          let src = (skip_y + row) * result.width + skip_x;
+          let dst = row * width + (col_start - roi.x());
+          band[dst..dst + col_len].copy_from_slice(&result.data[src..src + col_len]);
+        }
+      }
+    });
+    out
+  }
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+  use crate::imgop::sensor::bayer::{amaze::AmazeDemosaic, lmmse::LmmseDemosaic, ppg::PPGDemosaic};
+
+  fn mosaic(width: usize, height: usize) -> PixF32 {
+    // Smooth gradients with some texture, deterministic.
+    let data = (0..width * height)
+      .map(|i| {
+        let (x, y) = ((i % width) as f32, (i / width) as f32);
+        (0.3 + 0.2 * (x * 0.37).sin() * (y * 0.23).cos() + 0.001 * x + ((i * 7919) % 97) as f32 * 0.001).clamp(0.0, 1.0)
+      })
+      .collect();
+    PixF32::new_with(data, width, height)
+  }
+
+  fn assert_same(a: &Color2D<f32, 3>, b: &Color2D<f32, 3>, tolerance: f32) {
+    assert_eq!((a.width, a.height), (b.width, b.height));
+    for (i, (p, q)) in a.data.iter().zip(b.data.iter()).enumerate() {
+      for c in 0..3 {
+        assert!((p[c] - q[c]).abs() < tolerance, "pixel {} channel {}: {} != {}", i, c, p[c], q[c]);
+      }
+    }
+  }
+
+  #[test]
+  fn tiles_match_the_full_image() {
+    let pixels = mosaic(200, 150);
+    let cfa = CFA::new("RGGB");
+    let colors = PlaneColor::new("RGB");
+    // An active area that starts off the pattern origin
+    let roi = Rect::new(Point::new(2, 4), Dim2::new(190, 140));
+    let full = PPGDemosaic::new().demosaic(&pixels, &cfa, &colors, roi);
+    let tiled = TiledDemosaic::with_tile_size(PPGDemosaic::new(), 64, 16).demosaic(&pixels, &cfa, &colors, roi);
+    assert_same(&tiled, &full, 1e-6);
+  }
+
+  #[test]
+  fn tiles_match_the_full_image_with_amaze() {
+    let pixels = mosaic(260, 200);
+    let cfa = CFA::new("GRBG");
+    let colors = PlaneColor::new("RGB");
+    let roi = Rect::new(Point::zero(), pixels.dim());
+    let full = AmazeDemosaic::new().demosaic(&pixels, &cfa, &colors, roi);
+    let amaze = AmazeDemosaic::for_image(&pixels, &cfa, roi);
+    let tiled = TiledDemosaic::with_tile_size(amaze, 96, 32).demosaic(&pixels, &cfa, &colors, roi);
+    assert_same(&tiled, &full, 1e-6);
+  }
+
+  #[test]
+  fn tiles_match_the_full_image_with_lmmse() {
+    let pixels = mosaic(220, 180);
+    let cfa = CFA::new("BGGR");
+    let colors = PlaneColor::new("RGB");
+    let roi = Rect::new(Point::new(1, 1), Dim2::new(216, 176));
+    let full = LmmseDemosaic::new().demosaic(&pixels, &cfa, &colors, roi);
+    let lmmse = LmmseDemosaic::for_image(&pixels, roi);
+    let tiled = TiledDemosaic::with_tile_size(lmmse, 64, 32).demosaic(&pixels, &cfa, &colors, roi);
+    assert_same(&tiled, &full, 1e-6);
+  }
+}
@@ -0,0 +1,321 @@
+// SPDX-License-Identifier: LGPL-2.1
+
+//! White balance from color temperature and tint
+//!
+//! The camera records white balance as multipliers for the raw channels. To
+//! set it as a color temperature, the white point on the Planckian locus is
+//! converted into the camera's color space with the D65 color matrix, the
+//! multipliers are what makes that white neutral. Tint moves the white point
+//! off the locus, positive values towards magenta.
+
+use crate::imgop::matrix::pseudo_inverse;
+use crate::imgop::{Point, Rect};
+use crate::rawimage::{RawImage, RawPhotometricInterpretation};
+
+use super::xyz::Illuminant;
+
+/// Range the color temperature is clamped to, in Kelvin. The approximation
+/// of the Planckian locus is valid in it.
+pub const MIN_TEMPERATURE: f32 = 1500.0;
+pub const MAX_TEMPERATURE: f32 = 15000.0;
+
+/// Tint units per unit of distance in the CIE 1960 UCS, as used by DNG.
+const TINT_SCALE: f32 = 3000.0;
+
+/// Samples above this fraction of the clip level are left out of the automatic
+/// estimate, clipped highlights have lost their color.
+const AUTO_CLIPPED: f32 = 0.95;
+
+/// Samples below this fraction of the clip level are mostly noise.
+const AUTO_DARK: f32 = 0.01;
+
+/// White balance used by `ProcessingStep::WhiteBalance`.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub enum WhiteBalance {
+  /// Multipliers recorded by the camera.
+  #[default]
+  AsShot,
+  /// Estimated from the raw data, assuming the scene averages to gray.
+  Auto,
+  /// Color temperature in Kelvin and tint, positive towards magenta.
+  Temperature { kelvin: f32, tint: f32 },
+}
+
+/// Planckian locus in the CIE 1960 UCS (Krystek's approximation).
+fn planckian_uv(kelvin: f32) -> (f32, f32) {
+  let t = kelvin as f64;
+  let u = (0.860117757 + 1.54118254e-4 * t + 1.28641212e-7 * t * t) / (1.0 + 8.42420235e-4 * t + 7.08145163e-7 * t * t);
+  let v = (0.317398726 + 4.22806245e-5 * t + 4.20481691e-8 * t * t) / (1.0 - 2.89741816e-5 * t + 1.61456053e-7 * t * t);
+  (u as f32, v as f32)
+}
+
+/// Unit vector perpendicular to the locus at `kelvin`, pointing towards green.
+fn locus_normal(kelvin: f32) -> (f32, f32) {
+  let (u0, v0) = planckian_uv(kelvin - 1.0);
+  let (u1, v1) = planckian_uv(kelvin + 1.0);
+  let (du, dv) = (u1 - u0, v1 - v0);
+  let length = (du * du + dv * dv).sqrt().max(f32::EPSILON);
+  let normal = (-dv / length, du / length);
+  if normal.1 < 0.0 {
+    (-normal.0, -normal.1)
+  } else {
+    normal
+  }
+}
+
+/// XYZ of the white point, normalized to Y = 1.
+pub fn temperature_to_xyz(kelvin: f32, tint: f32) -> [f32; 3] {
+  let kelvin = kelvin.clamp(MIN_TEMPERATURE, MAX_TEMPERATURE);
+  let (u, v) = planckian_uv(kelvin);
+  let (nu, nv) = locus_normal(kelvin);
+  let offset = -tint / TINT_SCALE;
+  let (u, v) = (u + nu * offset, v + nv * offset);
+
+  let denominator = 2.0 * u - 8.0 * v + 4.0;
+  let (x, y) = (3.0 * u / denominator, 2.0 * v / denominator);
+  [x / y, 1.0, (1.0 - x - y) / y]
+}
+
+/// Color temperature and tint of the white point `xyz`.
+pub fn xyz_to_temperature(xyz: [f32; 3]) -> (f32, f32) {
+  let denominator = xyz[0] + 15.0 * xyz[1] + 3.0 * xyz[2];
+  if denominator <= f32::EPSILON {
+    return (6500.0, 0.0);
+  }
+  let (u, v) = (4.0 * xyz[0] / denominator, 6.0 * xyz[1] / denominator);
+  let distance = |kelvin: f32| {
+    let (lu, lv) = planckian_uv(kelvin);
+    (u - lu).powi(2) + (v - lv).powi(2)
+  };
+
+  // The locus is close to evenly spaced in mired, search coarse and then fine.
+  let (min_mired, max_mired) = (1.0e6 / MAX_TEMPERATURE, 1.0e6 / MIN_TEMPERATURE);
+  let mut best = min_mired;
+  for step in 0..=((max_mired - min_mired) as usize) {
+    let mired = min_mired + step as f32;
+    if distance(1.0e6 / mired) < distance(1.0e6 / best) {
+      best = mired;
+    }
+  }
+  let coarse = best;
+  for step in -100..=100 {
+    let mired = (coarse + step as f32 * 0.01).clamp(min_mired, max_mired);
+    if distance(1.0e6 / mired) < distance(1.0e6 / best) {
+      best = mired;
+    }
+  }
+
+  let kelvin = 1.0e6 / best;
+  let (lu, lv) = planckian_uv(kelvin);
+  let (nu, nv) = locus_normal(kelvin);
+  let offset = (u - lu) * nu + (v - lv) * nv;
+  (kelvin, -offset * TINT_SCALE)
+}
+
+/// The XYZ to camera matrix for D65 and the number of color channels in it.
+pub fn d65_matrix(rawimage: &RawImage) -> crate::Result<([[f32; 3]; 4], usize)> {
+  let color_matrix = rawimage.color_matrix.iter().find(|(illuminant, _m)| **illuminant == Illuminant::D65).ok_or("Illuminant matrix D65 not found")?.1;
+  if color_matrix.len() % 3 != 0 || color_matrix.is_empty() || color_matrix.len() > 12 {
+    return Err(format!("Unexpected color matrix size {}", color_matrix.len()).into());
+  }
+  let components = color_matrix.len() / 3;
+  let mut xyz2cam: [[f32; 3]; 4] = [[0.0; 3]; 4];
+  for i in 0..components {
+    for j in 0..3 {
+      xyz2cam[i][j] = color_matrix[i * 3 + j];
+    }
+  }
+  Ok((xyz2cam, components))
+}
+
+/// Multipliers that make a white lit by `kelvin` and `tint` neutral,
+/// normalized like the camera's to a green multiplier of 1.0.
+pub fn temperature_to_coeffs(xyz2cam: &[[f32; 3]; 4], components: usize, kelvin: f32, tint: f32) -> [f32; 4] {
+  let xyz = temperature_to_xyz(kelvin, tint);
+  let mut coeffs = [f32::NAN; 4];
+  for (c, coeff) in coeffs.iter_mut().enumerate().take(components) {
+    let response: f32 = (0..3).map(|j| xyz2cam[c][j] * xyz[j]).sum();
+    *coeff = 1.0 / response.max(f32::EPSILON);
+  }
+  let green = coeffs[1];
+  coeffs.iter_mut().take(components).for_each(|c| *c /= green);
+  coeffs
+}
+
+/// Color temperature and tint the multipliers `coeffs` correspond to.
+pub fn coeffs_to_temperature(xyz2cam: &[[f32; 3]; 4], components: usize, coeffs: &[f32; 4]) -> (f32, f32) {
+  let cam2xyz = pseudo_inverse(*xyz2cam);
+  let mut white = [0.0; 4];
+  for c in 0..components {
+    white[c] = 1.0 / coeffs[c].max(f32::EPSILON);
+  }
+  let xyz = [0, 1, 2].map(|i| (0..4).map(|c| cam2xyz[i][c] * white[c]).sum::<f32>());
+  xyz_to_temperature(xyz)
+}
+
+/// Gray world estimate of the multipliers from scaled raw data, where the
+/// sensor clips at `clip`. Averages whole CFA patterns, so a sample that
+/// clipped or is lost in the noise removes the colors around it as well.
+pub fn auto_coeffs(rawimage: &RawImage, clip: f32) -> Option<[f32; 4]> {
+  let config = match &rawimage.photometric {
+    RawPhotometricInterpretation::Cfa(config) if rawimage.cpp == 1 => Some(config),
+    _ if rawimage.cpp >= 3 => None,
+    _ => return None,
+  };
+  let cfa = config.map(|config| &config.cfa);
+  // The multipliers are in plane order, which differs from the CFA color values for 4 color sensors.
+  let plane_map = config.map(|config| config.plane_colors().plane_lookup_table());
+  let (pattern_w, pattern_h) = cfa.map_or((1, 1), |cfa| (cfa.width.max(1), cfa.height.max(1)));
+  let color_count = cfa.map_or(rawimage.cpp, |cfa| cfa.unique_colors()).min(4);
+  if color_count < 3 {
+    return None;
+  }
+
+  let data = rawimage.data.as_f32();
+  let area = rawimage.active_area.unwrap_or_else(|| Rect::new(Point::zero(), rawimage.dim()));
+  let bottom = (area.p.y + area.d.h).min(rawimage.height);
+  let right = (area.p.x + area.d.w).min(rawimage.width);
+
+  let mut sums = [0.0_f64; 4];
+  for row in (area.p.y..bottom.saturating_sub(pattern_h - 1)).step_by(pattern_h) {
+    'cells: for col in (area.p.x..right.saturating_sub(pattern_w - 1)).step_by(pattern_w) {
+      let mut cell = [(0.0_f32, 0_u32); 4];
+      for r in row..row + pattern_h {
+        for c in col..col + pattern_w {
+          for sample in 0..color_count.min(rawimage.cpp) {
+            let value = data[(r * rawimage.width + c) * rawimage.cpp + sample];
+            if value >= clip * AUTO_CLIPPED || value <= clip * AUTO_DARK {
+              continue 'cells;
+            }
+            let color = cfa.zip(plane_map.as_ref()).map_or(sample, |(cfa, map)| map[cfa.color_at(r, c)]).min(3);
+            cell[color].0 += value;
+            cell[color].1 += 1;
+          }
+        }
+      }
+      for (sum, (value, count)) in sums.iter_mut().zip(cell) {
+        if count > 0 {
+          *sum += (value / count as f32) as f64;
+        }
+      }
+    }
+  }
+
+  if sums.iter().take(color_count).any(|sum| *sum <= 0.0) {
+    return None;
+  }
+  let mut coeffs = [f32::NAN; 4];
+  for c in 0..color_count {
+    coeffs[c] = (sums[1] / sums[c]) as f32;
+  }
+  Some(coeffs)
+}
+
+/// Multipliers for `white_balance`, `rawimage` must be scaled to the range
+/// the sensor clips at `clip`. Falls back to the camera's multipliers if the
+/// requested white balance cannot be computed for this image.
+pub fn resolve_coeffs(rawimage: &RawImage, white_balance: WhiteBalance, clip: f32) -> [f32; 4] {
+  let as_shot = if rawimage.wb_coeffs[0].is_nan() { [1.0, 1.0, 1.0, 1.0] } else { rawimage.wb_coeffs };
+  match white_balance {
+    WhiteBalance::AsShot => as_shot,
+    WhiteBalance::Auto => auto_coeffs(rawimage, clip).unwrap_or_else(|| {
+      log::debug!("No automatic white balance for this image, using as shot");
+      as_shot
+    }),
+    WhiteBalance::Temperature { kelvin, tint } => match d65_matrix(rawimage) {
+      Ok((xyz2cam, components)) if components >= 3 => temperature_to_coeffs(&xyz2cam, components, kelvin, tint),
+      _ => as_shot,
+    },
+  }
+}
+
+/// Color temperature and tint of the white balance of an unscaled raw image,
+/// to show the as shot or automatic white balance on temperature sliders.
+pub fn white_balance_temperature(rawimage: &RawImage, white_balance: WhiteBalance) -> crate::Result<(f32, f32)> {
+  if let WhiteBalance::Temperature { kelvin, tint } = white_balance {
+    return Ok((kelvin, tint));
+  }
+  let (xyz2cam, components) = d65_matrix(rawimage)?;
+  if components < 3 {
+    return Err("Monochrome images have no white balance".into());
+  }
+  let coeffs = match white_balance {
+    WhiteBalance::Auto => {
+      let mut scaled = rawimage.clone();
+      scaled.apply_scaling()?;
+      resolve_coeffs(&scaled, white_balance, 1.0)
+    }
+    _ => resolve_coeffs(rawimage, white_balance, 1.0),
+  };
+  Ok(coeffs_to_temperature(&xyz2cam, components, &coeffs))
+}
+
+#[cfg(test)]
+mod tests {
+  use super::*;
+
+  // XYZ to camera matrix of a Canon EOS 5D Mark II for D65.
+  const XYZ2CAM: [[f32; 3]; 4] = [[0.4716, 0.0603, -0.0830], [-0.7798, 1.5474, 0.2480], [-0.1496, 0.1937, 0.6651], [0.0, 0.0, 0.0]];
+
+  #[test]
+  fn temperature_round_trips_through_coeffs() {
+    for (kelvin, tint) in [(2800.0, 0.0), (5500.0, 10.0), (7500.0, -25.0)] {
+      let coeffs = temperature_to_coeffs(&XYZ2CAM, 3, kelvin, tint);
+      assert_eq!(coeffs[1], 1.0);
+      let (k, t) = coeffs_to_temperature(&XYZ2CAM, 3, &coeffs);
+      assert!((k - kelvin).abs() / kelvin < 0.01, "{} != {}", k, kelvin);
+      assert!((t - tint).abs() < 1.0, "{} != {}", t, tint);
+    }
+  }
+
+  #[test]
+  fn warmer_light_needs_more_blue() {
+    let tungsten = temperature_to_coeffs(&XYZ2CAM, 3, 3000.0, 0.0);
+    let daylight = temperature_to_coeffs(&XYZ2CAM, 3, 6500.0, 0.0);
+    assert!(tungsten[2] > daylight[2]);
+    assert!(tungsten[0] < daylight[0]);
+  }
+
+  #[test]
+  fn auto_white_balance_neutralizes_a_cast() {
+    use crate::{
+      decoders::Camera,
+      pixarray::PixU16,
+      rawimage::{BlackLevel, WhiteLevel},
+    };
+
+    // A gray gradient seen through a camera that records red at half and blue at a
+    // quarter of green, with a clipped block that must not take part.
+    let size = 64;
+    let data: Vec<u16> = (0..size * size)
+      .flat_map(|i| {
+        let gray = if i % size < 8 { 4000.0 } else { 400.0 + 20.0 * (i / size) as f32 };
+        [(gray * 0.5).min(4000.0) as u16, gray.min(4000.0) as u16, (gray * 0.25).min(4000.0) as u16]
+      })
+      .collect();
+    let raw = RawImage::new(
+      Camera::default(),
+      PixU16::new_with(data, size * 3, size),
+      3,
+      [1.0; 4],
+      RawPhotometricInterpretation::LinearRaw,
+      Some(BlackLevel::new(&[0_u32], 1, 1, 1)),
+      Some(WhiteLevel::new(vec![4000])),
+      false,
+    );
+    let coeffs = auto_coeffs(&raw, 4000.0).unwrap();
+    assert!((coeffs[0] - 2.0).abs() < 0.02, "{:?}", coeffs);
+    assert_eq!(coeffs[1], 1.0);
+    assert!((coeffs[2] - 4.0).abs() < 0.04, "{:?}", coeffs);
+  }
+
+  #[test]
+  fn magenta_tint_lowers_green() {
+    let (_, y_neutral, _) = {
+      let xyz = temperature_to_xyz(5000.0, 0.0);
+      (xyz[0], xyz[1] / (xyz[0] + xyz[1] + xyz[2]), xyz[2])
+    };
+    let xyz = temperature_to_xyz(5000.0, 50.0);
+    assert!(xyz[1] / (xyz[0] + xyz[1] + xyz[2]) < y_neutral);
+  }
+}
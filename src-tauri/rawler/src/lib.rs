@@ -247,6 +247,11 @@ pub fn raw_image_count_file<P: AsRef<Path>>(path: P) -> Result<usize> {
   LOADER.raw_image_count_file(path.as_ref())
 }
 
+/// Decode a pixel shift file into one merged image, `None` if the file is no pixel shift burst
+pub fn decode_pixel_shift(rawfile: &RawSource) -> Result<Option<RawImage>> {
+  LOADER.decode_pixel_shift(rawfile, false)
+}
+
 pub fn global_loader() -> &'static RawLoader {
   &LOADER
 }
@@ -6,7 +6,7 @@ use log::debug;
 use serde::{Deserialize, Serialize};
 
 use crate::Result;
-use crate::cfa::PlaneColor;
+use crate::cfa::{CFAColor, PlaneColor};
 use crate::imgop::raw::{correct_blacklevel, correct_blacklevel_cfa};
 use crate::imgop::{convert_from_f32_scaled_u16, convert_to_f32_unscaled};
 use crate::{
@@ -187,6 +187,24 @@ impl CFAConfig {
       colors: cam.plane_color.clone(),
     }
   }
+
+  /// Color planes for demosaic and calibration. Camera definitions of
+  /// some 4 color sensors lack the plane colors and get the RGB default,
+  /// the planes are taken from the pattern then, in color order.
+  pub fn plane_colors(&self) -> PlaneColor {
+    let unique = self.cfa.unique_colors();
+    if self.colors.plane_count() == unique {
+      return self.colors.clone();
+    }
+    let colors: Vec<CFAColor> = (0..self.cfa.height)
+      .flat_map(|row| (0..self.cfa.width).map(move |col| (row, col)))
+      .map(|(row, col)| self.cfa.cfa_color_at(row, col))
+      .sorted()
+      .dedup()
+      .collect();
+    debug!("Plane colors {:?} do not match CFA {}, using {:?}", self.colors, self.cfa, colors);
+    PlaneColor::from(colors)
+  }
 }
 
 /// All the data needed to process this raw image, including the image data itself as well
@@ -408,6 +426,17 @@ impl RawImage {
     }
   }
 
+  /// Replace the black and white level read from the metadata, for files where
+  /// the camera records them wrong. Every channel gets the same level.
+  pub fn override_levels(&mut self, black: Option<u32>, white: Option<u32>) {
+    if let Some(black) = black {
+      self.blacklevel.levels.iter_mut().for_each(|x| *x = Rational::new(black, 1));
+    }
+    if let Some(white) = white {
+      self.whitelevel.0.iter_mut().for_each(|x| *x = white);
+    }
+  }
+
   /// Apply blacklevel and whitelevel scaling, replacing raw image data
   /// with floating point values in range 0.0 .. 1.0.
   /// Internal blacklevel and whitelevel is reset to 0.0 and 1.0 to match image data.
@@ -633,4 +662,31 @@ mod tests {
     );
     Ok(())
   }
+
+  #[test]
+  fn level_overrides_keep_the_layout() {
+    let mut raw = RawImage::new(
+      Camera::default(),
+      PixU16::new_with(vec![0; 16], 4, 4),
+      1,
+      [1.0; 4],
+      RawPhotometricInterpretation::LinearRaw,
+      Some(BlackLevel::new(&[10_u32, 11, 12, 13], 2, 2, 1)),
+      Some(WhiteLevel::new(vec![1000, 1000, 1000, 1000])),
+      false,
+    );
+    raw.override_levels(Some(64), None);
+    assert_eq!(raw.blacklevel.as_bayer_array(), [64.0; 4]);
+    assert_eq!(raw.whitelevel.as_bayer_array(), [1000.0; 4]);
+    raw.override_levels(None, Some(4095));
+    assert_eq!(raw.whitelevel.0, vec![4095; 4]);
+  }
+
+  #[test]
+  fn missing_plane_colors_come_from_the_pattern() {
+    let rgbe = CFAConfig::new(&CFA::new("RGEB"), &PlaneColor::default());
+    assert_eq!(rgbe.plane_colors().colors, vec![CFAColor::RED, CFAColor::GREEN, CFAColor::BLUE, CFAColor::CYAN]);
+    let cygm = CFAConfig::new(&CFA::new("GMYC"), &PlaneColor::new("GMCY"));
+    assert_eq!(cygm.plane_colors(), PlaneColor::new("GMCY"));
+  }
 }
@@ -40,7 +40,7 @@ pub struct AiState {
     pub embeddings: Option<ImageEmbeddings>,
 }
 
-fn get_models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
+pub fn get_models_dir(app_handle: &tauri::AppHandle) -> Result<PathBuf> {
     let models_dir = app_handle
         .path()
         .app_data_dir()?
@@ -60,6 +60,9 @@ async fn download_model(url: &str, dest: &Path) -> Result<()> {
 }
 
 pub async fn get_or_init_ai_models(app_handle: &tauri::AppHandle) -> Result<Arc<AiModels>> {
+    if crate::safe_mode::is_active() {
+        anyhow::bail!("AI features are disabled in safe mode.");
+    }
     let models_dir = get_models_dir(app_handle)?;
     let encoder_path = models_dir.join(ENCODER_FILENAME);
     let decoder_path = models_dir.join(DECODER_FILENAME);
@@ -0,0 +1,205 @@
+use std::fs::{self, OpenOptions};
+use std::io::{BufRead, BufReader, Write};
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+// One JSON object per line, so recording an operation is a single append and a damaged
+// line only loses that entry.
+const AUDIT_LOG_FILENAME: &str = "audit_log.jsonl";
+
+static LOG_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditOperation {
+    DeleteFiles,
+    DeleteFolder,
+    MoveFiles,
+    RenameFolder,
+    ClearSidecars,
+    ResetAdjustments,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditEntry {
+    pub id: String,
+    pub timestamp: String,
+    pub user: String,
+    pub operation: AuditOperation,
+    pub paths: Vec<String>,
+    pub destination: Option<String>,
+    pub details: Option<String>,
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct AuditLogQuery {
+    pub operation: Option<AuditOperation>,
+    pub path_contains: Option<String>,
+    pub since: Option<String>,
+    pub until: Option<String>,
+    pub limit: Option<usize>,
+}
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum AuditExportFormat {
+    #[default]
+    Csv,
+    Json,
+}
+
+fn get_log_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join(AUDIT_LOG_FILENAME))
+}
+
+fn current_user() -> String {
+    std::env::var("USER")
+        .or_else(|_| std::env::var("USERNAME"))
+        .unwrap_or_else(|_| "unknown".to_string())
+}
+
+// Called after an operation succeeded. Failing to log never fails the operation itself.
+pub fn record(
+    app_handle: &AppHandle,
+    operation: AuditOperation,
+    paths: &[String],
+    destination: Option<&str>,
+    details: Option<String>,
+) {
+    let entry = AuditEntry {
+        id: Uuid::new_v4().to_string(),
+        timestamp: Local::now().to_rfc3339(),
+        user: current_user(),
+        operation,
+        paths: paths.to_vec(),
+        destination: destination.map(str::to_string),
+        details,
+    };
+
+    let result = (|| -> Result<(), String> {
+        let line = serde_json::to_string(&entry).map_err(|e| e.to_string())?;
+        let _guard = LOG_LOCK.lock().unwrap();
+        let mut file = OpenOptions::new()
+            .create(true)
+            .append(true)
+            .open(get_log_path(app_handle)?)
+            .map_err(|e| e.to_string())?;
+        writeln!(file, "{}", line).map_err(|e| e.to_string())
+    })();
+    if let Err(e) = result {
+        eprintln!("Failed to write audit log entry: {}", e);
+    }
+}
+
+fn read_entries(app_handle: &AppHandle) -> Result<Vec<AuditEntry>, String> {
+    let path = get_log_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let _guard = LOG_LOCK.lock().unwrap();
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    Ok(BufReader::new(file)
+        .lines()
+        .map_while(Result::ok)
+        .filter_map(|line| serde_json::from_str(&line).ok())
+        .collect())
+}
+
+fn parse_time(value: &Option<String>) -> Option<DateTime<chrono::FixedOffset>> {
+    value.as_deref().and_then(|v| DateTime::parse_from_rfc3339(v).ok())
+}
+
+fn matches_query(entry: &AuditEntry, query: &AuditLogQuery) -> bool {
+    if query.operation.is_some_and(|op| op != entry.operation) {
+        return false;
+    }
+    if let Some(needle) = query.path_contains.as_deref().filter(|n| !n.is_empty()) {
+        let needle = needle.to_lowercase();
+        let in_paths = entry.paths.iter().any(|p| p.to_lowercase().contains(&needle));
+        let in_destination = entry.destination.as_deref().is_some_and(|d| d.to_lowercase().contains(&needle));
+        if !in_paths && !in_destination {
+            return false;
+        }
+    }
+    let Ok(timestamp) = DateTime::parse_from_rfc3339(&entry.timestamp) else {
+        return false;
+    };
+    if parse_time(&query.since).is_some_and(|since| timestamp < since) {
+        return false;
+    }
+    if parse_time(&query.until).is_some_and(|until| timestamp > until) {
+        return false;
+    }
+    true
+}
+
+fn query_entries(app_handle: &AppHandle, query: &AuditLogQuery) -> Result<Vec<AuditEntry>, String> {
+    let mut entries: Vec<AuditEntry> = read_entries(app_handle)?
+        .into_iter()
+        .rev()
+        .filter(|e| matches_query(e, query))
+        .collect();
+    if let Some(limit) = query.limit {
+        entries.truncate(limit);
+    }
+    Ok(entries)
+}
+
+fn csv_field(value: &str) -> String {
+    if value.contains([',', '"', '\n', '\r']) {
+        format!("\"{}\"", value.replace('"', "\"\""))
+    } else {
+        value.to_string()
+    }
+}
+
+fn entries_to_csv(entries: &[AuditEntry]) -> Result<String, String> {
+    let mut csv = String::from("timestamp,user,operation,paths,destination,details\n");
+    for entry in entries {
+        let operation = serde_json::to_value(entry.operation).map_err(|e| e.to_string())?;
+        let fields = [
+            entry.timestamp.clone(),
+            entry.user.clone(),
+            operation.as_str().unwrap_or_default().to_string(),
+            entry.paths.join("; "),
+            entry.destination.clone().unwrap_or_default(),
+            entry.details.clone().unwrap_or_default(),
+        ];
+        csv.push_str(&fields.iter().map(|f| csv_field(f)).collect::<Vec<_>>().join(","));
+        csv.push('\n');
+    }
+    Ok(csv)
+}
+
+// Newest entries first.
+#[tauri::command]
+pub fn get_audit_log(query: Option<AuditLogQuery>, app_handle: AppHandle) -> Result<Vec<AuditEntry>, String> {
+    query_entries(&app_handle, &query.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn export_audit_log(
+    output_path: String,
+    format: Option<AuditExportFormat>,
+    query: Option<AuditLogQuery>,
+    app_handle: AppHandle,
+) -> Result<usize, String> {
+    let entries = query_entries(&app_handle, &query.unwrap_or_default())?;
+    let content = match format.unwrap_or_default() {
+        AuditExportFormat::Csv => entries_to_csv(&entries)?,
+        AuditExportFormat::Json => serde_json::to_string_pretty(&entries).map_err(|e| e.to_string())?,
+    };
+    fs::write(&output_path, content).map_err(|e| e.to_string())?;
+    Ok(entries.len())
+}
@@ -0,0 +1,249 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::Duration;
+
+use chrono::{DateTime, Local};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Emitter, Manager};
+use walkdir::WalkDir;
+
+use crate::file_management::{load_settings, AppSettings};
+
+const BACKUP_FOLDER_NAME: &str = "RapidRAW Backups";
+const MANIFEST_FILENAME: &str = "manifest.json";
+const LIBRARY_DIR: &str = "library";
+const APP_DIR: &str = "app";
+const SCHEDULER_INTERVAL: Duration = Duration::from_secs(10 * 60);
+const BACKUP_EXISTS_ERROR: &str = "A backup was already created this second.";
+
+// Per-folder library files that hold edits or organisation, next to the images themselves.
+const LIBRARY_FILENAMES: [&str; 2] = [".rapidraw_stacks.json", ".rapidraw_notes.json"];
+// App-level files, relative to the app data directory.
+const APP_FILES: [&str; 3] = ["settings.json", "presets/presets.json", "camera_profiles.json"];
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupSettings {
+    pub enabled: bool,
+    pub location: String,
+    pub interval_hours: Option<u32>,
+    pub keep_count: Option<usize>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BackupInfo {
+    pub id: String,
+    pub created_at: String,
+    pub root_path: Option<String>,
+    pub file_count: usize,
+}
+
+fn backups_dir(location: &str) -> PathBuf {
+    Path::new(location).join(BACKUP_FOLDER_NAME)
+}
+
+fn backup_settings(settings: &AppSettings) -> Result<&BackupSettings, String> {
+    settings
+        .backup
+        .as_ref()
+        .filter(|b| !b.location.trim().is_empty())
+        .ok_or_else(|| "No backup location is configured.".to_string())
+}
+
+fn is_library_file(path: &Path) -> bool {
+    let Some(name) = path.file_name().and_then(|n| n.to_str()) else {
+        return false;
+    };
+    name.ends_with(".rrdata") || LIBRARY_FILENAMES.contains(&name)
+}
+
+fn copy_file(source: &Path, target: &Path) -> Result<(), String> {
+    if let Some(parent) = target.parent() {
+        fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+    }
+    fs::copy(source, target)
+        .map(|_| ())
+        .map_err(|e| format!("Failed to copy {}: {}", source.display(), e))
+}
+
+fn read_manifest(backup_path: &Path) -> Option<BackupInfo> {
+    let content = fs::read_to_string(backup_path.join(MANIFEST_FILENAME)).ok()?;
+    serde_json::from_str(&content).ok()
+}
+
+fn list_backups_in(location: &str) -> Vec<BackupInfo> {
+    let Ok(entries) = fs::read_dir(backups_dir(location)) else {
+        return Vec::new();
+    };
+    let mut backups: Vec<BackupInfo> = entries
+        .filter_map(|e| e.ok())
+        .filter(|e| e.path().is_dir())
+        .filter_map(|e| read_manifest(&e.path()))
+        .collect();
+    backups.sort_by(|a, b| b.created_at.cmp(&a.created_at));
+    backups
+}
+
+fn apply_retention(location: &str, keep_count: usize) {
+    for expired in list_backups_in(location).into_iter().skip(keep_count.max(1)) {
+        if let Err(e) = fs::remove_dir_all(backups_dir(location).join(&expired.id)) {
+            eprintln!("Failed to remove expired backup {}: {}", expired.id, e);
+        }
+    }
+}
+
+fn run_backup(app_handle: &AppHandle, prune: bool) -> Result<BackupInfo, String> {
+    let settings = load_settings(app_handle.clone())?;
+    let backup = backup_settings(&settings)?;
+    let root_path = settings.last_root_path.clone();
+
+    let now = Local::now();
+    let id = now.format("%Y%m%d-%H%M%S").to_string();
+    let target_dir = backups_dir(&backup.location).join(&id);
+    if target_dir.exists() {
+        return Err(BACKUP_EXISTS_ERROR.to_string());
+    }
+
+    let mut file_count = 0;
+    if let Some(root) = root_path.as_deref().and_then(|r| fs::canonicalize(r).ok()) {
+        // The backup location may live inside the library; earlier backups must not be copied
+        // into every new one.
+        let excluded = backups_dir(&backup.location);
+        let excluded = fs::canonicalize(&excluded).unwrap_or(excluded);
+        let entries = WalkDir::new(&root)
+            .into_iter()
+            .filter_entry(|e| !e.path().starts_with(&excluded))
+            .filter_map(|e| e.ok());
+        for entry in entries {
+            let path = entry.path();
+            if !path.is_file() || !is_library_file(path) {
+                continue;
+            }
+            let Ok(relative) = path.strip_prefix(&root) else { continue };
+            copy_file(path, &target_dir.join(LIBRARY_DIR).join(relative))?;
+            file_count += 1;
+        }
+    }
+
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    for relative in APP_FILES {
+        let source = data_dir.join(relative);
+        if source.exists() {
+            copy_file(&source, &target_dir.join(APP_DIR).join(relative))?;
+            file_count += 1;
+        }
+    }
+
+    let info = BackupInfo {
+        id,
+        created_at: now.to_rfc3339(),
+        root_path,
+        file_count,
+    };
+    fs::create_dir_all(&target_dir).map_err(|e| e.to_string())?;
+    let manifest = serde_json::to_string_pretty(&info).map_err(|e| e.to_string())?;
+    fs::write(target_dir.join(MANIFEST_FILENAME), manifest).map_err(|e| e.to_string())?;
+
+    if prune {
+        apply_retention(&backup.location, backup.keep_count.unwrap_or(10));
+    }
+    Ok(info)
+}
+
+// Checks periodically instead of sleeping for the whole interval, so changes to the backup
+// settings and the time spent asleep (laptops) are picked up.
+pub fn start_backup_scheduler(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(SCHEDULER_INTERVAL);
+
+        let Ok(settings) = load_settings(app_handle.clone()) else { continue };
+        let Some(backup) = settings.backup.as_ref().filter(|b| b.enabled && !b.location.trim().is_empty()) else {
+            continue;
+        };
+
+        let interval = chrono::Duration::hours(backup.interval_hours.unwrap_or(24).max(1) as i64);
+        let last_backup = list_backups_in(&backup.location)
+            .first()
+            .and_then(|b| DateTime::parse_from_rfc3339(&b.created_at).ok());
+        if last_backup.is_some_and(|last| Local::now().signed_duration_since(last) < interval) {
+            continue;
+        }
+
+        match run_backup(&app_handle, true) {
+            Ok(info) => {
+                let _ = app_handle.emit("backup-complete", info);
+            }
+            Err(e) => {
+                eprintln!("Scheduled backup failed: {}", e);
+                let _ = app_handle.emit("backup-error", e);
+            }
+        }
+    });
+}
+
+#[tauri::command]
+pub async fn create_backup(app_handle: AppHandle) -> Result<BackupInfo, String> {
+    tauri::async_runtime::spawn_blocking(move || run_backup(&app_handle, true))
+        .await
+        .map_err(|e| e.to_string())?
+}
+
+#[tauri::command]
+pub fn list_backups(app_handle: AppHandle) -> Result<Vec<BackupInfo>, String> {
+    let settings = load_settings(app_handle)?;
+    let backup = backup_settings(&settings)?;
+    Ok(list_backups_in(&backup.location))
+}
+
+// Library files are restored to the folder they were backed up from. A fresh backup is taken
+// first, so a restore can itself be undone; it skips retention so the backup being restored
+// cannot be pruned by it. If a backup was already taken this second, that one serves instead.
+#[tauri::command]
+pub async fn restore_backup(
+    backup_id: String,
+    restore_app_data: bool,
+    app_handle: AppHandle,
+) -> Result<usize, String> {
+    tauri::async_runtime::spawn_blocking(move || {
+        let settings = load_settings(app_handle.clone())?;
+        let location = backup_settings(&settings)?.location.clone();
+        let backup_path = backups_dir(&location).join(&backup_id);
+        let info = read_manifest(&backup_path).ok_or_else(|| format!("Backup {} not found.", backup_id))?;
+
+        match run_backup(&app_handle, false) {
+            Err(e) if e != BACKUP_EXISTS_ERROR => return Err(e),
+            _ => {}
+        }
+
+        let mut restored = 0;
+        let library_dir = backup_path.join(LIBRARY_DIR);
+        if let (Some(root), true) = (info.root_path.as_deref(), library_dir.exists()) {
+            for entry in WalkDir::new(&library_dir).into_iter().filter_map(|e| e.ok()) {
+                let path = entry.path();
+                if !path.is_file() {
+                    continue;
+                }
+                let Ok(relative) = path.strip_prefix(&library_dir) else { continue };
+                copy_file(path, &Path::new(root).join(relative))?;
+                restored += 1;
+            }
+        }
+
+        if restore_app_data {
+            let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+            for relative in APP_FILES {
+                let source = backup_path.join(APP_DIR).join(relative);
+                if source.exists() {
+                    copy_file(&source, &data_dir.join(relative))?;
+                    restored += 1;
+                }
+            }
+        }
+
+        Ok(restored)
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
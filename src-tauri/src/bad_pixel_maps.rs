@@ -0,0 +1,106 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use rawler::{decoders::RawMetadata, imgop::Point};
+use tauri::{AppHandle, Manager};
+
+use crate::raw_processing::read_raw_metadata;
+
+// Bad pixel maps are plain text files with one "x y" (or "x,y") position per line, in raw
+// sensor coordinates. Lines starting with # are comments. Each map is stored as
+// `<serial number>.txt` and applies to every RAW file from that camera body.
+static MAPS_DIR: Mutex<Option<PathBuf>> = Mutex::new(None);
+
+fn get_maps_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let config_dir = app_handle.path().app_config_dir().map_err(|e| e.to_string())?;
+    let dir = config_dir.join("bad_pixel_maps");
+    if !dir.exists() {
+        fs::create_dir_all(&dir).map_err(|e| e.to_string())?;
+    }
+    Ok(dir)
+}
+
+pub fn init(app_handle: &AppHandle) {
+    match get_maps_dir(app_handle) {
+        Ok(dir) => *MAPS_DIR.lock().unwrap() = Some(dir),
+        Err(e) => eprintln!("Failed to prepare the bad pixel map folder: {}", e),
+    }
+}
+
+fn file_name_for(serial: &str) -> String {
+    let name: String = serial
+        .chars()
+        .map(|c| if c.is_ascii_alphanumeric() || c == '-' { c } else { '_' })
+        .collect();
+    format!("{}.txt", name)
+}
+
+fn parse_map(content: &str) -> Result<Vec<Point>, String> {
+    content
+        .lines()
+        .enumerate()
+        .map(|(number, line)| (number, line.trim()))
+        .filter(|(_, line)| !line.is_empty() && !line.starts_with('#'))
+        .map(|(number, line)| {
+            let mut values = line
+                .split(|c: char| c == ',' || c.is_whitespace())
+                .filter(|v| !v.is_empty())
+                .map(str::parse::<usize>);
+            match (values.next(), values.next(), values.next()) {
+                (Some(Ok(x)), Some(Ok(y)), None) => Ok(Point { x, y }),
+                _ => Err(format!("Line {} is not an \"x y\" position: {}", number + 1, line)),
+            }
+        })
+        .collect()
+}
+
+fn serial_of(metadata: &RawMetadata) -> Option<&str> {
+    metadata.exif.serial_number.as_deref().filter(|s| !s.is_empty())
+}
+
+// Called while developing. A broken map is logged and ignored rather than failing to open.
+pub fn bad_pixels_for(metadata: &RawMetadata) -> Vec<Point> {
+    let Some(dir) = MAPS_DIR.lock().unwrap().clone() else {
+        return Vec::new();
+    };
+    let Some(serial) = serial_of(metadata) else {
+        return Vec::new();
+    };
+    let path = dir.join(file_name_for(serial));
+    let Ok(content) = fs::read_to_string(&path) else {
+        return Vec::new();
+    };
+    parse_map(&content).unwrap_or_else(|e| {
+        eprintln!("Ignoring bad pixel map {}: {}", path.display(), e);
+        Vec::new()
+    })
+}
+
+// Stores the map for the camera that took `image_path`. Returns the number of bad pixels.
+#[tauri::command]
+pub fn import_bad_pixel_map(image_path: String, map_path: String, app_handle: AppHandle) -> Result<usize, String> {
+    let file_bytes = fs::read(&image_path).map_err(|e| e.to_string())?;
+    let metadata = read_raw_metadata(&file_bytes).map_err(|e| format!("Not a RAW file: {}", e))?;
+    let serial = serial_of(&metadata).ok_or("The camera does not record a serial number.")?;
+
+    let content = fs::read_to_string(&map_path).map_err(|e| e.to_string())?;
+    let pixels = parse_map(&content)?;
+
+    let dir = get_maps_dir(&app_handle)?;
+    fs::write(dir.join(file_name_for(serial)), content).map_err(|e| e.to_string())?;
+    Ok(pixels.len())
+}
+
+#[tauri::command]
+pub fn remove_bad_pixel_map(image_path: String, app_handle: AppHandle) -> Result<(), String> {
+    let file_bytes = fs::read(&image_path).map_err(|e| e.to_string())?;
+    let metadata = read_raw_metadata(&file_bytes).map_err(|e| format!("Not a RAW file: {}", e))?;
+    let serial = serial_of(&metadata).ok_or("The camera does not record a serial number.")?;
+
+    let path = get_maps_dir(&app_handle)?.join(file_name_for(serial));
+    if path.exists() {
+        fs::remove_file(path).map_err(|e| e.to_string())?;
+    }
+    Ok(())
+}
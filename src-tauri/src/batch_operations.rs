@@ -0,0 +1,248 @@
+use std::fs;
+use std::thread;
+
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+use serde_json::{json, Value};
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::file_management::{generate_thumbnails_progressive, get_sidecar_path};
+use crate::image_processing::{ImageFlag, ImageMetadata};
+use crate::audit_log::{self, AuditOperation};
+use crate::metadata_writeback;
+use crate::AppState;
+
+const MAX_BATCH_HISTORY: usize = 20;
+
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum BatchOperation {
+    Rate { rating: u8 },
+    Label { label: Option<String> },
+    Keywords { keywords: Vec<String> },
+    Flag { flag: Option<ImageFlag> },
+    Preset { adjustments: Value },
+    Rotate { degrees: f64 },
+    ResetGroup { group: String },
+}
+
+pub struct BatchHistoryEntry {
+    id: String,
+    // `None` means the image had no sidecar before the operation.
+    previous: Vec<(String, Option<String>)>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BatchOperationResult {
+    pub history_id: String,
+    pub paths: Vec<String>,
+}
+
+// Keys removed for each reset group; the frontend fills missing keys with its defaults.
+fn keys_for_group(group: &str) -> Result<&'static [&'static str], String> {
+    match group {
+        "basic" => Ok(&["exposure", "contrast", "highlights", "shadows", "whites", "blacks"]),
+        "color" => Ok(&["saturation", "temperature", "tint", "rawWhiteBalance", "vibrance", "colorGrading", "splitToning", "hsl", "hueCurves", "calibration"]),
+        "details" => Ok(&[
+            "sharpness", "sharpenRadius", "sharpenDetail", "sharpenMasking", "lumaNoiseReduction", "lumaNoiseReductionDetail", "colorNoiseReduction", "colorNoiseReductionSmoothness", "defringe", "texture", "clarity", "dehaze", "structure",
+        ]),
+        "effects" => Ok(&[
+            "vignetteAmount", "vignetteMidpoint", "vignetteRoundness", "vignetteFeather",
+            "grainAmount", "grainSize", "grainRoughness", "lut",
+        ]),
+        "curves" => Ok(&["curves", "lumaCurveMode", "levels"]),
+        "transform" => Ok(&["crop", "aspectRatio", "rotation", "flipHorizontal", "flipVertical", "lensDistortion"]),
+        "masks" => Ok(&["masks"]),
+        "ai" => Ok(&["aiPatches"]),
+        _ => Err(format!("Unknown adjustment group: {}", group)),
+    }
+}
+
+fn apply_operation(metadata: &mut ImageMetadata, operation: &BatchOperation) -> Result<(), String> {
+    if !metadata.adjustments.is_object() {
+        metadata.adjustments = json!({});
+    }
+    let adjustments = metadata.adjustments.as_object_mut().unwrap();
+
+    match operation {
+        BatchOperation::Rate { rating } => {
+            let rating = (*rating).min(5);
+            metadata.rating = rating;
+            adjustments.insert("rating".into(), json!(rating));
+        }
+        BatchOperation::Label { label } => {
+            metadata.label = label.clone().filter(|l| !l.is_empty());
+        }
+        BatchOperation::Keywords { keywords } => {
+            let mut cleaned: Vec<String> = Vec::with_capacity(keywords.len());
+            for keyword in keywords.iter().map(|k| k.trim()).filter(|k| !k.is_empty()) {
+                if !cleaned.iter().any(|k| k == keyword) {
+                    cleaned.push(keyword.to_string());
+                }
+            }
+            metadata.keywords = cleaned;
+        }
+        BatchOperation::Flag { flag } => {
+            metadata.flag = *flag;
+        }
+        BatchOperation::Preset { adjustments: preset } => {
+            if let Some(preset_map) = preset.as_object() {
+                for (k, v) in preset_map {
+                    // A preset never overrides the rating of the images it is applied to.
+                    if k != "rating" {
+                        adjustments.insert(k.clone(), v.clone());
+                    }
+                }
+            }
+        }
+        BatchOperation::Rotate { degrees } => {
+            let current = adjustments.get("rotation").and_then(|v| v.as_f64()).unwrap_or(0.0);
+            let rotated = (current + degrees + 180.0).rem_euclid(360.0) - 180.0;
+            adjustments.insert("rotation".into(), json!(rotated));
+            // A crop rectangle is only valid for the orientation it was drawn in.
+            adjustments.insert("crop".into(), Value::Null);
+        }
+        BatchOperation::ResetGroup { group } => {
+            for key in keys_for_group(group)? {
+                adjustments.remove(*key);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+fn restore_sidecars(previous: &[(String, Option<String>)]) {
+    for (path, content) in previous {
+        let sidecar_path = get_sidecar_path(path);
+        let result = match content {
+            Some(content) => fs::write(&sidecar_path, content),
+            None if sidecar_path.exists() => fs::remove_file(&sidecar_path),
+            None => Ok(()),
+        };
+        if let Err(e) = result {
+            eprintln!("Failed to restore sidecar for {}: {}", path, e);
+        }
+    }
+}
+
+// All sidecars are computed before anything is written, and a failed write rolls back the
+// ones already written, so a batch either applies to every image or to none.
+#[tauri::command]
+pub fn batch_apply_operation(
+    paths: Vec<String>,
+    operation: BatchOperation,
+    app_handle: AppHandle,
+) -> Result<BatchOperationResult, String> {
+    let total = paths.len();
+
+    let prepared: Vec<(String, Option<String>, String, bool)> = paths
+        .par_iter()
+        .map(|path| {
+            let previous = fs::read_to_string(get_sidecar_path(path)).ok();
+            let mut metadata: ImageMetadata = previous
+                .as_deref()
+                .and_then(|content| serde_json::from_str(content).ok())
+                .unwrap_or_default();
+            let previous_metadata = metadata.clone();
+            apply_operation(&mut metadata, &operation)?;
+            let write_back = metadata_writeback::write_back_fields_changed(&previous_metadata, &metadata);
+            let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+            Ok((path.clone(), previous, json_string, write_back))
+        })
+        .collect::<Result<_, String>>()?;
+
+    let mut written: Vec<(String, Option<String>)> = Vec::with_capacity(total);
+    let mut write_back_paths = Vec::new();
+    for (i, (path, previous, json_string, write_back)) in prepared.into_iter().enumerate() {
+        let _ = app_handle.emit(
+            "batch-operation-progress",
+            json!({ "current": i, "total": total, "path": path }),
+        );
+        if let Err(e) = fs::write(get_sidecar_path(&path), json_string) {
+            restore_sidecars(&written);
+            return Err(format!("Failed to update {}: {}", path, e));
+        }
+        if write_back {
+            write_back_paths.push(path.clone());
+        }
+        written.push((path, previous));
+    }
+    let _ = app_handle.emit(
+        "batch-operation-progress",
+        json!({ "current": total, "total": total, "path": "" }),
+    );
+
+    let history_id = Uuid::new_v4().to_string();
+    {
+        let state = app_handle.state::<AppState>();
+        let mut history = state.batch_history.lock().unwrap();
+        history.push(BatchHistoryEntry {
+            id: history_id.clone(),
+            previous: written,
+        });
+        if history.len() > MAX_BATCH_HISTORY {
+            history.remove(0);
+        }
+    }
+
+    if let BatchOperation::ResetGroup { group } = &operation {
+        audit_log::record(
+            &app_handle,
+            AuditOperation::ResetAdjustments,
+            &paths,
+            None,
+            Some(format!("reset group: {}", group)),
+        );
+    }
+
+    let thumbnail_paths = paths.clone();
+    thread::spawn(move || {
+        metadata_writeback::write_back_to_originals(&app_handle, &write_back_paths);
+        let _ = generate_thumbnails_progressive(thumbnail_paths, app_handle);
+    });
+
+    Ok(BatchOperationResult { history_id, paths })
+}
+
+#[tauri::command]
+pub fn undo_batch_operation(history_id: String, app_handle: AppHandle) -> Result<Vec<String>, String> {
+    let entry = {
+        let state = app_handle.state::<AppState>();
+        let mut history = state.batch_history.lock().unwrap();
+        let index = history
+            .iter()
+            .position(|e| e.id == history_id)
+            .ok_or_else(|| "This batch operation can no longer be undone.".to_string())?;
+        history.remove(index)
+    };
+
+    let read_metadata = |content: Option<&str>| -> ImageMetadata {
+        content.and_then(|c| serde_json::from_str(c).ok()).unwrap_or_default()
+    };
+    let write_back_paths: Vec<String> = entry
+        .previous
+        .iter()
+        .filter(|(path, previous)| {
+            let current = fs::read_to_string(get_sidecar_path(path)).ok();
+            metadata_writeback::write_back_fields_changed(
+                &read_metadata(current.as_deref()),
+                &read_metadata(previous.as_deref()),
+            )
+        })
+        .map(|(path, _)| path.clone())
+        .collect();
+
+    restore_sidecars(&entry.previous);
+
+    let paths: Vec<String> = entry.previous.into_iter().map(|(path, _)| path).collect();
+    let thumbnail_paths = paths.clone();
+    thread::spawn(move || {
+        metadata_writeback::write_back_to_originals(&app_handle, &write_back_paths);
+        let _ = generate_thumbnails_progressive(thumbnail_paths, app_handle);
+    });
+
+    Ok(paths)
+}
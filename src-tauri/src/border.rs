@@ -0,0 +1,58 @@
+use image::{imageops, DynamicImage, GenericImageView, ImageBuffer, Rgba};
+use serde::{Deserialize, Serialize};
+
+use crate::watermark::parse_color;
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct BorderSettings {
+    // Percent of the image's shorter edge, added on every side.
+    pub width: f32,
+    pub color: Option<String>,
+    // Width divided by height of the final canvas, e.g. 1.0 for square or 0.8 for 4:5.
+    pub aspect_ratio: Option<f32>,
+}
+
+fn canvas_size(image_size: (u32, u32), settings: &BorderSettings) -> (u32, u32) {
+    let (width, height) = image_size;
+    let border = (width.min(height) as f32 * settings.width.max(0.0) / 100.0).round() as u32;
+    let (mut canvas_w, mut canvas_h) = (width + border * 2, height + border * 2);
+
+    if let Some(ratio) = settings.aspect_ratio.filter(|r| *r > 0.0) {
+        let current = canvas_w as f32 / canvas_h as f32;
+        if current < ratio {
+            canvas_w = (canvas_h as f32 * ratio).round() as u32;
+        } else {
+            canvas_h = (canvas_w as f32 / ratio).round() as u32;
+        }
+    }
+
+    (canvas_w, canvas_h)
+}
+
+pub fn apply_border(image: DynamicImage, settings: &BorderSettings) -> DynamicImage {
+    let (width, height) = image.dimensions();
+    let (canvas_w, canvas_h) = canvas_size((width, height), settings);
+    if canvas_w == width && canvas_h == height {
+        return image;
+    }
+
+    let x = ((canvas_w - width) / 2) as i64;
+    let y = ((canvas_h - height) / 2) as i64;
+    let color = parse_color(settings.color.as_deref());
+
+    match image {
+        DynamicImage::ImageRgba16(buffer) => {
+            let to_16 = |c: u8| c as u16 * 257;
+            let fill = Rgba([to_16(color[0]), to_16(color[1]), to_16(color[2]), u16::MAX]);
+            let mut canvas = ImageBuffer::from_pixel(canvas_w, canvas_h, fill);
+            imageops::replace(&mut canvas, &buffer, x, y);
+            DynamicImage::ImageRgba16(canvas)
+        }
+        other => {
+            let mut canvas = ImageBuffer::from_pixel(canvas_w, canvas_h, color);
+            imageops::replace(&mut canvas, &other.to_rgba8(), x, y);
+            DynamicImage::ImageRgba8(canvas)
+        }
+    }
+}
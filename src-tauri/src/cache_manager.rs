@@ -0,0 +1,247 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::thread;
+use std::time::{Duration, SystemTime, UNIX_EPOCH};
+
+use image::GenericImageView;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use walkdir::WalkDir;
+
+use crate::ai_processing::get_models_dir;
+use crate::file_management::{get_sidecar_path, get_thumbnail_cache_dir, load_settings, thumbnail_cache_filename};
+use crate::formats::is_supported_image_file;
+use crate::AppState;
+
+const SIZE_CAP_INTERVAL: Duration = Duration::from_secs(10 * 60);
+
+// Thumbnails and AI models live on disk. The preview pyramid and the AI embeddings are held in
+// memory for the open image, so their size is an estimate and clearing them only costs a
+// re-render of the current image.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum CacheKind {
+    Thumbnails,
+    Previews,
+    AiEmbeddings,
+    AiModels,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheUsage {
+    pub kind: CacheKind,
+    pub size_bytes: u64,
+    pub entry_count: usize,
+    pub location: Option<String>,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CachePurgeResult {
+    pub removed_count: usize,
+    pub freed_bytes: u64,
+}
+
+impl CachePurgeResult {
+    fn remove_file(&mut self, path: &Path, size: u64) {
+        if fs::remove_file(path).is_ok() {
+            self.removed_count += 1;
+            self.freed_bytes += size;
+        }
+    }
+}
+
+struct CacheEntry {
+    path: PathBuf,
+    size: u64,
+    modified: SystemTime,
+}
+
+fn list_entries(dir: &Path) -> Vec<CacheEntry> {
+    WalkDir::new(dir)
+        .into_iter()
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .filter_map(|e| {
+            let metadata = e.metadata().ok()?;
+            Some(CacheEntry {
+                path: e.into_path(),
+                size: metadata.len(),
+                modified: metadata.modified().unwrap_or(UNIX_EPOCH),
+            })
+        })
+        .collect()
+}
+
+fn disk_usage(kind: CacheKind, dir: &Path) -> CacheUsage {
+    let entries = list_entries(dir);
+    CacheUsage {
+        kind,
+        size_bytes: entries.iter().map(|e| e.size).sum(),
+        entry_count: entries.len(),
+        location: Some(dir.to_string_lossy().into_owned()),
+    }
+}
+
+fn memory_usage(kind: CacheKind, size_bytes: u64, present: bool) -> CacheUsage {
+    CacheUsage { kind, size_bytes, entry_count: present as usize, location: None }
+}
+
+fn clear_dir(dir: &Path) -> CachePurgeResult {
+    let mut result = CachePurgeResult::default();
+    for entry in list_entries(dir) {
+        result.remove_file(&entry.path, entry.size);
+    }
+    result
+}
+
+fn mod_time_secs(path: &Path) -> Option<u64> {
+    fs::metadata(path)
+        .ok()?
+        .modified()
+        .ok()?
+        .duration_since(UNIX_EPOCH)
+        .ok()
+        .map(|d| d.as_secs())
+}
+
+// Removes the oldest thumbnails until the cache fits in `limit_bytes`. Models are left alone,
+// they are not regenerated locally and would have to be downloaded again.
+fn enforce_size_limit(thumb_cache_dir: &Path, limit_bytes: u64) -> CachePurgeResult {
+    let mut entries = list_entries(thumb_cache_dir);
+    let mut total: u64 = entries.iter().map(|e| e.size).sum();
+    let mut result = CachePurgeResult::default();
+    if total <= limit_bytes {
+        return result;
+    }
+    entries.sort_by_key(|e| e.modified);
+    for entry in entries {
+        if total <= limit_bytes {
+            break;
+        }
+        result.remove_file(&entry.path, entry.size);
+        total = total.saturating_sub(entry.size);
+    }
+    result
+}
+
+pub fn start_cache_size_enforcer(app_handle: AppHandle) {
+    thread::spawn(move || loop {
+        thread::sleep(SIZE_CAP_INTERVAL);
+
+        let Ok(settings) = load_settings(app_handle.clone()) else { continue };
+        let Some(limit_mb) = settings.cache_size_limit_mb.filter(|l| *l > 0) else { continue };
+        let Ok(thumb_cache_dir) = get_thumbnail_cache_dir(&app_handle) else { continue };
+
+        let result = enforce_size_limit(&thumb_cache_dir, limit_mb * 1024 * 1024);
+        if result.removed_count > 0 {
+            println!(
+                "Cache size limit: removed {} thumbnails ({} bytes).",
+                result.removed_count, result.freed_bytes
+            );
+        }
+    });
+}
+
+#[tauri::command]
+pub fn get_cache_usage(state: tauri::State<AppState>, app_handle: AppHandle) -> Result<Vec<CacheUsage>, String> {
+    let thumb_cache_dir = get_thumbnail_cache_dir(&app_handle)?;
+    let models_dir = get_models_dir(&app_handle).map_err(|e| e.to_string())?;
+
+    let preview_bytes = state.cached_preview.lock().unwrap().as_ref().map(|p| {
+        let (width, height) = p.image.dimensions();
+        width as u64 * height as u64 * p.image.color().bytes_per_pixel() as u64
+    });
+    let embedding_bytes = state
+        .ai_state
+        .lock()
+        .unwrap()
+        .as_ref()
+        .and_then(|s| s.embeddings.as_ref())
+        .map(|e| (e.embeddings.len() * std::mem::size_of::<f32>()) as u64);
+
+    Ok(vec![
+        disk_usage(CacheKind::Thumbnails, &thumb_cache_dir),
+        memory_usage(CacheKind::Previews, preview_bytes.unwrap_or(0), preview_bytes.is_some()),
+        memory_usage(CacheKind::AiEmbeddings, embedding_bytes.unwrap_or(0), embedding_bytes.is_some()),
+        disk_usage(CacheKind::AiModels, &models_dir),
+    ])
+}
+
+#[tauri::command]
+pub fn clear_cache(
+    kind: CacheKind,
+    state: tauri::State<AppState>,
+    app_handle: AppHandle,
+) -> Result<CachePurgeResult, String> {
+    match kind {
+        CacheKind::Thumbnails => Ok(clear_dir(&get_thumbnail_cache_dir(&app_handle)?)),
+        CacheKind::Previews => {
+            *state.cached_preview.lock().unwrap() = None;
+            Ok(CachePurgeResult::default())
+        }
+        CacheKind::AiEmbeddings => {
+            if let Some(ai_state) = state.ai_state.lock().unwrap().as_mut() {
+                ai_state.embeddings = None;
+            }
+            Ok(CachePurgeResult::default())
+        }
+        CacheKind::AiModels => {
+            // Dropping the loaded sessions makes the next AI tool fetch the models again.
+            *state.ai_state.lock().unwrap() = None;
+            Ok(clear_dir(&get_models_dir(&app_handle).map_err(|e| e.to_string())?))
+        }
+    }
+}
+
+// Cache names are hashes, so a folder's thumbnails are found by recomputing the names for its
+// current images. Thumbnails of earlier edits are not reachable this way and age out instead.
+#[tauri::command]
+pub fn purge_folder_thumbnails(folder_path: String, app_handle: AppHandle) -> Result<CachePurgeResult, String> {
+    let thumb_cache_dir = get_thumbnail_cache_dir(&app_handle)?;
+    let mut result = CachePurgeResult::default();
+
+    let entries = fs::read_dir(&folder_path).map_err(|e| e.to_string())?;
+    for entry in entries.filter_map(|e| e.ok()) {
+        let path = entry.path();
+        let path_str = path.to_string_lossy();
+        if !path.is_file() || !is_supported_image_file(&path_str) {
+            continue;
+        }
+        let Some(img_mod_time) = mod_time_secs(&path) else { continue };
+        let sidecar_mod_time = mod_time_secs(&get_sidecar_path(&path_str)).unwrap_or(0);
+
+        for embedded in [false, true] {
+            let cache_path =
+                thumb_cache_dir.join(thumbnail_cache_filename(&path_str, img_mod_time, sidecar_mod_time, embedded));
+            if let Ok(metadata) = fs::metadata(&cache_path) {
+                result.remove_file(&cache_path, metadata.len());
+            }
+        }
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn prune_thumbnail_cache(max_age_days: u32, app_handle: AppHandle) -> Result<CachePurgeResult, String> {
+    let thumb_cache_dir = get_thumbnail_cache_dir(&app_handle)?;
+    let cutoff = SystemTime::now()
+        .checked_sub(Duration::from_secs(max_age_days as u64 * 24 * 60 * 60))
+        .unwrap_or(UNIX_EPOCH);
+
+    let mut result = CachePurgeResult::default();
+    for entry in list_entries(&thumb_cache_dir).into_iter().filter(|e| e.modified < cutoff) {
+        result.remove_file(&entry.path, entry.size);
+    }
+    Ok(result)
+}
+
+#[tauri::command]
+pub fn enforce_cache_size_limit(app_handle: AppHandle) -> Result<CachePurgeResult, String> {
+    let settings = load_settings(app_handle.clone())?;
+    let Some(limit_mb) = settings.cache_size_limit_mb.filter(|l| *l > 0) else {
+        return Ok(CachePurgeResult::default());
+    };
+    Ok(enforce_size_limit(&get_thumbnail_cache_dir(&app_handle)?, limit_mb * 1024 * 1024))
+}
@@ -0,0 +1,268 @@
+use std::fs;
+use std::path::PathBuf;
+
+use chrono::Local;
+use image::{DynamicImage, GenericImageView};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+
+use crate::image_loader::{load_base_image_from_bytes, read_capture_info};
+use crate::raw_processing::DevelopOverrides;
+use crate::reference_swatches::{srgb_to_lab, srgb_to_linear, COLOR_CHECKER};
+
+const CHART_COLUMNS: usize = 6;
+const CHART_ROWS: usize = 4;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct ChartPoint {
+    pub x: f32,
+    pub y: f32,
+}
+
+// `matrix` maps linear RGB as it comes out of the RAW development to corrected linear RGB,
+// row by row. It is applied before any other adjustment.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CameraProfile {
+    pub id: String,
+    pub name: String,
+    pub camera_make: String,
+    pub camera_model: String,
+    pub matrix: [[f32; 3]; 3],
+    pub mean_delta_e_before: f32,
+    pub mean_delta_e_after: f32,
+    pub created_at: String,
+}
+
+fn get_profiles_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join("camera_profiles.json"))
+}
+
+fn load_profiles(app_handle: &AppHandle) -> Result<Vec<CameraProfile>, String> {
+    let path = get_profiles_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save_profiles(app_handle: &AppHandle, profiles: &[CameraProfile]) -> Result<(), String> {
+    let path = get_profiles_path(app_handle)?;
+    let json_string = serde_json::to_string_pretty(profiles).map_err(|e| e.to_string())?;
+    fs::write(path, json_string).map_err(|e| e.to_string())
+}
+
+// Corners are the centres of the dark skin, bluish green, black and white patches (clockwise
+// from top left) in image pixels. Without them the chart is assumed to fill the frame, which
+// is what a tight crop of the chart looks like.
+fn patch_centers(corners: Option<[ChartPoint; 4]>, width: u32, height: u32) -> (Vec<(f32, f32)>, f32) {
+    let (w, h) = (width as f32, height as f32);
+    let corners = corners.unwrap_or([
+        ChartPoint { x: w * 0.5 / CHART_COLUMNS as f32, y: h * 0.5 / CHART_ROWS as f32 },
+        ChartPoint { x: w * (CHART_COLUMNS as f32 - 0.5) / CHART_COLUMNS as f32, y: h * 0.5 / CHART_ROWS as f32 },
+        ChartPoint { x: w * (CHART_COLUMNS as f32 - 0.5) / CHART_COLUMNS as f32, y: h * (CHART_ROWS as f32 - 0.5) / CHART_ROWS as f32 },
+        ChartPoint { x: w * 0.5 / CHART_COLUMNS as f32, y: h * (CHART_ROWS as f32 - 0.5) / CHART_ROWS as f32 },
+    ]);
+    let [tl, tr, br, bl] = corners;
+
+    let mut centers = Vec::with_capacity(CHART_COLUMNS * CHART_ROWS);
+    for row in 0..CHART_ROWS {
+        for col in 0..CHART_COLUMNS {
+            let u = col as f32 / (CHART_COLUMNS - 1) as f32;
+            let v = row as f32 / (CHART_ROWS - 1) as f32;
+            let x = (1.0 - u) * (1.0 - v) * tl.x + u * (1.0 - v) * tr.x + u * v * br.x + (1.0 - u) * v * bl.x;
+            let y = (1.0 - u) * (1.0 - v) * tl.y + u * (1.0 - v) * tr.y + u * v * br.y + (1.0 - u) * v * bl.y;
+            centers.push((x, y));
+        }
+    }
+
+    // Sample a quarter of the patch pitch around each centre to stay clear of the gaps.
+    let pitch = ((tr.x - tl.x).hypot(tr.y - tl.y) / (CHART_COLUMNS - 1) as f32)
+        .min((bl.x - tl.x).hypot(bl.y - tl.y) / (CHART_ROWS - 1) as f32);
+    (centers, (pitch * 0.25).max(1.0))
+}
+
+fn sample_linear(image: &DynamicImage, center: (f32, f32), radius: f32) -> Result<[f32; 3], String> {
+    let (w, h) = image.dimensions();
+    let x0 = (center.0 - radius).max(0.0) as u32;
+    let y0 = (center.1 - radius).max(0.0) as u32;
+    let x1 = ((center.0 + radius) as u32).min(w.saturating_sub(1));
+    let y1 = ((center.1 + radius) as u32).min(h.saturating_sub(1));
+    if x0 > x1 || y0 > y1 {
+        return Err("A chart patch lies outside the image.".to_string());
+    }
+
+    let mut sum = [0.0f64; 3];
+    let mut count = 0.0f64;
+    for y in y0..=y1 {
+        for x in x0..=x1 {
+            let pixel = image.get_pixel(x, y);
+            for c in 0..3 {
+                sum[c] += srgb_to_linear(pixel[c] as f32 / 255.0) as f64;
+            }
+            count += 1.0;
+        }
+    }
+    Ok(sum.map(|s| (s / count) as f32))
+}
+
+fn invert_3x3(m: [[f64; 3]; 3]) -> Option<[[f64; 3]; 3]> {
+    let det = m[0][0] * (m[1][1] * m[2][2] - m[1][2] * m[2][1])
+        - m[0][1] * (m[1][0] * m[2][2] - m[1][2] * m[2][0])
+        + m[0][2] * (m[1][0] * m[2][1] - m[1][1] * m[2][0]);
+    if det.abs() < 1e-12 {
+        return None;
+    }
+    let inv_det = 1.0 / det;
+    Some([
+        [
+            (m[1][1] * m[2][2] - m[1][2] * m[2][1]) * inv_det,
+            (m[0][2] * m[2][1] - m[0][1] * m[2][2]) * inv_det,
+            (m[0][1] * m[1][2] - m[0][2] * m[1][1]) * inv_det,
+        ],
+        [
+            (m[1][2] * m[2][0] - m[1][0] * m[2][2]) * inv_det,
+            (m[0][0] * m[2][2] - m[0][2] * m[2][0]) * inv_det,
+            (m[0][2] * m[1][0] - m[0][0] * m[1][2]) * inv_det,
+        ],
+        [
+            (m[1][0] * m[2][1] - m[1][1] * m[2][0]) * inv_det,
+            (m[0][1] * m[2][0] - m[0][0] * m[2][1]) * inv_det,
+            (m[0][0] * m[1][1] - m[0][1] * m[1][0]) * inv_det,
+        ],
+    ])
+}
+
+// Least-squares fit of `matrix * measured ≈ reference` over all patches.
+fn fit_matrix(measured: &[[f32; 3]], reference: &[[f32; 3]]) -> Option<[[f32; 3]; 3]> {
+    let mut ata = [[0.0f64; 3]; 3];
+    let mut atb = [[0.0f64; 3]; 3];
+    for (m, r) in measured.iter().zip(reference) {
+        for i in 0..3 {
+            for j in 0..3 {
+                ata[i][j] += m[i] as f64 * m[j] as f64;
+                atb[i][j] += m[i] as f64 * r[j] as f64;
+            }
+        }
+    }
+    let inv = invert_3x3(ata)?;
+
+    let mut matrix = [[0.0f32; 3]; 3];
+    for (row, out_row) in matrix.iter_mut().enumerate() {
+        for (col, value) in out_row.iter_mut().enumerate() {
+            *value = (0..3).map(|k| inv[col][k] * atb[k][row]).sum::<f64>() as f32;
+        }
+    }
+    Some(matrix)
+}
+
+fn apply_matrix(matrix: &[[f32; 3]; 3], rgb: [f32; 3]) -> [f32; 3] {
+    matrix.map(|row| (row[0] * rgb[0] + row[1] * rgb[1] + row[2] * rgb[2]).max(0.0))
+}
+
+fn linear_to_srgb8(rgb: [f32; 3]) -> [f32; 3] {
+    rgb.map(|c| {
+        let c = c.clamp(0.0, 1.0);
+        let encoded = if c <= 0.0031308 { c * 12.92 } else { 1.055 * c.powf(1.0 / 2.4) - 0.055 };
+        encoded * 255.0
+    })
+}
+
+fn mean_delta_e(values: &[[f32; 3]], reference: &[[f32; 3]]) -> f32 {
+    let total: f32 = values
+        .iter()
+        .zip(reference)
+        .map(|(v, r)| {
+            let a = srgb_to_lab(linear_to_srgb8(*v));
+            let b = srgb_to_lab(linear_to_srgb8(*r));
+            ((a[0] - b[0]).powi(2) + (a[1] - b[1]).powi(2) + (a[2] - b[2]).powi(2)).sqrt()
+        })
+        .sum();
+    total / values.len().max(1) as f32
+}
+
+#[tauri::command]
+pub fn create_camera_profile(
+    path: String,
+    chart_corners: Option<[ChartPoint; 4]>,
+    name: Option<String>,
+    app_handle: AppHandle,
+) -> Result<CameraProfile, String> {
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let image = load_base_image_from_bytes(&file_bytes, &path, false, DevelopOverrides::default()).map_err(|e| e.to_string())?;
+    let (width, height) = image.dimensions();
+
+    let (centers, radius) = patch_centers(chart_corners, width, height);
+    let mut measured = centers
+        .iter()
+        .map(|center| sample_linear(&image, *center, radius))
+        .collect::<Result<Vec<_>, _>>()?;
+    let reference: Vec<[f32; 3]> = COLOR_CHECKER
+        .iter()
+        .map(|(_, rgb)| rgb.map(|c| srgb_to_linear(c as f32 / 255.0)))
+        .collect();
+
+    // Charts are rarely shot at exactly the right exposure. Scaling the measurements so the
+    // grey patches match their reference keeps the fitted matrix exposure-neutral.
+    let neutral = 19..23;
+    let measured_grey: f32 = measured[neutral.clone()].iter().map(|m| m[1]).sum();
+    let reference_grey: f32 = reference[neutral].iter().map(|r| r[1]).sum();
+    if measured_grey <= 1e-6 {
+        return Err("The chart's grey patches are black; check the chart position.".to_string());
+    }
+    let exposure_scale = reference_grey / measured_grey;
+    for m in measured.iter_mut() {
+        *m = m.map(|c| c * exposure_scale);
+    }
+
+    let matrix = fit_matrix(&measured, &reference)
+        .ok_or_else(|| "Could not fit a color matrix to the chart; check the chart position.".to_string())?;
+    let corrected: Vec<[f32; 3]> = measured.iter().map(|m| apply_matrix(&matrix, *m)).collect();
+
+    let capture_info = read_capture_info(&file_bytes, &path);
+    let (camera_make, camera_model) = (capture_info.make, capture_info.model);
+    let profile = CameraProfile {
+        id: Uuid::new_v4().to_string(),
+        name: name
+            .filter(|n| !n.trim().is_empty())
+            .unwrap_or_else(|| format!("{} {} (ColorChecker)", camera_make, camera_model).trim().to_string()),
+        camera_make,
+        camera_model,
+        matrix,
+        mean_delta_e_before: mean_delta_e(&measured, &reference),
+        mean_delta_e_after: mean_delta_e(&corrected, &reference),
+        created_at: Local::now().to_rfc3339(),
+    };
+
+    let mut profiles = load_profiles(&app_handle)?;
+    profiles.push(profile.clone());
+    save_profiles(&app_handle, &profiles)?;
+
+    Ok(profile)
+}
+
+// With a camera model only that camera's profiles are returned, for the calibration panel.
+#[tauri::command]
+pub fn list_camera_profiles(
+    camera_model: Option<String>,
+    app_handle: AppHandle,
+) -> Result<Vec<CameraProfile>, String> {
+    let profiles = load_profiles(&app_handle)?;
+    Ok(match camera_model {
+        Some(model) => profiles.into_iter().filter(|p| p.camera_model == model).collect(),
+        None => profiles,
+    })
+}
+
+#[tauri::command]
+pub fn delete_camera_profile(id: String, app_handle: AppHandle) -> Result<(), String> {
+    let mut profiles = load_profiles(&app_handle)?;
+    profiles.retain(|p| p.id != id);
+    save_profiles(&app_handle, &profiles)
+}
@@ -0,0 +1,98 @@
+use image::codecs::png::PngEncoder;
+use image::{DynamicImage, ImageEncoder};
+
+// Renders are sRGB encoded. The PNG carries the sRGB profile so receiving apps do not have to
+// guess, which they would for a plain bitmap.
+fn encode_srgb_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let rgba_image = image.to_rgba8();
+    let (width, height) = rgba_image.dimensions();
+    let icc_profile = lcms2::Profile::new_srgb().icc().map_err(|e| e.to_string())?;
+
+    let mut png = Vec::new();
+    let mut encoder = PngEncoder::new(&mut png);
+    encoder.set_icc_profile(icc_profile).map_err(|e| e.to_string())?;
+    encoder
+        .write_image(rgba_image.as_raw(), width, height, image::ExtendedColorType::Rgba8)
+        .map_err(|e| e.to_string())?;
+    Ok(png)
+}
+
+// Places the image on the clipboard as PNG, under the format name each platform uses for it.
+pub fn copy_image(image: &DynamicImage) -> Result<(), String> {
+    let png = encode_srgb_png(image)?;
+    write_png(&png, image)
+}
+
+#[cfg(target_os = "macos")]
+fn write_png(png: &[u8], _image: &DynamicImage) -> Result<(), String> {
+    use objc2_app_kit::{NSPasteboard, NSPasteboardTypePNG};
+    use objc2_foundation::NSData;
+
+    let data = NSData::with_bytes(png);
+    let written = unsafe {
+        let pasteboard = NSPasteboard::generalPasteboard();
+        pasteboard.clearContents();
+        pasteboard.setData_forType(Some(&data), NSPasteboardTypePNG)
+    };
+    if written {
+        Ok(())
+    } else {
+        Err("Failed to copy image to clipboard.".to_string())
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn write_png(png: &[u8], _image: &DynamicImage) -> Result<(), String> {
+    let _clipboard = clipboard_win::Clipboard::new_attempts(10)
+        .map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    let format = clipboard_win::register_format("PNG").ok_or("Failed to register the PNG clipboard format.")?;
+    clipboard_win::empty().map_err(|e| format!("Failed to access clipboard: {}", e))?;
+    clipboard_win::raw::set_without_clear(format.get(), png)
+        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))
+}
+
+// The clipboard is served by its owning process, so it is kept alive after the command returns.
+#[cfg(all(unix, not(target_os = "macos")))]
+static FALLBACK_CLIPBOARD: std::sync::Mutex<Option<arboard::Clipboard>> = std::sync::Mutex::new(None);
+
+// wl-copy and xclip offer the bytes as image/png and keep serving them in the background.
+// Without either tool, arboard is used as a fallback; it offers a PNG it encodes itself,
+// without the sRGB profile.
+#[cfg(all(unix, not(target_os = "macos")))]
+fn write_png(png: &[u8], image: &DynamicImage) -> Result<(), String> {
+    use std::io::Write;
+    use std::process::{Command, Stdio};
+
+    let (program, args): (&str, &[&str]) = if std::env::var_os("WAYLAND_DISPLAY").is_some() {
+        ("wl-copy", &["--type", "image/png"])
+    } else {
+        ("xclip", &["-selection", "clipboard", "-target", "image/png"])
+    };
+
+    if let Ok(mut child) = Command::new(program).args(args).stdin(Stdio::piped()).spawn() {
+        let written = child.stdin.take().map(|mut stdin| stdin.write_all(png));
+        let status = child.wait().map_err(|e| e.to_string())?;
+        if matches!(written, Some(Ok(()))) && status.success() {
+            return Ok(());
+        }
+        eprintln!("{} could not take the image, falling back to arboard.", program);
+    }
+
+    let rgba_image = image.to_rgba8();
+    let (width, height) = rgba_image.dimensions();
+    let image_data = arboard::ImageData {
+        width: width as usize,
+        height: height as usize,
+        bytes: std::borrow::Cow::Owned(rgba_image.into_raw()),
+    };
+
+    let mut clipboard_lock = FALLBACK_CLIPBOARD.lock().unwrap();
+    if clipboard_lock.is_none() {
+        *clipboard_lock = Some(arboard::Clipboard::new().map_err(|e| format!("Failed to access clipboard: {}", e))?);
+    }
+    clipboard_lock
+        .as_mut()
+        .unwrap()
+        .set_image(image_data)
+        .map_err(|e| format!("Failed to copy image to clipboard: {}", e))
+}
@@ -0,0 +1,97 @@
+use std::fs;
+use std::io::Cursor;
+
+use image::{DynamicImage, GenericImageView};
+use lcms2::{Flags, Intent, PixelFormat, Profile, Transform};
+use serde::{Deserialize, Serialize};
+use tiff::encoder::{colortype, Rational, TiffEncoder};
+use tiff::tags::{ResolutionUnit, Tag};
+
+const TIFF_TAG_ICC_PROFILE: u16 = 34675;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum RenderingIntent {
+    #[default]
+    Perceptual,
+    RelativeColorimetric,
+    Saturation,
+    AbsoluteColorimetric,
+}
+
+impl From<RenderingIntent> for Intent {
+    fn from(intent: RenderingIntent) -> Self {
+        match intent {
+            RenderingIntent::Perceptual => Intent::Perceptual,
+            RenderingIntent::RelativeColorimetric => Intent::RelativeColorimetric,
+            RenderingIntent::Saturation => Intent::Saturation,
+            RenderingIntent::AbsoluteColorimetric => Intent::AbsoluteColorimetric,
+        }
+    }
+}
+
+// `profile_path` points at the press profile supplied by the print shop (e.g. FOGRA39 or
+// GRACoL); it is embedded in the TIFF so the shop can verify what the file was separated for.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct CmykSettings {
+    pub profile_path: String,
+    #[serde(default)]
+    pub intent: RenderingIntent,
+    #[serde(default = "default_black_point_compensation")]
+    pub black_point_compensation: bool,
+}
+
+fn default_black_point_compensation() -> bool {
+    true
+}
+
+pub fn encode_cmyk_tiff(image: &DynamicImage, settings: &CmykSettings, dpi: Option<u32>) -> Result<Vec<u8>, String> {
+    let profile_bytes = fs::read(&settings.profile_path)
+        .map_err(|e| format!("Failed to read ICC profile {}: {}", settings.profile_path, e))?;
+    let press_profile = Profile::new_icc(&profile_bytes).map_err(|e| format!("Invalid ICC profile: {}", e))?;
+    if press_profile.color_space() != lcms2::ColorSpaceSignature::CmykData {
+        return Err("The selected ICC profile is not a CMYK profile.".to_string());
+    }
+
+    // Export renders are sRGB-encoded, which is also what the previews show.
+    let srgb = Profile::new_srgb();
+    let intent = Intent::from(settings.intent);
+    let transform: Transform<[u8; 3], [u8; 4]> = if settings.black_point_compensation {
+        Transform::new_flags(
+            &srgb,
+            PixelFormat::RGB_8,
+            &press_profile,
+            PixelFormat::CMYK_8,
+            intent,
+            Flags::BLACKPOINT_COMPENSATION,
+        )
+    } else {
+        Transform::new(&srgb, PixelFormat::RGB_8, &press_profile, PixelFormat::CMYK_8, intent)
+    }
+    .map_err(|e| format!("Failed to create CMYK transform: {}", e))?;
+
+    let (width, height) = image.dimensions();
+    let rgb = image.to_rgb8();
+    let rgb_pixels: Vec<[u8; 3]> = rgb.pixels().map(|p| p.0).collect();
+    let mut cmyk_pixels = vec![[0u8; 4]; rgb_pixels.len()];
+    transform.transform_pixels(&rgb_pixels, &mut cmyk_pixels);
+    let cmyk_data: Vec<u8> = cmyk_pixels.into_iter().flatten().collect();
+
+    let mut bytes = Vec::new();
+    {
+        let mut encoder = TiffEncoder::new(Cursor::new(&mut bytes)).map_err(|e| e.to_string())?;
+        let mut tiff_image = encoder
+            .new_image::<colortype::CMYK8>(width, height)
+            .map_err(|e| e.to_string())?;
+        if let Some(dpi) = dpi {
+            tiff_image.resolution(ResolutionUnit::Inch, Rational { n: dpi, d: 1 });
+        }
+        tiff_image
+            .encoder()
+            .write_tag(Tag::Unknown(TIFF_TAG_ICC_PROFILE), &profile_bytes[..])
+            .map_err(|e| e.to_string())?;
+        tiff_image.write_data(&cmyk_data).map_err(|e| e.to_string())?;
+    }
+    Ok(bytes)
+}
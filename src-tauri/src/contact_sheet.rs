@@ -0,0 +1,316 @@
+use std::fs;
+use std::io::{Cursor, Write};
+use std::path::Path;
+
+use image::codecs::jpeg::JpegEncoder;
+use image::DynamicImage;
+use serde::Deserialize;
+use tauri::Emitter;
+
+use crate::geotagging::read_capture_time;
+use crate::image_processing::{get_or_init_gpu_context, GpuContext};
+use crate::AppState;
+
+const MARGIN: f32 = 36.0;
+const HEADER_HEIGHT: f32 = 28.0;
+const CAPTION_HEIGHT: f32 = 24.0;
+const CELL_PADDING: f32 = 6.0;
+// Previews are rendered at roughly 200 dpi for the cell they end up in, which prints sharply
+// without making the PDF huge.
+const PREVIEW_DPI: f32 = 200.0;
+
+#[derive(Deserialize, Debug, Clone, Copy, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum PageSize {
+    #[default]
+    A4,
+    Letter,
+}
+
+impl PageSize {
+    fn dimensions(self) -> (f32, f32) {
+        match self {
+            PageSize::A4 => (595.28, 841.89),
+            PageSize::Letter => (612.0, 792.0),
+        }
+    }
+}
+
+#[derive(Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ContactSheetOptions {
+    pub columns: Option<u32>,
+    pub rows: Option<u32>,
+    pub page_size: Option<PageSize>,
+    pub title: Option<String>,
+}
+
+// A minimal PDF writer: JPEG previews are embedded as-is (DCTDecode) and captions use the
+// built-in Helvetica font, so no font or image data has to be converted.
+struct PdfDocument {
+    objects: Vec<Vec<u8>>,
+}
+
+const CATALOG_ID: usize = 1;
+const PAGES_ID: usize = 2;
+const FONT_ID: usize = 3;
+
+impl PdfDocument {
+    fn new() -> Self {
+        Self { objects: vec![Vec::new(); 3] }
+    }
+
+    fn add(&mut self, body: Vec<u8>) -> usize {
+        self.objects.push(body);
+        self.objects.len()
+    }
+
+    fn stream(dictionary: &str, data: &[u8]) -> Vec<u8> {
+        let mut body = format!("<< {} /Length {} >>\nstream\n", dictionary, data.len()).into_bytes();
+        body.extend_from_slice(data);
+        body.extend_from_slice(b"\nendstream");
+        body
+    }
+
+    fn finish(mut self, page_ids: &[usize]) -> Vec<u8> {
+        let kids = page_ids.iter().map(|id| format!("{} 0 R", id)).collect::<Vec<_>>().join(" ");
+        self.objects[CATALOG_ID - 1] = format!("<< /Type /Catalog /Pages {} 0 R >>", PAGES_ID).into_bytes();
+        self.objects[PAGES_ID - 1] =
+            format!("<< /Type /Pages /Kids [{}] /Count {} >>", kids, page_ids.len()).into_bytes();
+        self.objects[FONT_ID - 1] =
+            b"<< /Type /Font /Subtype /Type1 /BaseFont /Helvetica /Encoding /WinAnsiEncoding >>".to_vec();
+
+        let mut out = b"%PDF-1.4\n%\xE2\xE3\xCF\xD3\n".to_vec();
+        let mut offsets = Vec::with_capacity(self.objects.len());
+        for (i, body) in self.objects.iter().enumerate() {
+            offsets.push(out.len());
+            out.extend_from_slice(format!("{} 0 obj\n", i + 1).as_bytes());
+            out.extend_from_slice(body);
+            out.extend_from_slice(b"\nendobj\n");
+        }
+
+        let xref_offset = out.len();
+        let _ = write!(out, "xref\n0 {}\n0000000000 65535 f \n", self.objects.len() + 1);
+        for offset in offsets {
+            let _ = write!(out, "{:010} 00000 n \n", offset);
+        }
+        let _ = write!(
+            out,
+            "trailer\n<< /Size {} /Root {} 0 R >>\nstartxref\n{}\n%%EOF\n",
+            self.objects.len() + 1,
+            CATALOG_ID,
+            xref_offset
+        );
+        out
+    }
+}
+
+// Helvetica is encoded as WinAnsi, which covers Latin-1; anything else becomes '?'.
+fn pdf_string(text: &str) -> String {
+    let mut out = String::with_capacity(text.len() + 2);
+    out.push('(');
+    for c in text.chars() {
+        match c {
+            '(' | ')' | '\\' => {
+                out.push('\\');
+                out.push(c);
+            }
+            ' '..='~' => out.push(c),
+            '\u{a0}'..='\u{ff}' => out.push_str(&format!("\\{:03o}", c as u32)),
+            _ => out.push('?'),
+        }
+    }
+    out.push(')');
+    out
+}
+
+// Helvetica glyphs average a little over half the font size in width, which is close enough
+// to keep captions inside their cell.
+fn fit_caption(text: &str, font_size: f32, max_width: f32) -> String {
+    let max_chars = (max_width / (font_size * 0.55)).floor().max(4.0) as usize;
+    if text.chars().count() <= max_chars {
+        return text.to_string();
+    }
+    let truncated: String = text.chars().take(max_chars - 3).collect();
+    format!("{}...", truncated)
+}
+
+struct SheetEntry {
+    jpeg: Vec<u8>,
+    width: u32,
+    height: u32,
+    file_name: String,
+    capture_date: Option<String>,
+}
+
+fn render_entry(context: &GpuContext, path: &str, max_dim: u32) -> Result<SheetEntry, String> {
+    let image = crate::render_library_image_preview(context, path, max_dim)?;
+    let rgb = DynamicImage::ImageRgb8(image.to_rgb8());
+
+    let mut jpeg = Vec::new();
+    rgb.write_with_encoder(JpegEncoder::new_with_quality(&mut Cursor::new(&mut jpeg), 85))
+        .map_err(|e| e.to_string())?;
+
+    Ok(SheetEntry {
+        jpeg,
+        width: rgb.width(),
+        height: rgb.height(),
+        file_name: Path::new(path)
+            .file_name()
+            .map(|n| n.to_string_lossy().into_owned())
+            .unwrap_or_default(),
+        capture_date: read_capture_time(path)
+            .ok()
+            .map(|t| t.format("%Y-%m-%d %H:%M").to_string()),
+    })
+}
+
+fn build_contact_sheet(
+    context: &GpuContext,
+    paths: &[String],
+    options: &ContactSheetOptions,
+    app_handle: &tauri::AppHandle,
+) -> Result<Vec<u8>, String> {
+    let (page_w, page_h) = options.page_size.unwrap_or_default().dimensions();
+    let columns = options.columns.unwrap_or(4).clamp(1, 12);
+    let rows = options.rows.unwrap_or(5).clamp(1, 16);
+    let per_page = (columns * rows) as usize;
+
+    let title = options.title.clone().filter(|t| !t.trim().is_empty());
+    let header_height = if title.is_some() { HEADER_HEIGHT } else { 0.0 };
+    let cell_w = (page_w - 2.0 * MARGIN) / columns as f32;
+    let cell_h = (page_h - 2.0 * MARGIN - header_height) / rows as f32;
+    let image_box_w = cell_w - 2.0 * CELL_PADDING;
+    let image_box_h = cell_h - 2.0 * CELL_PADDING - CAPTION_HEIGHT;
+    if image_box_w <= 0.0 || image_box_h <= 0.0 {
+        return Err("Too many rows or columns for the selected page size.".to_string());
+    }
+    let max_dim = (image_box_w.max(image_box_h) / 72.0 * PREVIEW_DPI).ceil() as u32;
+
+    let mut pdf = PdfDocument::new();
+    let mut page_ids = Vec::new();
+    let total = paths.len();
+    let page_count = total.div_ceil(per_page).max(1);
+
+    for (page_index, page_paths) in paths.chunks(per_page.max(1)).enumerate() {
+        let mut content = String::new();
+        let mut xobjects = Vec::new();
+
+        if let Some(title) = &title {
+            let header = format!("{}  -  page {} of {}", title, page_index + 1, page_count);
+            content.push_str(&format!(
+                "BT /F{} 12 Tf {:.2} {:.2} Td {} Tj ET\n",
+                FONT_ID,
+                MARGIN,
+                page_h - MARGIN - 14.0,
+                pdf_string(&fit_caption(&header, 12.0, page_w - 2.0 * MARGIN))
+            ));
+        }
+
+        for (slot, path) in page_paths.iter().enumerate() {
+            let index = page_index * per_page + slot;
+            let _ = app_handle.emit(
+                "contact-sheet-progress",
+                serde_json::json!({ "current": index, "total": total, "path": path }),
+            );
+
+            let column = (slot as u32 % columns) as f32;
+            let row = (slot as u32 / columns) as f32;
+            let cell_x = MARGIN + column * cell_w;
+            let cell_top = page_h - MARGIN - header_height - row * cell_h;
+
+            let entry = match render_entry(context, path, max_dim) {
+                Ok(entry) => entry,
+                Err(e) => {
+                    eprintln!("Skipping {} in contact sheet: {}", path, e);
+                    continue;
+                }
+            };
+
+            let scale = (image_box_w / entry.width as f32).min(image_box_h / entry.height as f32);
+            let draw_w = entry.width as f32 * scale;
+            let draw_h = entry.height as f32 * scale;
+            let draw_x = cell_x + CELL_PADDING + (image_box_w - draw_w) / 2.0;
+            let draw_y = cell_top - CELL_PADDING - image_box_h + (image_box_h - draw_h) / 2.0;
+
+            let image_id = pdf.add(PdfDocument::stream(
+                &format!(
+                    "/Type /XObject /Subtype /Image /Width {} /Height {} /ColorSpace /DeviceRGB /BitsPerComponent 8 /Filter /DCTDecode",
+                    entry.width, entry.height
+                ),
+                &entry.jpeg,
+            ));
+            let name = format!("Im{}", slot);
+            content.push_str(&format!(
+                "q {:.2} 0 0 {:.2} {:.2} {:.2} cm /{} Do Q\n",
+                draw_w, draw_h, draw_x, draw_y, name
+            ));
+            xobjects.push(format!("/{} {} 0 R", name, image_id));
+
+            let caption_x = cell_x + CELL_PADDING;
+            let caption_top = cell_top - CELL_PADDING - image_box_h;
+            content.push_str(&format!(
+                "BT /F{} 8 Tf {:.2} {:.2} Td {} Tj ET\n",
+                FONT_ID,
+                caption_x,
+                caption_top - 10.0,
+                pdf_string(&fit_caption(&entry.file_name, 8.0, image_box_w))
+            ));
+            if let Some(date) = &entry.capture_date {
+                content.push_str(&format!(
+                    "BT /F{} 7 Tf {:.2} {:.2} Td {} Tj ET\n",
+                    FONT_ID,
+                    caption_x,
+                    caption_top - 20.0,
+                    pdf_string(date)
+                ));
+            }
+        }
+
+        let content_id = pdf.add(PdfDocument::stream("", content.as_bytes()));
+        let page_id = pdf.add(
+            format!(
+                "<< /Type /Page /Parent {} 0 R /MediaBox [0 0 {:.2} {:.2}] /Resources << /Font << /F{} {} 0 R >> /XObject << {} >> >> /Contents {} 0 R >>",
+                PAGES_ID,
+                page_w,
+                page_h,
+                FONT_ID,
+                FONT_ID,
+                xobjects.join(" "),
+                content_id
+            )
+            .into_bytes(),
+        );
+        page_ids.push(page_id);
+    }
+
+    let _ = app_handle.emit(
+        "contact-sheet-progress",
+        serde_json::json!({ "current": total, "total": total, "path": "" }),
+    );
+
+    Ok(pdf.finish(&page_ids))
+}
+
+#[tauri::command]
+pub async fn export_contact_sheet(
+    paths: Vec<String>,
+    output_path: String,
+    options: Option<ContactSheetOptions>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    if paths.is_empty() {
+        return Err("No images selected for the contact sheet.".to_string());
+    }
+
+    let context = get_or_init_gpu_context(&state)?;
+    let options = options.unwrap_or_default();
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let pdf_bytes = build_contact_sheet(&context, &paths, &options, &app_handle)?;
+        fs::write(&output_path, pdf_bytes).map_err(|e| e.to_string())
+    })
+    .await
+    .map_err(|e| e.to_string())?
+}
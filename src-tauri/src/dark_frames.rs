@@ -0,0 +1,173 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use rawler::{
+    decoders::{RawDecodeParams, RawMetadata},
+    rawimage::RawImage,
+    rawsource::RawSource,
+};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::raw_processing::read_raw_metadata;
+
+// Exposure times within this fraction of each other are treated as the same exposure.
+const EXPOSURE_TOLERANCE: f32 = 0.1;
+
+// A frame shot with the lens cap on. It is subtracted from RAW files of the same camera,
+// ISO and exposure time to remove hot pixels and amp glow.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct DarkFrame {
+    pub path: String,
+    pub camera_make: String,
+    pub camera_model: String,
+    pub iso: Option<u32>,
+    // Seconds
+    pub exposure_time: Option<f32>,
+    pub created_at: String,
+}
+
+static DARK_FRAMES: Mutex<Vec<DarkFrame>> = Mutex::new(Vec::new());
+// The last decoded dark frame. A series of long exposures usually shares the same one.
+static DECODED: Mutex<Option<(String, Arc<RawImage>)>> = Mutex::new(None);
+
+fn get_dark_frames_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join("dark_frames.json"))
+}
+
+fn load_dark_frames(app_handle: &AppHandle) -> Result<Vec<DarkFrame>, String> {
+    let path = get_dark_frames_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save_dark_frames(app_handle: &AppHandle, frames: &[DarkFrame]) -> Result<(), String> {
+    let path = get_dark_frames_path(app_handle)?;
+    let json_string = serde_json::to_string_pretty(frames).map_err(|e| e.to_string())?;
+    fs::write(path, json_string).map_err(|e| e.to_string())?;
+    *DARK_FRAMES.lock().unwrap() = frames.to_vec();
+    Ok(())
+}
+
+pub fn init(app_handle: &AppHandle) {
+    match load_dark_frames(app_handle) {
+        Ok(frames) => *DARK_FRAMES.lock().unwrap() = frames,
+        Err(e) => eprintln!("Failed to load dark frames: {}", e),
+    }
+}
+
+fn iso_of(metadata: &RawMetadata) -> Option<u32> {
+    metadata.exif.iso_speed_ratings.map(u32::from).or(metadata.exif.iso_speed)
+}
+
+fn exposure_time_of(metadata: &RawMetadata) -> Option<f32> {
+    metadata
+        .exif
+        .exposure_time
+        .filter(|t| t.d != 0)
+        .map(|t| t.n as f32 / t.d as f32)
+}
+
+pub(crate) fn decode(path: &str) -> anyhow::Result<RawImage> {
+    let file_bytes = fs::read(path)?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source)?;
+    Ok(decoder.raw_image(&source, &RawDecodeParams::default(), false)?)
+}
+
+// The registered dark frame matching the image, closest in exposure time if there are several.
+fn find_match(metadata: &RawMetadata) -> Option<DarkFrame> {
+    let iso = iso_of(metadata);
+    let exposure_time = exposure_time_of(metadata);
+    let frames = DARK_FRAMES.lock().unwrap();
+
+    frames
+        .iter()
+        .filter(|f| f.camera_make == metadata.make && f.camera_model == metadata.model && f.iso == iso)
+        .filter_map(|f| {
+            let deviation = match (f.exposure_time, exposure_time) {
+                (Some(dark), Some(light)) if light > 0.0 => (dark - light).abs() / light,
+                (None, None) => 0.0,
+                _ => return None,
+            };
+            (deviation <= EXPOSURE_TOLERANCE).then_some((deviation, f))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, f)| f.clone())
+}
+
+// Called while developing. Problems with a dark frame are logged and the image is developed
+// without it, rather than failing to open.
+pub fn dark_frame_for(raw_image: &RawImage, metadata: &RawMetadata) -> Option<Arc<RawImage>> {
+    let frame = find_match(metadata)?;
+
+    let mut decoded = DECODED.lock().unwrap();
+    let dark = match decoded.as_ref() {
+        Some((path, dark)) if *path == frame.path => dark.clone(),
+        _ => match decode(&frame.path) {
+            Ok(dark) => {
+                let dark = Arc::new(dark);
+                *decoded = Some((frame.path.clone(), dark.clone()));
+                dark
+            }
+            Err(e) => {
+                eprintln!("Failed to decode dark frame {}: {}", frame.path, e);
+                return None;
+            }
+        },
+    };
+
+    if dark.width != raw_image.width || dark.height != raw_image.height || dark.cpp != raw_image.cpp {
+        eprintln!("Dark frame {} does not match the image size, skipping it", frame.path);
+        return None;
+    }
+    Some(dark)
+}
+
+#[tauri::command]
+pub fn register_dark_frame(path: String, app_handle: AppHandle) -> Result<DarkFrame, String> {
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let metadata = read_raw_metadata(&file_bytes).map_err(|e| format!("Not a RAW file: {}", e))?;
+    let frame = DarkFrame {
+        path: path.clone(),
+        camera_make: metadata.make.clone(),
+        camera_model: metadata.model.clone(),
+        iso: iso_of(&metadata),
+        exposure_time: exposure_time_of(&metadata),
+        created_at: Local::now().to_rfc3339(),
+    };
+
+    let mut frames = load_dark_frames(&app_handle)?;
+    frames.retain(|f| f.path != path);
+    frames.push(frame.clone());
+    save_dark_frames(&app_handle, &frames)?;
+    Ok(frame)
+}
+
+#[tauri::command]
+pub fn list_dark_frames(app_handle: AppHandle) -> Result<Vec<DarkFrame>, String> {
+    load_dark_frames(&app_handle)
+}
+
+#[tauri::command]
+pub fn remove_dark_frame(path: String, app_handle: AppHandle) -> Result<(), String> {
+    let mut frames = load_dark_frames(&app_handle)?;
+    frames.retain(|f| f.path != path);
+    save_dark_frames(&app_handle, &frames)?;
+
+    let mut decoded = DECODED.lock().unwrap();
+    if decoded.as_ref().is_some_and(|(p, _)| *p == path) {
+        *decoded = None;
+    }
+    Ok(())
+}
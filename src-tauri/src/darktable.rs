@@ -0,0 +1,247 @@
+use std::collections::HashMap;
+use std::fs;
+use std::io::Read;
+use std::path::PathBuf;
+
+use anyhow::{anyhow, Result};
+use base64::{engine::general_purpose, Engine as _};
+use flate2::read::ZlibDecoder;
+use serde_json::{json, Map, Value};
+
+use crate::file_management::get_sidecar_path;
+use crate::image_processing::ImageMetadata;
+
+const DT_NS: &str = "http://darktable.sf.net/";
+const DC_NS: &str = "http://purl.org/dc/elements/1.1/";
+const XMP_NS: &str = "http://ns.adobe.com/xap/1.0/";
+const RDF_NS: &str = "http://www.w3.org/1999/02/22-rdf-syntax-ns#";
+
+// darktable stores its colour labels as indices into this list.
+const DT_COLOR_LABELS: &[&str] = &["red", "yellow", "green", "blue", "purple"];
+
+struct HistoryItem {
+    operation: String,
+    enabled: bool,
+    params: Vec<u8>,
+}
+
+pub fn get_darktable_sidecar_path(image_path: &str) -> PathBuf {
+    let path = PathBuf::from(image_path);
+    let original_filename = path.file_name().unwrap_or_default().to_string_lossy();
+    path.with_file_name(format!("{}.xmp", original_filename))
+}
+
+fn decode_params(encoded: &str) -> Result<Vec<u8>> {
+    if let Some(compressed) = encoded.strip_prefix("gz") {
+        // "gz" is followed by a two digit compression factor before the base64 payload.
+        let payload = compressed.get(2..).ok_or_else(|| anyhow!("Truncated params"))?;
+        let bytes = general_purpose::STANDARD.decode(payload)?;
+        let mut decoded = Vec::new();
+        ZlibDecoder::new(&bytes[..]).read_to_end(&mut decoded)?;
+        return Ok(decoded);
+    }
+
+    (0..encoded.len() / 2)
+        .map(|i| u8::from_str_radix(&encoded[i * 2..i * 2 + 2], 16).map_err(Into::into))
+        .collect()
+}
+
+fn read_f32(params: &[u8], offset: usize) -> Option<f32> {
+    params
+        .get(offset..offset + 4)
+        .map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn read_i32(params: &[u8], offset: usize) -> Option<i32> {
+    params
+        .get(offset..offset + 4)
+        .map(|b| i32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+}
+
+fn dt_attr<'a>(node: &roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.attribute((DT_NS, name)).or_else(|| {
+        node.children()
+            .find(|c| c.tag_name().namespace() == Some(DT_NS) && c.tag_name().name() == name)
+            .and_then(|c| c.text())
+    })
+}
+
+fn parse_history(doc: &roxmltree::Document) -> Vec<HistoryItem> {
+    let history_end = doc
+        .descendants()
+        .find_map(|n| dt_attr(&n, "history_end"))
+        .and_then(|v| v.trim().parse::<usize>().ok());
+
+    let Some(history) = doc
+        .descendants()
+        .find(|n| n.tag_name().namespace() == Some(DT_NS) && n.tag_name().name() == "history")
+    else {
+        return Vec::new();
+    };
+
+    let items: Vec<HistoryItem> = history
+        .descendants()
+        .filter(|n| n.tag_name().namespace() == Some(RDF_NS) && n.tag_name().name() == "li")
+        .filter_map(|n| {
+            let operation = dt_attr(&n, "operation")?.to_string();
+            let enabled = dt_attr(&n, "enabled").map(|v| v.trim() == "1").unwrap_or(true);
+            let params = decode_params(dt_attr(&n, "params")?.trim()).ok()?;
+            Some(HistoryItem { operation, enabled, params })
+        })
+        .collect();
+
+    let end = history_end.unwrap_or(items.len()).min(items.len());
+    items.into_iter().take(end).collect()
+}
+
+fn map_history_to_adjustments(history: &[HistoryItem]) -> Map<String, Value> {
+    // Later history entries win, so only the final state of each module is applied.
+    let mut latest: HashMap<&str, &HistoryItem> = HashMap::new();
+    for item in history {
+        latest.insert(item.operation.as_str(), item);
+    }
+
+    let mut adjustments = Map::new();
+    let clamp = |v: f32| v.clamp(-100.0, 100.0).round() as f64;
+
+    for (operation, item) in latest {
+        if !item.enabled {
+            continue;
+        }
+        let p = &item.params;
+        match operation {
+            "exposure" => {
+                if let Some(black) = read_f32(p, 4) {
+                    adjustments.insert("blacks".into(), json!(clamp(-black * 1000.0)));
+                }
+                if let Some(exposure) = read_f32(p, 8) {
+                    adjustments.insert("exposure".into(), json!((exposure.clamp(-5.0, 5.0) * 100.0).round() / 100.0));
+                }
+            }
+            "colisa" => {
+                if let Some(contrast) = read_f32(p, 0) {
+                    adjustments.insert("contrast".into(), json!(clamp(contrast * 100.0)));
+                }
+                if let Some(saturation) = read_f32(p, 8) {
+                    adjustments.insert("saturation".into(), json!(clamp(saturation * 100.0)));
+                }
+            }
+            "velvia" | "vibrance" => {
+                if let Some(strength) = read_f32(p, 0) {
+                    adjustments.insert("vibrance".into(), json!(clamp(strength)));
+                }
+            }
+            "bilat" => {
+                if let Some(detail) = read_f32(p, 12) {
+                    adjustments.insert("clarity".into(), json!(clamp((detail - 1.0) * 100.0)));
+                }
+            }
+            "sharpen" => {
+                if let Some(amount) = read_f32(p, 4) {
+                    adjustments.insert("sharpness".into(), json!(clamp(amount * 40.0).max(0.0)));
+                }
+            }
+            "flip" => {
+                // Only pure flips are mapped; rotations by 90 degrees have no equivalent yet.
+                match read_i32(p, 0) {
+                    Some(1) => { adjustments.insert("flipVertical".into(), json!(true)); }
+                    Some(2) => { adjustments.insert("flipHorizontal".into(), json!(true)); }
+                    Some(3) => {
+                        adjustments.insert("flipHorizontal".into(), json!(true));
+                        adjustments.insert("flipVertical".into(), json!(true));
+                    }
+                    _ => {}
+                }
+            }
+            _ => {}
+        }
+    }
+
+    adjustments
+}
+
+fn parse_darktable_xmp(content: &str) -> Result<ImageMetadata> {
+    let doc = roxmltree::Document::parse(content)?;
+
+    let is_darktable = doc.descendants().any(|n| {
+        n.tag_name().namespace() == Some(DT_NS) || n.attributes().any(|a| a.namespace() == Some(DT_NS))
+    });
+    if !is_darktable {
+        return Err(anyhow!("Not a darktable sidecar"));
+    }
+
+    let rating = doc
+        .descendants()
+        .find_map(|n| {
+            n.attribute((XMP_NS, "Rating")).map(str::to_string).or_else(|| {
+                (n.tag_name().namespace() == Some(XMP_NS) && n.tag_name().name() == "Rating")
+                    .then(|| n.text().unwrap_or("").to_string())
+            })
+        })
+        .and_then(|r| r.trim().parse::<i32>().ok())
+        .unwrap_or(0)
+        .clamp(0, 5) as u8;
+
+    let list_items = |ns: &str, name: &str| -> Vec<String> {
+        doc.descendants()
+            .find(|n| n.tag_name().namespace() == Some(ns) && n.tag_name().name() == name)
+            .map(|n| {
+                n.descendants()
+                    .filter(|c| c.tag_name().name() == "li")
+                    .filter_map(|c| c.text().map(|t| t.trim().to_string()))
+                    .filter(|t| !t.is_empty())
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+
+    let keywords = list_items(DC_NS, "subject");
+    let label = list_items(DT_NS, "colorlabels")
+        .first()
+        .and_then(|l| l.parse::<usize>().ok())
+        .and_then(|i| DT_COLOR_LABELS.get(i))
+        .map(|l| l.to_string());
+
+    let mut adjustments = map_history_to_adjustments(&parse_history(&doc));
+    adjustments.insert("rating".into(), json!(rating));
+
+    Ok(ImageMetadata {
+        rating,
+        adjustments: Value::Object(adjustments),
+        label,
+        keywords,
+        ..ImageMetadata::default()
+    })
+}
+
+pub fn read_darktable_sidecar(image_path: &str) -> Option<ImageMetadata> {
+    let xmp_path = get_darktable_sidecar_path(image_path);
+    if !xmp_path.exists() {
+        return None;
+    }
+    let content = fs::read_to_string(&xmp_path).ok()?;
+    match parse_darktable_xmp(&content) {
+        Ok(metadata) => Some(metadata),
+        Err(e) => {
+            eprintln!("Failed to read darktable sidecar {}: {}", xmp_path.display(), e);
+            None
+        }
+    }
+}
+
+#[tauri::command]
+pub fn import_darktable_sidecars(paths: Vec<String>, overwrite: bool) -> Result<usize, String> {
+    let mut imported = 0;
+    for path in &paths {
+        let sidecar_path = get_sidecar_path(path);
+        if sidecar_path.exists() && !overwrite {
+            continue;
+        }
+        if let Some(metadata) = read_darktable_sidecar(path) {
+            let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+            fs::write(&sidecar_path, json_string).map_err(|e| e.to_string())?;
+            imported += 1;
+        }
+    }
+    Ok(imported)
+}
@@ -0,0 +1,178 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::export_queue::{enqueue_export_job, ExportJobKind};
+use crate::{AppState, ExportSettings};
+
+const CHECKPOINTS_FILENAME: &str = "export_checkpoints.json";
+
+// The export thread advances checkpoints while commands add and remove them.
+static CHECKPOINTS_LOCK: Mutex<()> = Mutex::new(());
+
+// Everything needed to start a batch export again. Written when the job is queued, advanced
+// after every image and removed once the job finishes, so whatever is left in the file on
+// launch belongs to jobs that were cut short by closing or crashing the app.
+//
+// Single exports are queued too, but `create` returns early for them: they are one image and
+// are not checkpointed. Imports and AI edits have no queued job to resume yet.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportCheckpoint {
+    pub job_id: String,
+    pub output_folder: String,
+    pub paths: Vec<String>,
+    pub output_format: String,
+    #[serde(default)]
+    pub publish_target_id: Option<String>,
+    pub export_settings: ExportSettings,
+    // Images before this index have been exported.
+    pub next_index: usize,
+    pub updated_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct InterruptedExportJob {
+    pub job_id: String,
+    pub output_folder: String,
+    pub is_publish: bool,
+    pub completed: usize,
+    pub total: usize,
+    pub interrupted_at: String,
+}
+
+fn get_checkpoints_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join(CHECKPOINTS_FILENAME))
+}
+
+fn load_checkpoints(app_handle: &AppHandle) -> Result<Vec<ExportCheckpoint>, String> {
+    let path = get_checkpoints_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+// Written to a temporary file first so a crash mid-write cannot lose every checkpoint.
+fn save_checkpoints(app_handle: &AppHandle, checkpoints: &[ExportCheckpoint]) -> Result<(), String> {
+    let path = get_checkpoints_path(app_handle)?;
+    let temp_path = path.with_extension("json.tmp");
+    let json_string = serde_json::to_string(checkpoints).map_err(|e| e.to_string())?;
+    fs::write(&temp_path, json_string).map_err(|e| e.to_string())?;
+    fs::rename(&temp_path, &path).map_err(|e| e.to_string())
+}
+
+fn update_checkpoints(
+    app_handle: &AppHandle,
+    update: impl FnOnce(&mut Vec<ExportCheckpoint>),
+) -> Result<(), String> {
+    let _guard = CHECKPOINTS_LOCK.lock().unwrap();
+    let mut checkpoints = load_checkpoints(app_handle)?;
+    update(&mut checkpoints);
+    save_checkpoints(app_handle, &checkpoints)
+}
+
+// Checkpointing is best effort; an export must never fail because the file could not be written.
+fn update_or_log(app_handle: &AppHandle, update: impl FnOnce(&mut Vec<ExportCheckpoint>)) {
+    if let Err(e) = update_checkpoints(app_handle, update) {
+        eprintln!("Failed to update export checkpoints: {}", e);
+    }
+}
+
+pub fn create(app_handle: &AppHandle, job_id: &str, kind: &ExportJobKind, export_settings: &ExportSettings) {
+    let ExportJobKind::Batch { output_folder, paths, output_format, publish_target_id, start_index } = kind else {
+        return;
+    };
+    let checkpoint = ExportCheckpoint {
+        job_id: job_id.to_string(),
+        output_folder: output_folder.clone(),
+        paths: paths.clone(),
+        output_format: output_format.clone(),
+        publish_target_id: publish_target_id.clone(),
+        export_settings: export_settings.clone(),
+        next_index: *start_index,
+        updated_at: Local::now().to_rfc3339(),
+    };
+    update_or_log(app_handle, |checkpoints| checkpoints.push(checkpoint));
+}
+
+pub fn advance(app_handle: &AppHandle, job_id: &str, next_index: usize) {
+    update_or_log(app_handle, |checkpoints| {
+        if let Some(checkpoint) = checkpoints.iter_mut().find(|c| c.job_id == job_id) {
+            checkpoint.next_index = next_index;
+            checkpoint.updated_at = Local::now().to_rfc3339();
+        }
+    });
+}
+
+pub fn remove(app_handle: &AppHandle, job_id: &str) {
+    update_or_log(app_handle, |checkpoints| checkpoints.retain(|c| c.job_id != job_id));
+}
+
+// Checkpoints of jobs in this session's queue are still being worked on.
+fn interrupted_checkpoints(app_handle: &AppHandle) -> Result<Vec<ExportCheckpoint>, String> {
+    let checkpoints = {
+        let _guard = CHECKPOINTS_LOCK.lock().unwrap();
+        load_checkpoints(app_handle)?
+    };
+    let state = app_handle.state::<AppState>();
+    let queue = state.export_queue.lock().unwrap();
+    Ok(checkpoints
+        .into_iter()
+        .filter(|c| !queue.contains_job(&c.job_id))
+        .collect())
+}
+
+#[tauri::command]
+pub fn list_interrupted_export_jobs(app_handle: AppHandle) -> Result<Vec<InterruptedExportJob>, String> {
+    Ok(interrupted_checkpoints(&app_handle)?
+        .into_iter()
+        .map(|c| InterruptedExportJob {
+            job_id: c.job_id,
+            output_folder: c.output_folder,
+            is_publish: c.publish_target_id.is_some(),
+            completed: c.next_index.min(c.paths.len()),
+            total: c.paths.len(),
+            interrupted_at: c.updated_at,
+        })
+        .collect())
+}
+
+// Queues the job again as a new job that skips the images exported before the interruption.
+// Sequence numbers in filename templates stay the same as in the original run.
+#[tauri::command]
+pub fn resume_interrupted_export_job(job_id: String, app_handle: AppHandle) -> Result<String, String> {
+    let checkpoint = interrupted_checkpoints(&app_handle)?
+        .into_iter()
+        .find(|c| c.job_id == job_id)
+        .ok_or_else(|| "Interrupted export job not found.".to_string())?;
+    update_checkpoints(&app_handle, |checkpoints| checkpoints.retain(|c| c.job_id != job_id))?;
+
+    let start_index = checkpoint.next_index.min(checkpoint.paths.len());
+    Ok(enqueue_export_job(
+        &app_handle,
+        ExportJobKind::Batch {
+            output_folder: checkpoint.output_folder,
+            paths: checkpoint.paths,
+            output_format: checkpoint.output_format,
+            publish_target_id: checkpoint.publish_target_id,
+            start_index,
+        },
+        checkpoint.export_settings,
+    ))
+}
+
+#[tauri::command]
+pub fn discard_interrupted_export_job(job_id: String, app_handle: AppHandle) -> Result<(), String> {
+    update_checkpoints(&app_handle, |checkpoints| checkpoints.retain(|c| c.job_id != job_id))
+}
@@ -0,0 +1,251 @@
+use std::fs;
+use std::io::{Cursor, Write};
+use std::net::TcpStream;
+use std::path::Path;
+
+use chrono::Local;
+use s3::creds::Credentials;
+use s3::{Bucket, Region};
+use serde::{Deserialize, Serialize};
+use ssh2::{Session, Sftp};
+use suppaftp::types::FileType;
+use suppaftp::FtpStream;
+
+use crate::file_management::AppSettings;
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum RemoteProtocol {
+    Sftp,
+    Ftp,
+    S3,
+}
+
+// For S3 `host` is the endpoint URL, `username`/`password` hold the access key id and secret,
+// and objects are named by `key_template` (`{filename}` and `{date}` are substituted).
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RemoteDestination {
+    pub id: String,
+    pub name: String,
+    pub protocol: RemoteProtocol,
+    pub host: String,
+    pub port: Option<u16>,
+    pub username: String,
+    pub password: Option<String>,
+    pub private_key_path: Option<String>,
+    pub remote_folder: String,
+    pub bucket: Option<String>,
+    pub region: Option<String>,
+    pub key_template: Option<String>,
+}
+
+// Export routines hand finished files to a destination instead of writing them directly,
+// so the same pipeline can deliver to disk or to a server. `write` returns where the file
+// ended up, which is what gets reported to hooks and the UI.
+pub trait ExportDestination: Send {
+    fn write(&mut self, output_path: &Path, bytes: &[u8]) -> Result<String, String>;
+    fn exists(&mut self, output_path: &Path) -> Result<bool, String>;
+}
+
+pub struct LocalDestination;
+
+impl ExportDestination for LocalDestination {
+    fn write(&mut self, output_path: &Path, bytes: &[u8]) -> Result<String, String> {
+        fs::write(output_path, bytes).map_err(|e| e.to_string())?;
+        Ok(output_path.to_string_lossy().into_owned())
+    }
+
+    fn exists(&mut self, output_path: &Path) -> Result<bool, String> {
+        Ok(output_path.exists())
+    }
+}
+
+fn remote_file_path(remote_folder: &str, output_path: &Path) -> Result<String, String> {
+    let file_name = output_path
+        .file_name()
+        .and_then(|n| n.to_str())
+        .ok_or_else(|| format!("Invalid output file name: {}", output_path.display()))?;
+    let folder = remote_folder.trim_end_matches('/');
+    Ok(if folder.is_empty() { file_name.to_string() } else { format!("{}/{}", folder, file_name) })
+}
+
+pub struct SftpDestination {
+    // The session has to outlive the SFTP channel that was opened on it.
+    _session: Session,
+    sftp: Sftp,
+    remote_folder: String,
+}
+
+impl SftpDestination {
+    fn connect(config: &RemoteDestination) -> Result<Self, String> {
+        let tcp = TcpStream::connect((config.host.as_str(), config.port.unwrap_or(22)))
+            .map_err(|e| format!("Failed to connect to {}: {}", config.host, e))?;
+        let mut session = Session::new().map_err(|e| e.to_string())?;
+        session.set_tcp_stream(tcp);
+        session.handshake().map_err(|e| format!("SSH handshake failed: {}", e))?;
+
+        match &config.private_key_path {
+            Some(key_path) if !key_path.is_empty() => session.userauth_pubkey_file(
+                &config.username,
+                None,
+                Path::new(key_path),
+                config.password.as_deref(),
+            ),
+            _ => session.userauth_password(&config.username, config.password.as_deref().unwrap_or("")),
+        }
+        .map_err(|e| format!("SFTP authentication failed: {}", e))?;
+
+        let sftp = session.sftp().map_err(|e| e.to_string())?;
+        Ok(Self {
+            _session: session,
+            sftp,
+            remote_folder: config.remote_folder.clone(),
+        })
+    }
+}
+
+impl ExportDestination for SftpDestination {
+    fn write(&mut self, output_path: &Path, bytes: &[u8]) -> Result<String, String> {
+        let remote_path = remote_file_path(&self.remote_folder, output_path)?;
+        let mut file = self
+            .sftp
+            .create(Path::new(&remote_path))
+            .map_err(|e| format!("Failed to create {}: {}", remote_path, e))?;
+        file.write_all(bytes).map_err(|e| format!("Failed to upload {}: {}", remote_path, e))?;
+        Ok(remote_path)
+    }
+
+    fn exists(&mut self, output_path: &Path) -> Result<bool, String> {
+        let remote_path = remote_file_path(&self.remote_folder, output_path)?;
+        Ok(self.sftp.stat(Path::new(&remote_path)).is_ok())
+    }
+}
+
+pub struct FtpDestination {
+    stream: FtpStream,
+    remote_folder: String,
+}
+
+impl FtpDestination {
+    fn connect(config: &RemoteDestination) -> Result<Self, String> {
+        let mut stream = FtpStream::connect((config.host.as_str(), config.port.unwrap_or(21)))
+            .map_err(|e| format!("Failed to connect to {}: {}", config.host, e))?;
+        stream
+            .login(&config.username, config.password.as_deref().unwrap_or(""))
+            .map_err(|e| format!("FTP authentication failed: {}", e))?;
+        stream.transfer_type(FileType::Binary).map_err(|e| e.to_string())?;
+        Ok(Self {
+            stream,
+            remote_folder: config.remote_folder.clone(),
+        })
+    }
+}
+
+impl ExportDestination for FtpDestination {
+    fn write(&mut self, output_path: &Path, bytes: &[u8]) -> Result<String, String> {
+        let remote_path = remote_file_path(&self.remote_folder, output_path)?;
+        self.stream
+            .put_file(&remote_path, &mut Cursor::new(bytes))
+            .map_err(|e| format!("Failed to upload {}: {}", remote_path, e))?;
+        Ok(remote_path)
+    }
+
+    fn exists(&mut self, output_path: &Path) -> Result<bool, String> {
+        let remote_path = remote_file_path(&self.remote_folder, output_path)?;
+        Ok(self.stream.size(&remote_path).is_ok())
+    }
+}
+
+impl Drop for FtpDestination {
+    fn drop(&mut self) {
+        let _ = self.stream.quit();
+    }
+}
+
+pub struct S3Destination {
+    bucket: Box<Bucket>,
+    key_template: String,
+}
+
+impl S3Destination {
+    fn connect(config: &RemoteDestination) -> Result<Self, String> {
+        let bucket_name = config
+            .bucket
+            .as_deref()
+            .filter(|b| !b.is_empty())
+            .ok_or_else(|| "S3 destination has no bucket configured.".to_string())?;
+        let region = Region::Custom {
+            region: config.region.clone().unwrap_or_else(|| "us-east-1".to_string()),
+            endpoint: config.host.clone(),
+        };
+        let credentials = Credentials::new(
+            Some(&config.username),
+            config.password.as_deref(),
+            None,
+            None,
+            None,
+        )
+        .map_err(|e| format!("Invalid S3 credentials: {}", e))?;
+
+        // Path-style addressing works with every S3-compatible service, not just AWS.
+        let bucket = Bucket::new(bucket_name, region, credentials)
+            .map_err(|e| format!("Failed to open S3 bucket: {}", e))?
+            .with_path_style();
+
+        Ok(Self {
+            bucket,
+            key_template: config.key_template.clone().unwrap_or_else(|| "{filename}".to_string()),
+        })
+    }
+
+    fn object_key(&self, output_path: &Path) -> Result<String, String> {
+        let file_name = remote_file_path("", output_path)?;
+        Ok(self
+            .key_template
+            .replace("{filename}", &file_name)
+            .replace("{date}", &Local::now().format("%Y-%m-%d").to_string())
+            .trim_start_matches('/')
+            .to_string())
+    }
+}
+
+impl ExportDestination for S3Destination {
+    fn write(&mut self, output_path: &Path, bytes: &[u8]) -> Result<String, String> {
+        let key = self.object_key(output_path)?;
+        let response = tauri::async_runtime::block_on(self.bucket.put_object(&key, bytes))
+            .map_err(|e| format!("Failed to upload {}: {}", key, e))?;
+        if !(200..300).contains(&response.status_code()) {
+            return Err(format!("Failed to upload {}: server returned {}", key, response.status_code()));
+        }
+        Ok(format!("s3://{}/{}", self.bucket.name(), key))
+    }
+
+    fn exists(&mut self, output_path: &Path) -> Result<bool, String> {
+        let key = self.object_key(output_path)?;
+        Ok(tauri::async_runtime::block_on(self.bucket.head_object(&key))
+            .map(|(_, status)| (200..300).contains(&status))
+            .unwrap_or(false))
+    }
+}
+
+pub fn open_destination(
+    settings: &AppSettings,
+    destination_id: Option<&str>,
+) -> Result<Box<dyn ExportDestination>, String> {
+    let Some(id) = destination_id else {
+        return Ok(Box::new(LocalDestination));
+    };
+
+    let config = settings
+        .remote_destinations
+        .as_ref()
+        .and_then(|destinations| destinations.iter().find(|d| d.id == id))
+        .ok_or_else(|| format!("Export destination '{}' is not configured.", id))?;
+
+    Ok(match config.protocol {
+        RemoteProtocol::Sftp => Box::new(SftpDestination::connect(config)?),
+        RemoteProtocol::Ftp => Box::new(FtpDestination::connect(config)?),
+        RemoteProtocol::S3 => Box::new(S3Destination::connect(config)?),
+    })
+}
@@ -0,0 +1,497 @@
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+use image::DynamicImage;
+use serde::Serialize;
+use serde_json::Value;
+use tauri::{AppHandle, Emitter, Manager};
+use uuid::Uuid;
+
+use crate::export_checkpoints;
+use crate::{AppState, ExportSettings, ExportTarget};
+
+pub enum ExportJobKind {
+    Single {
+        original_path: String,
+        output_path: String,
+        js_adjustments: Value,
+        image: DynamicImage,
+        additional_targets: Vec<ExportTarget>,
+    },
+    Batch {
+        output_folder: String,
+        paths: Vec<String>,
+        output_format: String,
+        // Set when the batch publishes changed images of a publish target.
+        publish_target_id: Option<String>,
+        // Images before this index were exported before the app was closed.
+        start_index: usize,
+    },
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportJobStatus {
+    Queued,
+    Running,
+    Paused,
+    Completed,
+    Failed,
+    Cancelled,
+}
+
+impl ExportJobStatus {
+    fn is_finished(self) -> bool {
+        matches!(self, Self::Completed | Self::Failed | Self::Cancelled)
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ExportStage {
+    Decode,
+    Process,
+    Encode,
+    Write,
+}
+
+pub struct ExportJob {
+    id: String,
+    kind: Arc<ExportJobKind>,
+    export_settings: Arc<ExportSettings>,
+    status: ExportJobStatus,
+    current: usize,
+    total: usize,
+    current_path: Option<String>,
+    stage: Option<ExportStage>,
+    eta_seconds: Option<u64>,
+    error: Option<String>,
+    // Images that had nothing to export, e.g. because they lack the selected version.
+    skipped_paths: Vec<String>,
+    cancel_flag: Arc<AtomicBool>,
+    pause_flag: Arc<AtomicBool>,
+    // The status a paused job returns to when resumed.
+    paused_from: Option<ExportJobStatus>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportJobSummary {
+    id: String,
+    kind: String,
+    label: String,
+    status: ExportJobStatus,
+    current: usize,
+    total: usize,
+    current_path: Option<String>,
+    stage: Option<ExportStage>,
+    eta_seconds: Option<u64>,
+    error: Option<String>,
+    skipped_paths: Vec<String>,
+}
+
+impl ExportJob {
+    fn summary(&self) -> ExportJobSummary {
+        let (kind, label) = match &*self.kind {
+            ExportJobKind::Single { output_path, .. } => ("single", output_path.clone()),
+            ExportJobKind::Batch { output_folder, paths, publish_target_id: Some(_), .. } => {
+                ("publish", format!("Publishing {} images to {}", paths.len(), output_folder))
+            }
+            ExportJobKind::Batch { output_folder, paths, .. } => {
+                ("batch", format!("{} images to {}", paths.len(), output_folder))
+            }
+        };
+        ExportJobSummary {
+            id: self.id.clone(),
+            kind: kind.to_string(),
+            label,
+            status: self.status,
+            current: self.current,
+            total: self.total,
+            current_path: self.current_path.clone(),
+            stage: self.stage,
+            eta_seconds: self.eta_seconds,
+            error: self.error.clone(),
+            skipped_paths: self.skipped_paths.clone(),
+        }
+    }
+}
+
+#[derive(Default)]
+pub struct ExportQueue {
+    jobs: Vec<ExportJob>,
+}
+
+impl ExportQueue {
+    fn find_mut(&mut self, job_id: &str) -> Option<&mut ExportJob> {
+        self.jobs.iter_mut().find(|j| j.id == job_id)
+    }
+
+    pub fn contains_job(&self, job_id: &str) -> bool {
+        self.jobs.iter().any(|j| j.id == job_id)
+    }
+
+    // A running job that was paused still holds its slot, so nothing else starts meanwhile.
+    fn has_active_job(&self) -> bool {
+        self.jobs.iter().any(|j| {
+            j.status == ExportJobStatus::Running || j.paused_from == Some(ExportJobStatus::Running)
+        })
+    }
+}
+
+// Handed to the export routines so they can report progress and notice cancellation
+// without knowing about the queue itself.
+#[derive(Clone)]
+pub struct ExportJobHandle {
+    app_handle: AppHandle,
+    job_id: String,
+    cancel_flag: Arc<AtomicBool>,
+    pause_flag: Arc<AtomicBool>,
+    paused_millis: Arc<AtomicU64>,
+    started_at: Instant,
+    // Images a resumed job skips; they do not count towards the time estimate.
+    resumed_from: usize,
+}
+
+impl ExportJobHandle {
+    pub fn app_handle(&self) -> &AppHandle {
+        &self.app_handle
+    }
+
+    pub fn is_cancelled(&self) -> bool {
+        self.cancel_flag.load(Ordering::SeqCst)
+    }
+
+    // Called by the export routines between images and stages. Blocks while the job is paused
+    // and returns whether the job should stop.
+    pub fn checkpoint(&self) -> bool {
+        if self.pause_flag.load(Ordering::SeqCst) {
+            let paused_at = Instant::now();
+            while self.pause_flag.load(Ordering::SeqCst) && !self.is_cancelled() {
+                thread::sleep(Duration::from_millis(200));
+            }
+            self.paused_millis
+                .fetch_add(paused_at.elapsed().as_millis() as u64, Ordering::SeqCst);
+        }
+        self.is_cancelled()
+    }
+
+    // The estimate is based on fully finished images only; stage timings differ too much
+    // between RAW and non-RAW sources to be a useful predictor on their own.
+    fn estimate_remaining(&self, current: usize, total: usize) -> Option<u64> {
+        let done = current.saturating_sub(self.resumed_from);
+        if done == 0 || current >= total {
+            return None;
+        }
+        let paused = Duration::from_millis(self.paused_millis.load(Ordering::SeqCst));
+        let active = self.started_at.elapsed().saturating_sub(paused);
+        let per_image = active.as_secs_f64() / done as f64;
+        Some((per_image * (total - current) as f64).round() as u64)
+    }
+
+    // Records on disk that every image up to `index` is exported, so the job can be resumed
+    // from the next one after a crash.
+    pub fn image_finished(&self, index: usize) {
+        export_checkpoints::advance(&self.app_handle, &self.job_id, index + 1);
+    }
+
+    // Records an image that was passed over without exporting anything and warns about it, so
+    // a batch that finishes does not look as if every image was exported.
+    pub fn image_skipped(&self, index: usize, path: &str, reason: &str) {
+        let summary = update_job(&self.app_handle, &self.job_id, |job| {
+            job.skipped_paths.push(path.to_string());
+        });
+        if let Some(summary) = summary {
+            let _ = self.app_handle.emit("export-job-update", summary);
+        }
+        let _ = self.app_handle.emit(
+            "export-warning",
+            serde_json::json!({ "jobId": self.job_id, "path": path, "reason": reason }),
+        );
+        self.image_finished(index);
+    }
+
+    pub fn report_progress(&self, current: usize, total: usize, path: &str) {
+        self.report(current, total, path, None);
+    }
+
+    pub fn report_stage(&self, current: usize, total: usize, path: &str, stage: ExportStage) {
+        self.report(current, total, path, Some(stage));
+    }
+
+    fn report(&self, current: usize, total: usize, path: &str, stage: Option<ExportStage>) {
+        let eta_seconds = self.estimate_remaining(current, total);
+        let summary = update_job(&self.app_handle, &self.job_id, |job| {
+            job.current = current;
+            job.total = total;
+            job.current_path = (!path.is_empty()).then(|| path.to_string());
+            job.stage = stage;
+            job.eta_seconds = eta_seconds;
+        });
+        if let Some(summary) = summary {
+            let _ = self.app_handle.emit("export-job-update", summary);
+        }
+        let _ = self.app_handle.emit(
+            "batch-export-progress",
+            serde_json::json!({
+                "current": current,
+                "total": total,
+                "path": path,
+                "jobId": self.job_id,
+                "stage": stage,
+                "etaSeconds": eta_seconds,
+            }),
+        );
+    }
+}
+
+fn update_job(
+    app_handle: &AppHandle,
+    job_id: &str,
+    update: impl FnOnce(&mut ExportJob),
+) -> Option<ExportJobSummary> {
+    let state = app_handle.state::<AppState>();
+    let mut queue = state.export_queue.lock().unwrap();
+    let job = queue.find_mut(job_id)?;
+    update(job);
+    Some(job.summary())
+}
+
+pub fn enqueue_export_job(
+    app_handle: &AppHandle,
+    kind: ExportJobKind,
+    export_settings: ExportSettings,
+) -> String {
+    let (total, start_index) = match &kind {
+        ExportJobKind::Single { .. } => (1, 0),
+        ExportJobKind::Batch { paths, start_index, .. } => (paths.len(), *start_index),
+    };
+    let job = ExportJob {
+        id: Uuid::new_v4().to_string(),
+        kind: Arc::new(kind),
+        export_settings: Arc::new(export_settings),
+        status: ExportJobStatus::Queued,
+        current: start_index,
+        total,
+        current_path: None,
+        stage: None,
+        eta_seconds: None,
+        error: None,
+        skipped_paths: Vec::new(),
+        cancel_flag: Arc::new(AtomicBool::new(false)),
+        pause_flag: Arc::new(AtomicBool::new(false)),
+        paused_from: None,
+    };
+    let job_id = job.id.clone();
+    let summary = job.summary();
+    export_checkpoints::create(app_handle, &job_id, &job.kind, &job.export_settings);
+
+    app_handle.state::<AppState>().export_queue.lock().unwrap().jobs.push(job);
+    let _ = app_handle.emit("export-job-update", summary);
+
+    start_next_job(app_handle);
+    job_id
+}
+
+fn start_next_job(app_handle: &AppHandle) {
+    let state = app_handle.state::<AppState>();
+    let mut queue = state.export_queue.lock().unwrap();
+    if queue.has_active_job() {
+        return;
+    }
+    let Some(job) = queue.jobs.iter_mut().find(|j| j.status == ExportJobStatus::Queued) else {
+        return;
+    };
+
+    job.status = ExportJobStatus::Running;
+    let kind = job.kind.clone();
+    let export_settings = job.export_settings.clone();
+    let resumed_from = match &*kind {
+        ExportJobKind::Batch { start_index, .. } => *start_index,
+        ExportJobKind::Single { .. } => 0,
+    };
+    let handle = ExportJobHandle {
+        app_handle: app_handle.clone(),
+        job_id: job.id.clone(),
+        cancel_flag: job.cancel_flag.clone(),
+        pause_flag: job.pause_flag.clone(),
+        paused_millis: Arc::new(AtomicU64::new(0)),
+        started_at: Instant::now(),
+        resumed_from,
+    };
+    let summary = job.summary();
+    drop(queue);
+
+    let _ = app_handle.emit("export-job-update", summary);
+
+    tauri::async_runtime::spawn_blocking(move || {
+        let result = crate::run_export_job(&handle.app_handle, &kind, &export_settings, &handle);
+        finish_job(&handle, result);
+        start_next_job(&handle.app_handle);
+    });
+}
+
+fn finish_job(handle: &ExportJobHandle, result: Result<(), String>) {
+    let cancelled = handle.is_cancelled();
+    export_checkpoints::remove(&handle.app_handle, &handle.job_id);
+    let summary = update_job(&handle.app_handle, &handle.job_id, |job| {
+        job.current_path = None;
+        job.stage = None;
+        job.eta_seconds = None;
+        job.paused_from = None;
+        job.pause_flag.store(false, Ordering::SeqCst);
+        match (&result, cancelled) {
+            (_, true) => job.status = ExportJobStatus::Cancelled,
+            (Ok(()), false) => {
+                job.status = ExportJobStatus::Completed;
+                job.current = job.total;
+            }
+            (Err(e), false) => {
+                job.status = ExportJobStatus::Failed;
+                job.error = Some(e.clone());
+            }
+        }
+    });
+    if let Some(summary) = summary {
+        let _ = handle.app_handle.emit("export-job-update", summary);
+    }
+
+    match (result, cancelled) {
+        (_, true) => {
+            let _ = handle.app_handle.emit("export-cancelled", ());
+        }
+        (Ok(()), false) => {
+            let _ = handle.app_handle.emit("export-complete", ());
+        }
+        (Err(e), false) => {
+            eprintln!("Export job {} failed: {}", handle.job_id, e);
+            let _ = handle.app_handle.emit("export-error", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn get_export_queue(state: tauri::State<AppState>) -> Result<Vec<ExportJobSummary>, String> {
+    let queue = state.export_queue.lock().unwrap();
+    Ok(queue.jobs.iter().map(ExportJob::summary).collect())
+}
+
+// Without a job id every queued and running job is cancelled, which matches the old
+// single-export behaviour of the cancel button.
+#[tauri::command]
+pub fn cancel_export(job_id: Option<String>, app_handle: AppHandle) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let mut updates = Vec::new();
+    {
+        let mut queue = state.export_queue.lock().unwrap();
+        let targets: Vec<&mut ExportJob> = queue
+            .jobs
+            .iter_mut()
+            .filter(|j| !j.status.is_finished())
+            .filter(|j| job_id.as_ref().map_or(true, |id| &j.id == id))
+            .collect();
+
+        if targets.is_empty() {
+            return Err("No matching export job is queued or running.".to_string());
+        }
+
+        for job in targets {
+            job.cancel_flag.store(true, Ordering::SeqCst);
+            // Running jobs stop at their next checkpoint and report the cancellation themselves.
+            let was_queued = job.status == ExportJobStatus::Queued
+                || job.paused_from == Some(ExportJobStatus::Queued);
+            if was_queued {
+                job.status = ExportJobStatus::Cancelled;
+                job.paused_from = None;
+                updates.push(job.summary());
+            }
+        }
+    }
+
+    for summary in &updates {
+        export_checkpoints::remove(&app_handle, &summary.id);
+    }
+    for summary in updates {
+        let _ = app_handle.emit("export-job-update", summary);
+    }
+    Ok(())
+}
+
+fn set_paused(app_handle: &AppHandle, job_id: Option<String>, paused: bool) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let mut updates = Vec::new();
+    {
+        let mut queue = state.export_queue.lock().unwrap();
+        for job in queue
+            .jobs
+            .iter_mut()
+            .filter(|j| job_id.as_ref().map_or(true, |id| &j.id == id))
+        {
+            match (paused, job.status) {
+                (true, ExportJobStatus::Queued | ExportJobStatus::Running) => {
+                    job.paused_from = Some(job.status);
+                    job.status = ExportJobStatus::Paused;
+                }
+                (false, ExportJobStatus::Paused) => {
+                    job.status = job.paused_from.take().unwrap_or(ExportJobStatus::Queued);
+                }
+                _ => continue,
+            }
+            job.pause_flag.store(paused, Ordering::SeqCst);
+            updates.push(job.summary());
+        }
+    }
+
+    if updates.is_empty() {
+        return Err(if paused {
+            "No matching export job is queued or running.".to_string()
+        } else {
+            "No matching export job is paused.".to_string()
+        });
+    }
+    for summary in updates {
+        let _ = app_handle.emit("export-job-update", summary);
+    }
+    Ok(())
+}
+
+// A running job finishes the image it is working on and then waits; queued jobs are held back until
+// resumed. Without a job id the whole queue is paused or resumed.
+#[tauri::command]
+pub fn pause_export(job_id: Option<String>, app_handle: AppHandle) -> Result<(), String> {
+    set_paused(&app_handle, job_id, true)
+}
+
+#[tauri::command]
+pub fn resume_export(job_id: Option<String>, app_handle: AppHandle) -> Result<(), String> {
+    set_paused(&app_handle, job_id, false)?;
+    start_next_job(&app_handle);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn reorder_export_job(job_id: String, new_index: usize, state: tauri::State<AppState>) -> Result<(), String> {
+    let mut queue = state.export_queue.lock().unwrap();
+    let current_index = queue
+        .jobs
+        .iter()
+        .position(|j| j.id == job_id)
+        .ok_or_else(|| "Export job not found.".to_string())?;
+
+    if queue.jobs[current_index].status != ExportJobStatus::Queued {
+        return Err("Only queued export jobs can be reordered.".to_string());
+    }
+
+    let job = queue.jobs.remove(current_index);
+    let new_index = new_index.min(queue.jobs.len());
+    queue.jobs.insert(new_index, job);
+    Ok(())
+}
+
+#[tauri::command]
+pub fn clear_finished_export_jobs(state: tauri::State<AppState>) -> Result<(), String> {
+    state.export_queue.lock().unwrap().jobs.retain(|j| !j.status.is_finished());
+    Ok(())
+}
@@ -19,14 +19,26 @@ use uuid::Uuid;
 use walkdir::WalkDir;
 
 use crate::gpu_processing;
-use crate::formats::is_supported_image_file;
+use crate::formats::{is_raw_file, is_supported_image_file};
 use crate::image_processing::GpuContext;
 use crate::image_loader;
+use crate::lens_correction;
+use crate::lut;
+use crate::proofing::{self, ProofingSettings};
+use crate::raw_processing::{self, DemosaicQuality, DevelopOverrides, FastRawResolution, HighlightRecovery, RawCropOverride};
 use crate::image_processing::{
     apply_crop, apply_flip, apply_rotation, auto_results_to_json, get_all_adjustments_from_json,
-    perform_auto_analysis, Crop, ImageMetadata,
+    perform_auto_analysis, Crop, ImageMetadata, ImageVersion,
 };
 use crate::mask_generation::{generate_mask_bitmap, MaskDefinition};
+use crate::metadata_writeback;
+use crate::darktable;
+use crate::stacks;
+use crate::export_destinations::RemoteDestination;
+use crate::power::{self, LowPowerMode};
+use crate::backup::BackupSettings;
+use crate::audit_log::{self, AuditOperation};
+use crate::performance::{self, Cache};
 use crate::AppState;
 
 const THUMBNAIL_WIDTH: u32 = 640;
@@ -88,6 +100,17 @@ pub struct LastFolderState {
     pub expanded_folders: Vec<String>,
 }
 
+// `export_settings` mirrors the payload of the export commands (quality, resize, metadata,
+// filename template, ...) so presets keep working as export options grow.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportPreset {
+    pub id: String,
+    pub name: String,
+    pub file_format: String,
+    pub export_settings: Value,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 pub struct AppSettings {
@@ -102,6 +125,27 @@ pub struct AppSettings {
     pub last_folder_state: Option<LastFolderState>,
     pub adaptive_editor_theme: Option<bool>,
     pub ui_visibility: Option<Value>,
+    pub write_back_metadata: Option<bool>,
+    pub culling_mode: Option<bool>,
+    pub export_presets: Option<Vec<ExportPreset>>,
+    pub notes_to_xmp_description: Option<bool>,
+    pub drag_export_preset_id: Option<String>,
+    pub remote_destinations: Option<Vec<RemoteDestination>>,
+    pub max_preview_resolution: Option<u32>,
+    pub low_power_mode: Option<LowPowerMode>,
+    pub backup: Option<BackupSettings>,
+    pub safe_mode: Option<bool>,
+    pub cache_size_limit_mb: Option<u64>,
+    pub demosaic_quality: Option<DemosaicQuality>,
+    pub fast_raw_resolution: Option<FastRawResolution>,
+    pub match_picture_style: Option<bool>,
+    pub highlight_recovery: Option<HighlightRecovery>,
+    pub highlight_rolloff: Option<bool>,
+    pub ca_correction: Option<bool>,
+    pub raw_crop_overrides: Option<Vec<RawCropOverride>>,
+    pub proofing: Option<ProofingSettings>,
+    pub performance_overlay: Option<bool>,
+    pub embedded_preview_first: Option<bool>,
 }
 
 impl Default for AppSettings {
@@ -121,6 +165,27 @@ impl Default for AppSettings {
             last_folder_state: None,
             adaptive_editor_theme: Some(false),
             ui_visibility: None,
+            write_back_metadata: Some(false),
+            culling_mode: Some(false),
+            export_presets: None,
+            notes_to_xmp_description: Some(false),
+            drag_export_preset_id: None,
+            remote_destinations: None,
+            max_preview_resolution: Some(5120),
+            low_power_mode: Some(LowPowerMode::Auto),
+            backup: None,
+            safe_mode: Some(false),
+            cache_size_limit_mb: None,
+            demosaic_quality: Some(DemosaicQuality::Standard),
+            fast_raw_resolution: Some(FastRawResolution::Quarter),
+            match_picture_style: Some(false),
+            highlight_recovery: Some(HighlightRecovery::Blend),
+            highlight_rolloff: Some(true),
+            ca_correction: Some(true),
+            raw_crop_overrides: None,
+            proofing: None,
+            performance_overlay: Some(false),
+            embedded_preview_first: Some(true),
         }
     }
 }
@@ -130,29 +195,101 @@ pub struct ImageFile {
     path: String,
     modified: u64,
     is_edited: bool,
+    has_masks: bool,
+    has_ai_patches: bool,
+    has_generative_content: bool,
+    stack_id: Option<String>,
+    stack_size: usize,
+    is_stack_cover: bool,
+}
+
+#[derive(Debug, Default, Clone, Copy)]
+struct EditStatus {
+    is_edited: bool,
+    has_masks: bool,
+    has_ai_patches: bool,
+    has_generative_content: bool,
 }
 
-fn has_sidecar_adjustments(image_path: &str) -> bool {
+// Each flag is optional: `Some(true)` keeps only matching images, `Some(false)` excludes them.
+#[derive(Deserialize, Debug, Default, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct EditStatusFilter {
+    pub is_edited: Option<bool>,
+    pub has_masks: Option<bool>,
+    pub has_ai_patches: Option<bool>,
+    pub has_generative_content: Option<bool>,
+}
+
+impl EditStatusFilter {
+    fn matches(&self, image: &ImageFile) -> bool {
+        let check = |wanted: Option<bool>, actual: bool| wanted.map_or(true, |w| w == actual);
+        check(self.is_edited, image.is_edited)
+            && check(self.has_masks, image.has_masks)
+            && check(self.has_ai_patches, image.has_ai_patches)
+            && check(self.has_generative_content, image.has_generative_content)
+    }
+}
+
+fn read_edit_status(image_path: &str) -> EditStatus {
     let sidecar_path = get_sidecar_path(image_path);
     if !sidecar_path.exists() {
-        return false;
+        return EditStatus::default();
     }
 
-    if let Ok(content) = fs::read_to_string(sidecar_path) {
-        if let Ok(value) = serde_json::from_str::<serde_json::Value>(&content) {
-            if let Some(adjustments) = value.get("adjustments").and_then(|a| a.as_object()) {
-                return adjustments.keys().len() > 1
-                    || (adjustments.keys().len() == 1 && !adjustments.contains_key("rating"));
-            }
-        }
-    }
+    let Some(adjustments) = fs::read_to_string(sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str::<Value>(&content).ok())
+        .and_then(|value| value.get("adjustments").cloned())
+    else {
+        return EditStatus::default();
+    };
+    let Some(adjustments_obj) = adjustments.as_object() else {
+        return EditStatus::default();
+    };
 
-    false
+    let is_edited = adjustments_obj.keys().len() > 1
+        || (adjustments_obj.keys().len() == 1 && !adjustments_obj.contains_key("rating"));
+
+    let has_masks = adjustments_obj
+        .get("masks")
+        .and_then(|m| m.as_array())
+        .map_or(false, |masks| !masks.is_empty());
+
+    let patches: Vec<&Value> = adjustments_obj
+        .get("aiPatches")
+        .and_then(|p| p.as_array())
+        .map(|arr| {
+            arr.iter()
+                .filter(|p| p.get("patchDataBase64").and_then(|d| d.as_str()).map_or(false, |d| !d.is_empty()))
+                .collect()
+        })
+        .unwrap_or_default();
+
+    // Patches created from a text prompt are generated content; prompt-less ones are plain AI removals.
+    let has_generative_content = patches
+        .iter()
+        .any(|p| p.get("prompt").and_then(|v| v.as_str()).map_or(false, |v| !v.trim().is_empty()));
+
+    EditStatus {
+        is_edited,
+        has_masks,
+        has_ai_patches: !patches.is_empty(),
+        has_generative_content,
+    }
 }
 
 #[tauri::command]
-pub fn list_images_in_dir(path: String) -> Result<Vec<ImageFile>, String> {
-    let entries: Vec<ImageFile> = fs::read_dir(path)
+pub fn list_images_in_dir(
+    path: String,
+    collapse_stacks: Option<bool>,
+    filter: Option<EditStatusFilter>,
+) -> Result<Vec<ImageFile>, String> {
+    let stack_file = stacks::load_stacks(Path::new(&path));
+    let collapse_stacks = collapse_stacks.unwrap_or(false);
+    let filter = filter.unwrap_or_default();
+
+    let entries: Vec<ImageFile> = fs::read_dir(&path)
         .map_err(|e| e.to_string())?
         .filter_map(std::result::Result::ok)
         .map(|entry| entry.path())
@@ -171,13 +308,23 @@ pub fn list_images_in_dir(path: String) -> Result<Vec<ImageFile>, String> {
                 .and_then(|t| t.duration_since(std::time::UNIX_EPOCH).ok())
                 .map(|d| d.as_secs())
                 .unwrap_or(0);
-            let is_edited = has_sidecar_adjustments(&path.to_string_lossy().into_owned());
+            let status = read_edit_status(&path.to_string_lossy());
+            let file_name = path.file_name().unwrap_or_default().to_string_lossy().into_owned();
+            let stack = stacks::find_stack_for(&stack_file, &file_name);
             ImageFile {
                 path: path.to_string_lossy().into_owned(),
                 modified,
-                is_edited,
+                is_edited: status.is_edited,
+                has_masks: status.has_masks,
+                has_ai_patches: status.has_ai_patches,
+                has_generative_content: status.has_generative_content,
+                stack_id: stack.map(|s| s.id.clone()),
+                stack_size: stack.map_or(0, |s| s.members.len()),
+                is_stack_cover: stack.map_or(false, |s| s.cover == file_name),
             }
         })
+        .filter(|image| !collapse_stacks || image.stack_id.is_none() || image.is_stack_cover)
+        .filter(|image| filter.matches(image))
         .collect();
     Ok(entries)
 }
@@ -299,8 +446,11 @@ pub fn generate_thumbnail_data(
                 .as_bool()
                 .unwrap_or(false);
             let flip_vertical = meta.adjustments["flipVertical"].as_bool().unwrap_or(false);
+            let lens_distortion = meta.adjustments["lensDistortion"].as_f64().unwrap_or(0.0) as f32;
 
-            let flipped_image = apply_flip(processing_base, flip_horizontal, flip_vertical);
+            let undistorted_base =
+                lens_correction::apply_manual_distortion(processing_base, lens_distortion / 100.0);
+            let flipped_image = apply_flip(undistorted_base, flip_horizontal, flip_vertical);
             let rotated_image = apply_rotation(&flipped_image, rotation_degrees);
 
             let crop_data: Option<Crop> =
@@ -345,12 +495,14 @@ pub fn generate_thumbnail_data(
                 .collect();
 
             let gpu_adjustments = get_all_adjustments_from_json(&meta.adjustments);
+            let lut = lut::lut_for(&meta.adjustments);
 
             if let Ok(processed_image) = gpu_processing::process_and_get_dynamic_image(
                 context,
                 &cropped_preview,
                 gpu_adjustments,
                 &mask_bitmaps,
+                lut.as_deref(),
             ) {
                 return Ok(processed_image);
             } else {
@@ -362,6 +514,11 @@ pub fn generate_thumbnail_data(
     Ok(base_image)
 }
 
+fn generate_embedded_preview_thumbnail(path_str: &str) -> anyhow::Result<DynamicImage> {
+    let file_bytes = fs::read(path_str)?;
+    raw_processing::extract_embedded_preview(&file_bytes)
+}
+
 fn encode_thumbnail(image: &DynamicImage) -> Result<Vec<u8>> {
     let thumbnail = image.thumbnail(THUMBNAIL_WIDTH, THUMBNAIL_WIDTH);
     let mut buf = Cursor::new(Vec::new());
@@ -370,25 +527,62 @@ fn encode_thumbnail(image: &DynamicImage) -> Result<Vec<u8>> {
     Ok(buf.into_inner())
 }
 
+pub fn get_thumbnail_cache_dir(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let cache_dir = app_handle
+        .path()
+        .app_cache_dir()
+        .map_err(|e| e.to_string())?;
+    let thumb_cache_dir = cache_dir.join("thumbnails");
+    if !thumb_cache_dir.exists() {
+        fs::create_dir_all(&thumb_cache_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(thumb_cache_dir)
+}
+
+// Any change to the image or its sidecar yields a new name, so stale entries are never read
+// back; they are left for the cache manager to prune.
+pub fn thumbnail_cache_filename(path_str: &str, img_mod_time: u64, sidecar_mod_time: u64, embedded: bool) -> String {
+    let mut hasher = blake3::Hasher::new();
+    hasher.update(path_str.as_bytes());
+    hasher.update(&img_mod_time.to_le_bytes());
+    hasher.update(&sidecar_mod_time.to_le_bytes());
+    if embedded {
+        hasher.update(b"embedded");
+    }
+    format!("{}.jpg", hasher.finalize().to_hex())
+}
+
+// In low-power mode thumbnails are generated one at a time instead of on every core, and
+// RAW files use their embedded previews, so browsing a folder does not spin up the CPU and GPU.
+fn thumbnail_thread_pool(low_power: bool) -> Result<rayon::ThreadPool, String> {
+    rayon::ThreadPoolBuilder::new()
+        .num_threads(if low_power { 1 } else { 0 })
+        .build()
+        .map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub async fn generate_thumbnails(
     paths: Vec<String>,
     app_handle: tauri::AppHandle,
 ) -> Result<HashMap<String, String>, String> {
     tauri::async_runtime::spawn_blocking(move || {
-        let cache_dir = app_handle
-            .path()
-            .app_cache_dir()
-            .map_err(|e| e.to_string())?;
-        let thumb_cache_dir = cache_dir.join("thumbnails");
-        if !thumb_cache_dir.exists() {
-            fs::create_dir_all(&thumb_cache_dir).map_err(|e| e.to_string())?;
-        }
+        let thumb_cache_dir = get_thumbnail_cache_dir(&app_handle)?;
+
+        let settings = load_settings(app_handle.clone()).unwrap_or_default();
+        let culling_mode = settings.culling_mode.unwrap_or(false);
+        let low_power = power::is_low_power_active(&settings);
+        let fast_thumbnails = culling_mode || low_power;
 
         let state = app_handle.state::<AppState>();
-        let gpu_context = gpu_processing::get_or_init_gpu_context(&state).ok();
+        let gpu_context = if fast_thumbnails {
+            None
+        } else {
+            gpu_processing::get_or_init_gpu_context(&state).ok()
+        };
 
-        let thumbnails: HashMap<String, String> = paths
+        let pool = thumbnail_thread_pool(low_power)?;
+        let thumbnails: HashMap<String, String> = pool.install(|| paths
             .par_iter()
             .filter_map(|path_str| {
                 let original_path = Path::new(path_str);
@@ -406,26 +600,29 @@ pub async fn generate_thumbnails(
                     .and_then(|m| m.modified().ok())
                     .map(|t| t.duration_since(std::time::UNIX_EPOCH).unwrap().as_secs())
                     .unwrap_or(0);
+                let use_embedded_preview = fast_thumbnails && is_raw_file(path_str);
 
-                let mut hasher = blake3::Hasher::new();
-                hasher.update(path_str.as_bytes());
-                hasher.update(&img_mod_time.to_le_bytes());
-                hasher.update(&sidecar_mod_time.to_le_bytes());
-                let hash = hasher.finalize();
-                let cache_filename = format!("{}.jpg", hash.to_hex());
+                let cache_filename =
+                    thumbnail_cache_filename(path_str, img_mod_time, sidecar_mod_time, use_embedded_preview);
                 let cache_path = thumb_cache_dir.join(cache_filename);
 
-                if cache_path.exists() {
-                    if let Ok(data) = fs::read(&cache_path) {
-                        let base64_str = general_purpose::STANDARD.encode(&data);
-                        return Some((
-                            path_str.clone(),
-                            format!("data:image/jpeg;base64,{}", base64_str),
-                        ));
-                    }
+                let cached = cache_path.exists().then(|| fs::read(&cache_path).ok()).flatten();
+                performance::record_cache(Cache::Thumbnail, cached.is_some());
+                if let Some(data) = cached {
+                    let base64_str = general_purpose::STANDARD.encode(&data);
+                    return Some((
+                        path_str.clone(),
+                        format!("data:image/jpeg;base64,{}", base64_str),
+                    ));
                 }
 
-                if let Ok(thumb_image) = generate_thumbnail_data(path_str, gpu_context.as_ref()) {
+                let thumb_result = if use_embedded_preview {
+                    generate_embedded_preview_thumbnail(path_str)
+                } else {
+                    generate_thumbnail_data(path_str, gpu_context.as_ref())
+                };
+
+                if let Ok(thumb_image) = thumb_result {
                     if let Ok(thumb_data) = encode_thumbnail(&thumb_image) {
                         let _ = fs::write(&cache_path, &thumb_data);
                         let base64_str = general_purpose::STANDARD.encode(&thumb_data);
@@ -437,7 +634,7 @@ pub async fn generate_thumbnails(
                 }
                 None
             })
-            .collect();
+            .collect());
 
         Ok(thumbnails)
     })
@@ -450,24 +647,28 @@ pub fn generate_thumbnails_progressive(
     paths: Vec<String>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    let cache_dir = app_handle
-        .path()
-        .app_cache_dir()
-        .map_err(|e| e.to_string())?;
-    let thumb_cache_dir = cache_dir.join("thumbnails");
-    if !thumb_cache_dir.exists() {
-        fs::create_dir_all(&thumb_cache_dir).map_err(|e| e.to_string())?;
-    }
+    let thumb_cache_dir = get_thumbnail_cache_dir(&app_handle)?;
 
     let app_handle_clone = app_handle.clone();
     let total_count = paths.len();
     let completed_count = Arc::new(AtomicUsize::new(0));
 
+    let settings = load_settings(app_handle.clone()).unwrap_or_default();
+    let culling_mode = settings.culling_mode.unwrap_or(false);
+    let low_power = power::is_low_power_active(&settings);
+    let fast_thumbnails = culling_mode || low_power;
+    let pool = thumbnail_thread_pool(low_power)?;
+
     thread::spawn(move || {
         let state = app_handle.state::<AppState>();
-        let gpu_context = gpu_processing::get_or_init_gpu_context(&state).ok();
+        let gpu_context = if fast_thumbnails {
+            None
+        } else {
+            gpu_processing::get_or_init_gpu_context(&state).ok()
+        };
 
-        paths.par_iter().for_each(|path_str| {
+        pool.install(|| paths.par_iter().for_each(|path_str| {
+            let use_embedded_preview = fast_thumbnails && is_raw_file(path_str);
             let result = (|| -> Option<(String, u8)> {
                 let original_path = Path::new(path_str);
                 let sidecar_path = get_sidecar_path(path_str);
@@ -497,22 +698,24 @@ pub fn generate_thumbnails_progressive(
                         (0, 0)
                     };
 
-                let mut hasher = blake3::Hasher::new();
-                hasher.update(path_str.as_bytes());
-                hasher.update(&img_mod_time.to_le_bytes());
-                hasher.update(&sidecar_mod_time.to_le_bytes());
-                let hash = hasher.finalize();
-                let cache_filename = format!("{}.jpg", hash.to_hex());
+                let cache_filename =
+                    thumbnail_cache_filename(path_str, img_mod_time, sidecar_mod_time, use_embedded_preview);
                 let cache_path = thumb_cache_dir.join(cache_filename);
 
-                if cache_path.exists() {
-                    if let Ok(data) = fs::read(&cache_path) {
-                        let base64_str = general_purpose::STANDARD.encode(&data);
-                        return Some((format!("data:image/jpeg;base64,{}", base64_str), rating));
-                    }
+                let cached = cache_path.exists().then(|| fs::read(&cache_path).ok()).flatten();
+                performance::record_cache(Cache::Thumbnail, cached.is_some());
+                if let Some(data) = cached {
+                    let base64_str = general_purpose::STANDARD.encode(&data);
+                    return Some((format!("data:image/jpeg;base64,{}", base64_str), rating));
                 }
 
-                if let Ok(thumb_image) = generate_thumbnail_data(path_str, gpu_context.as_ref()) {
+                let thumb_result = if use_embedded_preview {
+                    generate_embedded_preview_thumbnail(path_str)
+                } else {
+                    generate_thumbnail_data(path_str, gpu_context.as_ref())
+                };
+
+                if let Ok(thumb_image) = thumb_result {
                     if let Ok(thumb_data) = encode_thumbnail(&thumb_image) {
                         let _ = fs::write(&cache_path, &thumb_data);
                         let base64_str = general_purpose::STANDARD.encode(&thumb_data);
@@ -525,7 +728,7 @@ pub fn generate_thumbnails_progressive(
             if let Some((thumbnail_data, rating)) = result {
                 let _ = app_handle_clone.emit(
                     "thumbnail-generated",
-                    serde_json::json!({ "path": path_str, "data": thumbnail_data, "rating": rating }),
+                    serde_json::json!({ "path": path_str, "data": thumbnail_data, "rating": rating, "isEmbeddedPreview": use_embedded_preview }),
                 );
             }
 
@@ -534,7 +737,7 @@ pub fn generate_thumbnails_progressive(
                 "thumbnail-progress",
                 serde_json::json!({ "completed": completed, "total": total_count }),
             );
-        });
+        }));
 
         let _ = app_handle_clone.emit("thumbnail-generation-complete", true);
     });
@@ -564,7 +767,7 @@ pub fn create_folder(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn rename_folder(path: String, new_name: String) -> Result<(), String> {
+pub fn rename_folder(path: String, new_name: String, app_handle: AppHandle) -> Result<(), String> {
     let p = Path::new(&path);
     if !p.is_dir() {
         return Err("Path is not a directory.".to_string());
@@ -580,15 +783,25 @@ pub fn rename_folder(path: String, new_name: String) -> Result<(), String> {
             }
         }
         let new_path = parent.join(&new_name);
-        fs::rename(p, new_path).map_err(|e| e.to_string())
+        fs::rename(p, &new_path).map_err(|e| e.to_string())?;
+        audit_log::record(
+            &app_handle,
+            AuditOperation::RenameFolder,
+            &[path.clone()],
+            Some(&new_path.to_string_lossy()),
+            None,
+        );
+        Ok(())
     } else {
         Err("Could not determine parent directory.".to_string())
     }
 }
 
 #[tauri::command]
-pub fn delete_folder(path: String) -> Result<(), String> {
-    trash::delete(&path).map_err(|e| e.to_string())
+pub fn delete_folder(path: String, app_handle: AppHandle) -> Result<(), String> {
+    trash::delete(&path).map_err(|e| e.to_string())?;
+    audit_log::record(&app_handle, AuditOperation::DeleteFolder, &[path], None, None);
+    Ok(())
 }
 
 #[tauri::command]
@@ -674,7 +887,11 @@ pub fn copy_files(source_paths: Vec<String>, destination_folder: String) -> Resu
 }
 
 #[tauri::command]
-pub fn move_files(source_paths: Vec<String>, destination_folder: String) -> Result<(), String> {
+pub fn move_files(
+    source_paths: Vec<String>,
+    destination_folder: String,
+    app_handle: AppHandle,
+) -> Result<(), String> {
     let dest_path = Path::new(&destination_folder);
     if !dest_path.is_dir() {
         return Err(format!(
@@ -715,6 +932,13 @@ pub fn move_files(source_paths: Vec<String>, destination_folder: String) -> Resu
     trash::delete_all(&files_to_delete).map_err(|e| e.to_string())?;
     trash::delete_all(&sidecars_to_delete).map_err(|e| e.to_string())?;
 
+    audit_log::record(
+        &app_handle,
+        AuditOperation::MoveFiles,
+        &source_paths,
+        Some(&destination_folder),
+        None,
+    );
     Ok(())
 }
 
@@ -726,16 +950,28 @@ pub fn save_metadata_and_update_thumbnail(
 ) -> Result<(), String> {
     let sidecar_path = get_sidecar_path(&path);
 
+    let existing_metadata: ImageMetadata = fs::read_to_string(&sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let previous_metadata = existing_metadata.clone();
     let metadata = ImageMetadata {
         version: 1,
         rating: adjustments["rating"].as_u64().unwrap_or(0) as u8,
         adjustments,
+        ..existing_metadata
     };
 
     let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
     std::fs::write(sidecar_path, json_string).map_err(|e| e.to_string())?;
 
+    let write_back = metadata_writeback::write_back_fields_changed(&previous_metadata, &metadata);
+
     thread::spawn(move || {
+        if write_back {
+            metadata_writeback::write_back_to_originals(&app_handle, std::slice::from_ref(&path));
+        }
         let _ = app_handle.emit(
             "thumbnail-progress",
             serde_json::json!({ "completed": 0, "total": 1 }),
@@ -752,7 +988,7 @@ pub fn apply_adjustments_to_paths(
     adjustments: Value,
     app_handle: AppHandle,
 ) -> Result<(), String> {
-    paths.par_iter().for_each(|path| {
+    let changed_paths: Vec<String> = paths.par_iter().filter_map(|path| {
         let sidecar_path = get_sidecar_path(path);
 
         let existing_metadata: ImageMetadata = if sidecar_path.exists() {
@@ -763,6 +999,7 @@ pub fn apply_adjustments_to_paths(
         } else {
             ImageMetadata::default()
         };
+        let previous_rating = existing_metadata.rating;
 
         let mut new_adjustments = existing_metadata.adjustments;
         if new_adjustments.is_null() {
@@ -781,14 +1018,17 @@ pub fn apply_adjustments_to_paths(
             version: 1,
             rating: new_adjustments["rating"].as_u64().unwrap_or(0) as u8,
             adjustments: new_adjustments,
+            ..existing_metadata
         };
 
-        if let Ok(json_string) = serde_json::to_string_pretty(&metadata) {
-            let _ = std::fs::write(sidecar_path, json_string);
-        }
-    });
+        let json_string = serde_json::to_string_pretty(&metadata).ok()?;
+        std::fs::write(sidecar_path, json_string).ok()?;
+        // Pasting adjustments can only change the rating of the written-back fields.
+        (metadata.rating != previous_rating).then(|| path.clone())
+    }).collect();
 
     thread::spawn(move || {
+        metadata_writeback::write_back_to_originals(&app_handle, &changed_paths);
         let _ = generate_thumbnails_progressive(paths, app_handle);
     });
 
@@ -800,7 +1040,7 @@ pub fn reset_adjustments_for_paths(
     paths: Vec<String>,
     app_handle: AppHandle,
 ) -> Result<(), String> {
-    paths.par_iter().for_each(|path| {
+    let reset_paths: Vec<String> = paths.par_iter().filter_map(|path| {
         let sidecar_path = get_sidecar_path(path);
 
         let existing_metadata: ImageMetadata = if sidecar_path.exists() {
@@ -820,12 +1060,17 @@ pub fn reset_adjustments_for_paths(
             version: 1,
             rating: existing_metadata.rating,
             adjustments: new_adjustments,
+            ..existing_metadata
         };
 
-        if let Ok(json_string) = serde_json::to_string_pretty(&metadata) {
-            let _ = std::fs::write(sidecar_path, json_string);
-        }
-    });
+        let json_string = serde_json::to_string_pretty(&metadata).ok()?;
+        std::fs::write(sidecar_path, json_string).ok()?;
+        Some(path.clone())
+    }).collect();
+
+    if !reset_paths.is_empty() {
+        audit_log::record(&app_handle, AuditOperation::ResetAdjustments, &reset_paths, None, None);
+    }
 
     thread::spawn(move || {
         let _ = generate_thumbnails_progressive(paths, app_handle);
@@ -843,7 +1088,7 @@ pub fn apply_auto_adjustments_to_paths(
         let result: Result<(), String> = (|| {
             let file_bytes = fs::read(path).map_err(|e| e.to_string())?;
             let image =
-                image_loader::load_base_image_from_bytes(&file_bytes, path, false)
+                image_loader::load_base_image_from_bytes(&file_bytes, path, false, DevelopOverrides::default())
                     .map_err(|e| e.to_string())?;
 
             let auto_results = perform_auto_analysis(&image);
@@ -888,8 +1133,7 @@ pub fn apply_auto_adjustments_to_paths(
 
             let metadata = ImageMetadata {
                 version: 1,
-                rating: existing_metadata.rating,
-                adjustments: existing_metadata.adjustments,
+                ..existing_metadata
             };
             if let Ok(json_string) = serde_json::to_string_pretty(&metadata) {
                 let _ = std::fs::write(sidecar_path, json_string);
@@ -906,6 +1150,55 @@ pub fn apply_auto_adjustments_to_paths(
     Ok(())
 }
 
+#[tauri::command]
+pub fn save_image_version(
+    path: String,
+    name: String,
+    adjustments: Value,
+) -> Result<ImageVersion, String> {
+    let sidecar_path = get_sidecar_path(&path);
+    let mut metadata: ImageMetadata = if sidecar_path.exists() {
+        let file_content = fs::read_to_string(&sidecar_path).map_err(|e| e.to_string())?;
+        serde_json::from_str(&file_content).map_err(|e| e.to_string())?
+    } else {
+        ImageMetadata::default()
+    };
+
+    let version = match metadata.versions.iter_mut().find(|v| v.name == name) {
+        Some(existing) => {
+            existing.adjustments = adjustments;
+            existing.clone()
+        }
+        None => {
+            let version = ImageVersion {
+                id: Uuid::new_v4().to_string(),
+                name,
+                adjustments,
+            };
+            metadata.versions.push(version.clone());
+            version
+        }
+    };
+
+    let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    fs::write(sidecar_path, json_string).map_err(|e| e.to_string())?;
+    Ok(version)
+}
+
+#[tauri::command]
+pub fn delete_image_version(path: String, version_id: String) -> Result<(), String> {
+    let sidecar_path = get_sidecar_path(&path);
+    if !sidecar_path.exists() {
+        return Ok(());
+    }
+    let file_content = fs::read_to_string(&sidecar_path).map_err(|e| e.to_string())?;
+    let mut metadata: ImageMetadata = serde_json::from_str(&file_content).map_err(|e| e.to_string())?;
+    metadata.versions.retain(|v| v.id != version_id);
+
+    let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    fs::write(sidecar_path, json_string).map_err(|e| e.to_string())
+}
+
 #[tauri::command]
 pub fn load_metadata(path: String) -> Result<ImageMetadata, String> {
     let sidecar_path = get_sidecar_path(&path);
@@ -913,7 +1206,7 @@ pub fn load_metadata(path: String) -> Result<ImageMetadata, String> {
         let file_content = std::fs::read_to_string(sidecar_path).map_err(|e| e.to_string())?;
         serde_json::from_str(&file_content).map_err(|e| e.to_string())
     } else {
-        Ok(ImageMetadata::default())
+        Ok(darktable::read_darktable_sidecar(&path).unwrap_or_default())
     }
 }
 
@@ -973,11 +1266,63 @@ pub fn load_settings(app_handle: AppHandle) -> Result<AppSettings, String> {
 
 #[tauri::command]
 pub fn save_settings(settings: AppSettings, app_handle: AppHandle) -> Result<(), String> {
+    raw_processing::apply_develop_settings(&settings);
+    proofing::apply_proofing_settings(&settings);
     let path = get_settings_path(&app_handle)?;
     let json_string = serde_json::to_string_pretty(&settings).map_err(|e| e.to_string())?;
     fs::write(path, json_string).map_err(|e| e.to_string())
 }
 
+#[tauri::command]
+pub fn load_export_presets(app_handle: AppHandle) -> Result<Vec<ExportPreset>, String> {
+    let settings = load_settings(app_handle)?;
+    Ok(settings.export_presets.unwrap_or_default())
+}
+
+#[tauri::command]
+pub fn save_export_preset(
+    name: String,
+    file_format: String,
+    export_settings: Value,
+    app_handle: AppHandle,
+) -> Result<Vec<ExportPreset>, String> {
+    let name = name.trim().to_string();
+    if name.is_empty() {
+        return Err("Export preset name cannot be empty.".to_string());
+    }
+
+    let mut settings = load_settings(app_handle.clone())?;
+    let mut presets = settings.export_presets.take().unwrap_or_default();
+
+    // Saving under an existing name updates that preset in place.
+    if let Some(existing) = presets.iter_mut().find(|p| p.name == name) {
+        existing.file_format = file_format;
+        existing.export_settings = export_settings;
+    } else {
+        presets.push(ExportPreset {
+            id: Uuid::new_v4().to_string(),
+            name,
+            file_format,
+            export_settings,
+        });
+    }
+
+    settings.export_presets = Some(presets.clone());
+    save_settings(settings, app_handle)?;
+    Ok(presets)
+}
+
+#[tauri::command]
+pub fn delete_export_preset(id: String, app_handle: AppHandle) -> Result<Vec<ExportPreset>, String> {
+    let mut settings = load_settings(app_handle.clone())?;
+    let mut presets = settings.export_presets.take().unwrap_or_default();
+    presets.retain(|p| p.id != id);
+
+    settings.export_presets = Some(presets.clone());
+    save_settings(settings, app_handle)?;
+    Ok(presets)
+}
+
 #[tauri::command]
 pub fn handle_import_presets_from_file(
     file_path: String,
@@ -1046,13 +1391,13 @@ pub fn handle_export_presets_to_file(
 }
 
 #[tauri::command]
-pub fn clear_all_sidecars(root_path: String) -> Result<usize, String> {
+pub fn clear_all_sidecars(root_path: String, app_handle: AppHandle) -> Result<usize, String> {
     if !Path::new(&root_path).exists() {
         return Err(format!("Root path does not exist: {}", root_path));
     }
 
     let mut deleted_count = 0;
-    let walker = WalkDir::new(root_path).into_iter();
+    let walker = WalkDir::new(&root_path).into_iter();
 
     for entry in walker.filter_map(|e| e.ok()) {
         let path = entry.path();
@@ -1069,28 +1414,16 @@ pub fn clear_all_sidecars(root_path: String) -> Result<usize, String> {
         }
     }
 
+    audit_log::record(
+        &app_handle,
+        AuditOperation::ClearSidecars,
+        &[root_path],
+        None,
+        Some(format!("{} sidecar files deleted", deleted_count)),
+    );
     Ok(deleted_count)
 }
 
-#[tauri::command]
-pub fn clear_thumbnail_cache(app_handle: AppHandle) -> Result<(), String> {
-    let cache_dir = app_handle
-        .path()
-        .app_cache_dir()
-        .map_err(|e| e.to_string())?;
-    let thumb_cache_dir = cache_dir.join("thumbnails");
-
-    if thumb_cache_dir.exists() {
-        fs::remove_dir_all(&thumb_cache_dir)
-            .map_err(|e| format!("Failed to remove thumbnail cache: {}", e))?;
-    }
-
-    fs::create_dir_all(&thumb_cache_dir)
-        .map_err(|e| format!("Failed to recreate thumbnail cache directory: {}", e))?;
-
-    Ok(())
-}
-
 #[tauri::command]
 pub fn show_in_finder(path: String) -> Result<(), String> {
     #[cfg(target_os = "windows")]
@@ -1125,8 +1458,9 @@ pub fn show_in_finder(path: String) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn delete_files_from_disk(paths: Vec<String>) -> Result<(), String> {
+pub fn delete_files_from_disk(paths: Vec<String>, app_handle: AppHandle) -> Result<(), String> {
     trash::delete_all(&paths).map_err(|e| e.to_string())?;
+    audit_log::record(&app_handle, AuditOperation::DeleteFiles, &paths, None, None);
 
     for path in paths {
         let sidecar_path = get_sidecar_path(&path);
@@ -1139,7 +1473,7 @@ pub fn delete_files_from_disk(paths: Vec<String>) -> Result<(), String> {
 }
 
 #[tauri::command]
-pub fn delete_files_with_associated(paths: Vec<String>) -> Result<(), String> {
+pub fn delete_files_with_associated(paths: Vec<String>, app_handle: AppHandle) -> Result<(), String> {
     let mut files_to_delete = HashSet::new();
 
     for path_str in &paths {
@@ -1175,6 +1509,13 @@ pub fn delete_files_with_associated(paths: Vec<String>) -> Result<(), String> {
     }
 
     trash::delete_all(&final_paths_to_delete).map_err(|e| e.to_string())?;
+    audit_log::record(
+        &app_handle,
+        AuditOperation::DeleteFiles,
+        &final_paths_to_delete,
+        None,
+        Some("including associated files".to_string()),
+    );
 
     for path in final_paths_to_delete {
         let sidecar_path = get_sidecar_path(&path);
@@ -0,0 +1,165 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+
+use chrono::Local;
+use rawler::{decoders::RawMetadata, rawimage::RawImage};
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::dark_frames;
+use crate::raw_processing::read_raw_metadata;
+
+// A frame of an evenly lit target through the same lens and filters. The raw data of matching
+// images is divided by it to remove vignetting and the color cast of technical lenses.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct FlatField {
+    pub path: String,
+    pub camera_make: String,
+    pub camera_model: String,
+    pub lens_model: Option<String>,
+    pub aperture: Option<f32>,
+    pub created_at: String,
+}
+
+static FLAT_FIELDS: Mutex<Vec<FlatField>> = Mutex::new(Vec::new());
+// The last decoded flat field, shared by the images of a session.
+static DECODED: Mutex<Option<(String, Arc<RawImage>)>> = Mutex::new(None);
+
+fn get_flat_fields_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join("flat_fields.json"))
+}
+
+fn load_flat_fields(app_handle: &AppHandle) -> Result<Vec<FlatField>, String> {
+    let path = get_flat_fields_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save_flat_fields(app_handle: &AppHandle, fields: &[FlatField]) -> Result<(), String> {
+    let path = get_flat_fields_path(app_handle)?;
+    let json_string = serde_json::to_string_pretty(fields).map_err(|e| e.to_string())?;
+    fs::write(path, json_string).map_err(|e| e.to_string())?;
+    *FLAT_FIELDS.lock().unwrap() = fields.to_vec();
+    Ok(())
+}
+
+pub fn init(app_handle: &AppHandle) {
+    match load_flat_fields(app_handle) {
+        Ok(fields) => *FLAT_FIELDS.lock().unwrap() = fields,
+        Err(e) => eprintln!("Failed to load flat fields: {}", e),
+    }
+}
+
+fn lens_model_of(metadata: &RawMetadata) -> Option<String> {
+    metadata
+        .exif
+        .lens_model
+        .clone()
+        .filter(|m| !m.trim().is_empty())
+}
+
+fn aperture_of(metadata: &RawMetadata) -> Option<f32> {
+    metadata
+        .exif
+        .fnumber
+        .filter(|f| f.d != 0)
+        .map(|f| f.n as f32 / f.d as f32)
+}
+
+// The registered flat field for the same camera and lens, closest in aperture if there are
+// several. Manual lenses report no lens or aperture, those only match each other.
+fn find_match(metadata: &RawMetadata) -> Option<FlatField> {
+    let lens_model = lens_model_of(metadata);
+    let aperture = aperture_of(metadata);
+    let fields = FLAT_FIELDS.lock().unwrap();
+
+    fields
+        .iter()
+        .filter(|f| f.camera_make == metadata.make && f.camera_model == metadata.model && f.lens_model == lens_model)
+        .filter_map(|f| {
+            let distance = match (f.aperture, aperture) {
+                // Vignetting changes with every stop, so compare in stops.
+                (Some(flat), Some(image)) if flat > 0.0 && image > 0.0 => (flat / image).log2().abs(),
+                (None, None) => 0.0,
+                _ => return None,
+            };
+            Some((distance, f))
+        })
+        .min_by(|a, b| a.0.total_cmp(&b.0))
+        .map(|(_, f)| f.clone())
+}
+
+// Called while developing. Problems with a flat field are logged and the image is developed
+// without it, rather than failing to open.
+pub fn flat_field_for(raw_image: &RawImage, metadata: &RawMetadata) -> Option<Arc<RawImage>> {
+    let field = find_match(metadata)?;
+
+    let mut decoded = DECODED.lock().unwrap();
+    let flat = match decoded.as_ref() {
+        Some((path, flat)) if *path == field.path => flat.clone(),
+        _ => match dark_frames::decode(&field.path) {
+            Ok(flat) => {
+                let flat = Arc::new(flat);
+                *decoded = Some((field.path.clone(), flat.clone()));
+                flat
+            }
+            Err(e) => {
+                eprintln!("Failed to decode flat field {}: {}", field.path, e);
+                return None;
+            }
+        },
+    };
+
+    if flat.width != raw_image.width || flat.height != raw_image.height || flat.cpp != raw_image.cpp {
+        eprintln!("Flat field {} does not match the image size, skipping it", field.path);
+        return None;
+    }
+    Some(flat)
+}
+
+#[tauri::command]
+pub fn register_flat_field(path: String, app_handle: AppHandle) -> Result<FlatField, String> {
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let metadata = read_raw_metadata(&file_bytes).map_err(|e| format!("Not a RAW file: {}", e))?;
+    let field = FlatField {
+        path: path.clone(),
+        camera_make: metadata.make.clone(),
+        camera_model: metadata.model.clone(),
+        lens_model: lens_model_of(&metadata),
+        aperture: aperture_of(&metadata),
+        created_at: Local::now().to_rfc3339(),
+    };
+
+    let mut fields = load_flat_fields(&app_handle)?;
+    fields.retain(|f| f.path != path);
+    fields.push(field.clone());
+    save_flat_fields(&app_handle, &fields)?;
+    Ok(field)
+}
+
+#[tauri::command]
+pub fn list_flat_fields(app_handle: AppHandle) -> Result<Vec<FlatField>, String> {
+    load_flat_fields(&app_handle)
+}
+
+#[tauri::command]
+pub fn remove_flat_field(path: String, app_handle: AppHandle) -> Result<(), String> {
+    let mut fields = load_flat_fields(&app_handle)?;
+    fields.retain(|f| f.path != path);
+    save_flat_fields(&app_handle, &fields)?;
+
+    let mut decoded = DECODED.lock().unwrap();
+    if decoded.as_ref().is_some_and(|(p, _)| *p == path) {
+        *decoded = None;
+    }
+    Ok(())
+}
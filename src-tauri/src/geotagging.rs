@@ -0,0 +1,241 @@
+use std::fs;
+use std::io::BufReader;
+use std::path::Path;
+
+use chrono::{DateTime, Duration, NaiveDateTime, Utc};
+use little_exif::exif_tag::ExifTag;
+use little_exif::metadata::Metadata;
+use little_exif::rational::uR64;
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::file_management::get_sidecar_path;
+use crate::formats::is_raw_file;
+use crate::image_processing::{GpsCoordinates, ImageMetadata};
+
+const DEFAULT_MAX_GAP_SECONDS: i64 = 300;
+
+#[derive(Debug, Clone)]
+struct TrackPoint {
+    time: DateTime<Utc>,
+    latitude: f64,
+    longitude: f64,
+    altitude: Option<f64>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct GeotagResult {
+    pub path: String,
+    pub gps: Option<GpsCoordinates>,
+    pub exif_written: bool,
+    pub error: Option<String>,
+}
+
+fn parse_gpx(content: &str) -> Result<Vec<TrackPoint>, String> {
+    let doc = roxmltree::Document::parse(content).map_err(|e| format!("Invalid GPX file: {}", e))?;
+
+    let mut points: Vec<TrackPoint> = doc
+        .descendants()
+        .filter(|n| matches!(n.tag_name().name(), "trkpt" | "rtept" | "wpt"))
+        .filter_map(|n| {
+            let latitude = n.attribute("lat")?.parse::<f64>().ok()?;
+            let longitude = n.attribute("lon")?.parse::<f64>().ok()?;
+            let child_text = |name: &str| {
+                n.children()
+                    .find(|c| c.tag_name().name() == name)
+                    .and_then(|c| c.text())
+                    .map(|t| t.trim().to_string())
+            };
+            let time = DateTime::parse_from_rfc3339(&child_text("time")?).ok()?.with_timezone(&Utc);
+            let altitude = child_text("ele").and_then(|e| e.parse::<f64>().ok());
+            Some(TrackPoint { time, latitude, longitude, altitude })
+        })
+        .collect();
+
+    if points.is_empty() {
+        return Err("GPX file contains no timestamped track points.".to_string());
+    }
+
+    points.sort_by_key(|p| p.time);
+    Ok(points)
+}
+
+pub fn read_capture_time(path: &str) -> Result<NaiveDateTime, String> {
+    let file = fs::File::open(path).map_err(|e| e.to_string())?;
+    let exif = exif::Reader::new()
+        .read_from_container(&mut BufReader::new(file))
+        .map_err(|e| format!("Could not read EXIF data: {}", e))?;
+
+    let field = [exif::Tag::DateTimeOriginal, exif::Tag::DateTimeDigitized, exif::Tag::DateTime]
+        .iter()
+        .find_map(|tag| exif.get_field(*tag, exif::In::PRIMARY))
+        .ok_or_else(|| "Image has no capture time.".to_string())?;
+
+    match &field.value {
+        exif::Value::Ascii(values) if !values.is_empty() => {
+            let dt = exif::DateTime::from_ascii(&values[0]).map_err(|e| e.to_string())?;
+            chrono::NaiveDate::from_ymd_opt(dt.year as i32, dt.month as u32, dt.day as u32)
+                .and_then(|d| d.and_hms_opt(dt.hour as u32, dt.minute as u32, dt.second as u32))
+                .ok_or_else(|| "Invalid capture time.".to_string())
+        }
+        _ => Err("Invalid capture time.".to_string()),
+    }
+}
+
+fn locate_in_track(track: &[TrackPoint], time: DateTime<Utc>, max_gap: Duration) -> Option<GpsCoordinates> {
+    let idx = track.partition_point(|p| p.time < time);
+
+    let to_coords = |p: &TrackPoint| GpsCoordinates {
+        latitude: p.latitude,
+        longitude: p.longitude,
+        altitude: p.altitude,
+    };
+
+    if idx == 0 {
+        let first = &track[0];
+        return (first.time - time <= max_gap).then(|| to_coords(first));
+    }
+    if idx == track.len() {
+        let last = &track[track.len() - 1];
+        return (time - last.time <= max_gap).then(|| to_coords(last));
+    }
+
+    let before = &track[idx - 1];
+    let after = &track[idx];
+    let span = after.time - before.time;
+
+    if span > max_gap {
+        let nearest = if time - before.time <= after.time - time { before } else { after };
+        let distance = if nearest.time > time { nearest.time - time } else { time - nearest.time };
+        return (distance <= max_gap).then(|| to_coords(nearest));
+    }
+
+    let t = if span.num_milliseconds() > 0 {
+        (time - before.time).num_milliseconds() as f64 / span.num_milliseconds() as f64
+    } else {
+        0.0
+    };
+    let altitude = match (before.altitude, after.altitude) {
+        (Some(a), Some(b)) => Some(a + (b - a) * t),
+        (a, b) => a.or(b),
+    };
+
+    Some(GpsCoordinates {
+        latitude: before.latitude + (after.latitude - before.latitude) * t,
+        longitude: before.longitude + (after.longitude - before.longitude) * t,
+        altitude,
+    })
+}
+
+fn to_dms_rationals(value: f64) -> Vec<uR64> {
+    let value = value.abs();
+    let degrees = value.trunc();
+    let minutes = ((value - degrees) * 60.0).trunc();
+    let seconds = (value - degrees - minutes / 60.0) * 3600.0;
+    vec![
+        uR64 { nominator: degrees as u32, denominator: 1 },
+        uR64 { nominator: minutes as u32, denominator: 1 },
+        uR64 { nominator: (seconds * 10000.0).round() as u32, denominator: 10000 },
+    ]
+}
+
+fn write_gps_to_exif(path: &str, gps: &GpsCoordinates) -> Result<(), String> {
+    let file_path = Path::new(path);
+    let mut metadata = Metadata::new_from_path(file_path).map_err(|e| e.to_string())?;
+
+    metadata.set_tag(ExifTag::GPSVersionID(vec![2, 3, 0, 0]));
+    metadata.set_tag(ExifTag::GPSLatitudeRef(if gps.latitude >= 0.0 { "N" } else { "S" }.to_string()));
+    metadata.set_tag(ExifTag::GPSLatitude(to_dms_rationals(gps.latitude)));
+    metadata.set_tag(ExifTag::GPSLongitudeRef(if gps.longitude >= 0.0 { "E" } else { "W" }.to_string()));
+    metadata.set_tag(ExifTag::GPSLongitude(to_dms_rationals(gps.longitude)));
+    if let Some(altitude) = gps.altitude {
+        metadata.set_tag(ExifTag::GPSAltitudeRef(vec![if altitude >= 0.0 { 0 } else { 1 }]));
+        metadata.set_tag(ExifTag::GPSAltitude(vec![uR64 {
+            nominator: (altitude.abs() * 100.0).round() as u32,
+            denominator: 100,
+        }]));
+    }
+
+    metadata.write_to_file(file_path).map_err(|e| e.to_string())
+}
+
+fn geotag_single_image(
+    path: &str,
+    track: &[TrackPoint],
+    timezone_offset: Duration,
+    max_gap: Duration,
+    write_to_original: bool,
+) -> Result<GeotagResult, String> {
+    let local_capture_time = read_capture_time(path)?;
+    let capture_time_utc = DateTime::<Utc>::from_naive_utc_and_offset(local_capture_time - timezone_offset, Utc);
+
+    let Some(gps) = locate_in_track(track, capture_time_utc, max_gap) else {
+        return Ok(GeotagResult {
+            path: path.to_string(),
+            gps: None,
+            exif_written: false,
+            error: Some("Capture time is outside the GPX track.".to_string()),
+        });
+    };
+
+    let sidecar_path = get_sidecar_path(path);
+    let mut metadata: ImageMetadata = fs::read_to_string(&sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    metadata.gps = Some(gps);
+    let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    fs::write(&sidecar_path, json_string).map_err(|e| e.to_string())?;
+
+    let mut error = None;
+    let mut exif_written = false;
+    if write_to_original {
+        if is_raw_file(path) {
+            error = Some("Writing GPS data into RAW originals is not supported.".to_string());
+        } else {
+            match write_gps_to_exif(path, &gps) {
+                Ok(()) => exif_written = true,
+                Err(e) => error = Some(format!("Failed to write EXIF GPS data: {}", e)),
+            }
+        }
+    }
+
+    Ok(GeotagResult {
+        path: path.to_string(),
+        gps: Some(gps),
+        exif_written,
+        error,
+    })
+}
+
+#[tauri::command]
+pub fn geotag_from_gpx(
+    paths: Vec<String>,
+    gpx_path: String,
+    timezone_offset_minutes: Option<i64>,
+    max_gap_seconds: Option<i64>,
+    write_to_original: Option<bool>,
+) -> Result<Vec<GeotagResult>, String> {
+    let content = fs::read_to_string(&gpx_path).map_err(|e| format!("Failed to read GPX file: {}", e))?;
+    let track = parse_gpx(&content)?;
+
+    let timezone_offset = Duration::minutes(timezone_offset_minutes.unwrap_or(0));
+    let max_gap = Duration::seconds(max_gap_seconds.unwrap_or(DEFAULT_MAX_GAP_SECONDS));
+    let write_to_original = write_to_original.unwrap_or(false);
+
+    let results = paths
+        .par_iter()
+        .map(|path| {
+            geotag_single_image(path, &track, timezone_offset, max_gap, write_to_original)
+                .unwrap_or_else(|e| GeotagResult {
+                    path: path.clone(),
+                    gps: None,
+                    exif_written: false,
+                    error: Some(e),
+                })
+        })
+        .collect();
+
+    Ok(results)
+}
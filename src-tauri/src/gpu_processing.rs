@@ -1,11 +1,19 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
 
 use bytemuck;
-use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba, Luma};
+use half::f16;
+use image::{DynamicImage, GenericImageView, ImageBuffer, Rgba, Rgba32FImage, Luma};
+use rayon::prelude::*;
 use wgpu::util::{DeviceExt, TextureDataOrder};
 
 use crate::AppState;
-use crate::image_processing::{AllAdjustments, GpuContext};
+use crate::safe_mode;
+use crate::image_processing::{active_stages, AllAdjustments, GpuContext};
+use crate::lut::Lut;
+use crate::resize::{like_source, ResizeFilter};
+use crate::performance::{self, Stage};
 
 pub fn get_or_init_gpu_context(state: &tauri::State<AppState>) -> Result<GpuContext, String> {
     let mut context_lock = state.gpu_context.lock().unwrap();
@@ -13,8 +21,17 @@ pub fn get_or_init_gpu_context(state: &tauri::State<AppState>) -> Result<GpuCont
         return Ok(context.clone());
     }
     let instance = wgpu::Instance::new(wgpu::InstanceDescriptor::default());
-    let adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()))
-        .ok_or("Failed to find a wgpu adapter.")?;
+    let hardware_adapter = pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions::default()));
+    let adapter = match hardware_adapter {
+        Some(adapter) if !safe_mode::should_use_fallback_adapter(&adapter.get_info()) => adapter,
+        _ => pollster::block_on(instance.request_adapter(&wgpu::RequestAdapterOptions {
+            force_fallback_adapter: true,
+            ..Default::default()
+        }))
+        .ok_or("Failed to find a wgpu adapter.")?,
+    };
+    let adapter_info = adapter.get_info();
+    let is_software_adapter = adapter_info.device_type == wgpu::DeviceType::Cpu;
 
     let mut required_features = wgpu::Features::TEXTURE_BINDING_ARRAY;
     if adapter.features().contains(wgpu::Features::TEXTURE_ADAPTER_SPECIFIC_FORMAT_FEATURES) {
@@ -23,87 +40,165 @@ pub fn get_or_init_gpu_context(state: &tauri::State<AppState>) -> Result<GpuCont
 
     let limits = adapter.limits();
 
-    let (device, queue) = pollster::block_on(adapter.request_device(
+    if !is_software_adapter {
+        safe_mode::begin_gpu_init(&adapter_info);
+    }
+    let device_result = pollster::block_on(adapter.request_device(
         &wgpu::DeviceDescriptor {
             label: Some("Processing Device"),
             required_features,
             required_limits: limits.clone(),
         },
         None,
-    )).map_err(|e| e.to_string())?;
+    ));
+    safe_mode::end_gpu_init();
+    let (device, queue) = device_result.map_err(|e| e.to_string())?;
 
     let new_context = GpuContext {
         device: Arc::new(device),
         queue: Arc::new(queue),
         limits,
+        pipelines: Arc::new(Mutex::new(HashMap::new())),
+        frame_resources: Arc::new(Mutex::new(None)),
+        resize_pipeline: Arc::new(Mutex::new(None)),
     };
     *context_lock = Some(new_context.clone());
     Ok(new_context)
 }
 
+fn padded_bytes_per_row(width: u32, bytes_per_pixel: u32) -> u32 {
+    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
+    (bytes_per_pixel * width + align - 1) & !(align - 1)
+}
+
+// Waits for a `map_async` callback by polling the device. `Maintain::Wait` would block until
+// the newest submission on the device is done, so a preview readback also waited on export or
+// thumbnail work submitted after it. Polling returns as soon as this map is ready.
+fn wait_for_map(
+    device: &wgpu::Device,
+    rx: &std::sync::mpsc::Receiver<Result<(), wgpu::BufferAsyncError>>,
+) -> Result<(), String> {
+    loop {
+        device.poll(wgpu::Maintain::Poll);
+        match rx.recv_timeout(READBACK_POLL_INTERVAL) {
+            Ok(result) => return result.map_err(|e| e.to_string()),
+            Err(std::sync::mpsc::RecvTimeoutError::Timeout) => continue,
+            Err(std::sync::mpsc::RecvTimeoutError::Disconnected) => return Err("GPU readback was aborted".to_string()),
+        }
+    }
+}
+
+// Finishes `encoder` with a copy of `texture` into `buffer`, which has rows padded to the copy
+// alignment, and returns the pixels without the padding.
 fn read_texture_data(
     device: &wgpu::Device,
     queue: &wgpu::Queue,
+    mut encoder: wgpu::CommandEncoder,
     texture: &wgpu::Texture,
+    buffer: &wgpu::Buffer,
     size: wgpu::Extent3d,
+    bytes_per_pixel: u32,
 ) -> Result<Vec<u8>, String> {
-    let unpadded_bytes_per_row = 4 * size.width;
-    let align = wgpu::COPY_BYTES_PER_ROW_ALIGNMENT;
-    let padded_bytes_per_row = (unpadded_bytes_per_row + align - 1) & !(align - 1);
-    let output_buffer_size = (padded_bytes_per_row * size.height) as u64;
-
-    let output_buffer = device.create_buffer(&wgpu::BufferDescriptor {
-        label: Some("Readback Buffer"),
-        size: output_buffer_size,
-        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
-        mapped_at_creation: false,
-    });
+    let unpadded_bytes_per_row = bytes_per_pixel * size.width;
+    let padded_bytes_per_row = padded_bytes_per_row(size.width, bytes_per_pixel);
 
-    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Readback Encoder") });
     encoder.copy_texture_to_buffer(
         wgpu::ImageCopyTexture { texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: wgpu::TextureAspect::All },
         wgpu::ImageCopyBuffer {
-            buffer: &output_buffer,
+            buffer,
             layout: wgpu::ImageDataLayout { offset: 0, bytes_per_row: Some(padded_bytes_per_row), rows_per_image: Some(size.height) },
         },
         size,
     );
 
     queue.submit(Some(encoder.finish()));
-    let buffer_slice = output_buffer.slice(..);
+    let buffer_slice = buffer.slice(..padded_bytes_per_row as u64 * size.height as u64);
     let (tx, rx) = std::sync::mpsc::channel();
-    buffer_slice.map_async(wgpu::MapMode::Read, move |result| { tx.send(result).unwrap(); });
-    device.poll(wgpu::Maintain::Wait);
-    rx.recv().unwrap().map_err(|e| e.to_string())?;
-
-    let padded_data = buffer_slice.get_mapped_range().to_vec();
-    output_buffer.unmap();
+    buffer_slice.map_async(wgpu::MapMode::Read, move |result| { let _ = tx.send(result); });
+    wait_for_map(device, &rx)?;
 
-    if padded_bytes_per_row == unpadded_bytes_per_row {
-        Ok(padded_data)
+    let data = if padded_bytes_per_row == unpadded_bytes_per_row {
+        buffer_slice.get_mapped_range().to_vec()
     } else {
         let mut unpadded_data = Vec::with_capacity((unpadded_bytes_per_row * size.height) as usize);
-        for chunk in padded_data.chunks(padded_bytes_per_row as usize) {
+        for chunk in buffer_slice.get_mapped_range().chunks(padded_bytes_per_row as usize) {
             unpadded_data.extend_from_slice(&chunk[..unpadded_bytes_per_row as usize]);
         }
-        Ok(unpadded_data)
+        unpadded_data
+    };
+    buffer.unmap();
+    Ok(data)
+}
+
+// Grayscale images, like those from monochrome sensors, are uploaded as a single channel and
+// expanded in the shader, which also leaves out the steps that would tint them.
+fn is_monochrome(image: &DynamicImage) -> bool {
+    !image.color().has_color()
+}
+
+// Float images, from float formats or raws developed for a ToneMapper, may hold values above
+// white. Integer images are display referred and never tone mapped.
+pub fn is_hdr(image: &DynamicImage) -> bool {
+    matches!(image, DynamicImage::ImageRgb32F(_) | DynamicImage::ImageRgba32F(_))
+}
+
+// Every stage works on half floats, from the input texture to the readback, so strong curves
+// and recovered highlights keep their gradation. 8-bit output is encoded from the result.
+fn input_texture_format(image: &DynamicImage) -> (wgpu::TextureFormat, u32) {
+    if is_monochrome(image) {
+        (wgpu::TextureFormat::R16Float, 2)
+    } else {
+        (wgpu::TextureFormat::Rgba16Float, 8)
     }
 }
 
-pub fn run_gpu_processing(
-    context: &GpuContext,
-    image: &DynamicImage,
-    adjustments: AllAdjustments,
-    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
-) -> Result<Vec<u8>, String> {
-    let device = &context.device;
-    let queue = &context.queue;
-    let (width, height) = image.dimensions();
-    let max_dim = context.limits.max_texture_dimension_2d;
+fn image_to_texture_data(image: &DynamicImage) -> Vec<u8> {
+    let values = if is_monochrome(image) {
+        image.to_luma32f().into_raw()
+    } else {
+        image.to_rgba32f().into_raw()
+    };
+    values.par_iter().flat_map_iter(|v| f16::from_f32(*v).to_le_bytes()).collect()
+}
+
+fn f16_values(data: &[u8]) -> impl IndexedParallelIterator<Item = f32> + '_ {
+    data.par_chunks_exact(2).map(|b| f16::from_le_bytes([b[0], b[1]]).to_f32().clamp(0.0, 1.0))
+}
+
+fn encode_u8(data: &[u8]) -> Vec<u8> {
+    f16_values(data).map(|v| (v * 255.0).round() as u8).collect()
+}
+
+fn encode_u16(data: &[u8]) -> Vec<u16> {
+    f16_values(data).map(|v| (v * 65535.0).round() as u16).collect()
+}
+
+pub(crate) struct ProcessingPipeline {
+    // Tells bind groups made for an earlier pipeline apart
+    id: u64,
+    texture_format: wgpu::TextureFormat,
+    bytes_per_pixel: u32,
+    bind_group_layout: wgpu::BindGroupLayout,
+    compute_pipeline: wgpu::ComputePipeline,
+    empty_mask_texture: wgpu::Texture,
+}
+
+static NEXT_PIPELINE_ID: AtomicU64 = AtomicU64::new(0);
+
+// The shader is specialized for `active_stages`: stages outside it are constant false in
+// `stage_enabled`, so their code and texture reads drop out when the pipeline is compiled.
+fn create_pipeline(device: &wgpu::Device, active_stages: u32) -> ProcessingPipeline {
+    let (texture_format, bytes_per_pixel) = (wgpu::TextureFormat::Rgba16Float, 8u32);
+
+    let shader_source = include_str!("shader.wgsl").replacen(
+        "const ACTIVE_STAGES: u32 = 131071u;",
+        &format!("const ACTIVE_STAGES: u32 = {}u;", active_stages),
+        1,
+    );
 
     let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
         label: Some("Image Processing Shader"),
-        source: wgpu::ShaderSource::Wgsl(include_str!("shader.wgsl").into()),
+        source: wgpu::ShaderSource::Wgsl(shader_source.into()),
     });
 
     let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
@@ -122,7 +217,7 @@ pub fn run_gpu_processing(
                 binding: 1, visibility: wgpu::ShaderStages::COMPUTE,
                 ty: wgpu::BindingType::StorageTexture {
                     access: wgpu::StorageTextureAccess::WriteOnly,
-                    format: wgpu::TextureFormat::Rgba8Unorm,
+                    format: texture_format,
                     view_dimension: wgpu::TextureViewDimension::D2,
                 }, count: None,
             },
@@ -144,6 +239,16 @@ pub fn run_gpu_processing(
                 },
                 count: None,
             },
+            // 3D LUT
+            wgpu::BindGroupLayoutEntry {
+                binding: 4, visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D3,
+                    multisampled: false,
+                },
+                count: None,
+            },
         ],
     });
 
@@ -158,7 +263,6 @@ pub fn run_gpu_processing(
         module: &shader_module, entry_point: "main",
     });
 
-    let num_masks = mask_bitmaps.len();
     // Create the texture once. It's cheap and can be reused to create views.
     let empty_mask_texture = device.create_texture(&wgpu::TextureDescriptor {
         label: Some("Empty Mask Texture"),
@@ -170,103 +274,186 @@ pub fn run_gpu_processing(
         view_formats: &[],
     });
 
-    if width <= max_dim && height <= max_dim {
-        let img_rgba = image.to_rgba8();
-        let texture_size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+    let id = NEXT_PIPELINE_ID.fetch_add(1, Ordering::Relaxed);
+    ProcessingPipeline { id, texture_format, bytes_per_pixel, bind_group_layout, compute_pipeline, empty_mask_texture }
+}
 
-        let adjustments_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-            label: Some("Adjustments Buffer"),
-            contents: bytemuck::bytes_of(&adjustments),
-            usage: wgpu::BufferUsages::UNIFORM,
-        });
+// Pipelines built so far, one per set of active stages. Scrubbing a slider keeps the same set
+// and reuses its pipeline, only moving an amount away from zero compiles a new one.
+fn pipeline_for(context: &GpuContext, active_stages: u32) -> Arc<ProcessingPipeline> {
+    let mut pipelines = context.pipelines.lock().unwrap();
+    if pipelines.len() >= MAX_CACHED_PIPELINES && !pipelines.contains_key(&active_stages) {
+        pipelines.clear();
+    }
+    pipelines
+        .entry(active_stages)
+        .or_insert_with(|| Arc::new(create_pipeline(&context.device, active_stages)))
+        .clone()
+}
 
-        let input_texture = device.create_texture_with_data(
-            queue,
-            &wgpu::TextureDescriptor {
-                label: Some("Input Texture"), size: texture_size, mip_level_count: 1, sample_count: 1,
-                dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, view_formats: &[],
-            },
-            TextureDataOrder::MipMajor, &img_rgba,
-        );
+// Without a LUT a 1x1x1 texture is bound, and `lut_size` of 0 makes the shader skip it.
+fn create_lut_view(device: &wgpu::Device, queue: &wgpu::Queue, lut: Option<&Lut>) -> wgpu::TextureView {
+    let (size, data): (u32, Vec<f32>) = match lut {
+        Some(lut) => (lut.size, lut.data.iter().flat_map(|[r, g, b]| [*r, *g, *b, 1.0]).collect()),
+        None => (1, vec![0.0; 4]),
+    };
+    let texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("LUT Texture"),
+            size: wgpu::Extent3d { width: size, height: size, depth_or_array_layers: size },
+            mip_level_count: 1, sample_count: 1,
+            dimension: wgpu::TextureDimension::D3,
+            format: wgpu::TextureFormat::Rgba32Float,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        },
+        TextureDataOrder::MipMajor, bytemuck::cast_slice(&data),
+    );
+    texture.create_view(&Default::default())
+}
+
+// Pixels a tile is processed beyond its edges. Sharpening, noise reduction and clarity read
+// their neighbours, up to 20 pixels away for structure, so each tile is rendered with this
+// margin of real image around it and only the inside is kept. The kept pixels then match a
+// render of the whole image and the tiles join without seams.
+const TILE_OVERLAP: u32 = 32;
+
+// How long a readback sleeps between polls of the device while its map is pending.
+const READBACK_POLL_INTERVAL: std::time::Duration = std::time::Duration::from_millis(1);
+
+// Edits rarely switch between more stage sets than this; past it the cache starts over.
+const MAX_CACHED_PIPELINES: usize = 16;
+
+// Renders at or below this size keep their textures and buffers in `GpuContext`. It covers
+// the preview, larger exports allocate theirs per call instead of holding on to the memory.
+const MAX_CACHED_FRAME_PIXELS: u64 = 4096 * 4096;
+
+// Textures and buffers for rendering at one size. The set for the preview size is kept in
+// `GpuContext`, so dragging a slider writes new values into the same objects instead of
+// allocating them, and the bind group is reused while the pipeline and the LUT stay the same.
+pub(crate) struct FrameResources {
+    size: wgpu::Extent3d,
+    input_format: wgpu::TextureFormat,
+    mask_layers: u32,
+    input_texture: wgpu::Texture,
+    output_texture: wgpu::Texture,
+    mask_texture_array: Option<wgpu::Texture>,
+    adjustments_buffer: wgpu::Buffer,
+    readback_buffer: wgpu::Buffer,
+    // The uploaded LUT with the size and values it was made from
+    lut: Option<(Option<(u32, Vec<[f32; 3]>)>, wgpu::TextureView)>,
+    // The bind group with the id of the pipeline it was made for
+    bind_group: Option<(u64, wgpu::BindGroup)>,
+}
+
+impl FrameResources {
+    fn new(device: &wgpu::Device, pipeline: &ProcessingPipeline, size: wgpu::Extent3d, input_format: wgpu::TextureFormat, mask_layers: u32) -> Self {
+        let input_texture = device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Input Texture"), size, mip_level_count: 1, sample_count: 1,
+            dimension: wgpu::TextureDimension::D2, format: input_format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, view_formats: &[],
+        });
 
         let output_texture = device.create_texture(&wgpu::TextureDescriptor {
-            label: Some("Output Texture"), size: texture_size, mip_level_count: 1, sample_count: 1,
-            dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba8Unorm,
+            label: Some("Output Texture"), size, mip_level_count: 1, sample_count: 1,
+            dimension: wgpu::TextureDimension::D2, format: pipeline.texture_format,
             usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC, view_formats: &[],
         });
 
-        let mask_texture_array_view = if num_masks > 0 {
-            let mask_texture_array = device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Mask Texture Array"),
-                size: wgpu::Extent3d { width, height, depth_or_array_layers: num_masks as u32 },
-                mip_level_count: 1, sample_count: 1,
-                dimension: wgpu::TextureDimension::D2,
-                format: wgpu::TextureFormat::R8Unorm,
-                usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                view_formats: &[],
-            });
-
-            for (i, mask_bitmap) in mask_bitmaps.iter().enumerate() {
-                queue.write_texture(
-                    wgpu::ImageCopyTexture {
-                        texture: &mask_texture_array,
-                        mip_level: 0,
-                        origin: wgpu::Origin3d { x: 0, y: 0, z: i as u32 },
-                        aspect: wgpu::TextureAspect::All,
-                    },
-                    mask_bitmap,
-                    wgpu::ImageDataLayout {
-                        offset: 0,
-                        bytes_per_row: Some(width),
-                        rows_per_image: Some(height),
-                    },
-                    texture_size,
-                );
-            }
-            mask_texture_array.create_view(&wgpu::TextureViewDescriptor {
-                dimension: Some(wgpu::TextureViewDimension::D2Array),
-                ..Default::default()
-            })
-        } else {
-            // Create a new view from the empty texture.
-            empty_mask_texture.create_view(&wgpu::TextureViewDescriptor {
-                dimension: Some(wgpu::TextureViewDimension::D2Array),
-                ..Default::default()
-            })
-        };
+        let mask_texture_array = (mask_layers > 0).then(|| device.create_texture(&wgpu::TextureDescriptor {
+            label: Some("Mask Texture Array"),
+            size: wgpu::Extent3d { depth_or_array_layers: mask_layers, ..size },
+            mip_level_count: 1, sample_count: 1,
+            dimension: wgpu::TextureDimension::D2,
+            format: wgpu::TextureFormat::R8Unorm,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
+            view_formats: &[],
+        }));
+
+        let adjustments_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Adjustments Buffer"),
+            size: std::mem::size_of::<AllAdjustments>() as u64,
+            usage: wgpu::BufferUsages::UNIFORM | wgpu::BufferUsages::COPY_DST,
+            mapped_at_creation: false,
+        });
 
-        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-            label: Some("Single Texture Bind Group"), layout: &bind_group_layout,
-            entries: &[
-                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&input_texture.create_view(&Default::default())) },
-                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&output_texture.create_view(&Default::default())) },
-                wgpu::BindGroupEntry { binding: 2, resource: adjustments_buffer.as_entire_binding() },
-                wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&mask_texture_array_view) },
-            ],
+        let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+            label: Some("Readback Buffer"),
+            size: padded_bytes_per_row(size.width, pipeline.bytes_per_pixel) as u64 * size.height as u64,
+            usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+            mapped_at_creation: false,
         });
 
-        let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: None });
-        {
-            let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
-            compute_pass.set_pipeline(&compute_pipeline);
-            compute_pass.set_bind_group(0, &bind_group, &[]);
-            compute_pass.dispatch_workgroups((width + 7) / 8, (height + 7) / 8, 1);
+        FrameResources {
+            size, input_format, mask_layers, input_texture, output_texture, mask_texture_array,
+            adjustments_buffer, readback_buffer, lut: None, bind_group: None,
         }
+    }
 
-        queue.submit(Some(encoder.finish()));
-        return read_texture_data(device, queue, &output_texture, texture_size);
+    fn fits(&self, size: wgpu::Extent3d, input_format: wgpu::TextureFormat, mask_layers: u32) -> bool {
+        self.size == size && self.input_format == input_format && self.mask_layers == mask_layers
     }
 
-    // Tiling logic for very large images
-    let tile_size = (max_dim / 2).min(2048);
-    let img_rgba = image.to_rgba8();
-    let mut final_pixels = vec![0u8; (width * height * 4) as usize];
+    // Uploads the LUT if it is not the one from the last render and returns the bind group for
+    // `pipeline`, made again only when one of them changed.
+    fn bind_group(&mut self, device: &wgpu::Device, queue: &wgpu::Queue, pipeline: &ProcessingPipeline, lut: Option<&Lut>) -> &wgpu::BindGroup {
+        let lut_key = lut.map(|l| (l.size, l.data.as_slice()));
+        let lut_changed = self.lut.as_ref().map_or(true, |(key, _)| key.as_ref().map(|(size, data)| (*size, data.as_slice())) != lut_key);
+        if lut_changed {
+            self.lut = Some((lut.map(|l| (l.size, l.data.clone())), create_lut_view(device, queue, lut)));
+            self.bind_group = None;
+        }
+        if self.bind_group.as_ref().map_or(true, |(id, _)| *id != pipeline.id) {
+            let mask_view = self.mask_texture_array.as_ref().unwrap_or(&pipeline.empty_mask_texture).create_view(&wgpu::TextureViewDescriptor {
+                dimension: Some(wgpu::TextureViewDimension::D2Array),
+                ..Default::default()
+            });
+            let lut_view = &self.lut.as_ref().unwrap().1;
+            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+                label: Some("Bind Group"), layout: &pipeline.bind_group_layout,
+                entries: &[
+                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&self.input_texture.create_view(&Default::default())) },
+                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&self.output_texture.create_view(&Default::default())) },
+                    wgpu::BindGroupEntry { binding: 2, resource: self.adjustments_buffer.as_entire_binding() },
+                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&mask_view) },
+                    wgpu::BindGroupEntry { binding: 4, resource: wgpu::BindingResource::TextureView(lut_view) },
+                ],
+            });
+            self.bind_group = Some((pipeline.id, bind_group));
+        }
+        &self.bind_group.as_ref().unwrap().1
+    }
+}
+
+// Processes the image in tiles of at most `tile_size` pixels and hands each one to `on_tile`
+// as (x, y, width, height, pixels), row by row. Used for images larger than a texture and for
+// exports that are written tile by tile. The textures are up to `tile_size + 2 * TILE_OVERLAP`
+// wide.
+fn process_tiles(
+    context: &GpuContext,
+    pipeline: &ProcessingPipeline,
+    img_data: &[u8],
+    input_format: (wgpu::TextureFormat, u32),
+    width: u32,
+    height: u32,
+    adjustments: AllAdjustments,
+    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
+    tile_size: u32,
+    mut on_tile: impl FnMut(u32, u32, u32, u32, Vec<u8>) -> Result<(), String>,
+) -> Result<(), String> {
+    let device = &context.device;
+    let queue = &context.queue;
+    let bytes_per_pixel = pipeline.bytes_per_pixel;
+    let mask_layers = mask_bitmaps.len() as u32;
+    let (input_format, input_bytes_per_pixel) = input_format;
 
     let tiles_x = (width + tile_size - 1) / tile_size;
     let tiles_y = (height + tile_size - 1) / tile_size;
 
-    let raw_buffer = img_rgba.as_raw();
+    // Tiles of the same size share one set of resources
+    let mut tile_resources: Option<FrameResources> = None;
 
     for tile_y in 0..tiles_y {
         for tile_x in 0..tiles_x {
@@ -278,129 +465,393 @@ pub fn run_gpu_processing(
             let tile_width = x_end - x_start;
             let tile_height = y_end - y_start;
 
-            let mut tile_pixels = Vec::with_capacity((tile_width * tile_height * 4) as usize);
-
-            for y in y_start..y_end {
-                let pixel_row_start = (y * width + x_start) as usize * 4;
-                let pixel_row_end = pixel_row_start + (tile_width as usize * 4);
-                tile_pixels.extend_from_slice(&raw_buffer[pixel_row_start..pixel_row_end]);
-            }
-
-            let texture_size = wgpu::Extent3d { width: tile_width, height: tile_height, depth_or_array_layers: 1 };
-
-            let mut tile_adjustments = adjustments;
-            tile_adjustments.tile_offset_x = x_start;
-            tile_adjustments.tile_offset_y = y_start;
+            // The rendered area, with the overlap on every side that is not an image edge
+            let padded_x = x_start.saturating_sub(TILE_OVERLAP);
+            let padded_y = y_start.saturating_sub(TILE_OVERLAP);
+            let padded_width = (x_end + TILE_OVERLAP).min(width) - padded_x;
+            let padded_height = (y_end + TILE_OVERLAP).min(height) - padded_y;
 
-            let adjustments_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
-                label: Some("Tile Adjustments Buffer"),
-                contents: bytemuck::bytes_of(&tile_adjustments),
-                usage: wgpu::BufferUsages::UNIFORM,
-            });
+            let texture_size = wgpu::Extent3d { width: padded_width, height: padded_height, depth_or_array_layers: 1 };
 
-            let input_texture = device.create_texture_with_data(
-                queue,
-                &wgpu::TextureDescriptor {
-                    label: Some("Input Tile Texture"), size: texture_size, mip_level_count: 1, sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba8Unorm,
-                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, view_formats: &[],
+            let whole_image = padded_width == width && padded_height == height;
+            let mut cached = if whole_image && width as u64 * height as u64 <= MAX_CACHED_FRAME_PIXELS {
+                // Another render holding the cached set gets its own below.
+                context.frame_resources.try_lock().ok()
+            } else {
+                None
+            };
+            let slot = match cached.as_deref_mut() {
+                Some(slot) => slot,
+                None => &mut tile_resources,
+            };
+            if !slot.as_ref().is_some_and(|r| r.fits(texture_size, input_format, mask_layers)) {
+                *slot = Some(FrameResources::new(device, pipeline, texture_size, input_format, mask_layers));
+            }
+            let resources = slot.as_mut().unwrap();
+
+            // The tile is read straight out of the full image rows, without copying it out first.
+            queue.write_texture(
+                wgpu::ImageCopyTexture { texture: &resources.input_texture, mip_level: 0, origin: wgpu::Origin3d::ZERO, aspect: wgpu::TextureAspect::All },
+                img_data,
+                wgpu::ImageDataLayout {
+                    offset: (padded_y as u64 * width as u64 + padded_x as u64) * input_bytes_per_pixel as u64,
+                    bytes_per_row: Some(width * input_bytes_per_pixel),
+                    rows_per_image: Some(padded_height),
                 },
-                TextureDataOrder::MipMajor, &tile_pixels,
+                texture_size,
             );
 
-            let output_texture = device.create_texture(&wgpu::TextureDescriptor {
-                label: Some("Output Tile Texture"), size: texture_size, mip_level_count: 1, sample_count: 1,
-                dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba8Unorm,
-                usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC, view_formats: &[],
-            });
-
-            // Create a texture array with cropped versions of the masks for this tile
-            let mask_texture_array_view = if num_masks > 0 {
-                let mask_texture_array = device.create_texture(&wgpu::TextureDescriptor {
-                    label: Some("Tile Mask Texture Array"),
-                    size: wgpu::Extent3d { width: tile_width, height: tile_height, depth_or_array_layers: num_masks as u32 },
-                    mip_level_count: 1, sample_count: 1,
-                    dimension: wgpu::TextureDimension::D2,
-                    format: wgpu::TextureFormat::R8Unorm,
-                    usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST,
-                    view_formats: &[],
-                });
-
+            if let Some(mask_texture_array) = &resources.mask_texture_array {
                 for (i, full_mask_bitmap) in mask_bitmaps.iter().enumerate() {
-                    let cropped_mask = image::imageops::crop_imm(full_mask_bitmap, x_start, y_start, tile_width, tile_height).to_image();
                     queue.write_texture(
                         wgpu::ImageCopyTexture {
-                            texture: &mask_texture_array,
+                            texture: mask_texture_array,
                             mip_level: 0,
                             origin: wgpu::Origin3d { x: 0, y: 0, z: i as u32 },
                             aspect: wgpu::TextureAspect::All,
                         },
-                        &cropped_mask,
+                        full_mask_bitmap.as_raw(),
                         wgpu::ImageDataLayout {
-                            offset: 0,
-                            bytes_per_row: Some(tile_width),
-                            rows_per_image: Some(tile_height),
+                            offset: padded_y as u64 * full_mask_bitmap.width() as u64 + padded_x as u64,
+                            bytes_per_row: Some(full_mask_bitmap.width()),
+                            rows_per_image: Some(padded_height),
                         },
                         texture_size,
                     );
                 }
-                mask_texture_array.create_view(&wgpu::TextureViewDescriptor {
-                    dimension: Some(wgpu::TextureViewDimension::D2Array),
-                    ..Default::default()
-                })
-            } else {
-                // Create a new view from the empty texture for this tile.
-                empty_mask_texture.create_view(&wgpu::TextureViewDescriptor {
-                    dimension: Some(wgpu::TextureViewDimension::D2Array),
-                    ..Default::default()
-                })
-            };
+            }
 
-            let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
-                label: Some("Tile Bind Group"), layout: &bind_group_layout,
-                entries: &[
-                    wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&input_texture.create_view(&Default::default())) },
-                    wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&output_texture.create_view(&Default::default())) },
-                    wgpu::BindGroupEntry { binding: 2, resource: adjustments_buffer.as_entire_binding() },
-                    wgpu::BindGroupEntry { binding: 3, resource: wgpu::BindingResource::TextureView(&mask_texture_array_view) },
-                ],
-            });
+            let mut tile_adjustments = adjustments;
+            tile_adjustments.tile_offset_x = padded_x;
+            tile_adjustments.tile_offset_y = padded_y;
+            queue.write_buffer(&resources.adjustments_buffer, 0, bytemuck::bytes_of(&tile_adjustments));
 
             let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Tile Encoder") });
             {
+                let bind_group = resources.bind_group(device, queue, pipeline, lut);
                 let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
-                compute_pass.set_pipeline(&compute_pipeline);
-                compute_pass.set_bind_group(0, &bind_group, &[]);
-                compute_pass.dispatch_workgroups((tile_width + 7) / 8, (tile_height + 7) / 8, 1);
+                compute_pass.set_pipeline(&pipeline.compute_pipeline);
+                compute_pass.set_bind_group(0, bind_group, &[]);
+                compute_pass.dispatch_workgroups((padded_width + 7) / 8, (padded_height + 7) / 8, 1);
             }
-            queue.submit(Some(encoder.finish()));
-
-            let processed_tile_data = read_texture_data(device, queue, &output_texture, texture_size)?;
 
-            for row in 0..tile_height {
-                let final_y = y_start + row;
-                let final_row_offset = (final_y * width + x_start) as usize * 4;
-                let tile_row_offset = (row * tile_width) as usize * 4;
-                let copy_bytes = (tile_width * 4) as usize;
+            let padded_tile_data = read_texture_data(device, queue, encoder, &resources.output_texture, &resources.readback_buffer, texture_size, bytes_per_pixel)?;
+            drop(cached);
 
-                final_pixels[final_row_offset..final_row_offset + copy_bytes]
-                    .copy_from_slice(&processed_tile_data[tile_row_offset..tile_row_offset + copy_bytes]);
-            }
+            let processed_tile_data = if tile_width == padded_width && tile_height == padded_height {
+                padded_tile_data
+            } else {
+                let out_bpp = bytes_per_pixel as usize;
+                let row_bytes = tile_width as usize * out_bpp;
+                let mut processed_tile_data = Vec::with_capacity(row_bytes * tile_height as usize);
+                for row in (y_start - padded_y)..(y_end - padded_y) {
+                    let row_start = (row * padded_width + x_start - padded_x) as usize * out_bpp;
+                    processed_tile_data.extend_from_slice(&padded_tile_data[row_start..row_start + row_bytes]);
+                }
+                processed_tile_data
+            };
+            on_tile(x_start, y_start, tile_width, tile_height, processed_tile_data)?;
         }
     }
 
+    Ok(())
+}
+
+// Renders the image and returns its RGBA values in the sample type of `encode`, which turns
+// the half float readback into the output depth. Large images are encoded tile by tile, so
+// the full image is only ever held at the output depth.
+pub fn run_gpu_processing<T: Copy + Default>(
+    context: &GpuContext,
+    image: &DynamicImage,
+    mut adjustments: AllAdjustments,
+    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
+    encode: fn(&[u8]) -> Vec<T>,
+) -> Result<Vec<T>, String> {
+    let (width, height) = image.dimensions();
+    let max_dim = context.limits.max_texture_dimension_2d;
+
+    let img_data = image_to_texture_data(image);
+    let input_format = input_texture_format(image);
+    adjustments.global.monochrome = is_monochrome(image) as u32;
+    if !is_hdr(image) {
+        adjustments.global.tone_mapper = 0;
+    }
+    adjustments.global.lut_size = lut.map_or(0, |l| l.size);
+    adjustments.image_width = width;
+    adjustments.image_height = height;
+    let pipeline = pipeline_for(context, active_stages(&adjustments));
+
+    // Large files can fit the texture limit but not the readback buffer, 150 MP is over 1 GB
+    // in half floats, so both decide whether the image is rendered in one go.
+    let readback_size = padded_bytes_per_row(width, pipeline.bytes_per_pixel) as u64 * height as u64;
+    if width <= max_dim && height <= max_dim && readback_size <= context.limits.max_buffer_size {
+        let mut processed_pixels = Vec::new();
+        process_tiles(context, &pipeline, &img_data, input_format, width, height, adjustments, mask_bitmaps, lut, width.max(height), |_, _, _, _, pixels| {
+            processed_pixels = encode(&pixels);
+            Ok(())
+        })?;
+        return Ok(processed_pixels);
+    }
+
+    // Tiling logic for very large images
+    let tile_size = (max_dim - 2 * TILE_OVERLAP).min(2048);
+    let bpp = 4;
+    let mut final_pixels = vec![T::default(); (width * height) as usize * bpp];
+
+    process_tiles(context, &pipeline, &img_data, input_format, width, height, adjustments, mask_bitmaps, lut, tile_size, |x_start, y_start, tile_width, tile_height, processed_tile_data| {
+        let processed_tile_data = encode(&processed_tile_data);
+        for row in 0..tile_height {
+            let final_y = y_start + row;
+            let final_row_offset = (final_y * width + x_start) as usize * bpp;
+            let tile_row_offset = (row * tile_width) as usize * bpp;
+            let copy_bytes = tile_width as usize * bpp;
+
+            final_pixels[final_row_offset..final_row_offset + copy_bytes]
+                .copy_from_slice(&processed_tile_data[tile_row_offset..tile_row_offset + copy_bytes]);
+        }
+        Ok(())
+    })?;
+
     Ok(final_pixels)
 }
 
+// Processing that never holds the full output, for exports written tile by tile. Tiles are
+// handed over as 8-bit RGBA. `tile_size` is capped by the texture size limit of the device.
+pub fn run_gpu_processing_tiled(
+    context: &GpuContext,
+    image: &DynamicImage,
+    mut adjustments: AllAdjustments,
+    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
+    tile_size: u32,
+    mut on_tile: impl FnMut(u32, u32, u32, u32, Vec<u8>) -> Result<(), String>,
+) -> Result<(), String> {
+    let (width, height) = image.dimensions();
+    let tile_size = tile_size.min(context.limits.max_texture_dimension_2d.saturating_sub(2 * TILE_OVERLAP)).max(1);
+    let img_data = image_to_texture_data(image);
+    adjustments.global.monochrome = is_monochrome(image) as u32;
+    if !is_hdr(image) {
+        adjustments.global.tone_mapper = 0;
+    }
+    adjustments.global.lut_size = lut.map_or(0, |l| l.size);
+    adjustments.image_width = width;
+    adjustments.image_height = height;
+    let pipeline = pipeline_for(context, active_stages(&adjustments));
+    process_tiles(context, &pipeline, &img_data, input_texture_format(image), width, height, adjustments, mask_bitmaps, lut, tile_size, |x, y, w, h, pixels| {
+        on_tile(x, y, w, h, encode_u8(&pixels))
+    })
+}
+
 pub fn process_and_get_dynamic_image(
     context: &GpuContext,
     base_image: &DynamicImage,
     all_adjustments: AllAdjustments,
     mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
 ) -> Result<DynamicImage, String> {
-    let processed_pixels = run_gpu_processing(context, base_image, all_adjustments, mask_bitmaps)?;
+    let processed_pixels = performance::time(Stage::Render, || run_gpu_processing(context, base_image, all_adjustments, mask_bitmaps, lut, encode_u8))?;
     let (width, height) = base_image.dimensions();
     let img_buf = ImageBuffer::<Rgba<u8>, Vec<u8>>::from_raw(width, height, processed_pixels)
         .ok_or("Failed to create image buffer from GPU data")?;
     Ok(DynamicImage::ImageRgba8(img_buf))
-}
\ No newline at end of file
+}
+
+pub fn process_and_get_dynamic_image_16bit(
+    context: &GpuContext,
+    base_image: &DynamicImage,
+    all_adjustments: AllAdjustments,
+    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
+) -> Result<DynamicImage, String> {
+    let pixels_u16 = performance::time(Stage::Render, || run_gpu_processing(context, base_image, all_adjustments, mask_bitmaps, lut, encode_u16))?;
+    let (width, height) = base_image.dimensions();
+    let img_buf = ImageBuffer::<Rgba<u16>, Vec<u16>>::from_raw(width, height, pixels_u16)
+        .ok_or("Failed to create image buffer from GPU data")?;
+    Ok(DynamicImage::ImageRgba16(img_buf))
+}
+#[derive(Clone, Copy, bytemuck::Pod, bytemuck::Zeroable)]
+#[repr(C)]
+struct ResizeParams {
+    scale: f32,
+    support: f32,
+    kernel_type: u32,
+    vertical: u32,
+}
+
+pub(crate) struct ResizePipeline {
+    bind_group_layout: wgpu::BindGroupLayout,
+    compute_pipeline: wgpu::ComputePipeline,
+}
+
+fn create_resize_pipeline(device: &wgpu::Device) -> ResizePipeline {
+    let shader_module = device.create_shader_module(wgpu::ShaderModuleDescriptor {
+        label: Some("Resize Shader"),
+        source: wgpu::ShaderSource::Wgsl(include_str!("resize.wgsl").into()),
+    });
+
+    let bind_group_layout = device.create_bind_group_layout(&wgpu::BindGroupLayoutDescriptor {
+        label: Some("Resize Bind Group Layout"),
+        entries: &[
+            wgpu::BindGroupLayoutEntry {
+                binding: 0, visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Texture {
+                    sample_type: wgpu::TextureSampleType::Float { filterable: false },
+                    view_dimension: wgpu::TextureViewDimension::D2, multisampled: false,
+                }, count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 1, visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::StorageTexture {
+                    access: wgpu::StorageTextureAccess::WriteOnly,
+                    format: wgpu::TextureFormat::Rgba32Float,
+                    view_dimension: wgpu::TextureViewDimension::D2,
+                }, count: None,
+            },
+            wgpu::BindGroupLayoutEntry {
+                binding: 2, visibility: wgpu::ShaderStages::COMPUTE,
+                ty: wgpu::BindingType::Buffer {
+                    ty: wgpu::BufferBindingType::Uniform,
+                    has_dynamic_offset: false, min_binding_size: None,
+                }, count: None,
+            },
+        ],
+    });
+
+    let pipeline_layout = device.create_pipeline_layout(&wgpu::PipelineLayoutDescriptor {
+        label: Some("Resize Pipeline Layout"),
+        bind_group_layouts: &[&bind_group_layout],
+        push_constant_ranges: &[],
+    });
+
+    let compute_pipeline = device.create_compute_pipeline(&wgpu::ComputePipelineDescriptor {
+        label: Some("Resize Pipeline"), layout: Some(&pipeline_layout),
+        module: &shader_module, entry_point: "main",
+    });
+
+    ResizePipeline { bind_group_layout, compute_pipeline }
+}
+
+fn resize_pipeline_for(context: &GpuContext) -> Arc<ResizePipeline> {
+    context.resize_pipeline
+        .lock()
+        .unwrap()
+        .get_or_insert_with(|| Arc::new(create_resize_pipeline(&context.device)))
+        .clone()
+}
+
+// Resamples `image` to `width` x `height` in two separable passes, in linear light like
+// `resize_image` on the CPU. The result has the color type of the source.
+pub fn gpu_resize(context: &GpuContext, image: &DynamicImage, width: u32, height: u32, filter: ResizeFilter) -> Result<DynamicImage, String> {
+    let device = &context.device;
+    let queue = &context.queue;
+    let (src_width, src_height) = image.dimensions();
+    let (width, height) = (width.max(1), height.max(1));
+    let max_dim = context.limits.max_texture_dimension_2d;
+    if src_width.max(src_height).max(width).max(height) > max_dim {
+        return Err(format!("{}x{} does not fit in a texture", src_width, src_height));
+    }
+    let output_size = wgpu::Extent3d { width, height, depth_or_array_layers: 1 };
+    let readback_size = padded_bytes_per_row(width, 16) as u64 * height as u64;
+    if readback_size > context.limits.max_buffer_size {
+        return Err(format!("{}x{} is too large to read back", width, height));
+    }
+
+    let pipeline = resize_pipeline_for(context);
+    let (kernel_type, support) = match filter {
+        ResizeFilter::CatmullRom => (1, 2.0),
+        ResizeFilter::Fast | ResizeFilter::Lanczos => (0, 3.0),
+    };
+
+    // 8-bit sources are uploaded as they are, deeper ones as floats to keep their precision.
+    let rgba8;
+    let rgba32f;
+    let (input_format, input_data): (_, &[u8]) = match image {
+        DynamicImage::ImageLuma8(_) | DynamicImage::ImageLumaA8(_) | DynamicImage::ImageRgb8(_) | DynamicImage::ImageRgba8(_) => {
+            rgba8 = image.to_rgba8();
+            (wgpu::TextureFormat::Rgba8Unorm, rgba8.as_raw())
+        }
+        _ => {
+            rgba32f = image.to_rgba32f();
+            (wgpu::TextureFormat::Rgba32Float, bytemuck::cast_slice(rgba32f.as_raw()))
+        }
+    };
+
+    let input_texture = device.create_texture_with_data(
+        queue,
+        &wgpu::TextureDescriptor {
+            label: Some("Resize Input Texture"),
+            size: wgpu::Extent3d { width: src_width, height: src_height, depth_or_array_layers: 1 },
+            mip_level_count: 1, sample_count: 1,
+            dimension: wgpu::TextureDimension::D2, format: input_format,
+            usage: wgpu::TextureUsages::TEXTURE_BINDING | wgpu::TextureUsages::COPY_DST, view_formats: &[],
+        },
+        TextureDataOrder::MipMajor, input_data,
+    );
+
+    // Rows are filtered first, into a texture that is already at the output width.
+    let rows_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Resize Rows Texture"),
+        size: wgpu::Extent3d { width, height: src_height, depth_or_array_layers: 1 },
+        mip_level_count: 1, sample_count: 1,
+        dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::TEXTURE_BINDING, view_formats: &[],
+    });
+
+    let output_texture = device.create_texture(&wgpu::TextureDescriptor {
+        label: Some("Resize Output Texture"), size: output_size, mip_level_count: 1, sample_count: 1,
+        dimension: wgpu::TextureDimension::D2, format: wgpu::TextureFormat::Rgba32Float,
+        usage: wgpu::TextureUsages::STORAGE_BINDING | wgpu::TextureUsages::COPY_SRC, view_formats: &[],
+    });
+
+    let passes = [
+        (&input_texture, &rows_texture, src_width as f32 / width as f32, 0u32, width, src_height),
+        (&rows_texture, &output_texture, src_height as f32 / height as f32, 1u32, width, height),
+    ];
+
+    let mut encoder = device.create_command_encoder(&wgpu::CommandEncoderDescriptor { label: Some("Resize Encoder") });
+    for (input, output, scale, vertical, pass_width, pass_height) in passes {
+        let params_buffer = device.create_buffer_init(&wgpu::util::BufferInitDescriptor {
+            label: Some("Resize Params Buffer"),
+            contents: bytemuck::bytes_of(&ResizeParams { scale, support, kernel_type, vertical }),
+            usage: wgpu::BufferUsages::UNIFORM,
+        });
+        let bind_group = device.create_bind_group(&wgpu::BindGroupDescriptor {
+            label: Some("Resize Bind Group"), layout: &pipeline.bind_group_layout,
+            entries: &[
+                wgpu::BindGroupEntry { binding: 0, resource: wgpu::BindingResource::TextureView(&input.create_view(&Default::default())) },
+                wgpu::BindGroupEntry { binding: 1, resource: wgpu::BindingResource::TextureView(&output.create_view(&Default::default())) },
+                wgpu::BindGroupEntry { binding: 2, resource: params_buffer.as_entire_binding() },
+            ],
+        });
+        let mut compute_pass = encoder.begin_compute_pass(&wgpu::ComputePassDescriptor { label: None, timestamp_writes: None });
+        compute_pass.set_pipeline(&pipeline.compute_pipeline);
+        compute_pass.set_bind_group(0, &bind_group, &[]);
+        compute_pass.dispatch_workgroups((pass_width + 7) / 8, (pass_height + 7) / 8, 1);
+    }
+
+    let readback_buffer = device.create_buffer(&wgpu::BufferDescriptor {
+        label: Some("Resize Readback Buffer"),
+        size: readback_size,
+        usage: wgpu::BufferUsages::COPY_DST | wgpu::BufferUsages::MAP_READ,
+        mapped_at_creation: false,
+    });
+    let data = read_texture_data(device, queue, encoder, &output_texture, &readback_buffer, output_size, 16)?;
+    let values: Vec<f32> = data.chunks_exact(4).map(|b| f32::from_le_bytes([b[0], b[1], b[2], b[3]])).collect();
+    let resized = Rgba32FImage::from_raw(width, height, values).ok_or("Failed to create image buffer from GPU data")?;
+    Ok(like_source(resized, image))
+}
+
+// Stands in for `DynamicImage::thumbnail`: fits the image into `max_width` x `max_height`
+// keeping its aspect ratio, with Lanczos on the GPU. Images the GPU cannot take, such as
+// panoramas wider than a texture, are scaled on the CPU as before.
+pub fn gpu_thumbnail(context: &GpuContext, image: &DynamicImage, max_width: u32, max_height: u32) -> DynamicImage {
+    let (src_width, src_height) = image.dimensions();
+    let ratio = (max_width as f64 / src_width.max(1) as f64).min(max_height as f64 / src_height.max(1) as f64);
+    let width = ((src_width as f64 * ratio).round() as u32).max(1);
+    let height = ((src_height as f64 * ratio).round() as u32).max(1);
+    gpu_resize(context, image, width, height, ResizeFilter::Lanczos).unwrap_or_else(|e| {
+        eprintln!("GPU downscale failed, using the CPU: {}", e);
+        image.thumbnail(max_width, max_height)
+    })
+}
@@ -0,0 +1,143 @@
+use image::{imageops::FilterType, DynamicImage, GenericImageView, ImageBuffer, Luma};
+use serde_json::json;
+use uuid::Uuid;
+
+use crate::ai_processing::AiForegroundMaskParameters;
+use crate::mask_generation::{MaskDefinition, SubMask, SubMaskMode};
+
+const ANALYSIS_WIDTH: u32 = 256;
+// Rows averaged on each side of a candidate horizon, in analysis pixels.
+const EDGE_WINDOW: usize = 3;
+// Edges weaker than this (in 0..1 luma) are noise, not a horizon.
+const MIN_EDGE_STRENGTH: f32 = 0.03;
+// Candidates further than this fraction of the height from the median are outliers.
+const OUTLIER_FRACTION: f32 = 0.12;
+// About 11 degrees, steeper lines are more likely a slope than the horizon.
+const MAX_SLOPE: f32 = 0.2;
+
+// The horizon as the y coordinate at the left and right image edge, in image pixels.
+pub fn estimate_horizon(image: &DynamicImage) -> (f32, f32) {
+    let (width, height) = image.dimensions();
+    let fallback = (height as f32 * 0.5, height as f32 * 0.5);
+    if width == 0 || height == 0 {
+        return fallback;
+    }
+
+    let analysis_height = ((height as f32 * ANALYSIS_WIDTH as f32 / width as f32).round() as u32).max(16);
+    let luma = image
+        .resize_exact(ANALYSIS_WIDTH, analysis_height, FilterType::Triangle)
+        .to_luma32f();
+    let candidates = horizon_candidates(&luma);
+    if candidates.len() < (ANALYSIS_WIDTH / 4) as usize {
+        return fallback;
+    }
+
+    let mut rows: Vec<f32> = candidates.iter().map(|&(_, y)| y).collect();
+    rows.sort_by(f32::total_cmp);
+    let median = rows[rows.len() / 2];
+    let tolerance = analysis_height as f32 * OUTLIER_FRACTION;
+    let inliers: Vec<(f32, f32)> = candidates
+        .into_iter()
+        .filter(|&(_, y)| (y - median).abs() <= tolerance)
+        .collect();
+
+    let (intercept, slope) = fit_line(&inliers).unwrap_or((median, 0.0));
+    let slope = slope.clamp(-MAX_SLOPE, MAX_SLOPE);
+    let to_image = height as f32 / analysis_height as f32;
+    let left = intercept * to_image;
+    let right = (intercept + slope * (ANALYSIS_WIDTH - 1) as f32) * to_image;
+    (
+        left.clamp(0.0, height as f32),
+        right.clamp(0.0, height as f32),
+    )
+}
+
+// Per column, the row where the image turns darker the most from above to below.
+// Skies are brighter than the land below them in almost every landscape.
+fn horizon_candidates(luma: &ImageBuffer<Luma<f32>, Vec<f32>>) -> Vec<(f32, f32)> {
+    let (width, height) = (luma.width() as usize, luma.height() as usize);
+    let first = ((height as f32 * 0.05) as usize).max(EDGE_WINDOW);
+    let last = ((height as f32 * 0.9) as usize).min(height - EDGE_WINDOW);
+    let raw = luma.as_raw();
+
+    (0..width)
+        .filter_map(|x| {
+            let column: Vec<f32> = (0..height).map(|y| raw[y * width + x]).collect();
+            (first..last)
+                .map(|y| {
+                    let above: f32 = column[y - EDGE_WINDOW..y].iter().sum();
+                    let below: f32 = column[y..y + EDGE_WINDOW].iter().sum();
+                    (y, (above - below) / EDGE_WINDOW as f32)
+                })
+                .max_by(|a, b| a.1.total_cmp(&b.1))
+                .filter(|&(_, strength)| strength >= MIN_EDGE_STRENGTH)
+                .map(|(y, _)| (x as f32, y as f32))
+        })
+        .collect()
+}
+
+// Least squares fit of y = intercept + slope * x.
+fn fit_line(points: &[(f32, f32)]) -> Option<(f32, f32)> {
+    if points.len() < 2 {
+        return None;
+    }
+    let n = points.len() as f32;
+    let mean_x = points.iter().map(|p| p.0).sum::<f32>() / n;
+    let mean_y = points.iter().map(|p| p.1).sum::<f32>() / n;
+    let sxx: f32 = points.iter().map(|p| (p.0 - mean_x).powi(2)).sum();
+    let sxy: f32 = points.iter().map(|p| (p.0 - mean_x) * (p.1 - mean_y)).sum();
+    if sxx <= f32::EPSILON {
+        return None;
+    }
+    let slope = sxy / sxx;
+    Some((mean_y - slope * mean_x, slope))
+}
+
+// A mask that darkens the sky down to the horizon. The linear gradient is fully on at the top
+// and fades out at the horizon; the foreground model's mask is subtracted so that mountains,
+// trees and buildings reaching into the sky keep their exposure.
+pub fn build_graduated_sky_mask(
+    width: u32,
+    height: u32,
+    horizon: (f32, f32),
+    foreground: Option<AiForegroundMaskParameters>,
+) -> MaskDefinition {
+    let (left, right) = horizon;
+    let range = ((left + right) * 0.5 * 0.3).max(height as f32 * 0.05);
+
+    let mut sub_masks = vec![SubMask {
+        id: Uuid::new_v4().to_string(),
+        mask_type: "linear".to_string(),
+        visible: true,
+        mode: SubMaskMode::Additive,
+        parameters: json!({
+            "startX": 0.0,
+            "startY": left - range,
+            "endX": width as f32,
+            "endY": right - range,
+            "range": range,
+        }),
+    }];
+
+    if let Some(params) = foreground {
+        sub_masks.push(SubMask {
+            id: Uuid::new_v4().to_string(),
+            mask_type: "ai-foreground".to_string(),
+            visible: true,
+            mode: SubMaskMode::Subtractive,
+            parameters: serde_json::to_value(params).unwrap_or_default(),
+        });
+    }
+
+    MaskDefinition {
+        id: Uuid::new_v4().to_string(),
+        name: "Graduated Sky".to_string(),
+        visible: true,
+        invert: false,
+        adjustments: json!({
+            "exposure": -0.7,
+            "dehaze": 10,
+        }),
+        sub_masks,
+    }
+}
@@ -1,6 +1,6 @@
 use anyhow::{Result, Context};
 use base64::{engine::general_purpose, Engine as _};
-use image::{imageops, DynamicImage, ImageReader, RgbaImage};
+use image::{DynamicImage, ImageReader, RgbaImage};
 use rawler::Orientation;
 use std::io::Cursor;
 use rayon::prelude::*;
@@ -11,7 +11,83 @@ use exif::{Reader as ExifReader, Tag};
 use crate::image_processing::apply_orientation;
 
 use crate::formats::is_raw_file;
-use crate::raw_processing::develop_raw_image;
+use crate::lens_correction;
+use crate::performance::{self, Stage};
+use crate::resize::{linear_to_srgb, srgb_to_linear};
+use crate::raw_processing::{develop_raw_image, read_raw_metadata, DevelopOverrides};
+
+// Capture details used for filename tokens and camera profiles. RAW files go through
+// rawler, which knows the vendor makernotes; everything else is read from EXIF.
+#[derive(Debug, Clone, Default)]
+pub struct CaptureInfo {
+    pub make: String,
+    pub model: String,
+    pub lens: Option<String>,
+    pub iso: Option<u32>,
+    pub capture_date: Option<String>,
+    pub focal_length: Option<f32>,
+    pub aperture: Option<f32>,
+}
+
+fn exif_datetime_to_date(value: &str) -> Option<String> {
+    let date = value.trim().get(..10)?;
+    Some(date.replace(':', "-"))
+}
+
+pub fn read_capture_info(file_bytes: &[u8], path: &str) -> CaptureInfo {
+    if is_raw_file(path) {
+        if let Ok(metadata) = read_raw_metadata(file_bytes) {
+            return CaptureInfo {
+                make: metadata.make,
+                model: metadata.model,
+                lens: metadata
+                    .lens
+                    .map(|l| l.lens_model)
+                    .or(metadata.exif.lens_model)
+                    .filter(|l| !l.is_empty()),
+                iso: metadata.exif.iso_speed_ratings.map(u32::from).or(metadata.exif.iso_speed),
+                capture_date: metadata.exif.date_time_original.as_deref().and_then(exif_datetime_to_date),
+                focal_length: metadata.exif.focal_length.filter(|f| f.d != 0).map(|f| f.n as f32 / f.d as f32),
+                aperture: metadata.exif.fnumber.filter(|f| f.d != 0).map(|f| f.n as f32 / f.d as f32),
+            };
+        }
+    }
+
+    let Ok(exif) = ExifReader::new().read_from_container(&mut Cursor::new(file_bytes)) else {
+        return CaptureInfo::default();
+    };
+    let read_ascii = |tag: Tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .and_then(|f| match &f.value {
+                exif::Value::Ascii(values) => values.first().map(|v| String::from_utf8_lossy(v).trim().to_string()),
+                _ => None,
+            })
+            .filter(|v| !v.is_empty())
+    };
+    let read_rational = |tag: Tag| {
+        exif.get_field(tag, exif::In::PRIMARY)
+            .and_then(|f| match &f.value {
+                exif::Value::Rational(values) => values.first().map(|v| v.to_f64() as f32),
+                _ => None,
+            })
+            .filter(|v| v.is_finite() && *v > 0.0)
+    };
+
+    CaptureInfo {
+        make: read_ascii(Tag::Make).unwrap_or_default(),
+        model: read_ascii(Tag::Model).unwrap_or_default(),
+        lens: read_ascii(Tag::LensModel),
+        iso: exif
+            .get_field(Tag::PhotographicSensitivity, exif::In::PRIMARY)
+            .and_then(|f| f.value.get_uint(0)),
+        capture_date: read_ascii(Tag::DateTimeOriginal)
+            .or_else(|| read_ascii(Tag::DateTime))
+            .as_deref()
+            .and_then(exif_datetime_to_date),
+        focal_length: read_rational(Tag::FocalLength),
+        aperture: read_rational(Tag::FNumber),
+    }
+}
 
 pub fn load_and_composite(
     path: &str,
@@ -19,19 +95,32 @@ pub fn load_and_composite(
     use_fast_raw_dev: bool,
 ) -> Result<DynamicImage> {
     let file_bytes = fs::read(path)?;
-    let base_image = load_base_image_from_bytes(&file_bytes, path, use_fast_raw_dev)?;
+    let base_image = load_corrected_base_image(&file_bytes, path, use_fast_raw_dev, adjustments)?;
     composite_patches_on_image(&base_image, adjustments)
 }
 
+// The base image with everything the adjustments change about developing it: the raw
+// develop overrides and the lens correction.
+pub fn load_corrected_base_image(
+    bytes: &[u8],
+    path: &str,
+    use_fast_raw_dev: bool,
+    adjustments: &Value,
+) -> Result<DynamicImage> {
+    let image = load_base_image_from_bytes(bytes, path, use_fast_raw_dev, DevelopOverrides::from_adjustments(adjustments))?;
+    Ok(performance::time(Stage::Develop, || lens_correction::correct_image(image, bytes, path, adjustments)))
+}
+
 pub fn load_base_image_from_bytes(
     bytes: &[u8],
     path_for_ext_check: &str,
     use_fast_raw_dev: bool,
+    overrides: DevelopOverrides,
 ) -> Result<DynamicImage> {
     if is_raw_file(path_for_ext_check) {
-        develop_raw_image(bytes, use_fast_raw_dev)
+        develop_raw_image(bytes, use_fast_raw_dev, overrides)
     } else {
-        load_image_with_orientation(bytes)
+        performance::time(Stage::Decode, || load_image_with_orientation(bytes))
     }
 }
 
@@ -98,10 +187,39 @@ pub fn composite_patches_on_image(
         .collect();
 
     let patch_layers = patch_layers?;
-    let mut composited_rgba = base_image.to_rgba8();
-    for patch_layer in &patch_layers {
-        imageops::overlay(&mut composited_rgba, patch_layer, 0, 0);
-    }
+    let mut composited = base_image.to_rgba16();
+    let width = composited.width() as usize;
+
+    // Patches are blended in linear light. Mixing the gamma encoded values darkens the
+    // feathered edges, which shows as a dark halo on bright backgrounds.
+    let base_to_linear: Vec<f32> = (0..=u16::MAX).map(|v| srgb_to_linear(v as f32 / 65535.0)).collect();
+    let patch_to_linear: Vec<f32> = (0..=u8::MAX).map(|v| srgb_to_linear(v as f32 / 255.0)).collect();
+
+    composited
+        .par_chunks_exact_mut(width * 4)
+        .enumerate()
+        .for_each(|(y, row)| {
+            for patch_layer in &patch_layers {
+                if y as u32 >= patch_layer.height() {
+                    continue;
+                }
+                for x in 0..width.min(patch_layer.width() as usize) {
+                    let patch_pixel = patch_layer.get_pixel(x as u32, y as u32);
+                    let alpha = patch_pixel[3] as f32 / 255.0;
+                    if alpha <= 0.0 {
+                        continue;
+                    }
+                    let pixel = &mut row[x * 4..x * 4 + 4];
+                    for c in 0..3 {
+                        let base = base_to_linear[pixel[c] as usize];
+                        let top = patch_to_linear[patch_pixel[c] as usize];
+                        let mixed = base + (top - base) * alpha;
+                        pixel[c] = (linear_to_srgb(mixed) * 65535.0).round() as u16;
+                    }
+                    pixel[3] = (pixel[3] as f32 + (65535.0 - pixel[3] as f32) * alpha).round() as u16;
+                }
+            }
+        });
 
-    Ok(DynamicImage::ImageRgba8(composited_rgba))
+    Ok(DynamicImage::ImageRgba16(composited))
 }
\ No newline at end of file
@@ -1,6 +1,7 @@
-use std::sync::Arc;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
 use bytemuck::{Pod, Zeroable};
-use image::{DynamicImage, GenericImageView, Rgba};
+use image::{DynamicImage, GenericImageView, GrayImage, Luma, Rgba};
 use imageproc::geometric_transformations::{rotate_about_center, Interpolation};
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
@@ -8,14 +9,28 @@ use std::f32::consts::PI;
 use rawler::decoders::Orientation;
 use serde_json::json;
 
-pub use crate::gpu_processing::{get_or_init_gpu_context, process_and_get_dynamic_image};
-use crate::{AppState, mask_generation::MaskDefinition, load_settings};
+pub use crate::gpu_processing::{get_or_init_gpu_context, process_and_get_dynamic_image, process_and_get_dynamic_image_16bit};
+use crate::gpu_processing::{FrameResources, ProcessingPipeline, ResizePipeline};
+use crate::raw_processing::ToneMapper;
+use crate::{AppState, mask_generation::{dodge_burn_sub_mask, MaskDefinition}, load_settings, resolve_preview_dimension};
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct ImageMetadata {
     pub version: u32,
     pub rating: u8,
     pub adjustments: Value,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub gps: Option<GpsCoordinates>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub label: Option<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub keywords: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub versions: Vec<ImageVersion>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub notes: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub flag: Option<ImageFlag>,
 }
 
 impl Default for ImageMetadata {
@@ -24,10 +39,37 @@ impl Default for ImageMetadata {
             version: 1,
             rating: 0,
             adjustments: Value::Null,
+            gps: None,
+            label: None,
+            keywords: Vec::new(),
+            versions: Vec::new(),
+            notes: None,
+            flag: None,
         }
     }
 }
 
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ImageFlag {
+    Pick,
+    Reject,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+pub struct ImageVersion {
+    pub id: String,
+    pub name: String,
+    pub adjustments: Value,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+pub struct GpsCoordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+    pub altitude: Option<f64>,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy)]
 pub struct Crop {
     pub x: f64,
@@ -101,6 +143,31 @@ pub fn apply_flip(image: DynamicImage, horizontal: bool, vertical: bool) -> Dyna
     img
 }
 
+// How auto adjust meters exposure. The other auto values are always taken from the whole frame.
+#[derive(Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum AutoExposureMode {
+    #[default]
+    Global,
+    // Caps exposure so the brightest part of the frame does not clip, e.g. skies.
+    ProtectHighlights,
+    // Meters on the salient subject found by the u2netp foreground model. This stands in for
+    // the requested face-weighted metering: there is no face detector in the app yet. Metering
+    // on detected faces is a follow-up once one is added.
+    SubjectWeighted,
+    // Meters only inside a region picked by the user.
+    Spot,
+}
+
+// Normalized to the cropped and rotated frame, 0..1 on both axes.
+#[derive(Deserialize, Debug, Clone, Copy)]
+pub struct MeteringRegion {
+    pub x: f32,
+    pub y: f32,
+    pub width: f32,
+    pub height: f32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone)]
 pub struct AutoAdjustmentResults {
     pub exposure: f64,
@@ -141,6 +208,20 @@ pub struct ColorGradeSettings {
     _pad: f32,
 }
 
+// Input and output range of one levels channel, normalized to 0..1.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Pod, Zeroable, Default)]
+#[repr(C)]
+pub struct LevelsChannel {
+    pub in_black: f32,
+    pub in_white: f32,
+    pub gamma: f32,
+    pub out_black: f32,
+    pub out_white: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+}
+
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Pod, Zeroable, Default)]
 #[repr(C)]
 pub struct GlobalAdjustments {
@@ -156,11 +237,17 @@ pub struct GlobalAdjustments {
     pub vibrance: f32,
     
     pub sharpness: f32,
+    pub sharpen_radius: f32,
+    pub sharpen_detail: f32,
+    pub sharpen_masking: f32,
     pub luma_noise_reduction: f32,
+    pub luma_noise_reduction_detail: f32,
     pub color_noise_reduction: f32,
+    pub color_noise_reduction_smoothness: f32,
     pub clarity: f32,
     pub dehaze: f32,
     pub structure: f32,
+    pub texture: f32,
     pub vignette_amount: f32,
     pub vignette_midpoint: f32,
     pub vignette_roundness: f32,
@@ -176,8 +263,6 @@ pub struct GlobalAdjustments {
     pub negative_red_balance: f32,
     pub negative_green_balance: f32,
     pub negative_blue_balance: f32,
-    _pad_neg1: f32,
-    _pad_neg2: f32,
 
     pub color_grading_shadows: ColorGradeSettings,
     pub color_grading_midtones: ColorGradeSettings,
@@ -187,6 +272,28 @@ pub struct GlobalAdjustments {
     _pad2: f32,
     _pad3: f32,
 
+    pub split_toning_shadow_hue: f32,
+    pub split_toning_shadow_saturation: f32,
+    pub split_toning_highlight_hue: f32,
+    pub split_toning_highlight_saturation: f32,
+    pub split_toning_balance: f32,
+    _pad_st1: f32,
+    _pad_st2: f32,
+    _pad_st3: f32,
+
+    pub defringe_purple_amount: f32,
+    pub defringe_purple_hue: f32,
+    pub defringe_purple_range: f32,
+    pub defringe_green_amount: f32,
+    pub defringe_green_hue: f32,
+    pub defringe_green_range: f32,
+    pub lut_intensity: f32,
+    // Grid size of the LUT bound to the pipeline, 0 without one. Set when processing.
+    pub lut_size: u32,
+
+    // Master, red, green and blue
+    pub levels: [LevelsChannel; 4],
+
     pub hsl: [HslColor; 8],
     pub luma_curve: [Point; 16],
     pub red_curve: [Point; 16],
@@ -196,6 +303,28 @@ pub struct GlobalAdjustments {
     pub red_curve_count: u32,
     pub green_curve_count: u32,
     pub blue_curve_count: u32,
+    // 1 when the luma curve only changes L of CIELAB, which leaves saturation alone.
+    pub luma_curve_lab: u32,
+    _pad_c1: u32,
+    _pad_c2: u32,
+    _pad_c3: u32,
+
+    // Points are 0..255 on both axes and 127.5 leaves a color alone. The hue curves wrap around.
+    pub hue_vs_hue: [Point; 16],
+    pub hue_vs_sat: [Point; 16],
+    pub lum_vs_sat: [Point; 16],
+    pub hue_vs_hue_count: u32,
+    pub hue_vs_sat_count: u32,
+    pub lum_vs_sat_count: u32,
+    _pad_hc: u32,
+
+    pub camera_matrix: [[f32; 4]; 3],
+    pub camera_matrix_enabled: u32,
+    pub enabled_stages: u32,
+    // Set by the GPU pipeline for grayscale images, which are uploaded as a single channel.
+    pub monochrome: u32,
+    // A ToneMapper id for HDR sources, 0 when developing already mapped the image.
+    pub tone_mapper: u32,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone, Copy, Pod, Zeroable, Default)]
@@ -218,11 +347,12 @@ pub struct MaskAdjustments {
     pub clarity: f32,
     pub dehaze: f32,
     pub structure: f32,
+    pub texture: f32,
     
-    _pad1: f32,
-    _pad2: f32,
+    pub enabled_stages: u32,
+    // 1 for dodge & burn masks, whose bitmap is centered on 128 and burns below it.
+    pub signed_influence: u32,
     _pad3: f32,
-    _pad4: f32,
 
     pub color_grading_shadows: ColorGradeSettings,
     pub color_grading_midtones: ColorGradeSettings,
@@ -241,6 +371,11 @@ pub struct MaskAdjustments {
     pub red_curve_count: u32,
     pub green_curve_count: u32,
     pub blue_curve_count: u32,
+    // 1 when the luma curve only changes L of CIELAB, which leaves saturation alone.
+    pub luma_curve_lab: u32,
+    _pad_c1: u32,
+    _pad_c2: u32,
+    _pad_c3: u32,
 }
 
 #[derive(Debug, Clone, Copy, Pod, Zeroable, Default)]
@@ -251,7 +386,12 @@ pub struct AllAdjustments {
     pub mask_count: u32,
     pub tile_offset_x: u32,
     pub tile_offset_y: u32,
-    _pad1: u32,
+    pub image_width: u32,
+    pub image_height: u32,
+    // Preview only, 1 flags clipped pixels for paint_clipping_overlay.
+    pub clipping_overlay: u32,
+    _pad2: u32,
+    _pad3: u32,
 }
 
 struct AdjustmentScales {
@@ -267,11 +407,16 @@ struct AdjustmentScales {
     vibrance: f32,
     
     sharpness: f32,
+    sharpen_detail: f32,
+    sharpen_masking: f32,
     luma_noise_reduction: f32,
+    luma_noise_reduction_detail: f32,
     color_noise_reduction: f32,
+    color_noise_reduction_smoothness: f32,
     clarity: f32,
     dehaze: f32,
     structure: f32,
+    texture: f32,
 
     vignette_amount: f32,
     vignette_midpoint: f32,
@@ -289,6 +434,12 @@ struct AdjustmentScales {
     color_grading_luminance: f32,
     color_grading_blending: f32,
     color_grading_balance: f32,
+
+    split_toning_saturation: f32,
+    split_toning_balance: f32,
+
+    defringe_amount: f32,
+    lut_intensity: f32,
 }
 
 const SCALES: AdjustmentScales = AdjustmentScales {
@@ -304,11 +455,16 @@ const SCALES: AdjustmentScales = AdjustmentScales {
     vibrance: 80.0,
     
     sharpness: 40.0,
+    sharpen_detail: 100.0,
+    sharpen_masking: 100.0,
     luma_noise_reduction: 100.0,
+    luma_noise_reduction_detail: 100.0,
     color_noise_reduction: 100.0,
+    color_noise_reduction_smoothness: 100.0,
     clarity: 75.0,
     dehaze: 750.0,
     structure: 75.0,
+    texture: 100.0,
 
     vignette_amount: 100.0,
     vignette_midpoint: 100.0,
@@ -326,8 +482,31 @@ const SCALES: AdjustmentScales = AdjustmentScales {
     color_grading_luminance: 500.0,
     color_grading_blending: 100.0,
     color_grading_balance: 200.0,
+
+    split_toning_saturation: 100.0,
+    split_toning_balance: 100.0,
+
+    defringe_amount: 100.0,
+    lut_intensity: 100.0,
 };
 
+fn parse_levels(js_levels: &serde_json::Value) -> [LevelsChannel; 4] {
+    ["luma", "red", "green", "blue"].map(|channel| {
+        let js_channel = &js_levels[channel];
+        let value = |key: &str, default: f64| js_channel[key].as_f64().unwrap_or(default) as f32;
+        LevelsChannel {
+            in_black: value("inBlack", 0.0) / 255.0,
+            in_white: value("inWhite", 255.0) / 255.0,
+            gamma: value("gamma", 1.0),
+            out_black: value("outBlack", 0.0) / 255.0,
+            out_white: value("outWhite", 255.0) / 255.0,
+            _pad1: 0.0,
+            _pad2: 0.0,
+            _pad3: 0.0,
+        }
+    })
+}
+
 fn parse_hsl_adjustments(js_hsl: &serde_json::Value) -> [HslColor; 8] {
     let mut hsl_array = [HslColor::default(); 8];
     if let Some(hsl_map) = js_hsl.as_object() {
@@ -371,9 +550,100 @@ fn convert_points_to_aligned(frontend_points: Vec<serde_json::Value>) -> [Point;
     aligned_points
 }
 
+// Points of one of the hue curves, or no points when the color section is hidden.
+fn parse_hue_curve(js_adjustments: &serde_json::Value, key: &str, visible: bool) -> ([Point; 16], u32) {
+    let points = if visible { js_adjustments["hueCurves"][key].as_array().cloned().unwrap_or_default() } else { Vec::new() };
+    let count = points.len().min(16) as u32;
+    (convert_points_to_aligned(points), count)
+}
+
+// Bits of `enabled_stages`, matching the STAGE_* constants in shader.wgsl. A cleared bit makes
+// the shader skip that stage entirely instead of running it with neutral values.
+const STAGE_NOISE_REDUCTION: u32 = 1 << 0;
+const STAGE_WHITE_BALANCE: u32 = 1 << 1;
+const STAGE_TONE: u32 = 1 << 2;
+const STAGE_DEHAZE: u32 = 1 << 3;
+const STAGE_SHARPENING: u32 = 1 << 4;
+const STAGE_CLARITY: u32 = 1 << 5;
+const STAGE_SATURATION: u32 = 1 << 6;
+const STAGE_HSL: u32 = 1 << 7;
+const STAGE_COLOR_GRADING: u32 = 1 << 8;
+const STAGE_CURVES: u32 = 1 << 9;
+const STAGE_GRAIN: u32 = 1 << 10;
+const STAGE_VIGNETTE: u32 = 1 << 11;
+const STAGE_SPLIT_TONING: u32 = 1 << 12;
+const STAGE_DEFRINGE: u32 = 1 << 13;
+const STAGE_LUT: u32 = 1 << 14;
+const STAGE_LEVELS: u32 = 1 << 15;
+const STAGE_HUE_CURVES: u32 = 1 << 16;
+const ALL_STAGES: u32 = (1 << 17) - 1;
+
+// (section, group, stages). Groups are toggled through `enabledGroups` without touching their
+// values, so an effect can be compared on and off and presets can carry switched-off groups.
+const ADJUSTMENT_GROUPS: &[(&str, &str, u32)] = &[
+    ("basic", "tone", STAGE_TONE),
+    ("curves", "curves", STAGE_CURVES),
+    ("curves", "levels", STAGE_LEVELS),
+    ("color", "whiteBalance", STAGE_WHITE_BALANCE),
+    ("color", "saturation", STAGE_SATURATION),
+    ("color", "hsl", STAGE_HSL),
+    ("color", "hueCurves", STAGE_HUE_CURVES),
+    ("color", "colorGrading", STAGE_COLOR_GRADING),
+    ("color", "splitToning", STAGE_SPLIT_TONING),
+    ("details", "sharpening", STAGE_SHARPENING),
+    ("details", "noiseReduction", STAGE_NOISE_REDUCTION),
+    ("details", "defringe", STAGE_DEFRINGE),
+    ("effects", "presence", STAGE_DEHAZE | STAGE_CLARITY),
+    ("effects", "vignette", STAGE_VIGNETTE),
+    ("effects", "grain", STAGE_GRAIN),
+    ("effects", "lut", STAGE_LUT),
+];
+
+fn enabled_stages(js_adjustments: &serde_json::Value) -> u32 {
+    let is_on = |key: &str, name: &str| -> bool {
+        js_adjustments
+            .get(key)
+            .and_then(|v| v.get(name))
+            .and_then(|s| s.as_bool())
+            .unwrap_or(true)
+    };
+    ADJUSTMENT_GROUPS
+        .iter()
+        .filter(|(section, group, _)| is_on("sectionVisibility", section) && is_on("enabledGroups", group))
+        .fold(0, |stages, (_, _, bits)| stages | bits)
+}
+
+// Stages the shader is compiled with for these adjustments. On top of the switched off groups,
+// stages whose amounts are all zero are left out, so the neighbourhood reads of sharpening,
+// noise reduction and clarity are not in the pipeline at all when no edit uses them.
+pub fn active_stages(adjustments: &AllAdjustments) -> u32 {
+    let unused = |used: bool, stage: u32| if used { 0 } else { stage };
+
+    let g = &adjustments.global;
+    let mut stages = g.enabled_stages
+        & !(unused(g.luma_noise_reduction > 0.0 || g.color_noise_reduction > 0.0, STAGE_NOISE_REDUCTION)
+            | unused(g.sharpness > 0.0, STAGE_SHARPENING)
+            | unused(g.clarity != 0.0 || g.structure != 0.0 || g.texture != 0.0, STAGE_CLARITY)
+            | unused(g.dehaze != 0.0, STAGE_DEHAZE)
+            | unused(g.defringe_purple_amount > 0.0 || g.defringe_green_amount > 0.0, STAGE_DEFRINGE)
+            | unused(g.hue_vs_hue_count + g.hue_vs_sat_count + g.lum_vs_sat_count > 0, STAGE_HUE_CURVES)
+            | unused(g.grain_amount > 0.0, STAGE_GRAIN)
+            | unused(g.vignette_amount != 0.0, STAGE_VIGNETTE)
+            | unused(g.lut_size > 0, STAGE_LUT));
+
+    for mask in adjustments.mask_adjustments.iter().take(adjustments.mask_count as usize) {
+        stages |= mask.enabled_stages
+            & !(unused(mask.luma_noise_reduction > 0.0 || mask.color_noise_reduction > 0.0, STAGE_NOISE_REDUCTION)
+                | unused(mask.sharpness > 0.0, STAGE_SHARPENING)
+                | unused(mask.clarity != 0.0 || mask.structure != 0.0 || mask.texture != 0.0, STAGE_CLARITY)
+                | unused(mask.dehaze != 0.0, STAGE_DEHAZE));
+    }
+    stages
+}
+
 fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> GlobalAdjustments {
     if js_adjustments.is_null() {
-        return GlobalAdjustments::default();
+        return GlobalAdjustments { enabled_stages: ALL_STAGES, ..Default::default() };
     }
 
     let visibility = js_adjustments.get("sectionVisibility");
@@ -398,7 +668,36 @@ fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> Globa
     let green_points: Vec<serde_json::Value> = if is_visible("curves") { curves_obj["green"].as_array().cloned().unwrap_or_default() } else { Vec::new() };
     let blue_points: Vec<serde_json::Value> = if is_visible("curves") { curves_obj["blue"].as_array().cloned().unwrap_or_default() } else { Vec::new() };
 
+    let (hue_vs_hue, hue_vs_hue_count) = parse_hue_curve(js_adjustments, "hueVsHue", is_visible("color"));
+    let (hue_vs_sat, hue_vs_sat_count) = parse_hue_curve(js_adjustments, "hueVsSat", is_visible("color"));
+    let (lum_vs_sat, lum_vs_sat_count) = parse_hue_curve(js_adjustments, "lumVsSat", is_visible("color"));
+
     let cg_obj = js_adjustments.get("colorGrading").cloned().unwrap_or_default();
+    let st_obj = js_adjustments.get("splitToning").cloned().unwrap_or_default();
+    let split_toning_val = |key: &str, scale: f32| -> f32 {
+        if is_visible("color") { st_obj[key].as_f64().unwrap_or(0.0) as f32 / scale } else { 0.0 }
+    };
+    let df_obj = js_adjustments.get("defringe").cloned().unwrap_or_default();
+    let defringe_val = |key: &str, scale: f32, default: f32| -> f32 {
+        if is_visible("details") { df_obj[key].as_f64().map_or(default, |v| v as f32 / scale) } else { default }
+    };
+
+    let camera_matrix = if is_visible("calibration") {
+        js_adjustments.get("cameraProfile").and_then(parse_camera_matrix)
+    } else {
+        None
+    };
+    let calibration_enabled = js_adjustments["enabledGroups"]["calibration"].as_bool().unwrap_or(true);
+    let calibration = if is_visible("color") && calibration_enabled {
+        calibration_matrix(&js_adjustments["calibration"])
+    } else {
+        None
+    };
+    let camera_matrix = match (camera_matrix, calibration) {
+        (Some(profile), Some(calibration)) => Some(multiply_camera_matrix(calibration, profile)),
+        (None, Some(calibration)) => Some(calibration),
+        (profile, None) => profile,
+    };
 
     let neg_conv_enabled = js_adjustments["enableNegativeConversion"].as_bool().unwrap_or(false);
     let film_base_hex = js_adjustments["filmBaseColor"].as_str().unwrap_or("#ff8800");
@@ -425,12 +724,18 @@ fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> Globa
         vibrance: get_val("color", "vibrance", SCALES.vibrance, None),
         
         sharpness: get_val("details", "sharpness", SCALES.sharpness, None),
+        sharpen_radius: get_val("details", "sharpenRadius", 1.0, Some(1.0)),
+        sharpen_detail: get_val("details", "sharpenDetail", SCALES.sharpen_detail, Some(25.0)),
+        sharpen_masking: get_val("details", "sharpenMasking", SCALES.sharpen_masking, None),
         luma_noise_reduction: get_val("details", "lumaNoiseReduction", SCALES.luma_noise_reduction, None),
+        luma_noise_reduction_detail: get_val("details", "lumaNoiseReductionDetail", SCALES.luma_noise_reduction_detail, Some(50.0)),
         color_noise_reduction: get_val("details", "colorNoiseReduction", SCALES.color_noise_reduction, None),
+        color_noise_reduction_smoothness: get_val("details", "colorNoiseReductionSmoothness", SCALES.color_noise_reduction_smoothness, Some(50.0)),
         
         clarity: get_val("effects", "clarity", SCALES.clarity, None),
         dehaze: get_val("effects", "dehaze", SCALES.dehaze, None),
         structure: get_val("effects", "structure", SCALES.structure, None),
+        texture: get_val("effects", "texture", SCALES.texture, None),
         vignette_amount: get_val("effects", "vignetteAmount", SCALES.vignette_amount, None),
         vignette_midpoint: get_val("effects", "vignetteMidpoint", SCALES.vignette_midpoint, Some(50.0)),
         vignette_roundness: get_val("effects", "vignetteRoundness", SCALES.vignette_roundness, Some(0.0)),
@@ -446,8 +751,6 @@ fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> Globa
         negative_red_balance: js_adjustments["negativeRedBalance"].as_f64().unwrap_or(0.0) as f32 / 100.0,
         negative_green_balance: js_adjustments["negativeGreenBalance"].as_f64().unwrap_or(0.0) as f32 / 100.0,
         negative_blue_balance: js_adjustments["negativeBlueBalance"].as_f64().unwrap_or(0.0) as f32 / 100.0,
-        _pad_neg1: 0.0,
-        _pad_neg2: 0.0,
 
         color_grading_shadows: if is_visible("color") { parse_color_grade_settings(&cg_obj["shadows"]) } else { ColorGradeSettings::default() },
         color_grading_midtones: if is_visible("color") { parse_color_grade_settings(&cg_obj["midtones"]) } else { ColorGradeSettings::default() },
@@ -457,6 +760,26 @@ fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> Globa
         _pad2: 0.0,
         _pad3: 0.0,
 
+        split_toning_shadow_hue: split_toning_val("shadowHue", 1.0),
+        split_toning_shadow_saturation: split_toning_val("shadowSaturation", SCALES.split_toning_saturation),
+        split_toning_highlight_hue: split_toning_val("highlightHue", 1.0),
+        split_toning_highlight_saturation: split_toning_val("highlightSaturation", SCALES.split_toning_saturation),
+        split_toning_balance: split_toning_val("balance", SCALES.split_toning_balance),
+        _pad_st1: 0.0,
+        _pad_st2: 0.0,
+        _pad_st3: 0.0,
+
+        defringe_purple_amount: defringe_val("purpleAmount", SCALES.defringe_amount, 0.0),
+        defringe_purple_hue: defringe_val("purpleHue", 1.0, 290.0),
+        defringe_purple_range: defringe_val("purpleRange", 1.0, 60.0),
+        defringe_green_amount: defringe_val("greenAmount", SCALES.defringe_amount, 0.0),
+        defringe_green_hue: defringe_val("greenHue", 1.0, 120.0),
+        defringe_green_range: defringe_val("greenRange", 1.0, 60.0),
+        lut_intensity: if is_visible("effects") { js_adjustments["lut"]["intensity"].as_f64().unwrap_or(100.0) as f32 / SCALES.lut_intensity } else { 0.0 },
+        lut_size: 0,
+
+        levels: parse_levels(&js_adjustments["levels"]),
+
         hsl: if is_visible("color") { parse_hsl_adjustments(&js_adjustments.get("hsl").cloned().unwrap_or_default()) } else { [HslColor::default(); 8] },
         luma_curve: convert_points_to_aligned(luma_points.clone()),
         red_curve: convert_points_to_aligned(red_points.clone()),
@@ -466,7 +789,93 @@ fn get_global_adjustments_from_json(js_adjustments: &serde_json::Value) -> Globa
         red_curve_count: red_points.len() as u32,
         green_curve_count: green_points.len() as u32,
         blue_curve_count: blue_points.len() as u32,
+        luma_curve_lab: (js_adjustments["lumaCurveMode"].as_str() == Some("luminosity")) as u32,
+        _pad_c1: 0,
+        _pad_c2: 0,
+        _pad_c3: 0,
+
+        hue_vs_hue,
+        hue_vs_sat,
+        lum_vs_sat,
+        hue_vs_hue_count,
+        hue_vs_sat_count,
+        lum_vs_sat_count,
+        _pad_hc: 0,
+
+        camera_matrix: camera_matrix.unwrap_or_default(),
+        camera_matrix_enabled: if camera_matrix.is_some() { 1 } else { 0 },
+        enabled_stages: enabled_stages(js_adjustments),
+        monochrome: 0,
+        // Not tied to the section visibility: developing left the mapping to the shader.
+        tone_mapper: serde_json::from_value::<ToneMapper>(js_adjustments["toneMapper"].clone()).map_or(0, |t| t as u32),
+    }
+}
+
+// Primaries turn by at most this much around the neutral axis at a hue of ±100.
+const CALIBRATION_MAX_HUE_DEGREES: f32 = 30.0;
+
+// Hue and saturation of the red, green and blue primaries, as in a calibration panel. Each
+// primary is turned around the neutral axis and its distance from gray scaled, then the rows
+// are normalized so neutral colors stay neutral. None when all sliders are at zero.
+fn calibration_matrix(calibration: &serde_json::Value) -> Option<[[f32; 4]; 3]> {
+    let settings = ["red", "green", "blue"].map(|primary| {
+        let value = |name: &str| calibration[format!("{}{}", primary, name)].as_f64().unwrap_or(0.0) as f32 / 100.0;
+        (value("Hue"), value("Saturation"))
+    });
+    if settings.iter().all(|&(hue, saturation)| hue == 0.0 && saturation == 0.0) {
+        return None;
+    }
+
+    // Rotation around (1, 1, 1) / sqrt(3): cos * I + sin * [u]x + (1 - cos) * u * u^T
+    let cross = [[0.0, -1.0, 1.0], [1.0, 0.0, -1.0], [-1.0, 1.0, 0.0]];
+    let gray = 1.0 / 3.0;
+    let mut matrix = [[0.0f32; 4]; 3];
+    for (column, &(hue, saturation)) in settings.iter().enumerate() {
+        let (sin, cos) = (hue * CALIBRATION_MAX_HUE_DEGREES).to_radians().sin_cos();
+        for (row, values) in matrix.iter_mut().enumerate() {
+            let identity = if row == column { 1.0 } else { 0.0 };
+            let turned = cos * identity + sin * cross[row][column] / 3.0f32.sqrt() + (1.0 - cos) * gray;
+            values[column] = gray + (1.0 + saturation) * (turned - gray);
+        }
+    }
+    for row in matrix.iter_mut() {
+        let sum: f32 = row[..3].iter().sum();
+        if sum.abs() > 1e-6 {
+            row[..3].iter_mut().for_each(|v| *v /= sum);
+        }
+    }
+    Some(matrix)
+}
+
+// `a * b`, so the result applies `b` first.
+fn multiply_camera_matrix(a: [[f32; 4]; 3], b: [[f32; 4]; 3]) -> [[f32; 4]; 3] {
+    let mut result = [[0.0f32; 4]; 3];
+    for (r, row) in result.iter_mut().enumerate() {
+        for (c, value) in row.iter_mut().take(3).enumerate() {
+            *value = (0..3).map(|k| a[r][k] * b[k][c]).sum();
+        }
+    }
+    result
+}
+
+// The selected camera profile is stored in the adjustments together with its matrix, so
+// edits keep rendering the same even if the profile is later deleted or recreated.
+fn parse_camera_matrix(profile: &serde_json::Value) -> Option<[[f32; 4]; 3]> {
+    let rows = profile.get("matrix")?.as_array()?;
+    if rows.len() != 3 {
+        return None;
+    }
+    let mut matrix = [[0.0f32; 4]; 3];
+    for (r, row) in rows.iter().enumerate() {
+        let values = row.as_array()?;
+        if values.len() != 3 {
+            return None;
+        }
+        for (c, value) in values.iter().enumerate() {
+            matrix[r][c] = value.as_f64()? as f32;
+        }
     }
+    Some(matrix)
 }
 
 fn get_mask_adjustments_from_json(adj: &serde_json::Value) -> MaskAdjustments {
@@ -517,8 +926,11 @@ fn get_mask_adjustments_from_json(adj: &serde_json::Value) -> MaskAdjustments {
         clarity: get_val("effects", "clarity", SCALES.clarity),
         dehaze: get_val("effects", "dehaze", SCALES.dehaze),
         structure: get_val("effects", "structure", SCALES.structure),
+        texture: get_val("effects", "texture", SCALES.texture),
         
-        _pad1: 0.0, _pad2: 0.0, _pad3: 0.0, _pad4: 0.0,
+        enabled_stages: enabled_stages(adj),
+        signed_influence: 0,
+        _pad3: 0.0,
 
         color_grading_shadows: if is_visible("color") { parse_color_grade_settings(&cg_obj["shadows"]) } else { ColorGradeSettings::default() },
         color_grading_midtones: if is_visible("color") { parse_color_grade_settings(&cg_obj["midtones"]) } else { ColorGradeSettings::default() },
@@ -537,6 +949,22 @@ fn get_mask_adjustments_from_json(adj: &serde_json::Value) -> MaskAdjustments {
         red_curve_count: red_points.len() as u32,
         green_curve_count: green_points.len() as u32,
         blue_curve_count: blue_points.len() as u32,
+        luma_curve_lab: (adj["lumaCurveMode"].as_str() == Some("luminosity")) as u32,
+        _pad_c1: 0,
+        _pad_c2: 0,
+        _pad_c3: 0,
+    }
+}
+
+// Dodge & burn paints exposure and contrast from its brush parameters. The shader turns the
+// exposure around where the bitmap burns; contrast is added the same way in both.
+fn get_dodge_burn_adjustments(params: &serde_json::Value) -> MaskAdjustments {
+    MaskAdjustments {
+        exposure: params["exposure"].as_f64().unwrap_or(1.0) as f32 / SCALES.exposure,
+        contrast: params["contrast"].as_f64().unwrap_or(0.0) as f32 / SCALES.contrast,
+        enabled_stages: STAGE_TONE,
+        signed_influence: 1,
+        ..Default::default()
     }
 }
 
@@ -550,7 +978,10 @@ pub fn get_all_adjustments_from_json(js_adjustments: &serde_json::Value) -> AllA
         .unwrap_or_else(Vec::new);
 
     for (i, mask_def) in mask_definitions.iter().filter(|m| m.visible).enumerate().take(16) {
-        mask_adjustments[i] = get_mask_adjustments_from_json(&mask_def.adjustments);
+        mask_adjustments[i] = match dodge_burn_sub_mask(mask_def) {
+            Some(sub_mask) => get_dodge_burn_adjustments(&sub_mask.parameters),
+            None => get_mask_adjustments_from_json(&mask_def.adjustments),
+        };
         mask_count += 1;
     }
 
@@ -560,7 +991,11 @@ pub fn get_all_adjustments_from_json(js_adjustments: &serde_json::Value) -> AllA
         mask_count,
         tile_offset_x: 0,
         tile_offset_y: 0,
-        _pad1: 0,
+        image_width: 0,
+        image_height: 0,
+        clipping_overlay: 0,
+        _pad2: 0,
+        _pad3: 0,
     }
 }
 
@@ -569,6 +1004,9 @@ pub struct GpuContext {
     pub device: Arc<wgpu::Device>,
     pub queue: Arc<wgpu::Queue>,
     pub limits: wgpu::Limits,
+    pub(crate) pipelines: Arc<Mutex<HashMap<u32, Arc<ProcessingPipeline>>>>,
+    pub(crate) frame_resources: Arc<Mutex<Option<FrameResources>>>,
+    pub(crate) resize_pipeline: Arc<Mutex<Option<Arc<ResizePipeline>>>>,
 }
 
 #[derive(Serialize, Clone)]
@@ -592,12 +1030,49 @@ pub fn generate_histogram(state: tauri::State<AppState>, app_handle: tauri::AppH
             .image.clone();
 
         let settings = load_settings(app_handle).unwrap_or_default();
-        let preview_dim = settings.editor_preview_resolution.unwrap_or(1920);
+        let preview_dim = resolve_preview_dimension(&state, &settings);
         let preview = image.thumbnail(preview_dim, preview_dim);
         calculate_histogram_from_image(&preview)
     }
 }
 
+// Alpha the shader writes for clipped pixels when the clipping overlay is on. Flagging them
+// instead of painting keeps the real colors for the histogram and waveform of the frame.
+const CLIPPED_HIGHLIGHT_ALPHA: u8 = 0;
+const CLIPPED_SHADOW_ALPHA: u8 = 128;
+
+// Paints the pixels the shader flagged, highlights red and shadows blue, and makes the frame
+// opaque again.
+pub fn paint_clipping_overlay(image: &mut DynamicImage) {
+    let DynamicImage::ImageRgba8(buffer) = image else { return };
+    for pixel in buffer.pixels_mut() {
+        match pixel[3] {
+            CLIPPED_HIGHLIGHT_ALPHA => *pixel = Rgba([255, 0, 0, 255]),
+            CLIPPED_SHADOW_ALPHA => *pixel = Rgba([0, 0, 255, 255]),
+            _ => pixel[3] = 255,
+        }
+    }
+}
+
+// Before/after preview: copies the right half of the comparison render over the edited one and
+// draws a divider between them. Both are renders of the same preview.
+pub fn compose_split_preview(edited: &mut DynamicImage, comparison: &DynamicImage) {
+    let (DynamicImage::ImageRgba8(left), DynamicImage::ImageRgba8(right)) = (edited, comparison) else { return };
+    if left.dimensions() != right.dimensions() {
+        return;
+    }
+    let (width, height) = left.dimensions();
+    let split = width / 2;
+    for y in 0..height {
+        for x in split..width {
+            left.put_pixel(x, y, *right.get_pixel(x, y));
+        }
+        if split < width {
+            left.put_pixel(split, y, Rgba([255, 255, 255, 255]));
+        }
+    }
+}
+
 pub fn calculate_histogram_from_image(image: &DynamicImage) -> Result<HistogramData, String> {
     let mut red_counts = vec![0u32; 256];
     let mut green_counts = vec![0u32; 256];
@@ -717,7 +1192,7 @@ pub fn generate_waveform(state: tauri::State<AppState>, app_handle: tauri::AppHa
             .image.clone();
 
         let settings = load_settings(app_handle).unwrap_or_default();
-        let preview_dim = settings.editor_preview_resolution.unwrap_or(1920);
+        let preview_dim = resolve_preview_dimension(&state, &settings);
         let preview = image.thumbnail(preview_dim, preview_dim);
         calculate_waveform_from_image(&preview)
     }
@@ -771,6 +1246,38 @@ pub fn calculate_waveform_from_image(image: &DynamicImage) -> Result<WaveformDat
 }
 
 pub fn perform_auto_analysis(image: &DynamicImage) -> AutoAdjustmentResults {
+    perform_metered_auto_analysis(image, AutoExposureMode::Global, None)
+}
+
+// Median luma with every pixel counted by its metering weight. The weight map may have any
+// resolution, it is stretched over the image.
+fn weighted_median_luma(rgb_image: &image::RgbImage, weights: &GrayImage) -> Option<f64> {
+    let (width, height) = rgb_image.dimensions();
+    let weights = image::imageops::resize(weights, width, height, image::imageops::FilterType::Triangle);
+    let mut hist = [0.0f64; 256];
+    for (pixel, weight) in rgb_image.pixels().zip(weights.pixels()) {
+        let luma = (0.2126 * pixel[0] as f32 + 0.7152 * pixel[1] as f32 + 0.0722 * pixel[2] as f32).round() as usize;
+        hist[luma.min(255)] += weight[0] as f64;
+    }
+    let total: f64 = hist.iter().sum();
+    if total <= 0.0 {
+        return None;
+    }
+    let mut cumulative = 0.0;
+    for (luma, count) in hist.iter().enumerate() {
+        cumulative += count;
+        if cumulative >= total / 2.0 {
+            return Some(luma as f64);
+        }
+    }
+    None
+}
+
+pub fn perform_metered_auto_analysis(
+    image: &DynamicImage,
+    mode: AutoExposureMode,
+    metering_weights: Option<&GrayImage>,
+) -> AutoAdjustmentResults {
     let analysis_preview = image.thumbnail(1024, 1024);
     let rgb_image = analysis_preview.to_rgb8();
     let total_pixels = (rgb_image.width() * rgb_image.height()) as f64;
@@ -845,6 +1352,26 @@ pub fn perform_auto_analysis(image: &DynamicImage) -> AutoAdjustmentResults {
         highlights = -(highlight_percent * 150.0).min(80.0);
     }
 
+    if let Some(metered_mid) = metering_weights.and_then(|w| weighted_median_luma(&rgb_image, w)) {
+        exposure = (128.0 - metered_mid) * 0.35;
+    }
+
+    if mode == AutoExposureMode::ProtectHighlights {
+        let bright_threshold = (total_pixels * 0.005) as u32;
+        let mut cumulative = 0u32;
+        let mut bright_point = 255;
+        for i in (0..256).rev() {
+            cumulative += luma_hist[i];
+            if cumulative > bright_threshold { bright_point = i; break; }
+        }
+        // Stops that keep the brightest 0.5% below 250, with the histogram treated as gamma 2.2.
+        let headroom = 2.2 * (250.0 / bright_point.max(1) as f64).log2();
+        exposure = exposure.min(headroom * 20.0);
+        if white_point >= 250 {
+            highlights = f64::min(highlights, -40.0);
+        }
+    }
+
     brightest_pixels.sort_by(|a, b| b.0.cmp(&a.0));
     let num_brightest = (total_pixels * 0.01).ceil() as usize;
     let top_pixels = &brightest_pixels[..num_brightest.min(brightest_pixels.len())];
@@ -962,14 +1489,59 @@ pub fn auto_results_to_json(results: &AutoAdjustmentResults) -> serde_json::Valu
     })
 }
 
+fn region_weights(region: &MeteringRegion, width: u32, height: u32) -> GrayImage {
+    const SIZE: u32 = 256;
+    let (w, h) = if width >= height {
+        (SIZE, (SIZE * height / width.max(1)).max(1))
+    } else {
+        ((SIZE * width / height.max(1)).max(1), SIZE)
+    };
+    let x0 = (region.x.clamp(0.0, 1.0) * w as f32) as u32;
+    let y0 = (region.y.clamp(0.0, 1.0) * h as f32) as u32;
+    let x1 = (((region.x + region.width).clamp(0.0, 1.0) * w as f32).ceil() as u32).max(x0 + 1);
+    let y1 = (((region.y + region.height).clamp(0.0, 1.0) * h as f32).ceil() as u32).max(y0 + 1);
+    GrayImage::from_fn(w, h, |x, y| {
+        if x >= x0 && x < x1 && y >= y0 && y < y1 { Luma([255]) } else { Luma([0]) }
+    })
+}
+
+// `adjustments` carries the current geometry, so the analysis only looks at the visible frame.
 #[tauri::command]
-pub fn calculate_auto_adjustments(state: tauri::State<AppState>) -> Result<serde_json::Value, String> {
+pub async fn calculate_auto_adjustments(
+    mode: Option<AutoExposureMode>,
+    region: Option<MeteringRegion>,
+    adjustments: Option<serde_json::Value>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<serde_json::Value, String> {
     let original_image = state.original_image.lock().unwrap()
         .as_ref()
         .ok_or("No image loaded for auto adjustments")?
         .image.clone();
 
-    let results = perform_auto_analysis(&original_image);
+    let image = match &adjustments {
+        Some(adjustments) => crate::apply_all_transformations(&original_image, adjustments, 1.0).0,
+        None => original_image,
+    };
+
+    let mode = mode.unwrap_or_default();
+    let metering_weights = match mode {
+        AutoExposureMode::Global | AutoExposureMode::ProtectHighlights => None,
+        AutoExposureMode::Spot => {
+            let region = region.ok_or("Spot metering needs a region")?;
+            Some(region_weights(&region, image.width(), image.height()))
+        }
+        AutoExposureMode::SubjectWeighted => {
+            let models = crate::get_cached_ai_models(&state, &app_handle).await?;
+            let mask = crate::ai_processing::run_u2netp_model(&image, &models.u2netp).map_err(|e| e.to_string())?;
+            // The background still counts a little, so a small subject does not decide alone.
+            Some(GrayImage::from_fn(mask.width(), mask.height(), |x, y| {
+                Luma([(51 + mask.get_pixel(x, y)[0] as u32 * 204 / 255) as u8])
+            }))
+        }
+    };
+
+    let results = perform_metered_auto_analysis(&image, mode, metering_weights.as_ref());
 
     Ok(auto_results_to_json(&results))
 }
\ No newline at end of file
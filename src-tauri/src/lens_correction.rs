@@ -0,0 +1,592 @@
+use std::fs;
+use std::path::PathBuf;
+use std::sync::Mutex;
+
+use image::{DynamicImage, ImageBuffer, Rgb, Rgb32FImage};
+use rayon::prelude::*;
+use serde::Deserialize;
+use serde_json::Value;
+use tauri::{AppHandle, Manager};
+
+use crate::formats::is_raw_file;
+use crate::image_loader::{read_capture_info, CaptureInfo};
+use crate::resize::srgb_to_linear;
+
+// Lensfun installs its database as XML files. Profiles the user drops into the app data
+// folder are read as well, so the database can be updated without a system package.
+const SYSTEM_DATABASE_DIRS: &[&str] = &[
+    "/usr/share/lensfun/version_1",
+    "/usr/local/share/lensfun/version_1",
+    "/opt/homebrew/share/lensfun/version_1",
+];
+
+// Border samples used to find the zoom that keeps the corrected image free of empty corners.
+const AUTOSCALE_SAMPLES: usize = 64;
+
+// Poly3 coefficient of the manual distortion slider at its end points.
+const MANUAL_DISTORTION_STRENGTH: f32 = 0.15;
+
+// Radial distortion models of lensfun. Radii are normalized to half the shorter image side.
+#[derive(Debug, Clone, Copy)]
+enum Distortion {
+    PtLens { a: f32, b: f32, c: f32 },
+    Poly3 { k1: f32 },
+    Poly5 { k1: f32, k2: f32 },
+}
+
+impl Distortion {
+    // Radius in the distorted (recorded) image for a radius in the corrected one.
+    fn distort(&self, r: f32) -> f32 {
+        let r2 = r * r;
+        match *self {
+            Distortion::PtLens { a, b, c } => r * (a * r2 * r + b * r2 + c * r + 1.0 - a - b - c),
+            Distortion::Poly3 { k1 } => r * (1.0 - k1 + k1 * r2),
+            Distortion::Poly5 { k1, k2 } => r * (1.0 + k1 * r2 + k2 * r2 * r2),
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let l = |x: f32, y: f32| x + (y - x) * t;
+        match (*self, *other) {
+            (Distortion::PtLens { a, b, c }, Distortion::PtLens { a: a2, b: b2, c: c2 }) => {
+                Distortion::PtLens { a: l(a, a2), b: l(b, b2), c: l(c, c2) }
+            }
+            (Distortion::Poly3 { k1 }, Distortion::Poly3 { k1: o1 }) => Distortion::Poly3 { k1: l(k1, o1) },
+            (Distortion::Poly5 { k1, k2 }, Distortion::Poly5 { k1: o1, k2: o2 }) => {
+                Distortion::Poly5 { k1: l(k1, o1), k2: l(k2, o2) }
+            }
+            _ if t < 0.5 => *self,
+            _ => *other,
+        }
+    }
+}
+
+// Lateral chromatic aberration: red and blue are distorted relative to green.
+#[derive(Debug, Clone, Copy)]
+enum Tca {
+    Linear { kr: f32, kb: f32 },
+    Poly3 { vr: f32, vb: f32, cr: f32, cb: f32, br: f32, bb: f32 },
+}
+
+impl Tca {
+    // Radius of the red and blue channel for a radius in the green one.
+    fn distort(&self, r: f32) -> (f32, f32) {
+        match *self {
+            Tca::Linear { kr, kb } => (r * kr, r * kb),
+            Tca::Poly3 { vr, vb, cr, cb, br, bb } => {
+                let r2 = r * r;
+                (r * (br * r2 + cr * r + vr), r * (bb * r2 + cb * r + vb))
+            }
+        }
+    }
+
+    fn lerp(&self, other: &Self, t: f32) -> Self {
+        let l = |x: f32, y: f32| x + (y - x) * t;
+        match (*self, *other) {
+            (Tca::Linear { kr, kb }, Tca::Linear { kr: r2, kb: b2 }) => Tca::Linear { kr: l(kr, r2), kb: l(kb, b2) },
+            (
+                Tca::Poly3 { vr, vb, cr, cb, br, bb },
+                Tca::Poly3 { vr: vr2, vb: vb2, cr: cr2, cb: cb2, br: br2, bb: bb2 },
+            ) => Tca::Poly3 {
+                vr: l(vr, vr2),
+                vb: l(vb, vb2),
+                cr: l(cr, cr2),
+                cb: l(cb, cb2),
+                br: l(br, br2),
+                bb: l(bb, bb2),
+            },
+            _ if t < 0.5 => *self,
+            _ => *other,
+        }
+    }
+}
+
+// Lensfun's "pa" vignetting model, radii normalized to half the image diagonal.
+#[derive(Debug, Clone, Copy)]
+struct VignettingEntry {
+    focal: f32,
+    aperture: f32,
+    distance: f32,
+    k: [f32; 3],
+}
+
+#[derive(Debug, Clone)]
+struct LensProfile {
+    maker: String,
+    model: String,
+    crop_factor: f32,
+    distortion: Vec<(f32, Distortion)>,
+    tca: Vec<(f32, Tca)>,
+    vignetting: Vec<VignettingEntry>,
+}
+
+impl LensProfile {
+    fn id(&self) -> String {
+        if self.model.to_lowercase().starts_with(&self.maker.to_lowercase()) {
+            self.model.clone()
+        } else {
+            format!("{} {}", self.maker, self.model)
+        }
+    }
+
+    // A prime lens needs no focal length from EXIF, which manual lenses don't record.
+    fn prime_focal_length(&self) -> Option<f32> {
+        let mut focals = self
+            .distortion
+            .iter()
+            .map(|d| d.0)
+            .chain(self.tca.iter().map(|t| t.0))
+            .chain(self.vignetting.iter().map(|v| v.focal));
+        let first = focals.next()?;
+        focals.all(|f| f == first).then_some(first)
+    }
+}
+
+struct CameraEntry {
+    maker: String,
+    model: String,
+    crop_factor: f32,
+}
+
+struct Database {
+    lenses: Vec<LensProfile>,
+    cameras: Vec<CameraEntry>,
+}
+
+static DATABASE: Mutex<Database> = Mutex::new(Database {
+    lenses: Vec::new(),
+    cameras: Vec::new(),
+});
+
+// Per-image settings from the adjustments. Without any, profiles are applied automatically to
+// RAW files only.
+#[derive(Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", default)]
+pub struct LensCorrectionSettings {
+    // Unset until the user toggles it. JPEG and HEIC files are usually corrected in camera
+    // already, so unset means on for RAWs and off for everything else.
+    pub enabled: Option<bool>,
+    pub distortion: bool,
+    pub vignetting: bool,
+    pub tca: bool,
+    // Profile id to use instead of the one matched from EXIF
+    pub lens: Option<String>,
+    pub focal_length: Option<f32>,
+    pub aperture: Option<f32>,
+}
+
+impl Default for LensCorrectionSettings {
+    fn default() -> Self {
+        Self {
+            enabled: None,
+            distortion: true,
+            vignetting: true,
+            tca: true,
+            lens: None,
+            focal_length: None,
+            aperture: None,
+        }
+    }
+}
+
+pub fn settings_from(adjustments: &Value) -> LensCorrectionSettings {
+    adjustments
+        .get("lensCorrection")
+        .filter(|v| !v.is_null())
+        .and_then(|v| serde_json::from_value(v.clone()).ok())
+        .unwrap_or_default()
+}
+
+fn child_text<'a>(node: roxmltree::Node<'a, '_>, name: &str) -> Option<&'a str> {
+    node.children()
+        .find(|c| c.has_tag_name(name) && c.attribute("lang").is_none())
+        .and_then(|c| c.text())
+        .map(str::trim)
+}
+
+fn attr_f32(node: roxmltree::Node, name: &str) -> Option<f32> {
+    node.attribute(name)?.trim().parse().ok()
+}
+
+fn parse_lens(node: roxmltree::Node) -> Option<LensProfile> {
+    let mut profile = LensProfile {
+        maker: child_text(node, "maker").unwrap_or_default().to_string(),
+        model: child_text(node, "model")?.to_string(),
+        crop_factor: child_text(node, "cropfactor").and_then(|v| v.parse().ok()).unwrap_or(1.0),
+        distortion: Vec::new(),
+        tca: Vec::new(),
+        vignetting: Vec::new(),
+    };
+
+    for entry in node.descendants().filter(|n| n.is_element()) {
+        let Some(focal) = attr_f32(entry, "focal") else { continue };
+        let param = |name: &str, default: f32| attr_f32(entry, name).unwrap_or(default);
+        match (entry.tag_name().name(), entry.attribute("model")) {
+            ("distortion", Some("ptlens")) => profile.distortion.push((
+                focal,
+                Distortion::PtLens { a: param("a", 0.0), b: param("b", 0.0), c: param("c", 0.0) },
+            )),
+            ("distortion", Some("poly3")) => profile.distortion.push((focal, Distortion::Poly3 { k1: param("k1", 0.0) })),
+            ("distortion", Some("poly5")) => profile
+                .distortion
+                .push((focal, Distortion::Poly5 { k1: param("k1", 0.0), k2: param("k2", 0.0) })),
+            ("tca", Some("linear")) => profile.tca.push((focal, Tca::Linear { kr: param("kr", 1.0), kb: param("kb", 1.0) })),
+            ("tca", Some("poly3")) => profile.tca.push((
+                focal,
+                Tca::Poly3 {
+                    vr: param("vr", 1.0),
+                    vb: param("vb", 1.0),
+                    cr: param("cr", 0.0),
+                    cb: param("cb", 0.0),
+                    br: param("br", 0.0),
+                    bb: param("bb", 0.0),
+                },
+            )),
+            ("vignetting", Some("pa")) => {
+                let Some(aperture) = attr_f32(entry, "aperture") else { continue };
+                profile.vignetting.push(VignettingEntry {
+                    focal,
+                    aperture,
+                    distance: param("distance", 1000.0),
+                    k: [param("k1", 0.0), param("k2", 0.0), param("k3", 0.0)],
+                });
+            }
+            _ => {}
+        }
+    }
+
+    profile.distortion.sort_by(|a, b| a.0.total_cmp(&b.0));
+    profile.tca.sort_by(|a, b| a.0.total_cmp(&b.0));
+    Some(profile)
+}
+
+fn parse_database(content: &str, database: &mut Database) -> Result<(), roxmltree::Error> {
+    let doc = roxmltree::Document::parse(content)?;
+    for node in doc.root_element().children().filter(|n| n.is_element()) {
+        match node.tag_name().name() {
+            "lens" => database.lenses.extend(parse_lens(node)),
+            "camera" => {
+                if let (Some(maker), Some(model)) = (child_text(node, "maker"), child_text(node, "model")) {
+                    database.cameras.push(CameraEntry {
+                        maker: maker.to_string(),
+                        model: model.to_string(),
+                        crop_factor: child_text(node, "cropfactor").and_then(|v| v.parse().ok()).unwrap_or(1.0),
+                    });
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+fn database_dirs(app_handle: &AppHandle) -> Vec<PathBuf> {
+    let mut dirs: Vec<PathBuf> = SYSTEM_DATABASE_DIRS.iter().map(PathBuf::from).collect();
+    if let Ok(data_dir) = app_handle.path().app_data_dir() {
+        dirs.push(data_dir.join("lensfun"));
+    }
+    dirs
+}
+
+pub fn init(app_handle: &AppHandle) {
+    let mut database = Database {
+        lenses: Vec::new(),
+        cameras: Vec::new(),
+    };
+    for dir in database_dirs(app_handle) {
+        let Ok(entries) = fs::read_dir(&dir) else { continue };
+        for path in entries.flatten().map(|e| e.path()) {
+            if !path.extension().is_some_and(|e| e.eq_ignore_ascii_case("xml")) {
+                continue;
+            }
+            let result = fs::read_to_string(&path)
+                .map_err(|e| e.to_string())
+                .and_then(|content| parse_database(&content, &mut database).map_err(|e| e.to_string()));
+            if let Err(e) = result {
+                eprintln!("Skipping lens database {}: {}", path.display(), e);
+            }
+        }
+    }
+    *DATABASE.lock().unwrap() = database;
+}
+
+// Lowercase words, split between letters and digits so "EF24-105mm" matches "EF 24-105mm".
+fn tokens(s: &str) -> Vec<String> {
+    let is_number = |c: char| c.is_ascii_digit() || c == '.';
+    let mut out = Vec::new();
+    let mut current = String::new();
+    for c in s.to_lowercase().chars() {
+        let keep = c.is_alphanumeric() || c == '.';
+        let boundary = current.chars().last().is_some_and(|last| is_number(last) != is_number(c));
+        if (!keep || boundary) && !current.is_empty() {
+            out.push(std::mem::take(&mut current));
+        }
+        if keep {
+            current.push(c);
+        }
+    }
+    if !current.is_empty() {
+        out.push(current);
+    }
+    out
+}
+
+// The most specific profile whose model words all appear in the EXIF lens name.
+fn find_profile(database: &Database, capture: &CaptureInfo) -> Option<LensProfile> {
+    let lens = capture.lens.as_deref()?;
+    let wanted = tokens(&format!("{} {}", capture.make, lens));
+    database
+        .lenses
+        .iter()
+        .filter_map(|profile| {
+            let maker = tokens(&profile.maker);
+            let required: Vec<String> = tokens(&profile.model).into_iter().filter(|t| !maker.contains(t)).collect();
+            (!required.is_empty() && required.iter().all(|t| wanted.contains(t))).then_some((required.len(), profile))
+        })
+        .max_by_key(|(score, _)| *score)
+        .map(|(_, profile)| profile.clone())
+}
+
+fn camera_crop_factor(database: &Database, capture: &CaptureInfo) -> Option<f32> {
+    database
+        .cameras
+        .iter()
+        .find(|c| c.maker.eq_ignore_ascii_case(&capture.make) && c.model.eq_ignore_ascii_case(&capture.model))
+        .map(|c| c.crop_factor)
+}
+
+fn interpolate<T: Copy>(entries: &[(f32, T)], focal: f32, lerp: impl Fn(&T, &T, f32) -> T) -> Option<T> {
+    let first = entries.first()?;
+    let last = entries.last()?;
+    if focal <= first.0 {
+        return Some(first.1);
+    }
+    if focal >= last.0 {
+        return Some(last.1);
+    }
+    let i = entries.iter().position(|e| e.0 >= focal)?;
+    let (lo, hi) = (&entries[i - 1], &entries[i]);
+    let t = if hi.0 > lo.0 { (focal - lo.0) / (hi.0 - lo.0) } else { 0.0 };
+    Some(lerp(&lo.1, &hi.1, t))
+}
+
+// Nearest calibrated focal length, interpolated in stops between the apertures around it.
+fn vignetting_at(entries: &[VignettingEntry], focal: f32, aperture: f32) -> Option<[f32; 3]> {
+    let nearest = entries
+        .iter()
+        .map(|e| e.focal)
+        .min_by(|a, b| (a / focal).ln().abs().total_cmp(&(b / focal).ln().abs()))?;
+    // The furthest calibrated distance suits most photos, vignetting barely changes with it.
+    let distance = entries
+        .iter()
+        .filter(|e| e.focal == nearest)
+        .map(|e| e.distance)
+        .fold(0.0, f32::max);
+    let mut candidates: Vec<(f32, [f32; 3])> = entries
+        .iter()
+        .filter(|e| e.focal == nearest && e.distance == distance && e.aperture > 0.0)
+        .map(|e| (e.aperture.log2(), e.k))
+        .collect();
+    candidates.sort_by(|a, b| a.0.total_cmp(&b.0));
+    interpolate(&candidates, aperture.log2(), |a, b, t| {
+        [a[0] + (b[0] - a[0]) * t, a[1] + (b[1] - a[1]) * t, a[2] + (b[2] - a[2]) * t]
+    })
+}
+
+struct Correction {
+    distortion: Option<Distortion>,
+    tca: Option<Tca>,
+    vignetting: Option<[f32; 3]>,
+    // Converts radii of this image to radii of the sensor the lens was calibrated on.
+    crop_scale: f32,
+}
+
+fn resolve(capture: &CaptureInfo, settings: &LensCorrectionSettings) -> Option<Correction> {
+    let database = DATABASE.lock().unwrap();
+    let profile = match &settings.lens {
+        Some(id) => database.lenses.iter().find(|p| p.id() == *id).cloned(),
+        None => find_profile(&database, capture),
+    }?;
+    let focal = settings
+        .focal_length
+        .or(capture.focal_length)
+        .or_else(|| profile.prime_focal_length())?;
+    let aperture = settings.aperture.or(capture.aperture);
+    let camera_crop = camera_crop_factor(&database, capture).unwrap_or(profile.crop_factor);
+
+    let correction = Correction {
+        distortion: settings
+            .distortion
+            .then(|| interpolate(&profile.distortion, focal, Distortion::lerp))
+            .flatten(),
+        tca: settings.tca.then(|| interpolate(&profile.tca, focal, Tca::lerp)).flatten(),
+        vignetting: settings
+            .vignetting
+            .then(|| aperture.and_then(|n| vignetting_at(&profile.vignetting, focal, n)))
+            .flatten(),
+        crop_scale: profile.crop_factor / camera_crop.max(0.1),
+    };
+    (correction.distortion.is_some() || correction.tca.is_some() || correction.vignetting.is_some())
+        .then_some(correction)
+}
+
+impl Correction {
+    // Source offsets of the green, red and blue channel for an offset in the corrected image.
+    // Offsets are relative to the center, in units of half the shorter image side.
+    fn source_offsets(&self, x: f32, y: f32) -> [(f32, f32); 3] {
+        let r = x.hypot(y) * self.crop_scale;
+        if r <= f32::EPSILON {
+            return [(x, y); 3];
+        }
+        let rd = self.distortion.map_or(r, |d| d.distort(r));
+        let green = rd / r;
+        let (red, blue) = match self.tca {
+            Some(tca) if rd > f32::EPSILON => {
+                let (r_red, r_blue) = tca.distort(rd);
+                (r_red / r, r_blue / r)
+            }
+            _ => (green, green),
+        };
+        [(x * green, y * green), (x * red, y * red), (x * blue, y * blue)]
+    }
+
+    // Zoom into the corrected image until no border pixel samples outside the recorded one.
+    fn autoscale(&self, half_w: f32, half_h: f32) -> f32 {
+        let mut border = Vec::with_capacity(AUTOSCALE_SAMPLES * 4);
+        for i in 0..=AUTOSCALE_SAMPLES {
+            let t = i as f32 / AUTOSCALE_SAMPLES as f32 * 2.0 - 1.0;
+            border.extend([(t * half_w, half_h), (t * half_w, -half_h), (half_w, t * half_h), (-half_w, t * half_h)]);
+        }
+
+        let mut zoom = 1.0_f32;
+        for _ in 0..3 {
+            let fit = border
+                .iter()
+                .flat_map(|&(x, y)| self.source_offsets(x * zoom, y * zoom))
+                .map(|(sx, sy)| (half_w / sx.abs().max(f32::EPSILON)).min(half_h / sy.abs().max(f32::EPSILON)))
+                .fold(f32::INFINITY, f32::min);
+            if fit >= 1.0 {
+                break;
+            }
+            zoom *= fit;
+        }
+        zoom
+    }
+}
+
+fn sample(image: &Rgb32FImage, x: f32, y: f32, channel: usize) -> f32 {
+    let (w, h) = (image.width() as usize, image.height() as usize);
+    let x = x.clamp(0.0, (w - 1) as f32);
+    let y = y.clamp(0.0, (h - 1) as f32);
+    let (x0, y0) = (x.floor() as usize, y.floor() as usize);
+    let (x1, y1) = ((x0 + 1).min(w - 1), (y0 + 1).min(h - 1));
+    let (tx, ty) = (x - x0 as f32, y - y0 as f32);
+    let raw = image.as_raw();
+    let at = |col: usize, row: usize| raw[(row * w + col) * 3 + channel];
+    let top = at(x0, y0) * (1.0 - tx) + at(x1, y0) * tx;
+    let bottom = at(x0, y1) * (1.0 - tx) + at(x1, y1) * tx;
+    top * (1.0 - ty) + bottom * ty
+}
+
+// Unlike `linear_to_srgb` this keeps values above 1.0 of float images.
+fn encode_srgb(v: f32) -> f32 {
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn apply(image: DynamicImage, correction: &Correction) -> DynamicImage {
+    let (width, height) = (image.width(), image.height());
+    if width < 2 || height < 2 {
+        return image;
+    }
+    let color = image.color();
+    let source = image.into_rgb32f();
+
+    let unit = width.min(height) as f32 / 2.0;
+    let (cx, cy) = ((width - 1) as f32 / 2.0, (height - 1) as f32 / 2.0);
+    let zoom = correction.autoscale(cx / unit, cy / unit);
+    // Vignetting radii are normalized to half the diagonal instead of half the shorter side.
+    let diagonal_scale = unit / (width as f32).hypot(height as f32) * 2.0 * correction.crop_scale;
+
+    let mut data = vec![0.0_f32; width as usize * height as usize * 3];
+    data.par_chunks_exact_mut(width as usize * 3).enumerate().for_each(|(row, line)| {
+        let y = (row as f32 - cy) / unit * zoom;
+        for (col, pixel) in line.chunks_exact_mut(3).enumerate() {
+            let x = (col as f32 - cx) / unit * zoom;
+            let offsets = correction.source_offsets(x, y);
+            for (channel, (sx, sy)) in [1, 0, 2].into_iter().zip(offsets) {
+                pixel[channel] = sample(&source, cx + sx * unit, cy + sy * unit, channel);
+            }
+
+            if let Some([k1, k2, k3]) = correction.vignetting {
+                let (gx, gy) = offsets[0];
+                let r2 = (gx * gx + gy * gy) * diagonal_scale * diagonal_scale;
+                let falloff = 1.0 + k1 * r2 + k2 * r2 * r2 + k3 * r2 * r2 * r2;
+                if falloff > 0.05 {
+                    for v in pixel.iter_mut() {
+                        *v = encode_srgb(srgb_to_linear(v.max(0.0)) / falloff);
+                    }
+                }
+            }
+        }
+    });
+
+    let corrected: Rgb32FImage = ImageBuffer::<Rgb<f32>, Vec<f32>>::from_raw(width, height, data).unwrap();
+    let corrected = DynamicImage::ImageRgb32F(corrected);
+    match color.bytes_per_pixel() / color.channel_count() {
+        1 => DynamicImage::ImageRgb8(corrected.to_rgb8()),
+        2 => DynamicImage::ImageRgb16(corrected.to_rgb16()),
+        _ => corrected,
+    }
+}
+
+// Applies the lens profile matched from the file's EXIF, or the one chosen in the adjustments.
+pub fn correct_image(image: DynamicImage, file_bytes: &[u8], path: &str, adjustments: &Value) -> DynamicImage {
+    let settings = settings_from(adjustments);
+    let enabled = settings.enabled.unwrap_or_else(|| is_raw_file(path));
+    if !enabled || !(settings.distortion || settings.tca || settings.vignetting) {
+        return image;
+    }
+    let capture = read_capture_info(file_bytes, path);
+    match resolve(&capture, &settings) {
+        Some(correction) => apply(image, &correction),
+        None => image,
+    }
+}
+
+// Manual correction for lenses without a profile. Positive amounts remove barrel distortion,
+// negative ones pincushion. `amount` ranges from -1 to 1. A positive Poly3 k1 makes pixels
+// inside the frame sample nearer the centre, which magnifies the middle and adds barrel, so
+// the coefficient has the opposite sign of the amount.
+pub fn apply_manual_distortion(image: DynamicImage, amount: f32) -> DynamicImage {
+    if amount.abs() <= f32::EPSILON {
+        return image;
+    }
+    let correction = Correction {
+        distortion: Some(Distortion::Poly3 { k1: -amount.clamp(-1.0, 1.0) * MANUAL_DISTORTION_STRENGTH }),
+        tca: None,
+        vignetting: None,
+        crop_scale: 1.0,
+    };
+    apply(image, &correction)
+}
+
+#[tauri::command]
+pub fn list_lens_profiles() -> Vec<String> {
+    let database = DATABASE.lock().unwrap();
+    let mut ids: Vec<String> = database.lenses.iter().map(LensProfile::id).collect();
+    ids.sort();
+    ids.dedup();
+    ids
+}
+
+#[tauri::command]
+pub fn detect_lens_profile(path: String) -> Result<Option<String>, String> {
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let capture = read_capture_info(&file_bytes, &path);
+    let database = DATABASE.lock().unwrap();
+    Ok(find_profile(&database, &capture).map(|p| p.id()))
+}
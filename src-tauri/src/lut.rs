@@ -0,0 +1,141 @@
+use std::fs;
+use std::sync::{Arc, Mutex};
+use std::time::SystemTime;
+
+use serde::Serialize;
+use serde_json::Value;
+
+// 65 is the largest grid in common use. Anything much bigger is more likely a broken file.
+const MAX_LUT_SIZE: usize = 129;
+
+// A 3D LUT from a .cube file. `data` holds `size`³ output colors with red changing fastest,
+// which is also the x, y, z order of a 3D texture.
+pub struct Lut {
+    pub size: u32,
+    pub data: Vec<[f32; 3]>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct LutInfo {
+    pub path: String,
+    pub hash: String,
+    pub name: String,
+    pub size: u32,
+}
+
+// The last LUT used, keyed by path and modification time. Previews render it again on every
+// slider change.
+static LOADED: Mutex<Option<(String, Option<SystemTime>, Arc<Lut>)>> = Mutex::new(None);
+
+fn parse_triplet<'a>(mut values: impl Iterator<Item = &'a str>) -> Option<[f32; 3]> {
+    let mut next = || values.next().and_then(|v| v.parse::<f32>().ok());
+    let triplet = [next()?, next()?, next()?];
+    values.next().is_none().then_some(triplet)
+}
+
+fn parse_cube(content: &str) -> Result<(Lut, Option<String>), String> {
+    let mut size = None;
+    let mut title = None;
+    let mut data = Vec::new();
+
+    for (number, line) in content.lines().enumerate() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let mut parts = line.split_whitespace();
+        let keyword = parts.next().unwrap_or_default();
+        let invalid = || format!("Line {} is not valid: {}", number + 1, line);
+        match keyword {
+            "TITLE" => title = Some(line["TITLE".len()..].trim().trim_matches('"').to_string()),
+            "LUT_1D_SIZE" => return Err("1D LUTs are not supported. Use a 3D LUT.".to_string()),
+            "LUT_3D_SIZE" => {
+                let n = parts.next().and_then(|v| v.parse::<usize>().ok()).ok_or_else(invalid)?;
+                if !(2..=MAX_LUT_SIZE).contains(&n) {
+                    return Err(format!("Unsupported LUT size {}.", n));
+                }
+                size = Some(n);
+            }
+            "DOMAIN_MIN" | "DOMAIN_MAX" => {
+                let expected = if keyword == "DOMAIN_MIN" { 0.0 } else { 1.0 };
+                let domain = parse_triplet(parts).ok_or_else(invalid)?;
+                if domain.iter().any(|v| (v - expected).abs() > 1e-6) {
+                    return Err("Only LUTs with an input domain of 0 to 1 are supported.".to_string());
+                }
+            }
+            "LUT_3D_INPUT_RANGE" => {
+                let range: Vec<f32> = parts.filter_map(|v| v.parse().ok()).collect();
+                if range != [0.0, 1.0] {
+                    return Err("Only LUTs with an input domain of 0 to 1 are supported.".to_string());
+                }
+            }
+            _ if keyword.starts_with(|c: char| c.is_ascii_alphabetic()) => {}
+            _ => data.push(parse_triplet(line.split_whitespace()).ok_or_else(invalid)?),
+        }
+    }
+
+    let size = size.ok_or("The file has no LUT_3D_SIZE.")?;
+    if data.len() != size * size * size {
+        return Err(format!("Expected {} entries for a {}³ LUT but found {}.", size * size * size, size, data.len()));
+    }
+    Ok((Lut { size: size as u32, data }, title))
+}
+
+fn modified_time(path: &str) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|m| m.modified()).ok()
+}
+
+// The LUT of the adjustments, or None without one or at zero intensity. A file that can no
+// longer be read is logged and skipped, so the image still renders.
+pub fn lut_for(adjustments: &Value) -> Option<Arc<Lut>> {
+    let settings = adjustments.get("lut")?;
+    let path = settings.get("path")?.as_str()?;
+    if settings["intensity"].as_f64().unwrap_or(100.0) <= 0.0 {
+        return None;
+    }
+
+    let modified = modified_time(path);
+    let mut loaded = LOADED.lock().unwrap();
+    if let Some((loaded_path, loaded_modified, lut)) = loaded.as_ref() {
+        if loaded_path == path && *loaded_modified == modified {
+            return Some(lut.clone());
+        }
+    }
+
+    let lut = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|content| parse_cube(&content));
+    match lut {
+        Ok((lut, _)) => {
+            let lut = Arc::new(lut);
+            *loaded = Some((path.to_string(), modified, lut.clone()));
+            Some(lut)
+        }
+        Err(e) => {
+            eprintln!("Ignoring LUT {}: {}", path, e);
+            None
+        }
+    }
+}
+
+// Checks the file and returns what the sidecar keeps of it. The hash tells whether the file
+// at the path is still the one that was chosen.
+#[tauri::command]
+pub fn load_lut(path: String) -> Result<LutInfo, String> {
+    let bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let content = String::from_utf8_lossy(&bytes);
+    let (lut, title) = parse_cube(&content)?;
+    let name = title.filter(|t| !t.is_empty()).unwrap_or_else(|| {
+        std::path::Path::new(&path)
+            .file_stem()
+            .map(|s| s.to_string_lossy().into_owned())
+            .unwrap_or_default()
+    });
+    Ok(LutInfo {
+        hash: blake3::hash(&bytes).to_hex().to_string(),
+        path,
+        name,
+        size: lut.size,
+    })
+}
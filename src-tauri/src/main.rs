@@ -5,66 +5,168 @@ mod image_processing;
 mod file_management;
 mod gpu_processing;
 mod raw_processing;
+mod picture_styles;
 mod mask_generation;
 mod ai_processing;
 mod formats;
 mod image_loader;
+mod geotagging;
+mod metadata_writeback;
+mod darktable;
+mod stacks;
+mod watermark;
+mod border;
+mod export_checkpoints;
+mod export_queue;
+mod notes;
+mod post_export;
+mod share;
+mod clipboard;
+mod batch_operations;
+mod export_destinations;
+mod power;
+mod contact_sheet;
+mod reference_swatches;
+mod camera_profiles;
+mod backup;
+mod cmyk;
+mod safe_mode;
+mod publish;
+mod audit_log;
+mod cache_manager;
+mod metadata_profiles;
+mod resize;
+mod proofing;
+mod bad_pixel_maps;
+mod dark_frames;
+mod flat_fields;
+mod lens_correction;
+mod lut;
+mod performance;
+mod tiled_export;
+mod graduated_sky;
+mod pixel_shift;
+mod raw_histogram;
+mod preview_queue;
 
 use std::io::Cursor;
 use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicU64, Ordering};
 use std::thread;
+use std::time::{Duration, Instant};
 use std::fs;
 use std::collections::{HashMap, hash_map::DefaultHasher};
 use std::hash::{Hash, Hasher};
 
 use image::{DynamicImage, GenericImageView, ImageBuffer, Luma, Rgba, RgbaImage, ImageFormat, GrayImage};
-use image::codecs::jpeg::JpegEncoder;
+use image::codecs::jpeg::{JpegEncoder, PixelDensity};
 use tauri::{Manager, Emitter};
 use base64::{Engine as _, engine::general_purpose};
 use serde_json::Value;
-use tokio::task::JoinHandle;
 use window_vibrancy::{apply_acrylic, apply_vibrancy, NSVisualEffectMaterial};
 use serde::{Serialize, Deserialize};
 use chrono::Local;
+use uuid::Uuid;
 use little_exif::metadata::Metadata;
 use little_exif::exif_tag::ExifTag;
 use little_exif::filetype::FileExtension;
 use little_exif::rational::uR64;
+use metadata_profiles::MetadataProfile;
+use rawler::formats::tiff::{reader::TiffReader, GenericTiffReader};
+use libheif_rs::{
+    Channel, ColorSpace, CompressionFormat, EncoderQuality, HeifContext, Image as HeifImage, LibHeif, RgbChroma,
+};
 
 use crate::image_processing::{
-    get_all_adjustments_from_json, get_or_init_gpu_context, GpuContext,
-    ImageMetadata, process_and_get_dynamic_image, Crop, apply_crop, apply_rotation, apply_flip,
+    get_all_adjustments_from_json, get_or_init_gpu_context, AllAdjustments, GpuContext,
+    ImageMetadata, process_and_get_dynamic_image, process_and_get_dynamic_image_16bit, Crop, apply_crop, apply_rotation, apply_flip,
 };
 use crate::file_management::{get_sidecar_path, load_settings, AppSettings};
-use crate::mask_generation::{MaskDefinition, generate_mask_bitmap};
+use crate::preview_queue::LatestJobQueue;
+use crate::mask_generation::{MaskDefinition, generate_mask_bitmap, dodge_burn_sub_mask, DODGE_BURN_NEUTRAL};
 use crate::ai_processing::{
-    AiState, get_or_init_ai_models, generate_image_embeddings, run_sam_decoder,
+    AiModels, AiState, get_or_init_ai_models, generate_image_embeddings, run_sam_decoder,
     AiSubjectMaskParameters, run_u2netp_model, AiForegroundMaskParameters
 };
 use crate::formats::{is_raw_file};
-use crate::image_loader::{load_base_image_from_bytes, composite_patches_on_image, load_and_composite};
+use crate::image_loader::{load_base_image_from_bytes, load_corrected_base_image, composite_patches_on_image, read_capture_info, CaptureInfo};
+use crate::raw_processing::{extract_embedded_preview, read_raw_metadata, DevelopOverrides};
+use crate::watermark::{apply_watermark, WatermarkSettings};
+use crate::border::{apply_border, BorderSettings};
+use crate::resize::{resize_image, ResizeFilter};
+use crate::gpu_processing::{gpu_resize, gpu_thumbnail, is_hdr};
+use crate::proofing::{apply_proof_overlay, overlay_for_path};
+use crate::tiled_export::is_tiled_format;
+use crate::post_export::{run_post_export_hook, PostExportHook};
+use crate::batch_operations::BatchHistoryEntry;
+use crate::export_destinations::{open_destination, ExportDestination};
+use crate::performance::{Cache, Stage};
+use crate::export_queue::{enqueue_export_job, ExportJobHandle, ExportJobKind, ExportQueue, ExportStage};
 
 #[derive(Clone)]
 pub struct LoadedImage {
     image: DynamicImage,
+    path: String,
     full_width: u32,
     full_height: u32,
 }
 
+#[derive(Clone, Copy, PartialEq)]
+pub struct PreviewViewport {
+    width: u32,
+    height: u32,
+    device_pixel_ratio: f32,
+}
+
 #[derive(Clone)]
 pub struct CachedPreview {
     image: DynamicImage,
     transform_hash: u64,
     scale: f32,
     unscaled_crop_offset: (f32, f32),
+    // The last before/after comparison render of this preview, keyed by comparison_key.
+    comparison: Option<(u64, Arc<DynamicImage>)>,
+}
+
+// Before/after preview: the right half of the frame shows the image with the adjustments of a
+// snapshot, or without any for the unedited image.
+#[derive(serde::Deserialize, Clone)]
+#[serde(rename_all = "camelCase")]
+struct PreviewCompare {
+    adjustments: Option<serde_json::Value>,
+}
+
+// The base preview was developed for the current tone mapper, so both halves keep it. The
+// geometry is always the current one, a snapshot only changes the rendering.
+fn comparison_adjustments(compare: &PreviewCompare, current: &serde_json::Value) -> serde_json::Value {
+    let mut adjustments = compare.adjustments.clone().filter(|a| a.is_object()).unwrap_or_else(|| serde_json::json!({}));
+    adjustments["toneMapper"] = current["toneMapper"].clone();
+    adjustments
+}
+
+fn comparison_key(adjustments: &serde_json::Value, show_clipping: bool) -> u64 {
+    let mut hasher = DefaultHasher::new();
+    adjustments.to_string().hash(&mut hasher);
+    show_clipping.hash(&mut hasher);
+    hasher.finish()
 }
 
 pub struct AppState {
     original_image: Mutex<Option<LoadedImage>>,
     cached_preview: Mutex<Option<CachedPreview>>,
+    preview_viewport: Mutex<Option<PreviewViewport>>,
     gpu_context: Mutex<Option<GpuContext>>,
     ai_state: Mutex<Option<AiState>>,
-    export_task_handle: Mutex<Option<JoinHandle<()>>>,
+    export_queue: Mutex<ExportQueue>,
+    batch_history: Mutex<Vec<BatchHistoryEntry>>,
+    preview_generation: AtomicU64,
+    // Editor previews render on one worker and are encoded and sent on another, so the next
+    // frame renders while the last one is encoded. Both keep only the newest waiting frame.
+    preview_renders: LatestJobQueue,
+    preview_emits: LatestJobQueue,
+    uncropped_renders: LatestJobQueue,
+    // Proofing overlay of the loaded image, drawn on every editor preview.
+    proof_overlay: Mutex<Option<WatermarkSettings>>,
 }
 
 #[derive(serde::Serialize)]
@@ -75,6 +177,18 @@ struct LoadImageResult {
     metadata: ImageMetadata,
     exif: HashMap<String, String>,
     is_raw: bool,
+    is_hdr: bool,
+}
+
+#[derive(serde::Serialize)]
+struct EmbeddedPreviewResult {
+    preview_base64: String,
+    width: u32,
+    height: u32,
+    metadata: ImageMetadata,
+    exif: HashMap<String, String>,
+    is_raw: bool,
+    is_embedded_preview: bool,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
@@ -83,24 +197,97 @@ enum ResizeMode {
     LongEdge,
     Width,
     Height,
+    Megapixels,
+    Percent,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
 struct ResizeOptions {
     mode: ResizeMode,
-    value: u32,
+    value: f64,
     dont_enlarge: bool,
+    #[serde(default)]
+    filter: ResizeFilter,
 }
 
 #[derive(Serialize, Deserialize, Debug, Clone)]
 #[serde(rename_all = "camelCase")]
-struct ExportSettings {
+pub struct ExportSettings {
     jpeg_quality: u8,
     resize: Option<ResizeOptions>,
-    keep_metadata: bool,
-    strip_gps: bool,
+    #[serde(default)]
+    metadata_profile: Option<MetadataProfile>,
+    // Superseded by `metadata_profile`; still read so presets saved before profiles existed
+    // export the same way.
+    #[serde(default, skip_serializing)]
+    keep_metadata: Option<bool>,
+    #[serde(default, skip_serializing)]
+    strip_gps: Option<bool>,
     filename_template: Option<String>,
+    #[serde(default)]
+    jxl_lossless: bool,
+    bit_depth: Option<u8>,
+    version_selection: Option<VersionSelection>,
+    watermark: Option<WatermarkSettings>,
+    border: Option<BorderSettings>,
+    post_export_hook: Option<PostExportHook>,
+    destination_id: Option<String>,
+    dpi: Option<u32>,
+    cmyk: Option<cmyk::CmykSettings>,
+    #[serde(default)]
+    write_xmp_sidecar: bool,
+}
+
+impl ExportSettings {
+    fn metadata_profile(&self) -> MetadataProfile {
+        if let Some(profile) = self.metadata_profile {
+            return profile;
+        }
+        match (self.keep_metadata.unwrap_or(true), self.strip_gps.unwrap_or(false)) {
+            (false, _) => MetadataProfile::None,
+            (true, true) => MetadataProfile::AllExceptLocation,
+            (true, false) => MetadataProfile::Everything,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ExportTarget {
+    output_path: String,
+    export_settings: ExportSettings,
+}
+
+impl Default for ExportSettings {
+    fn default() -> Self {
+        Self {
+            jpeg_quality: 90,
+            resize: None,
+            metadata_profile: Some(MetadataProfile::Everything),
+            keep_metadata: None,
+            strip_gps: None,
+            filename_template: None,
+            jxl_lossless: false,
+            bit_depth: None,
+            version_selection: None,
+            watermark: None,
+            border: None,
+            post_export_hook: None,
+            destination_id: None,
+            dpi: None,
+            cmyk: None,
+            write_xmp_sidecar: false,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+enum VersionSelection {
+    Current,
+    Named(String),
+    All,
 }
 
 fn apply_all_transformations(
@@ -111,8 +298,10 @@ fn apply_all_transformations(
     let rotation_degrees = adjustments["rotation"].as_f64().unwrap_or(0.0) as f32;
     let flip_horizontal = adjustments["flipHorizontal"].as_bool().unwrap_or(false);
     let flip_vertical = adjustments["flipVertical"].as_bool().unwrap_or(false);
+    let lens_distortion = adjustments["lensDistortion"].as_f64().unwrap_or(0.0) as f32;
 
-    let flipped_image = apply_flip(image.clone(), flip_horizontal, flip_vertical);
+    let undistorted_image = lens_correction::apply_manual_distortion(image.clone(), lens_distortion / 100.0);
+    let flipped_image = apply_flip(undistorted_image, flip_horizontal, flip_vertical);
     let rotated_image = apply_rotation(&flipped_image, rotation_degrees);
 
     let crop_data: Option<Crop> = serde_json::from_value(adjustments["crop"].clone()).ok();
@@ -147,6 +336,9 @@ fn calculate_transform_hash(adjustments: &serde_json::Value) -> u64 {
     let flip_v = adjustments["flipVertical"].as_bool().unwrap_or(false);
     flip_v.hash(&mut hasher);
 
+    let lens_distortion = adjustments["lensDistortion"].as_f64().unwrap_or(0.0);
+    lens_distortion.to_bits().hash(&mut hasher);
+
     if let Some(crop_val) = adjustments.get("crop") {
         if !crop_val.is_null() {
             crop_val.to_string().hash(&mut hasher);
@@ -172,6 +364,7 @@ fn calculate_transform_hash(adjustments: &serde_json::Value) -> u64 {
 }
 
 fn generate_transformed_preview(
+    context: &GpuContext,
     loaded_image: &LoadedImage,
     adjustments: &serde_json::Value,
     app_handle: &tauri::AppHandle,
@@ -182,24 +375,65 @@ fn generate_transformed_preview(
     let (full_w, full_h) = (loaded_image.full_width, loaded_image.full_height);
 
     let settings = load_settings(app_handle.clone()).unwrap_or_default();
-    let final_preview_dim = settings.editor_preview_resolution.unwrap_or(1920);
+    let final_preview_dim = resolve_preview_dimension(&app_handle.state::<AppState>(), &settings);
 
     let (processing_base, scale_for_gpu) = 
         if full_w > final_preview_dim || full_h > final_preview_dim {
-            let base = patched_original_image.thumbnail(final_preview_dim, final_preview_dim);
+            let base = gpu_thumbnail(context, &patched_original_image, final_preview_dim, final_preview_dim);
             let scale = if full_w > 0 { base.width() as f32 / full_w as f32 } else { 1.0 };
             (base, scale)
         } else {
             (patched_original_image.clone(), 1.0)
         };
 
-    let (final_preview_base, unscaled_crop_offset) = 
-        apply_all_transformations(&processing_base, adjustments, scale_for_gpu);
+    let (final_preview_base, unscaled_crop_offset) = performance::time(Stage::Transform, || {
+        apply_all_transformations(&processing_base, adjustments, scale_for_gpu)
+    });
     
     Ok((final_preview_base, scale_for_gpu, unscaled_crop_offset))
 }
 
+// With a reported viewport the preview matches the physical canvas size, capped by
+// `max_preview_resolution`; otherwise the fixed editor preview resolution is used.
+pub fn resolve_preview_dimension(state: &AppState, settings: &AppSettings) -> u32 {
+    let fallback = settings.editor_preview_resolution.unwrap_or(1920);
+    let dimension = match *state.preview_viewport.lock().unwrap() {
+        Some(viewport) => {
+            let long_edge = viewport.width.max(viewport.height) as f32 * viewport.device_pixel_ratio.max(1.0);
+            let max_dim = settings.max_preview_resolution.unwrap_or(5120).max(512);
+            (long_edge.ceil() as u32).clamp(512, max_dim)
+        }
+        None => fallback,
+    };
+    if power::is_low_power_active(settings) {
+        dimension.min(power::LOW_POWER_PREVIEW_LIMIT)
+    } else {
+        dimension
+    }
+}
+
+#[tauri::command]
+fn set_preview_viewport(
+    width: u32,
+    height: u32,
+    device_pixel_ratio: f32,
+    state: tauri::State<AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<u32, String> {
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let previous_dim = resolve_preview_dimension(&state, &settings);
+
+    *state.preview_viewport.lock().unwrap() = Some(PreviewViewport { width, height, device_pixel_ratio });
+    let new_dim = resolve_preview_dimension(&state, &settings);
+
+    if new_dim != previous_dim {
+        *state.cached_preview.lock().unwrap() = None;
+    }
+    Ok(new_dim)
+}
+
 fn encode_to_base64(image: &DynamicImage, quality: u8) -> Result<String, String> {
+    let start = Instant::now();
     let rgb_image = image.to_rgb8();
 
     let mut buf = Cursor::new(Vec::new());
@@ -207,6 +441,7 @@ fn encode_to_base64(image: &DynamicImage, quality: u8) -> Result<String, String>
     rgb_image.write_with_encoder(encoder).map_err(|e| e.to_string())?;
     
     let base64_str = general_purpose::STANDARD.encode(buf.get_ref());
+    performance::record(Stage::Encode, start.elapsed());
     Ok(format!("data:image/jpeg;base64,{}", base64_str))
 }
 
@@ -238,26 +473,48 @@ async fn load_image(path: String, state: tauri::State<'_, AppState>, app_handle:
         let file_content = fs::read_to_string(sidecar_path).map_err(|e| e.to_string())?;
         serde_json::from_str(&file_content).unwrap_or_default()
     } else {
-        ImageMetadata::default()
+        darktable::read_darktable_sidecar(&path).unwrap_or_default()
     };
 
-    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
-    let pristine_img = load_base_image_from_bytes(&file_bytes, &path, false)
+    performance::set_active_image(&path);
+    let _scope = performance::image_scope(&path);
+    let file_bytes = performance::time(Stage::Decode, || fs::read(&path)).map_err(|e| e.to_string())?;
+    let is_raw = is_raw_file(&path);
+    let settings = load_settings(app_handle.clone()).unwrap_or_default();
+    let display_preview_dim = resolve_preview_dimension(&state, &settings);
+
+    // Developing a large RAW takes a while, the camera's own JPEG is shown in the meantime.
+    if is_raw && settings.embedded_preview_first.unwrap_or(true) {
+        if let Ok(preview) = extract_embedded_preview(&file_bytes) {
+            let preview = preview.thumbnail(display_preview_dim, display_preview_dim);
+            if let Ok(preview_base64) = encode_to_base64(&preview, 85) {
+                let _ = app_handle.emit(
+                    "embedded-preview",
+                    serde_json::json!({ "path": path, "previewBase64": preview_base64 }),
+                );
+            }
+        }
+    }
+
+    let pristine_img = load_corrected_base_image(&file_bytes, &path, false, &metadata.adjustments)
         .map_err(|e| e.to_string())?;
 
     let (orig_width, orig_height) = pristine_img.dimensions();
-    let is_raw = is_raw_file(&path);
+    let is_hdr = is_hdr(&pristine_img);
 
-    let exif_data = read_exif_data(&file_bytes);
+    let mut exif_data = read_exif_data(&file_bytes);
+    if let Some(style) = is_raw.then(|| read_raw_metadata(&file_bytes).ok()).flatten().and_then(|m| m.picture_style) {
+        exif_data.insert("PictureStyle".to_string(), style.name().to_string());
+    }
 
-    let settings = load_settings(app_handle).unwrap_or_default();
-    let display_preview_dim = settings.editor_preview_resolution.unwrap_or(1920);
     let display_preview = pristine_img.thumbnail(display_preview_dim, display_preview_dim);
     let original_base64 = encode_to_base64(&display_preview, 85)?;
 
     *state.cached_preview.lock().unwrap() = None;
+    *state.proof_overlay.lock().unwrap() = overlay_for_path(&path);
     *state.original_image.lock().unwrap() = Some(LoadedImage {
         image: pristine_img,
+        path: path.clone(),
         full_width: orig_width,
         full_height: orig_height,
     });
@@ -269,12 +526,84 @@ async fn load_image(path: String, state: tauri::State<'_, AppState>, app_handle:
         metadata,
         exif: exif_data,
         is_raw,
+        is_hdr,
+    })
+}
+
+// Loads the base image of the open file again after the demosaic choice or the lens
+// correction changed. Returns the new display preview; adjustments are reapplied by the frontend.
+#[tauri::command]
+async fn reload_base_image(
+    path: String,
+    js_adjustments: serde_json::Value,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let _scope = performance::image_scope(&path);
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let pristine_img = load_corrected_base_image(&file_bytes, &path, false, &js_adjustments)
+        .map_err(|e| e.to_string())?;
+    let (full_width, full_height) = pristine_img.dimensions();
+
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let display_preview_dim = resolve_preview_dimension(&state, &settings);
+    let display_preview = pristine_img.thumbnail(display_preview_dim, display_preview_dim);
+
+    *state.cached_preview.lock().unwrap() = None;
+    *state.original_image.lock().unwrap() = Some(LoadedImage {
+        image: pristine_img,
+        path: path.clone(),
+        full_width,
+        full_height,
+    });
+
+    encode_to_base64(&display_preview, 85)
+}
+
+#[tauri::command]
+async fn load_embedded_preview(
+    path: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<EmbeddedPreviewResult, String> {
+    let sidecar_path = get_sidecar_path(&path);
+    let metadata: ImageMetadata = fs::read_to_string(sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    let file_bytes = fs::read(&path).map_err(|e| e.to_string())?;
+    let is_raw = is_raw_file(&path);
+
+    let (image, is_embedded_preview) = match is_raw.then(|| extract_embedded_preview(&file_bytes)) {
+        Some(Ok(preview)) => (preview, true),
+        _ => (
+            load_base_image_from_bytes(&file_bytes, &path, true, DevelopOverrides::default()).map_err(|e| e.to_string())?,
+            false,
+        ),
+    };
+
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let preview_dim = resolve_preview_dimension(&state, &settings);
+    let (width, height) = image.dimensions();
+    let preview = image.thumbnail(preview_dim, preview_dim);
+
+    Ok(EmbeddedPreviewResult {
+        preview_base64: encode_to_base64(&preview, 85)?,
+        width,
+        height,
+        metadata,
+        exif: read_exif_data(&file_bytes),
+        is_raw,
+        is_embedded_preview,
     })
 }
 
 #[tauri::command]
 fn apply_adjustments(
     js_adjustments: serde_json::Value,
+    show_clipping: Option<bool>,
+    compare: Option<PreviewCompare>,
     state: tauri::State<AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
@@ -283,52 +612,114 @@ fn apply_adjustments(
     
     let loaded_image = state.original_image.lock().unwrap().clone().ok_or("No original image loaded")?;
     let new_transform_hash = calculate_transform_hash(&adjustments_clone);
+    let active_image = performance::active_image();
+    let _scope = active_image.as_deref().map(performance::image_scope);
 
     let mut cached_preview_lock = state.cached_preview.lock().unwrap();
+    let cache_hit = cached_preview_lock.as_ref().is_some_and(|c| c.transform_hash == new_transform_hash);
+    performance::record_cache(Cache::PreviewBase, cache_hit);
     
     let (final_preview_base, scale_for_gpu, unscaled_crop_offset) = 
         if let Some(cached) = &*cached_preview_lock {
             if cached.transform_hash == new_transform_hash {
                 (cached.image.clone(), cached.scale, cached.unscaled_crop_offset)
             } else {
-                let (base, scale, offset) = generate_transformed_preview(&loaded_image, &adjustments_clone, &app_handle)?;
+                let (base, scale, offset) = generate_transformed_preview(&context, &loaded_image, &adjustments_clone, &app_handle)?;
                 *cached_preview_lock = Some(CachedPreview {
                     image: base.clone(),
                     transform_hash: new_transform_hash,
                     scale,
                     unscaled_crop_offset: offset,
+                    comparison: None,
                 });
                 (base, scale, offset)
             }
         } else {
-            let (base, scale, offset) = generate_transformed_preview(&loaded_image, &adjustments_clone, &app_handle)?;
+            let (base, scale, offset) = generate_transformed_preview(&context, &loaded_image, &adjustments_clone, &app_handle)?;
             *cached_preview_lock = Some(CachedPreview {
                 image: base.clone(),
                 transform_hash: new_transform_hash,
                 scale,
                 unscaled_crop_offset: offset,
+                comparison: None,
             });
             (base, scale, offset)
         };
     
     drop(cached_preview_lock);
+
+    let generation = state.preview_generation.fetch_add(1, Ordering::SeqCst) + 1;
+    let proof_overlay = state.proof_overlay.lock().unwrap().clone();
+    let low_power = power::is_low_power_active(&load_settings(app_handle.clone()).unwrap_or_default());
+    let show_clipping = show_clipping.unwrap_or(false);
     
-    thread::spawn(move || {
+    state.preview_renders.push(move || {
+        let _scope = active_image.as_deref().map(performance::image_scope);
+        let is_stale = || app_handle.state::<AppState>().preview_generation.load(Ordering::SeqCst) != generation;
+        if low_power {
+            thread::sleep(power::LOW_POWER_PREVIEW_DEBOUNCE);
+            if is_stale() {
+                return;
+            }
+        }
+
         let (preview_width, preview_height) = final_preview_base.dimensions();
+        let scaled_crop_offset = (unscaled_crop_offset.0 * scale_for_gpu, unscaled_crop_offset.1 * scale_for_gpu);
 
-        let mask_definitions: Vec<MaskDefinition> = js_adjustments.get("masks")
-            .and_then(|m| serde_json::from_value(m.clone()).ok())
-            .unwrap_or_else(Vec::new);
+        let render = |adjustments: &serde_json::Value| {
+            let mask_definitions: Vec<MaskDefinition> = adjustments.get("masks")
+                .and_then(|m| serde_json::from_value(m.clone()).ok())
+                .unwrap_or_else(Vec::new);
 
-        let scaled_crop_offset = (unscaled_crop_offset.0 * scale_for_gpu, unscaled_crop_offset.1 * scale_for_gpu);
+            let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
+                .filter_map(|def| generate_mask_bitmap(def, preview_width, preview_height, scale_for_gpu, scaled_crop_offset))
+                .collect();
 
-        let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
-            .filter_map(|def| generate_mask_bitmap(def, preview_width, preview_height, scale_for_gpu, scaled_crop_offset))
-            .collect();
+            let mut all_adjustments = get_all_adjustments_from_json(adjustments);
+            all_adjustments.clipping_overlay = show_clipping as u32;
+            let lut = lut::lut_for(adjustments);
+
+            process_and_get_dynamic_image(&context, &final_preview_base, all_adjustments, &mask_bitmaps, lut.as_deref())
+        };
+
+        let Ok(mut final_processed_image) = render(&js_adjustments) else {
+            return;
+        };
 
-        let final_adjustments = get_all_adjustments_from_json(&adjustments_clone);
+        // The comparison only changes with the snapshot or the geometry, so it is rendered once
+        // and kept with the base preview while the sliders move.
+        let comparison = compare.map(|compare| -> Result<Arc<DynamicImage>, String> {
+            let adjustments = comparison_adjustments(&compare, &js_adjustments);
+            let key = comparison_key(&adjustments, show_clipping);
+            let state = app_handle.state::<AppState>();
+            let cached = state.cached_preview.lock().unwrap().as_ref()
+                .filter(|c| c.transform_hash == new_transform_hash)
+                .and_then(|c| c.comparison.clone())
+                .filter(|(cached_key, _)| *cached_key == key);
+            if let Some((_, image)) = cached {
+                return Ok(image);
+            }
 
-        if let Ok(final_processed_image) = process_and_get_dynamic_image(&context, &final_preview_base, final_adjustments, &mask_bitmaps) {
+            let image = Arc::new(render(&adjustments)?);
+            if let Some(cached) = state.cached_preview.lock().unwrap().as_mut().filter(|c| c.transform_hash == new_transform_hash) {
+                cached.comparison = Some((key, image.clone()));
+            }
+            Ok(image)
+        });
+        let comparison = match comparison.transpose() {
+            Ok(comparison) => comparison,
+            Err(e) => {
+                eprintln!("Failed to render the comparison preview: {}", e);
+                None
+            }
+        };
+        // Encoding runs on its own worker while this one takes the next frame. A frame that a
+        // newer one overtook before its encode started is dropped there.
+        let emit_handle = app_handle.clone();
+        let active_image = active_image.clone();
+        app_handle.state::<AppState>().preview_emits.push(move || {
+            let app_handle = emit_handle;
+            let _scope = active_image.as_deref().map(performance::image_scope);
             if let Ok(histogram_data) = image_processing::calculate_histogram_from_image(&final_processed_image) {
                 let _ = app_handle.emit("histogram-update", histogram_data);
             }
@@ -337,10 +728,27 @@ fn apply_adjustments(
                 let _ = app_handle.emit("waveform-update", waveform_data);
             }
 
+            // Drawn after the analysis so the overlays do not show up in the histogram.
+            if let Some(comparison) = &comparison {
+                image_processing::compose_split_preview(&mut final_processed_image, comparison);
+            }
+            if show_clipping {
+                image_processing::paint_clipping_overlay(&mut final_processed_image);
+            }
+            if let Err(e) = apply_proof_overlay(&mut final_processed_image, proof_overlay.as_ref()) {
+                eprintln!("Failed to draw proofing overlay: {}", e);
+            }
+
             if let Ok(base64_str) = encode_to_base64(&final_processed_image, 88) {
                 let _ = app_handle.emit("preview-update-final", base64_str);
             }
-        }
+
+            if load_settings(app_handle.clone()).unwrap_or_default().performance_overlay.unwrap_or(false) {
+                if let Some(timings) = active_image.as_deref().and_then(performance::image_timings) {
+                    let _ = app_handle.emit("performance-update", timings);
+                }
+            }
+        });
     });
 
     Ok(())
@@ -356,7 +764,7 @@ fn generate_uncropped_preview(
     let adjustments_clone = js_adjustments.clone();
     let loaded_image = state.original_image.lock().unwrap().clone().ok_or("No original image loaded")?;
 
-    thread::spawn(move || {
+    state.uncropped_renders.push(move || {
         let patched_image = match composite_patches_on_image(&loaded_image.image, &adjustments_clone) {
             Ok(img) => img,
             Err(e) => {
@@ -368,11 +776,11 @@ fn generate_uncropped_preview(
         let (full_w, full_h) = (loaded_image.full_width, loaded_image.full_height);
 
         let settings = load_settings(app_handle.clone()).unwrap_or_default();
-        let preview_dim = settings.editor_preview_resolution.unwrap_or(1920);
+        let preview_dim = resolve_preview_dimension(&app_handle.state::<AppState>(), &settings);
 
         let (processing_base, scale_for_gpu) = 
             if full_w > preview_dim || full_h > preview_dim {
-                let base = patched_image.thumbnail(preview_dim, preview_dim);
+                let base = gpu_thumbnail(&context, &patched_image, preview_dim, preview_dim);
                 let scale = if full_w > 0 { base.width() as f32 / full_w as f32 } else { 1.0 };
                 (base, scale)
             } else {
@@ -390,8 +798,9 @@ fn generate_uncropped_preview(
             .collect();
 
         let uncropped_adjustments = get_all_adjustments_from_json(&adjustments_clone);
+        let lut = lut::lut_for(&adjustments_clone);
 
-        if let Ok(processed_image) = process_and_get_dynamic_image(&context, &processing_base, uncropped_adjustments, &mask_bitmaps) {
+        if let Ok(processed_image) = process_and_get_dynamic_image(&context, &processing_base, uncropped_adjustments, &mask_bitmaps, lut.as_deref()) {
             if let Ok(base64_str) = encode_to_base64(&processed_image, 85) {
                 let _ = app_handle.emit("preview-update-uncropped", base64_str);
             }
@@ -430,267 +839,854 @@ fn generate_fullscreen_preview(
         .collect();
 
     let all_adjustments = get_all_adjustments_from_json(&js_adjustments);
-    let final_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps)?;
+    let lut = lut::lut_for(&js_adjustments);
+    let mut final_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps, lut.as_deref())?;
+    apply_proof_overlay(&mut final_image, state.proof_overlay.lock().unwrap().as_ref())?;
     
     encode_to_base64(&final_image, 95)
 }
 
+// Renders through the export pipeline, so the copy matches what an export of the same size
+// would contain.
 #[tauri::command]
-async fn export_image(
-    original_path: String,
-    output_path: String,
+async fn copy_to_clipboard(
     js_adjustments: Value,
-    export_settings: ExportSettings,
+    resize: Option<ResizeOptions>,
     state: tauri::State<'_, AppState>,
-    app_handle: tauri::AppHandle,
 ) -> Result<(), String> {
-    if state.export_task_handle.lock().unwrap().is_some() {
-        return Err("An export is already in progress.".to_string());
+    let context = get_or_init_gpu_context(&state)?;
+    let (original_image, path) = {
+        let original_image_lock = state.original_image.lock().unwrap();
+        let loaded_image = original_image_lock.as_ref().ok_or("No original image loaded")?;
+        (loaded_image.image.clone(), loaded_image.path.clone())
+    };
+    let base_image = composite_patches_on_image(&original_image, &js_adjustments)
+        .map_err(|e| format!("Failed to composite AI patches for clipboard: {}", e))?;
+
+    let export_settings = ExportSettings { resize, ..ExportSettings::default() };
+    let final_image = process_image_for_export(&context, &base_image, &path, &js_adjustments, &export_settings, false)?;
+    clipboard::copy_image(&final_image)
+}
+
+fn target_extension(output_path: &str) -> String {
+    std::path::Path::new(output_path)
+        .extension()
+        .and_then(|s| s.to_str())
+        .unwrap_or("")
+        .to_lowercase()
+}
+
+// All targets share a single GPU render; only resizing, watermarking and encoding run per target.
+fn run_single_export(
+    context: &GpuContext,
+    original_image: &DynamicImage,
+    original_path: &str,
+    targets: &[(&str, &ExportSettings)],
+    js_adjustments: &Value,
+    destination: &mut dyn ExportDestination,
+    job: &ExportJobHandle,
+) -> Result<Vec<String>, String> {
+    job.report_stage(0, 1, original_path, ExportStage::Process);
+    let _scope = performance::image_scope(original_path);
+    let base_image = composite_patches_on_image(original_image, js_adjustments)
+        .map_err(|e| format!("Failed to composite AI patches for export: {}", e))?;
+
+    let source_is_raw = is_raw_file(original_path);
+    let any_high_precision = targets.iter().any(|(output_path, export_settings)| {
+        needs_high_precision_output(export_settings, &target_extension(output_path), source_is_raw)
+    });
+    let needs_render = targets.iter().any(|(output_path, _)| !is_tiled_format(&target_extension(output_path)));
+    let rendered = if needs_render {
+        Some(render_for_export(context, &base_image, js_adjustments, any_high_precision)?)
+    } else {
+        None
+    };
+    let mut exported_files = Vec::with_capacity(targets.len());
+
+    for (output_path, export_settings) in targets {
+        if job.checkpoint() {
+            return Ok(exported_files);
+        }
+
+        let extension = target_extension(output_path);
+        let Some(rendered) = rendered.as_ref().filter(|_| !is_tiled_format(&extension)) else {
+            job.report_stage(0, 1, original_path, ExportStage::Write);
+            exported_files.extend(export_tiled_target(
+                context,
+                &base_image,
+                original_path,
+                js_adjustments,
+                std::path::Path::new(output_path),
+                export_settings,
+            )?);
+            continue;
+        };
+        let high_precision = needs_high_precision_output(export_settings, &extension, source_is_raw);
+        let final_image = finalize_for_target(context, rendered.clone(), original_path, export_settings, high_precision)?;
+
+        job.report_stage(0, 1, original_path, ExportStage::Encode);
+        let mut image_bytes = encode_image_for_export(&final_image, &extension, export_settings, source_is_raw)?;
+
+        write_image_with_metadata(
+            &mut image_bytes,
+            original_path,
+            &extension,
+            export_settings.metadata_profile(),
+            export_settings.dpi,
+        )?;
+
+        job.report_stage(0, 1, original_path, ExportStage::Write);
+        exported_files.push(destination.write(std::path::Path::new(output_path), &image_bytes)?);
+        if export_settings.write_xmp_sidecar {
+            let metadata = load_image_metadata(original_path);
+            write_export_sidecar(
+                destination,
+                std::path::Path::new(output_path),
+                original_path,
+                &metadata,
+                js_adjustments,
+                export_settings.metadata_profile(),
+            )?;
+        }
     }
 
-    let context = get_or_init_gpu_context(&state)?;
-    let original_image_data = get_full_image_for_processing(&state)?;
-    let context = Arc::new(context);
+    job.report_progress(1, 1, "");
+    Ok(exported_files)
+}
 
-    let task = tokio::spawn(async move {
-        let processing_result: Result<(), String> = (|| {
-            let base_image = composite_patches_on_image(&original_image_data, &js_adjustments)
-                .map_err(|e| format!("Failed to composite AI patches for export: {}", e))?;
+fn run_batch_export(
+    context: &GpuContext,
+    output_folder: &str,
+    paths: &[String],
+    output_format: &str,
+    export_settings: &ExportSettings,
+    destination: &mut dyn ExportDestination,
+    job: &ExportJobHandle,
+    publish_target_id: Option<&str>,
+    start_index: usize,
+) -> Result<Vec<String>, String> {
+    let output_folder_path = std::path::Path::new(output_folder);
+    let total_paths = paths.len();
+    let mut exported_files = Vec::new();
+
+    for (i, image_path_str) in paths.iter().enumerate().skip(start_index) {
+        if job.checkpoint() {
+            println!("Export cancelled during batch processing.");
+            return Ok(exported_files);
+        }
 
-            let (transformed_image, unscaled_crop_offset) = 
-                apply_all_transformations(&base_image, &js_adjustments, 1.0);
-            let (img_w, img_h) = transformed_image.dimensions();
+        job.report_stage(i, total_paths, image_path_str, ExportStage::Decode);
+        let _scope = performance::image_scope(image_path_str);
 
-            let mask_definitions: Vec<MaskDefinition> = js_adjustments.get("masks")
-                .and_then(|m| serde_json::from_value(m.clone()).ok())
-                .unwrap_or_else(Vec::new);
+        let sidecar_path = get_sidecar_path(image_path_str);
+        let metadata: ImageMetadata = if sidecar_path.exists() {
+            let file_content = fs::read_to_string(sidecar_path).map_err(|e| e.to_string())?;
+            serde_json::from_str(&file_content).unwrap_or_default()
+        } else {
+            ImageMetadata::default()
+        };
 
-            let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
-                .filter_map(|def| generate_mask_bitmap(def, img_w, img_h, 1.0, unscaled_crop_offset))
-                .collect();
+        let versions_to_export = select_versions_for_export(&metadata, export_settings.version_selection.as_ref());
+        if versions_to_export.is_empty() {
+            // Only a named version can be missing; every image has a current edit.
+            job.image_skipped(i, image_path_str, "The image has no version with the selected name.");
+            continue;
+        }
+        // Taken before rendering, so edits made while the image exports count as a change.
+        let fingerprint = publish_target_id.map(|_| publish::image_fingerprint(image_path_str));
 
-            let all_adjustments = get_all_adjustments_from_json(&js_adjustments);
-            let mut final_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps)?;
-
-            if let Some(resize_opts) = export_settings.resize {
-                let (current_w, current_h) = final_image.dimensions();
-                let should_resize = if resize_opts.dont_enlarge {
-                    match resize_opts.mode {
-                        ResizeMode::LongEdge => current_w.max(current_h) > resize_opts.value,
-                        ResizeMode::Width => current_w > resize_opts.value,
-                        ResizeMode::Height => current_h > resize_opts.value,
-                    }
-                } else { true };
-
-                if should_resize {
-                    final_image = match resize_opts.mode {
-                        ResizeMode::LongEdge => {
-                            let (w, h) = if current_w > current_h {
-                                (resize_opts.value, (resize_opts.value as f32 * (current_h as f32 / current_w as f32)).round() as u32)
-                            } else {
-                                ((resize_opts.value as f32 * (current_w as f32 / current_h as f32)).round() as u32, resize_opts.value)
-                            };
-                            final_image.thumbnail(w, h)
-                        },
-                        ResizeMode::Width => final_image.thumbnail(resize_opts.value, u32::MAX),
-                        ResizeMode::Height => final_image.thumbnail(u32::MAX, resize_opts.value),
-                    };
-                }
-            }
+        let file_bytes = fs::read(image_path_str).map_err(|e| e.to_string())?;
+        let pristine_image = load_corrected_base_image(&file_bytes, image_path_str, false, &metadata.adjustments)
+            .map_err(|e| e.to_string())?;
 
-            let output_path_obj = std::path::Path::new(&output_path);
-            let extension = output_path_obj.extension().and_then(|s| s.to_str()).unwrap_or("").to_lowercase();
-            
-            let mut image_bytes = Vec::new();
-            let mut cursor = Cursor::new(&mut image_bytes);
-
-            match extension.as_str() {
-                "jpg" | "jpeg" => {
-                    let rgb_image = final_image.to_rgb8();
-                    let encoder = JpegEncoder::new_with_quality(&mut cursor, export_settings.jpeg_quality);
-                    rgb_image.write_with_encoder(encoder).map_err(|e| e.to_string())?;
-                }
-                "png" => {
-                    final_image.write_to(&mut cursor, image::ImageFormat::Png).map_err(|e| e.to_string())?;
-                }
-                "tiff" => {
-                    final_image.write_to(&mut cursor, image::ImageFormat::Tiff).map_err(|e| e.to_string())?;
-                }
-                _ => return Err(format!("Unsupported file extension: {}", extension)),
+        let source_is_raw = is_raw_file(image_path_str);
+        let high_precision = needs_high_precision_output(export_settings, output_format, source_is_raw);
+        let original_path = std::path::Path::new(image_path_str);
+        let capture_info = read_capture_info(&file_bytes, image_path_str);
+
+        let mut filename_template = export_settings.filename_template.clone().unwrap_or_else(|| "{original_filename}_edited".to_string());
+        if versions_to_export.len() > 1 && !filename_template.contains("{version}") {
+            filename_template.push_str("_{version}");
+        }
+
+        for (version_name, js_adjustments) in &versions_to_export {
+            job.report_stage(i, total_paths, image_path_str, ExportStage::Process);
+            let base_image = composite_patches_on_image(&pristine_image, js_adjustments)
+                .map_err(|e| e.to_string())?;
+
+            let new_stem = generate_filename_from_template(
+                &filename_template,
+                original_path,
+                i + 1,
+                total_paths,
+                version_name,
+                &capture_info,
+                metadata.rating,
+            );
+            let new_filename = format!("{}.{}", new_stem, output_format);
+            // Published images replace their previous export instead of getting a new name.
+            let output_path = if publish_target_id.is_some() {
+                output_folder_path.join(new_filename)
+            } else {
+                resolve_output_collision(output_folder_path.join(new_filename), destination)?
             };
 
+            if is_tiled_format(output_format) {
+                job.report_stage(i, total_paths, image_path_str, ExportStage::Write);
+                exported_files.extend(export_tiled_target(
+                    context,
+                    &base_image,
+                    image_path_str,
+                    js_adjustments,
+                    &output_path,
+                    export_settings,
+                )?);
+                continue;
+            }
+
+            let final_image = process_image_for_export(context, &base_image, image_path_str, js_adjustments, export_settings, high_precision)?;
+            job.report_stage(i, total_paths, image_path_str, ExportStage::Encode);
+            let mut image_bytes = encode_image_for_export(&final_image, output_format, export_settings, source_is_raw)?;
+
             write_image_with_metadata(
                 &mut image_bytes,
-                &original_path,
-                &extension,
-                export_settings.keep_metadata,
-                export_settings.strip_gps,
+                image_path_str,
+                output_format,
+                export_settings.metadata_profile(),
+                export_settings.dpi,
             )?;
 
-            fs::write(&output_path, image_bytes).map_err(|e| e.to_string())?;
+            job.report_stage(i, total_paths, image_path_str, ExportStage::Write);
+            exported_files.push(destination.write(&output_path, &image_bytes)?);
+            if export_settings.write_xmp_sidecar {
+                write_export_sidecar(
+                    destination,
+                    &output_path,
+                    image_path_str,
+                    &metadata,
+                    js_adjustments,
+                    export_settings.metadata_profile(),
+                )?;
+            }
+        }
 
-            Ok(())
-        })();
+        if let (Some(target_id), Some(fingerprint)) = (publish_target_id, fingerprint) {
+            publish::record_published(job.app_handle(), target_id, image_path_str, fingerprint);
+        }
+        job.image_finished(i);
+    }
 
-        if let Err(e) = processing_result {
-            let _ = app_handle.emit("export-error", e);
-        } else {
-            let _ = app_handle.emit("export-complete", ());
+    job.report_progress(total_paths, total_paths, "");
+    Ok(exported_files)
+}
+
+fn run_export_job(
+    app_handle: &tauri::AppHandle,
+    kind: &ExportJobKind,
+    export_settings: &ExportSettings,
+    job: &ExportJobHandle,
+) -> Result<(), String> {
+    let state = app_handle.state::<AppState>();
+    let context = get_or_init_gpu_context(&state)?;
+
+    // One connection per job; additional targets of a single export go to the same destination.
+    let settings = load_settings(app_handle.clone()).unwrap_or_default();
+    let mut destination = open_destination(&settings, export_settings.destination_id.as_deref())?;
+
+    let exported_files = match kind {
+        ExportJobKind::Single { original_path, output_path, js_adjustments, image, additional_targets } => {
+            let targets: Vec<(&str, &ExportSettings)> = std::iter::once((output_path.as_str(), export_settings))
+                .chain(additional_targets.iter().map(|t| (t.output_path.as_str(), &t.export_settings)))
+                .collect();
+            run_single_export(&context, image, original_path, &targets, js_adjustments, destination.as_mut(), job)?
         }
+        ExportJobKind::Batch { output_folder, paths, output_format, publish_target_id, start_index } => run_batch_export(
+            &context,
+            output_folder,
+            paths,
+            output_format,
+            export_settings,
+            destination.as_mut(),
+            job,
+            publish_target_id.as_deref(),
+            *start_index,
+        )?,
+    };
 
-        *app_handle.state::<AppState>().export_task_handle.lock().unwrap() = None;
-    });
+    // A failing hook is reported separately; the exported files themselves are fine.
+    if let Some(hook) = export_settings.post_export_hook.as_ref().filter(|_| !job.is_cancelled()) {
+        if let Err(e) = run_post_export_hook(hook, &exported_files) {
+            eprintln!("{}", e);
+            let _ = app_handle.emit("post-export-hook-error", e);
+        }
+    }
 
-    *state.export_task_handle.lock().unwrap() = Some(task);
     Ok(())
 }
 
+#[tauri::command]
+async fn export_image(
+    original_path: String,
+    output_path: String,
+    js_adjustments: Value,
+    export_settings: ExportSettings,
+    additional_targets: Option<Vec<ExportTarget>>,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let original_image_data = get_full_image_for_processing(&state)?;
+
+    let job_id = enqueue_export_job(
+        &app_handle,
+        ExportJobKind::Single {
+            original_path,
+            output_path,
+            js_adjustments,
+            image: original_image_data,
+            additional_targets: additional_targets.unwrap_or_default(),
+        },
+        export_settings,
+    );
+    Ok(job_id)
+}
+
+fn extension_for_format(file_format: &str) -> String {
+    match file_format.to_lowercase().as_str() {
+        "jpeg" => "jpg".to_string(),
+        other => other.to_string(),
+    }
+}
+
+fn resolve_export_preset(settings: &AppSettings, preset_id: Option<&str>) -> Result<(String, ExportSettings), String> {
+    let preset = preset_id.and_then(|id| settings.export_presets.as_ref()?.iter().find(|p| p.id == id));
+    match preset {
+        Some(preset) => Ok((
+            preset.file_format.clone(),
+            serde_json::from_value::<ExportSettings>(preset.export_settings.clone())
+                .map_err(|e| format!("Invalid export preset '{}': {}", preset.name, e))?,
+        )),
+        None => Ok(("jpeg".to_string(), ExportSettings::default())),
+    }
+}
+
+// Renders an image from the library (using its sidecar adjustments) into a file inside
+// the given temp subfolder. Used for drag and drop as well as sharing.
+const DRAG_TEMP_SUBFOLDER: &str = "rapidraw-drag";
+const SHARE_TEMP_SUBFOLDER: &str = "rapidraw-share";
+const TEMP_EXPORT_MAX_AGE: Duration = Duration::from_secs(60 * 60);
+
+// Dropped and shared files are read by other apps at a time we never learn about, so they are
+// removed once they are old instead of right after the drop.
+fn prune_temp_exports(temp_subfolder: &str, max_age: Duration) {
+    let Ok(entries) = fs::read_dir(std::env::temp_dir().join(temp_subfolder)) else {
+        return;
+    };
+    for entry in entries.filter_map(|e| e.ok()) {
+        let expired = entry
+            .metadata()
+            .and_then(|m| m.modified())
+            .ok()
+            .and_then(|modified| modified.elapsed().ok())
+            .map(|age| age >= max_age)
+            .unwrap_or(true);
+        if expired {
+            let path = entry.path();
+            let result = if path.is_dir() { fs::remove_dir_all(&path) } else { fs::remove_file(&path) };
+            if let Err(e) = result {
+                eprintln!("Failed to remove temporary export {}: {}", path.display(), e);
+            }
+        }
+    }
+}
+
+fn render_library_image_to_temp(
+    context: &GpuContext,
+    path: &str,
+    file_format: &str,
+    export_settings: &ExportSettings,
+    temp_subfolder: &str,
+) -> Result<std::path::PathBuf, String> {
+    let extension = extension_for_format(file_format);
+    if is_tiled_format(&extension) {
+        return Err("Tiled formats cannot be shared or dragged out.".to_string());
+    }
+
+    let sidecar_path = get_sidecar_path(path);
+    let metadata: ImageMetadata = fs::read_to_string(sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let js_adjustments = metadata.adjustments;
+
+    let file_bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let pristine_image = load_corrected_base_image(&file_bytes, path, false, &js_adjustments)
+        .map_err(|e| e.to_string())?;
+    let base_image = composite_patches_on_image(&pristine_image, &js_adjustments).map_err(|e| e.to_string())?;
+
+    let source_is_raw = is_raw_file(path);
+    let high_precision = needs_high_precision_output(export_settings, &extension, source_is_raw);
+    let final_image = process_image_for_export(context, &base_image, path, &js_adjustments, export_settings, high_precision)?;
+
+    let mut image_bytes = encode_image_for_export(&final_image, &extension, export_settings, source_is_raw)?;
+    write_image_with_metadata(
+        &mut image_bytes,
+        path,
+        &extension,
+        export_settings.metadata_profile(),
+        export_settings.dpi,
+    )?;
+
+    // Each request gets its own folder so two images with the same name never overwrite a file
+    // another app may still be reading.
+    prune_temp_exports(temp_subfolder, TEMP_EXPORT_MAX_AGE);
+    let temp_dir = std::env::temp_dir().join(temp_subfolder).join(Uuid::new_v4().to_string());
+    fs::create_dir_all(&temp_dir).map_err(|e| e.to_string())?;
+    let stem = std::path::Path::new(path)
+        .file_stem()
+        .and_then(|s| s.to_str())
+        .unwrap_or("image");
+    let output_path = temp_dir.join(format!("{}.{}", stem, extension));
+    fs::write(&output_path, image_bytes).map_err(|e| e.to_string())?;
+
+    Ok(output_path)
+}
+
+// Renders a library image with its saved edits at a reduced size, for overviews such as
+// contact sheets where a full-resolution render would be wasted work.
+fn render_library_image_preview(context: &GpuContext, path: &str, max_dim: u32) -> Result<DynamicImage, String> {
+    let metadata: ImageMetadata = fs::read_to_string(get_sidecar_path(path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    let js_adjustments = metadata.adjustments;
+
+    let file_bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let pristine_image = load_corrected_base_image(&file_bytes, path, false, &js_adjustments)
+        .map_err(|e| e.to_string())?;
+    let base_image = composite_patches_on_image(&pristine_image, &js_adjustments).map_err(|e| e.to_string())?;
+
+    let full_w = base_image.width();
+    let (preview_base, scale) = if base_image.width() > max_dim || base_image.height() > max_dim {
+        let base = base_image.thumbnail(max_dim, max_dim);
+        let scale = if full_w > 0 { base.width() as f32 / full_w as f32 } else { 1.0 };
+        (base, scale)
+    } else {
+        (base_image, 1.0)
+    };
+
+    let (transformed_image, unscaled_crop_offset) =
+        apply_all_transformations(&preview_base, &js_adjustments, scale);
+    let (img_w, img_h) = transformed_image.dimensions();
+    let scaled_crop_offset = (unscaled_crop_offset.0 * scale, unscaled_crop_offset.1 * scale);
+
+    let mask_definitions: Vec<MaskDefinition> = js_adjustments.get("masks")
+        .and_then(|m| serde_json::from_value(m.clone()).ok())
+        .unwrap_or_else(Vec::new);
+
+    let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
+        .filter_map(|def| generate_mask_bitmap(def, img_w, img_h, scale, scaled_crop_offset))
+        .collect();
+
+    let all_adjustments = get_all_adjustments_from_json(&js_adjustments);
+    let lut = lut::lut_for(&js_adjustments);
+    let mut final_image = process_and_get_dynamic_image(context, &transformed_image, all_adjustments, &mask_bitmaps, lut.as_deref())?;
+    apply_proof_overlay(&mut final_image, overlay_for_path(path).as_ref())?;
+    Ok(final_image)
+}
+
+// Uses the export preset chosen for drag and drop (or plain JPEG defaults), so the frontend
+// can hand the OS a real file to drop.
+#[tauri::command]
+async fn prepare_drag_export(
+    path: String,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let (file_format, export_settings) =
+        resolve_export_preset(&settings, settings.drag_export_preset_id.as_deref())?;
+
+    let context = get_or_init_gpu_context(&state)?;
+    let output_path = render_library_image_to_temp(&context, &path, &file_format, &export_settings, DRAG_TEMP_SUBFOLDER)?;
+    Ok(output_path.to_string_lossy().into_owned())
+}
+
+#[tauri::command]
+async fn share_image(
+    path: String,
+    preset_id: Option<String>,
+    window: tauri::WebviewWindow,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<(), String> {
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let (file_format, export_settings) = resolve_export_preset(&settings, preset_id.as_deref())?;
+
+    let context = get_or_init_gpu_context(&state)?;
+    let output_path = render_library_image_to_temp(&context, &path, &file_format, &export_settings, SHARE_TEMP_SUBFOLDER)?;
+    share::show_share_sheet(&window, &output_path).await
+}
+
 #[tauri::command]
 async fn batch_export_images(
     output_folder: String,
     paths: Vec<String>,
     export_settings: ExportSettings,
     output_format: String,
-    state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
+) -> Result<String, String> {
+    let job_id = enqueue_export_job(
+        &app_handle,
+        ExportJobKind::Batch {
+            output_folder,
+            paths,
+            output_format,
+            publish_target_id: None,
+            start_index: 0,
+        },
+        export_settings,
+    );
+    Ok(job_id)
+}
+
+fn needs_high_precision_output(export_settings: &ExportSettings, extension: &str, source_is_raw: bool) -> bool {
+    match extension {
+        "png" | "tiff" => export_settings.bit_depth == Some(16),
+        "heic" | "heif" => source_is_raw,
+        "jxl" => source_is_raw || export_settings.bit_depth == Some(16),
+        _ => false,
+    }
+}
+
+fn encode_image_for_export(
+    image: &DynamicImage,
+    extension: &str,
+    export_settings: &ExportSettings,
+    source_is_raw: bool,
+) -> Result<Vec<u8>, String> {
+    let mut image_bytes = Vec::new();
+    let mut cursor = Cursor::new(&mut image_bytes);
+
+    match extension {
+        "jpg" | "jpeg" => {
+            let rgb_image = image.to_rgb8();
+            let mut encoder = JpegEncoder::new_with_quality(&mut cursor, export_settings.jpeg_quality);
+            if let Some(dpi) = export_settings.dpi {
+                encoder.set_pixel_density(PixelDensity::dpi(dpi.min(u16::MAX as u32) as u16));
+            }
+            rgb_image.write_with_encoder(encoder).map_err(|e| e.to_string())?;
+        }
+        "png" => {
+            image.write_to(&mut cursor, image::ImageFormat::Png).map_err(|e| e.to_string())?;
+        }
+        "tiff" => {
+            if let Some(cmyk_settings) = &export_settings.cmyk {
+                return cmyk::encode_cmyk_tiff(image, cmyk_settings, export_settings.dpi);
+            }
+            match export_settings.dpi {
+                Some(dpi) => encode_tiff_with_resolution(image, dpi, &mut cursor)?,
+                None => image.write_to(&mut cursor, image::ImageFormat::Tiff).map_err(|e| e.to_string())?,
+            }
+        }
+        "jxl" => return encode_jxl(image, export_settings.jpeg_quality, export_settings.jxl_lossless),
+        "heic" | "heif" => return encode_heic(image, export_settings.jpeg_quality, source_is_raw),
+        _ => return Err(format!("Unsupported file format: {}", extension)),
+    };
+
+    Ok(image_bytes)
+}
+
+// The image crate's TIFF encoder has no way to set the resolution tags, so TIFFs with a DPI
+// are written through the tiff crate directly.
+fn encode_tiff_with_resolution(
+    image: &DynamicImage,
+    dpi: u32,
+    writer: &mut Cursor<&mut Vec<u8>>,
 ) -> Result<(), String> {
-    if state.export_task_handle.lock().unwrap().is_some() {
-        return Err("An export is already in progress.".to_string());
+    use tiff::encoder::{colortype, Rational, TiffEncoder};
+    use tiff::tags::ResolutionUnit;
+
+    let (width, height) = image.dimensions();
+    let mut encoder = TiffEncoder::new(writer).map_err(|e| e.to_string())?;
+    let resolution = Rational { n: dpi, d: 1 };
+
+    macro_rules! write_tiff {
+        ($color:ty, $data:expr) => {{
+            let mut tiff_image = encoder.new_image::<$color>(width, height).map_err(|e| e.to_string())?;
+            tiff_image.resolution(ResolutionUnit::Inch, resolution);
+            tiff_image.write_data($data).map_err(|e| e.to_string())
+        }};
     }
 
-    let context = get_or_init_gpu_context(&state)?;
-    let context = Arc::new(context);
+    match image {
+        DynamicImage::ImageRgb16(buffer) => write_tiff!(colortype::RGB16, buffer.as_raw()),
+        DynamicImage::ImageRgba16(buffer) => write_tiff!(colortype::RGBA16, buffer.as_raw()),
+        DynamicImage::ImageRgb8(buffer) => write_tiff!(colortype::RGB8, buffer.as_raw()),
+        _ => write_tiff!(colortype::RGBA8, image.to_rgba8().as_raw()),
+    }
+}
 
-    let task = tokio::spawn(async move {
-        let output_folder_path = std::path::Path::new(&output_folder);
-        let total_paths = paths.len();
+// The sample type passed to the encoder sets the bits per sample of the file, so 16-bit
+// renders stay 16-bit instead of being quantized to 8 bits.
+fn encode_jxl(image: &DynamicImage, quality: u8, lossless: bool) -> Result<Vec<u8>, String> {
+    let (width, height) = image.dimensions();
 
-        for (i, image_path_str) in paths.iter().enumerate() {
-            if app_handle.state::<AppState>().export_task_handle.lock().unwrap().is_none() {
-                println!("Export cancelled during batch processing.");
-                let _ = app_handle.emit("export-cancelled", ());
-                return;
+    // Same mapping libjxl uses to turn a JPEG-style quality into a butteraugli distance.
+    let quality = quality.clamp(1, 100) as f32;
+    let distance = if quality >= 30.0 {
+        0.1 + (100.0 - quality) * 0.09
+    } else {
+        53.0 / 3000.0 * quality * quality - 23.0 / 20.0 * quality + 25.0
+    };
+
+    let mut encoder = jpegxl_rs::encoder_builder()
+        .lossless(lossless)
+        .uses_original_profile(lossless)
+        .quality(distance)
+        .speed(jpegxl_rs::encode::EncoderSpeed::Squirrel)
+        .build()
+        .map_err(|e| e.to_string())?;
+
+    macro_rules! encode_as {
+        ($sample:ty, $pixels:expr) => {{
+            let result: jpegxl_rs::encode::EncoderResult<$sample> = encoder
+                .encode::<$sample, $sample>($pixels.as_raw(), width, height)
+                .map_err(|e| e.to_string())?;
+            Ok(result.data)
+        }};
+    }
+
+    match image {
+        DynamicImage::ImageRgb16(_) | DynamicImage::ImageRgba16(_) => encode_as!(u16, image.to_rgb16()),
+        _ => encode_as!(u8, image.to_rgb8()),
+    }
+}
+
+fn encode_heic(image: &DynamicImage, quality: u8, ten_bit: bool) -> Result<Vec<u8>, String> {
+    let (width, height) = image.dimensions();
+    let lib_heif = LibHeif::new();
+
+    let heif_image = if ten_bit {
+        let mut heif_image = HeifImage::new(width, height, ColorSpace::Rgb(RgbChroma::HdrRgbLe))
+            .map_err(|e| e.to_string())?;
+        heif_image.create_plane(Channel::Interleaved, width, height, 10).map_err(|e| e.to_string())?;
+
+        let rgb_image = image.to_rgb16();
+        let planes = heif_image.planes_mut();
+        let plane = planes.interleaved.ok_or("Failed to allocate HEIF image plane")?;
+        let row_len = width as usize * 3;
+        for (y, row) in rgb_image.as_raw().chunks_exact(row_len).enumerate() {
+            let dst = &mut plane.data[y * plane.stride..y * plane.stride + row_len * 2];
+            for (value, out) in row.iter().zip(dst.chunks_exact_mut(2)) {
+                out.copy_from_slice(&(value >> 6).to_le_bytes());
             }
+        }
+        heif_image
+    } else {
+        let mut heif_image = HeifImage::new(width, height, ColorSpace::Rgb(RgbChroma::Rgb))
+            .map_err(|e| e.to_string())?;
+        heif_image.create_plane(Channel::Interleaved, width, height, 8).map_err(|e| e.to_string())?;
+
+        let rgb_image = image.to_rgb8();
+        let planes = heif_image.planes_mut();
+        let plane = planes.interleaved.ok_or("Failed to allocate HEIF image plane")?;
+        let row_len = width as usize * 3;
+        for (y, row) in rgb_image.as_raw().chunks_exact(row_len).enumerate() {
+            plane.data[y * plane.stride..y * plane.stride + row_len].copy_from_slice(row);
+        }
+        heif_image
+    };
 
-            let _ = app_handle.emit("batch-export-progress", serde_json::json!({ "current": i, "total": total_paths, "path": image_path_str }));
-
-            let processing_result: Result<(), String> = (|| {
-                let sidecar_path = get_sidecar_path(image_path_str);
-                let metadata: ImageMetadata = if sidecar_path.exists() {
-                    let file_content = fs::read_to_string(sidecar_path).map_err(|e| e.to_string())?;
-                    serde_json::from_str(&file_content).unwrap_or_default()
-                } else {
-                    ImageMetadata::default()
-                };
-                let js_adjustments = metadata.adjustments;
-
-                let base_image = load_and_composite(image_path_str, &js_adjustments, false)
-                    .map_err(|e| e.to_string())?;
-                
-                let (transformed_image, unscaled_crop_offset) = 
-                    apply_all_transformations(&base_image, &js_adjustments, 1.0);
-                let (img_w, img_h) = transformed_image.dimensions();
-
-                let mask_definitions: Vec<MaskDefinition> = js_adjustments.get("masks")
-                    .and_then(|m| serde_json::from_value(m.clone()).ok())
-                    .unwrap_or_else(Vec::new);
-
-                let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
-                    .filter_map(|def| generate_mask_bitmap(def, img_w, img_h, 1.0, unscaled_crop_offset))
-                    .collect();
-
-                let all_adjustments = get_all_adjustments_from_json(&js_adjustments);
-                let mut final_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps)?;
-
-                if let Some(resize_opts) = &export_settings.resize {
-                    let (current_w, current_h) = final_image.dimensions();
-                    let should_resize = if resize_opts.dont_enlarge {
-                        match resize_opts.mode {
-                            ResizeMode::LongEdge => current_w.max(current_h) > resize_opts.value,
-                            ResizeMode::Width => current_w > resize_opts.value,
-                            ResizeMode::Height => current_h > resize_opts.value,
-                        }
-                    } else { true };
-
-                    if should_resize {
-                        final_image = match resize_opts.mode {
-                            ResizeMode::LongEdge => {
-                                let (w, h) = if current_w > current_h {
-                                    (resize_opts.value, (resize_opts.value as f32 * (current_h as f32 / current_w as f32)).round() as u32)
-                                } else {
-                                    ((resize_opts.value as f32 * (current_w as f32 / current_h as f32)).round() as u32, resize_opts.value)
-                                };
-                                final_image.thumbnail(w, h)
-                            },
-                            ResizeMode::Width => final_image.thumbnail(resize_opts.value, u32::MAX),
-                            ResizeMode::Height => final_image.thumbnail(u32::MAX, resize_opts.value),
-                        };
-                    }
-                }
+    let mut context = HeifContext::new().map_err(|e| e.to_string())?;
+    let mut encoder = lib_heif
+        .encoder_for_format(CompressionFormat::Hevc)
+        .map_err(|e| e.to_string())?;
+    encoder
+        .set_quality(EncoderQuality::Lossy(quality.min(100)))
+        .map_err(|e| e.to_string())?;
+    context
+        .encode_image(&heif_image, &mut encoder, None)
+        .map_err(|e| e.to_string())?;
 
-                let original_path = std::path::Path::new(image_path_str);
-                let filename_template = export_settings.filename_template.as_deref().unwrap_or("{original_filename}_edited");
-                let new_stem = generate_filename_from_template(filename_template, original_path, i + 1, total_paths);
-                let new_filename = format!("{}.{}", new_stem, output_format);
-                let output_path = output_folder_path.join(new_filename);
-
-                let mut image_bytes = Vec::new();
-                let mut cursor = Cursor::new(&mut image_bytes);
-
-                match output_format.as_str() {
-                    "jpg" | "jpeg" => {
-                        let rgb_image = final_image.to_rgb8();
-                        let encoder = JpegEncoder::new_with_quality(&mut cursor, export_settings.jpeg_quality);
-                        rgb_image.write_with_encoder(encoder).map_err(|e| e.to_string())?;
-                    }
-                    "png" => {
-                        final_image.write_to(&mut cursor, image::ImageFormat::Png).map_err(|e| e.to_string())?;
-                    }
-                    "tiff" => {
-                        final_image.write_to(&mut cursor, image::ImageFormat::Tiff).map_err(|e| e.to_string())?;
-                    }
-                    _ => return Err(format!("Unsupported file format: {}", output_format)),
-                };
-
-                write_image_with_metadata(
-                    &mut image_bytes,
-                    image_path_str,
-                    &output_format,
-                    export_settings.keep_metadata,
-                    export_settings.strip_gps,
-                )?;
+    context.write_to_bytes().map_err(|e| e.to_string())
+}
 
-                fs::write(&output_path, image_bytes).map_err(|e| e.to_string())?;
+fn select_versions_for_export(
+    metadata: &ImageMetadata,
+    selection: Option<&VersionSelection>,
+) -> Vec<(String, Value)> {
+    let current = ("current".to_string(), metadata.adjustments.clone());
+    match selection {
+        None | Some(VersionSelection::Current) => vec![current],
+        Some(VersionSelection::Named(name)) => metadata.versions.iter()
+            .find(|v| &v.name == name || &v.id == name)
+            .map(|v| vec![(v.name.clone(), v.adjustments.clone())])
+            .unwrap_or_default(),
+        Some(VersionSelection::All) => std::iter::once(current)
+            .chain(metadata.versions.iter().map(|v| (v.name.clone(), v.adjustments.clone())))
+            .collect(),
+    }
+}
 
-                Ok(())
-            })();
+fn resize_for_export(context: &GpuContext, image: DynamicImage, resize_opts: &ResizeOptions) -> DynamicImage {
+    let (current_w, current_h) = image.dimensions();
+    let (w, h) = (current_w as f64, current_h as f64);
+    let value = resize_opts.value.max(0.0);
+
+    let should_resize = if resize_opts.dont_enlarge {
+        match resize_opts.mode {
+            ResizeMode::LongEdge => w.max(h) > value,
+            ResizeMode::Width => w > value,
+            ResizeMode::Height => h > value,
+            ResizeMode::Megapixels => w * h > value * 1_000_000.0,
+            ResizeMode::Percent => value < 100.0,
+        }
+    } else { true };
 
-            if let Err(e) = processing_result {
-                eprintln!("Failed to export {}: {}", image_path_str, e);
-                let _ = app_handle.emit("export-error", e);
-                *app_handle.state::<AppState>().export_task_handle.lock().unwrap() = None;
-                return;
+    if !should_resize || value <= 0.0 {
+        return image;
+    }
+
+    let (new_w, new_h) = match resize_opts.mode {
+        ResizeMode::LongEdge => {
+            if current_w > current_h {
+                (value, value * (h / w))
+            } else {
+                (value * (w / h), value)
             }
+        },
+        ResizeMode::Width => (value, value * (h / w)),
+        ResizeMode::Height => (value * (w / h), value),
+        ResizeMode::Megapixels | ResizeMode::Percent => {
+            let scale = match resize_opts.mode {
+                ResizeMode::Megapixels => (value * 1_000_000.0 / (w * h)).sqrt(),
+                _ => value / 100.0,
+            };
+            (w * scale, h * scale)
+        }
+    };
+    let new_w = (new_w.round() as u32).max(1);
+    let new_h = (new_h.round() as u32).max(1);
+
+    gpu_resize(context, &image, new_w, new_h, resize_opts.filter).unwrap_or_else(|e| {
+        eprintln!("GPU resize failed, resizing on the CPU: {}", e);
+        match (resize_opts.filter, &resize_opts.mode) {
+            (ResizeFilter::Fast, ResizeMode::Megapixels | ResizeMode::Percent) => {
+                image.resize_exact(new_w, new_h, image::imageops::FilterType::Lanczos3)
+            }
+            (filter, _) => resize_image(&image, new_w, new_h, filter),
         }
+    })
+}
 
-        let _ = app_handle.emit("batch-export-progress", serde_json::json!({ "current": total_paths, "total": total_paths, "path": "" }));
-        let _ = app_handle.emit("export-complete", ());
-        *app_handle.state::<AppState>().export_task_handle.lock().unwrap() = None;
-    });
+fn prepare_for_render(
+    base_image: &DynamicImage,
+    js_adjustments: &Value,
+) -> (DynamicImage, AllAdjustments, Vec<ImageBuffer<Luma<u8>, Vec<u8>>>) {
+    let (transformed_image, unscaled_crop_offset) =
+        apply_all_transformations(base_image, js_adjustments, 1.0);
+    let (img_w, img_h) = transformed_image.dimensions();
 
-    *state.export_task_handle.lock().unwrap() = Some(task);
-    Ok(())
+    let mask_definitions: Vec<MaskDefinition> = js_adjustments.get("masks")
+        .and_then(|m| serde_json::from_value(m.clone()).ok())
+        .unwrap_or_else(Vec::new);
+
+    let mask_bitmaps: Vec<ImageBuffer<Luma<u8>, Vec<u8>>> = mask_definitions.iter()
+        .filter_map(|def| generate_mask_bitmap(def, img_w, img_h, 1.0, unscaled_crop_offset))
+        .collect();
+
+    let all_adjustments = get_all_adjustments_from_json(js_adjustments);
+    (transformed_image, all_adjustments, mask_bitmaps)
 }
 
-#[tauri::command]
-fn cancel_export(state: tauri::State<AppState>) -> Result<(), String> {
-    if let Some(handle) = state.export_task_handle.lock().unwrap().take() {
-        handle.abort();
-        println!("Export task cancellation requested.");
+fn render_for_export(
+    context: &GpuContext,
+    base_image: &DynamicImage,
+    js_adjustments: &Value,
+    high_precision: bool,
+) -> Result<DynamicImage, String> {
+    let (transformed_image, all_adjustments, mask_bitmaps) = prepare_for_render(base_image, js_adjustments);
+    let lut = lut::lut_for(js_adjustments);
+    if high_precision {
+        process_and_get_dynamic_image_16bit(context, &transformed_image, all_adjustments, &mask_bitmaps, lut.as_deref())
     } else {
-        return Err("No export task is currently running.".to_string());
+        process_and_get_dynamic_image(context, &transformed_image, all_adjustments, &mask_bitmaps, lut.as_deref())
     }
-    Ok(())
+}
+
+// Tiled targets are rendered tile by tile and written straight into the output folder, so
+// the full render never exists in memory. Resizing, watermarks and borders do not apply.
+fn export_tiled_target(
+    context: &GpuContext,
+    base_image: &DynamicImage,
+    source_path: &str,
+    js_adjustments: &Value,
+    output_path: &std::path::Path,
+    export_settings: &ExportSettings,
+) -> Result<Vec<String>, String> {
+    if export_settings.destination_id.is_some() {
+        return Err("Tiled formats can only be exported to a local folder.".to_string());
+    }
+    if overlay_for_path(source_path).is_some() {
+        return Err("Images in a proofing folder cannot be exported as tiles.".to_string());
+    }
+    let (transformed_image, all_adjustments, mask_bitmaps) = prepare_for_render(base_image, js_adjustments);
+    let lut = lut::lut_for(js_adjustments);
+    tiled_export::export_tiled(
+        context,
+        &transformed_image,
+        all_adjustments,
+        &mask_bitmaps,
+        lut.as_deref(),
+        output_path,
+        export_settings.jpeg_quality,
+    )
+}
+
+fn finalize_for_target(
+    context: &GpuContext,
+    mut final_image: DynamicImage,
+    source_path: &str,
+    export_settings: &ExportSettings,
+    high_precision: bool,
+) -> Result<DynamicImage, String> {
+    if !high_precision && matches!(final_image, DynamicImage::ImageRgba16(_)) {
+        final_image = DynamicImage::ImageRgba8(final_image.to_rgba8());
+    }
+
+    if let Some(resize_opts) = &export_settings.resize {
+        final_image = resize_for_export(context, final_image, resize_opts);
+    }
+
+    apply_proof_overlay(&mut final_image, overlay_for_path(source_path).as_ref())?;
+
+    if let Some(watermark_settings) = &export_settings.watermark {
+        apply_watermark(&mut final_image, watermark_settings)?;
+    }
+
+    if let Some(border_settings) = &export_settings.border {
+        final_image = apply_border(final_image, border_settings);
+    }
+
+    Ok(final_image)
+}
+
+fn process_image_for_export(
+    context: &GpuContext,
+    base_image: &DynamicImage,
+    source_path: &str,
+    js_adjustments: &Value,
+    export_settings: &ExportSettings,
+    high_precision: bool,
+) -> Result<DynamicImage, String> {
+    let rendered = render_for_export(context, base_image, js_adjustments, high_precision)?;
+    finalize_for_target(context, rendered, source_path, export_settings, high_precision)
+}
+
+fn sanitize_filename_component(value: &str) -> String {
+    value.chars()
+        .map(|c| if c.is_alphanumeric() || c == '-' || c == '_' || c == ' ' { c } else { '_' })
+        .collect()
 }
 
 fn generate_filename_from_template(
@@ -698,6 +1694,9 @@ fn generate_filename_from_template(
     original_path: &std::path::Path,
     sequence: usize,
     total: usize,
+    version_name: &str,
+    capture_info: &CaptureInfo,
+    rating: u8,
 ) -> String {
     let now = Local::now();
     let stem = original_path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
@@ -711,18 +1710,137 @@ fn generate_filename_from_template(
     result = result.replace("{DD}", &now.format("%d").to_string());
     result = result.replace("{hh}", &now.format("%H").to_string());
     result = result.replace("{mm}", &now.format("%M").to_string());
+    result = result.replace("{version}", &sanitize_filename_component(version_name));
+
+    let exif_token = |value: Option<String>| {
+        value
+            .map(|v| sanitize_filename_component(v.trim()))
+            .filter(|v| !v.is_empty())
+            .unwrap_or_else(|| "unknown".to_string())
+    };
+    result = result.replace("{camera}", &exif_token(Some(capture_info.model.clone())));
+    result = result.replace("{lens}", &exif_token(capture_info.lens.clone()));
+    result = result.replace("{iso}", &exif_token(capture_info.iso.map(|iso| iso.to_string())));
+    result = result.replace("{capture_date}", &exif_token(capture_info.capture_date.clone()));
+    result = result.replace("{rating}", &rating.to_string());
 
     result
 }
 
+// A Deep Zoom target also writes a `_files` folder, so a name is only free when that is too.
+fn output_path_taken(path: &std::path::Path, destination: &mut dyn ExportDestination) -> Result<bool, String> {
+    if destination.exists(path)? {
+        return Ok(true);
+    }
+    match tiled_export::companion_dir(path) {
+        Some(dir) => destination.exists(&dir),
+        None => Ok(false),
+    }
+}
+
+// Appends `-1`, `-2`, ... to the file stem until the destination has no file of that name,
+// so batch exports never overwrite earlier results.
+fn resolve_output_collision(
+    output_path: std::path::PathBuf,
+    destination: &mut dyn ExportDestination,
+) -> Result<std::path::PathBuf, String> {
+    if !output_path_taken(&output_path, destination)? {
+        return Ok(output_path);
+    }
+
+    let stem = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("image").to_string();
+    let extension = output_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_string();
+    for suffix in 1.. {
+        let file_name = if extension.is_empty() {
+            format!("{}-{}", stem, suffix)
+        } else {
+            format!("{}-{}.{}", stem, suffix, extension)
+        };
+        let candidate = output_path.with_file_name(file_name);
+        if !output_path_taken(&candidate, destination)? {
+            return Ok(candidate);
+        }
+    }
+    unreachable!()
+}
+
+fn set_resolution_tags(metadata: &mut Metadata, dpi: u32) {
+    let resolution = vec![uR64 { nominator: dpi, denominator: 1 }];
+    metadata.set_tag(ExifTag::XResolution(resolution.clone()));
+    metadata.set_tag(ExifTag::YResolution(resolution));
+    // 2 = inches
+    metadata.set_tag(ExifTag::ResolutionUnit(vec![2u16]));
+}
+
+fn load_image_metadata(path: &str) -> ImageMetadata {
+    fs::read_to_string(get_sidecar_path(path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+// Written as `<export>.xmp` through the same destination as the image, so DAM tools indexing
+// the export folder pick it up. It is not part of the exported file list handed to hooks.
+fn write_export_sidecar(
+    destination: &mut dyn ExportDestination,
+    output_path: &std::path::Path,
+    original_path: &str,
+    metadata: &ImageMetadata,
+    adjustments: &Value,
+    profile: MetadataProfile,
+) -> Result<(), String> {
+    // The sidecar carries no more than the profile lets into the image itself.
+    let source_exif = read_source_exif(original_path).map(|exif| metadata_profiles::filter_rawler_exif(&exif, profile));
+    let xmp = metadata_writeback::build_export_sidecar(
+        profile.copies_library_tags().then_some(metadata),
+        adjustments,
+        source_exif.as_ref().and_then(|e| e.copyright.as_deref()),
+        source_exif.as_ref().and_then(|e| e.artist.as_deref()),
+    );
+    // The full file name is kept, so targets sharing a stem (x.jpg, x.tiff) get their own sidecar.
+    let mut sidecar_name = output_path.file_name().unwrap_or_default().to_os_string();
+    sidecar_name.push(".xmp");
+    destination.write(&output_path.with_file_name(sidecar_name), xmp.as_bytes())?;
+    Ok(())
+}
+
+fn read_source_exif(path: &str) -> Option<rawler::exif::Exif> {
+    let file_bytes = fs::read(path).ok()?;
+    if is_raw_file(path) {
+        return read_raw_metadata(&file_bytes).ok().map(|metadata| metadata.exif);
+    }
+    let source_exif = exif::Reader::new().read_from_container(&mut Cursor::new(&file_bytes)).ok()?;
+    let reader = GenericTiffReader::new_with_buffer(source_exif.buf(), 0, 0, Some(0)).ok()?;
+    rawler::exif::Exif::new(reader.root_ifd()).ok()
+}
+
+// little_exif cannot write TIFF, so the encoded TIFF is rebuilt with the source EXIF in its
+// first IFD. A failure only loses the metadata, never the export itself.
+fn write_tiff_exif(image_bytes: &mut Vec<u8>, original_path_str: &str, profile: MetadataProfile) {
+    let Some(source_exif) = read_source_exif(original_path_str) else {
+        eprintln!("No EXIF data found to copy from {}", original_path_str);
+        return;
+    };
+    let mut exif = metadata_profiles::filter_rawler_exif(&source_exif, profile);
+    exif.orientation = Some(1);
+    match rawler::formats::tiff::writer::rewrite_tiff_with_exif(image_bytes, &exif) {
+        Ok(bytes) => *image_bytes = bytes,
+        Err(e) => eprintln!("Failed to write EXIF to TIFF for {}: {}", original_path_str, e),
+    }
+}
+
 fn write_image_with_metadata(
     image_bytes: &mut Vec<u8>,
     original_path_str: &str,
     output_format: &str,
-    keep_metadata: bool,
-    strip_gps: bool,
+    profile: MetadataProfile,
+    dpi: Option<u32>,
 ) -> Result<(), String> {
-    if !keep_metadata || output_format.to_lowercase() == "tiff" { // FIXME: temporary solution until I find a way to write metadata to TIFF
+    // The TIFF encoders already wrote the resolution tags, so only the EXIF is left to add.
+    if output_format.to_lowercase() == "tiff" {
+        if profile.copies_metadata() {
+            write_tiff_exif(image_bytes, original_path_str, profile);
+        }
         return Ok(());
     }
 
@@ -733,53 +1851,31 @@ fn write_image_with_metadata(
         _ => return Ok(()),
     };
 
+    // Without metadata the output still carries its print resolution if one was requested.
+    if !profile.copies_metadata() {
+        if let Some(dpi) = dpi {
+            let mut metadata = Metadata::new();
+            set_resolution_tags(&mut metadata, dpi);
+            if metadata.write_to_vec(image_bytes, file_type).is_err() {
+                eprintln!("Failed to write resolution tags for {}", original_path_str);
+            }
+        }
+        return Ok(());
+    }
+
     let original_path = std::path::Path::new(original_path_str);
     if !original_path.exists() {
         eprintln!("Original file not found, cannot copy metadata: {}", original_path_str);
         return Ok(());
     }
 
-    if let Ok(mut metadata) = Metadata::new_from_path(original_path) {
-        if strip_gps {
-            let dummy_rational = uR64 { nominator: 0, denominator: 1 };
-            let dummy_rational_vec1 = vec![dummy_rational.clone()];
-            let dummy_rational_vec3 = vec![dummy_rational.clone(), dummy_rational.clone(), dummy_rational.clone()];
-
-            metadata.remove_tag(ExifTag::GPSVersionID([0,0,0,0].to_vec()));
-            metadata.remove_tag(ExifTag::GPSLatitudeRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSLatitude(dummy_rational_vec3.clone()));
-            metadata.remove_tag(ExifTag::GPSLongitudeRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSLongitude(dummy_rational_vec3.clone()));
-            metadata.remove_tag(ExifTag::GPSAltitudeRef(vec![0]));
-            metadata.remove_tag(ExifTag::GPSAltitude(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSTimeStamp(dummy_rational_vec3.clone()));
-            metadata.remove_tag(ExifTag::GPSSatellites("".to_string()));
-            metadata.remove_tag(ExifTag::GPSStatus("".to_string()));
-            metadata.remove_tag(ExifTag::GPSMeasureMode("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDOP(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSSpeedRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSSpeed(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSTrackRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSTrack(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSImgDirectionRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSImgDirection(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSMapDatum("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDestLatitudeRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDestLatitude(dummy_rational_vec3.clone()));
-            metadata.remove_tag(ExifTag::GPSDestLongitudeRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDestLongitude(dummy_rational_vec3.clone()));
-            metadata.remove_tag(ExifTag::GPSDestBearingRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDestBearing(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSDestDistanceRef("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDestDistance(dummy_rational_vec1.clone()));
-            metadata.remove_tag(ExifTag::GPSProcessingMethod(vec![]));
-            metadata.remove_tag(ExifTag::GPSAreaInformation(vec![]));
-            metadata.remove_tag(ExifTag::GPSDateStamp("".to_string()));
-            metadata.remove_tag(ExifTag::GPSDifferential(vec![0u16]));
-            metadata.remove_tag(ExifTag::GPSHPositioningError(dummy_rational_vec1.clone()));
-        }
+    if let Ok(source_metadata) = Metadata::new_from_path(original_path) {
+        let mut metadata = metadata_profiles::filter_metadata(source_metadata, profile);
 
         metadata.set_tag(ExifTag::Orientation(vec![1u16]));
+        if let Some(dpi) = dpi {
+            set_resolution_tags(&mut metadata, dpi);
+        }
 
         if metadata.write_to_vec(image_bytes, file_type).is_err() {
             eprintln!("Failed to write metadata to image vector for {}", original_path_str);
@@ -803,9 +1899,17 @@ fn generate_mask_overlay(
     let scaled_crop_offset = (crop_offset.0 * scale, crop_offset.1 * scale);
 
     if let Some(gray_mask) = generate_mask_bitmap(&mask_def, width, height, scale, scaled_crop_offset) {
+        let is_dodge_burn = dodge_burn_sub_mask(&mask_def).is_some();
         let mut rgba_mask = RgbaImage::new(width, height);
         for (x, y, pixel) in gray_mask.enumerate_pixels() {
             let intensity = pixel[0];
+            if is_dodge_burn {
+                // Dodged areas in yellow, burned areas in blue.
+                let strength = (intensity as i32 - DODGE_BURN_NEUTRAL as i32).unsigned_abs() * 2;
+                let color = if intensity > DODGE_BURN_NEUTRAL { [255, 200, 0] } else { [0, 120, 255] };
+                rgba_mask.put_pixel(x, y, Rgba([color[0], color[1], color[2], (strength as f32 * 0.5) as u8]));
+                continue;
+            }
             let alpha = (intensity as f32 * 0.5) as u8;
             rgba_mask.put_pixel(x, y, Rgba([255, 0, 0, alpha]));
         }
@@ -820,6 +1924,26 @@ fn generate_mask_overlay(
     }
 }
 
+// Loads the AI models once and keeps them in the app state for later calls.
+async fn get_cached_ai_models(state: &AppState, app_handle: &tauri::AppHandle) -> Result<Arc<AiModels>, String> {
+    let models = state.ai_state.lock().unwrap().as_ref().map(|s| s.models.clone());
+    if let Some(models) = models {
+        return Ok(models);
+    }
+
+    let new_models = get_or_init_ai_models(app_handle).await.map_err(|e| e.to_string())?;
+    let mut ai_state_lock = state.ai_state.lock().unwrap();
+    if let Some(ai_state) = &mut *ai_state_lock {
+        Ok(ai_state.models.clone())
+    } else {
+        *ai_state_lock = Some(AiState {
+            models: new_models.clone(),
+            embeddings: None,
+        });
+        Ok(new_models)
+    }
+}
+
 #[tauri::command]
 async fn generate_ai_foreground_mask(
     rotation: f32,
@@ -828,24 +1952,7 @@ async fn generate_ai_foreground_mask(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<AiForegroundMaskParameters, String> {
-    let models = state.ai_state.lock().unwrap().as_ref().map(|s| s.models.clone());
-
-    let models = if let Some(models) = models {
-        models
-    } else {
-        drop(models);
-        let new_models = get_or_init_ai_models(&app_handle).await.map_err(|e| e.to_string())?;
-        let mut ai_state_lock = state.ai_state.lock().unwrap();
-        if let Some(ai_state) = &mut *ai_state_lock {
-            ai_state.models.clone()
-        } else {
-            *ai_state_lock = Some(AiState {
-                models: new_models.clone(),
-                embeddings: None,
-            });
-            new_models
-        }
-    };
+    let models = get_cached_ai_models(&state, &app_handle).await?;
 
     let full_image = get_full_image_for_processing(&state)?;
     let full_mask_image = run_u2netp_model(&full_image, &models.u2netp).map_err(|e| e.to_string())?;
@@ -859,6 +1966,38 @@ async fn generate_ai_foreground_mask(
     })
 }
 
+#[tauri::command]
+async fn generate_graduated_sky_mask(
+    rotation: f32,
+    flip_horizontal: bool,
+    flip_vertical: bool,
+    state: tauri::State<'_, AppState>,
+    app_handle: tauri::AppHandle,
+) -> Result<MaskDefinition, String> {
+    let full_image = get_full_image_for_processing(&state)?;
+    let horizon = graduated_sky::estimate_horizon(&full_image);
+
+    // Without the model the gradient alone is still a usable starting point.
+    let foreground = match get_cached_ai_models(&state, &app_handle).await {
+        Ok(models) => {
+            let mask_image = run_u2netp_model(&full_image, &models.u2netp).map_err(|e| e.to_string())?;
+            Some(AiForegroundMaskParameters {
+                mask_data_base64: Some(encode_to_base64_png(&mask_image)?),
+                rotation: Some(rotation),
+                flip_horizontal: Some(flip_horizontal),
+                flip_vertical: Some(flip_vertical),
+            })
+        }
+        Err(e) => {
+            eprintln!("Graduated sky without foreground mask: {}", e);
+            None
+        }
+    };
+
+    let (width, height) = full_image.dimensions();
+    Ok(graduated_sky::build_graduated_sky_mask(width, height, horizon, foreground))
+}
+
 #[tauri::command]
 async fn generate_ai_subject_mask(
     path: String,
@@ -870,24 +2009,7 @@ async fn generate_ai_subject_mask(
     state: tauri::State<'_, AppState>,
     app_handle: tauri::AppHandle,
 ) -> Result<AiSubjectMaskParameters, String> {
-    let models = state.ai_state.lock().unwrap().as_ref().map(|s| s.models.clone());
-
-    let models = if let Some(models) = models {
-        models
-    } else {
-        drop(models);
-        let new_models = get_or_init_ai_models(&app_handle).await.map_err(|e| e.to_string())?;
-        let mut ai_state_lock = state.ai_state.lock().unwrap();
-        if let Some(ai_state) = &mut *ai_state_lock {
-            ai_state.models.clone()
-        } else {
-            *ai_state_lock = Some(AiState {
-                models: new_models.clone(),
-                embeddings: None,
-            });
-            new_models
-        }
-    };
+    let models = get_cached_ai_models(&state, &app_handle).await?;
 
     let embeddings = {
         let mut ai_state_lock = state.ai_state.lock().unwrap();
@@ -1008,8 +2130,9 @@ fn generate_preset_preview(
         .collect();
 
     let all_adjustments = get_all_adjustments_from_json(&js_adjustments);
+    let lut = lut::lut_for(&js_adjustments);
     
-    let processed_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps)?;
+    let processed_image = process_and_get_dynamic_image(&context, &transformed_image, all_adjustments, &mask_bitmaps, lut.as_deref())?;
     
     encode_to_base64(&processed_image, 50)
 }
@@ -1056,6 +2179,9 @@ fn apply_window_effect(theme: String, window: impl raw_window_handle::HasWindowH
 
 #[tauri::command]
 fn update_window_effect(theme: String, window: tauri::Window) {
+    if safe_mode::is_active() {
+        return;
+    }
     apply_window_effect(theme, window);
 }
 
@@ -1086,6 +2212,9 @@ async fn invoke_generative_replace(
     app_handle: tauri::AppHandle,
     state: tauri::State<'_, AppState>,
 ) -> Result<String, String> {
+    if safe_mode::is_active() {
+        return Err("AI features are disabled in safe mode.".to_string());
+    }
     let settings = load_settings(app_handle).unwrap_or_default();
     let address = settings.comfyui_address
         .ok_or_else(|| "ComfyUI address is not configured in settings.".to_string())?;
@@ -1140,6 +2269,7 @@ fn main() {
         .plugin(tauri_plugin_fs::init())
         .plugin(tauri_plugin_dialog::init())
         .plugin(tauri_plugin_process::init())
+        .plugin(tauri_plugin_drag::init())
         .setup(|app| {
             let app_handle = app.handle().clone();
 
@@ -1157,9 +2287,26 @@ fn main() {
             std::env::set_var("ORT_DYLIB_PATH", &ort_library_path);
             println!("Set ORT_DYLIB_PATH to: {}", ort_library_path.display());
 
+            if let Ok(settings) = load_settings(app_handle.clone()) {
+                raw_processing::apply_develop_settings(&settings);
+                proofing::apply_proofing_settings(&settings);
+            }
+            dark_frames::init(&app_handle);
+            flat_fields::init(&app_handle);
+            bad_pixel_maps::init(&app_handle);
+            lens_correction::init(&app_handle);
+
+            for temp_subfolder in [DRAG_TEMP_SUBFOLDER, SHARE_TEMP_SUBFOLDER] {
+                prune_temp_exports(temp_subfolder, Duration::ZERO);
+            }
+            backup::start_backup_scheduler(app_handle.clone());
+            cache_manager::start_cache_size_enforcer(app_handle.clone());
+
             let settings: AppSettings = load_settings(app_handle.clone()).unwrap_or_default();
+            safe_mode::init(&app_handle, &settings);
+
             let window_cfg = app.config().app.windows.get(0).unwrap().clone();
-            let transparent = settings.transparent.unwrap_or(window_cfg.transparent);
+            let transparent = !safe_mode::is_active() && settings.transparent.unwrap_or(window_cfg.transparent);
             let decorations = settings.decorations.unwrap_or(window_cfg.decorations);
 
             let window = tauri::WebviewWindowBuilder::from_config(app.handle(), &window_cfg)
@@ -1179,22 +2326,41 @@ fn main() {
         .manage(AppState {
             original_image: Mutex::new(None),
             cached_preview: Mutex::new(None),
+            preview_viewport: Mutex::new(None),
             gpu_context: Mutex::new(None),
             ai_state: Mutex::new(None),
-            export_task_handle: Mutex::new(None),
+            export_queue: Mutex::new(ExportQueue::default()),
+            batch_history: Mutex::new(Vec::new()),
+            preview_generation: AtomicU64::new(0),
+            preview_renders: LatestJobQueue::default(),
+            preview_emits: LatestJobQueue::default(),
+            uncropped_renders: LatestJobQueue::default(),
+            proof_overlay: Mutex::new(None),
         })
         .invoke_handler(tauri::generate_handler![
             load_image,
+            load_embedded_preview,
+            reload_base_image,
+            set_preview_viewport,
             apply_adjustments,
             export_image,
             batch_export_images,
-            cancel_export,
+            copy_to_clipboard,
+            prepare_drag_export,
+            share_image,
+            export_queue::cancel_export,
+            export_queue::pause_export,
+            export_queue::resume_export,
+            export_queue::get_export_queue,
+            export_queue::reorder_export_job,
+            export_queue::clear_finished_export_jobs,
             generate_fullscreen_preview,
             generate_preset_preview,
             generate_uncropped_preview,
             generate_mask_overlay,
             generate_ai_subject_mask,
             generate_ai_foreground_mask,
+            generate_graduated_sky_mask,
             update_window_effect,
             check_comfyui_status,
             test_comfyui_connection,
@@ -1219,16 +2385,78 @@ fn main() {
             file_management::save_metadata_and_update_thumbnail,
             file_management::apply_adjustments_to_paths,
             file_management::load_metadata,
+            file_management::save_image_version,
+            file_management::delete_image_version,
             file_management::load_presets,
             file_management::save_presets,
             file_management::load_settings,
             file_management::save_settings,
+            file_management::load_export_presets,
+            file_management::save_export_preset,
+            file_management::delete_export_preset,
             file_management::reset_adjustments_for_paths,
             file_management::apply_auto_adjustments_to_paths,
             file_management::handle_import_presets_from_file,
             file_management::handle_export_presets_to_file,
             file_management::clear_all_sidecars,
-            file_management::clear_thumbnail_cache
+            cache_manager::get_cache_usage,
+            cache_manager::clear_cache,
+            cache_manager::purge_folder_thumbnails,
+            cache_manager::prune_thumbnail_cache,
+            cache_manager::enforce_cache_size_limit,
+            geotagging::geotag_from_gpx,
+            metadata_writeback::write_metadata_to_originals,
+            darktable::import_darktable_sidecars,
+            stacks::create_stack,
+            stacks::set_stack_cover,
+            stacks::remove_from_stack,
+            stacks::dissolve_stack,
+            stacks::get_stacks,
+            notes::set_image_notes,
+            notes::get_folder_notes,
+            notes::set_folder_notes,
+            notes::search_notes,
+            batch_operations::batch_apply_operation,
+            batch_operations::undo_batch_operation,
+            power::get_power_status,
+            contact_sheet::export_contact_sheet,
+            reference_swatches::get_reference_swatches,
+            camera_profiles::create_camera_profile,
+            camera_profiles::list_camera_profiles,
+            camera_profiles::delete_camera_profile,
+            dark_frames::register_dark_frame,
+            dark_frames::list_dark_frames,
+            dark_frames::remove_dark_frame,
+            flat_fields::register_flat_field,
+            flat_fields::list_flat_fields,
+            flat_fields::remove_flat_field,
+            bad_pixel_maps::import_bad_pixel_map,
+            bad_pixel_maps::remove_bad_pixel_map,
+            lens_correction::list_lens_profiles,
+            lens_correction::detect_lens_profile,
+            lut::load_lut,
+            performance::get_performance_report,
+            performance::reset_performance_report,
+            export_checkpoints::list_interrupted_export_jobs,
+            export_checkpoints::resume_interrupted_export_job,
+            export_checkpoints::discard_interrupted_export_job,
+            raw_processing::get_raw_white_balance,
+            raw_processing::get_raw_levels,
+            raw_histogram::generate_raw_histogram,
+            pixel_shift::find_pixel_shift_bursts,
+            pixel_shift::merge_pixel_shift_burst,
+            backup::create_backup,
+            backup::list_backups,
+            backup::restore_backup,
+            safe_mode::get_safe_mode_status,
+            safe_mode::clear_gpu_blacklist,
+            publish::create_publish_target,
+            publish::list_publish_targets,
+            publish::delete_publish_target,
+            publish::get_publish_status,
+            publish::publish_changed,
+            audit_log::get_audit_log,
+            audit_log::export_audit_log
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
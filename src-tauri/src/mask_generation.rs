@@ -99,6 +99,35 @@ struct BrushMaskParameters {
     lines: Vec<BrushLine>,
 }
 
+// A dodge & burn stroke. Points are stored flat as [x, y, x, y, ...] in image pixels, so long
+// painting sessions stay small in the sidecar.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+struct DodgeBurnStroke {
+    tool: String,
+    brush_size: f32,
+    #[serde(default = "default_brush_feather")]
+    feather: f32,
+    #[serde(default = "default_flow")]
+    flow: f32,
+    #[serde(default)]
+    points: Vec<f32>,
+}
+
+fn default_flow() -> f32 {
+    0.25
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+struct DodgeBurnParameters {
+    #[serde(default)]
+    strokes: Vec<DodgeBurnStroke>,
+}
+
+// Dodge & burn bitmaps are signed: 128 leaves a pixel alone, 255 is full dodge and 1 full burn.
+pub const DODGE_BURN_NEUTRAL: u8 = 128;
+
 fn draw_feathered_ellipse_mut(
     mask: &mut GrayImage,
     center: (i32, i32),
@@ -303,6 +332,82 @@ fn generate_brush_bitmap(
     mask
 }
 
+fn generate_dodge_burn_bitmap(
+    params_value: &Value,
+    width: u32,
+    height: u32,
+    scale: f32,
+    crop_offset: (f32, f32),
+) -> GrayImage {
+    let params: DodgeBurnParameters = serde_json::from_value(params_value.clone()).unwrap_or_default();
+    // Signed strength per pixel, -1 for full burn to 1 for full dodge.
+    let mut strength = vec![0.0f32; (width * height) as usize];
+
+    for stroke in &params.strokes {
+        let points: Vec<(f32, f32)> = stroke.points
+            .chunks_exact(2)
+            .map(|p| (p[0] * scale - crop_offset.0, p[1] * scale - crop_offset.1))
+            .collect();
+        let radius = (stroke.brush_size * scale / 2.0).max(0.0);
+        if points.is_empty() || radius <= 0.0 {
+            continue;
+        }
+        let target = match stroke.tool.as_str() {
+            "dodge" => 1.0,
+            "burn" => -1.0,
+            _ => 0.0,
+        };
+
+        // Coverage of the whole stroke first, so overlapping dabs within one stroke do not
+        // build up. Only repeated strokes add to each other, scaled by the flow.
+        let left = (points.iter().map(|p| p.0).fold(f32::MAX, f32::min) - radius).floor().max(0.0) as u32;
+        let top = (points.iter().map(|p| p.1).fold(f32::MAX, f32::min) - radius).floor().max(0.0) as u32;
+        let right = ((points.iter().map(|p| p.0).fold(f32::MIN, f32::max) + radius).ceil().max(0.0) as u32).min(width);
+        let bottom = ((points.iter().map(|p| p.1).fold(f32::MIN, f32::max) + radius).ceil().max(0.0) as u32).min(height);
+        if left >= right || top >= bottom {
+            continue;
+        }
+        let mut coverage = GrayImage::new(right - left, bottom - top);
+        let feather = stroke.feather.clamp(0.0, 1.0);
+        let step_size = (radius * (1.0 - feather) / 2.0).max(1.0);
+        let mut stamp = |x: f32, y: f32| {
+            let center = ((x - left as f32) as i32, (y - top as f32) as i32);
+            draw_feathered_ellipse_mut(&mut coverage, center, radius, feather, 255, false);
+        };
+        stamp(points[0].0, points[0].1);
+        for pair in points.windows(2) {
+            let ((x1, y1), (x2, y2)) = (pair[0], pair[1]);
+            let steps = (((x2 - x1).powi(2) + (y2 - y1).powi(2)).sqrt() / step_size).ceil().max(1.0) as i32;
+            for i in 1..=steps {
+                let t = i as f32 / steps as f32;
+                stamp(x1 + t * (x2 - x1), y1 + t * (y2 - y1));
+            }
+        }
+
+        let flow = stroke.flow.clamp(0.0, 1.0);
+        for (x, y, pixel) in coverage.enumerate_pixels() {
+            if pixel[0] == 0 {
+                continue;
+            }
+            let amount = pixel[0] as f32 / 255.0 * flow;
+            let value = &mut strength[((top + y) * width + left + x) as usize];
+            *value += (target - *value) * amount;
+        }
+    }
+
+    let data = strength
+        .iter()
+        .map(|s| (DODGE_BURN_NEUTRAL as f32 + (s * 127.0).round()).clamp(1.0, 255.0) as u8)
+        .collect();
+    GrayImage::from_raw(width, height, data).unwrap()
+}
+
+// The dodge & burn sub-mask of a mask. Such a mask paints with its own exposure and contrast
+// instead of the mask adjustments, and the other sub-masks are ignored.
+pub fn dodge_burn_sub_mask(mask_def: &MaskDefinition) -> Option<&SubMask> {
+    mask_def.sub_masks.iter().find(|s| s.visible && s.mask_type == "dodge-burn")
+}
+
 fn generate_ai_bitmap_from_full_mask(
     full_mask_image: &GrayImage,
     rotation: f32,
@@ -454,6 +559,10 @@ pub fn generate_mask_bitmap(
         return None;
     }
 
+    if let Some(sub_mask) = dodge_burn_sub_mask(mask_def) {
+        return Some(generate_dodge_burn_bitmap(&sub_mask.parameters, width, height, scale, crop_offset));
+    }
+
     let mut additive_canvas = GrayImage::new(width, height);
     let mut subtractive_canvas = GrayImage::new(width, height);
 
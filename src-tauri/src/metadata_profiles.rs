@@ -0,0 +1,234 @@
+use little_exif::exif_tag::ExifTag;
+use little_exif::metadata::Metadata;
+use little_exif::rational::{iR64, uR64};
+use serde::{Deserialize, Serialize};
+
+// Which source metadata an export carries over. Orientation and print resolution are always
+// written by the exporter itself and are not affected by the profile.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum MetadataProfile {
+    #[default]
+    Everything,
+    AllExceptLocation,
+    AllExceptSerialNumbers,
+    CameraAndExposure,
+    CopyrightOnly,
+    None,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum TagGroup {
+    Copyright,
+    Camera,
+    Exposure,
+    Dates,
+    Location,
+    SerialNumbers,
+    // Everything not listed in one of the groups above, including maker notes.
+    Other,
+}
+
+const ALL_GROUPS: &[TagGroup] = &[
+    TagGroup::Copyright,
+    TagGroup::Camera,
+    TagGroup::Exposure,
+    TagGroup::Dates,
+    TagGroup::Location,
+    TagGroup::SerialNumbers,
+    TagGroup::Other,
+];
+
+impl MetadataProfile {
+    fn allowed_groups(self) -> &'static [TagGroup] {
+        match self {
+            MetadataProfile::Everything => ALL_GROUPS,
+            MetadataProfile::AllExceptLocation => &[
+                TagGroup::Copyright,
+                TagGroup::Camera,
+                TagGroup::Exposure,
+                TagGroup::Dates,
+                TagGroup::SerialNumbers,
+                TagGroup::Other,
+            ],
+            MetadataProfile::AllExceptSerialNumbers => &[
+                TagGroup::Copyright,
+                TagGroup::Camera,
+                TagGroup::Exposure,
+                TagGroup::Dates,
+                TagGroup::Location,
+                TagGroup::Other,
+            ],
+            MetadataProfile::CameraAndExposure => {
+                &[TagGroup::Copyright, TagGroup::Camera, TagGroup::Exposure, TagGroup::Dates]
+            }
+            MetadataProfile::CopyrightOnly => &[TagGroup::Copyright],
+            MetadataProfile::None => &[],
+        }
+    }
+
+    fn allows(self, group: TagGroup) -> bool {
+        self.allowed_groups().contains(&group)
+    }
+
+    pub fn copies_metadata(self) -> bool {
+        self != MetadataProfile::None
+    }
+
+    // Rating, label and keywords have no tag group of their own and go with the rest.
+    pub fn copies_library_tags(self) -> bool {
+        self.allows(TagGroup::Other)
+    }
+}
+
+fn group_tags(group: TagGroup) -> Vec<ExifTag> {
+    let r1 = || vec![uR64 { nominator: 0, denominator: 1 }];
+    let r3 = || vec![uR64 { nominator: 0, denominator: 1 }; 3];
+    match group {
+        TagGroup::Copyright => vec![ExifTag::Artist(String::new()), ExifTag::Copyright(String::new())],
+        TagGroup::Camera => vec![
+            ExifTag::Make(String::new()),
+            ExifTag::Model(String::new()),
+            ExifTag::LensMake(String::new()),
+            ExifTag::LensModel(String::new()),
+            ExifTag::LensInfo(vec![uR64 { nominator: 0, denominator: 1 }; 4]),
+        ],
+        TagGroup::Exposure => vec![
+            ExifTag::ExposureTime(r1()),
+            ExifTag::FNumber(r1()),
+            ExifTag::ExposureProgram(vec![0u16]),
+            ExifTag::ISO(vec![0u16]),
+            ExifTag::ExposureCompensation(vec![iR64 { nominator: 0, denominator: 1 }]),
+            ExifTag::MaxApertureValue(r1()),
+            ExifTag::MeteringMode(vec![0u16]),
+            ExifTag::LightSource(vec![0u16]),
+            ExifTag::Flash(vec![0u16]),
+            ExifTag::FocalLength(r1()),
+            ExifTag::ExposureMode(vec![0u16]),
+            ExifTag::WhiteBalance(vec![0u16]),
+        ],
+        TagGroup::Dates => vec![
+            ExifTag::DateTimeOriginal(String::new()),
+            ExifTag::CreateDate(String::new()),
+            ExifTag::ModifyDate(String::new()),
+            ExifTag::OffsetTime(String::new()),
+            ExifTag::OffsetTimeOriginal(String::new()),
+            ExifTag::OffsetTimeDigitized(String::new()),
+        ],
+        TagGroup::Location => vec![
+            ExifTag::GPSVersionID([0, 0, 0, 0].to_vec()),
+            ExifTag::GPSLatitudeRef(String::new()),
+            ExifTag::GPSLatitude(r3()),
+            ExifTag::GPSLongitudeRef(String::new()),
+            ExifTag::GPSLongitude(r3()),
+            ExifTag::GPSAltitudeRef(vec![0]),
+            ExifTag::GPSAltitude(r1()),
+            ExifTag::GPSTimeStamp(r3()),
+            ExifTag::GPSSatellites(String::new()),
+            ExifTag::GPSStatus(String::new()),
+            ExifTag::GPSMeasureMode(String::new()),
+            ExifTag::GPSDOP(r1()),
+            ExifTag::GPSSpeedRef(String::new()),
+            ExifTag::GPSSpeed(r1()),
+            ExifTag::GPSTrackRef(String::new()),
+            ExifTag::GPSTrack(r1()),
+            ExifTag::GPSImgDirectionRef(String::new()),
+            ExifTag::GPSImgDirection(r1()),
+            ExifTag::GPSMapDatum(String::new()),
+            ExifTag::GPSDestLatitudeRef(String::new()),
+            ExifTag::GPSDestLatitude(r3()),
+            ExifTag::GPSDestLongitudeRef(String::new()),
+            ExifTag::GPSDestLongitude(r3()),
+            ExifTag::GPSDestBearingRef(String::new()),
+            ExifTag::GPSDestBearing(r1()),
+            ExifTag::GPSDestDistanceRef(String::new()),
+            ExifTag::GPSDestDistance(r1()),
+            ExifTag::GPSProcessingMethod(vec![]),
+            ExifTag::GPSAreaInformation(vec![]),
+            ExifTag::GPSDateStamp(String::new()),
+            ExifTag::GPSDifferential(vec![0u16]),
+            ExifTag::GPSHPositioningError(r1()),
+        ],
+        TagGroup::SerialNumbers => vec![
+            ExifTag::SerialNumber(String::new()),
+            ExifTag::LensSerialNumber(String::new()),
+            ExifTag::OwnerName(String::new()),
+            ExifTag::ImageUniqueID(String::new()),
+        ],
+        TagGroup::Other => Vec::new(),
+    }
+}
+
+// Profiles that keep unlisted tags start from the source and drop the excluded groups; the
+// narrow profiles start empty and copy only their allowlisted tags, so nothing unexpected
+// (maker notes, vendor tags) slips through.
+pub fn filter_metadata(source: Metadata, profile: MetadataProfile) -> Metadata {
+    if profile.allows(TagGroup::Other) {
+        let mut metadata = source;
+        for group in ALL_GROUPS.iter().filter(|g| !profile.allows(**g)) {
+            for tag in group_tags(*group) {
+                metadata.remove_tag(tag);
+            }
+        }
+        return metadata;
+    }
+
+    let mut metadata = Metadata::new();
+    for group in profile.allowed_groups() {
+        for tag in group_tags(*group) {
+            if let Some(found) = source.get_tag(&tag).next() {
+                metadata.set_tag(found.clone());
+            }
+        }
+    }
+    metadata
+}
+
+// Same allowlists for the EXIF that is rebuilt into TIFF exports.
+pub fn filter_rawler_exif(source: &rawler::exif::Exif, profile: MetadataProfile) -> rawler::exif::Exif {
+    let mut exif = if profile.allows(TagGroup::Other) {
+        source.clone()
+    } else {
+        rawler::exif::Exif { orientation: source.orientation, ..Default::default() }
+    };
+
+    macro_rules! take {
+        ($group:expr, $($field:ident),+) => {
+            let allowed = profile.allows($group);
+            $(exif.$field = if allowed { source.$field.clone() } else { None };)+
+        };
+    }
+
+    take!(TagGroup::Copyright, artist, copyright);
+    take!(TagGroup::Camera, lens_make, lens_model, lens_spec);
+    take!(
+        TagGroup::Exposure,
+        exposure_time,
+        fnumber,
+        aperture_value,
+        iso_speed_ratings,
+        iso_speed,
+        exposure_bias,
+        exposure_program,
+        shutter_speed_value,
+        max_aperture_value,
+        metering_mode,
+        light_source,
+        flash,
+        focal_length,
+        exposure_mode,
+        white_balance
+    );
+    take!(
+        TagGroup::Dates,
+        date_time_original,
+        create_date,
+        modify_date,
+        offset_time,
+        offset_time_original,
+        offset_time_digitized
+    );
+    take!(TagGroup::Location, gps);
+    take!(TagGroup::SerialNumbers, serial_number, lens_serial_number, owner_name);
+    exif
+}
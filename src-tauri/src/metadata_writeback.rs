@@ -0,0 +1,527 @@
+use std::collections::BTreeMap;
+use std::fs;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+use anyhow::{anyhow, bail, Result};
+use tauri::AppHandle;
+use uuid::Uuid;
+
+use crate::file_management::{get_sidecar_path, load_settings};
+use crate::image_processing::ImageMetadata;
+
+// Only formats whose container layout we fully understand are touched. Proprietary RAW
+// formats are never rewritten, even if they happen to be TIFF based.
+const WRITE_BACK_EXTENSIONS: &[&str] = &["jpg", "jpeg", "dng"];
+
+const XMP_APP1_HEADER: &[u8] = b"http://ns.adobe.com/xap/1.0/\0";
+const TIFF_TAG_XMP: u16 = 700;
+
+// One lock per original so concurrent saves of the same image never interleave their
+// read-modify-write of the file.
+static PATH_LOCKS: Mutex<BTreeMap<PathBuf, Arc<Mutex<()>>>> = Mutex::new(BTreeMap::new());
+
+const WRITE_BACK_DESCRIPTION_OPEN: &str = "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:rrw=\"urn:rapidraw:writeback:1.0\">";
+
+pub fn is_write_back_supported(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .and_then(|e| e.to_str())
+        .map(|e| WRITE_BACK_EXTENSIONS.contains(&e.to_lowercase().as_str()))
+        .unwrap_or(false)
+}
+
+fn escape_xml(value: &str) -> String {
+    value
+        .replace('&', "&amp;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+        .replace('"', "&quot;")
+}
+
+fn build_write_back_description(metadata: &ImageMetadata) -> String {
+    let mut description = String::from(WRITE_BACK_DESCRIPTION_OPEN);
+    description.push_str(&format!("<xmp:Rating>{}</xmp:Rating>", metadata.rating));
+    if let Some(label) = &metadata.label {
+        description.push_str(&format!("<xmp:Label>{}</xmp:Label>", escape_xml(label)));
+    }
+    if !metadata.keywords.is_empty() {
+        description.push_str("<dc:subject><rdf:Bag>");
+        for keyword in &metadata.keywords {
+            description.push_str(&format!("<rdf:li>{}</rdf:li>", escape_xml(keyword)));
+        }
+        description.push_str("</rdf:Bag></dc:subject>");
+    }
+    if let Some(notes) = metadata.notes.as_deref().filter(|n| !n.trim().is_empty()) {
+        description.push_str(&format!(
+            "<dc:description><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:description>",
+            escape_xml(notes)
+        ));
+    }
+    description.push_str("</rdf:Description>");
+    description
+}
+
+fn wrap_xmp_packet(description: &str) -> String {
+    format!(
+        "<?xpacket begin=\"\u{feff}\" id=\"W5M0MpCehiHzreSzNTczkc9d\"?><x:xmpmeta xmlns:x=\"adobe:ns:meta/\"><rdf:RDF xmlns:rdf=\"http://www.w3.org/1999/02/22-rdf-syntax-ns#\">{}</rdf:RDF></x:xmpmeta><?xpacket end=\"w\"?>",
+        description
+    )
+}
+
+// AI patches and masks carry their bitmaps as base64 PNGs in keys like `patchDataBase64` and
+// `maskDataBase64`. They are left out of the sidecar, which would otherwise grow to megabytes.
+fn strip_bitmap_payloads(value: &mut serde_json::Value) {
+    match value {
+        serde_json::Value::Object(map) => {
+            map.retain(|key, _| !key.ends_with("Base64"));
+            map.values_mut().for_each(strip_bitmap_payloads);
+        }
+        serde_json::Value::Array(items) => items.iter_mut().for_each(strip_bitmap_payloads),
+        _ => {}
+    }
+}
+
+// Standalone sidecar written next to an export. Notes stay private; the adjustments are
+// stored as JSON so the edit can be traced back or re-applied from the exported file alone.
+// Rating, label and keywords are only written when `metadata` is given, which the caller
+// decides from the export's metadata profile.
+pub fn build_export_sidecar(
+    metadata: Option<&ImageMetadata>,
+    adjustments: &serde_json::Value,
+    copyright: Option<&str>,
+    creator: Option<&str>,
+) -> String {
+    let mut description = String::from(
+        "<rdf:Description rdf:about=\"\" xmlns:xmp=\"http://ns.adobe.com/xap/1.0/\" xmlns:dc=\"http://purl.org/dc/elements/1.1/\" xmlns:rrw=\"urn:rapidraw:writeback:1.0\">",
+    );
+    description.push_str("<xmp:CreatorTool>RapidRAW</xmp:CreatorTool>");
+    if let Some(metadata) = metadata {
+        description.push_str(&format!("<xmp:Rating>{}</xmp:Rating>", metadata.rating));
+        if let Some(label) = &metadata.label {
+            description.push_str(&format!("<xmp:Label>{}</xmp:Label>", escape_xml(label)));
+        }
+        if !metadata.keywords.is_empty() {
+            description.push_str("<dc:subject><rdf:Bag>");
+            for keyword in &metadata.keywords {
+                description.push_str(&format!("<rdf:li>{}</rdf:li>", escape_xml(keyword)));
+            }
+            description.push_str("</rdf:Bag></dc:subject>");
+        }
+    }
+    if let Some(creator) = creator.map(str::trim).filter(|c| !c.is_empty()) {
+        description.push_str(&format!(
+            "<dc:creator><rdf:Seq><rdf:li>{}</rdf:li></rdf:Seq></dc:creator>",
+            escape_xml(creator)
+        ));
+    }
+    if let Some(copyright) = copyright.map(str::trim).filter(|c| !c.is_empty()) {
+        description.push_str(&format!(
+            "<dc:rights><rdf:Alt><rdf:li xml:lang=\"x-default\">{}</rdf:li></rdf:Alt></dc:rights>",
+            escape_xml(copyright)
+        ));
+    }
+    if !adjustments.is_null() {
+        let mut adjustments = adjustments.clone();
+        strip_bitmap_payloads(&mut adjustments);
+        description.push_str(&format!("<rrw:Adjustments>{}</rrw:Adjustments>", escape_xml(&adjustments.to_string())));
+    }
+    description.push_str("</rdf:Description>");
+    wrap_xmp_packet(&description)
+}
+
+fn remove_element(xmp: &mut String, name: &str) {
+    let open = format!("<{}", name);
+    let close = format!("</{}>", name);
+    let mut search_from = 0;
+    while let Some(found) = xmp[search_from..].find(&open) {
+        let start = search_from + found;
+        let after_open = start + open.len();
+        if !xmp[after_open..].starts_with(['>', ' ', '/']) {
+            search_from = after_open;
+            continue;
+        }
+        let end = match (xmp[start..].find("/>"), xmp[start..].find('>'), xmp[start..].find(&close)) {
+            (Some(self_close), Some(first_gt), _) if self_close + 1 == first_gt => start + self_close + 2,
+            (_, _, Some(close_pos)) => start + close_pos + close.len(),
+            _ => break,
+        };
+        xmp.replace_range(start..end, "");
+        search_from = start;
+    }
+}
+
+fn remove_attribute(xmp: &mut String, name: &str) {
+    let pattern = format!(" {}=\"", name);
+    while let Some(start) = xmp.find(&pattern) {
+        let value_start = start + pattern.len();
+        let Some(value_len) = xmp[value_start..].find('"') else { break };
+        xmp.replace_range(start..value_start + value_len + 1, "");
+    }
+}
+
+fn merge_into_xmp_packet(existing: Option<&str>, metadata: &ImageMetadata) -> Result<String> {
+    let description = build_write_back_description(metadata);
+
+    let Some(existing) = existing else {
+        return Ok(wrap_xmp_packet(&description));
+    };
+
+    let mut xmp = existing.to_string();
+
+    while let Some(start) = xmp.find(WRITE_BACK_DESCRIPTION_OPEN) {
+        let end = xmp[start..]
+            .find("</rdf:Description>")
+            .ok_or_else(|| anyhow!("Malformed XMP packet"))?;
+        xmp.replace_range(start..start + end + "</rdf:Description>".len(), "");
+    }
+
+    remove_attribute(&mut xmp, "xmp:Rating");
+    remove_attribute(&mut xmp, "xmp:Label");
+    remove_element(&mut xmp, "xmp:Rating");
+    remove_element(&mut xmp, "xmp:Label");
+    remove_element(&mut xmp, "dc:subject");
+    // An existing caption is only replaced when there are notes to put in its place.
+    if metadata.notes.as_deref().map_or(false, |n| !n.trim().is_empty()) {
+        remove_element(&mut xmp, "dc:description");
+    }
+
+    let insert_at = xmp
+        .find("</rdf:RDF>")
+        .ok_or_else(|| anyhow!("Existing XMP packet has no rdf:RDF element"))?;
+    xmp.insert_str(insert_at, &description);
+    Ok(xmp)
+}
+
+fn write_jpeg_xmp(bytes: &[u8], metadata: &ImageMetadata) -> Result<Vec<u8>> {
+    if bytes.len() < 4 || bytes[0] != 0xFF || bytes[1] != 0xD8 {
+        bail!("Not a valid JPEG file");
+    }
+
+    let mut segments: Vec<&[u8]> = Vec::new();
+    let mut existing_xmp: Option<String> = None;
+    let mut insert_index = 0;
+    let mut pos = 2;
+
+    loop {
+        if pos + 4 > bytes.len() || bytes[pos] != 0xFF {
+            bail!("Unexpected JPEG structure");
+        }
+        let marker = bytes[pos + 1];
+        if marker == 0xDA {
+            break;
+        }
+        let length = u16::from_be_bytes([bytes[pos + 2], bytes[pos + 3]]) as usize;
+        let end = pos + 2 + length;
+        if end > bytes.len() {
+            bail!("Truncated JPEG segment");
+        }
+        let segment = &bytes[pos..end];
+        let payload = &segment[4..];
+
+        if marker == 0xE1 && payload.starts_with(XMP_APP1_HEADER) {
+            existing_xmp = Some(String::from_utf8_lossy(&payload[XMP_APP1_HEADER.len()..]).into_owned());
+        } else {
+            segments.push(segment);
+            if marker == 0xE0 || marker == 0xE1 {
+                insert_index = segments.len();
+            }
+        }
+        pos = end;
+    }
+
+    let packet = merge_into_xmp_packet(existing_xmp.as_deref(), metadata)?;
+    let segment_length = 2 + XMP_APP1_HEADER.len() + packet.len();
+    if segment_length > u16::MAX as usize {
+        bail!("XMP packet is too large for a single JPEG segment");
+    }
+
+    let mut xmp_segment = vec![0xFF, 0xE1];
+    xmp_segment.extend_from_slice(&(segment_length as u16).to_be_bytes());
+    xmp_segment.extend_from_slice(XMP_APP1_HEADER);
+    xmp_segment.extend_from_slice(packet.as_bytes());
+
+    let mut output = Vec::with_capacity(bytes.len() + xmp_segment.len());
+    output.extend_from_slice(&bytes[..2]);
+    for (i, segment) in segments.iter().enumerate() {
+        if i == insert_index {
+            output.extend_from_slice(&xmp_segment);
+        }
+        output.extend_from_slice(segment);
+    }
+    if insert_index == segments.len() {
+        output.extend_from_slice(&xmp_segment);
+    }
+    output.extend_from_slice(&bytes[pos..]);
+    Ok(output)
+}
+
+struct TiffEndian {
+    little: bool,
+}
+
+impl TiffEndian {
+    fn u16(&self, b: &[u8], at: usize) -> u16 {
+        let v = [b[at], b[at + 1]];
+        if self.little { u16::from_le_bytes(v) } else { u16::from_be_bytes(v) }
+    }
+
+    fn u32(&self, b: &[u8], at: usize) -> u32 {
+        let v = [b[at], b[at + 1], b[at + 2], b[at + 3]];
+        if self.little { u32::from_le_bytes(v) } else { u32::from_be_bytes(v) }
+    }
+
+    fn put_u16(&self, out: &mut Vec<u8>, v: u16) {
+        out.extend_from_slice(&if self.little { v.to_le_bytes() } else { v.to_be_bytes() });
+    }
+
+    fn put_u32(&self, out: &mut Vec<u8>, v: u32) {
+        out.extend_from_slice(&if self.little { v.to_le_bytes() } else { v.to_be_bytes() });
+    }
+}
+
+// Rather than rewriting the whole file, IFD0 is copied to the end of the file with an updated
+// XMP entry and the header is pointed at the copy. All other data stays at its original offset.
+// The first write leaves the original IFD0 and packet behind; later writes replace their own.
+fn write_tiff_xmp(bytes: &[u8], metadata: &ImageMetadata) -> Result<Vec<u8>> {
+    if bytes.len() < 8 {
+        bail!("Not a valid TIFF file");
+    }
+    let endian = match &bytes[0..4] {
+        b"II*\0" => TiffEndian { little: true },
+        b"MM\0*" => TiffEndian { little: false },
+        _ => bail!("Not a classic TIFF/DNG file"),
+    };
+
+    let ifd0_offset = endian.u32(bytes, 4) as usize;
+    if ifd0_offset + 2 > bytes.len() {
+        bail!("Invalid IFD0 offset");
+    }
+    let entry_count = endian.u16(bytes, ifd0_offset) as usize;
+    let entries_start = ifd0_offset + 2;
+    let next_ifd_pos = entries_start + entry_count * 12;
+    if next_ifd_pos + 4 > bytes.len() {
+        bail!("Truncated IFD0");
+    }
+
+    let mut entries: Vec<[u8; 12]> = Vec::with_capacity(entry_count + 1);
+    let mut existing_xmp: Option<String> = None;
+    let mut xmp_data_start = None;
+    for i in 0..entry_count {
+        let at = entries_start + i * 12;
+        let tag = endian.u16(bytes, at);
+        if tag == TIFF_TAG_XMP {
+            let count = endian.u32(bytes, at + 4) as usize;
+            let data_start = if count <= 4 { at + 8 } else { endian.u32(bytes, at + 8) as usize };
+            if let Some(data) = bytes.get(data_start..data_start + count) {
+                existing_xmp = Some(String::from_utf8_lossy(data).into_owned());
+                xmp_data_start = Some((data_start, count));
+            }
+            continue;
+        }
+        let mut entry = [0u8; 12];
+        entry.copy_from_slice(&bytes[at..at + 12]);
+        entries.push(entry);
+    }
+    let next_ifd = endian.u32(bytes, next_ifd_pos);
+
+    let packet = merge_into_xmp_packet(existing_xmp.as_deref(), metadata)?;
+
+    // A previous write-back left its packet and IFD0 copy as the last thing in the file. That
+    // block is replaced rather than appended to again, so repeated writes do not grow the file.
+    // Nothing else can point into it: the packet is followed directly by IFD0, which ends the file.
+    let ifd0_at_tail = next_ifd_pos + 4 == bytes.len();
+    let keep_len = match xmp_data_start {
+        Some((start, count)) if ifd0_at_tail && (start + count + 1) & !1 == ifd0_offset => start,
+        _ => bytes.len(),
+    };
+
+    let mut output = bytes[..keep_len].to_vec();
+    if output.len() % 2 != 0 {
+        output.push(0);
+    }
+    let xmp_offset = output.len();
+    output.extend_from_slice(packet.as_bytes());
+    if output.len() % 2 != 0 {
+        output.push(0);
+    }
+    if output.len() > u32::MAX as usize {
+        bail!("File is too large to update in place");
+    }
+
+    let mut xmp_entry = Vec::with_capacity(12);
+    endian.put_u16(&mut xmp_entry, TIFF_TAG_XMP);
+    endian.put_u16(&mut xmp_entry, 1);
+    endian.put_u32(&mut xmp_entry, packet.len() as u32);
+    endian.put_u32(&mut xmp_entry, xmp_offset as u32);
+    let mut xmp_entry_arr = [0u8; 12];
+    xmp_entry_arr.copy_from_slice(&xmp_entry);
+    entries.push(xmp_entry_arr);
+    entries.sort_by_key(|e| endian.u16(e, 0));
+
+    let new_ifd_offset = output.len();
+    endian.put_u16(&mut output, entries.len() as u16);
+    for entry in &entries {
+        output.extend_from_slice(entry);
+    }
+    endian.put_u32(&mut output, next_ifd);
+
+    let mut header_offset = Vec::with_capacity(4);
+    endian.put_u32(&mut header_offset, new_ifd_offset as u32);
+    output[4..8].copy_from_slice(&header_offset);
+
+    Ok(output)
+}
+
+fn atomic_write(path: &Path, bytes: &[u8]) -> Result<()> {
+    let file_name = path
+        .file_name()
+        .ok_or_else(|| anyhow!("Invalid path"))?
+        .to_string_lossy();
+    let temp_path = path.with_file_name(format!(
+        ".{}.{}-{}.rrtmp",
+        file_name,
+        std::process::id(),
+        Uuid::new_v4().simple()
+    ));
+
+    let result = (|| -> Result<()> {
+        let mut file = fs::File::create(&temp_path)?;
+        file.write_all(bytes)?;
+        file.sync_all()?;
+        drop(file);
+        fs::rename(&temp_path, path)?;
+        Ok(())
+    })();
+
+    if result.is_err() {
+        let _ = fs::remove_file(&temp_path);
+    }
+    result
+}
+
+fn lock_for_path(path: &Path) -> Arc<Mutex<()>> {
+    let mut locks = PATH_LOCKS.lock().unwrap();
+    locks.retain(|_, lock| Arc::strong_count(lock) > 1);
+    locks.entry(path.to_path_buf()).or_default().clone()
+}
+
+fn write_metadata_to_original(path: &str, metadata: &ImageMetadata) -> Result<()> {
+    if !is_write_back_supported(path) {
+        bail!("Writing metadata into this file format is not supported");
+    }
+
+    let original_path = Path::new(path);
+    let bytes = fs::read(original_path)?;
+    let extension = original_path
+        .extension()
+        .and_then(|e| e.to_str())
+        .unwrap_or("")
+        .to_lowercase();
+
+    let updated = match extension.as_str() {
+        "jpg" | "jpeg" => write_jpeg_xmp(&bytes, metadata)?,
+        "dng" => write_tiff_xmp(&bytes, metadata)?,
+        _ => unreachable!(),
+    };
+
+    atomic_write(original_path, &updated)
+}
+
+// The sidecar is read while holding the lock, so whichever save runs last writes the newest
+// state, regardless of the order the callers were scheduled in.
+fn write_sidecar_to_original(path: &str, include_notes: bool) -> Result<()> {
+    let lock = lock_for_path(Path::new(path));
+    let _guard = lock.lock().unwrap();
+
+    let mut metadata: ImageMetadata = fs::read_to_string(get_sidecar_path(path))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    if !include_notes {
+        metadata.notes = None;
+    }
+    write_metadata_to_original(path, &metadata)
+}
+
+pub fn write_back_fields_changed(previous: &ImageMetadata, current: &ImageMetadata) -> bool {
+    previous.rating != current.rating
+        || previous.label != current.label
+        || previous.keywords != current.keywords
+}
+
+// Called after sidecar saves with the paths whose rating, label or keywords changed.
+// Adjustment-only saves never reach this, so the original is not rewritten on every edit.
+pub fn write_back_to_originals(app_handle: &AppHandle, paths: &[String]) {
+    if paths.is_empty() {
+        return;
+    }
+    let settings = load_settings(app_handle.clone()).unwrap_or_default();
+    if !settings.write_back_metadata.unwrap_or(false) {
+        return;
+    }
+    let include_notes = settings.notes_to_xmp_description.unwrap_or(false);
+
+    for path in paths.iter().filter(|p| is_write_back_supported(p)) {
+        if let Err(e) = write_sidecar_to_original(path, include_notes) {
+            eprintln!("Failed to write metadata back to {}: {}", path, e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn write_metadata_to_originals(paths: Vec<String>, include_notes: Option<bool>) -> Result<usize, String> {
+    let mut written = 0;
+    let mut errors = Vec::new();
+
+    for path in paths.iter().filter(|p| is_write_back_supported(p)) {
+        match write_sidecar_to_original(path, include_notes.unwrap_or(false)) {
+            Ok(()) => written += 1,
+            Err(e) => errors.push(format!("{}: {}", path, e)),
+        }
+    }
+
+    if !errors.is_empty() {
+        return Err(format!("Failed to write metadata for some files:\n{}", errors.join("\n")));
+    }
+    Ok(written)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Little-endian TIFF with an IFD0 holding only ImageWidth.
+    fn minimal_tiff() -> Vec<u8> {
+        let mut bytes = b"II*\0".to_vec();
+        bytes.extend_from_slice(&8u32.to_le_bytes());
+        bytes.extend_from_slice(&1u16.to_le_bytes());
+        bytes.extend_from_slice(&256u16.to_le_bytes());
+        bytes.extend_from_slice(&3u16.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&1u32.to_le_bytes());
+        bytes.extend_from_slice(&0u32.to_le_bytes());
+        bytes
+    }
+
+    #[test]
+    fn repeated_tiff_write_back_keeps_file_size() {
+        let metadata = ImageMetadata { rating: 3, ..Default::default() };
+        let first = write_tiff_xmp(&minimal_tiff(), &metadata).unwrap();
+        let second = write_tiff_xmp(&first, &metadata).unwrap();
+        let third = write_tiff_xmp(&second, &metadata).unwrap();
+        assert_eq!(first.len(), second.len());
+        assert_eq!(second.len(), third.len());
+        assert!(String::from_utf8_lossy(&third).contains("<xmp:Rating>3</xmp:Rating>"));
+    }
+
+    #[test]
+    fn tiff_write_back_replaces_previous_packet() {
+        let first = write_tiff_xmp(&minimal_tiff(), &ImageMetadata { rating: 2, ..Default::default() }).unwrap();
+        let second = write_tiff_xmp(&first, &ImageMetadata { rating: 5, ..Default::default() }).unwrap();
+        let text = String::from_utf8_lossy(&second);
+        assert!(text.contains("<xmp:Rating>5</xmp:Rating>"));
+        assert!(!text.contains("<xmp:Rating>2</xmp:Rating>"));
+    }
+}
@@ -0,0 +1,121 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use walkdir::WalkDir;
+
+use crate::file_management::get_sidecar_path;
+use crate::image_processing::ImageMetadata;
+
+const FOLDER_NOTES_FILENAME: &str = ".rapidraw_notes.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+struct FolderNotes {
+    notes: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct NoteSearchResult {
+    pub path: String,
+    pub is_folder: bool,
+    pub notes: String,
+}
+
+fn get_folder_notes_path(folder: &Path) -> PathBuf {
+    folder.join(FOLDER_NOTES_FILENAME)
+}
+
+fn read_folder_notes(folder: &Path) -> Option<String> {
+    fs::read_to_string(get_folder_notes_path(folder))
+        .ok()
+        .and_then(|content| serde_json::from_str::<FolderNotes>(&content).ok())
+        .map(|f| f.notes)
+        .filter(|n| !n.trim().is_empty())
+}
+
+fn normalize_notes(notes: Option<String>) -> Option<String> {
+    notes.filter(|n| !n.trim().is_empty())
+}
+
+#[tauri::command]
+pub fn set_image_notes(path: String, notes: Option<String>) -> Result<(), String> {
+    let sidecar_path = get_sidecar_path(&path);
+    let mut metadata: ImageMetadata = fs::read_to_string(&sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    metadata.notes = normalize_notes(notes);
+
+    let json_string = serde_json::to_string_pretty(&metadata).map_err(|e| e.to_string())?;
+    fs::write(sidecar_path, json_string).map_err(|e| e.to_string())
+}
+
+#[tauri::command]
+pub fn get_folder_notes(folder_path: String) -> Result<Option<String>, String> {
+    Ok(read_folder_notes(Path::new(&folder_path)))
+}
+
+#[tauri::command]
+pub fn set_folder_notes(folder_path: String, notes: Option<String>) -> Result<(), String> {
+    let path = get_folder_notes_path(Path::new(&folder_path));
+    match normalize_notes(notes) {
+        Some(notes) => {
+            let json_string =
+                serde_json::to_string_pretty(&FolderNotes { notes }).map_err(|e| e.to_string())?;
+            fs::write(path, json_string).map_err(|e| e.to_string())
+        }
+        None if path.exists() => fs::remove_file(path).map_err(|e| e.to_string()),
+        None => Ok(()),
+    }
+}
+
+#[tauri::command]
+pub fn search_notes(root_path: String, query: String, recursive: Option<bool>) -> Result<Vec<NoteSearchResult>, String> {
+    let query = query.trim().to_lowercase();
+    if query.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let max_depth = if recursive.unwrap_or(true) { usize::MAX } else { 1 };
+    let mut results = Vec::new();
+
+    for entry in WalkDir::new(&root_path).max_depth(max_depth).into_iter().filter_map(|e| e.ok()) {
+        let path = entry.path();
+
+        if entry.file_type().is_dir() {
+            if let Some(notes) = read_folder_notes(path) {
+                if notes.to_lowercase().contains(&query) {
+                    results.push(NoteSearchResult {
+                        path: path.to_string_lossy().into_owned(),
+                        is_folder: true,
+                        notes,
+                    });
+                }
+            }
+            continue;
+        }
+
+        if path.extension().map_or(true, |ext| ext != "rrdata") {
+            continue;
+        }
+
+        let notes = fs::read_to_string(path)
+            .ok()
+            .and_then(|content| serde_json::from_str::<ImageMetadata>(&content).ok())
+            .and_then(|metadata| metadata.notes);
+        let Some(notes) = notes else { continue };
+
+        if notes.to_lowercase().contains(&query) {
+            // Sidecars are named `<image file>.rrdata`, so dropping the extension yields the image.
+            results.push(NoteSearchResult {
+                path: path.with_extension("").to_string_lossy().into_owned(),
+                is_folder: false,
+                notes,
+            });
+        }
+    }
+
+    Ok(results)
+}
@@ -0,0 +1,204 @@
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::Serialize;
+
+// Images whose timings are kept, oldest are dropped first.
+const MAX_TRACKED_IMAGES: usize = 50;
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Stage {
+    // Reading the file and decoding the raw data or the image format
+    Decode,
+    // Raw development and lens corrections on the CPU
+    Develop,
+    // Flip, rotation and crop of the preview base
+    Transform,
+    // GPU adjustments including upload and readback
+    Render,
+    // JPEG/PNG encoding of previews
+    Encode,
+}
+
+#[derive(Serialize, Debug, Clone, Copy, PartialEq, Eq, Hash)]
+#[serde(rename_all = "camelCase")]
+pub enum Cache {
+    PreviewBase,
+    Thumbnail,
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct StageStats {
+    pub count: u32,
+    pub total_ms: f64,
+    pub max_ms: f64,
+    pub last_ms: f64,
+}
+
+impl StageStats {
+    fn add(&mut self, ms: f64) {
+        self.count += 1;
+        self.total_ms += ms;
+        self.max_ms = self.max_ms.max(ms);
+        self.last_ms = ms;
+    }
+}
+
+#[derive(Serialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct CacheStats {
+    pub hits: u32,
+    pub misses: u32,
+    pub hit_rate: f64,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageTimings {
+    pub path: String,
+    pub stages: HashMap<Stage, StageStats>,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PerformanceReport {
+    pub session_seconds: f64,
+    pub stages: HashMap<Stage, StageStats>,
+    pub caches: HashMap<Cache, CacheStats>,
+    // Most recent first
+    pub images: Vec<ImageTimings>,
+    // The stage that took the most time this session
+    pub bottleneck: Option<Stage>,
+}
+
+struct Telemetry {
+    started: Instant,
+    stages: HashMap<Stage, StageStats>,
+    caches: HashMap<Cache, (u32, u32)>,
+    images: VecDeque<ImageTimings>,
+    active_image: Option<String>,
+}
+
+static TELEMETRY: Mutex<Option<Telemetry>> = Mutex::new(None);
+
+thread_local! {
+    static CURRENT_IMAGE: RefCell<Option<String>> = const { RefCell::new(None) };
+}
+
+fn with_telemetry<T>(f: impl FnOnce(&mut Telemetry) -> T) -> T {
+    let mut telemetry = TELEMETRY.lock().unwrap();
+    let telemetry = telemetry.get_or_insert_with(|| Telemetry {
+        started: Instant::now(),
+        stages: HashMap::new(),
+        caches: HashMap::new(),
+        images: VecDeque::new(),
+        active_image: None,
+    });
+    f(telemetry)
+}
+
+// Timings recorded on this thread until the scope is dropped belong to `path`.
+pub struct ImageScope {
+    previous: Option<String>,
+}
+
+impl Drop for ImageScope {
+    fn drop(&mut self) {
+        let previous = self.previous.take();
+        CURRENT_IMAGE.with(|current| *current.borrow_mut() = previous);
+    }
+}
+
+pub fn image_scope(path: &str) -> ImageScope {
+    let previous = CURRENT_IMAGE.with(|current| current.borrow_mut().replace(path.to_string()));
+    ImageScope { previous }
+}
+
+// The image open in the editor. Preview renders run on their own threads and scope to it.
+pub fn set_active_image(path: &str) {
+    with_telemetry(|t| t.active_image = Some(path.to_string()));
+}
+
+pub fn active_image() -> Option<String> {
+    with_telemetry(|t| t.active_image.clone())
+}
+
+pub fn record(stage: Stage, elapsed: Duration) {
+    let ms = elapsed.as_secs_f64() * 1000.0;
+    let path = CURRENT_IMAGE.with(|current| current.borrow().clone());
+    with_telemetry(|t| {
+        t.stages.entry(stage).or_default().add(ms);
+        let Some(path) = path else { return };
+
+        let index = match t.images.iter().position(|image| image.path == path) {
+            Some(index) => index,
+            None => {
+                if t.images.len() >= MAX_TRACKED_IMAGES {
+                    t.images.pop_back();
+                }
+                t.images.push_front(ImageTimings { path, stages: HashMap::new() });
+                0
+            }
+        };
+        t.images[index].stages.entry(stage).or_default().add(ms);
+    });
+}
+
+pub fn time<T>(stage: Stage, f: impl FnOnce() -> T) -> T {
+    let start = Instant::now();
+    let result = f();
+    record(stage, start.elapsed());
+    result
+}
+
+pub fn record_cache(cache: Cache, hit: bool) {
+    with_telemetry(|t| {
+        let entry = t.caches.entry(cache).or_default();
+        if hit {
+            entry.0 += 1;
+        } else {
+            entry.1 += 1;
+        }
+    });
+}
+
+pub fn image_timings(path: &str) -> Option<ImageTimings> {
+    with_telemetry(|t| t.images.iter().find(|image| image.path == path).cloned())
+}
+
+#[tauri::command]
+pub fn get_performance_report() -> PerformanceReport {
+    with_telemetry(|t| PerformanceReport {
+        session_seconds: t.started.elapsed().as_secs_f64(),
+        stages: t.stages.clone(),
+        caches: t
+            .caches
+            .iter()
+            .map(|(cache, &(hits, misses))| {
+                let total = hits + misses;
+                let hit_rate = if total > 0 { hits as f64 / total as f64 } else { 0.0 };
+                (*cache, CacheStats { hits, misses, hit_rate })
+            })
+            .collect(),
+        images: t.images.iter().cloned().collect(),
+        bottleneck: t
+            .stages
+            .iter()
+            .max_by(|a, b| a.1.total_ms.total_cmp(&b.1.total_ms))
+            .map(|(stage, _)| *stage),
+    })
+}
+
+#[tauri::command]
+pub fn reset_performance_report() {
+    with_telemetry(|t| {
+        t.started = Instant::now();
+        t.stages.clear();
+        t.caches.clear();
+        t.images.clear();
+    });
+}
@@ -0,0 +1,80 @@
+use rawler::decoders::PictureStyle;
+use rayon::prelude::*;
+
+// Approximations of the in-camera looks, applied to the gamma encoded output of the RAW
+// development. They are a starting point that resembles the camera JPEG, not an emulation.
+struct StyleProfile {
+    // Tone curve control points (input, output), linearly interpolated.
+    tone: &'static [(f32, f32)],
+    saturation: f32,
+    // Per channel gain after the curve for the cast of some film simulations.
+    tint: [f32; 3],
+    monochrome: bool,
+}
+
+const CURVE_LINEAR: &[(f32, f32)] = &[(0.0, 0.0), (1.0, 1.0)];
+const CURVE_STANDARD: &[(f32, f32)] = &[(0.0, 0.0), (0.25, 0.22), (0.5, 0.5), (0.75, 0.78), (1.0, 1.0)];
+const CURVE_SOFT: &[(f32, f32)] = &[(0.0, 0.0), (0.25, 0.24), (0.5, 0.51), (0.75, 0.77), (1.0, 1.0)];
+const CURVE_HARD: &[(f32, f32)] = &[(0.0, 0.0), (0.25, 0.19), (0.5, 0.5), (0.75, 0.81), (1.0, 1.0)];
+const CURVE_CHROME: &[(f32, f32)] = &[(0.0, 0.0), (0.25, 0.21), (0.5, 0.49), (0.75, 0.76), (1.0, 0.98)];
+const CURVE_CINEMA: &[(f32, f32)] = &[(0.0, 0.02), (0.25, 0.26), (0.5, 0.5), (0.75, 0.73), (1.0, 0.97)];
+const CURVE_NEGATIVE: &[(f32, f32)] = &[(0.0, 0.02), (0.25, 0.25), (0.5, 0.52), (0.75, 0.77), (1.0, 0.98)];
+
+const NO_TINT: [f32; 3] = [1.0, 1.0, 1.0];
+
+fn style_profile(style: PictureStyle) -> StyleProfile {
+    let color = |tone, saturation, tint| StyleProfile { tone, saturation, tint, monochrome: false };
+    let mono = |tone, tint| StyleProfile { tone, saturation: 0.0, tint, monochrome: true };
+    match style {
+        PictureStyle::Standard | PictureStyle::Provia => color(CURVE_STANDARD, 1.1, NO_TINT),
+        PictureStyle::Portrait => color(CURVE_SOFT, 1.0, [1.02, 1.0, 0.99]),
+        PictureStyle::Astia => color(CURVE_SOFT, 1.05, [1.01, 1.0, 1.0]),
+        PictureStyle::Landscape => color(CURVE_STANDARD, 1.25, [0.99, 1.01, 1.01]),
+        PictureStyle::Velvia => color(CURVE_HARD, 1.35, NO_TINT),
+        PictureStyle::Neutral | PictureStyle::ProNegStd => color(CURVE_LINEAR, 0.95, NO_TINT),
+        PictureStyle::Faithful => color(CURVE_LINEAR, 1.0, NO_TINT),
+        PictureStyle::ProNegHi => color(CURVE_STANDARD, 1.0, NO_TINT),
+        PictureStyle::RealaAce => color(CURVE_STANDARD, 1.05, NO_TINT),
+        PictureStyle::ClassicChrome => color(CURVE_CHROME, 0.75, [1.0, 1.0, 0.97]),
+        PictureStyle::ClassicNeg => color(CURVE_HARD, 0.85, [1.0, 1.01, 0.97]),
+        PictureStyle::Eterna => color(CURVE_CINEMA, 0.7, [0.99, 1.0, 1.01]),
+        PictureStyle::BleachBypass => color(CURVE_HARD, 0.35, NO_TINT),
+        PictureStyle::NostalgicNeg => color(CURVE_NEGATIVE, 1.0, [1.04, 1.0, 0.94]),
+        PictureStyle::Monochrome => mono(CURVE_STANDARD, NO_TINT),
+        PictureStyle::Acros => mono(CURVE_HARD, NO_TINT),
+        PictureStyle::Sepia => mono(CURVE_STANDARD, [1.07, 1.0, 0.86]),
+    }
+}
+
+fn eval_curve(points: &[(f32, f32)], x: f32) -> f32 {
+    let x = x.clamp(0.0, 1.0);
+    for pair in points.windows(2) {
+        let ((x0, y0), (x1, y1)) = (pair[0], pair[1]);
+        if x <= x1 {
+            let t = if x1 > x0 { (x - x0) / (x1 - x0) } else { 0.0 };
+            return y0 + t * (y1 - y0);
+        }
+    }
+    points.last().map(|p| p.1).unwrap_or(x)
+}
+
+pub fn apply_picture_style_rgb(pixels: &mut [[f32; 3]], style: PictureStyle) {
+    let profile = style_profile(style);
+    pixels.par_iter_mut().for_each(|p| {
+        let luma = 0.2126 * p[0] + 0.7152 * p[1] + 0.0722 * p[2];
+        let mut rgb = if profile.monochrome {
+            [luma; 3]
+        } else {
+            [0, 1, 2].map(|c| luma + (p[c] - luma) * profile.saturation)
+        };
+        for (c, v) in rgb.iter_mut().enumerate() {
+            *v = (eval_curve(profile.tone, *v) * profile.tint[c]).clamp(0.0, 1.0);
+        }
+        *p = rgb;
+    });
+}
+
+pub fn apply_picture_style_mono(pixels: &mut [f32], style: PictureStyle) {
+    let profile = style_profile(style);
+    pixels.par_iter_mut().for_each(|v| *v = eval_curve(profile.tone, *v));
+}
@@ -0,0 +1,181 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Write};
+use std::path::{Path, PathBuf};
+
+use chrono::NaiveDateTime;
+use rawler::{
+    decoders::{RawDecodeParams, RawMetadata},
+    dng::convert::{write_raw_image, ConvertParams},
+    imgop::pixel_shift::{four_shot_offsets, merge_pixel_shift},
+    rawimage::RawImage,
+    rawsource::RawSource,
+};
+use serde::Serialize;
+
+use crate::stacks;
+
+// Sony writes every frame of a pixel shift burst to its own ARW file, without a burst marker in
+// the metadata we read. Frames are grouped by camera, identical shooting settings and capture
+// times no further apart than the longest interval the cameras offer. Pentax keeps the whole
+// burst in one PEF, which is merged while developing.
+const BURST_FRAMES: usize = 4;
+const MAX_FRAME_INTERVAL_SECS: i64 = 31;
+
+struct Frame {
+    path: String,
+    metadata: RawMetadata,
+    captured: NaiveDateTime,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PixelShiftBurst {
+    pub paths: Vec<String>,
+}
+
+fn is_arw(path: &str) -> bool {
+    Path::new(path)
+        .extension()
+        .map_or(false, |ext| ext.eq_ignore_ascii_case("arw"))
+}
+
+fn read_frame(path: &str) -> Option<Frame> {
+    let file_bytes = fs::read(path).ok()?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source).ok()?;
+    let metadata = decoder.raw_metadata(&source, &RawDecodeParams::default()).ok()?;
+    let captured = metadata
+        .exif
+        .date_time_original
+        .as_deref()
+        .and_then(|date| NaiveDateTime::parse_from_str(date, "%Y:%m:%d %H:%M:%S").ok())?;
+    Some(Frame { path: path.to_string(), metadata, captured })
+}
+
+fn same_settings(a: &RawMetadata, b: &RawMetadata) -> bool {
+    a.make == b.make
+        && a.model == b.model
+        && a.exif.exposure_time == b.exif.exposure_time
+        && a.exif.fnumber == b.exif.fnumber
+        && a.exif.iso_speed_ratings == b.exif.iso_speed_ratings
+        && a.exif.iso_speed == b.exif.iso_speed
+}
+
+// Runs of frames with the same settings, each split into bursts. Runs that do not divide into
+// whole bursts are ambiguous and skipped.
+fn group_bursts(mut frames: Vec<Frame>) -> Vec<Vec<String>> {
+    frames.sort_by(|a, b| a.captured.cmp(&b.captured).then_with(|| a.path.cmp(&b.path)));
+
+    let mut runs: Vec<Vec<Frame>> = Vec::new();
+    for frame in frames {
+        match runs.last_mut() {
+            Some(run)
+                if run.last().map_or(false, |last| {
+                    same_settings(&last.metadata, &frame.metadata)
+                        && (frame.captured - last.captured).num_seconds() <= MAX_FRAME_INTERVAL_SECS
+                }) =>
+            {
+                run.push(frame)
+            }
+            _ => runs.push(vec![frame]),
+        }
+    }
+
+    runs.into_iter()
+        .filter(|run| run.len() % BURST_FRAMES == 0)
+        .flat_map(|run| {
+            run.chunks(BURST_FRAMES)
+                .map(|burst| burst.iter().map(|frame| frame.path.clone()).collect())
+                .collect::<Vec<Vec<String>>>()
+        })
+        .collect()
+}
+
+fn decode_frame(path: &str) -> Result<(RawImage, RawMetadata), String> {
+    let file_bytes = fs::read(path).map_err(|e| e.to_string())?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source).map_err(|e| e.to_string())?;
+    let params = RawDecodeParams::default();
+    let raw_image = decoder.raw_image(&source, &params, false).map_err(|e| e.to_string())?;
+    let metadata = decoder.raw_metadata(&source, &params).map_err(|e| e.to_string())?;
+    Ok((raw_image, metadata))
+}
+
+fn merged_path_for(first_frame: &str) -> PathBuf {
+    let path = Path::new(first_frame);
+    let stem = path.file_stem().map(|s| s.to_string_lossy().into_owned()).unwrap_or_default();
+    let parent = path.parent().unwrap_or_else(|| Path::new(""));
+    let mut candidate = parent.join(format!("{}_PixelShift.dng", stem));
+    let mut counter = 2;
+    while candidate.exists() {
+        candidate = parent.join(format!("{}_PixelShift_{}.dng", stem, counter));
+        counter += 1;
+    }
+    candidate
+}
+
+#[tauri::command]
+pub async fn find_pixel_shift_bursts(paths: Vec<String>) -> Result<Vec<PixelShiftBurst>, String> {
+    let frames = paths.iter().filter(|p| is_arw(p)).filter_map(|p| read_frame(p)).collect();
+    Ok(group_bursts(frames)
+        .into_iter()
+        .map(|paths| PixelShiftBurst { paths })
+        .collect())
+}
+
+// Writes the merged burst as a linear DNG next to the frames and stacks the frames under it,
+// so the library shows the burst as the one merged image. Returns the path of the DNG.
+#[tauri::command]
+pub async fn merge_pixel_shift_burst(paths: Vec<String>) -> Result<String, String> {
+    if paths.len() != BURST_FRAMES {
+        return Err(format!("A pixel shift burst has {} frames.", BURST_FRAMES));
+    }
+
+    let mut decoded = paths
+        .iter()
+        .map(|path| decode_frame(path).map(|(raw_image, metadata)| (path.clone(), raw_image, metadata)))
+        .collect::<Result<Vec<_>, String>>()?;
+    // The offsets follow the shooting order, whatever order the frames were selected in.
+    decoded.sort_by(|a, b| {
+        a.2.exif
+            .date_time_original
+            .cmp(&b.2.exif.date_time_original)
+            .then_with(|| a.0.cmp(&b.0))
+    });
+    if decoded.windows(2).any(|pair| !same_settings(&pair[0].2, &pair[1].2)) {
+        return Err("The frames were not taken with the same camera settings.".to_string());
+    }
+
+    let paths: Vec<String> = decoded.iter().map(|(path, _, _)| path.clone()).collect();
+    let (frames, mut metadata): (Vec<RawImage>, Vec<RawMetadata>) =
+        decoded.into_iter().map(|(_, raw_image, metadata)| (raw_image, metadata)).unzip();
+    let metadata = metadata.swap_remove(0);
+
+    let merged = merge_pixel_shift(&frames, &four_shot_offsets()).map_err(|e| e.to_string())?;
+    drop(frames);
+
+    let output_path = merged_path_for(&paths[0]);
+    let params = ConvertParams {
+        embedded: false,
+        software: "RapidRAW".to_string(),
+        ..Default::default()
+    };
+    let written = File::create(&output_path).map_err(|e| e.to_string()).and_then(|file| {
+        let mut writer = BufWriter::new(file);
+        write_raw_image(&merged, &metadata, &mut writer, &params).map_err(|e| e.to_string())?;
+        writer.flush().map_err(|e| e.to_string())
+    });
+    if let Err(e) = written {
+        let _ = fs::remove_file(&output_path);
+        return Err(e);
+    }
+
+    let output_path = output_path.to_string_lossy().into_owned();
+    let mut stack_paths = vec![output_path.clone()];
+    stack_paths.extend(paths);
+    if let Err(e) = stacks::create_stack(stack_paths, Some(output_path.clone())) {
+        eprintln!("Failed to stack pixel shift frames under {}: {}", output_path, e);
+    }
+
+    Ok(output_path)
+}
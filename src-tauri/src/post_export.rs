@@ -0,0 +1,87 @@
+use std::process::Command;
+
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase", tag = "type")]
+pub enum PostExportHook {
+    // `{files}` in the command is replaced with the quoted exported paths; without the
+    // placeholder the paths are appended to the end of the command.
+    ShellCommand { command: String },
+    OpenWith { application: String },
+}
+
+#[cfg(target_os = "windows")]
+fn quote_for_shell(path: &str) -> String {
+    format!("\"{}\"", path.replace('"', "\\\""))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn quote_for_shell(path: &str) -> String {
+    format!("'{}'", path.replace('\'', "'\\''"))
+}
+
+fn build_shell_command(command: &str, exported_files: &[String]) -> String {
+    let files = exported_files
+        .iter()
+        .map(|f| quote_for_shell(f))
+        .collect::<Vec<_>>()
+        .join(" ");
+
+    if command.contains("{files}") {
+        command.replace("{files}", &files)
+    } else {
+        format!("{} {}", command, files)
+    }
+}
+
+fn spawn_shell_command(command: &str, exported_files: &[String]) -> Result<(), String> {
+    let full_command = build_shell_command(command, exported_files);
+
+    #[cfg(target_os = "windows")]
+    let mut process = {
+        let mut c = Command::new("cmd");
+        c.args(["/C", &full_command]);
+        c
+    };
+    #[cfg(not(target_os = "windows"))]
+    let mut process = {
+        let mut c = Command::new("sh");
+        c.args(["-c", &full_command]);
+        c
+    };
+
+    process
+        .env("RAPIDRAW_EXPORTED_FILES", exported_files.join("\n"))
+        .spawn()
+        .map(|_| ())
+        .map_err(|e| format!("Failed to run post-export command: {}", e))
+}
+
+fn open_with_application(application: &str, exported_files: &[String]) -> Result<(), String> {
+    #[cfg(target_os = "macos")]
+    let result = Command::new("open").arg("-a").arg(application).args(exported_files).spawn();
+
+    #[cfg(not(target_os = "macos"))]
+    let result = Command::new(application).args(exported_files).spawn();
+
+    result
+        .map(|_| ())
+        .map_err(|e| format!("Failed to open exported files with {}: {}", application, e))
+}
+
+pub fn run_post_export_hook(hook: &PostExportHook, exported_files: &[String]) -> Result<(), String> {
+    if exported_files.is_empty() {
+        return Ok(());
+    }
+
+    match hook {
+        PostExportHook::ShellCommand { command } if !command.trim().is_empty() => {
+            spawn_shell_command(command, exported_files)
+        }
+        PostExportHook::OpenWith { application } if !application.trim().is_empty() => {
+            open_with_application(application, exported_files)
+        }
+        _ => Ok(()),
+    }
+}
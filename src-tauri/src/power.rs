@@ -0,0 +1,78 @@
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+
+use serde::{Deserialize, Serialize};
+
+use crate::file_management::{load_settings, AppSettings};
+
+// Long edge of the editor preview while low-power mode is active, regardless of viewport.
+pub const LOW_POWER_PREVIEW_LIMIT: u32 = 1280;
+// Slider drags fire a preview request per frame; in low-power mode only the last one of a
+// burst is rendered.
+pub const LOW_POWER_PREVIEW_DEBOUNCE: Duration = Duration::from_millis(120);
+
+const BATTERY_POLL_INTERVAL: Duration = Duration::from_secs(30);
+
+static BATTERY_STATE: Mutex<Option<(Instant, bool)>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum LowPowerMode {
+    #[default]
+    Auto,
+    On,
+    Off,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PowerStatus {
+    pub on_battery: bool,
+    pub low_power_active: bool,
+    pub mode: LowPowerMode,
+}
+
+fn query_on_battery() -> bool {
+    let Ok(manager) = starship_battery::Manager::new() else {
+        return false;
+    };
+    let Ok(batteries) = manager.batteries() else {
+        return false;
+    };
+    batteries
+        .flatten()
+        .any(|b| b.state() == starship_battery::State::Discharging)
+}
+
+// Querying the battery goes through the OS power APIs, so the answer is cached for a while;
+// this is called for every preview and thumbnail batch.
+pub fn is_on_battery() -> bool {
+    let mut cached = BATTERY_STATE.lock().unwrap();
+    if let Some((checked_at, on_battery)) = *cached {
+        if checked_at.elapsed() < BATTERY_POLL_INTERVAL {
+            return on_battery;
+        }
+    }
+    let on_battery = query_on_battery();
+    *cached = Some((Instant::now(), on_battery));
+    on_battery
+}
+
+pub fn is_low_power_active(settings: &AppSettings) -> bool {
+    match settings.low_power_mode.unwrap_or_default() {
+        LowPowerMode::On => true,
+        LowPowerMode::Off => false,
+        LowPowerMode::Auto => is_on_battery(),
+    }
+}
+
+#[tauri::command]
+pub fn get_power_status(app_handle: tauri::AppHandle) -> Result<PowerStatus, String> {
+    let settings = load_settings(app_handle).unwrap_or_default();
+    let mode = settings.low_power_mode.unwrap_or_default();
+    Ok(PowerStatus {
+        on_battery: is_on_battery(),
+        low_power_active: is_low_power_active(&settings),
+        mode,
+    })
+}
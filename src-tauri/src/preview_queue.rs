@@ -0,0 +1,47 @@
+use std::panic::{self, AssertUnwindSafe};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+type Job = Box<dyn FnOnce() + Send>;
+
+#[derive(Default)]
+struct QueueState {
+    pending: Option<Job>,
+    running: bool,
+}
+
+// Runs jobs one at a time on a worker thread, keeping only the newest job that has not started.
+// Dragging a slider sends many more previews than can be rendered. Each new one replaces the
+// waiting one, so no backlog of stale frames builds up and the last values always render.
+#[derive(Default)]
+pub struct LatestJobQueue {
+    state: Arc<Mutex<QueueState>>,
+}
+
+impl LatestJobQueue {
+    pub fn push(&self, job: impl FnOnce() + Send + 'static) {
+        let mut state = self.state.lock().unwrap();
+        state.pending = Some(Box::new(job));
+        if state.running {
+            return;
+        }
+        state.running = true;
+
+        let shared = self.state.clone();
+        thread::spawn(move || loop {
+            let job = {
+                let mut state = shared.lock().unwrap();
+                match state.pending.take() {
+                    Some(job) => job,
+                    None => {
+                        state.running = false;
+                        return;
+                    }
+                }
+            };
+            if panic::catch_unwind(AssertUnwindSafe(job)).is_err() {
+                eprintln!("A preview job panicked; continuing with the next one.");
+            }
+        });
+    }
+}
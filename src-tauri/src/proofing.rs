@@ -0,0 +1,56 @@
+use std::path::Path;
+use std::sync::Mutex;
+
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+use crate::file_management::AppSettings;
+use crate::watermark::{apply_watermark, WatermarkAnchor, WatermarkSettings};
+
+// Client proofing: every image inside one of `folders` (including subfolders) gets a text
+// overlay on previews and exports, so unpurchased images can be sent out for selection.
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+#[serde(rename_all = "camelCase")]
+pub struct ProofingSettings {
+    #[serde(default)]
+    pub folders: Vec<String>,
+    pub text: Option<String>,
+    pub color: Option<String>,
+    // 0-100
+    pub opacity: Option<f32>,
+    // Text height in percent of the image's shorter edge.
+    pub scale: Option<f32>,
+}
+
+static PROOFING: Mutex<Option<ProofingSettings>> = Mutex::new(None);
+
+pub fn apply_proofing_settings(settings: &AppSettings) {
+    *PROOFING.lock().unwrap() = settings.proofing.clone();
+}
+
+// The overlay for an image, or None if it is not inside a proofing folder.
+pub fn overlay_for_path(path: &str) -> Option<WatermarkSettings> {
+    let proofing = PROOFING.lock().unwrap();
+    let proofing = proofing.as_ref()?;
+    if !proofing.folders.iter().any(|folder| Path::new(path).starts_with(folder)) {
+        return None;
+    }
+
+    Some(WatermarkSettings {
+        text: Some(proofing.text.clone().filter(|t| !t.trim().is_empty()).unwrap_or_else(|| "PROOF".to_string())),
+        image_path: None,
+        font_path: None,
+        color: proofing.color.clone(),
+        anchor: WatermarkAnchor::Center,
+        opacity: proofing.opacity.unwrap_or(35.0),
+        scale: proofing.scale.unwrap_or(20.0),
+        margin: 0.0,
+    })
+}
+
+pub fn apply_proof_overlay(image: &mut DynamicImage, overlay: Option<&WatermarkSettings>) -> Result<(), String> {
+    match overlay {
+        Some(settings) => apply_watermark(image, settings),
+        None => Ok(()),
+    }
+}
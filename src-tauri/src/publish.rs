@@ -0,0 +1,260 @@
+use std::collections::HashMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::UNIX_EPOCH;
+
+use chrono::Local;
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+use uuid::Uuid;
+use walkdir::WalkDir;
+
+use crate::export_queue::{enqueue_export_job, ExportJobKind};
+use crate::file_management::get_sidecar_path;
+use crate::formats::is_supported_image_file;
+use crate::image_processing::ImageMetadata;
+use crate::ExportSettings;
+
+const PUBLISH_TARGETS_FILENAME: &str = "publish_targets.json";
+
+// Publish records are written from the export thread while commands may edit the targets,
+// so every load-modify-save of the file goes through this lock.
+static TARGETS_LOCK: Mutex<()> = Mutex::new(());
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishedImage {
+    pub fingerprint: String,
+    pub published_at: String,
+}
+
+// A folder bound to an export configuration. `published` is keyed by source path and holds
+// the fingerprint of the edit that was last exported, which is what change tracking compares.
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishTarget {
+    pub id: String,
+    pub name: String,
+    pub source_folder: String,
+    #[serde(default)]
+    pub recursive: bool,
+    pub output_folder: String,
+    pub output_format: String,
+    pub export_settings: ExportSettings,
+    #[serde(default)]
+    pub published: HashMap<String, PublishedImage>,
+    pub created_at: String,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct PublishStatus {
+    pub target_id: String,
+    pub new_paths: Vec<String>,
+    pub changed_paths: Vec<String>,
+    pub unchanged_count: usize,
+}
+
+fn get_targets_path(app_handle: &AppHandle) -> Result<PathBuf, String> {
+    let data_dir = app_handle.path().app_data_dir().map_err(|e| e.to_string())?;
+    if !data_dir.exists() {
+        fs::create_dir_all(&data_dir).map_err(|e| e.to_string())?;
+    }
+    Ok(data_dir.join(PUBLISH_TARGETS_FILENAME))
+}
+
+fn load_targets(app_handle: &AppHandle) -> Result<Vec<PublishTarget>, String> {
+    let path = get_targets_path(app_handle)?;
+    if !path.exists() {
+        return Ok(Vec::new());
+    }
+    let content = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    serde_json::from_str(&content).map_err(|e| e.to_string())
+}
+
+fn save_targets(app_handle: &AppHandle, targets: &[PublishTarget]) -> Result<(), String> {
+    let path = get_targets_path(app_handle)?;
+    let json_string = serde_json::to_string_pretty(targets).map_err(|e| e.to_string())?;
+    fs::write(path, json_string).map_err(|e| e.to_string())
+}
+
+fn find_target(targets: &[PublishTarget], target_id: &str) -> Result<PublishTarget, String> {
+    targets
+        .iter()
+        .find(|t| t.id == target_id)
+        .cloned()
+        .ok_or_else(|| format!("Publish target {} not found.", target_id))
+}
+
+fn is_hidden(path: &Path) -> bool {
+    path.file_name()
+        .and_then(|n| n.to_str())
+        .is_some_and(|n| n.starts_with('.'))
+}
+
+fn collect_source_images(target: &PublishTarget) -> Vec<String> {
+    let max_depth = if target.recursive { usize::MAX } else { 1 };
+    let mut paths: Vec<String> = WalkDir::new(&target.source_folder)
+        .max_depth(max_depth)
+        .into_iter()
+        .filter_entry(|e| e.depth() == 0 || !is_hidden(e.path()))
+        .filter_map(|e| e.ok())
+        .filter(|e| e.file_type().is_file())
+        .map(|e| e.path().to_string_lossy().into_owned())
+        .filter(|p| is_supported_image_file(p))
+        .collect();
+    paths.sort();
+    paths
+}
+
+// Covers everything that changes the exported pixels: the edit, its versions and the source
+// file itself. Rating, labels and notes are left out on purpose.
+pub fn image_fingerprint(image_path: &str) -> String {
+    let mut hasher = blake3::Hasher::new();
+
+    let sidecar_path = get_sidecar_path(image_path);
+    let metadata: ImageMetadata = fs::read_to_string(sidecar_path)
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+    hasher.update(metadata.adjustments.to_string().as_bytes());
+    for version in &metadata.versions {
+        hasher.update(version.id.as_bytes());
+        hasher.update(version.adjustments.to_string().as_bytes());
+    }
+
+    let modified = fs::metadata(image_path)
+        .ok()
+        .and_then(|m| m.modified().ok())
+        .and_then(|t| t.duration_since(UNIX_EPOCH).ok())
+        .map(|d| d.as_secs())
+        .unwrap_or(0);
+    hasher.update(&modified.to_le_bytes());
+
+    hasher.finalize().to_hex().to_string()
+}
+
+pub fn record_published(app_handle: &AppHandle, target_id: &str, image_path: &str, fingerprint: String) {
+    let _guard = TARGETS_LOCK.lock().unwrap();
+    let result = load_targets(app_handle).and_then(|mut targets| {
+        if let Some(target) = targets.iter_mut().find(|t| t.id == target_id) {
+            target.published.insert(
+                image_path.to_string(),
+                PublishedImage { fingerprint, published_at: Local::now().to_rfc3339() },
+            );
+        }
+        save_targets(app_handle, &targets)
+    });
+    if let Err(e) = result {
+        eprintln!("Failed to record published image {}: {}", image_path, e);
+    }
+}
+
+fn compute_status(target: &PublishTarget) -> PublishStatus {
+    let mut status = PublishStatus {
+        target_id: target.id.clone(),
+        new_paths: Vec::new(),
+        changed_paths: Vec::new(),
+        unchanged_count: 0,
+    };
+    for path in collect_source_images(target) {
+        match target.published.get(&path) {
+            None => status.new_paths.push(path),
+            Some(published) if published.fingerprint != image_fingerprint(&path) => status.changed_paths.push(path),
+            Some(_) => status.unchanged_count += 1,
+        }
+    }
+    status
+}
+
+#[tauri::command]
+pub fn create_publish_target(
+    name: String,
+    source_folder: String,
+    recursive: Option<bool>,
+    output_folder: String,
+    output_format: String,
+    export_settings: ExportSettings,
+    app_handle: AppHandle,
+) -> Result<PublishTarget, String> {
+    if !Path::new(&source_folder).is_dir() {
+        return Err(format!("Folder not found: {}", source_folder));
+    }
+    let target = PublishTarget {
+        id: Uuid::new_v4().to_string(),
+        name,
+        source_folder,
+        recursive: recursive.unwrap_or(false),
+        output_folder,
+        output_format,
+        export_settings,
+        published: HashMap::new(),
+        created_at: Local::now().to_rfc3339(),
+    };
+
+    let _guard = TARGETS_LOCK.lock().unwrap();
+    let mut targets = load_targets(&app_handle)?;
+    targets.push(target.clone());
+    save_targets(&app_handle, &targets)?;
+    Ok(target)
+}
+
+#[tauri::command]
+pub fn list_publish_targets(app_handle: AppHandle) -> Result<Vec<PublishTarget>, String> {
+    let _guard = TARGETS_LOCK.lock().unwrap();
+    load_targets(&app_handle)
+}
+
+#[tauri::command]
+pub fn delete_publish_target(target_id: String, app_handle: AppHandle) -> Result<(), String> {
+    let _guard = TARGETS_LOCK.lock().unwrap();
+    let mut targets = load_targets(&app_handle)?;
+    targets.retain(|t| t.id != target_id);
+    save_targets(&app_handle, &targets)
+}
+
+#[tauri::command]
+pub async fn get_publish_status(target_id: String, app_handle: AppHandle) -> Result<PublishStatus, String> {
+    let target = {
+        let _guard = TARGETS_LOCK.lock().unwrap();
+        find_target(&load_targets(&app_handle)?, &target_id)?
+    };
+    tauri::async_runtime::spawn_blocking(move || compute_status(&target))
+        .await
+        .map_err(|e| e.to_string())
+}
+
+// Queues an export of the target's new and modified images. Returns the export job id, or
+// None when everything is already up to date.
+#[tauri::command]
+pub async fn publish_changed(target_id: String, app_handle: AppHandle) -> Result<Option<String>, String> {
+    let target = {
+        let _guard = TARGETS_LOCK.lock().unwrap();
+        find_target(&load_targets(&app_handle)?, &target_id)?
+    };
+    let status = tauri::async_runtime::spawn_blocking({
+        let target = target.clone();
+        move || compute_status(&target)
+    })
+    .await
+    .map_err(|e| e.to_string())?;
+
+    let paths: Vec<String> = status.new_paths.into_iter().chain(status.changed_paths).collect();
+    if paths.is_empty() {
+        return Ok(None);
+    }
+
+    let job_id = enqueue_export_job(
+        &app_handle,
+        ExportJobKind::Batch {
+            output_folder: target.output_folder,
+            paths,
+            output_format: target.output_format,
+            publish_target_id: Some(target.id),
+            start_index: 0,
+        },
+        target.export_settings,
+    );
+    Ok(Some(job_id))
+}
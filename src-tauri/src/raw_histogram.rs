@@ -0,0 +1,193 @@
+use rawler::{
+    decoders::RawDecodeParams,
+    imgop::{Point, Rect},
+    rawimage::{RawImage, RawPhotometricInterpretation},
+    rawsource::RawSource,
+};
+use rayon::prelude::*;
+use serde::Serialize;
+
+use crate::raw_processing::RawLevels;
+
+// The bins cover this many stops below the white level, deeper shadows end up in the first bin.
+const HISTOGRAM_STOPS: f32 = 12.0;
+const HISTOGRAM_BINS: usize = 256;
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawHistogramChannel {
+    pub name: String,
+    pub bins: Vec<u32>,
+    // Share of the samples at or above the white level.
+    pub clipped_percent: f32,
+    // Share of the samples at or below the black level.
+    pub black_percent: f32,
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawHistogram {
+    pub stops: f32,
+    pub channels: Vec<RawHistogramChannel>,
+}
+
+#[derive(Clone)]
+struct ChannelCounts {
+    bins: Vec<u32>,
+    clipped: u64,
+    black: u64,
+    total: u64,
+}
+
+impl ChannelCounts {
+    fn new() -> Self {
+        Self { bins: vec![0; HISTOGRAM_BINS], clipped: 0, black: 0, total: 0 }
+    }
+
+    fn add(&mut self, value: f32, black: f32, white: f32) {
+        self.total += 1;
+        if value >= white {
+            self.clipped += 1;
+            self.bins[HISTOGRAM_BINS - 1] += 1;
+        } else if value <= black {
+            self.black += 1;
+            self.bins[0] += 1;
+        } else {
+            let stops_below_white = ((value - black) / (white - black)).log2();
+            let position = (stops_below_white + HISTOGRAM_STOPS) / HISTOGRAM_STOPS;
+            let bin = (position * (HISTOGRAM_BINS - 1) as f32).round().clamp(0.0, (HISTOGRAM_BINS - 1) as f32);
+            self.bins[bin as usize] += 1;
+        }
+    }
+
+    fn merge(mut self, other: Self) -> Self {
+        self.bins.iter_mut().zip(other.bins).for_each(|(a, b)| *a += b);
+        self.clipped += other.clipped;
+        self.black += other.black;
+        self.total += other.total;
+        self
+    }
+}
+
+// Which histogram a sample belongs to. Bayer sensors get one per pattern position, so the two
+// greens can be compared, larger patterns like X-Trans one per color.
+struct ChannelLayout {
+    names: Vec<String>,
+    pattern: Option<(usize, usize, Vec<usize>)>,
+}
+
+impl ChannelLayout {
+    fn for_image(raw_image: &RawImage) -> Self {
+        match &raw_image.photometric {
+            RawPhotometricInterpretation::Cfa(config) if raw_image.cpp == 1 && config.cfa.width > 0 => {
+                let cfa = &config.cfa;
+                let colors: Vec<char> = cfa.name.chars().collect();
+                let mut names: Vec<String> = Vec::new();
+                let mut lookup = Vec::with_capacity(colors.len());
+                if cfa.width == 2 && cfa.height == 2 {
+                    for (i, color) in colors.iter().enumerate() {
+                        let repeats = colors.iter().filter(|c| *c == color).count();
+                        let number = colors[..i].iter().filter(|c| *c == color).count() + 1;
+                        names.push(if repeats > 1 { format!("{}{}", color, number) } else { color.to_string() });
+                        lookup.push(i);
+                    }
+                } else {
+                    for color in &colors {
+                        let name = color.to_string();
+                        let index = names.iter().position(|n| *n == name).unwrap_or_else(|| {
+                            names.push(name);
+                            names.len() - 1
+                        });
+                        lookup.push(index);
+                    }
+                }
+                Self { names, pattern: Some((cfa.width, cfa.height, lookup)) }
+            }
+            _ => {
+                let names = match raw_image.cpp {
+                    1 => vec!["L".to_string()],
+                    3 => vec!["R".to_string(), "G".to_string(), "B".to_string()],
+                    cpp => (1..=cpp).map(|c| format!("C{}", c)).collect(),
+                };
+                Self { names, pattern: None }
+            }
+        }
+    }
+
+    fn channel(&self, row: usize, col: usize, component: usize) -> usize {
+        match &self.pattern {
+            Some((width, height, lookup)) => lookup[(row % height) * width + col % width],
+            None => component,
+        }
+    }
+}
+
+fn black_level_at(raw_image: &RawImage, row: usize, col: usize, component: usize) -> f32 {
+    let black = &raw_image.blacklevel;
+    let (width, height, cpp) = (black.width.max(1), black.height.max(1), black.cpp.max(1));
+    let index = ((row % height) * width + col % width) * cpp + component % cpp;
+    black.levels.get(index).or(black.levels.first()).map(|level| level.as_f32()).unwrap_or(0.0)
+}
+
+fn white_level_at(raw_image: &RawImage, component: usize) -> f32 {
+    let white = &raw_image.whitelevel.0;
+    white.get(component).or(white.first()).map(|level| *level as f32).unwrap_or(u16::MAX as f32)
+}
+
+pub fn compute_raw_histogram(raw_image: &RawImage, levels: RawLevels) -> RawHistogram {
+    let layout = ChannelLayout::for_image(raw_image);
+    let data = raw_image.data.as_f32();
+    let cpp = raw_image.cpp;
+    let area = raw_image.active_area.unwrap_or_else(|| Rect::new(Point::zero(), raw_image.dim()));
+    let bottom = (area.p.y + area.d.h).min(raw_image.height);
+    let right = (area.p.x + area.d.w).min(raw_image.width);
+
+    let counts = (area.p.y..bottom)
+        .into_par_iter()
+        .fold(
+            || vec![ChannelCounts::new(); layout.names.len()],
+            |mut counts, row| {
+                for col in area.p.x..right {
+                    for component in 0..cpp {
+                        let value = data[(row * raw_image.width + col) * cpp + component];
+                        let black = levels.black_level.map(|b| b as f32).unwrap_or_else(|| black_level_at(raw_image, row, col, component));
+                        let white = levels.white_level.map(|w| w as f32).unwrap_or_else(|| white_level_at(raw_image, component));
+                        counts[layout.channel(row, col, component)].add(value, black, white);
+                    }
+                }
+                counts
+            },
+        )
+        .reduce(
+            || vec![ChannelCounts::new(); layout.names.len()],
+            |a, b| a.into_iter().zip(b).map(|(a, b)| a.merge(b)).collect(),
+        );
+
+    let percent = |count: u64, total: u64| if total > 0 { count as f32 / total as f32 * 100.0 } else { 0.0 };
+    let channels = layout
+        .names
+        .into_iter()
+        .zip(counts)
+        .map(|(name, counts)| RawHistogramChannel {
+            name,
+            clipped_percent: percent(counts.clipped, counts.total),
+            black_percent: percent(counts.black, counts.total),
+            bins: counts.bins,
+        })
+        .collect();
+
+    RawHistogram { stops: HISTOGRAM_STOPS, channels }
+}
+
+// Histograms of the sensor data before demosaic and white balance, to judge the exposure
+// of the capture itself rather than of the rendered preview.
+#[tauri::command]
+pub async fn generate_raw_histogram(path: String, levels: Option<RawLevels>) -> Result<RawHistogram, String> {
+    let file_bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source).map_err(|e| e.to_string())?;
+    let raw_image = decoder
+        .raw_image(&source, &RawDecodeParams::default(), false)
+        .map_err(|e| e.to_string())?;
+    Ok(compute_raw_histogram(&raw_image, levels.unwrap_or_default()))
+}
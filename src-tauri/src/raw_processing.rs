@@ -1,18 +1,241 @@
+use std::sync::Mutex;
+use std::time::Instant;
+
 use anyhow::Result;
-use image::DynamicImage;
+use image::{DynamicImage, ImageBuffer};
 use rawler::{
-    decoders::{Orientation, RawDecodeParams},
+    decoders::{Orientation, RawDecodeParams, RawMetadata},
     imgop::develop::{DemosaicAlgorithm, Intermediate, ProcessingStep, RawDevelop},
-    rawimage::RawImage,
+    imgop::highlights::HighlightMode,
+    imgop::white_balance::{white_balance_temperature, WhiteBalance},
+    imgop::{Dim2, Point, Rect},
+    rawimage::{RawImage, RawImageData},
     rawsource::RawSource,
 };
+use serde::{Deserialize, Serialize};
+use crate::bad_pixel_maps;
+use crate::dark_frames;
+use crate::flat_fields;
+use crate::performance::{self, Stage};
+use crate::file_management::AppSettings;
 use crate::image_processing::apply_orientation;
+use crate::picture_styles::{apply_picture_style_mono, apply_picture_style_rgb};
+
+// Demosaic used for full quality developing. Fast developing uses a superpixel path at
+// `FastRawResolution` regardless of this.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum DemosaicQuality {
+    #[default]
+    Standard,
+    Best,
+    LowNoise,
+}
+
+// Size of fast developed RAW files, used for thumbnails and quick previews.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum FastRawResolution {
+    #[default]
+    Quarter,
+    Half,
+}
+
+// White balance applied to the raw channels before the camera matrix, unlike the
+// temperature and tint adjustments which shift the rendered colors.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq)]
+#[serde(rename_all = "camelCase", tag = "mode")]
+pub enum RawWhiteBalance {
+    AsShot,
+    Auto,
+    Custom { temperature: f32, tint: f32 },
+}
+
+impl From<RawWhiteBalance> for WhiteBalance {
+    fn from(value: RawWhiteBalance) -> Self {
+        match value {
+            RawWhiteBalance::AsShot => WhiteBalance::AsShot,
+            RawWhiteBalance::Auto => WhiteBalance::Auto,
+            RawWhiteBalance::Custom { temperature, tint } => WhiteBalance::Temperature { kelvin: temperature, tint },
+        }
+    }
+}
+
+// Sensor levels for files whose metadata has them wrong, e.g. from modified astro cameras.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RawLevels {
+    pub black_level: Option<u32>,
+    pub white_level: Option<u32>,
+}
+
+// Per-image choices stored in the sidecar adjustments that change how the raw data is developed.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct DevelopOverrides {
+    // Overrides the global demosaic setting.
+    pub demosaic: Option<DemosaicQuality>,
+    pub white_balance: Option<RawWhiteBalance>,
+    pub levels: RawLevels,
+    // Develops the sensor data as grayscale, for cameras converted to monochrome that still
+    // report a color filter.
+    pub monochrome_sensor: bool,
+    // Median filters the chroma after demosaicing, against color moiré of sensors without an
+    // anti-aliasing filter.
+    pub false_color_suppression: bool,
+    // Removes row and column offsets of the sensor, seen as stripes at high ISO.
+    pub banding_correction: bool,
+    // Skips the camera's default crop and shows everything the sensor recorded.
+    pub full_sensor_area: bool,
+    // For float sources, leaves tone mapping to the GPU instead of the fixed curve below.
+    pub tone_mapper: Option<ToneMapper>,
+}
+
+impl DevelopOverrides {
+    pub fn from_adjustments(adjustments: &serde_json::Value) -> Self {
+        let read = |key: &str| adjustments.get(key).cloned().unwrap_or(serde_json::Value::Null);
+        Self {
+            demosaic: serde_json::from_value(read("demosaic")).ok(),
+            white_balance: serde_json::from_value(read("rawWhiteBalance")).ok(),
+            levels: serde_json::from_value(read("rawLevels")).unwrap_or_default(),
+            monochrome_sensor: read("monochromeSensor").as_bool().unwrap_or(false),
+            false_color_suppression: read("falseColorSuppression").as_bool().unwrap_or(false),
+            banding_correction: read("bandingCorrection").as_bool().unwrap_or(false),
+            full_sensor_area: read("showFullSensorArea").as_bool().unwrap_or(false),
+            tone_mapper: serde_json::from_value(read("toneMapper")).ok(),
+        }
+    }
+}
+
+// Operators for mapping HDR sources to the display, applied by the shader before the
+// adjustments. The values are the shader's ids, 0 is the curve baked in while developing.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum ToneMapper {
+    Reinhard = 1,
+    Filmic = 2,
+    Agx = 3,
+}
+
+// How channels that clipped on the sensor are treated before white balance.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum HighlightRecovery {
+    Off,
+    Clip,
+    #[default]
+    Blend,
+    Reconstruct,
+}
+
+// Replaces the default crop of a camera whose metadata trims too much. The margins are cut
+// from the active sensor area, so all zeros shows the whole usable area.
+#[derive(Serialize, Deserialize, Debug, Clone, Default, PartialEq)]
+#[serde(rename_all = "camelCase")]
+pub struct RawCropOverride {
+    pub make: String,
+    pub model: String,
+    pub left: u32,
+    pub top: u32,
+    pub right: u32,
+    pub bottom: u32,
+}
 
-pub fn develop_raw_image(file_bytes: &[u8], fast_demosaic: bool) -> Result<DynamicImage> {
-    let (developed_image, orientation) = develop_internal(file_bytes, fast_demosaic)?;
+static RAW_CROP_OVERRIDES: Mutex<Vec<RawCropOverride>> = Mutex::new(Vec::new());
+
+#[derive(Debug, Clone, Copy)]
+struct DevelopOptions {
+    demosaic_quality: DemosaicQuality,
+    fast_raw_resolution: FastRawResolution,
+    match_picture_style: bool,
+    highlight_recovery: HighlightRecovery,
+    highlight_rolloff: bool,
+    ca_correction: bool,
+}
+
+static DEVELOP_OPTIONS: Mutex<DevelopOptions> = Mutex::new(DevelopOptions {
+    demosaic_quality: DemosaicQuality::Standard,
+    fast_raw_resolution: FastRawResolution::Quarter,
+    match_picture_style: false,
+    highlight_recovery: HighlightRecovery::Blend,
+    highlight_rolloff: true,
+    ca_correction: true,
+});
+
+// The settings that change the developed image itself. Applied on startup and whenever the
+// settings are saved; images that are already loaded keep their development.
+pub fn apply_develop_settings(settings: &AppSettings) {
+    *DEVELOP_OPTIONS.lock().unwrap() = DevelopOptions {
+        demosaic_quality: settings.demosaic_quality.unwrap_or_default(),
+        fast_raw_resolution: settings.fast_raw_resolution.unwrap_or_default(),
+        match_picture_style: settings.match_picture_style.unwrap_or(false),
+        highlight_recovery: settings.highlight_recovery.unwrap_or_default(),
+        highlight_rolloff: settings.highlight_rolloff.unwrap_or(true),
+        ca_correction: settings.ca_correction.unwrap_or(true),
+    };
+    *RAW_CROP_OVERRIDES.lock().unwrap() = settings.raw_crop_overrides.clone().unwrap_or_default();
+}
+
+fn crop_override_for(raw_image: &RawImage) -> Option<Rect> {
+    let overrides = RAW_CROP_OVERRIDES.lock().unwrap();
+    let matches = |wanted: &str, names: [&str; 2]| names.iter().any(|name| name.trim().eq_ignore_ascii_case(wanted.trim()));
+    let entry = overrides.iter().find(|o| {
+        matches(&o.make, [&raw_image.make, &raw_image.clean_make]) && matches(&o.model, [&raw_image.model, &raw_image.clean_model])
+    })?;
+
+    let area = raw_image.active_area.unwrap_or_else(|| Rect::new(Point::zero(), raw_image.dim()));
+    let (left, top, right, bottom) = (entry.left as usize, entry.top as usize, entry.right as usize, entry.bottom as usize);
+    if left + right >= area.d.w || top + bottom >= area.d.h {
+        eprintln!("Ignoring crop override for {} {}, the margins are larger than the sensor", entry.make, entry.model);
+        return None;
+    }
+    Some(Rect::new(
+        Point::new(area.p.x + left, area.p.y + top),
+        Dim2::new(area.d.w - left - right, area.d.h - top - bottom),
+    ))
+}
+
+pub fn develop_raw_image(
+    file_bytes: &[u8],
+    fast_demosaic: bool,
+    overrides: DevelopOverrides,
+) -> Result<DynamicImage> {
+    let (developed_image, orientation) = develop_internal(file_bytes, fast_demosaic, overrides)?;
     Ok(apply_orientation(developed_image, orientation))
 }
 
+// Returns the largest JPEG preview embedded by the camera without decoding the sensor data.
+pub fn extract_embedded_preview(file_bytes: &[u8]) -> Result<DynamicImage> {
+    let source = RawSource::new_from_slice(file_bytes);
+    let decoder = rawler::get_decoder(&source)?;
+    let params = RawDecodeParams::default();
+
+    let preview = decoder
+        .full_image(&source, &params)
+        .ok()
+        .flatten()
+        .or_else(|| decoder.preview_image(&source, &params).ok().flatten())
+        .or_else(|| decoder.thumbnail_image(&source, &params).ok().flatten())
+        .ok_or_else(|| anyhow::anyhow!("No embedded preview found"))?;
+
+    let orientation = decoder
+        .raw_metadata(&source, &params)
+        .ok()
+        .and_then(|m| m.exif.orientation)
+        .map(Orientation::from_u16)
+        .unwrap_or(Orientation::Normal);
+
+    Ok(apply_orientation(preview, orientation))
+}
+
+pub fn read_raw_metadata(file_bytes: &[u8]) -> Result<RawMetadata> {
+    let source = RawSource::new_from_slice(file_bytes);
+    let decoder = rawler::get_decoder(&source)?;
+    Ok(decoder.raw_metadata(&source, &RawDecodeParams::default())?)
+}
+
+// Linear value at which apply_tonemap_and_gamma reaches white.
+const TONEMAP_WHITE: f32 = 7.24;
+
 fn apply_tonemap_and_gamma(linear_val: f32) -> f32 {
     let x = linear_val.max(0.0);
     let a = 2.51;
@@ -29,10 +252,42 @@ fn apply_tonemap_and_gamma(linear_val: f32) -> f32 {
     }
 }
 
-fn develop_internal(file_bytes: &[u8], fast_demosaic: bool) -> Result<(DynamicImage, Orientation)> {
+// Float sources mapped on the GPU keep everything above white. The values are only sRGB encoded,
+// as the shader decodes its input from sRGB.
+fn encode_hdr(linear_val: f32) -> f32 {
+    let x = linear_val.max(0.0);
+    if x <= 0.0031308 {
+        x * 12.92
+    } else {
+        1.055 * x.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+// Float sources are kept as 32-bit float RGB, which is also how the app tells HDR sources apart.
+fn to_float_image(intermediate: Intermediate) -> Option<DynamicImage> {
+    let (width, height) = (intermediate.dim().w as u32, intermediate.dim().h as u32);
+    let data: Vec<f32> = match intermediate {
+        Intermediate::Monochrome(pixels) => pixels.data.iter().flat_map(|&v| [v; 3]).collect(),
+        Intermediate::ThreeColor(pixels) => pixels.flatten(),
+        Intermediate::FourColor(_) => return intermediate.to_dynamic_image(),
+    };
+    Some(DynamicImage::ImageRgb32F(ImageBuffer::from_raw(width, height, data)?))
+}
+
+fn develop_internal(
+    file_bytes: &[u8],
+    fast_demosaic: bool,
+    overrides: DevelopOverrides,
+) -> Result<(DynamicImage, Orientation)> {
+    let decode_start = Instant::now();
     let source = RawSource::new_from_slice(file_bytes);
     let decoder = rawler::get_decoder(&source)?;
-    let mut raw_image: RawImage = decoder.raw_image(&source, &RawDecodeParams::default(), false)?;
+    // Pixel shift bursts are merged for full quality, fast previews use the first frame.
+    let merged = if fast_demosaic || overrides.monochrome_sensor { None } else { rawler::decode_pixel_shift(&source)? };
+    let mut raw_image: RawImage = match merged {
+        Some(raw_image) => raw_image,
+        None => decoder.raw_image(&source, &RawDecodeParams::default(), false)?,
+    };
 
     let metadata = decoder.raw_metadata(&source, &RawDecodeParams::default())?;
     let orientation = metadata
@@ -40,29 +295,116 @@ fn develop_internal(file_bytes: &[u8], fast_demosaic: bool) -> Result<(DynamicIm
         .orientation
         .map(Orientation::from_u16)
         .unwrap_or(Orientation::Normal);
+    performance::record(Stage::Decode, decode_start.elapsed());
+    let develop_start = Instant::now();
+
+    let dark_frame = dark_frames::dark_frame_for(&raw_image, &metadata);
+    let flat_field = flat_fields::flat_field_for(&raw_image, &metadata);
 
-    let original_white_level = raw_image.whitelevel.0.get(0).cloned().unwrap_or(u16::MAX as u32) as f32;
-    let original_black_level = raw_image.blacklevel.levels.get(0).map(|r| r.as_f32()).unwrap_or(0.0);
+    let mut levels = overrides.levels;
+    let metadata_white_level = raw_image.whitelevel.0.get(0).cloned().unwrap_or(u16::MAX as u32);
+    let metadata_black_level = raw_image.blacklevel.levels.get(0).map(|r| r.as_f32()).unwrap_or(0.0);
+    if levels.black_level.unwrap_or(metadata_black_level as u32) >= levels.white_level.unwrap_or(metadata_white_level) {
+        eprintln!("Ignoring raw level overrides {:?}, black is not below white", levels);
+        levels = RawLevels::default();
+    }
+    let original_white_level = levels.white_level.unwrap_or(metadata_white_level) as f32;
+    let original_black_level = levels.black_level.map(|b| b as f32).unwrap_or(metadata_black_level);
 
     let headroom_white_level = u32::MAX as f32;
     for level in raw_image.whitelevel.0.iter_mut() {
         *level = u32::MAX;
     }
 
+    let options = *DEVELOP_OPTIONS.lock().unwrap();
     let mut developer = RawDevelop::default();
-    if fast_demosaic {
-        developer.demosaic_algorithm = DemosaicAlgorithm::Speed;
-    }
+    developer.demosaic_algorithm = if fast_demosaic {
+        match options.fast_raw_resolution {
+            FastRawResolution::Quarter => DemosaicAlgorithm::Speed,
+            FastRawResolution::Half => DemosaicAlgorithm::Half,
+        }
+    } else {
+        match overrides.demosaic.unwrap_or(options.demosaic_quality) {
+            DemosaicQuality::Standard => DemosaicAlgorithm::Quality,
+            DemosaicQuality::Best => DemosaicAlgorithm::Best,
+            DemosaicQuality::LowNoise => DemosaicAlgorithm::LowNoise,
+        }
+    };
     developer.steps.retain(|&step| step != ProcessingStep::SRgb);
-
-    let mut developed_intermediate = developer.develop_intermediate(&raw_image)?;
+    if overrides.monochrome_sensor {
+        developer.steps.retain(|&step| {
+            !matches!(step, ProcessingStep::Demosaic | ProcessingStep::WhiteBalance | ProcessingStep::Calibrate)
+        });
+    }
+    if overrides.full_sensor_area {
+        developer.steps.retain(|&step| step != ProcessingStep::CropDefault);
+    } else {
+        developer.default_crop = crop_override_for(&raw_image);
+    }
+    developer.dark_frame = dark_frame;
+    developer.flat_field = flat_field;
+    developer.bad_pixels = bad_pixel_maps::bad_pixels_for(&metadata);
+    if let Some(white_balance) = overrides.white_balance {
+        developer.white_balance = white_balance.into();
+    }
+    // The white level is replaced for headroom anyway, an override only changes the rescale factor.
+    developer.black_level = levels.black_level;
 
     let denominator = (original_white_level - original_black_level).max(1.0);
     let rescale_factor = (headroom_white_level - original_black_level) / denominator;
 
+    // The quarter resolution fast path has too little detail to measure the aberration.
+    if options.ca_correction && !fast_demosaic {
+        developer.steps.push(ProcessingStep::ChromaticAberration);
+    }
+
+    if overrides.banding_correction {
+        developer.steps.push(ProcessingStep::BandingCorrection);
+    }
+    // Superpixel previews are not demosaiced and have no false colors to suppress.
+    if overrides.false_color_suppression && !fast_demosaic {
+        developer.steps.push(ProcessingStep::FalseColorSuppression);
+    }
+
+    let highlight_mode = match options.highlight_recovery {
+        HighlightRecovery::Off => None,
+        HighlightRecovery::Clip => Some(HighlightMode::Clip),
+        HighlightRecovery::Blend => Some(HighlightMode::Blend),
+        HighlightRecovery::Reconstruct => Some(HighlightMode::Reconstruct),
+    };
+    // Float data comes from merged HDR files, values above the white level are real highlights
+    // and not sensor clipping.
+    let is_float = matches!(raw_image.data, RawImageData::Float(_));
+    if let Some(mode) = highlight_mode.filter(|_| !is_float) {
+        developer.steps.push(ProcessingStep::HighlightRecovery);
+        developer.highlight_mode = mode;
+        // The white level was raised for headroom, so the sensor clips well below 1.0.
+        developer.highlight_clip = Some(1.0 / rescale_factor);
+    }
+
+    let keep_hdr = is_float && overrides.tone_mapper.is_some();
+
+    // Compresses what would clip in the tonemap below, in the developed (still headroom
+    // scaled) values.
+    if options.highlight_rolloff && !keep_hdr {
+        developer.steps.push(ProcessingStep::HighlightRolloff);
+        developer.rolloff_white = TONEMAP_WHITE / rescale_factor;
+    }
+
+    let mut developed_intermediate = developer.develop_intermediate(&raw_image)?;
+
     const HIGHLIGHT_COMPRESSION_POINT: f32 = 3.0; // FIXME: This is not a good solution yet
 
     match &mut developed_intermediate {
+        Intermediate::Monochrome(pixels) if keep_hdr => {
+            pixels.data.iter_mut().for_each(|p| *p = encode_hdr(*p * rescale_factor));
+        }
+        Intermediate::ThreeColor(pixels) if keep_hdr => {
+            pixels.data.iter_mut().flatten().for_each(|c| *c = encode_hdr(*c * rescale_factor));
+        }
+        Intermediate::FourColor(pixels) if keep_hdr => {
+            pixels.data.iter_mut().flatten().for_each(|c| *c = encode_hdr(*c * rescale_factor));
+        }
         Intermediate::Monochrome(pixels) => {
             pixels.data.iter_mut().for_each(|p| {
                 let linear_val = *p * rescale_factor;
@@ -112,9 +454,74 @@ fn develop_internal(file_bytes: &[u8], fast_demosaic: bool) -> Result<(DynamicIm
         }
     }
 
-    let dynamic_image = developed_intermediate
-        .to_dynamic_image()
+    if let Some(style) = metadata.picture_style.filter(|_| options.match_picture_style && !keep_hdr) {
+        match &mut developed_intermediate {
+            Intermediate::Monochrome(pixels) => apply_picture_style_mono(&mut pixels.data, style),
+            Intermediate::ThreeColor(pixels) => apply_picture_style_rgb(&mut pixels.data, style),
+            Intermediate::FourColor(_) => {}
+        }
+    }
+
+    let dynamic_image = if is_float { to_float_image(developed_intermediate) } else { developed_intermediate.to_dynamic_image() }
         .ok_or_else(|| anyhow::anyhow!("Failed to convert developed image to DynamicImage"))?;
+    performance::record(Stage::Develop, develop_start.elapsed());
 
     Ok((dynamic_image, orientation))
-}
\ No newline at end of file
+}
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct WhiteBalanceTemperature {
+    pub temperature: f32,
+    pub tint: f32,
+}
+
+#[derive(Serialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub struct RawWhiteBalanceInfo {
+    pub as_shot: Option<WhiteBalanceTemperature>,
+    pub auto: Option<WhiteBalanceTemperature>,
+}
+
+// The as shot and automatic white balance as temperature and tint, so the custom sliders can
+// start from either. `None` for images without a color matrix to convert them with.
+#[tauri::command]
+pub async fn get_raw_white_balance(path: String) -> Result<RawWhiteBalanceInfo, String> {
+    let file_bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source).map_err(|e| e.to_string())?;
+    let raw_image = decoder
+        .raw_image(&source, &RawDecodeParams::default(), false)
+        .map_err(|e| e.to_string())?;
+
+    let temperature_for = |white_balance: WhiteBalance| {
+        white_balance_temperature(&raw_image, white_balance)
+            .ok()
+            .map(|(temperature, tint)| WhiteBalanceTemperature { temperature, tint })
+    };
+    Ok(RawWhiteBalanceInfo {
+        as_shot: temperature_for(WhiteBalance::AsShot),
+        auto: temperature_for(WhiteBalance::Auto),
+    })
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct RawLevelsInfo {
+    pub black_levels: Vec<f32>,
+    pub white_levels: Vec<u32>,
+}
+
+// The levels from the metadata, shown next to the override fields.
+#[tauri::command]
+pub async fn get_raw_levels(path: String) -> Result<RawLevelsInfo, String> {
+    let file_bytes = std::fs::read(&path).map_err(|e| e.to_string())?;
+    let source = RawSource::new_from_slice(&file_bytes);
+    let decoder = rawler::get_decoder(&source).map_err(|e| e.to_string())?;
+    let raw_image = decoder
+        .raw_image(&source, &RawDecodeParams::default(), false)
+        .map_err(|e| e.to_string())?;
+    Ok(RawLevelsInfo {
+        black_levels: raw_image.blacklevel.as_vec(),
+        white_levels: raw_image.whitelevel.0.clone(),
+    })
+}
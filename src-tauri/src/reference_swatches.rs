@@ -0,0 +1,154 @@
+use image::{DynamicImage, ImageBuffer, Rgb, RgbImage, Rgba};
+use serde::Serialize;
+use serde_json::Value;
+
+use crate::image_processing::{
+    get_all_adjustments_from_json, get_or_init_gpu_context, process_and_get_dynamic_image_16bit,
+};
+use crate::AppState;
+
+const PATCH_SIZE: u32 = 32;
+const PATCH_COLUMNS: u32 = 6;
+// Only the middle of each patch is measured, so spatial adjustments (clarity, sharpening,
+// vignette) bleeding across patch borders do not skew the averages.
+const SAMPLE_INSET: u32 = 10;
+
+// X-Rite ColorChecker Classic, 8-bit sRGB (D65) values as published by BabelColor.
+pub const COLOR_CHECKER: [(&str, [u8; 3]); 24] = [
+    ("Dark Skin", [115, 82, 68]),
+    ("Light Skin", [194, 150, 130]),
+    ("Blue Sky", [98, 122, 157]),
+    ("Foliage", [87, 108, 67]),
+    ("Blue Flower", [133, 128, 177]),
+    ("Bluish Green", [103, 189, 170]),
+    ("Orange", [214, 126, 44]),
+    ("Purplish Blue", [80, 91, 166]),
+    ("Moderate Red", [193, 90, 99]),
+    ("Purple", [94, 60, 108]),
+    ("Yellow Green", [157, 188, 64]),
+    ("Orange Yellow", [224, 163, 46]),
+    ("Blue", [56, 61, 150]),
+    ("Green", [70, 148, 73]),
+    ("Red", [175, 54, 60]),
+    ("Yellow", [231, 199, 31]),
+    ("Magenta", [187, 86, 149]),
+    ("Cyan", [8, 133, 161]),
+    ("White 9.5", [243, 243, 242]),
+    ("Neutral 8", [200, 200, 200]),
+    ("Neutral 6.5", [160, 160, 160]),
+    ("Neutral 5", [122, 122, 121]),
+    ("Neutral 3.5", [85, 85, 85]),
+    ("Black 2", [52, 52, 52]),
+];
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ReferenceSwatch {
+    pub name: String,
+    pub reference_rgb: [u8; 3],
+    pub output_rgb: [f32; 3],
+    pub reference_lab: [f32; 3],
+    pub output_lab: [f32; 3],
+    pub delta_e: f32,
+}
+
+pub fn srgb_to_linear(c: f32) -> f32 {
+    if c <= 0.04045 {
+        c / 12.92
+    } else {
+        ((c + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+// sRGB (0-255) to CIE L*a*b* with a D65 white point.
+pub fn srgb_to_lab(rgb: [f32; 3]) -> [f32; 3] {
+    let [r, g, b] = rgb.map(|c| srgb_to_linear(c / 255.0));
+    let x = (0.4124 * r + 0.3576 * g + 0.1805 * b) / 0.95047;
+    let y = 0.2126 * r + 0.7152 * g + 0.0722 * b;
+    let z = (0.0193 * r + 0.1192 * g + 0.9505 * b) / 1.08883;
+
+    let f = |t: f32| {
+        if t > 216.0 / 24389.0 {
+            t.cbrt()
+        } else {
+            (24389.0 / 27.0 * t + 16.0) / 116.0
+        }
+    };
+    let (fx, fy, fz) = (f(x), f(y), f(z));
+    [116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz)]
+}
+
+fn build_chart() -> DynamicImage {
+    let rows = (COLOR_CHECKER.len() as u32).div_ceil(PATCH_COLUMNS);
+    let mut chart = RgbImage::new(PATCH_COLUMNS * PATCH_SIZE, rows * PATCH_SIZE);
+    for (i, (_, rgb)) in COLOR_CHECKER.iter().enumerate() {
+        let (px, py) = patch_origin(i);
+        for y in py..py + PATCH_SIZE {
+            for x in px..px + PATCH_SIZE {
+                chart.put_pixel(x, y, Rgb(*rgb));
+            }
+        }
+    }
+    DynamicImage::ImageRgb8(chart)
+}
+
+fn patch_origin(index: usize) -> (u32, u32) {
+    let index = index as u32;
+    ((index % PATCH_COLUMNS) * PATCH_SIZE, (index / PATCH_COLUMNS) * PATCH_SIZE)
+}
+
+fn average_patch(image: &ImageBuffer<Rgba<u16>, Vec<u16>>, index: usize) -> [f32; 3] {
+    let (px, py) = patch_origin(index);
+    let mut sum = [0.0f64; 3];
+    let mut count = 0.0f64;
+    for y in py + SAMPLE_INSET..py + PATCH_SIZE - SAMPLE_INSET {
+        for x in px + SAMPLE_INSET..px + PATCH_SIZE - SAMPLE_INSET {
+            let pixel = image.get_pixel(x, y);
+            for c in 0..3 {
+                sum[c] += pixel[c] as f64;
+            }
+            count += 1.0;
+        }
+    }
+    sum.map(|s| (s / count) as f32)
+}
+
+// Renders a synthetic ColorChecker through the same GPU pipeline and output transform used
+// for previews and exports. The output includes the default tone curve, so the numbers are
+// meant to be compared between builds or profiles rather than read as absolute accuracy.
+#[tauri::command]
+pub fn get_reference_swatches(
+    js_adjustments: Option<Value>,
+    state: tauri::State<AppState>,
+) -> Result<Vec<ReferenceSwatch>, String> {
+    let context = get_or_init_gpu_context(&state)?;
+    let js_adjustments = js_adjustments.unwrap_or_else(|| serde_json::json!({}));
+
+    let chart = build_chart();
+    let adjustments = get_all_adjustments_from_json(&js_adjustments);
+    let processed = process_and_get_dynamic_image_16bit(&context, &chart, adjustments, &[], None)?.to_rgba16();
+
+    Ok(COLOR_CHECKER
+        .iter()
+        .enumerate()
+        .map(|(i, (name, reference_rgb))| {
+            let output_rgb = average_patch(&processed, i).map(|c| c / 257.0);
+            let reference_lab = srgb_to_lab(reference_rgb.map(f32::from));
+            let output_lab = srgb_to_lab(output_rgb);
+            let delta_e = reference_lab
+                .iter()
+                .zip(output_lab.iter())
+                .map(|(a, b)| (a - b).powi(2))
+                .sum::<f32>()
+                .sqrt();
+            ReferenceSwatch {
+                name: name.to_string(),
+                reference_rgb: *reference_rgb,
+                output_rgb,
+                reference_lab,
+                output_lab,
+                delta_e,
+            }
+        })
+        .collect())
+}
@@ -0,0 +1,81 @@
+use image::{imageops::{self, FilterType}, DynamicImage, Rgba32FImage};
+use rayon::prelude::*;
+use serde::{Deserialize, Serialize};
+
+// Filter used to scale exports. Exports are scaled on the GPU in linear light, where `Fast`
+// uses Lanczos. Without a GPU `Fast` is the original thumbnail path, which averages in gamma
+// space and looks soft; the others resample in linear light on the CPU too.
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "camelCase")]
+pub enum ResizeFilter {
+    #[default]
+    Fast,
+    Lanczos,
+    CatmullRom,
+}
+
+pub fn srgb_to_linear(v: f32) -> f32 {
+    if v <= 0.04045 {
+        v / 12.92
+    } else {
+        ((v + 0.055) / 1.055).powf(2.4)
+    }
+}
+
+pub fn linear_to_srgb(v: f32) -> f32 {
+    let v = v.clamp(0.0, 1.0);
+    if v <= 0.0031308 {
+        v * 12.92
+    } else {
+        1.055 * v.powf(1.0 / 2.4) - 0.055
+    }
+}
+
+fn to_linear(image: &DynamicImage) -> Rgba32FImage {
+    let mut linear = image.to_rgba32f();
+    linear.par_chunks_exact_mut(4).for_each(|p| {
+        for c in &mut p[..3] {
+            *c = srgb_to_linear(*c);
+        }
+    });
+    linear
+}
+
+// Scales to exactly `width` x `height`. The bit depth of the source is kept, so 16-bit
+// exports stay 16-bit.
+pub fn resize_image(image: &DynamicImage, width: u32, height: u32, filter: ResizeFilter) -> DynamicImage {
+    let filter_type = match filter {
+        ResizeFilter::Fast => return image.thumbnail_exact(width, height),
+        ResizeFilter::Lanczos => FilterType::Lanczos3,
+        ResizeFilter::CatmullRom => FilterType::CatmullRom,
+    };
+
+    let mut resized = imageops::resize(&to_linear(image), width.max(1), height.max(1), filter_type);
+    // Both filters have negative lobes, so overshoot around edges is clamped here.
+    resized.par_chunks_exact_mut(4).for_each(|p| {
+        for c in &mut p[..3] {
+            *c = linear_to_srgb(*c);
+        }
+        p[3] = p[3].clamp(0.0, 1.0);
+    });
+
+    like_source(resized, image)
+}
+
+// Resized pixels in the color type of `source`, so bit depth and channels are kept. Grayscale
+// stays grayscale, which the GPU pipeline relies on to recognize monochrome images.
+pub fn like_source(resized: Rgba32FImage, source: &DynamicImage) -> DynamicImage {
+    let resized = DynamicImage::ImageRgba32F(resized);
+    match source {
+        DynamicImage::ImageLuma8(_) => DynamicImage::ImageLuma8(resized.to_luma8()),
+        DynamicImage::ImageLumaA8(_) => DynamicImage::ImageLumaA8(resized.to_luma_alpha8()),
+        DynamicImage::ImageRgb8(_) => DynamicImage::ImageRgb8(resized.to_rgb8()),
+        DynamicImage::ImageLuma16(_) => DynamicImage::ImageLuma16(resized.to_luma16()),
+        DynamicImage::ImageLumaA16(_) => DynamicImage::ImageLumaA16(resized.to_luma_alpha16()),
+        DynamicImage::ImageRgb16(_) => DynamicImage::ImageRgb16(resized.to_rgb16()),
+        DynamicImage::ImageRgba16(_) => DynamicImage::ImageRgba16(resized.to_rgba16()),
+        DynamicImage::ImageRgb32F(_) => DynamicImage::ImageRgb32F(resized.to_rgb32f()),
+        DynamicImage::ImageRgba32F(_) => resized,
+        _ => DynamicImage::ImageRgba8(resized.to_rgba8()),
+    }
+}
@@ -0,0 +1,81 @@
+// Separable resampling for downscaling. The first pass filters the rows into an intermediate
+// texture in linear light, the second filters its columns and encodes back to sRGB.
+
+struct ResizeParams {
+    // Source pixels per output pixel along the filtered axis
+    scale: f32,
+    // Kernel radius, 3 for Lanczos3 and 2 for Catmull-Rom
+    support: f32,
+    // 0 for Lanczos3, 1 for Catmull-Rom
+    kernel_type: u32,
+    vertical: u32,
+}
+
+@group(0) @binding(0) var input_texture: texture_2d<f32>;
+@group(0) @binding(1) var output_texture: texture_storage_2d<rgba32float, write>;
+@group(0) @binding(2) var<uniform> params: ResizeParams;
+
+const PI: f32 = 3.14159265;
+
+fn srgb_to_linear(c: vec3<f32>) -> vec3<f32> {
+    let v = max(c, vec3<f32>(0.0));
+    return select(pow((v + 0.055) / 1.055, vec3<f32>(2.4)), v / 12.92, v <= vec3<f32>(0.04045));
+}
+
+// Values above white are kept, previews of raw files carry highlights there.
+fn linear_to_srgb(c: vec3<f32>) -> vec3<f32> {
+    let v = max(c, vec3<f32>(0.0));
+    return select(1.055 * pow(v, vec3<f32>(1.0 / 2.4)) - 0.055, v * 12.92, v <= vec3<f32>(0.0031308));
+}
+
+fn sinc(x: f32) -> f32 {
+    if (abs(x) < 1e-5) { return 1.0; }
+    let px = PI * x;
+    return sin(px) / px;
+}
+
+fn resize_kernel(x: f32) -> f32 {
+    let ax = abs(x);
+    if (params.kernel_type == 0u) {
+        if (ax >= 3.0) { return 0.0; }
+        return sinc(x) * sinc(x / 3.0);
+    }
+    if (ax < 1.0) { return 1.5 * ax * ax * ax - 2.5 * ax * ax + 1.0; }
+    if (ax < 2.0) { return -0.5 * ax * ax * ax + 2.5 * ax * ax - 4.0 * ax + 2.0; }
+    return 0.0;
+}
+
+@compute @workgroup_size(8, 8, 1)
+fn main(@builtin(global_invocation_id) id: vec3<u32>) {
+    let out_dims = textureDimensions(output_texture);
+    if (id.x >= out_dims.x || id.y >= out_dims.y) { return; }
+
+    let in_dims = vec2<i32>(textureDimensions(input_texture));
+    let vertical = params.vertical == 1u;
+    let out_pos = select(id.x, id.y, vertical);
+    let in_len = select(in_dims.x, in_dims.y, vertical);
+
+    // When shrinking the kernel is stretched by the scale, so every source pixel contributes.
+    let center = (f32(out_pos) + 0.5) * params.scale;
+    let filter_scale = max(params.scale, 1.0);
+    let radius = params.support * filter_scale;
+    let first = i32(floor(center - radius));
+    let last = i32(ceil(center + radius));
+
+    var sum = vec4<f32>(0.0);
+    var weight_sum = 0.0;
+    for (var i = first; i <= last; i = i + 1) {
+        let weight = resize_kernel((f32(i) + 0.5 - center) / filter_scale);
+        if (weight == 0.0) { continue; }
+        let c = clamp(i, 0, in_len - 1);
+        let coords = select(vec2<i32>(c, i32(id.y)), vec2<i32>(i32(id.x), c), vertical);
+        var texel = textureLoad(input_texture, coords, 0);
+        if (!vertical) { texel = vec4<f32>(srgb_to_linear(texel.rgb), texel.a); }
+        sum += texel * weight;
+        weight_sum += weight;
+    }
+
+    var result = sum / max(weight_sum, 1e-6);
+    if (vertical) { result = vec4<f32>(linear_to_srgb(result.rgb), clamp(result.a, 0.0, 1.0)); }
+    textureStore(output_texture, id.xy, result);
+}
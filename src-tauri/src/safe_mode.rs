@@ -0,0 +1,158 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+
+use serde::{Deserialize, Serialize};
+use tauri::{AppHandle, Manager};
+
+use crate::file_management::AppSettings;
+
+// Written right before the GPU device is created and removed once it exists. If it is still
+// there on the next launch, the driver took the process down during initialisation.
+const GPU_INIT_MARKER_FILENAME: &str = "gpu_init.marker";
+const GPU_BLACKLIST_FILENAME: &str = "gpu_blacklist.json";
+
+static STATE: Mutex<Option<SafeModeState>> = Mutex::new(None);
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub enum SafeModeReason {
+    Forced,
+    GpuCrash,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, PartialEq, Eq)]
+#[serde(rename_all = "camelCase")]
+pub struct GpuAdapterId {
+    pub name: String,
+    pub vendor: u32,
+    pub device: u32,
+    pub driver: String,
+    pub driver_info: String,
+}
+
+impl From<&wgpu::AdapterInfo> for GpuAdapterId {
+    fn from(info: &wgpu::AdapterInfo) -> Self {
+        Self {
+            name: info.name.clone(),
+            vendor: info.vendor,
+            device: info.device,
+            driver: info.driver.clone(),
+            driver_info: info.driver_info.clone(),
+        }
+    }
+}
+
+#[derive(Serialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct SafeModeStatus {
+    pub active: bool,
+    pub reason: Option<SafeModeReason>,
+    pub blacklisted_adapters: Vec<GpuAdapterId>,
+}
+
+struct SafeModeState {
+    data_dir: PathBuf,
+    reason: Option<SafeModeReason>,
+    blacklist: Vec<GpuAdapterId>,
+}
+
+fn load_blacklist(data_dir: &Path) -> Vec<GpuAdapterId> {
+    fs::read_to_string(data_dir.join(GPU_BLACKLIST_FILENAME))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default()
+}
+
+fn save_blacklist(data_dir: &Path, blacklist: &[GpuAdapterId]) -> Result<(), String> {
+    let json_string = serde_json::to_string_pretty(blacklist).map_err(|e| e.to_string())?;
+    fs::write(data_dir.join(GPU_BLACKLIST_FILENAME), json_string).map_err(|e| e.to_string())
+}
+
+// Runs during setup, before the window is built, so the window itself can already start
+// without transparency effects.
+pub fn init(app_handle: &AppHandle, settings: &AppSettings) {
+    let Ok(data_dir) = app_handle.path().app_data_dir() else {
+        return;
+    };
+    let _ = fs::create_dir_all(&data_dir);
+    let mut blacklist = load_blacklist(&data_dir);
+
+    let marker_path = data_dir.join(GPU_INIT_MARKER_FILENAME);
+    let crashed_adapter = fs::read_to_string(&marker_path)
+        .ok()
+        .map(|content| serde_json::from_str::<GpuAdapterId>(&content).ok());
+    if crashed_adapter.is_some() {
+        let _ = fs::remove_file(&marker_path);
+    }
+    if let Some(Some(adapter)) = &crashed_adapter {
+        eprintln!("GPU initialisation crashed on the last launch ({}); blacklisting adapter.", adapter.name);
+        if !blacklist.contains(adapter) {
+            blacklist.push(adapter.clone());
+            if let Err(e) = save_blacklist(&data_dir, &blacklist) {
+                eprintln!("Failed to save GPU blacklist: {}", e);
+            }
+        }
+    }
+
+    let reason = if settings.safe_mode.unwrap_or(false) {
+        Some(SafeModeReason::Forced)
+    } else if crashed_adapter.is_some() {
+        Some(SafeModeReason::GpuCrash)
+    } else {
+        None
+    };
+
+    *STATE.lock().unwrap() = Some(SafeModeState { data_dir, reason, blacklist });
+}
+
+pub fn is_active() -> bool {
+    STATE.lock().unwrap().as_ref().is_some_and(|s| s.reason.is_some())
+}
+
+// Safe mode and blacklisted adapters render on the software (CPU) adapter instead.
+pub fn should_use_fallback_adapter(info: &wgpu::AdapterInfo) -> bool {
+    let state = STATE.lock().unwrap();
+    let Some(state) = state.as_ref() else {
+        return false;
+    };
+    state.reason.is_some() || state.blacklist.contains(&GpuAdapterId::from(info))
+}
+
+pub fn begin_gpu_init(info: &wgpu::AdapterInfo) {
+    let state = STATE.lock().unwrap();
+    if let Some(state) = state.as_ref() {
+        if let Ok(json_string) = serde_json::to_string(&GpuAdapterId::from(info)) {
+            let _ = fs::write(state.data_dir.join(GPU_INIT_MARKER_FILENAME), json_string);
+        }
+    }
+}
+
+pub fn end_gpu_init() {
+    let state = STATE.lock().unwrap();
+    if let Some(state) = state.as_ref() {
+        let _ = fs::remove_file(state.data_dir.join(GPU_INIT_MARKER_FILENAME));
+    }
+}
+
+#[tauri::command]
+pub fn get_safe_mode_status() -> Result<SafeModeStatus, String> {
+    let state = STATE.lock().unwrap();
+    Ok(match state.as_ref() {
+        Some(state) => SafeModeStatus {
+            active: state.reason.is_some(),
+            reason: state.reason,
+            blacklisted_adapters: state.blacklist.clone(),
+        },
+        None => SafeModeStatus { active: false, reason: None, blacklisted_adapters: Vec::new() },
+    })
+}
+
+// The GPU context is created once per session, so this takes effect on the next launch.
+#[tauri::command]
+pub fn clear_gpu_blacklist() -> Result<(), String> {
+    let mut state = STATE.lock().unwrap();
+    let state = state.as_mut().ok_or("Safe mode is not initialised.")?;
+    state.blacklist.clear();
+    save_blacklist(&state.data_dir, &state.blacklist)
+}
@@ -19,6 +19,17 @@ struct ColorGradeSettings {
     _pad: f32,
 }
 
+struct LevelsChannel {
+    in_black: f32,
+    in_white: f32,
+    gamma: f32,
+    out_black: f32,
+    out_white: f32,
+    _pad1: f32,
+    _pad2: f32,
+    _pad3: f32,
+}
+
 struct GlobalAdjustments {
     exposure: f32,
     contrast: f32,
@@ -32,11 +43,17 @@ struct GlobalAdjustments {
     vibrance: f32,
     
     sharpness: f32,
+    sharpen_radius: f32,
+    sharpen_detail: f32,
+    sharpen_masking: f32,
     luma_noise_reduction: f32,
+    luma_noise_reduction_detail: f32,
     color_noise_reduction: f32,
+    color_noise_reduction_smoothness: f32,
     clarity: f32,
     dehaze: f32,
     structure: f32,
+    texture: f32,
     vignette_amount: f32,
     vignette_midpoint: f32,
     vignette_roundness: f32,
@@ -52,8 +69,6 @@ struct GlobalAdjustments {
     negative_red_balance: f32,
     negative_green_balance: f32,
     negative_blue_balance: f32,
-    _pad_neg1: f32,
-    _pad_neg2: f32,
 
     color_grading_shadows: ColorGradeSettings,
     color_grading_midtones: ColorGradeSettings,
@@ -63,6 +78,26 @@ struct GlobalAdjustments {
     _pad2: f32,
     _pad3: f32,
 
+    split_toning_shadow_hue: f32,
+    split_toning_shadow_saturation: f32,
+    split_toning_highlight_hue: f32,
+    split_toning_highlight_saturation: f32,
+    split_toning_balance: f32,
+    _pad_st1: f32,
+    _pad_st2: f32,
+    _pad_st3: f32,
+
+    defringe_purple_amount: f32,
+    defringe_purple_hue: f32,
+    defringe_purple_range: f32,
+    defringe_green_amount: f32,
+    defringe_green_hue: f32,
+    defringe_green_range: f32,
+    lut_intensity: f32,
+    lut_size: u32,
+
+    levels: array<LevelsChannel, 4>,
+
     hsl: array<HslColor, 8>,
     luma_curve: array<Point, 16>,
     red_curve: array<Point, 16>,
@@ -72,6 +107,24 @@ struct GlobalAdjustments {
     red_curve_count: u32,
     green_curve_count: u32,
     blue_curve_count: u32,
+    luma_curve_lab: u32,
+    _pad_c1: u32,
+    _pad_c2: u32,
+    _pad_c3: u32,
+
+    hue_vs_hue: array<Point, 16>,
+    hue_vs_sat: array<Point, 16>,
+    lum_vs_sat: array<Point, 16>,
+    hue_vs_hue_count: u32,
+    hue_vs_sat_count: u32,
+    lum_vs_sat_count: u32,
+    _pad_hc: u32,
+
+    camera_matrix: array<vec4<f32>, 3>,
+    camera_matrix_enabled: u32,
+    enabled_stages: u32,
+    monochrome: u32,
+    tone_mapper: u32,
 }
 
 struct MaskAdjustments {
@@ -92,11 +145,11 @@ struct MaskAdjustments {
     clarity: f32,
     dehaze: f32,
     structure: f32,
+    texture: f32,
     
-    _pad1: f32,
-    _pad2: f32,
+    enabled_stages: u32,
+    signed_influence: u32,
     _pad3: f32,
-    _pad4: f32,
 
     color_grading_shadows: ColorGradeSettings,
     color_grading_midtones: ColorGradeSettings,
@@ -115,6 +168,10 @@ struct MaskAdjustments {
     red_curve_count: u32,
     green_curve_count: u32,
     blue_curve_count: u32,
+    luma_curve_lab: u32,
+    _pad_c1: u32,
+    _pad_c2: u32,
+    _pad_c3: u32,
 }
 
 struct AllAdjustments {
@@ -123,16 +180,58 @@ struct AllAdjustments {
     mask_count: u32,
     tile_offset_x: u32,
     tile_offset_y: u32,
-    _pad1: u32,
+    image_width: u32,
+    image_height: u32,
+    clipping_overlay: u32,
+    _pad2: u32,
+    _pad3: u32,
 }
 
 @group(0) @binding(0) var input_texture: texture_2d<f32>;
-@group(0) @binding(1) var output_texture: texture_storage_2d<rgba8unorm, write>;
+@group(0) @binding(1) var output_texture: texture_storage_2d<rgba16float, write>;
 @group(0) @binding(2) var<uniform> adjustments: AllAdjustments;
 @group(0) @binding(3) var mask_textures: texture_2d_array<f32>;
+@group(0) @binding(4) var lut_texture: texture_3d<f32>;
 
 const LUMA_COEFF = vec3<f32>(0.2126, 0.7152, 0.0722);
 
+const STAGE_NOISE_REDUCTION: u32 = 1u;
+const STAGE_WHITE_BALANCE: u32 = 2u;
+const STAGE_TONE: u32 = 4u;
+const STAGE_DEHAZE: u32 = 8u;
+const STAGE_SHARPENING: u32 = 16u;
+const STAGE_CLARITY: u32 = 32u;
+const STAGE_SATURATION: u32 = 64u;
+const STAGE_HSL: u32 = 128u;
+const STAGE_COLOR_GRADING: u32 = 256u;
+const STAGE_CURVES: u32 = 512u;
+const STAGE_GRAIN: u32 = 1024u;
+const STAGE_VIGNETTE: u32 = 2048u;
+const STAGE_SPLIT_TONING: u32 = 4096u;
+const STAGE_DEFRINGE: u32 = 8192u;
+const STAGE_LUT: u32 = 16384u;
+const STAGE_LEVELS: u32 = 32768u;
+const STAGE_HUE_CURVES: u32 = 65536u;
+
+// Stages the pipeline is built with, filled in by create_pipeline. Branches of the others are
+// constant false and compiled out.
+const ACTIVE_STAGES: u32 = 131071u;
+
+fn stage_enabled(stages: u32, stage: u32) -> bool {
+    return (ACTIVE_STAGES & stages & stage) != 0u;
+}
+
+// Grayscale images come as a single channel texture, which loads as (r, 0, 0, 1). HDR sources
+// are tone mapped here, before any adjustment, so neighbourhood reads see the same values.
+fn load_input(coords: vec2<i32>) -> vec4<f32> {
+    let texel = textureLoad(input_texture, coords, 0);
+    if (adjustments.global.monochrome == 1u) { return vec4<f32>(texel.rrr, texel.a); }
+    if (adjustments.global.tone_mapper != 0u) {
+        return vec4<f32>(linear_to_srgb(apply_tone_mapper(srgb_to_linear(texel.rgb), adjustments.global.tone_mapper)), texel.a);
+    }
+    return texel;
+}
+
 fn get_luma(c: vec3<f32>) -> f32 {
     return dot(c, LUMA_COEFF);
 }
@@ -341,8 +440,10 @@ fn apply_creative_color(color: vec3<f32>, sat: f32, vib: f32) -> vec3<f32> {
         let luminance_mask = shadow_boost * highlight_protection;
         let final_mask = saturation_mask * luminance_mask;
         if (vib > 0.0) {
+            // Skin sits around orange; boosting it quickly looks sunburnt.
+            let skin_protection = get_hsl_influence(rgb_to_hsv(sat_rgb).x, 25.0, 50.0) * 0.7;
             let strength_multiplier = 2.5;
-            let vibrance_amount = vib * final_mask * strength_multiplier;
+            let vibrance_amount = vib * final_mask * (1.0 - skin_protection) * strength_multiplier;
             sat_rgb = mix(vec3<f32>(luma_for_vib), sat_rgb, 1.0 + vibrance_amount);
         } else {
             let skin_luma_protection = 1.0 - smoothstep(0.3, 0.6, luma_for_vib);
@@ -394,6 +495,59 @@ fn apply_hsl_panel(color: vec3<f32>, hsl_adjustments: array<HslColor, 8>, coords
     return hsv_to_rgb(hsv);
 }
 
+// Point `index` of a hue or luminance curve. Indices past the ends wrap around for hue curves,
+// shifted by a full turn, and repeat the end points otherwise.
+fn color_curve_point(points: array<Point, 16>, count: u32, index: i32, periodic: bool) -> Point {
+    var local_points = points;
+    let n = i32(count);
+    if (!periodic) { return local_points[clamp(index, 0, n - 1)]; }
+    let wrapped = ((index % n) + n) % n;
+    let p = local_points[wrapped];
+    return Point(p.x + 255.0 * f32((index - wrapped) / n), p.y, 0.0, 0.0);
+}
+
+// Catmull-Rom spline through the curve points at `x` in 0..1. Returns 0.5, the neutral value,
+// for a curve without points.
+fn sample_color_curve(x: f32, points: array<Point, 16>, count: u32, periodic: bool) -> f32 {
+    if (count == 0u) { return 0.5; }
+    var local_points = points;
+    let n = i32(count);
+    let px = x * 255.0;
+    // Last point at or left of px. Left of the first point this is -1, which for hue curves
+    // is the last point one turn earlier.
+    var i = -1;
+    for (var k = 0; k < 16; k = k + 1) {
+        if (k >= n) { break; }
+        if (local_points[k].x <= px) { i = k; }
+    }
+    if (!periodic) {
+        if (i < 0) { return local_points[0].y / 255.0; }
+        if (i == n - 1) { return local_points[n - 1].y / 255.0; }
+    }
+    let p0 = color_curve_point(points, count, i - 1, periodic);
+    let p1 = color_curve_point(points, count, i, periodic);
+    let p2 = color_curve_point(points, count, i + 1, periodic);
+    let p3 = color_curve_point(points, count, i + 2, periodic);
+    let m1 = (p2.y - p0.y) / max(0.001, p2.x - p0.x);
+    let m2 = (p3.y - p1.y) / max(0.001, p3.x - p1.x);
+    return clamp(interpolate_cubic_hermite(px, p1, p2, m1, m2) / 255.0, 0.0, 1.0);
+}
+
+// Hue vs hue shifts by up to half a turn either way, hue vs sat and lum vs sat scale
+// saturation between 0 and 2x. Every curve is looked up with the incoming color.
+fn apply_hue_curves(color: vec3<f32>, hue_vs_hue: array<Point, 16>, hue_vs_hue_count: u32, hue_vs_sat: array<Point, 16>, hue_vs_sat_count: u32, lum_vs_sat: array<Point, 16>, lum_vs_sat_count: u32) -> vec3<f32> {
+    if (hue_vs_hue_count == 0u && hue_vs_sat_count == 0u && lum_vs_sat_count == 0u) { return color; }
+    var hsv = rgb_to_hsv(max(color, vec3<f32>(0.0)));
+    if (hsv.y < 0.001) { return color; }
+    let hue = hsv.x / 360.0;
+    let lum = clamp(linear_to_srgb(vec3<f32>(get_luma(max(color, vec3<f32>(0.0))))).x, 0.0, 1.0);
+    let hue_shift = (sample_color_curve(hue, hue_vs_hue, hue_vs_hue_count, true) - 0.5) * 360.0;
+    let sat_scale = 2.0 * sample_color_curve(hue, hue_vs_sat, hue_vs_sat_count, true) * 2.0 * sample_color_curve(lum, lum_vs_sat, lum_vs_sat_count, false);
+    hsv.x = (hsv.x + hue_shift + 360.0) % 360.0;
+    hsv.y = clamp(hsv.y * sat_scale, 0.0, 1.0);
+    return hsv_to_rgb(hsv);
+}
+
 fn apply_color_grading(color: vec3<f32>, shadows: ColorGradeSettings, midtones: ColorGradeSettings, highlights: ColorGradeSettings, blending: f32, balance: f32) -> vec3<f32> {
     let luma = get_luma(max(vec3(0.0), color));
     let base_shadow_crossover = 0.1;
@@ -422,6 +576,28 @@ fn apply_color_grading(color: vec3<f32>, shadows: ColorGradeSettings, midtones:
     return graded_color;
 }
 
+// Tints the shadows and highlights of the display referred image with one hue each. Balance
+// moves the split between them, the tint fades out towards pure black and white.
+fn apply_split_toning(color: vec3<f32>, shadow_hue: f32, shadow_sat: f32, highlight_hue: f32, highlight_sat: f32, balance: f32) -> vec3<f32> {
+    if (shadow_sat <= 0.001 && highlight_sat <= 0.001) { return color; }
+    let luma = clamp(get_luma(color), 0.0, 1.0);
+    let split = clamp(0.5 - balance * 0.4, 0.1, 0.9);
+    let highlight_mask = smoothstep(split - 0.3, split + 0.3, luma);
+    let shadow_mask = 1.0 - highlight_mask;
+    let range_fade = clamp(luma * 4.0, 0.0, 1.0) * clamp((1.0 - luma) * 4.0, 0.0, 1.0);
+    let strength = 0.5;
+    var toned = color;
+    if (shadow_sat > 0.001) {
+        let tint = hsv_to_rgb(vec3<f32>(shadow_hue, 1.0, 1.0));
+        toned += (tint - get_luma(tint)) * shadow_sat * shadow_mask * range_fade * strength;
+    }
+    if (highlight_sat > 0.001) {
+        let tint = hsv_to_rgb(vec3<f32>(highlight_hue, 1.0, 1.0));
+        toned += (tint - get_luma(tint)) * highlight_sat * highlight_mask * range_fade * strength;
+    }
+    return toned;
+}
+
 fn apply_local_contrast(processed_color: vec3<f32>, coords_i: vec2<i32>, radius: i32, amount: f32) -> vec3<f32> {
     if (amount == 0.0) { return processed_color; }
     let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
@@ -434,7 +610,7 @@ fn apply_local_contrast(processed_color: vec3<f32>, coords_i: vec2<i32>, radius:
         for (var x = -radius; x <= radius; x += 1) {
             let offset = vec2<i32>(x, y);
             let sample_coords = clamp(coords_i + offset, vec2<i32>(0), max_coords);
-            let sample_linear = srgb_to_linear(textureLoad(input_texture, sample_coords, 0).rgb);
+            let sample_linear = srgb_to_linear(load_input(sample_coords).rgb);
             let sample_luma = get_luma(sample_linear);
             let spatial_dist_sq = f32(x * x + y * y);
             let luma_dist = sample_luma - original_luma;
@@ -455,6 +631,103 @@ fn apply_local_contrast(processed_color: vec3<f32>, coords_i: vec2<i32>, radius:
     return processed_color + enhanced_detail;
 }
 
+// Capture sharpening as an unsharp mask of the luminance. Low detail compresses the largest
+// differences, which are the edges that would halo; masking limits sharpening to edges so
+// noise in flat areas is left alone. Radius and the mask come from the global settings.
+fn apply_sharpening(color: vec3<f32>, coords_i: vec2<i32>, amount: f32, radius: f32, detail: f32, masking: f32) -> vec3<f32> {
+    if (amount <= 0.0) { return color; }
+    let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
+    let sigma = clamp(radius, 0.5, 3.0);
+    let reach = i32(ceil(sigma * 2.0));
+    let center_luma = get_luma(srgb_to_linear(load_input(coords_i).rgb));
+    var blur_sum = 0.0;
+    var blur_weight = 0.0;
+    for (var y = -reach; y <= reach; y += 1) {
+        for (var x = -reach; x <= reach; x += 1) {
+            let sample_coords = clamp(coords_i + vec2<i32>(x, y), vec2<i32>(0), max_coords);
+            let weight = exp(-f32(x * x + y * y) / (2.0 * sigma * sigma));
+            blur_sum += get_luma(srgb_to_linear(load_input(sample_coords).rgb)) * weight;
+            blur_weight += weight;
+        }
+    }
+    let high_pass = center_luma - blur_sum / blur_weight;
+    let damping = (1.0 - clamp(detail, 0.0, 1.0)) * 20.0;
+    let detail_signal = high_pass / (1.0 + abs(high_pass) * damping);
+
+    var edge_mask = 1.0;
+    if (masking > 0.0) {
+        let luma_at = vec4<f32>(
+            get_luma(srgb_to_linear(load_input(clamp(coords_i + vec2<i32>(1, 0), vec2<i32>(0), max_coords)).rgb)),
+            get_luma(srgb_to_linear(load_input(clamp(coords_i - vec2<i32>(1, 0), vec2<i32>(0), max_coords)).rgb)),
+            get_luma(srgb_to_linear(load_input(clamp(coords_i + vec2<i32>(0, 1), vec2<i32>(0), max_coords)).rgb)),
+            get_luma(srgb_to_linear(load_input(clamp(coords_i - vec2<i32>(0, 1), vec2<i32>(0), max_coords)).rgb))
+        );
+        let gradient = length(vec2<f32>(luma_at.x - luma_at.y, luma_at.z - luma_at.w));
+        let threshold = masking * 0.1;
+        edge_mask = smoothstep(threshold * 0.5, threshold, gradient);
+    }
+    return color + vec3<f32>(detail_signal * amount * edge_mask);
+}
+
+// Boosts or smooths fine texture like pores and fabric without touching edges or larger
+// shapes. The band between a 1 and a 4 pixel blur is isolated on the luminance, so colors
+// are not sharpened; large luminance steps are excluded from the blurs to keep edges.
+fn apply_texture(processed_color: vec3<f32>, coords_i: vec2<i32>, amount: f32) -> vec3<f32> {
+    if (amount == 0.0) { return processed_color; }
+    let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
+    let center_luma = get_luma(srgb_to_linear(load_input(coords_i).rgb));
+    let range_sigma = 0.1;
+    var fine_sum = 0.0;
+    var fine_weight = 0.0;
+    var coarse_sum = 0.0;
+    var coarse_weight = 0.0;
+    for (var y = -4; y <= 4; y += 1) {
+        for (var x = -4; x <= 4; x += 1) {
+            let sample_coords = clamp(coords_i + vec2<i32>(x, y), vec2<i32>(0), max_coords);
+            let sample_luma = get_luma(srgb_to_linear(load_input(sample_coords).rgb));
+            let luma_dist = sample_luma - center_luma;
+            let range_weight = exp(-(luma_dist * luma_dist) / (2.0 * range_sigma * range_sigma));
+            let dist_sq = f32(x * x + y * y);
+            let w_fine = exp(-dist_sq / 2.0) * range_weight;
+            let w_coarse = exp(-dist_sq / 32.0) * range_weight;
+            fine_sum += sample_luma * w_fine;
+            fine_weight += w_fine;
+            coarse_sum += sample_luma * w_coarse;
+            coarse_weight += w_coarse;
+        }
+    }
+    let band = fine_sum / max(fine_weight, 1e-6) - coarse_sum / max(coarse_weight, 1e-6);
+    // At -100 the band is removed completely, positive values boost it further.
+    let strength = select(1.5, 1.0, amount < 0.0);
+    return processed_color + vec3<f32>(band * amount * strength);
+}
+
+// Lateral and longitudinal chromatic aberration leave purple and green halos along high
+// contrast edges. Pixels of those hues are desaturated, but only where the luminance around
+// them changes sharply, so purple or green objects keep their color.
+fn apply_defringe(color: vec3<f32>, coords_i: vec2<i32>, purple_amount: f32, purple_hue: f32, purple_range: f32, green_amount: f32, green_hue: f32, green_range: f32) -> vec3<f32> {
+    if (purple_amount <= 0.0 && green_amount <= 0.0) { return color; }
+    let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
+    var min_luma = 1e6;
+    var max_luma = 0.0;
+    for (var y = -2; y <= 2; y = y + 2) {
+        for (var x = -2; x <= 2; x = x + 2) {
+            let sample_coords = clamp(coords_i + vec2<i32>(x, y), vec2<i32>(0), max_coords);
+            let luma = get_luma(srgb_to_linear(load_input(sample_coords).rgb));
+            min_luma = min(min_luma, luma);
+            max_luma = max(max_luma, luma);
+        }
+    }
+    let edge = smoothstep(0.2, 0.6, (max_luma - min_luma) / (max_luma + 0.05));
+    if (edge <= 0.0) { return color; }
+
+    let hue = rgb_to_hsv(color).x;
+    let purple = get_hsl_influence(hue, purple_hue, purple_range) * purple_amount;
+    let green = get_hsl_influence(hue, green_hue, green_range) * green_amount;
+    let desaturation = clamp(max(purple, green) * edge, 0.0, 1.0);
+    return mix(color, vec3<f32>(get_luma(color)), desaturation);
+}
+
 fn apply_dehaze(color: vec3<f32>, amount: f32) -> vec3<f32> {
     if (amount == 0.0) { return color; }
     let atmospheric_light = vec3<f32>(0.95, 0.97, 1.0);
@@ -473,36 +746,218 @@ fn apply_dehaze(color: vec3<f32>, amount: f32) -> vec3<f32> {
     }
 }
 
-fn apply_noise_reduction(color: vec3<f32>, coords_i: vec2<i32>, luma_amount: f32, color_amount: f32) -> vec3<f32> {
-    if (luma_amount <= 0.0 && color_amount <= 0.0) { return color; }
-    var accum_color = vec3<f32>(0.0);
+fn rgb_to_ycbcr(c: vec3<f32>) -> vec3<f32> {
+    let y = get_luma(c);
+    return vec3<f32>(y, c.b - y, c.r - y);
+}
+
+fn ycbcr_to_rgb(c: vec3<f32>) -> vec3<f32> {
+    let r = c.x + c.z;
+    let b = c.x + c.y;
+    let g = (c.x - LUMA_COEFF.r * r - LUMA_COEFF.b * b) / LUMA_COEFF.g;
+    return vec3<f32>(r, g, b);
+}
+
+// Averages only Cb and Cr over a wide, sparse neighbourhood, since color blotches are much
+// larger than luminance grain. Smoothness spreads the taps further apart. Samples across a
+// luminance edge get little weight, so colors don't bleed into neighbouring objects.
+fn apply_chroma_noise_reduction(color: vec3<f32>, coords_i: vec2<i32>, amount: f32, smoothness: f32) -> vec3<f32> {
+    if (amount <= 0.0) { return color; }
+    let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
+    let center = rgb_to_ycbcr(srgb_to_linear(load_input(coords_i).rgb));
+    let spacing = 1 + i32(round(clamp(smoothness, 0.0, 1.0) * 3.0));
+    let chroma_sigma = amount * 0.15 + 0.001;
+    let luma_sigma = 0.1 * (center.x + 0.05);
+    var chroma_sum = vec2<f32>(0.0);
     var total_weight = 0.0;
-    let center_luma = get_luma(color);
+    for (var y = -2; y <= 2; y = y + 1) {
+        for (var x = -2; x <= 2; x = x + 1) {
+            let sample_coords = clamp(coords_i + vec2<i32>(x, y) * spacing, vec2<i32>(0), max_coords);
+            let neighbour = rgb_to_ycbcr(srgb_to_linear(load_input(sample_coords).rgb));
+            let chroma_diff = distance(neighbour.yz, center.yz);
+            let luma_diff = neighbour.x - center.x;
+            let weight = exp(-f32(x * x + y * y) / 8.0)
+                * exp(-(chroma_diff * chroma_diff) / (2.0 * chroma_sigma * chroma_sigma))
+                * exp(-(luma_diff * luma_diff) / (2.0 * luma_sigma * luma_sigma));
+            chroma_sum += neighbour.yz * weight;
+            total_weight += weight;
+        }
+    }
+    let ycbcr = rgb_to_ycbcr(color);
+    return ycbcr_to_rgb(vec3<f32>(ycbcr.x, ycbcr.yz + chroma_sum / total_weight - center.yz));
+}
+
+// Edge preserving blur of the luminance only, so colors and their edges stay put. Detail
+// narrows which luminance differences count as noise, keeping fine texture at the cost of
+// leaving more grain.
+fn apply_luma_noise_reduction(color: vec3<f32>, coords_i: vec2<i32>, amount: f32, detail: f32) -> vec3<f32> {
+    if (amount <= 0.0) { return color; }
     let max_coords = vec2<i32>(textureDimensions(input_texture) - 1u);
-    for (var y = -1; y <= 1; y = y + 1) {
-        for (var x = -1; x <= 1; x = x + 1) {
-            let offset = vec2<i32>(x, y);
-            let sample_coords = clamp(coords_i + offset, vec2<i32>(0), max_coords);
-            let sample_color_linear = srgb_to_linear(textureLoad(input_texture, sample_coords, 0).rgb);
-            var luma_weight = 1.0;
-            if (luma_amount > 0.0) { let luma_diff = abs(get_luma(sample_color_linear) - center_luma); luma_weight = 1.0 - smoothstep(0.0, 0.1, luma_diff / luma_amount); }
-            var color_weight = 1.0;
-            if (color_amount > 0.0) { let color_diff = distance(sample_color_linear, color); color_weight = 1.0 - smoothstep(0.0, 0.2, color_diff / color_amount); }
-            let weight = luma_weight * color_weight;
-            accum_color += sample_color_linear * weight;
+    let center_luma = get_luma(srgb_to_linear(load_input(coords_i).rgb));
+    let range_sigma = amount * 0.08 * (1.0 - 0.8 * clamp(detail, 0.0, 1.0)) + 0.001;
+    let spatial_sigma = 1.0 + amount;
+    var luma_sum = 0.0;
+    var total_weight = 0.0;
+    for (var y = -2; y <= 2; y = y + 1) {
+        for (var x = -2; x <= 2; x = x + 1) {
+            let sample_coords = clamp(coords_i + vec2<i32>(x, y), vec2<i32>(0), max_coords);
+            let sample_luma = get_luma(srgb_to_linear(load_input(sample_coords).rgb));
+            let luma_diff = sample_luma - center_luma;
+            let weight = exp(-f32(x * x + y * y) / (2.0 * spatial_sigma * spatial_sigma))
+                * exp(-(luma_diff * luma_diff) / (2.0 * range_sigma * range_sigma));
+            luma_sum += sample_luma * weight;
             total_weight += weight;
         }
     }
-    if (total_weight > 0.0) { return accum_color / total_weight; }
-    return color;
+    return color + vec3<f32>(luma_sum / total_weight - center_luma);
+}
+
+// Trilinear lookup in a .cube LUT, which expects display referred sRGB in 0 to 1. Loads are
+// interpolated by hand because the table is a 32-bit float texture, which can't be filtered.
+fn apply_lut(color: vec3<f32>, size: u32, intensity: f32) -> vec3<f32> {
+    if (size < 2u || intensity <= 0.0) { return color; }
+    let position = clamp(color, vec3<f32>(0.0), vec3<f32>(1.0)) * f32(size - 1u);
+    let base = min(vec3<u32>(floor(position)), vec3<u32>(size - 2u));
+    let t = position - vec3<f32>(base);
+    let b = vec3<i32>(base);
+    let c000 = textureLoad(lut_texture, b, 0).rgb;
+    let c100 = textureLoad(lut_texture, b + vec3<i32>(1, 0, 0), 0).rgb;
+    let c010 = textureLoad(lut_texture, b + vec3<i32>(0, 1, 0), 0).rgb;
+    let c110 = textureLoad(lut_texture, b + vec3<i32>(1, 1, 0), 0).rgb;
+    let c001 = textureLoad(lut_texture, b + vec3<i32>(0, 0, 1), 0).rgb;
+    let c101 = textureLoad(lut_texture, b + vec3<i32>(1, 0, 1), 0).rgb;
+    let c011 = textureLoad(lut_texture, b + vec3<i32>(0, 1, 1), 0).rgb;
+    let c111 = textureLoad(lut_texture, b + vec3<i32>(1, 1, 1), 0).rgb;
+    let c00 = mix(c000, c100, t.x);
+    let c10 = mix(c010, c110, t.x);
+    let c01 = mix(c001, c101, t.x);
+    let c11 = mix(c011, c111, t.x);
+    let looked_up = mix(mix(c00, c10, t.y), mix(c01, c11, t.y), t.z);
+    return mix(color, looked_up, clamp(intensity, 0.0, 1.0));
 }
 
 fn aces_fitted(c: vec3<f32>) -> vec3<f32> {
     return c; // FIXME: Maybe with a setting? Most people probably don't like this hardcoded ACES, so removed temporarly.
 }
 
-fn apply_all_curves(color: vec3<f32>, luma_curve: array<Point, 16>, luma_curve_count: u32, red_curve: array<Point, 16>, red_curve_count: u32, green_curve: array<Point, 16>, green_curve_count: u32, blue_curve: array<Point, 16>, blue_curve_count: u32) -> vec3<f32> {
+fn tonemap_reinhard(c: vec3<f32>) -> vec3<f32> {
+    // On luminance, so bright colors keep their hue instead of washing out per channel.
+    return c / (1.0 + get_luma(c));
+}
+
+fn hable_curve(x: vec3<f32>) -> vec3<f32> {
+    let a = 0.15;
+    let b = 0.50;
+    let c = 0.10;
+    let d = 0.20;
+    let e = 0.02;
+    let f = 0.30;
+    return ((x * (a * x + c * b) + d * e) / (x * (a * x + b) + d * f)) - e / f;
+}
+
+fn tonemap_filmic(c: vec3<f32>) -> vec3<f32> {
+    let white = 11.2;
+    return hable_curve(c * 2.0) / hable_curve(vec3<f32>(white));
+}
+
+// AgX with the polynomial fit of its default contrast look. Highlights desaturate towards white
+// as they would on film instead of skewing to the primaries.
+fn tonemap_agx(c: vec3<f32>) -> vec3<f32> {
+    let inset = mat3x3<f32>(
+        0.842479062253094, 0.0423282422610123, 0.0423756549057051,
+        0.0784335999999992, 0.878468636469772, 0.0784336,
+        0.0792237451477643, 0.0791661274605434, 0.879142973793104
+    );
+    let outset = mat3x3<f32>(
+        1.19687900512017, -0.0528968517574562, -0.0529716355144438,
+        -0.0980208811401368, 1.15190312990417, -0.0980434501171241,
+        -0.0990297440797205, -0.0989611768448433, 1.15107367264116
+    );
+    let min_ev = -12.47393;
+    let max_ev = 4.026069;
+
+    var v = inset * max(c, vec3<f32>(1e-10));
+    v = (clamp(log2(v), vec3<f32>(min_ev), vec3<f32>(max_ev)) - min_ev) / (max_ev - min_ev);
+    let v2 = v * v;
+    let v4 = v2 * v2;
+    v = 15.5 * v4 * v2 - 40.14 * v4 * v + 31.96 * v4 - 6.868 * v2 * v + 0.4298 * v2 + 0.1191 * v - 0.00232;
+    return pow(max(outset * v, vec3<f32>(0.0)), vec3<f32>(2.2));
+}
+
+// Maps HDR sources into the display range, ids as in ToneMapper.
+fn apply_tone_mapper(c: vec3<f32>, tone_mapper: u32) -> vec3<f32> {
+    if (tone_mapper == 1u) { return tonemap_reinhard(c); }
+    if (tone_mapper == 2u) { return tonemap_filmic(c); }
+    if (tone_mapper == 3u) { return tonemap_agx(c); }
+    return c;
+}
+
+// Photoshop style levels. A zeroed channel, as in the defaults of an empty adjustment, has no
+// input range and is left alone.
+fn apply_levels_channel(value: vec3<f32>, l: LevelsChannel) -> vec3<f32> {
+    if (l.in_white <= l.in_black) { return value; }
+    let normalized = clamp((value - l.in_black) / (l.in_white - l.in_black), vec3<f32>(0.0), vec3<f32>(1.0));
+    let corrected = pow(normalized, vec3<f32>(1.0 / max(l.gamma, 0.01)));
+    return l.out_black + corrected * (l.out_white - l.out_black);
+}
+
+fn apply_levels(color: vec3<f32>, levels: array<LevelsChannel, 4>) -> vec3<f32> {
+    let master = apply_levels_channel(color, levels[0]);
+    return vec3<f32>(
+        apply_levels_channel(master, levels[1]).r,
+        apply_levels_channel(master, levels[2]).g,
+        apply_levels_channel(master, levels[3]).b
+    );
+}
+
+// CIELAB with a D65 white, from and to sRGB.
+fn lab_f(t: f32) -> f32 {
+    if (t > 0.008856) { return pow(t, 1.0 / 3.0); }
+    return 7.787 * t + 16.0 / 116.0;
+}
+
+fn lab_f_inv(t: f32) -> f32 {
+    if (t > 0.206893) { return t * t * t; }
+    return (t - 16.0 / 116.0) / 7.787;
+}
+
+fn srgb_to_lab(c: vec3<f32>) -> vec3<f32> {
+    let rgb = srgb_to_linear(clamp(c, vec3<f32>(0.0), vec3<f32>(1.0)));
+    let x = dot(rgb, vec3<f32>(0.4124, 0.3576, 0.1805)) / 0.95047;
+    let y = dot(rgb, vec3<f32>(0.2126, 0.7152, 0.0722));
+    let z = dot(rgb, vec3<f32>(0.0193, 0.1192, 0.9505)) / 1.08883;
+    let fx = lab_f(x);
+    let fy = lab_f(y);
+    let fz = lab_f(z);
+    return vec3<f32>(116.0 * fy - 16.0, 500.0 * (fx - fy), 200.0 * (fy - fz));
+}
+
+fn lab_to_srgb(lab: vec3<f32>) -> vec3<f32> {
+    let fy = (lab.x + 16.0) / 116.0;
+    let x = lab_f_inv(fy + lab.y / 500.0) * 0.95047;
+    let y = lab_f_inv(fy);
+    let z = lab_f_inv(fy - lab.z / 200.0) * 1.08883;
+    let rgb = vec3<f32>(
+        dot(vec3<f32>(x, y, z), vec3<f32>(3.2406, -1.5372, -0.4986)),
+        dot(vec3<f32>(x, y, z), vec3<f32>(-0.9689, 1.8758, 0.0415)),
+        dot(vec3<f32>(x, y, z), vec3<f32>(0.0557, -0.2040, 1.0570))
+    );
+    return linear_to_srgb(rgb);
+}
+
+fn apply_all_curves(color: vec3<f32>, luma_curve: array<Point, 16>, luma_curve_count: u32, red_curve: array<Point, 16>, red_curve_count: u32, green_curve: array<Point, 16>, green_curve_count: u32, blue_curve: array<Point, 16>, blue_curve_count: u32, luma_curve_lab: u32) -> vec3<f32> {
     let rgb_curves_are_active = red_curve_count > 2u || green_curve_count > 2u || blue_curve_count > 2u;
+    if (luma_curve_lab == 1u) {
+        // The luma curve maps L from 0..100 as if it were a 0..1 channel value.
+        var graded = color;
+        if (rgb_curves_are_active) {
+            graded = vec3<f32>(apply_curve(color.r, red_curve, red_curve_count), apply_curve(color.g, green_curve, green_curve_count), apply_curve(color.b, blue_curve, blue_curve_count));
+        }
+        if (luma_curve_count < 2u) { return graded; }
+        let lab = srgb_to_lab(graded);
+        let lightness = apply_curve(lab.x / 100.0, luma_curve, luma_curve_count) * 100.0;
+        return lab_to_srgb(vec3<f32>(lightness, lab.y, lab.z));
+    }
     if (rgb_curves_are_active) {
         let color_graded = vec3<f32>(apply_curve(color.r, red_curve, red_curve_count), apply_curve(color.g, green_curve, green_curve_count), apply_curve(color.b, blue_curve, blue_curve_count));
         let luma_initial = get_luma(color);
@@ -519,32 +974,84 @@ fn apply_all_curves(color: vec3<f32>, luma_curve: array<Point, 16>, luma_curve_c
 }
 
 fn apply_all_adjustments(initial_rgb: vec3<f32>, adj: GlobalAdjustments, coords_i: vec2<i32>) -> vec3<f32> {
-    var processed_rgb = apply_noise_reduction(initial_rgb, coords_i, adj.luma_noise_reduction, adj.color_noise_reduction);
-    processed_rgb = apply_white_balance(processed_rgb, adj.temperature, adj.tint);
-    processed_rgb = processed_rgb * pow(2.0, adj.exposure);
-    processed_rgb = apply_tonal_adjustments(processed_rgb, adj.contrast, adj.highlights, adj.shadows, adj.whites, adj.blacks);
-    processed_rgb = apply_dehaze(processed_rgb, adj.dehaze);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 2, adj.sharpness);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 8, adj.clarity);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 20, adj.structure);
-    processed_rgb = apply_creative_color(processed_rgb, adj.saturation, adj.vibrance);
-    processed_rgb = apply_hsl_panel(processed_rgb, adj.hsl, coords_i);
-    processed_rgb = apply_color_grading(processed_rgb, adj.color_grading_shadows, adj.color_grading_midtones, adj.color_grading_highlights, adj.color_grading_blending, adj.color_grading_balance);
+    let stages = adj.enabled_stages;
+    var processed_rgb = initial_rgb;
+    if (stage_enabled(stages, STAGE_NOISE_REDUCTION)) {
+        processed_rgb = apply_chroma_noise_reduction(processed_rgb, coords_i, adj.color_noise_reduction, adjustments.global.color_noise_reduction_smoothness);
+        processed_rgb = apply_luma_noise_reduction(processed_rgb, coords_i, adj.luma_noise_reduction, adjustments.global.luma_noise_reduction_detail);
+    }
+    if (stage_enabled(stages, STAGE_WHITE_BALANCE) && adjustments.global.monochrome == 0u) {
+        processed_rgb = apply_white_balance(processed_rgb, adj.temperature, adj.tint);
+    }
+    if (stage_enabled(stages, STAGE_DEFRINGE)) {
+        processed_rgb = apply_defringe(processed_rgb, coords_i, adj.defringe_purple_amount, adj.defringe_purple_hue, adj.defringe_purple_range, adj.defringe_green_amount, adj.defringe_green_hue, adj.defringe_green_range);
+    }
+    if (stage_enabled(stages, STAGE_TONE)) {
+        processed_rgb = processed_rgb * pow(2.0, adj.exposure);
+        processed_rgb = apply_tonal_adjustments(processed_rgb, adj.contrast, adj.highlights, adj.shadows, adj.whites, adj.blacks);
+    }
+    if (stage_enabled(stages, STAGE_DEHAZE)) {
+        processed_rgb = apply_dehaze(processed_rgb, adj.dehaze);
+    }
+    if (stage_enabled(stages, STAGE_SHARPENING)) {
+        let g = adjustments.global;
+        processed_rgb = apply_sharpening(processed_rgb, coords_i, adj.sharpness, g.sharpen_radius, g.sharpen_detail, g.sharpen_masking);
+    }
+    if (stage_enabled(stages, STAGE_CLARITY)) {
+        processed_rgb = apply_texture(processed_rgb, coords_i, adj.texture);
+        processed_rgb = apply_local_contrast(processed_rgb, coords_i, 8, adj.clarity);
+        processed_rgb = apply_local_contrast(processed_rgb, coords_i, 20, adj.structure);
+    }
+    if (stage_enabled(stages, STAGE_SATURATION)) {
+        processed_rgb = apply_creative_color(processed_rgb, adj.saturation, adj.vibrance);
+    }
+    if (stage_enabled(stages, STAGE_HSL)) {
+        processed_rgb = apply_hsl_panel(processed_rgb, adj.hsl, coords_i);
+    }
+    if (stage_enabled(stages, STAGE_HUE_CURVES)) {
+        processed_rgb = apply_hue_curves(processed_rgb, adj.hue_vs_hue, adj.hue_vs_hue_count, adj.hue_vs_sat, adj.hue_vs_sat_count, adj.lum_vs_sat, adj.lum_vs_sat_count);
+    }
+    if (stage_enabled(stages, STAGE_COLOR_GRADING)) {
+        processed_rgb = apply_color_grading(processed_rgb, adj.color_grading_shadows, adj.color_grading_midtones, adj.color_grading_highlights, adj.color_grading_blending, adj.color_grading_balance);
+    }
     return processed_rgb;
 }
 
 fn apply_all_mask_adjustments(initial_rgb: vec3<f32>, adj: MaskAdjustments, coords_i: vec2<i32>) -> vec3<f32> {
-    var processed_rgb = apply_noise_reduction(initial_rgb, coords_i, adj.luma_noise_reduction, adj.color_noise_reduction);
-    processed_rgb = apply_white_balance(processed_rgb, adj.temperature, adj.tint);
-    processed_rgb = processed_rgb * pow(2.0, adj.exposure);
-    processed_rgb = apply_tonal_adjustments(processed_rgb, adj.contrast, adj.highlights, adj.shadows, adj.whites, adj.blacks);
-    processed_rgb = apply_dehaze(processed_rgb, adj.dehaze);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 2, adj.sharpness);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 8, adj.clarity);
-    processed_rgb = apply_local_contrast(processed_rgb, coords_i, 20, adj.structure);
-    processed_rgb = apply_creative_color(processed_rgb, adj.saturation, adj.vibrance);
-    processed_rgb = apply_hsl_panel(processed_rgb, adj.hsl, coords_i);
-    processed_rgb = apply_color_grading(processed_rgb, adj.color_grading_shadows, adj.color_grading_midtones, adj.color_grading_highlights, adj.color_grading_blending, adj.color_grading_balance);
+    let stages = adj.enabled_stages;
+    var processed_rgb = initial_rgb;
+    if (stage_enabled(stages, STAGE_NOISE_REDUCTION)) {
+        processed_rgb = apply_chroma_noise_reduction(processed_rgb, coords_i, adj.color_noise_reduction, adjustments.global.color_noise_reduction_smoothness);
+        processed_rgb = apply_luma_noise_reduction(processed_rgb, coords_i, adj.luma_noise_reduction, adjustments.global.luma_noise_reduction_detail);
+    }
+    if (stage_enabled(stages, STAGE_WHITE_BALANCE) && adjustments.global.monochrome == 0u) {
+        processed_rgb = apply_white_balance(processed_rgb, adj.temperature, adj.tint);
+    }
+    if (stage_enabled(stages, STAGE_TONE)) {
+        processed_rgb = processed_rgb * pow(2.0, adj.exposure);
+        processed_rgb = apply_tonal_adjustments(processed_rgb, adj.contrast, adj.highlights, adj.shadows, adj.whites, adj.blacks);
+    }
+    if (stage_enabled(stages, STAGE_DEHAZE)) {
+        processed_rgb = apply_dehaze(processed_rgb, adj.dehaze);
+    }
+    if (stage_enabled(stages, STAGE_SHARPENING)) {
+        let g = adjustments.global;
+        processed_rgb = apply_sharpening(processed_rgb, coords_i, adj.sharpness, g.sharpen_radius, g.sharpen_detail, g.sharpen_masking);
+    }
+    if (stage_enabled(stages, STAGE_CLARITY)) {
+        processed_rgb = apply_texture(processed_rgb, coords_i, adj.texture);
+        processed_rgb = apply_local_contrast(processed_rgb, coords_i, 8, adj.clarity);
+        processed_rgb = apply_local_contrast(processed_rgb, coords_i, 20, adj.structure);
+    }
+    if (stage_enabled(stages, STAGE_SATURATION)) {
+        processed_rgb = apply_creative_color(processed_rgb, adj.saturation, adj.vibrance);
+    }
+    if (stage_enabled(stages, STAGE_HSL)) {
+        processed_rgb = apply_hsl_panel(processed_rgb, adj.hsl, coords_i);
+    }
+    if (stage_enabled(stages, STAGE_COLOR_GRADING)) {
+        processed_rgb = apply_color_grading(processed_rgb, adj.color_grading_shadows, adj.color_grading_midtones, adj.color_grading_highlights, adj.color_grading_blending, adj.color_grading_balance);
+    }
     return processed_rgb;
 }
 
@@ -553,9 +1060,18 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     let in_dims = vec2<u32>(textureDimensions(input_texture));
     if (id.x >= in_dims.x || id.y >= in_dims.y) { return; }
 
-    let original_color = textureLoad(input_texture, id.xy, 0);
+    let original_color = load_input(vec2<i32>(id.xy));
     var initial_linear_rgb = srgb_to_linear(original_color.rgb);
 
+    if (adjustments.global.camera_matrix_enabled == 1u && adjustments.global.monochrome == 0u) {
+        let m = adjustments.global.camera_matrix;
+        initial_linear_rgb = max(vec3<f32>(
+            dot(m[0].xyz, initial_linear_rgb),
+            dot(m[1].xyz, initial_linear_rgb),
+            dot(m[2].xyz, initial_linear_rgb)
+        ), vec3<f32>(0.0));
+    }
+
     if (adjustments.global.enable_negative_conversion == 1u) {
         initial_linear_rgb = vec3<f32>(1.0) - initial_linear_rgb;
         let film_base_color = vec3<f32>(adjustments.global.film_base_r, adjustments.global.film_base_g, adjustments.global.film_base_b);
@@ -571,29 +1087,56 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
 
     let base_srgb = linear_to_srgb(aces_fitted(processed_rgb_linear));
     
-    var final_rgb = apply_all_curves(base_srgb,
-        adjustments.global.luma_curve, adjustments.global.luma_curve_count,
-        adjustments.global.red_curve, adjustments.global.red_curve_count,
-        adjustments.global.green_curve, adjustments.global.green_curve_count,
-        adjustments.global.blue_curve, adjustments.global.blue_curve_count
-    );
+    var final_rgb = base_srgb;
+    if (stage_enabled(adjustments.global.enabled_stages, STAGE_LEVELS)) {
+        final_rgb = apply_levels(final_rgb, adjustments.global.levels);
+    }
+    if (stage_enabled(adjustments.global.enabled_stages, STAGE_CURVES)) {
+        final_rgb = apply_all_curves(final_rgb,
+            adjustments.global.luma_curve, adjustments.global.luma_curve_count,
+            adjustments.global.red_curve, adjustments.global.red_curve_count,
+            adjustments.global.green_curve, adjustments.global.green_curve_count,
+            adjustments.global.blue_curve, adjustments.global.blue_curve_count,
+            adjustments.global.luma_curve_lab
+        );
+    }
 
     for (var i = 0u; i < adjustments.mask_count; i = i + 1u) {
-        let influence = textureLoad(mask_textures, id.xy, i, 0).r;
+        var mask_adj = adjustments.mask_adjustments[i];
+        var influence = textureLoad(mask_textures, id.xy, i, 0).r;
+        if (mask_adj.signed_influence == 1u) {
+            // Dodge & burn: 128 is neutral, above dodges and below burns with the same strength.
+            let signed_value = round(influence * 255.0) - 128.0;
+            influence = abs(signed_value) / 127.0;
+            if (signed_value < 0.0) { mask_adj.exposure = -mask_adj.exposure; }
+        }
         if (influence > 0.001) {
-            let mask_adjusted_linear = apply_all_mask_adjustments(processed_rgb_linear, adjustments.mask_adjustments[i], absolute_coord_i);
+            let mask_adjusted_linear = apply_all_mask_adjustments(processed_rgb_linear, mask_adj, absolute_coord_i);
             let mask_base_srgb = linear_to_srgb(aces_fitted(mask_adjusted_linear));
-            let mask_final_srgb = apply_all_curves(mask_base_srgb,
-                adjustments.mask_adjustments[i].luma_curve, adjustments.mask_adjustments[i].luma_curve_count,
-                adjustments.mask_adjustments[i].red_curve, adjustments.mask_adjustments[i].red_curve_count,
-                adjustments.mask_adjustments[i].green_curve, adjustments.mask_adjustments[i].green_curve_count,
-                adjustments.mask_adjustments[i].blue_curve, adjustments.mask_adjustments[i].blue_curve_count
-            );
+            var mask_final_srgb = mask_base_srgb;
+            if (stage_enabled(adjustments.mask_adjustments[i].enabled_stages, STAGE_CURVES)) {
+                mask_final_srgb = apply_all_curves(mask_base_srgb,
+                    adjustments.mask_adjustments[i].luma_curve, adjustments.mask_adjustments[i].luma_curve_count,
+                    adjustments.mask_adjustments[i].red_curve, adjustments.mask_adjustments[i].red_curve_count,
+                    adjustments.mask_adjustments[i].green_curve, adjustments.mask_adjustments[i].green_curve_count,
+                    adjustments.mask_adjustments[i].blue_curve, adjustments.mask_adjustments[i].blue_curve_count,
+                    adjustments.mask_adjustments[i].luma_curve_lab
+                );
+            }
             final_rgb = mix(final_rgb, mask_final_srgb, influence);
         }
     }
 
-    if (adjustments.global.grain_amount > 0.0) {
+    if (stage_enabled(adjustments.global.enabled_stages, STAGE_SPLIT_TONING)) {
+        let g = adjustments.global;
+        final_rgb = apply_split_toning(final_rgb, g.split_toning_shadow_hue, g.split_toning_shadow_saturation, g.split_toning_highlight_hue, g.split_toning_highlight_saturation, g.split_toning_balance);
+    }
+
+    if (stage_enabled(adjustments.global.enabled_stages, STAGE_LUT)) {
+        final_rgb = apply_lut(final_rgb, adjustments.global.lut_size, adjustments.global.lut_intensity);
+    }
+
+    if (adjustments.global.grain_amount > 0.0 && stage_enabled(adjustments.global.enabled_stages, STAGE_GRAIN)) {
         let g = adjustments.global;
         let coord = vec2<f32>(absolute_coord_i);
         let amount = g.grain_amount * 0.5;
@@ -610,19 +1153,33 @@ fn main(@builtin(global_invocation_id) id: vec3<u32>) {
     }
 
     let g = adjustments.global;
-    if (g.vignette_amount != 0.0) {
-        let out_coord = vec2<f32>(f32(id.x), f32(id.y));
+    if (g.vignette_amount != 0.0 && stage_enabled(g.enabled_stages, STAGE_VIGNETTE)) {
+        // Relative to the whole image, so tiles share one vignette.
+        let out_coord = vec2<f32>(absolute_coord_i);
+        let image_dims = vec2<f32>(f32(adjustments.image_width), f32(adjustments.image_height));
         let v_amount = g.vignette_amount;
         let v_mid = g.vignette_midpoint;
         let v_round = 1.0 - g.vignette_roundness;
         let v_feather = g.vignette_feather * 0.5;
-        let aspect = f32(in_dims.y) / f32(in_dims.x);
-        let uv_centered = (out_coord / vec2<f32>(in_dims) - 0.5) * 2.0;
+        let aspect = image_dims.y / image_dims.x;
+        let uv_centered = (out_coord / image_dims - 0.5) * 2.0;
         let uv_round = sign(uv_centered) * pow(abs(uv_centered), vec2<f32>(v_round, v_round));
         let d = length(uv_round * vec2<f32>(1.0, aspect)) * 0.5;
         let vignette_mask = smoothstep(v_mid - v_feather, v_mid + v_feather, d);
         if (v_amount < 0.0) { final_rgb *= (1.0 + v_amount * vignette_mask); } else { final_rgb = mix(final_rgb, vec3<f32>(1.0), v_amount * vignette_mask); }
     }
 
-    textureStore(output_texture, id.xy, vec4<f32>(clamp(final_rgb, vec3<f32>(0.0), vec3<f32>(1.0)), original_color.a));
+    final_rgb = clamp(final_rgb, vec3<f32>(0.0), vec3<f32>(1.0));
+    var alpha = original_color.a;
+    if (adjustments.clipping_overlay == 1u) {
+        // Flags for paint_clipping_overlay: a channel at 255 clips the highlights, one at 0
+        // crushes the shadows. Everything else is opaque, previews have no transparency.
+        alpha = 1.0;
+        if (any(final_rgb >= vec3<f32>(254.5 / 255.0))) {
+            alpha = 0.0;
+        } else if (any(final_rgb <= vec3<f32>(0.5 / 255.0))) {
+            alpha = 0.5;
+        }
+    }
+    textureStore(output_texture, id.xy, vec4<f32>(final_rgb, alpha));
 }
\ No newline at end of file
@@ -0,0 +1,200 @@
+use std::path::Path;
+#[cfg(not(target_os = "macos"))]
+use std::process::Command;
+
+#[cfg(target_os = "macos")]
+mod picker {
+    use std::cell::RefCell;
+
+    use objc2::rc::Retained;
+    use objc2::runtime::{NSObject, NSObjectProtocol, ProtocolObject};
+    use objc2::{declare_class, msg_send_id, mutability, ClassType, DeclaredClass};
+    use objc2_app_kit::{NSSharingService, NSSharingServicePicker, NSSharingServicePickerDelegate};
+    use objc2_foundation::MainThreadMarker;
+
+    thread_local! {
+        // The picker's delegate property is weak, so the open delegate is owned here. The
+        // delegate in turn owns the picker, and both are released once a service is chosen or
+        // the picker is dismissed.
+        static ACTIVE: RefCell<Option<Retained<PickerDelegate>>> = const { RefCell::new(None) };
+    }
+
+    declare_class!(
+        pub struct PickerDelegate;
+
+        unsafe impl ClassType for PickerDelegate {
+            type Super = NSObject;
+            type Mutability = mutability::MainThreadOnly;
+            const NAME: &'static str = "RapidRAWSharePickerDelegate";
+        }
+
+        impl DeclaredClass for PickerDelegate {
+            type Ivars = Retained<NSSharingServicePicker>;
+        }
+
+        unsafe impl NSObjectProtocol for PickerDelegate {}
+
+        unsafe impl NSSharingServicePickerDelegate for PickerDelegate {
+            #[method(sharingServicePicker:didChooseSharingService:)]
+            fn did_choose_sharing_service(
+                &self,
+                _picker: &NSSharingServicePicker,
+                _service: Option<&NSSharingService>,
+            ) {
+                // A picker replaced by a newer one reports here when it is closed; only the
+                // current one is released. AppKit is still inside the picker at this point, so
+                // the release waits for the autorelease pool of the current event.
+                let finished = ACTIVE.with(|active| {
+                    let mut active = active.borrow_mut();
+                    let is_self = active.as_deref().is_some_and(|current| std::ptr::eq(current, self));
+                    if is_self { active.take() } else { None }
+                });
+                if let Some(delegate) = finished {
+                    let _ = Retained::autorelease_ptr(delegate);
+                }
+            }
+        }
+    );
+
+    // Takes ownership of the picker until the user picks a service or dismisses it.
+    pub fn retain_until_done(mtm: MainThreadMarker, picker: Retained<NSSharingServicePicker>) {
+        let delegate = mtm.alloc::<PickerDelegate>().set_ivars(picker);
+        let delegate: Retained<PickerDelegate> = unsafe { msg_send_id![super(delegate), init] };
+        unsafe { delegate.ivars().setDelegate(Some(ProtocolObject::from_ref(&*delegate))) };
+
+        if let Some(previous) = ACTIVE.with(|active| active.borrow_mut().replace(delegate)) {
+            unsafe { previous.ivars().close() };
+        }
+    }
+}
+
+// Shows the native share sheet for the rendered file, anchored to the window.
+#[cfg(target_os = "macos")]
+pub async fn show_share_sheet(window: &tauri::WebviewWindow, file_path: &Path) -> Result<(), String> {
+    use objc2::rc::Retained;
+    use objc2_app_kit::{NSSharingServicePicker, NSView};
+    use objc2_foundation::{MainThreadMarker, NSArray, NSRectEdge, NSString, NSURL};
+
+    // Raw pointers are not Send, so the view is passed to the main thread as an address.
+    let ns_view = window.ns_view().map_err(|e| e.to_string())? as usize;
+    let path = file_path.to_string_lossy().into_owned();
+
+    window
+        .run_on_main_thread(move || {
+            let Some(mtm) = MainThreadMarker::new() else { return };
+            unsafe {
+                let view: &NSView = &*(ns_view as *const NSView);
+                let url = NSURL::fileURLWithPath(&NSString::from_str(&path));
+                let items: Retained<NSArray> = Retained::cast(NSArray::from_vec(vec![url]));
+                let picker = NSSharingServicePicker::initWithItems(mtm.alloc(), &items);
+                picker.showRelativeToRect_ofView_preferredEdge(view.bounds(), view, NSRectEdge::NSMinYEdge);
+                picker::retain_until_done(mtm, picker);
+            }
+        })
+        .map_err(|e| e.to_string())
+}
+
+#[cfg(target_os = "windows")]
+thread_local! {
+    // DataTransferManager is per window and keeps every handler it is given, so the handler
+    // for the previous share is removed before a new one is registered.
+    static DATA_REQUESTED: std::cell::RefCell<Option<(windows::ApplicationModel::DataTransfer::DataTransferManager, i64)>> =
+        const { std::cell::RefCell::new(None) };
+}
+
+// Shows the Windows share UI through DataTransferManager. Only if the share UI is unavailable
+// (Windows versions before 10) is the file revealed in Explorer instead, as a fallback.
+#[cfg(target_os = "windows")]
+pub async fn show_share_sheet(window: &tauri::WebviewWindow, file_path: &Path) -> Result<(), String> {
+    let hwnd = window.hwnd().map_err(|e| e.to_string())?.0 as usize;
+    let path = file_path.to_path_buf();
+
+    let (sender, receiver) = std::sync::mpsc::channel();
+    window
+        .run_on_main_thread(move || {
+            let hwnd = windows::Win32::Foundation::HWND(hwnd as *mut _);
+            let _ = sender.send(show_data_transfer_ui(hwnd, &path).map_err(|e| e.to_string()));
+        })
+        .map_err(|e| e.to_string())?;
+    let result = receiver.recv().map_err(|e| e.to_string())?;
+
+    result.or_else(|e| {
+        eprintln!("Share UI unavailable, revealing the file instead: {}", e);
+        Command::new("explorer")
+            .arg(format!("/select,{}", file_path.display()))
+            .spawn()
+            .map(|_| ())
+            .map_err(|e| e.to_string())
+    })
+}
+
+#[cfg(target_os = "windows")]
+fn show_data_transfer_ui(hwnd: windows::Win32::Foundation::HWND, path: &Path) -> windows::core::Result<()> {
+    use windows::core::{factory, Interface, HSTRING};
+    use windows::ApplicationModel::DataTransfer::{DataRequestedEventArgs, DataTransferManager};
+    use windows::Foundation::TypedEventHandler;
+    use windows::Storage::{IStorageItem, StorageFile};
+    use windows::Win32::UI::Shell::IDataTransferManagerInterop;
+    use windows_collections::IIterable;
+
+    let interop = factory::<DataTransferManager, IDataTransferManagerInterop>()?;
+    let manager: DataTransferManager = unsafe { interop.GetForWindow(hwnd)? };
+
+    let file_path = HSTRING::from(path.as_os_str());
+    let title = HSTRING::from(path.file_name().unwrap_or(path.as_os_str()));
+    let handler = TypedEventHandler::<DataTransferManager, DataRequestedEventArgs>::new(move |_, args| {
+        let request = args.ok()?.Request()?;
+        let data = request.Data()?;
+        data.Properties()?.SetTitle(&title)?;
+
+        // Loading the file is asynchronous; the deferral keeps the share UI waiting for it
+        // without blocking the window's thread.
+        let deferral = request.GetDeferral()?;
+        let file_path = file_path.clone();
+        std::thread::spawn(move || {
+            let result = StorageFile::GetFileFromPathAsync(&file_path)
+                .and_then(|operation| operation.get())
+                .and_then(|file| file.cast::<IStorageItem>())
+                .and_then(|item| data.SetStorageItemsReadOnly(&IIterable::<IStorageItem>::from(vec![Some(item)])));
+            if let Err(e) = result {
+                eprintln!("Failed to attach the shared file: {}", e);
+            }
+            let _ = deferral.Complete();
+        });
+        Ok(())
+    });
+
+    DATA_REQUESTED.with(|registered| -> windows::core::Result<()> {
+        if let Some((previous_manager, token)) = registered.borrow_mut().take() {
+            let _ = previous_manager.RemoveDataRequested(token);
+        }
+        let token = manager.DataRequested(&handler)?;
+        *registered.borrow_mut() = Some((manager, token));
+        Ok(())
+    })?;
+
+    unsafe { interop.ShowShareUIForWindow(hwnd) }
+}
+
+// There is no share sheet on Linux. The OpenURI portal's app chooser lets the user send the
+// file to any application; without a portal, xdg-email is used as a fallback and attaches the
+// file to a new message in the default mail client.
+#[cfg(all(unix, not(target_os = "macos")))]
+pub async fn show_share_sheet(_window: &tauri::WebviewWindow, file_path: &Path) -> Result<(), String> {
+    use ashpd::desktop::open_uri::OpenFileRequest;
+
+    let file = std::fs::File::open(file_path).map_err(|e| e.to_string())?;
+    match OpenFileRequest::default().ask(true).send_file(&file).await {
+        // The user dismissing the chooser is not an error.
+        Ok(_) => Ok(()),
+        Err(e) => {
+            eprintln!("OpenURI portal unavailable, falling back to xdg-email: {}", e);
+            Command::new("xdg-email")
+                .arg("--attach")
+                .arg(file_path)
+                .spawn()
+                .map(|_| ())
+                .map_err(|e| format!("Failed to open the share dialog: {}", e))
+        }
+    }
+}
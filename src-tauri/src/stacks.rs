@@ -0,0 +1,166 @@
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use serde::{Deserialize, Serialize};
+use uuid::Uuid;
+
+const STACKS_FILENAME: &str = ".rapidraw_stacks.json";
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct ImageStack {
+    pub id: String,
+    pub cover: String,
+    pub members: Vec<String>,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone, Default)]
+pub struct StackFile {
+    pub stacks: Vec<ImageStack>,
+}
+
+fn get_stacks_path(folder: &Path) -> PathBuf {
+    folder.join(STACKS_FILENAME)
+}
+
+fn file_name_of(path: &str) -> Result<String, String> {
+    Path::new(path)
+        .file_name()
+        .map(|n| n.to_string_lossy().into_owned())
+        .ok_or_else(|| format!("Invalid image path: {}", path))
+}
+
+fn parent_folder_of(path: &str) -> Result<PathBuf, String> {
+    Path::new(path)
+        .parent()
+        .map(Path::to_path_buf)
+        .ok_or_else(|| format!("Invalid image path: {}", path))
+}
+
+// Moves the cover to the first member when the cover itself left the stack.
+fn ensure_cover(stack: &mut ImageStack) {
+    if !stack.members.contains(&stack.cover) {
+        if let Some(first) = stack.members.first() {
+            stack.cover = first.clone();
+        }
+    }
+}
+
+// Members are stored as file names relative to the folder, so a folder can be moved or
+// renamed as a whole without breaking its stacks.
+pub fn load_stacks(folder: &Path) -> StackFile {
+    let mut stack_file: StackFile = fs::read_to_string(get_stacks_path(folder))
+        .ok()
+        .and_then(|content| serde_json::from_str(&content).ok())
+        .unwrap_or_default();
+
+    for stack in &mut stack_file.stacks {
+        stack.members.retain(|m| folder.join(m).exists());
+        ensure_cover(stack);
+    }
+    stack_file.stacks.retain(|s| s.members.len() > 1);
+    stack_file
+}
+
+fn save_stacks(folder: &Path, stack_file: &StackFile) -> Result<(), String> {
+    let path = get_stacks_path(folder);
+    if stack_file.stacks.is_empty() {
+        if path.exists() {
+            fs::remove_file(path).map_err(|e| e.to_string())?;
+        }
+        return Ok(());
+    }
+    let json_string = serde_json::to_string_pretty(stack_file).map_err(|e| e.to_string())?;
+    fs::write(path, json_string).map_err(|e| e.to_string())
+}
+
+pub fn find_stack_for<'a>(stack_file: &'a StackFile, file_name: &str) -> Option<&'a ImageStack> {
+    stack_file.stacks.iter().find(|s| s.members.iter().any(|m| m == file_name))
+}
+
+#[tauri::command]
+pub fn create_stack(paths: Vec<String>, cover_path: Option<String>) -> Result<ImageStack, String> {
+    if paths.len() < 2 {
+        return Err("A stack needs at least two images.".to_string());
+    }
+
+    let folder = parent_folder_of(&paths[0])?;
+    let mut members = Vec::with_capacity(paths.len());
+    for path in &paths {
+        if parent_folder_of(path)? != folder {
+            return Err("All images of a stack must be in the same folder.".to_string());
+        }
+        members.push(file_name_of(path)?);
+    }
+
+    let cover = match cover_path {
+        Some(cover_path) => file_name_of(&cover_path)?,
+        None => members[0].clone(),
+    };
+    if !members.contains(&cover) {
+        return Err("The cover image must be part of the stack.".to_string());
+    }
+
+    let mut stack_file = load_stacks(&folder);
+    // An image can only belong to one stack, so pull the new members out of existing ones.
+    for stack in &mut stack_file.stacks {
+        stack.members.retain(|m| !members.contains(m));
+        ensure_cover(stack);
+    }
+    stack_file.stacks.retain(|s| s.members.len() > 1);
+
+    let stack = ImageStack {
+        id: Uuid::new_v4().to_string(),
+        cover,
+        members,
+    };
+    stack_file.stacks.push(stack.clone());
+    save_stacks(&folder, &stack_file)?;
+
+    Ok(stack)
+}
+
+#[tauri::command]
+pub fn set_stack_cover(cover_path: String) -> Result<(), String> {
+    let folder = parent_folder_of(&cover_path)?;
+    let cover = file_name_of(&cover_path)?;
+    let mut stack_file = load_stacks(&folder);
+
+    let stack = stack_file
+        .stacks
+        .iter_mut()
+        .find(|s| s.members.contains(&cover))
+        .ok_or_else(|| "Image is not part of a stack.".to_string())?;
+    stack.cover = cover;
+
+    save_stacks(&folder, &stack_file)
+}
+
+#[tauri::command]
+pub fn remove_from_stack(paths: Vec<String>) -> Result<(), String> {
+    let Some(first) = paths.first() else { return Ok(()) };
+    let folder = parent_folder_of(first)?;
+    let names: Vec<String> = paths.iter().map(|p| file_name_of(p)).collect::<Result<_, _>>()?;
+
+    let mut stack_file = load_stacks(&folder);
+    for stack in &mut stack_file.stacks {
+        stack.members.retain(|m| !names.contains(m));
+        ensure_cover(stack);
+    }
+    stack_file.stacks.retain(|s| s.members.len() > 1);
+
+    save_stacks(&folder, &stack_file)
+}
+
+#[tauri::command]
+pub fn dissolve_stack(folder_path: String, stack_id: String) -> Result<(), String> {
+    let folder = PathBuf::from(folder_path);
+    let mut stack_file = load_stacks(&folder);
+    stack_file.stacks.retain(|s| s.id != stack_id);
+    save_stacks(&folder, &stack_file)
+}
+
+#[tauri::command]
+pub fn get_stacks(folder_path: String) -> Result<Vec<ImageStack>, String> {
+    Ok(load_stacks(Path::new(&folder_path)).stacks)
+}
@@ -0,0 +1,313 @@
+use std::fs::{self, File};
+use std::io::{BufWriter, Seek, SeekFrom, Write};
+use std::path::{Path, PathBuf};
+
+use flate2::{write::ZlibEncoder, Compression};
+use image::{codecs::jpeg::JpegEncoder, imageops, DynamicImage, ImageBuffer, Luma, RgbImage};
+
+use crate::gpu_processing::run_gpu_processing_tiled;
+use crate::image_processing::{AllAdjustments, GpuContext};
+use crate::lut::Lut;
+use crate::resize::{linear_to_srgb, srgb_to_linear};
+
+// Tile size of both formats. The processing tiles are a multiple of it, so every processing
+// tile yields whole output tiles and only their half resolution copy has to be kept.
+const TILE_SIZE: u32 = 256;
+const PROCESSING_TILE_SIZE: u32 = TILE_SIZE * 8;
+
+// `ptif` is a tiled BigTIFF with the reduced resolutions as further images, as read by
+// tile servers and slide viewers. `dzi` is a Deep Zoom descriptor next to a `_files` folder
+// of JPEG tiles, as used by OpenSeadragon.
+pub fn is_tiled_format(extension: &str) -> bool {
+    matches!(extension, "ptif" | "dzi")
+}
+
+// The folder a tiled target writes next to its output file, if it has one.
+pub fn companion_dir(output_path: &Path) -> Option<PathBuf> {
+    if !output_path.extension().and_then(|e| e.to_str()).is_some_and(|e| e.eq_ignore_ascii_case("dzi")) {
+        return None;
+    }
+    let stem = output_path.file_stem().and_then(|s| s.to_str()).unwrap_or("image");
+    Some(output_path.with_file_name(format!("{}_files", stem)))
+}
+
+trait TileWriter {
+    // `level` 0 is the full resolution, every further level halves the size.
+    fn write_tile(&mut self, level: usize, col: u32, row: u32, tile: &RgbImage) -> Result<(), String>;
+    fn finish(self: Box<Self>) -> Result<(), String>;
+}
+
+fn level_sizes(width: u32, height: u32, min_size: u32) -> Vec<(u32, u32)> {
+    let mut levels = vec![(width, height)];
+    let (mut w, mut h) = (width, height);
+    while w > min_size || h > min_size {
+        w = w.div_ceil(2).max(1);
+        h = h.div_ceil(2).max(1);
+        levels.push((w, h));
+    }
+    levels
+}
+
+fn write_level_tiles(writer: &mut dyn TileWriter, level: usize, image: &RgbImage, x: u32, y: u32) -> Result<(), String> {
+    for ty in (0..image.height()).step_by(TILE_SIZE as usize) {
+        for tx in (0..image.width()).step_by(TILE_SIZE as usize) {
+            let w = TILE_SIZE.min(image.width() - tx);
+            let h = TILE_SIZE.min(image.height() - ty);
+            let tile = imageops::crop_imm(image, tx, ty, w, h).to_image();
+            writer.write_tile(level, (x + tx) / TILE_SIZE, (y + ty) / TILE_SIZE, &tile)?;
+        }
+    }
+    Ok(())
+}
+
+// Halves `src` into `dst` at (`x`, `y`), averaging in linear light like the export resize.
+fn downsample_into(src: &RgbImage, dst: &mut RgbImage, x: u32, y: u32, to_linear: &[f32; 256]) {
+    for dy in 0..src.height().div_ceil(2) {
+        for dx in 0..src.width().div_ceil(2) {
+            let mut sum = [0.0f32; 3];
+            let mut count = 0.0;
+            for sy in dy * 2..(dy * 2 + 2).min(src.height()) {
+                for sx in dx * 2..(dx * 2 + 2).min(src.width()) {
+                    let p = src.get_pixel(sx, sy);
+                    for c in 0..3 {
+                        sum[c] += to_linear[p[c] as usize];
+                    }
+                    count += 1.0;
+                }
+            }
+            if x + dx < dst.width() && y + dy < dst.height() {
+                let out = sum.map(|s| (linear_to_srgb(s / count) * 255.0).round() as u8);
+                dst.put_pixel(x + dx, y + dy, image::Rgb(out));
+            }
+        }
+    }
+}
+
+// Renders the image tile by tile and writes it as `output_path`, a `.ptif` or `.dzi` file.
+// Only the full resolution tiles in flight and the half resolution level are held in memory.
+// Returns the written files.
+pub fn export_tiled(
+    context: &GpuContext,
+    image: &DynamicImage,
+    adjustments: AllAdjustments,
+    mask_bitmaps: &[ImageBuffer<Luma<u8>, Vec<u8>>],
+    lut: Option<&Lut>,
+    output_path: &Path,
+    jpeg_quality: u8,
+) -> Result<Vec<String>, String> {
+    let (width, height) = (image.width(), image.height());
+    let extension = output_path.extension().and_then(|e| e.to_str()).unwrap_or("").to_lowercase();
+    let (levels, mut writer): (Vec<(u32, u32)>, Box<dyn TileWriter>) = match extension.as_str() {
+        "ptif" => {
+            let levels = level_sizes(width, height, TILE_SIZE);
+            let writer = PyramidTiffWriter::create(output_path, &levels)?;
+            (levels, Box::new(writer))
+        }
+        "dzi" => {
+            let levels = level_sizes(width, height, 1);
+            let writer = DeepZoomWriter::create(output_path, &levels, jpeg_quality)?;
+            (levels, Box::new(writer))
+        }
+        other => return Err(format!("Unsupported tiled format: {}", other)),
+    };
+
+    let to_linear: [f32; 256] = std::array::from_fn(|i| srgb_to_linear(i as f32 / 255.0));
+    let mut half = levels.get(1).map(|&(w, h)| RgbImage::new(w, h));
+
+    run_gpu_processing_tiled(context, image, adjustments, mask_bitmaps, lut, PROCESSING_TILE_SIZE, |x, y, w, h, pixels| {
+        let rgb: Vec<u8> = pixels.chunks_exact(4).flat_map(|p| [p[0], p[1], p[2]]).collect();
+        let tile = RgbImage::from_raw(w, h, rgb).ok_or_else(|| "Failed to create tile from GPU data".to_string())?;
+        write_level_tiles(writer.as_mut(), 0, &tile, x, y)?;
+        if let Some(half) = half.as_mut() {
+            downsample_into(&tile, half, x / 2, y / 2, &to_linear);
+        }
+        Ok(())
+    })?;
+
+    let mut current = half;
+    for level in 1..levels.len() {
+        let Some(level_image) = current.take() else { break };
+        write_level_tiles(writer.as_mut(), level, &level_image, 0, 0)?;
+        if let Some(&(w, h)) = levels.get(level + 1) {
+            let mut next = RgbImage::new(w, h);
+            downsample_into(&level_image, &mut next, 0, 0, &to_linear);
+            current = Some(next);
+        }
+    }
+
+    writer.finish()?;
+    Ok(vec![output_path.to_string_lossy().into_owned()])
+}
+
+struct DeepZoomWriter {
+    descriptor_path: PathBuf,
+    files_dir: PathBuf,
+    max_level: usize,
+    size: (u32, u32),
+    quality: u8,
+}
+
+impl DeepZoomWriter {
+    fn create(output_path: &Path, levels: &[(u32, u32)], quality: u8) -> Result<Self, String> {
+        let files_dir = companion_dir(output_path).ok_or("Deep Zoom exports need a .dzi file name.")?;
+        // An existing folder is never emptied or reused: it may hold an earlier export, whose
+        // tiles would mix with the new ones, or be an unrelated folder of the user's.
+        fs::create_dir(&files_dir).map_err(|e| match e.kind() {
+            std::io::ErrorKind::AlreadyExists => format!("The tile folder {} already exists.", files_dir.display()),
+            _ => e.to_string(),
+        })?;
+        Ok(Self {
+            descriptor_path: output_path.to_path_buf(),
+            files_dir,
+            max_level: levels.len() - 1,
+            size: levels[0],
+            quality,
+        })
+    }
+}
+
+impl TileWriter for DeepZoomWriter {
+    fn write_tile(&mut self, level: usize, col: u32, row: u32, tile: &RgbImage) -> Result<(), String> {
+        // Deep Zoom counts levels up from the 1x1 pixel one.
+        let level_dir = self.files_dir.join((self.max_level - level).to_string());
+        fs::create_dir_all(&level_dir).map_err(|e| e.to_string())?;
+        let file = File::create(level_dir.join(format!("{}_{}.jpg", col, row))).map_err(|e| e.to_string())?;
+        let encoder = JpegEncoder::new_with_quality(BufWriter::new(file), self.quality);
+        tile.write_with_encoder(encoder).map_err(|e| e.to_string())
+    }
+
+    fn finish(self: Box<Self>) -> Result<(), String> {
+        let descriptor = format!(
+            "<?xml version=\"1.0\" encoding=\"UTF-8\"?>\n\
+             <Image xmlns=\"http://schemas.microsoft.com/deepzoom/2008\" Format=\"jpg\" Overlap=\"0\" TileSize=\"{}\">\n  \
+             <Size Width=\"{}\" Height=\"{}\"/>\n\
+             </Image>\n",
+            TILE_SIZE, self.size.0, self.size.1
+        );
+        fs::write(&self.descriptor_path, descriptor).map_err(|e| e.to_string())
+    }
+}
+
+struct TiffLevel {
+    width: u32,
+    height: u32,
+    tiles_across: u32,
+    offsets: Vec<u64>,
+    byte_counts: Vec<u64>,
+}
+
+// BigTIFF, since gigapixel outputs easily pass the 4 GB limit of classic TIFF offsets.
+// Tiles are written as they arrive and the directories are appended at the end.
+struct PyramidTiffWriter {
+    file: BufWriter<File>,
+    levels: Vec<TiffLevel>,
+}
+
+const TIFF_SHORT: u16 = 3;
+const TIFF_LONG: u16 = 4;
+const TIFF_LONG8: u16 = 16;
+
+impl PyramidTiffWriter {
+    fn create(output_path: &Path, levels: &[(u32, u32)]) -> Result<Self, String> {
+        let mut file = BufWriter::new(File::create(output_path).map_err(|e| e.to_string())?);
+        // Byte order, BigTIFF version, offset size, reserved, first directory (patched in `finish`)
+        file.write_all(b"II").map_err(|e| e.to_string())?;
+        for value in [43u16, 8, 0] {
+            file.write_all(&value.to_le_bytes()).map_err(|e| e.to_string())?;
+        }
+        file.write_all(&0u64.to_le_bytes()).map_err(|e| e.to_string())?;
+
+        let levels = levels
+            .iter()
+            .map(|&(width, height)| {
+                let tiles_across = width.div_ceil(TILE_SIZE);
+                let count = (tiles_across * height.div_ceil(TILE_SIZE)) as usize;
+                TiffLevel { width, height, tiles_across, offsets: vec![0; count], byte_counts: vec![0; count] }
+            })
+            .collect();
+        Ok(Self { file, levels })
+    }
+
+    fn position(&mut self) -> Result<u64, String> {
+        self.file.stream_position().map_err(|e| e.to_string())
+    }
+
+    fn write_u64s(&mut self, values: &[u64]) -> Result<u64, String> {
+        let offset = self.position()?;
+        for value in values {
+            self.file.write_all(&value.to_le_bytes()).map_err(|e| e.to_string())?;
+        }
+        Ok(offset)
+    }
+}
+
+impl TileWriter for PyramidTiffWriter {
+    fn write_tile(&mut self, level: usize, col: u32, row: u32, tile: &RgbImage) -> Result<(), String> {
+        // TIFF tiles always have the full size, edge tiles are padded.
+        let mut padded = RgbImage::new(TILE_SIZE, TILE_SIZE);
+        imageops::replace(&mut padded, tile, 0, 0);
+
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(padded.as_raw()).map_err(|e| e.to_string())?;
+        let compressed = encoder.finish().map_err(|e| e.to_string())?;
+
+        let offset = self.position()?;
+        self.file.write_all(&compressed).map_err(|e| e.to_string())?;
+        let level = &mut self.levels[level];
+        let index = (row * level.tiles_across + col) as usize;
+        level.offsets[index] = offset;
+        level.byte_counts[index] = compressed.len() as u64;
+        Ok(())
+    }
+
+    fn finish(mut self: Box<Self>) -> Result<(), String> {
+        // Offset of the field holding the next directory's offset: first the header's.
+        let mut link = 8u64;
+        let levels = std::mem::take(&mut self.levels);
+        for (index, level) in levels.iter().enumerate() {
+            let tile_count = level.offsets.len() as u64;
+            let (offsets, byte_counts) = if tile_count == 1 {
+                (level.offsets[0], level.byte_counts[0])
+            } else {
+                (self.write_u64s(&level.offsets)?, self.write_u64s(&level.byte_counts)?)
+            };
+
+            let entries: [(u16, u16, u64, u64); 12] = [
+                // NewSubfileType: reduced resolution for every level but the first
+                (254, TIFF_LONG, 1, (index > 0) as u64),
+                (256, TIFF_LONG, 1, level.width as u64),
+                (257, TIFF_LONG, 1, level.height as u64),
+                // BitsPerSample: three shorts fit into the value field
+                (258, TIFF_SHORT, 3, 8 | (8 << 16) | (8 << 32)),
+                // Compression: Deflate
+                (259, TIFF_SHORT, 1, 8),
+                // Photometric: RGB
+                (262, TIFF_SHORT, 1, 2),
+                (277, TIFF_SHORT, 1, 3),
+                // PlanarConfiguration: chunky
+                (284, TIFF_SHORT, 1, 1),
+                (322, TIFF_LONG, 1, TILE_SIZE as u64),
+                (323, TIFF_LONG, 1, TILE_SIZE as u64),
+                (324, TIFF_LONG8, tile_count, offsets),
+                (325, TIFF_LONG8, tile_count, byte_counts),
+            ];
+
+            let directory = self.position()?;
+            self.file.write_all(&(entries.len() as u64).to_le_bytes()).map_err(|e| e.to_string())?;
+            for (tag, field_type, count, value) in entries {
+                self.file.write_all(&tag.to_le_bytes()).map_err(|e| e.to_string())?;
+                self.file.write_all(&field_type.to_le_bytes()).map_err(|e| e.to_string())?;
+                self.file.write_all(&count.to_le_bytes()).map_err(|e| e.to_string())?;
+                self.file.write_all(&value.to_le_bytes()).map_err(|e| e.to_string())?;
+            }
+            let next_link = self.position()?;
+            self.file.write_all(&0u64.to_le_bytes()).map_err(|e| e.to_string())?;
+
+            self.file.seek(SeekFrom::Start(link)).map_err(|e| e.to_string())?;
+            self.file.write_all(&directory.to_le_bytes()).map_err(|e| e.to_string())?;
+            self.file.seek(SeekFrom::End(0)).map_err(|e| e.to_string())?;
+            link = next_link;
+        }
+        self.file.flush().map_err(|e| e.to_string())
+    }
+}
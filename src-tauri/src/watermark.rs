@@ -0,0 +1,159 @@
+use std::fs;
+
+use ab_glyph::{FontVec, PxScale};
+use image::{imageops, DynamicImage, GenericImageView, Rgba, RgbaImage};
+use imageproc::drawing::{draw_text_mut, text_size};
+use serde::{Deserialize, Serialize};
+
+#[cfg(target_os = "windows")]
+const FALLBACK_FONTS: &[&str] = &["C:\\Windows\\Fonts\\segoeui.ttf", "C:\\Windows\\Fonts\\arial.ttf"];
+#[cfg(target_os = "macos")]
+const FALLBACK_FONTS: &[&str] = &[
+    "/System/Library/Fonts/Supplemental/Arial.ttf",
+    "/Library/Fonts/Arial.ttf",
+    "/System/Library/Fonts/Helvetica.ttc",
+];
+#[cfg(all(not(target_os = "windows"), not(target_os = "macos")))]
+const FALLBACK_FONTS: &[&str] = &[
+    "/usr/share/fonts/truetype/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/TTF/DejaVuSans.ttf",
+    "/usr/share/fonts/dejavu/DejaVuSans.ttf",
+    "/usr/share/fonts/truetype/liberation/LiberationSans-Regular.ttf",
+    "/usr/share/fonts/noto/NotoSans-Regular.ttf",
+];
+
+#[derive(Serialize, Deserialize, Debug, Clone, Copy)]
+#[serde(rename_all = "camelCase")]
+pub enum WatermarkAnchor {
+    TopLeft,
+    Top,
+    TopRight,
+    Left,
+    Center,
+    Right,
+    BottomLeft,
+    Bottom,
+    BottomRight,
+}
+
+#[derive(Serialize, Deserialize, Debug, Clone)]
+#[serde(rename_all = "camelCase")]
+pub struct WatermarkSettings {
+    pub text: Option<String>,
+    pub image_path: Option<String>,
+    pub font_path: Option<String>,
+    pub color: Option<String>,
+    pub anchor: WatermarkAnchor,
+    pub opacity: f32,
+    pub scale: f32,
+    pub margin: f32,
+}
+
+fn load_font(font_path: Option<&str>) -> Result<FontVec, String> {
+    let candidates = font_path.into_iter().chain(FALLBACK_FONTS.iter().copied());
+    for path in candidates {
+        if let Ok(bytes) = fs::read(path) {
+            if let Ok(font) = FontVec::try_from_vec(bytes) {
+                return Ok(font);
+            }
+        }
+    }
+    Err("No usable font found for the text watermark.".to_string())
+}
+
+pub fn parse_color(hex: Option<&str>) -> Rgba<u8> {
+    let hex = hex.unwrap_or("#ffffff").trim_start_matches('#');
+    if hex.len() != 6 {
+        return Rgba([255, 255, 255, 255]);
+    }
+    let channel = |i: usize| u8::from_str_radix(&hex[i..i + 2], 16).unwrap_or(255);
+    Rgba([channel(0), channel(2), channel(4), 255])
+}
+
+fn render_text_watermark(text: &str, settings: &WatermarkSettings, short_edge: u32) -> Result<RgbaImage, String> {
+    let font = load_font(settings.font_path.as_deref())?;
+    let font_px = (short_edge as f32 * settings.scale / 100.0).max(8.0);
+    let scale = PxScale::from(font_px);
+
+    let (text_w, text_h) = text_size(scale, &font, text);
+    // Glyphs can extend slightly below the reported height, so leave some room.
+    let padding = (font_px * 0.25).ceil() as u32;
+    let mut overlay = RgbaImage::new(text_w + padding * 2, text_h + padding * 2);
+    draw_text_mut(
+        &mut overlay,
+        parse_color(settings.color.as_deref()),
+        padding as i32,
+        padding as i32,
+        scale,
+        &font,
+        text,
+    );
+    Ok(overlay)
+}
+
+fn render_image_watermark(path: &str, settings: &WatermarkSettings, short_edge: u32) -> Result<RgbaImage, String> {
+    let logo = image::open(path).map_err(|e| format!("Failed to load watermark image: {}", e))?;
+    let target = (short_edge as f32 * settings.scale / 100.0).round().max(1.0) as u32;
+    Ok(logo.resize(target, target, imageops::FilterType::Lanczos3).to_rgba8())
+}
+
+fn anchor_position(anchor: WatermarkAnchor, image: (u32, u32), overlay: (u32, u32), margin: u32) -> (i64, i64) {
+    let (iw, ih) = (image.0 as i64, image.1 as i64);
+    let (ow, oh) = (overlay.0 as i64, overlay.1 as i64);
+    let m = margin as i64;
+
+    let left = m;
+    let center_x = (iw - ow) / 2;
+    let right = iw - ow - m;
+    let top = m;
+    let center_y = (ih - oh) / 2;
+    let bottom = ih - oh - m;
+
+    match anchor {
+        WatermarkAnchor::TopLeft => (left, top),
+        WatermarkAnchor::Top => (center_x, top),
+        WatermarkAnchor::TopRight => (right, top),
+        WatermarkAnchor::Left => (left, center_y),
+        WatermarkAnchor::Center => (center_x, center_y),
+        WatermarkAnchor::Right => (right, center_y),
+        WatermarkAnchor::BottomLeft => (left, bottom),
+        WatermarkAnchor::Bottom => (center_x, bottom),
+        WatermarkAnchor::BottomRight => (right, bottom),
+    }
+}
+
+pub fn apply_watermark(image: &mut DynamicImage, settings: &WatermarkSettings) -> Result<(), String> {
+    let (width, height) = image.dimensions();
+    let short_edge = width.min(height);
+
+    let mut overlay = match (&settings.image_path, &settings.text) {
+        (Some(path), _) if !path.is_empty() => render_image_watermark(path, settings, short_edge)?,
+        (_, Some(text)) if !text.trim().is_empty() => render_text_watermark(text, settings, short_edge)?,
+        _ => return Ok(()),
+    };
+
+    let opacity = (settings.opacity / 100.0).clamp(0.0, 1.0);
+    for pixel in overlay.pixels_mut() {
+        pixel[3] = (pixel[3] as f32 * opacity).round() as u8;
+    }
+
+    let margin = (short_edge as f32 * settings.margin / 100.0).round() as u32;
+    let (x, y) = anchor_position(settings.anchor, (width, height), overlay.dimensions(), margin);
+
+    match image {
+        DynamicImage::ImageRgba16(buffer) => {
+            let overlay_16 = DynamicImage::ImageRgba8(overlay).to_rgba16();
+            imageops::overlay(buffer, &overlay_16, x, y);
+        }
+        DynamicImage::ImageRgba8(buffer) => {
+            imageops::overlay(buffer, &overlay, x, y);
+        }
+        _ => {
+            let mut buffer = image.to_rgba8();
+            imageops::overlay(&mut buffer, &overlay, x, y);
+            *image = DynamicImage::ImageRgba8(buffer);
+        }
+    }
+
+    Ok(())
+}
@@ -6,7 +6,7 @@ import { homeDir } from '@tauri-apps/api/path';
 import debounce from 'lodash.debounce';
 import { centerCrop, makeAspectCrop } from 'react-image-crop';
 import clsx from 'clsx';
-import { Copy, ClipboardPaste, RotateCcw, Star, Trash2, Folder, Edit, Check, X, Undo, Redo, FolderPlus, FileEdit, CopyPlus, Aperture } from 'lucide-react';
+import { Copy, ClipboardPaste, RotateCcw, Star, Trash2, Folder, Edit, Check, X, Undo, Redo, FolderPlus, FileEdit, CopyPlus, Aperture, Stamp, Moon, SunDim, Layers, Grid3x3, Tag, Tags } from 'lucide-react';
 import TitleBar from './window/TitleBar';
 import MainLibrary from './components/panel/MainLibrary';
 import FolderTree from './components/panel/FolderTree';
@@ -25,16 +25,18 @@ import BottomBar from './components/panel/BottomBar';
 import { ContextMenuProvider, useContextMenu } from './context/ContextMenuContext';
 import CreateFolderModal from './components/modals/CreateFolderModal';
 import RenameFolderModal from './components/modals/RenameFolderModal';
+import KeywordsModal from './components/modals/KeywordsModal';
 import ConfirmModal from './components/modals/ConfirmModal';
 import { useHistoryState } from './hooks/useHistoryState';
 import Resizer from './components/ui/Resizer';
-import { INITIAL_ADJUSTMENTS, COPYABLE_ADJUSTMENT_KEYS, normalizeLoadedAdjustments } from './utils/adjustments';
+import { INITIAL_ADJUSTMENTS, COPYABLE_ADJUSTMENT_KEYS, normalizeLoadedAdjustments, getBaseImageKey } from './utils/adjustments';
 import { generatePaletteFromImage } from './utils/palette';
 import { useKeyboardShortcuts } from './hooks/useKeyboardShortcuts';
 import { THEMES, DEFAULT_THEME_ID } from './utils/themes';
 import { v4 as uuidv4 } from 'uuid';
 
 const DEBUG = false;
+const COLOR_LABELS = ['red', 'yellow', 'green', 'blue', 'purple'];
 
 function App() {
   const [rootPath, setRootPath] = useState(null);
@@ -59,12 +61,16 @@ function App() {
   const { state: historyAdjustments, setState: setHistoryAdjustments, undo: undoAdjustments, redo: redoAdjustments, canUndo, canRedo, resetHistory: resetAdjustmentsHistory } = useHistoryState(INITIAL_ADJUSTMENTS);
   const [adjustments, setLiveAdjustments] = useState(INITIAL_ADJUSTMENTS);
   const [showOriginal, setShowOriginal] = useState(false);
+  const [showClipping, setShowClipping] = useState(false);
+  // Before/after split of the preview, against the unedited image or a snapshot of adjustments.
+  const [compareMode, setCompareMode] = useState(null);
   const [isTreeLoading, setIsTreeLoading] = useState(false);
   const [isViewLoading, setIsViewLoading] = useState(false);
   const [error, setError] = useState(null);
   const [histogram, setHistogram] = useState(null);
   const [waveform, setWaveform] = useState(null);
   const [isWaveformVisible, setIsWaveformVisible] = useState(false);
+  const [performanceTimings, setPerformanceTimings] = useState(null);
   const [uiVisibility, setUiVisibility] = useState({
     folderTree: true,
     filmstrip: true,
@@ -93,10 +99,11 @@ function App() {
   const [copiedMask, setCopiedMask] = useState(null);
   const [isCopied, setIsCopied] = useState(false);
   const [isPasted, setIsPasted] = useState(false);
-  const [brushSettings, setBrushSettings] = useState({ size: 50, feather: 50, tool: 'brush' });
+  const [brushSettings, setBrushSettings] = useState({ size: 50, feather: 50, flow: 25, tool: 'brush' });
   const [isCreateFolderModalOpen, setIsCreateFolderModalOpen] = useState(false);
   const [isRenameFolderModalOpen, setIsRenameFolderModalOpen] = useState(false);
   const [folderActionTarget, setFolderActionTarget] = useState(null);
+  const [keywordsTarget, setKeywordsTarget] = useState(null);
   const [confirmModalState, setConfirmModalState] = useState({ isOpen: false });
   const [customEscapeHandler, setCustomEscapeHandler] = useState(null);
   const [isGeneratingAiMask, setIsGeneratingAiMask] = useState(false);
@@ -112,6 +119,7 @@ function App() {
   const transformWrapperRef = useRef(null);
   const isProgrammaticZoom = useRef(false);
   const isInitialMount = useRef(true);
+  const developedBaseKeyRef = useRef(null);
   const [libraryScrollOffset, setLibraryScrollOffset] = useState(0);
 
   const [exportState, setExportState] = useState({
@@ -321,6 +329,24 @@ function App() {
     }
   };
 
+  const handleGenerateGraduatedSky = async () => {
+    if (!selectedImage?.path) return null;
+    setIsGeneratingAiMask(true);
+    try {
+      return await invoke('generate_graduated_sky_mask', {
+        rotation: adjustments.rotation,
+        flipHorizontal: adjustments.flipHorizontal,
+        flipVertical: adjustments.flipVertical,
+      });
+    } catch (error) {
+      console.error("Failed to generate graduated sky mask:", error);
+      setError(`Graduated Sky Failed: ${error}`);
+      return null;
+    } finally {
+      setIsGeneratingAiMask(false);
+    }
+  };
+
   const sortedImageList = useMemo(() => {
     const filteredList = imageList.filter(image => {
       if (filterCriteria.rating > 0) {
@@ -359,11 +385,11 @@ function App() {
     return list;
   }, [imageList, sortCriteria, imageRatings, filterCriteria, supportedTypes]);
 
-  const applyAdjustments = useCallback(debounce((currentAdjustments) => {
+  const applyAdjustments = useCallback(debounce((currentAdjustments, showClipping, compare) => {
     if (!selectedImage?.isReady) return;
     setIsAdjusting(true);
     setError(null);
-    invoke('apply_adjustments', { jsAdjustments: currentAdjustments }).catch(err => {
+    invoke('apply_adjustments', { jsAdjustments: currentAdjustments, showClipping, compare }).catch(err => {
       console.error("Failed to invoke apply_adjustments:", err);
       setError(`Processing failed: ${err}`);
       setIsAdjusting(false);
@@ -457,6 +483,34 @@ function App() {
       .finally(() => { isInitialMount.current = false; });
   }, []);
 
+  useEffect(() => {
+    invoke('list_interrupted_export_jobs')
+      .then(jobs => {
+        if (!jobs || jobs.length === 0) return;
+        const remaining = jobs.reduce((sum, job) => sum + job.total - job.completed, 0);
+        const details = jobs
+          .map(job => `${job.isPublish ? 'Publishing to' : 'Exporting to'} ${job.outputFolder}: ${job.completed} of ${job.total} done`)
+          .join('\n');
+        setConfirmModalState({
+          isOpen: true,
+          title: jobs.length === 1 ? 'Resume Interrupted Export?' : 'Resume Interrupted Exports?',
+          message: `RapidRAW was closed before ${jobs.length === 1 ? 'an export' : `${jobs.length} exports`} finished. ${remaining} images are left.\n\n${details}`,
+          confirmText: 'Resume',
+          cancelText: 'Discard',
+          onConfirm: () => {
+            setExportState({ status: 'exporting', progress: { current: jobs[0].completed, total: jobs[0].total }, errorMessage: '' });
+            jobs.forEach(job => invoke('resume_interrupted_export_job', { jobId: job.jobId })
+              .catch(err => setError(`Failed to resume export: ${err}`)));
+          },
+          onCancel: () => {
+            jobs.forEach(job => invoke('discard_interrupted_export_job', { jobId: job.jobId })
+              .catch(err => console.error("Failed to discard interrupted export:", err)));
+          },
+        });
+      })
+      .catch(err => console.error("Failed to check for interrupted exports:", err));
+  }, []);
+
   useEffect(() => {
     if (isInitialMount.current || !appSettings) return;
     if (JSON.stringify(appSettings.uiVisibility) !== JSON.stringify(uiVisibility)) {
@@ -601,6 +655,16 @@ function App() {
     if (currentFolderPath) handleSelectSubfolder(currentFolderPath, false);
   }, [currentFolderPath, handleSelectSubfolder]);
 
+  const handleToggleCullingMode = useCallback(() => {
+    if (!appSettings) return;
+    const newSettings = { ...appSettings, cullingMode: !appSettings.cullingMode };
+    setAppSettings(newSettings);
+    // Thumbnails are cached separately per mode, so reload once the backend has the new setting.
+    invoke('save_settings', { settings: newSettings })
+      .then(() => handleLibraryRefresh())
+      .catch(err => console.error("Failed to save settings:", err));
+  }, [appSettings, handleLibraryRefresh]);
+
   const handleToggleFolder = useCallback((path) => {
     setExpandedFolders(prev => {
       const newSet = new Set(prev);
@@ -642,6 +706,7 @@ function App() {
     setHistogram(null);
     setWaveform(null);
     setIsWaveformVisible(false);
+    setPerformanceTimings(null);
     setActiveMaskId(null);
     setActiveMaskContainerId(null);
     setAiTool(null);
@@ -738,10 +803,10 @@ function App() {
     setIsPasted(true);
   }, [copiedAdjustments, multiSelectedPaths, selectedImage, setAdjustments]);
 
-  const handleAutoAdjustments = async () => {
+  const handleAutoAdjustments = async (mode = 'global', region = null) => {
     if (!selectedImage) return;
     try {
-      const autoAdjustments = await invoke('calculate_auto_adjustments');
+      const autoAdjustments = await invoke('calculate_auto_adjustments', { mode, region, adjustments });
       setAdjustments(prev => {
         const newAdjustments = { ...prev, ...autoAdjustments };
         newAdjustments.sectionVisibility = {
@@ -791,6 +856,15 @@ function App() {
       });
   }, [multiSelectedPaths, selectedImage, libraryActivePath, adjustments.rating, libraryActiveAdjustments.rating, setAdjustments]);
 
+  const handleBatchOperation = useCallback((paths, operation) => {
+    if (paths.length === 0) return;
+    invoke('batch_apply_operation', { paths, operation })
+      .catch(err => {
+        console.error("Failed to apply batch operation:", err);
+        setError(`Failed to update images: ${err}`);
+      });
+  }, []);
+
   const closeConfirmModal = () => setConfirmModalState({ ...confirmModalState, isOpen: false });
 
   const handlePasteFiles = useCallback(async (mode = 'copy') => {
@@ -822,7 +896,7 @@ function App() {
     if (selectedImage?.path === path) return;
     applyAdjustments.cancel();
     debouncedSave.cancel();
-    setSelectedImage({ path, thumbnailUrl: thumbnails[path], isReady: false, originalUrl: null, width: 0, height: 0, metadata: null, exif: null, isRaw: false });
+    setSelectedImage({ path, thumbnailUrl: thumbnails[path], isReady: false, originalUrl: null, width: 0, height: 0, metadata: null, exif: null, isRaw: false, isHdr: false });
     setMultiSelectedPaths([path]);
     setLibraryActivePath(null);
     setIsViewLoading(true);
@@ -834,6 +908,7 @@ function App() {
     setLiveAdjustments(INITIAL_ADJUSTMENTS);
     resetAdjustmentsHistory(INITIAL_ADJUSTMENTS);
     setShowOriginal(false);
+    setCompareMode(null);
     setActiveMaskId(null);
     setActiveMaskContainerId(null);
     setAiTool(null);
@@ -862,6 +937,7 @@ function App() {
     setLibraryActivePath,
     setMultiSelectedPaths,
     handleRate,
+    handleToggleCullingMode,
     handleDeleteSelected,
     handleCopyAdjustments,
     handlePasteAdjustments,
@@ -872,6 +948,9 @@ function App() {
     handleBackToLibrary,
     handleToggleFullScreen,
     setShowOriginal,
+    setShowClipping,
+    setCompareMode,
+    adjustments,
     handleRightPanelSelect,
     setIsWaveformVisible,
     handleZoomChange,
@@ -884,8 +963,14 @@ function App() {
     const listeners = [
       listen('preview-update-final', (event) => { if (isEffectActive) { setFinalPreviewUrl(event.payload); setIsAdjusting(false); } }),
       listen('preview-update-uncropped', (event) => { if (isEffectActive) setUncroppedAdjustedPreviewUrl(event.payload); }),
+      listen('embedded-preview', (event) => {
+        if (!isEffectActive) return;
+        const { path, previewBase64 } = event.payload;
+        setSelectedImage(currentSelected => (currentSelected && currentSelected.path === path && !currentSelected.isReady) ? { ...currentSelected, embeddedPreviewUrl: previewBase64 } : currentSelected);
+      }),
       listen('histogram-update', (event) => { if (isEffectActive) setHistogram(event.payload); }),
       listen('waveform-update', (event) => { if (isEffectActive) setWaveform(event.payload); }),
+      listen('performance-update', (event) => { if (isEffectActive) setPerformanceTimings(event.payload); }),
       listen('thumbnail-generated', (event) => { if (isEffectActive) { const { path, rating } = event.payload; if (rating !== undefined) setImageRatings(prev => ({ ...prev, [path]: rating })); } }),
       listen('ai-model-download-start', (event) => { if (isEffectActive) setAiModelDownloadStatus(event.payload); }),
       listen('ai-model-download-finish', () => { if (isEffectActive) setAiModelDownloadStatus(null); }),
@@ -944,9 +1029,32 @@ function App() {
   }, [libraryActivePath]);
 
   useEffect(() => {
-    if (selectedImage?.isReady) { applyAdjustments(adjustments); debouncedSave(selectedImage.path, adjustments); }
+    if (selectedImage?.isReady) { applyAdjustments(adjustments, showClipping, compareMode); debouncedSave(selectedImage.path, adjustments); }
     return () => { applyAdjustments.cancel(); debouncedSave.cancel(); }
-  }, [adjustments, selectedImage?.path, selectedImage?.isReady, applyAdjustments, debouncedSave]);
+  }, [adjustments, selectedImage?.path, selectedImage?.isReady, applyAdjustments, debouncedSave, showClipping, compareMode]);
+
+  useEffect(() => {
+    if (!selectedImage?.isReady) return;
+    const baseKey = getBaseImageKey(adjustments);
+    if (baseKey === developedBaseKeyRef.current) return;
+    const path = selectedImage.path;
+    // Developing again is slow, so sliders that need it only do so once they come to rest.
+    const timer = setTimeout(() => {
+      developedBaseKeyRef.current = baseKey;
+      setIsAdjusting(true);
+      invoke('reload_base_image', { path, jsAdjustments: adjustments })
+        .then(originalBase64 => {
+          setSelectedImage(currentSelected => currentSelected?.path === path ? { ...currentSelected, originalUrl: originalBase64 } : currentSelected);
+          applyAdjustments(adjustments, showClipping, compareMode);
+        })
+        .catch(err => {
+          console.error("Failed to reload image:", err);
+          setError(`Failed to reload image: ${err}`);
+          setIsAdjusting(false);
+        });
+    }, 300);
+    return () => clearTimeout(timer);
+  }, [adjustments, selectedImage?.path, selectedImage?.isReady, applyAdjustments, showClipping, compareMode]);
 
   useEffect(() => {
     if (activeRightPanel === 'crop' && selectedImage?.isReady) debouncedGenerateUncroppedPreview(adjustments);
@@ -1069,7 +1177,7 @@ function App() {
           const histData = await invoke('generate_histogram');
           if (!isEffectActive) return;
           setSelectedImage(currentSelected => {
-            if (currentSelected && currentSelected.path === selectedImage.path) return { ...currentSelected, originalUrl: loadImageResult.original_base64, width: loadImageResult.width, height: loadImageResult.height, metadata: loadImageResult.metadata, exif: loadImageResult.exif, isRaw: loadImageResult.is_raw, isReady: true };
+            if (currentSelected && currentSelected.path === selectedImage.path) return { ...currentSelected, originalUrl: loadImageResult.original_base64, width: loadImageResult.width, height: loadImageResult.height, metadata: loadImageResult.metadata, exif: loadImageResult.exif, isRaw: loadImageResult.is_raw, isHdr: loadImageResult.is_hdr, isReady: true };
             return currentSelected;
           });
           
@@ -1085,6 +1193,7 @@ function App() {
           if (loadImageResult.metadata.adjustments && !loadImageResult.metadata.adjustments.is_null) {
             initialAdjusts = normalizeLoadedAdjustments(loadImageResult.metadata.adjustments);
           }
+          developedBaseKeyRef.current = getBaseImageKey(initialAdjusts);
           setLiveAdjustments(initialAdjusts);
           resetAdjustmentsHistory(initialAdjusts);
           setHistogram(histData);
@@ -1111,8 +1220,31 @@ function App() {
       .catch(err => { console.error("Failed to reset adjustments:", err); setError(`Failed to reset adjustments: ${err}`); });
   };
 
+  // Point under the cursor, normalized to the displayed (cropped) frame, or null when the
+  // click was outside the image.
+  const getNormalizedImagePoint = (event) => {
+    const img = event.target;
+    if (!(img instanceof HTMLImageElement) || !img.naturalWidth || !img.naturalHeight) return null;
+    const rect = img.getBoundingClientRect();
+    const scale = Math.min(rect.width / img.naturalWidth, rect.height / img.naturalHeight);
+    const width = img.naturalWidth * scale;
+    const height = img.naturalHeight * scale;
+    const x = (event.clientX - rect.left - (rect.width - width) / 2) / width;
+    const y = (event.clientY - rect.top - (rect.height - height) / 2) / height;
+    if (x < 0 || x > 1 || y < 0 || y > 1) return null;
+    return { x, y };
+  };
+
   const handleEditorContextMenu = (event) => {
     event.preventDefault(); event.stopPropagation();
+    const spotPoint = getNormalizedImagePoint(event);
+    const SPOT_SIZE = 0.04;
+    const spotRegion = spotPoint && {
+      x: Math.max(0, spotPoint.x - SPOT_SIZE / 2),
+      y: Math.max(0, spotPoint.y - SPOT_SIZE / 2),
+      width: SPOT_SIZE,
+      height: SPOT_SIZE,
+    };
     const options = [
       { label: 'Undo', icon: Undo, onClick: undo, disabled: !canUndo },
       { label: 'Redo', icon: Redo, onClick: redo, disabled: !canRedo },
@@ -1120,7 +1252,12 @@ function App() {
       { label: 'Copy Adjustments', icon: Copy, onClick: handleCopyAdjustments },
       { label: 'Paste Adjustments', icon: ClipboardPaste, onClick: handlePasteAdjustments, disabled: copiedAdjustments === null },
       { type: 'separator' },
-      { label: 'Auto Adjust', icon: Aperture, onClick: handleAutoAdjustments },
+      { label: 'Auto Adjust', icon: Aperture, submenu: [
+        { label: 'Whole Frame', onClick: () => handleAutoAdjustments('global') },
+        { label: 'Protect Highlights', onClick: () => handleAutoAdjustments('protectHighlights') },
+        { label: 'Weighted on Subject', onClick: () => handleAutoAdjustments('subjectWeighted') },
+        { label: 'Spot Meter Here', onClick: () => handleAutoAdjustments('spot', spotRegion), disabled: !spotRegion },
+      ] },
       { label: 'Set Rating', icon: Star, submenu: [0, 1, 2, 3, 4, 5].map(rating => ({ label: rating === 0 ? 'No Rating' : `${rating} Star${rating !== 1 ? 's' : ''}`, onClick: () => handleRate(rating) })) },
       { type: 'separator' },
       { label: 'Reset Adjustments', icon: RotateCcw, onClick: () => setAdjustments(prev => ({ ...INITIAL_ADJUSTMENTS, rating: prev.rating, aiPatches: [] })) },
@@ -1145,6 +1282,35 @@ function App() {
     const deleteLabel = isSingleSelection ? 'Delete Image' : `Delete ${selectionCount} Images`;
     const copyLabel = isSingleSelection ? 'Copy Image' : `Copy ${selectionCount} Images`;
     const autoAdjustLabel = isSingleSelection ? 'Auto Adjust Image' : `Auto Adjust ${selectionCount} Images`;
+    const isRawTarget = supportedTypes?.raw.includes(path.split('.').pop()?.toLowerCase() || '');
+    // Sony writes the frames of a pixel shift burst to separate files, Pentax bursts are merged on load.
+    const isPixelShiftCandidate = selectionCount === 4 && finalSelection.every(p => p.toLowerCase().endsWith('.arw'));
+
+    const handleMergePixelShift = async () => {
+      try {
+        const bursts = await invoke('find_pixel_shift_bursts', { paths: finalSelection });
+        if (bursts.length !== 1) {
+          setError('The selected images are not one pixel shift burst.');
+          return;
+        }
+        await invoke('merge_pixel_shift_burst', { paths: bursts[0].paths });
+        handleLibraryRefresh();
+      } catch (err) {
+        console.error("Failed to merge pixel shift burst:", err);
+        setError(`Failed to merge pixel shift burst: ${err}`);
+      }
+    };
+
+    const handleImportBadPixelMap = async () => {
+      const mapPath = await open({ multiple: false, filters: [{ name: 'Bad Pixel Map', extensions: ['txt', 'badpixels'] }] });
+      if (!mapPath) return;
+      try {
+        await invoke('import_bad_pixel_map', { imagePath: finalSelection[0], mapPath });
+      } catch (err) {
+        console.error("Failed to import bad pixel map:", err);
+        setError(`Could not import bad pixel map: ${err}`);
+      }
+    };
 
     const handleApplyAutoAdjustmentsToSelection = () => {
       if (finalSelection.length === 0) return;
@@ -1191,8 +1357,22 @@ function App() {
       { type: 'separator' },
       { label: copyLabel, icon: Copy, onClick: () => { setCopiedFilePaths(finalSelection); setIsCopied(true); } },
       { label: 'Duplicate Image', icon: CopyPlus, disabled: !isSingleSelection, onClick: async () => { try { await invoke('duplicate_file', { path: finalSelection[0] }); handleLibraryRefresh(); } catch (err) { console.error("Failed to duplicate file:", err); setError(`Failed to duplicate file: ${err}`); } } },
+      { label: 'Use as Dark Frame', icon: Moon, disabled: !isSingleSelection || !isRawTarget, onClick: () => { invoke('register_dark_frame', { path: finalSelection[0] }).catch(err => setError(`Could not register dark frame: ${err}`)); } },
+      { label: 'Merge Pixel Shift', icon: Layers, disabled: !isPixelShiftCandidate, onClick: handleMergePixelShift },
+      { label: 'Use as Flat Field', icon: SunDim, disabled: !isSingleSelection || !isRawTarget, onClick: () => { invoke('register_flat_field', { path: finalSelection[0] }).catch(err => setError(`Could not register flat field: ${err}`)); } },
+      { label: 'Bad Pixel Map', icon: Grid3x3, disabled: !isSingleSelection || !isRawTarget, submenu: [
+        { label: 'Import for This Camera...', onClick: handleImportBadPixelMap },
+        { label: 'Remove for This Camera', onClick: () => { invoke('remove_bad_pixel_map', { imagePath: finalSelection[0] }).catch(err => setError(`Could not remove bad pixel map: ${err}`)); } },
+      ] },
       { type: 'separator' },
       { label: 'Set Rating', icon: Star, submenu: [0, 1, 2, 3, 4, 5].map(rating => ({ label: rating === 0 ? 'No Rating' : `${rating} Star${rating !== 1 ? 's' : ''}`, onClick: () => handleRate(rating) })) },
+      { label: 'Set Color Label', icon: Tag, submenu: [...COLOR_LABELS, null].map(label => ({ label: label ? label.charAt(0).toUpperCase() + label.slice(1) : 'No Label', onClick: () => handleBatchOperation(finalSelection, { type: 'label', label }) })) },
+      { label: 'Edit Keywords...', icon: Tags, onClick: () => {
+          invoke('load_metadata', { path })
+            .then(metadata => setKeywordsTarget({ paths: finalSelection, keywords: metadata.keywords || [] }))
+            .catch(() => setKeywordsTarget({ paths: finalSelection, keywords: [] }));
+        },
+      },
       { type: 'separator' },
       { label: 'Show in File Explorer', icon: Folder, disabled: !isSingleSelection, onClick: () => { invoke('show_in_finder', { path: finalSelection[0] }).catch(err => setError(`Could not show file in explorer: ${err}`)); } },
       { label: resetLabel, icon: RotateCcw, onClick: () => {
@@ -1257,9 +1437,16 @@ function App() {
     const numCopied = copiedFilePaths.length;
     const copyPastedLabel = numCopied === 1 ? 'Copy image here' : `Copy ${numCopied} images here`;
     const movePastedLabel = numCopied === 1 ? 'Move image here' : `Move ${numCopied} images here`;
+    const proofingFolders = appSettings?.proofing?.folders || [];
+    const isProofing = proofingFolders.includes(targetPath);
+    const handleToggleProofing = () => {
+      const folders = isProofing ? proofingFolders.filter(f => f !== targetPath) : [...proofingFolders, targetPath];
+      handleSettingsChange({ ...appSettings, proofing: { ...appSettings?.proofing, folders } });
+    };
     const options = [
       { label: 'New Folder', icon: FolderPlus, onClick: () => { setFolderActionTarget(targetPath); setIsCreateFolderModalOpen(true); } },
       { label: 'Rename Folder', icon: FileEdit, disabled: isRoot, onClick: () => { setFolderActionTarget(targetPath); setIsRenameFolderModalOpen(true); } },
+      { label: isProofing ? 'Turn Off Proofing Mode' : 'Turn On Proofing Mode', icon: Stamp, onClick: handleToggleProofing },
       { type: 'separator' },
       { label: 'Paste', icon: ClipboardPaste, disabled: copiedFilePaths.length === 0, submenu: [
           { label: copyPastedLabel, onClick: async () => { try { await invoke('copy_files', { sourcePaths: copiedFilePaths, destinationFolder: targetPath }); if (targetPath === currentFolderPath) handleLibraryRefresh(); } catch (err) { setError(`Failed to copy files: ${err}`); } } },
@@ -1307,10 +1494,15 @@ function App() {
               uncroppedAdjustedPreviewUrl={uncroppedAdjustedPreviewUrl}
               showOriginal={showOriginal}
               setShowOriginal={setShowOriginal}
+              showClipping={showClipping}
+              setShowClipping={setShowClipping}
+              compareMode={compareMode}
+              setCompareMode={setCompareMode}
               isAdjusting={isAdjusting}
               waveform={waveform}
               isWaveformVisible={isWaveformVisible}
               onCloseWaveform={() => setIsWaveformVisible(false)}
+              performanceTimings={appSettings?.performanceOverlay ? performanceTimings : null}
               onBackToLibrary={handleBackToLibrary}
               isLoading={isViewLoading}
               isFullScreen={isFullScreen}
@@ -1398,6 +1590,7 @@ function App() {
                   isGeneratingAiMask={isGeneratingAiMask} 
                   aiModelDownloadStatus={aiModelDownloadStatus} 
                   onGenerateAiForegroundMask={handleGenerateAiForegroundMask} 
+                  onGenerateGraduatedSky={handleGenerateGraduatedSky} 
                   setIsMaskControlHovered={setIsMaskControlHovered}
                 />}
                 {renderedRightPanel === 'presets' && <PresetsPanel adjustments={adjustments} setAdjustments={setAdjustments} selectedImage={selectedImage} activePanel={activeRightPanel} />}
@@ -1453,6 +1646,7 @@ function App() {
             setFilterCriteria={setFilterCriteria}
             onSettingsChange={handleSettingsChange}
             onLibraryRefresh={handleLibraryRefresh}
+            onToggleCullingMode={handleToggleCullingMode}
             theme={theme}
             initialScrollOffset={libraryScrollOffset}
             onScroll={handleLibraryScroll}
@@ -1544,6 +1738,12 @@ function App() {
         onSave={handleRenameFolder}
         currentName={folderActionTarget ? folderActionTarget.split(/[\\/]/).pop() : ''}
       />
+      <KeywordsModal
+        isOpen={keywordsTarget !== null}
+        onClose={() => setKeywordsTarget(null)}
+        onSave={(keywords) => handleBatchOperation(keywordsTarget.paths, { type: 'keywords', keywords })}
+        currentKeywords={keywordsTarget?.keywords}
+      />
       <ConfirmModal
         {...confirmModalState}
         onClose={closeConfirmModal}
@@ -1,6 +1,14 @@
 import Slider from '../ui/Slider';
+import Dropdown from '../ui/Dropdown';
 
-export default function BasicAdjustments({ adjustments, setAdjustments }) {
+const toneMapperOptions = [
+  { value: null, label: 'Default' },
+  { value: 'reinhard', label: 'Reinhard' },
+  { value: 'filmic', label: 'Filmic' },
+  { value: 'agx', label: 'AgX' },
+];
+
+export default function BasicAdjustments({ adjustments, setAdjustments, isHdr }) {
   const handleAdjustmentChange = (key, value) => {
     const numericValue = parseFloat(value);
     setAdjustments(prev => ({ ...prev, [key]: numericValue }));
@@ -8,6 +16,17 @@ export default function BasicAdjustments({ adjustments, setAdjustments }) {
 
   return (
     <div>
+      {isHdr && (
+        <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
+          <p className="text-md font-semibold mb-2 text-primary">Tone Mapping</p>
+          <Dropdown
+            options={toneMapperOptions}
+            value={adjustments.toneMapper ?? null}
+            onChange={(value) => setAdjustments(prev => ({ ...prev, toneMapper: value }))}
+          />
+          <p className="text-xs text-text-secondary mt-2">Maps the full range of this HDR source to the screen before the adjustments below.</p>
+        </div>
+      )}
       <Slider
         label="Exposure"
         value={adjustments.exposure}
@@ -1,7 +1,11 @@
-import { useState } from 'react';
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import GroupHeader from './GroupHeader';
 import Slider from '../ui/Slider';
+import Dropdown from '../ui/Dropdown';
 import ColorWheel from '../ui/ColorWheel';
-import { INITIAL_ADJUSTMENTS } from '../../utils/adjustments';
+import HueCurves from './HueCurves';
+import { INITIAL_ADJUSTMENTS, INITIAL_RAW_WHITE_BALANCE } from '../../utils/adjustments';
 
 const ColorSwatch = ({ color, name, isActive, onClick }) => (
     <button
@@ -85,6 +89,169 @@ const ColorGradingPanel = ({ adjustments, setAdjustments }) => {
     );
 };
 
+const SplitToningPanel = ({ adjustments, setAdjustments }) => {
+    const splitToning = adjustments.splitToning || INITIAL_ADJUSTMENTS.splitToning;
+
+    const handleChange = (key, value) => {
+        setAdjustments(prev => ({
+          ...prev,
+          splitToning: {
+            ...(prev.splitToning || INITIAL_ADJUSTMENTS.splitToning),
+            [key]: parseFloat(value),
+          }
+        }));
+    };
+
+    return (
+        <div>
+            <Slider
+                label="Highlight Hue"
+                value={splitToning.highlightHue}
+                onChange={(e) => handleChange('highlightHue', e.target.value)}
+                min="0" max="360" step="1"
+                defaultValue={INITIAL_ADJUSTMENTS.splitToning.highlightHue}
+            />
+            <Slider
+                label="Highlight Saturation"
+                value={splitToning.highlightSaturation}
+                onChange={(e) => handleChange('highlightSaturation', e.target.value)}
+                min="0" max="100" step="1"
+                defaultValue={0}
+            />
+            <Slider
+                label="Balance"
+                value={splitToning.balance}
+                onChange={(e) => handleChange('balance', e.target.value)}
+                min="-100" max="100" step="1"
+                defaultValue={0}
+            />
+            <Slider
+                label="Shadow Hue"
+                value={splitToning.shadowHue}
+                onChange={(e) => handleChange('shadowHue', e.target.value)}
+                min="0" max="360" step="1"
+                defaultValue={INITIAL_ADJUSTMENTS.splitToning.shadowHue}
+            />
+            <Slider
+                label="Shadow Saturation"
+                value={splitToning.shadowSaturation}
+                onChange={(e) => handleChange('shadowSaturation', e.target.value)}
+                min="0" max="100" step="1"
+                defaultValue={0}
+            />
+        </div>
+    );
+};
+
+const rawWhiteBalanceOptions = [
+    { value: 'asShot', label: 'As Shot' },
+    { value: 'auto', label: 'Auto' },
+    { value: 'custom', label: 'Custom' },
+];
+
+const formatTemperature = (wb) => wb ? `${Math.round(wb.temperature / 50) * 50} K, tint ${Math.round(wb.tint)}` : 'unknown';
+
+const RawWhiteBalance = ({ adjustments, setAdjustments, imagePath }) => {
+    const rawWhiteBalance = adjustments.rawWhiteBalance || INITIAL_RAW_WHITE_BALANCE;
+    const [info, setInfo] = useState(null);
+
+    useEffect(() => {
+        setInfo(null);
+        if (!imagePath) return;
+        let isActive = true;
+        invoke('get_raw_white_balance', { path: imagePath })
+            .then(result => { if (isActive) setInfo(result); })
+            .catch(err => console.error("Failed to read white balance:", err));
+        return () => { isActive = false; };
+    }, [imagePath]);
+
+    const update = (changes) => {
+        setAdjustments(prev => ({
+            ...prev,
+            rawWhiteBalance: { ...(prev.rawWhiteBalance || INITIAL_RAW_WHITE_BALANCE), ...changes },
+        }));
+    };
+
+    // Custom starts from the white balance that was showing, so switching does not jump.
+    const handleModeChange = (mode) => {
+        const start = mode === 'custom' && (rawWhiteBalance.mode === 'auto' ? info?.auto : info?.asShot);
+        update(start ? { mode, temperature: Math.round(start.temperature), tint: Math.round(start.tint) } : { mode });
+    };
+
+    return (
+        <div className="mt-2 pt-2 border-t border-surface">
+            <p className="text-sm font-semibold mb-2 text-primary">RAW White Balance</p>
+            <Dropdown
+                options={rawWhiteBalanceOptions}
+                value={rawWhiteBalance.mode}
+                onChange={handleModeChange}
+            />
+            {rawWhiteBalance.mode === 'custom' ? (
+                <div className="mt-2">
+                    <Slider
+                        label="Kelvin"
+                        value={rawWhiteBalance.temperature}
+                        onChange={(e) => update({ temperature: parseFloat(e.target.value) })}
+                        min="2000" max="15000" step="50"
+                        defaultValue={Math.round(info?.asShot?.temperature ?? INITIAL_RAW_WHITE_BALANCE.temperature)}
+                    />
+                    <Slider
+                        label="Tint"
+                        value={rawWhiteBalance.tint}
+                        onChange={(e) => update({ tint: parseFloat(e.target.value) })}
+                        min="-150" max="150" step="1"
+                        defaultValue={Math.round(info?.asShot?.tint ?? 0)}
+                    />
+                </div>
+            ) : (
+                <p className="text-xs text-text-secondary mt-2">
+                    {formatTemperature(rawWhiteBalance.mode === 'auto' ? info?.auto : info?.asShot)}
+                </p>
+            )}
+        </div>
+    );
+};
+
+const CALIBRATION_PRIMARIES = [
+    { name: 'red', label: 'Red Primary' },
+    { name: 'green', label: 'Green Primary' },
+    { name: 'blue', label: 'Blue Primary' },
+];
+
+// Turns and saturates the primaries of the camera matrix, for looks and for matching cameras.
+const CalibrationPanel = ({ adjustments, setAdjustments }) => {
+    const calibration = adjustments.calibration || INITIAL_ADJUSTMENTS.calibration;
+
+    const handleChange = (key, value) => {
+        setAdjustments(prev => ({
+            ...prev,
+            calibration: { ...(prev.calibration || INITIAL_ADJUSTMENTS.calibration), [key]: parseFloat(value) },
+        }));
+    };
+
+    return (
+        <div>
+            {CALIBRATION_PRIMARIES.map(({ name, label }) => (
+                <div key={name} className="mb-2">
+                    <p className="text-sm font-semibold mb-1 text-primary">{label}</p>
+                    <Slider
+                        label="Hue"
+                        value={calibration[`${name}Hue`]}
+                        onChange={(e) => handleChange(`${name}Hue`, e.target.value)}
+                        min="-100" max="100" step="1"
+                    />
+                    <Slider
+                        label="Saturation"
+                        value={calibration[`${name}Saturation`]}
+                        onChange={(e) => handleChange(`${name}Saturation`, e.target.value)}
+                        min="-100" max="100" step="1"
+                    />
+                </div>
+            ))}
+        </div>
+    );
+};
+
 const HSL_COLORS = [
     { name: 'reds', color: '#f87171' },
     { name: 'oranges', color: '#fb923c' },
@@ -96,7 +263,7 @@ const HSL_COLORS = [
     { name: 'magentas', color: '#f472b6' },
 ];
 
-export default function ColorPanel({ adjustments, setAdjustments }) {
+export default function ColorPanel({ adjustments, setAdjustments, isRaw, imagePath, isForMask = false }) {
     const [activeColor, setActiveColor] = useState('reds');
 
     const handleGlobalChange = (key, value) => {
@@ -121,7 +288,7 @@ export default function ColorPanel({ adjustments, setAdjustments }) {
     return (
         <div> 
             <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
-                <p className="text-md font-semibold mb-2 text-primary">White Balance</p>
+                <GroupHeader title="White Balance" group="whiteBalance" adjustments={adjustments} setAdjustments={setAdjustments} />
                 <Slider
                     label="Temperature"
                     value={adjustments.temperature || 0}
@@ -134,10 +301,11 @@ export default function ColorPanel({ adjustments, setAdjustments }) {
                     onChange={(e) => handleGlobalChange('tint', e.target.value)}
                     min="-100" max="100" step="1"
                 />
+                {isRaw && <RawWhiteBalance adjustments={adjustments} setAdjustments={setAdjustments} imagePath={imagePath} />}
             </div>
 
             <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
-                <p className="text-md font-semibold mb-2 text-primary">Presence</p>
+                <GroupHeader title="Presence" group="saturation" adjustments={adjustments} setAdjustments={setAdjustments} />
                 <Slider
                     label="Vibrance"
                     value={adjustments.vibrance || 0}
@@ -153,12 +321,19 @@ export default function ColorPanel({ adjustments, setAdjustments }) {
             </div>
 
             <div className="p-2 bg-bg-tertiary rounded-md mt-4">
-                <p className="text-md font-semibold mb-3 text-primary">Color Grading</p>
+                <GroupHeader title="Color Grading" group="colorGrading" adjustments={adjustments} setAdjustments={setAdjustments} className="mb-3" />
                 <ColorGradingPanel adjustments={adjustments} setAdjustments={setAdjustments} />
             </div>
 
+            {!isForMask && (
+                <div className="p-2 bg-bg-tertiary rounded-md mt-4">
+                    <GroupHeader title="Split Toning" group="splitToning" adjustments={adjustments} setAdjustments={setAdjustments} className="mb-3" />
+                    <SplitToningPanel adjustments={adjustments} setAdjustments={setAdjustments} />
+                </div>
+            )}
+
             <div className="p-2 bg-bg-tertiary rounded-md mt-4">
-                <p className="text-md font-semibold mb-3 text-primary">Color Mixer</p>
+                <GroupHeader title="Color Mixer" group="hsl" adjustments={adjustments} setAdjustments={setAdjustments} className="mb-3" />
                 <div className="flex justify-between mb-4 px-1">
                     {HSL_COLORS.map(({ name, color }) => (
                         <ColorSwatch
@@ -189,6 +364,15 @@ export default function ColorPanel({ adjustments, setAdjustments }) {
                     min="-100" max="100" step="1"
                 />
             </div>
+
+            {!isForMask && <HueCurves adjustments={adjustments} setAdjustments={setAdjustments} />}
+
+            {!isForMask && (
+                <div className="p-2 bg-bg-tertiary rounded-md mt-4">
+                    <GroupHeader title="Calibration" group="calibration" adjustments={adjustments} setAdjustments={setAdjustments} className="mb-3" />
+                    <CalibrationPanel adjustments={adjustments} setAdjustments={setAdjustments} />
+                </div>
+            )}
         </div>
     );
 }
\ No newline at end of file
@@ -1,4 +1,5 @@
 import { useState, useRef, useEffect } from 'react';
+import Levels from './Levels';
 
 function getCurvePath(points) {
   if (points.length < 2) return '';
@@ -79,7 +80,7 @@ function getHistogramPath(data) {
     return `M0,255 L${pathData} L255,255 Z`;
 }
 
-export default function CurveGraph({ adjustments, setAdjustments, histogram, theme }) {
+export default function CurveGraph({ adjustments, setAdjustments, histogram, theme, isForMask = false }) {
   const [activeChannel, setActiveChannel] = useState('luma');
   const [draggingPointIndex, setDraggingPointIndex] = useState(null);
   const [localPoints, setLocalPoints] = useState(null);
@@ -223,6 +224,16 @@ export default function CurveGraph({ adjustments, setAdjustments, histogram, the
             {channel.charAt(0).toUpperCase()}
           </button>
         ))}
+        {activeChannel === 'luma' && (
+          <button
+            onClick={() => setAdjustments(prev => ({ ...prev, lumaCurveMode: prev.lumaCurveMode === 'luminosity' ? 'rgb' : 'luminosity' }))}
+            className={`ml-auto px-2 h-7 rounded-full text-xs transition-colors
+              ${adjustments.lumaCurveMode === 'luminosity' ? 'bg-accent text-primary' : 'bg-surface-secondary text-text-secondary'}`}
+            title="Apply the curve to Lab lightness only, so contrast changes don't shift saturation"
+          >
+            Luminosity
+          </button>
+        )}
       </div>
 
       <div 
@@ -256,6 +267,8 @@ export default function CurveGraph({ adjustments, setAdjustments, histogram, the
           ))}
         </svg>
       </div>
+
+      {!isForMask && <Levels adjustments={adjustments} setAdjustments={setAdjustments} channel={activeChannel} />}
     </div>
   );
 }
\ No newline at end of file
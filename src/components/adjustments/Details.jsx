@@ -1,25 +1,99 @@
+import { useState, useEffect } from 'react';
+import { invoke } from '@tauri-apps/api/core';
 import Slider from '../ui/Slider';
+import Dropdown from '../ui/Dropdown';
+import Switch from '../ui/Switch';
+import GroupHeader from './GroupHeader';
+import RawHistogram from './RawHistogram';
+import { INITIAL_ADJUSTMENTS } from '../../utils/adjustments';
+
+const demosaicOptions = [
+  { value: null, label: 'Default (from Settings)' },
+  { value: 'standard', label: 'Standard (PPG)' },
+  { value: 'best', label: 'Best Detail (AMaZE)' },
+  { value: 'lowNoise', label: 'Low Noise (LMMSE)' },
+];
+
+const formatLevels = (levels) => levels && [...new Set(levels.map(Math.round))].join(' / ');
+
+export default function DetailsPanel({ adjustments, setAdjustments, isRaw, imagePath, isForMask = false }) {
+  const [metadataLevels, setMetadataLevels] = useState(null);
+
+  useEffect(() => {
+    setMetadataLevels(null);
+    if (!isRaw || !imagePath) return;
+    let isActive = true;
+    invoke('get_raw_levels', { path: imagePath })
+      .then(levels => { if (isActive) setMetadataLevels(levels); })
+      .catch(err => console.error("Failed to read raw levels:", err));
+    return () => { isActive = false; };
+  }, [isRaw, imagePath]);
 
-export default function DetailsPanel({ adjustments, setAdjustments }) {
   const handleAdjustmentChange = (key, value) => {
     const numericValue = parseInt(value, 10);
     setAdjustments(prev => ({ ...prev, [key]: numericValue }));
   };
 
+  const defringe = adjustments.defringe || INITIAL_ADJUSTMENTS.defringe;
+
+  const handleDefringeChange = (key, value) => {
+    setAdjustments(prev => ({
+      ...prev,
+      defringe: { ...(prev.defringe || INITIAL_ADJUSTMENTS.defringe), [key]: parseFloat(value) },
+    }));
+  };
+
+  // An empty field goes back to the level from the metadata.
+  const handleLevelChange = (key, value) => {
+    const level = value === '' ? null : Math.max(0, parseInt(value, 10));
+    if (Number.isNaN(level)) return;
+    setAdjustments(prev => ({ ...prev, rawLevels: { ...(prev.rawLevels || {}), [key]: level } }));
+  };
+
   return (
     <div>
       <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
-        <p className="text-md font-semibold mb-2 text-primary">Sharpening</p>
+        <GroupHeader title="Sharpening" group="sharpening" adjustments={adjustments} setAdjustments={setAdjustments} />
         <Slider
-          label="Sharpness"
+          label="Amount"
           value={adjustments.sharpness}
           onChange={(e) => handleAdjustmentChange('sharpness', e.target.value)}
           min="0" max="100" step="1"
         />
+        {!isForMask && (
+          <>
+            <Slider
+              label="Radius"
+              value={adjustments.sharpenRadius ?? 1.0}
+              onChange={(e) => setAdjustments(prev => ({ ...prev, sharpenRadius: parseFloat(e.target.value) }))}
+              min="0.5" max="3" step="0.1"
+              defaultValue={1.0}
+            />
+            <Slider
+              label="Detail"
+              value={adjustments.sharpenDetail ?? 25}
+              onChange={(e) => handleAdjustmentChange('sharpenDetail', e.target.value)}
+              min="0" max="100" step="1"
+              defaultValue={25}
+            />
+            <Slider
+              label="Masking"
+              value={adjustments.sharpenMasking || 0}
+              onChange={(e) => handleAdjustmentChange('sharpenMasking', e.target.value)}
+              min="0" max="100" step="1"
+            />
+          </>
+        )}
       </div>
 
       <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
-        <p className="text-md font-semibold mb-2 text-primary">Presence</p>
+        <GroupHeader title="Presence" group="presence" adjustments={adjustments} setAdjustments={setAdjustments} />
+        <Slider
+          label="Texture"
+          value={adjustments.texture || 0}
+          onChange={(e) => handleAdjustmentChange('texture', e.target.value)}
+          min="-100" max="100" step="1"
+        />
         <Slider
           label="Clarity"
           value={adjustments.clarity}
@@ -41,20 +115,158 @@ export default function DetailsPanel({ adjustments, setAdjustments }) {
       </div>
 
       <div className="p-2 bg-bg-tertiary rounded-md">
-        <p className="text-md font-semibold mb-2 text-primary">Noise Reduction</p>
+        <GroupHeader title="Noise Reduction" group="noiseReduction" adjustments={adjustments} setAdjustments={setAdjustments} />
         <Slider
           label="Luminance"
           value={adjustments.lumaNoiseReduction}
           onChange={(e) => handleAdjustmentChange('lumaNoiseReduction', e.target.value)}
           min="0" max="100" step="1"
         />
+        {!isForMask && (
+          <Slider
+            label="Detail"
+            value={adjustments.lumaNoiseReductionDetail ?? 50}
+            onChange={(e) => handleAdjustmentChange('lumaNoiseReductionDetail', e.target.value)}
+            min="0" max="100" step="1"
+            defaultValue={50}
+          />
+        )}
         <Slider
           label="Color"
           value={adjustments.colorNoiseReduction}
           onChange={(e) => handleAdjustmentChange('colorNoiseReduction', e.target.value)}
           min="0" max="100" step="1"
         />
+        {!isForMask && (
+          <Slider
+            label="Smoothness"
+            value={adjustments.colorNoiseReductionSmoothness ?? 50}
+            onChange={(e) => handleAdjustmentChange('colorNoiseReductionSmoothness', e.target.value)}
+            min="0" max="100" step="1"
+            defaultValue={50}
+          />
+        )}
       </div>
+
+      {!isForMask && (
+        <div className="mt-4 p-2 bg-bg-tertiary rounded-md">
+          <GroupHeader title="Defringe" group="defringe" adjustments={adjustments} setAdjustments={setAdjustments} />
+          <Slider
+            label="Purple Amount"
+            value={defringe.purpleAmount}
+            onChange={(e) => handleDefringeChange('purpleAmount', e.target.value)}
+            min="0" max="100" step="1"
+          />
+          <Slider
+            label="Purple Hue"
+            value={defringe.purpleHue}
+            onChange={(e) => handleDefringeChange('purpleHue', e.target.value)}
+            min="240" max="340" step="1"
+            defaultValue={INITIAL_ADJUSTMENTS.defringe.purpleHue}
+          />
+          <Slider
+            label="Purple Range"
+            value={defringe.purpleRange}
+            onChange={(e) => handleDefringeChange('purpleRange', e.target.value)}
+            min="10" max="120" step="1"
+            defaultValue={INITIAL_ADJUSTMENTS.defringe.purpleRange}
+          />
+          <Slider
+            label="Green Amount"
+            value={defringe.greenAmount}
+            onChange={(e) => handleDefringeChange('greenAmount', e.target.value)}
+            min="0" max="100" step="1"
+          />
+          <Slider
+            label="Green Hue"
+            value={defringe.greenHue}
+            onChange={(e) => handleDefringeChange('greenHue', e.target.value)}
+            min="60" max="180" step="1"
+            defaultValue={INITIAL_ADJUSTMENTS.defringe.greenHue}
+          />
+          <Slider
+            label="Green Range"
+            value={defringe.greenRange}
+            onChange={(e) => handleDefringeChange('greenRange', e.target.value)}
+            min="10" max="120" step="1"
+            defaultValue={INITIAL_ADJUSTMENTS.defringe.greenRange}
+          />
+        </div>
+      )}
+
+      {isRaw && (
+        <div className="mt-4 p-2 bg-bg-tertiary rounded-md">
+          <p className="text-md font-semibold mb-2 text-primary">RAW Demosaic</p>
+          <Dropdown
+            options={demosaicOptions}
+            value={adjustments.demosaic ?? null}
+            onChange={(value) => setAdjustments(prev => ({ ...prev, demosaic: value }))}
+          />
+          <p className="text-xs text-text-secondary mt-2">Low Noise gives less color noise on high ISO shots.</p>
+
+          <p className="text-md font-semibold mt-4 mb-2 text-primary">Sensor Levels</p>
+          <div className="flex gap-2">
+            <label className="flex-1 text-xs text-text-secondary">
+              Black
+              <input
+                type="number"
+                min="0"
+                value={adjustments.rawLevels?.blackLevel ?? ''}
+                placeholder={formatLevels(metadataLevels?.blackLevels) || 'From file'}
+                onChange={(e) => handleLevelChange('blackLevel', e.target.value)}
+                className="mt-1 w-full bg-bg-primary text-center text-text-primary rounded-md p-1 border border-surface focus:border-accent focus:ring-accent"
+              />
+            </label>
+            <label className="flex-1 text-xs text-text-secondary">
+              White
+              <input
+                type="number"
+                min="1"
+                value={adjustments.rawLevels?.whiteLevel ?? ''}
+                placeholder={formatLevels(metadataLevels?.whiteLevels) || 'From file'}
+                onChange={(e) => handleLevelChange('whiteLevel', e.target.value)}
+                className="mt-1 w-full bg-bg-primary text-center text-text-primary rounded-md p-1 border border-surface focus:border-accent focus:ring-accent"
+              />
+            </label>
+          </div>
+          <p className="text-xs text-text-secondary mt-2">Overrides the levels recorded by the camera, e.g. for modified astro cameras. Leave empty to use the file's.</p>
+
+          <p className="text-md font-semibold mt-4 mb-2 text-primary">Sensor Histogram</p>
+          <RawHistogram imagePath={imagePath} rawLevels={adjustments.rawLevels} />
+
+          <Switch
+            label="Monochrome Sensor"
+            checked={!!adjustments.monochromeSensor}
+            onChange={(checked) => setAdjustments(prev => ({ ...prev, monochromeSensor: checked }))}
+            className="mt-4"
+          />
+          <p className="text-xs text-text-secondary mt-2">For cameras converted to monochrome. Skips demosaicing and white balance.</p>
+
+          <Switch
+            label="Suppress False Colors"
+            checked={!!adjustments.falseColorSuppression}
+            onChange={(checked) => setAdjustments(prev => ({ ...prev, falseColorSuppression: checked }))}
+            className="mt-4"
+          />
+          <p className="text-xs text-text-secondary mt-2">Removes color moiré from fine patterns, common on sensors without an anti-aliasing filter. Softens very fine color detail.</p>
+
+          <Switch
+            label="Remove Banding"
+            checked={!!adjustments.bandingCorrection}
+            onChange={(checked) => setAdjustments(prev => ({ ...prev, bandingCorrection: checked }))}
+            className="mt-4"
+          />
+          <p className="text-xs text-text-secondary mt-2">Removes horizontal and vertical stripes some sensors show at high ISO or with the electronic shutter.</p>
+
+          <Switch
+            label="Show Full Sensor Area"
+            checked={!!adjustments.showFullSensorArea}
+            onChange={(checked) => setAdjustments(prev => ({ ...prev, showFullSensorArea: checked }))}
+            className="mt-4"
+          />
+          <p className="text-xs text-text-secondary mt-2">Ignores the camera's default crop and shows every usable pixel, including edges that may be darker or distorted.</p>
+        </div>
+      )}
     </div>
   );
 }
\ No newline at end of file
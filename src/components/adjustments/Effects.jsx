@@ -1,5 +1,11 @@
+import { useState } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+import { open } from '@tauri-apps/plugin-dialog';
+import { X } from 'lucide-react';
 import Slider from '../ui/Slider';
+import GroupHeader from './GroupHeader';
 import Switch from '../ui/Switch';
+import { INITIAL_ADJUSTMENTS } from '../../utils/adjustments';
 
 export default function EffectsPanel({ adjustments, setAdjustments, isForMask = false }) {
   const handleAdjustmentChange = (key, value) => {
@@ -15,6 +21,32 @@ export default function EffectsPanel({ adjustments, setAdjustments, isForMask =
     setAdjustments(prev => ({ ...prev, [key]: value }));
   };
 
+  const [lutError, setLutError] = useState(null);
+  const lut = adjustments.lut || INITIAL_ADJUSTMENTS.lut;
+
+  const handleLoadLut = async () => {
+    const path = await open({
+      multiple: false,
+      filters: [{ name: 'Cube LUT', extensions: ['cube'] }],
+      title: 'Load LUT',
+    });
+    if (typeof path !== 'string') return;
+    try {
+      const info = await invoke('load_lut', { path });
+      setLutError(null);
+      setAdjustments(prev => ({
+        ...prev,
+        lut: { ...(prev.lut || INITIAL_ADJUSTMENTS.lut), path: info.path, hash: info.hash, name: info.name },
+      }));
+    } catch (err) {
+      setLutError(String(err));
+    }
+  };
+
+  const handleLutIntensityChange = (value) => {
+    setAdjustments(prev => ({ ...prev, lut: { ...(prev.lut || INITIAL_ADJUSTMENTS.lut), intensity: parseInt(value, 10) } }));
+  };
+
   return (
     <div>
       {!isForMask && (
@@ -63,7 +95,7 @@ export default function EffectsPanel({ adjustments, setAdjustments, isForMask =
           </div>
 
           <div className="mb-4 p-2 bg-bg-tertiary rounded-md">
-            <p className="text-md font-semibold mb-2 text-primary">Vignette</p>
+            <GroupHeader title="Vignette" group="vignette" adjustments={adjustments} setAdjustments={setAdjustments} />
             <Slider
               label="Amount"
               value={adjustments.vignetteAmount}
@@ -91,7 +123,7 @@ export default function EffectsPanel({ adjustments, setAdjustments, isForMask =
           </div>
 
           <div className="p-2 bg-bg-tertiary rounded-md">
-            <p className="text-md font-semibold mb-2 text-primary">Grain</p>
+            <GroupHeader title="Grain" group="grain" adjustments={adjustments} setAdjustments={setAdjustments} />
             <Slider
               label="Amount"
               value={adjustments.grainAmount}
@@ -111,6 +143,35 @@ export default function EffectsPanel({ adjustments, setAdjustments, isForMask =
               min="0" max="100" step="1" defaultValue="50"
             />
           </div>
+
+          <div className="mt-4 p-2 bg-bg-tertiary rounded-md">
+            <GroupHeader title="LUT" group="lut" adjustments={adjustments} setAdjustments={setAdjustments} />
+            <div className="flex items-center gap-2 mb-2">
+              <button
+                onClick={handleLoadLut}
+                className="flex-1 text-sm text-left truncate px-2 py-1 rounded-md bg-bg-secondary hover:bg-surface text-text-primary"
+                title={lut.path || 'Load a .cube file'}
+              >
+                {lut.name || 'Load .cube LUT...'}
+              </button>
+              {lut.path && (
+                <button
+                  onClick={() => setAdjustments(prev => ({ ...prev, lut: { ...INITIAL_ADJUSTMENTS.lut } }))}
+                  className="p-1 rounded-md hover:bg-surface"
+                  title="Remove LUT"
+                >
+                  <X size={14} />
+                </button>
+              )}
+            </div>
+            {lutError && <p className="text-xs text-red-400 mb-2">{lutError}</p>}
+            <Slider
+              label="Intensity"
+              value={lut.intensity ?? 100}
+              onChange={(e) => handleLutIntensityChange(e.target.value)}
+              min="0" max="100" step="1" defaultValue={100}
+            />
+          </div>
         </>
       )}
     </div>
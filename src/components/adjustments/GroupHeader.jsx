@@ -0,0 +1,27 @@
+import { Eye, EyeOff } from 'lucide-react';
+
+// Title of an adjustment group with a toggle that switches the group off in the pipeline
+// while keeping its values, so the effect can be compared on and off.
+export default function GroupHeader({ title, group, adjustments, setAdjustments, className = 'mb-2' }) {
+  const isEnabled = adjustments.enabledGroups?.[group] ?? true;
+
+  const handleToggle = () => {
+    setAdjustments(prev => ({
+      ...prev,
+      enabledGroups: { ...(prev.enabledGroups || {}), [group]: !isEnabled },
+    }));
+  };
+
+  return (
+    <div className={`flex items-center justify-between ${className}`}>
+      <p className={`text-md font-semibold text-primary ${isEnabled ? '' : 'opacity-50'}`}>{title}</p>
+      <button
+        onClick={handleToggle}
+        className="p-1 rounded-full text-text-secondary hover:bg-bg-primary hover:text-text-primary"
+        title={isEnabled ? `Disable ${title}` : `Enable ${title}`}
+      >
+        {isEnabled ? <Eye size={14} /> : <EyeOff size={14} />}
+      </button>
+    </div>
+  );
+}
@@ -0,0 +1,160 @@
+import { useState, useRef, useEffect } from 'react';
+import GroupHeader from './GroupHeader';
+
+const MAX_POINTS = 16;
+const NEUTRAL = 127.5;
+
+const CURVES = {
+  hueVsHue: { label: 'Hue vs Hue', periodic: true },
+  hueVsSat: { label: 'Hue vs Sat', periodic: true },
+  lumVsSat: { label: 'Lum vs Sat', periodic: false },
+};
+
+const HUE_GRADIENT = 'linear-gradient(to right, #f00, #ff0, #0f0, #0ff, #00f, #f0f, #f00)';
+const LUM_GRADIENT = 'linear-gradient(to right, #000, #fff)';
+
+// Point `index` of the curve, wrapped around a full turn for hue curves. Same as
+// color_curve_point in shader.wgsl.
+function curvePoint(points, index, periodic) {
+  const n = points.length;
+  if (!periodic) return points[Math.max(0, Math.min(n - 1, index))];
+  const wrapped = ((index % n) + n) % n;
+  return { x: points[wrapped].x + 255 * ((index - wrapped) / n), y: points[wrapped].y };
+}
+
+// Catmull-Rom spline through the points at x in 0..255, as sample_color_curve in shader.wgsl.
+function sampleCurve(points, x, periodic) {
+  const n = points.length;
+  if (n === 0) return NEUTRAL;
+  let i = -1;
+  points.forEach((p, k) => { if (p.x <= x) i = k; });
+  if (!periodic) {
+    if (i < 0) return points[0].y;
+    if (i === n - 1) return points[n - 1].y;
+  }
+  const p0 = curvePoint(points, i - 1, periodic);
+  const p1 = curvePoint(points, i, periodic);
+  const p2 = curvePoint(points, i + 1, periodic);
+  const p3 = curvePoint(points, i + 2, periodic);
+  const dx = p2.x - p1.x;
+  if (dx <= 0) return p1.y;
+  const m1 = (p2.y - p0.y) / Math.max(0.001, p2.x - p0.x);
+  const m2 = (p3.y - p1.y) / Math.max(0.001, p3.x - p1.x);
+  const t = (x - p1.x) / dx;
+  const t2 = t * t;
+  const t3 = t2 * t;
+  const y = (2 * t3 - 3 * t2 + 1) * p1.y + (t3 - 2 * t2 + t) * m1 * dx + (-2 * t3 + 3 * t2) * p2.y + (t3 - t2) * m2 * dx;
+  return Math.max(0, Math.min(255, y));
+}
+
+function getPath(points, periodic) {
+  let path = '';
+  for (let x = 0; x <= 255; x += 4) {
+    path += `${path ? ' L' : 'M'} ${x} ${(255 - sampleCurve(points, x, periodic)).toFixed(2)}`;
+  }
+  return `${path} L 255 ${(255 - sampleCurve(points, 255, periodic)).toFixed(2)}`;
+}
+
+// Resolve style hue curves. A curve without points is neutral; click to add a point, drag to
+// move it and double click to reset the curve.
+export default function HueCurves({ adjustments, setAdjustments }) {
+  const [activeCurve, setActiveCurve] = useState('hueVsHue');
+  const [draggingPointIndex, setDraggingPointIndex] = useState(null);
+  const svgRef = useRef(null);
+
+  const { periodic } = CURVES[activeCurve];
+  const points = adjustments.hueCurves?.[activeCurve] || [];
+
+  useEffect(() => {
+    setDraggingPointIndex(null);
+  }, [activeCurve]);
+
+  const setPoints = (newPoints) => {
+    setAdjustments(prev => ({
+      ...prev,
+      hueCurves: { ...(prev.hueCurves || {}), [activeCurve]: newPoints },
+    }));
+  };
+
+  const getMousePos = (e) => {
+    const rect = svgRef.current.getBoundingClientRect();
+    const x = Math.max(0, Math.min(255, (e.clientX - rect.left) / rect.width * 255));
+    const y = Math.max(0, Math.min(255, 255 - (e.clientY - rect.top) / rect.height * 255));
+    return { x, y };
+  };
+
+  const handleContainerMouseDown = (e) => {
+    if (e.button !== 0 || e.target.tagName === 'circle' || points.length >= MAX_POINTS) return;
+    const { x } = getMousePos(e);
+    // New points sit on the curve, so adding one does not change the image.
+    const newPoint = { x, y: sampleCurve(points, x, periodic) };
+    const newPoints = [...points, newPoint].sort((a, b) => a.x - b.x);
+    setPoints(newPoints);
+    setDraggingPointIndex(newPoints.indexOf(newPoint));
+  };
+
+  useEffect(() => {
+    if (draggingPointIndex === null) return;
+
+    const moveHandler = (e) => {
+      let { x, y } = getMousePos(e);
+      const prevX = draggingPointIndex > 0 ? points[draggingPointIndex - 1].x + 0.01 : 0;
+      const nextX = draggingPointIndex < points.length - 1 ? points[draggingPointIndex + 1].x - 0.01 : 255;
+      x = Math.max(prevX, Math.min(nextX, x));
+      const newPoints = [...points];
+      newPoints[draggingPointIndex] = { x, y };
+      setPoints(newPoints);
+    };
+    const upHandler = () => setDraggingPointIndex(null);
+
+    window.addEventListener('mousemove', moveHandler);
+    window.addEventListener('mouseup', upHandler);
+    return () => {
+      window.removeEventListener('mousemove', moveHandler);
+      window.removeEventListener('mouseup', upHandler);
+    };
+  }, [draggingPointIndex, points, activeCurve]);
+
+  return (
+    <div className="p-2 bg-bg-tertiary rounded-md mt-4 select-none">
+      <GroupHeader title="Hue Curves" group="hueCurves" adjustments={adjustments} setAdjustments={setAdjustments} className="mb-3" />
+      <div className="flex gap-1 mb-2">
+        {Object.entries(CURVES).map(([key, { label }]) => (
+          <button
+            key={key}
+            onClick={() => setActiveCurve(key)}
+            className={`flex-1 px-2 h-7 rounded-full text-xs transition-colors
+              ${activeCurve === key ? 'bg-accent text-primary' : 'bg-surface-secondary text-text-secondary'}`}
+          >
+            {label}
+          </button>
+        ))}
+      </div>
+      <div
+        className="w-full aspect-square bg-surface-secondary p-1 rounded-md relative"
+        onMouseDown={handleContainerMouseDown}
+        onDoubleClick={() => setPoints([])}
+      >
+        <svg ref={svgRef} viewBox="0 0 255 255" className="w-full h-full overflow-visible">
+          <path d="M 63.75,0 V 255 M 127.5,0 V 255 M 191.25,0 V 255 M 0,63.75 H 255 M 0,191.25 H 255" stroke="rgba(255,255,255,0.1)" strokeWidth="0.5" />
+          <line x1="0" y1={255 - NEUTRAL} x2="255" y2={255 - NEUTRAL} stroke="rgba(255,255,255,0.2)" strokeWidth="1" strokeDasharray="2 2" />
+          <path d={getPath(points, periodic)} fill="none" stroke="rgb(var(--color-accent))" strokeWidth="2.5" />
+          {points.map((p, i) => (
+            <circle
+              key={i}
+              cx={p.x}
+              cy={255 - p.y}
+              r="6"
+              fill="rgb(var(--color-accent))"
+              stroke="#1e1e1e"
+              strokeWidth="2"
+              className="cursor-pointer"
+              onMouseDown={(e) => { e.preventDefault(); setDraggingPointIndex(i); }}
+            />
+          ))}
+        </svg>
+      </div>
+      <div className="h-2 mt-1 rounded-full" style={{ background: activeCurve === 'lumVsSat' ? LUM_GRADIENT : HUE_GRADIENT }} />
+    </div>
+  );
+}
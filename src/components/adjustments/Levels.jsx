@@ -0,0 +1,61 @@
+import Slider from '../ui/Slider';
+import GroupHeader from './GroupHeader';
+import { INITIAL_ADJUSTMENTS } from '../../utils/adjustments';
+
+const CHANNEL_NAMES = { luma: 'RGB', red: 'Red', green: 'Green', blue: 'Blue' };
+
+// Levels of the channel picked with the curve channel buttons above.
+export default function Levels({ adjustments, setAdjustments, channel }) {
+  const defaults = INITIAL_ADJUSTMENTS.levels[channel];
+  const levels = { ...defaults, ...(adjustments.levels?.[channel] || {}) };
+
+  const handleChange = (key, value) => {
+    setAdjustments(prev => {
+      const prevLevels = prev.levels || INITIAL_ADJUSTMENTS.levels;
+      const next = { ...defaults, ...(prevLevels[channel] || {}), [key]: parseFloat(value) };
+      // Keep the black point below the white point.
+      if (key === 'inBlack') next.inBlack = Math.min(next.inBlack, next.inWhite - 1);
+      if (key === 'inWhite') next.inWhite = Math.max(next.inWhite, next.inBlack + 1);
+      return { ...prev, levels: { ...prevLevels, [channel]: next } };
+    });
+  };
+
+  return (
+    <div className="mt-4 p-2 bg-bg-tertiary rounded-md">
+      <GroupHeader title={`Levels (${CHANNEL_NAMES[channel]})`} group="levels" adjustments={adjustments} setAdjustments={setAdjustments} />
+      <Slider
+        label="Input Black"
+        value={levels.inBlack}
+        onChange={(e) => handleChange('inBlack', e.target.value)}
+        min="0" max="254" step="1"
+      />
+      <Slider
+        label="Gamma"
+        value={levels.gamma}
+        onChange={(e) => handleChange('gamma', e.target.value)}
+        min="0.1" max="3" step="0.01"
+        defaultValue={1}
+      />
+      <Slider
+        label="Input White"
+        value={levels.inWhite}
+        onChange={(e) => handleChange('inWhite', e.target.value)}
+        min="1" max="255" step="1"
+        defaultValue={255}
+      />
+      <Slider
+        label="Output Black"
+        value={levels.outBlack}
+        onChange={(e) => handleChange('outBlack', e.target.value)}
+        min="0" max="255" step="1"
+      />
+      <Slider
+        label="Output White"
+        value={levels.outWhite}
+        onChange={(e) => handleChange('outWhite', e.target.value)}
+        min="0" max="255" step="1"
+        defaultValue={255}
+      />
+    </div>
+  );
+}
@@ -0,0 +1,80 @@
+import { useState, useEffect, useRef } from 'react';
+import { invoke } from '@tauri-apps/api/core';
+
+const WIDTH = 256;
+const HEIGHT = 80;
+
+const channelColor = (name) => ({
+  R: 'rgb(239, 68, 68)',
+  G: 'rgb(34, 197, 94)',
+  B: 'rgb(59, 130, 246)',
+}[name[0]] || 'rgb(212, 212, 212)');
+
+const formatPercent = (value) => (value > 0 && value < 0.01 ? '<0.01' : value.toFixed(2));
+
+// Histogram of the sensor data before demosaic, on a scale of stops below the white level.
+export default function RawHistogram({ imagePath, rawLevels }) {
+  const [histogram, setHistogram] = useState(null);
+  const canvasRef = useRef(null);
+  const levelsKey = JSON.stringify(rawLevels ?? null);
+
+  useEffect(() => {
+    setHistogram(null);
+    if (!imagePath) return;
+    let isActive = true;
+    invoke('generate_raw_histogram', { path: imagePath, levels: JSON.parse(levelsKey) })
+      .then(data => { if (isActive) setHistogram(data); })
+      .catch(err => console.error("Failed to generate raw histogram:", err));
+    return () => { isActive = false; };
+  }, [imagePath, levelsKey]);
+
+  useEffect(() => {
+    if (!histogram || !canvasRef.current) return;
+    const ctx = canvasRef.current.getContext('2d');
+    ctx.clearRect(0, 0, WIDTH, HEIGHT);
+
+    // The first and last bins hold the clipped samples, they would flatten everything else.
+    const maxCount = Math.max(1, ...histogram.channels.flatMap(c => c.bins.slice(1, -1)));
+    ctx.globalCompositeOperation = 'lighter';
+    histogram.channels.forEach(channel => {
+      ctx.strokeStyle = channelColor(channel.name);
+      ctx.lineWidth = 1;
+      ctx.beginPath();
+      channel.bins.forEach((count, i) => {
+        const x = (i / (channel.bins.length - 1)) * (WIDTH - 1);
+        const y = HEIGHT - Math.min(1, Math.sqrt(count / maxCount)) * (HEIGHT - 1);
+        if (i === 0) ctx.moveTo(x, y); else ctx.lineTo(x, y);
+      });
+      ctx.stroke();
+    });
+    ctx.globalCompositeOperation = 'source-over';
+  }, [histogram]);
+
+  if (!histogram) {
+    return <p className="text-xs text-text-secondary">Reading sensor data...</p>;
+  }
+
+  return (
+    <div>
+      <canvas ref={canvasRef} width={WIDTH} height={HEIGHT} className="w-full bg-black/50 rounded" />
+      <div className="flex justify-between text-[10px] text-text-secondary mt-1">
+        <span>-{histogram.stops} EV</span>
+        <span>White</span>
+      </div>
+      <div className="grid grid-cols-3 gap-x-2 text-xs mt-2">
+        <span className="text-text-secondary">Channel</span>
+        <span className="text-text-secondary text-right">Black %</span>
+        <span className="text-text-secondary text-right">Clipped %</span>
+        {histogram.channels.map(channel => (
+          <div key={channel.name} className="contents">
+            <span style={{ color: channelColor(channel.name) }}>{channel.name}</span>
+            <span className="text-right text-text-primary">{formatPercent(channel.blackPercent)}</span>
+            <span className={`text-right ${channel.clippedPercent >= 0.1 ? 'text-red-400' : 'text-text-primary'}`}>
+              {formatPercent(channel.clippedPercent)}
+            </span>
+          </div>
+        ))}
+      </div>
+    </div>
+  );
+}
@@ -5,6 +5,7 @@ export default function ConfirmModal({
   isOpen,
   onClose,
   onConfirm,
+  onCancel,
   title,
   message,
   confirmText = 'Confirm',
@@ -35,6 +36,11 @@ export default function ConfirmModal({
     onClose();
   }, [onConfirm, onClose]);
 
+  const handleCancel = useCallback(() => {
+    onCancel?.();
+    onClose();
+  }, [onCancel, onClose]);
+
   const handleKeyDown = useCallback((e) => {
     if (e.key === 'Enter') {
       handleConfirm();
@@ -81,7 +87,7 @@ export default function ConfirmModal({
         <p className="text-sm text-text-secondary mb-6 whitespace-pre-wrap">{message}</p>
         <div className="flex justify-end gap-3 mt-5">
           <Button
-            onClick={onClose}
+            onClick={handleCancel}
             variant="ghost"
             className="bg-bg-primary shadow-transparent hover:bg-bg-primary text-white shadow-none"
           >
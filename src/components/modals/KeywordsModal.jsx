@@ -0,0 +1,88 @@
+import { useState, useEffect, useCallback } from 'react';
+
+export default function KeywordsModal({ isOpen, onClose, onSave, currentKeywords }) {
+  const [value, setValue] = useState('');
+  const [isMounted, setIsMounted] = useState(false);
+  const [show, setShow] = useState(false);
+
+  useEffect(() => {
+    if (isOpen) {
+      setValue((currentKeywords || []).join(', '));
+      setIsMounted(true);
+      const timer = setTimeout(() => setShow(true), 10);
+      return () => clearTimeout(timer);
+    } else {
+      setShow(false);
+      const timer = setTimeout(() => {
+        setIsMounted(false);
+        setValue('');
+      }, 300);
+      return () => clearTimeout(timer);
+    }
+  }, [isOpen, currentKeywords]);
+
+  const handleSave = useCallback(() => {
+    onSave(value.split(',').map(k => k.trim()).filter(Boolean));
+    onClose();
+  }, [value, onSave, onClose]);
+
+  const handleKeyDown = useCallback((e) => {
+    if (e.key === 'Enter') {
+      handleSave();
+    } else if (e.key === 'Escape') {
+      onClose();
+    }
+  }, [handleSave, onClose]);
+
+  if (!isMounted) {
+    return null;
+  }
+
+  return (
+    <div
+      className={`
+        fixed inset-0 flex items-center justify-center z-50 
+        bg-black/30 backdrop-blur-sm 
+        transition-opacity duration-300 ease-in-out
+        ${show ? 'opacity-100' : 'opacity-0'}
+      `}
+      onClick={onClose}
+      role="dialog"
+      aria-modal="true"
+    >
+      <div
+        className={`
+          bg-surface rounded-lg shadow-xl p-6 w-full max-w-sm 
+          transform transition-all duration-300 ease-out
+          ${show ? 'scale-100 opacity-100 translate-y-0' : 'scale-95 opacity-0 -translate-y-4'}
+        `}
+        onClick={(e) => e.stopPropagation()}
+      >
+        <h3 className="text-lg font-semibold text-text-primary mb-4">Edit Keywords</h3>
+        <input
+          type="text"
+          value={value}
+          onChange={(e) => setValue(e.target.value)}
+          onKeyDown={handleKeyDown}
+          placeholder="Separate keywords with commas..."
+          autoFocus
+          className="w-full bg-bg-primary text-text-primary border border-border rounded-md px-3 py-2 focus:outline-none focus:ring-2 focus:ring-accent"
+        />
+        <div className="flex justify-end gap-3 mt-5">
+          <button
+            onClick={onClose}
+            className="px-4 py-2 rounded-md text-text-secondary hover:bg-surface transition-colors"
+          >
+            Cancel
+          </button>
+          <button
+            onClick={handleSave}
+            className="px-4 py-2 rounded-md bg-accent text-primary font-semibold hover:bg-accent-hover transition-colors"
+          >
+            Save
+          </button>
+        </div>
+      </div>
+    </div>
+  );
+}
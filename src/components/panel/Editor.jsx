@@ -12,16 +12,17 @@ import FullScreenViewer from './editor/FullScreenViewer';
 import EditorToolbar from './editor/EditorToolbar';
 import ImageCanvas from './editor/ImageCanvas';
 import Waveform from './editor/Waveform';
+import PerformanceOverlay from './editor/PerformanceOverlay';
 
 export default function Editor({
   selectedImage, finalPreviewUrl, uncroppedAdjustedPreviewUrl,
-  showOriginal, setShowOriginal, isAdjusting, onBackToLibrary, isLoading, isFullScreen,
+  showOriginal, setShowOriginal, showClipping, setShowClipping, compareMode, setCompareMode, isAdjusting, onBackToLibrary, isLoading, isFullScreen,
   isFullScreenLoading, fullScreenUrl, onToggleFullScreen, activeRightPanel,
   adjustments, setAdjustments, activeMaskId, activeMaskContainerId,
   onSelectMask, updateSubMask, transformWrapperRef, onZoomed, onContextMenu,
   onUndo, onRedo, canUndo, canRedo, brushSettings, 
   onGenerateAiMask, aiTool, onAiMaskDrawingComplete, isMaskControlHovered,
-  targetZoom, waveform, isWaveformVisible, onCloseWaveform,
+  targetZoom, waveform, isWaveformVisible, onCloseWaveform, performanceTimings,
 }) {
   const [crop, setCrop] = useState();
   const prevCropParams = useRef(null);
@@ -222,6 +223,8 @@ export default function Editor({
   }, [selectedImage, adjustments.crop, adjustments.rotation, setAdjustments]);
 
   const toggleShowOriginal = useCallback(() => setShowOriginal(prev => !prev), [setShowOriginal]);
+  const toggleShowClipping = useCallback(() => setShowClipping(prev => !prev), [setShowClipping]);
+  const toggleCompare = useCallback(() => setCompareMode(prev => prev ? null : { adjustments: null }), [setCompareMode]);
 
   const doubleClickProps = useMemo(() => {
     if (isCropping || isMasking) {
@@ -266,6 +269,7 @@ export default function Editor({
       <div className="flex-1 bg-bg-secondary rounded-lg flex flex-col relative overflow-hidden p-2 gap-2 min-h-0">
         <AnimatePresence>
           {isWaveformVisible && <Waveform waveformData={waveform} onClose={onCloseWaveform} />}
+          {performanceTimings && <PerformanceOverlay timings={performanceTimings} />}
         </AnimatePresence>
         <EditorToolbar
           onBackToLibrary={onBackToLibrary}
@@ -273,6 +277,10 @@ export default function Editor({
           isLoading={isLoading}
           onToggleShowOriginal={toggleShowOriginal}
           showOriginal={showOriginal}
+          onToggleShowClipping={toggleShowClipping}
+          showClipping={showClipping}
+          onToggleCompare={toggleCompare}
+          isComparing={!!compareMode}
           onToggleFullScreen={onToggleFullScreen}
           isFullScreenLoading={isFullScreenLoading}
           onUndo={onUndo}
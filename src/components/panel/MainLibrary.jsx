@@ -10,6 +10,7 @@ import {
   Star as StarIcon,
   Check,
   SlidersHorizontal,
+  Zap,
 } from 'lucide-react';
 import { motion, AnimatePresence } from 'framer-motion';
 import { FixedSizeGrid as Grid } from 'react-window';
@@ -305,7 +306,7 @@ const Cell = ({ columnIndex, rowIndex, style, data }) => {
 };
 
 export default function MainLibrary({
-  imageList, onImageClick, onImageDoubleClick, onContextMenu, onEmptyAreaContextMenu, multiSelectedPaths, activePath, rootPath, currentFolderPath, onOpenFolder, thumbnails, imageRatings, appSettings, onContinueSession, onGoHome, onClearSelection, sortCriteria, setSortCriteria, filterCriteria, setFilterCriteria, onSettingsChange, onLibraryRefresh, theme, initialScrollOffset, onScroll,
+  imageList, onImageClick, onImageDoubleClick, onContextMenu, onEmptyAreaContextMenu, multiSelectedPaths, activePath, rootPath, currentFolderPath, onOpenFolder, thumbnails, imageRatings, appSettings, onContinueSession, onGoHome, onClearSelection, sortCriteria, setSortCriteria, filterCriteria, setFilterCriteria, onSettingsChange, onLibraryRefresh, onToggleCullingMode, theme, initialScrollOffset, onScroll,
 }) {
   const [showSettings, setShowSettings] = useState(false);
   const [appVersion, setAppVersion] = useState('');
@@ -369,7 +370,10 @@ export default function MainLibrary({
     <div className="flex-1 flex flex-col h-full min-w-0 bg-bg-secondary rounded-lg overflow-hidden">
       <header className="p-4 flex-shrink-0 flex justify-between items-center border-b border-border-color">
         <div>
-          <h2 className="text-2xl font-bold text-primary">Library</h2>
+          <h2 className="text-2xl font-bold text-primary flex items-center gap-2">
+            Library
+            {appSettings?.cullingMode && <span className="text-xs font-semibold uppercase bg-accent text-primary rounded px-2 py-0.5">Culling</span>}
+          </h2>
           <p className="text-sm text-text-secondary truncate">{currentFolderPath}</p>
         </div>
         <div className="flex items-center gap-3">
@@ -381,6 +385,7 @@ export default function MainLibrary({
             sortCriteria={sortCriteria}
             setSortCriteria={setSortCriteria}
           />
+          <Button onClick={onToggleCullingMode} className={`h-12 w-12 shadow-none p-0 flex items-center justify-center ${appSettings?.cullingMode ? 'bg-accent text-primary' : 'bg-surface text-text-primary'}`} title={appSettings?.cullingMode ? 'Leave culling mode (C)' : 'Culling mode: embedded previews for fast browsing (C)'}><Zap className="w-8 h-8" /></Button>
           <Button onClick={onOpenFolder} className="h-12 w-12 bg-surface text-text-primary shadow-none p-0 flex items-center justify-center" title="Open another folder"><Folder className="w-8 h-8" /></Button>
           <Button onClick={onGoHome} className="h-12 w-12 bg-surface text-text-primary shadow-none p-0 flex items-center justify-center" title="Go to Home Screen"><Home className="w-8 h-8" /></Button>
         </div>
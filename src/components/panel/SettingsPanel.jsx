@@ -1,7 +1,8 @@
-import { useState } from 'react';
-import { ArrowLeft, Trash2, Wifi, WifiOff } from 'lucide-react';
+import { useState, useEffect } from 'react';
+import { ArrowLeft, Trash2, Wifi, WifiOff, FileDown } from 'lucide-react';
 import { invoke } from '@tauri-apps/api/core';
 import { relaunch } from '@tauri-apps/plugin-process';
+import { save } from '@tauri-apps/plugin-dialog';
 import Button from '../ui/Button';
 import ConfirmModal from '../modals/ConfirmModal';
 import Dropdown from '../ui/Dropdown';
@@ -17,6 +18,24 @@ const resolutions = [
   { value: 3840, label: '3840px' },
 ];
 
+const demosaicOptions = [
+  { value: 'standard', label: 'Standard (PPG)' },
+  { value: 'best', label: 'Best Detail (AMaZE)' },
+  { value: 'lowNoise', label: 'Low Noise (LMMSE)' },
+];
+
+const fastRawResolutionOptions = [
+  { value: 'quarter', label: 'Quarter Resolution' },
+  { value: 'half', label: 'Half Resolution' },
+];
+
+const highlightRecoveryOptions = [
+  { value: 'off', label: 'Off' },
+  { value: 'clip', label: 'Clip to White' },
+  { value: 'blend', label: 'Blend' },
+  { value: 'reconstruct', label: 'Reconstruct from Other Channels' },
+];
+
 const KeybindItem = ({ keys, description }) => (
   <div className="flex justify-between items-center py-2">
     <span className="text-text-secondary">{description}</span>
@@ -30,6 +49,20 @@ const KeybindItem = ({ keys, description }) => (
   </div>
 );
 
+const CACHE_LABELS = {
+  thumbnails: 'Thumbnails',
+  previews: 'Preview (in memory)',
+  aiEmbeddings: 'AI embeddings (in memory)',
+  aiModels: 'AI models',
+};
+
+const formatBytes = (bytes) => {
+  if (!bytes) return '0 B';
+  const units = ['B', 'KB', 'MB', 'GB'];
+  const exponent = Math.min(Math.floor(Math.log(bytes) / Math.log(1024)), units.length - 1);
+  return `${(bytes / Math.pow(1024, exponent)).toFixed(exponent === 0 ? 0 : 1)} ${units[exponent]}`;
+};
+
 export default function SettingsPanel({ onBack, appSettings, onSettingsChange, rootPath, onLibraryRefresh }) {
   const [isClearing, setIsClearing] = useState(false);
   const [clearMessage, setClearMessage] = useState('');
@@ -52,8 +85,26 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
     success: null,
   });
 
+  const [safeModeStatus, setSafeModeStatus] = useState(null);
+  const [auditExportMessage, setAuditExportMessage] = useState('');
+  const [cacheUsage, setCacheUsage] = useState([]);
+
   const effectiveRootPath = rootPath || appSettings?.lastRootPath;
 
+  useEffect(() => {
+    invoke('get_safe_mode_status')
+      .then(setSafeModeStatus)
+      .catch(err => console.error("Failed to get safe mode status:", err));
+  }, []);
+
+  const refreshCacheUsage = () => {
+    invoke('get_cache_usage')
+      .then(setCacheUsage)
+      .catch(err => console.error("Failed to get cache usage:", err));
+  };
+
+  useEffect(refreshCacheUsage, []);
+
   const executeClearSidecars = async () => {
     setIsClearing(true);
     setClearMessage('Deleting sidecar files, please wait...');
@@ -99,17 +150,62 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
     });
   };
 
-  const executeClearCache = async () => {
+  const executeSetSafeMode = async (safeMode) => {
+    onSettingsChange({ ...appSettings, safeMode });
+    await relaunch();
+  };
+
+  const handleSetSafeMode = (safeMode) => {
+    setConfirmModalState({
+      isOpen: true,
+      title: 'Confirm Safe Mode',
+      message: `Are you sure you want to ${ safeMode ? 'enable' : 'disable' } safe mode?\n\n${ safeMode ? 'Safe mode renders on the CPU and disables window effects and AI features.' : ''}\n\nThe application will relaunch to make this change.`,
+      onConfirm: () => executeSetSafeMode(safeMode),
+      confirmText: 'Toggle Safe Mode',
+      confirmVariant: 'primary',
+    });
+  };
+
+  const handleClearGpuBlacklist = async () => {
+    try {
+      await invoke('clear_gpu_blacklist');
+      setSafeModeStatus(prev => prev ? { ...prev, blacklistedAdapters: [] } : prev);
+    } catch (err) {
+      console.error("Failed to clear GPU blacklist:", err);
+    }
+  };
+
+  const handleExportAuditLog = async () => {
+    const outputPath = await save({
+      title: 'Export Operations Log',
+      defaultPath: 'rapidraw_operations_log.csv',
+      filters: [{ name: 'CSV', extensions: ['csv'] }, { name: 'JSON', extensions: ['json'] }],
+    });
+    if (!outputPath) return;
+    try {
+      const format = outputPath.toLowerCase().endsWith('.json') ? 'json' : 'csv';
+      const count = await invoke('export_audit_log', { outputPath, format });
+      setAuditExportMessage(`${count} log entries exported.`);
+    } catch (err) {
+      console.error("Failed to export operations log:", err);
+      setAuditExportMessage(`Error: ${err}`);
+    } finally {
+      setTimeout(() => setAuditExportMessage(''), 3000);
+    }
+  };
+
+  const runCacheOperation = async (command, args, pendingMessage) => {
     setIsClearingCache(true);
-    setCacheClearMessage('Clearing thumbnail cache...');
+    setCacheClearMessage(pendingMessage);
     try {
-      await invoke('clear_thumbnail_cache');
-      setCacheClearMessage('Thumbnail cache cleared successfully.');
+      const result = await invoke(command, args);
+      setCacheClearMessage(`Removed ${result.removedCount} files (${formatBytes(result.freedBytes)}).`);
       onLibraryRefresh();
     } catch (err) {
-      console.error("Failed to clear thumbnail cache:", err);
+      console.error(`Cache operation ${command} failed:`, err);
       setCacheClearMessage(`Error: ${err}`);
     } finally {
+      refreshCacheUsage();
       setTimeout(() => {
         setIsClearingCache(false);
         setCacheClearMessage('');
@@ -117,6 +213,26 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
     }
   };
 
+  const executeClearCache = () =>
+    runCacheOperation('clear_cache', { kind: 'thumbnails' }, 'Clearing thumbnail cache...');
+
+  const handlePurgeFolderThumbnails = () =>
+    runCacheOperation('purge_folder_thumbnails', { folderPath: effectiveRootPath }, 'Clearing thumbnails for this folder...');
+
+  const handlePruneThumbnails = () =>
+    runCacheOperation('prune_thumbnail_cache', { maxAgeDays: 30 }, 'Removing thumbnails older than 30 days...');
+
+  const handleClearAiModels = () => {
+    setConfirmModalState({
+      isOpen: true,
+      title: 'Remove AI Models',
+      message: 'Remove the downloaded AI models?\n\nThey will be downloaded again the next time an AI tool is used.',
+      onConfirm: () => runCacheOperation('clear_cache', { kind: 'aiModels' }, 'Removing AI models...'),
+      confirmText: 'Remove Models',
+      confirmVariant: 'destructive',
+    });
+  };
+
   const handleClearCache = () => {
     setConfirmModalState({
       isOpen: true,
@@ -142,6 +258,22 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
     }
   };
 
+  const rawCropOverrides = appSettings?.rawCropOverrides || [];
+
+  const updateRawCropOverride = (index, changes) => {
+    const updated = rawCropOverrides.map((entry, i) => (i === index ? { ...entry, ...changes } : entry));
+    onSettingsChange({ ...appSettings, rawCropOverrides: updated });
+  };
+
+  const handleAddRawCropOverride = () => {
+    const entry = { make: '', model: '', left: 0, top: 0, right: 0, bottom: 0 };
+    onSettingsChange({ ...appSettings, rawCropOverrides: [...rawCropOverrides, entry] });
+  };
+
+  const handleRemoveRawCropOverride = (index) => {
+    onSettingsChange({ ...appSettings, rawCropOverrides: rawCropOverrides.filter((_, i) => i !== index) });
+  };
+
   const closeConfirmModal = () => {
     setConfirmModalState({ ...confirmModalState, isOpen: false });
   };
@@ -202,9 +334,40 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
                   label="Transparency"
                   checked={appSettings?.transparent ?? true}
                   onChange={handleSetTransparent}
+                  disabled={safeModeStatus?.active}
                 />
               </div>
 
+              <div>
+                <label htmlFor="safe-mode-toggle" className="block text-sm font-medium text-text-primary mb-2">
+                  Safe Mode
+                </label>
+                <Switch
+                  id="safe-mode-toggle"
+                  label="Always Start in Safe Mode"
+                  checked={appSettings?.safeMode ?? false}
+                  onChange={handleSetSafeMode}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Renders on the CPU with window effects and AI features disabled. Use this if the editor stays blank or crashes because of graphics driver issues.
+                </p>
+                {safeModeStatus?.reason === 'gpuCrash' && (
+                  <p className="text-xs text-red-400 mt-2">
+                    Started in safe mode because the graphics driver crashed during the last launch.
+                  </p>
+                )}
+                {safeModeStatus?.blacklistedAdapters?.length > 0 && (
+                  <div className="mt-3">
+                    <p className="text-xs text-text-secondary mb-2">
+                      Disabled graphics adapters: {safeModeStatus.blacklistedAdapters.map(a => a.name).join(', ')}
+                    </p>
+                    <Button onClick={handleClearGpuBlacklist}>
+                      Re-enable Graphics Adapters
+                    </Button>
+                  </div>
+                )}
+              </div>
+
               <div className="pt-6 mt-6 border-t border-border-color">
                 <label htmlFor="preview-resolution" className="block text-sm font-medium text-text-primary mb-2">
                   Preview Resolution
@@ -218,6 +381,207 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
                   Higher resolutions provide a sharper preview but may impact performance on less powerful systems.
                 </p>
               </div>
+
+              <div>
+                <label htmlFor="demosaic-quality" className="block text-sm font-medium text-text-primary mb-2">
+                  RAW Demosaic
+                </label>
+                <Dropdown
+                  options={demosaicOptions}
+                  value={appSettings?.demosaicQuality || 'standard'}
+                  onChange={(value) => onSettingsChange({ ...appSettings, demosaicQuality: value })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Best Detail shows fewer maze artifacts in fine detail but takes longer to open and export RAW files.
+                </p>
+              </div>
+
+              <div>
+                <label className="block text-sm font-medium text-text-primary mb-2">
+                  Fast RAW Previews
+                </label>
+                <Dropdown
+                  options={fastRawResolutionOptions}
+                  value={appSettings?.fastRawResolution || 'quarter'}
+                  onChange={(value) => onSettingsChange({ ...appSettings, fastRawResolution: value })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Resolution of RAW files developed for thumbnails and quick previews. Half Resolution shows more detail but takes longer on slower machines.
+                </p>
+              </div>
+
+              <div>
+                <label className="block text-sm font-medium text-text-primary mb-2">
+                  Highlight Recovery
+                </label>
+                <Dropdown
+                  options={highlightRecoveryOptions}
+                  value={appSettings?.highlightRecovery || 'blend'}
+                  onChange={(value) => onSettingsChange({ ...appSettings, highlightRecovery: value })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  How blown highlights of RAW files are rendered. Off keeps the magenta cast of clipped skies; Reconstruct recovers the most detail.
+                </p>
+                <div className="mt-3">
+                  <Switch
+                    id="highlight-rolloff-toggle"
+                    label="Soft Highlight Rolloff"
+                    checked={appSettings?.highlightRolloff ?? true}
+                    onChange={(checked) => onSettingsChange({ ...appSettings, highlightRolloff: checked })}
+                  />
+                </div>
+                <p className="text-xs text-text-secondary mt-2">
+                  Compresses the brightest highlights like the shoulder of film instead of clipping them, which keeps gradation in contrasty scenes.
+                </p>
+              </div>
+
+              <div>
+                <label htmlFor="ca-correction-toggle" className="block text-sm font-medium text-text-primary mb-2">
+                  Chromatic Aberration
+                </label>
+                <Switch
+                  id="ca-correction-toggle"
+                  label="Remove Lateral CA Automatically"
+                  checked={appSettings?.caCorrection ?? true}
+                  onChange={(checked) => onSettingsChange({ ...appSettings, caCorrection: checked })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Removes red/cyan and blue/yellow fringes towards the corners of RAW files. Applies to images opened afterwards.
+                </p>
+              </div>
+
+              <div>
+                <label htmlFor="embedded-preview-toggle" className="block text-sm font-medium text-text-primary mb-2">
+                  Opening RAW Files
+                </label>
+                <Switch
+                  id="embedded-preview-toggle"
+                  label="Show Embedded Preview First"
+                  checked={appSettings?.embeddedPreviewFirst ?? true}
+                  onChange={(checked) => onSettingsChange({ ...appSettings, embeddedPreviewFirst: checked })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Shows the JPEG the camera stored in the RAW file while it is being developed, which makes large files appear much sooner. Colors change slightly once the developed image replaces it.
+                </p>
+              </div>
+
+              <div>
+                <label className="block text-sm font-medium text-text-primary mb-2">
+                  Default Crop Overrides
+                </label>
+                <div className="space-y-2">
+                  {rawCropOverrides.map((entry, index) => (
+                    <div key={index} className="flex items-center gap-2">
+                      <input
+                        type="text"
+                        placeholder="Make"
+                        value={entry.make}
+                        onChange={(e) => updateRawCropOverride(index, { make: e.target.value })}
+                        className="w-28 bg-bg-primary text-text-primary border border-border-color rounded-md px-2 py-1 text-sm"
+                      />
+                      <input
+                        type="text"
+                        placeholder="Model"
+                        value={entry.model}
+                        onChange={(e) => updateRawCropOverride(index, { model: e.target.value })}
+                        className="flex-grow min-w-0 bg-bg-primary text-text-primary border border-border-color rounded-md px-2 py-1 text-sm"
+                      />
+                      {['left', 'top', 'right', 'bottom'].map((side) => (
+                        <input
+                          key={side}
+                          type="number"
+                          min="0"
+                          title={`Pixels trimmed from the ${side}`}
+                          placeholder={side}
+                          value={entry[side]}
+                          onChange={(e) => updateRawCropOverride(index, { [side]: Math.max(0, parseInt(e.target.value, 10) || 0) })}
+                          className="w-16 bg-bg-primary text-text-primary border border-border-color rounded-md px-2 py-1 text-sm"
+                        />
+                      ))}
+                      <button
+                        onClick={() => handleRemoveRawCropOverride(index)}
+                        className="p-1 text-text-secondary hover:text-red-400"
+                        title="Remove"
+                      >
+                        <Trash2 size={16} />
+                      </button>
+                    </div>
+                  ))}
+                </div>
+                <Button onClick={handleAddRawCropOverride} className="mt-2">
+                  Add Camera
+                </Button>
+                <p className="text-xs text-text-secondary mt-2">
+                  For cameras whose built-in crop trims too much. The margins in sensor pixels are cut from the usable sensor area instead; all zeros keeps all of it. Applies to images opened afterwards.
+                </p>
+              </div>
+
+              <div>
+                <label htmlFor="picture-style-toggle" className="block text-sm font-medium text-text-primary mb-2">
+                  Camera Look
+                </label>
+                <Switch
+                  id="picture-style-toggle"
+                  label="Match In-Camera Picture Style"
+                  checked={appSettings?.matchPictureStyle ?? false}
+                  onChange={(checked) => onSettingsChange({ ...appSettings, matchPictureStyle: checked })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Starts Fujifilm and Canon RAW files from a profile resembling the film simulation or picture style set in the camera. Applies to images opened afterwards.
+                </p>
+              </div>
+
+              <div>
+                <label htmlFor="performance-overlay-toggle" className="block text-sm font-medium text-text-primary mb-2">
+                  Diagnostics
+                </label>
+                <Switch
+                  id="performance-overlay-toggle"
+                  label="Show Performance Overlay"
+                  checked={appSettings?.performanceOverlay ?? false}
+                  onChange={(checked) => onSettingsChange({ ...appSettings, performanceOverlay: checked })}
+                />
+                <p className="text-xs text-text-secondary mt-2">
+                  Shows how long decoding, developing and rendering took for the open image. Timings stay on this computer.
+                </p>
+              </div>
+            </div>
+          </div>
+
+          <div className="p-6 bg-surface rounded-xl shadow-md">
+            <h2 className="text-xl font-semibold mb-4 text-accent">Client Proofing</h2>
+            <div className="space-y-4">
+              <div>
+                <label htmlFor="proofing-text" className="block text-sm font-medium text-text-primary mb-2">
+                  Overlay Text
+                </label>
+                <Input
+                  id="proofing-text"
+                  type="text"
+                  placeholder="PROOF"
+                  value={appSettings?.proofing?.text || ''}
+                  onChange={(e) => onSettingsChange({ ...appSettings, proofing: { ...appSettings?.proofing, text: e.target.value } })}
+                />
+              </div>
+              <div className="flex items-center gap-2">
+                <span className="text-sm text-text-secondary">Overlay opacity (%)</span>
+                <input
+                  type="number"
+                  min="0"
+                  max="100"
+                  placeholder="35"
+                  value={appSettings?.proofing?.opacity ?? ''}
+                  onChange={(e) => onSettingsChange({
+                    ...appSettings,
+                    proofing: { ...appSettings?.proofing, opacity: e.target.value ? parseFloat(e.target.value) : null },
+                  })}
+                  className="w-28 bg-bg-primary text-text-primary border border-border-color rounded-md px-2 py-1 text-sm"
+                />
+              </div>
+              <p className="text-xs text-text-secondary">
+                Right-click a folder to turn proofing on. Previews, fullscreen views and exports of its images get the overlay.
+                {appSettings?.proofing?.folders?.length > 0 && ` Proofing is on for ${appSettings.proofing.folders.length} folder(s).`}
+              </p>
             </div>
           </div>
 
@@ -293,10 +657,58 @@ export default function SettingsPanel({ onBack, appSettings, onSettingsChange, r
                   <Trash2 size={16} className="mr-2" />
                   {isClearingCache ? 'Clearing...' : 'Clear Thumbnail Cache'}
                 </Button>
+                <div className="flex flex-wrap gap-2 mt-3">
+                  <Button onClick={handlePurgeFolderThumbnails} disabled={isClearingCache || !effectiveRootPath}>
+                    Clear Current Folder
+                  </Button>
+                  <Button onClick={handlePruneThumbnails} disabled={isClearingCache}>
+                    Remove Older Than 30 Days
+                  </Button>
+                  <Button variant="destructive" onClick={handleClearAiModels} disabled={isClearingCache}>
+                    Remove AI Models
+                  </Button>
+                </div>
+                {cacheUsage.length > 0 && (
+                  <ul className="text-xs text-text-secondary mt-3 space-y-1">
+                    {cacheUsage.map(usage => (
+                      <li key={usage.kind} className="flex justify-between">
+                        <span>{CACHE_LABELS[usage.kind] || usage.kind}</span>
+                        <span>{formatBytes(usage.sizeBytes)}</span>
+                      </li>
+                    ))}
+                  </ul>
+                )}
+                <div className="flex items-center gap-2 mt-3">
+                  <span className="text-sm text-text-secondary">Thumbnail cache limit (MB)</span>
+                  <input
+                    type="number"
+                    min="0"
+                    placeholder="Unlimited"
+                    value={appSettings?.cacheSizeLimitMb ?? ''}
+                    onChange={(e) => onSettingsChange({
+                      ...appSettings,
+                      cacheSizeLimitMb: e.target.value ? parseInt(e.target.value, 10) : null,
+                    })}
+                    className="w-28 bg-bg-primary text-text-primary border border-border-color rounded-md px-2 py-1 text-sm"
+                  />
+                </div>
                 {cacheClearMessage && (
                   <p className="text-sm text-accent mt-3">{cacheClearMessage}</p>
                 )}
               </div>
+              <div className="pt-6 border-t border-border-color">
+                <h3 className="font-medium text-text-primary">Operations Log</h3>
+                <p className="text-xs text-text-secondary mt-1 mb-3">
+                  Deletions, moves, renames, sidecar clears and adjustment resets are logged with the user and time. Export the log as CSV or JSON.
+                </p>
+                <Button onClick={handleExportAuditLog}>
+                  <FileDown size={16} className="mr-2" />
+                  Export Operations Log
+                </Button>
+                {auditExportMessage && (
+                  <p className="text-sm text-accent mt-3">{auditExportMessage}</p>
+                )}
+              </div>
             </div>
           </div>
 
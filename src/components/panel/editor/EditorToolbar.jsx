@@ -1,7 +1,7 @@
 import { memo } from 'react';
-import { Eye, EyeOff, ArrowLeft, Maximize, Loader2, Undo, Redo } from 'lucide-react';
+import { Eye, EyeOff, ArrowLeft, Maximize, Loader2, Undo, Redo, TriangleAlert, Columns2 } from 'lucide-react';
 
-const EditorToolbar = memo(({ onBackToLibrary, selectedImage, isLoading, onToggleShowOriginal, showOriginal, onToggleFullScreen, isFullScreenLoading, onUndo, onRedo, canUndo, canRedo }) => (
+const EditorToolbar = memo(({ onBackToLibrary, selectedImage, isLoading, onToggleShowOriginal, showOriginal, onToggleShowClipping, showClipping, onToggleCompare, isComparing, onToggleFullScreen, isFullScreenLoading, onUndo, onRedo, canUndo, canRedo }) => (
   <div className="relative flex-shrink-0 flex justify-between items-center px-4 h-14">
     <button onClick={onBackToLibrary} className="bg-surface text-text-primary p-2 rounded-full hover:bg-card-active transition-colors" title="Back to Library">
       <ArrowLeft size={20} />
@@ -20,6 +20,12 @@ const EditorToolbar = memo(({ onBackToLibrary, selectedImage, isLoading, onToggl
       <button onClick={onRedo} disabled={!canRedo} className="bg-surface text-text-primary p-2 rounded-full hover:bg-card-active transition-colors disabled:opacity-50 disabled:cursor-not-allowed" title="Redo (Ctrl+Y)">
         <Redo size={20} />
       </button>
+      <button onClick={onToggleShowClipping} className={`p-2 rounded-full transition-colors ${showClipping ? 'bg-accent text-primary' : 'bg-surface text-text-primary hover:bg-card-active'}`} title={showClipping ? "Hide Clipping (J)" : "Show Clipping (J)"}>
+        <TriangleAlert size={20} />
+      </button>
+      <button onClick={onToggleCompare} className={`p-2 rounded-full transition-colors ${isComparing ? 'bg-accent text-primary' : 'bg-surface text-text-primary hover:bg-card-active'}`} title={isComparing ? "Hide Before/After (Y)" : "Before/After (Y)"}>
+        <Columns2 size={20} />
+      </button>
       <button onClick={onToggleShowOriginal} className="bg-surface text-text-primary p-2 rounded-full hover:bg-card-active transition-colors" title={showOriginal ? "Show Edited (.)" : "Show Original (.)"}>
         {showOriginal ? <EyeOff size={20} /> : <Eye size={20} />}
       </button>
@@ -317,6 +317,9 @@ const MaskOverlay = memo(({ subMask, scale, onUpdate, isSelected, onSelect, onMa
   return null;
 });
 
+// The brush settings are shared with brush masks, whose plain brush dodges here.
+const dodgeBurnTool = (tool) => (tool === 'burn' || tool === 'eraser' ? tool : 'dodge');
+
 const ImageCanvas = memo(({
   isCropping, crop, setCrop, handleCropComplete, adjustments, selectedImage,
   isMasking, imageRenderSize, showOriginal, finalPreviewUrl, isAdjusting,
@@ -348,6 +351,8 @@ const ImageCanvas = memo(({
   );
 
   const isBrushActive = isMasking && activeSubMask?.type === 'brush';
+  const isDodgeBurnActive = isMasking && activeSubMask?.type === 'dodge-burn';
+  const isPaintActive = isBrushActive || isDodgeBurnActive;
   const isAiSubjectActive = isMasking && activeSubMask?.type === 'ai-subject';
 
   const isGenerativeReplaceActive = aiTool === 'generative-replace';
@@ -360,7 +365,7 @@ const ImageCanvas = memo(({
   }, [activeContainer, activeMaskId]);
 
   useEffect(() => {
-    const { path: currentImagePath, originalUrl, thumbnailUrl } = selectedImage;
+    const { path: currentImagePath, originalUrl, thumbnailUrl, embeddedPreviewUrl } = selectedImage;
     const topLayer = layers[layers.length - 1];
 
     const imageChanged = currentImagePath !== imagePathRef.current;
@@ -397,6 +402,12 @@ const ImageCanvas = memo(({
       return;
     }
 
+    // The camera's embedded JPEG stands in for the thumbnail until the first developed preview.
+    if (embeddedPreviewUrl && !finalPreviewUrl && !latestEditedUrlRef.current && !layers.some(l => l.id === embeddedPreviewUrl)) {
+      setLayers(prev => [...prev, { id: embeddedPreviewUrl, url: embeddedPreviewUrl, opacity: 0 }]);
+      return;
+    }
+
     if (finalPreviewUrl && finalPreviewUrl !== latestEditedUrlRef.current) {
       latestEditedUrlRef.current = finalPreviewUrl;
       const img = new Image();
@@ -449,7 +460,7 @@ const ImageCanvas = memo(({
   }, [isCropping]);
 
   const handleMouseDown = useCallback((e) => {
-    const toolActive = isGenerativeReplaceActive || isBrushActive || isAiSubjectActive;
+    const toolActive = isGenerativeReplaceActive || isPaintActive || isAiSubjectActive;
     if (toolActive) {
       e.evt.preventDefault();
       isDrawing.current = true;
@@ -460,10 +471,11 @@ const ImageCanvas = memo(({
       let toolType = 'brush';
       if (isGenerativeReplaceActive) toolType = 'generative-replace';
       else if (isAiSubjectActive) toolType = 'ai-selector';
+      else if (isDodgeBurnActive) toolType = dodgeBurnTool(brushSettings.tool);
 
       const newLine = {
         tool: toolType,
-        brushSize: isBrushActive ? brushSettings.size : (isGenerativeReplaceActive ? 50 : 2),
+        brushSize: isPaintActive ? brushSettings.size : (isGenerativeReplaceActive ? 50 : 2),
         points: [pos]
       };
       currentLine.current = newLine;
@@ -473,10 +485,10 @@ const ImageCanvas = memo(({
         onSelectMask(null);
       }
     }
-  }, [isGenerativeReplaceActive, isBrushActive, isAiSubjectActive, brushSettings, onSelectMask]);
+  }, [isGenerativeReplaceActive, isPaintActive, isAiSubjectActive, isDodgeBurnActive, brushSettings, onSelectMask]);
 
   const handleMouseMove = useCallback((e) => {
-    const toolActive = isGenerativeReplaceActive || isBrushActive || isAiSubjectActive;
+    const toolActive = isGenerativeReplaceActive || isPaintActive || isAiSubjectActive;
     if (toolActive) {
       const stage = e.target.getStage();
       const pos = stage.getPointerPosition();
@@ -499,7 +511,7 @@ const ImageCanvas = memo(({
     };
     currentLine.current = updatedLine;
     setPreviewLine(updatedLine);
-  }, [isGenerativeReplaceActive, isBrushActive, isAiSubjectActive]);
+  }, [isGenerativeReplaceActive, isPaintActive, isAiSubjectActive]);
 
   const handleMouseUp = useCallback(() => {
     if (!isDrawing.current || !currentLine.current) return;
@@ -563,7 +575,7 @@ const ImageCanvas = memo(({
       }
     } else if (isBrushActive) {
       const imageSpaceLine = {
-        tool: brushSettings.tool,
+        tool: brushSettings.tool === 'eraser' ? 'eraser' : 'brush',
         brushSize: brushSettings.size / scale,
         feather: brushSettings.feather / 100,
         points: line.points.map(p => ({
@@ -574,7 +586,7 @@ const ImageCanvas = memo(({
 
       const existingLines = activeSubMask.parameters.lines || [];
 
-      if (brushSettings.tool === 'eraser') {
+      if (imageSpaceLine.tool === 'eraser') {
         const remainingLines = existingLines.filter(
           drawnLine => !linesIntersect(imageSpaceLine, drawnLine)
         );
@@ -591,14 +603,32 @@ const ImageCanvas = memo(({
           }
         });
       }
+    } else if (isDodgeBurnActive) {
+      // Flat, rounded points keep the strokes small in the sidecar.
+      const stroke = {
+        tool: dodgeBurnTool(brushSettings.tool),
+        brushSize: Math.round(brushSettings.size / scale * 10) / 10,
+        feather: brushSettings.feather / 100,
+        flow: (brushSettings.flow ?? 25) / 100,
+        points: line.points.flatMap(p => [
+          Math.round((p.x / scale + cropX) * 10) / 10,
+          Math.round((p.y / scale + cropY) * 10) / 10,
+        ]),
+      };
+      updateSubMask(activeMaskId, {
+        parameters: {
+          ...activeSubMask.parameters,
+          strokes: [...(activeSubMask.parameters.strokes || []), stroke]
+        }
+      });
     }
-  }, [isGenerativeReplaceActive, isBrushActive, isAiSubjectActive, activeSubMask, activeMaskId, updateSubMask, adjustments.crop, imageRenderSize.scale, brushSettings, onGenerateAiMask, onAiMaskDrawingComplete, selectedImage.width, selectedImage.height]);
+  }, [isGenerativeReplaceActive, isPaintActive, isBrushActive, isDodgeBurnActive, isAiSubjectActive, activeSubMask, activeMaskId, updateSubMask, adjustments.crop, imageRenderSize.scale, brushSettings, onGenerateAiMask, onAiMaskDrawingComplete, selectedImage.width, selectedImage.height]);
 
   const handleMouseEnter = useCallback(() => {
-    if (isGenerativeReplaceActive || isBrushActive || isAiSubjectActive) {
+    if (isGenerativeReplaceActive || isPaintActive || isAiSubjectActive) {
       setCursorPreview(p => ({ ...p, visible: true }));
     }
-  }, [isGenerativeReplaceActive, isBrushActive, isAiSubjectActive]);
+  }, [isGenerativeReplaceActive, isPaintActive, isAiSubjectActive]);
 
   const handleMouseLeave = useCallback(() => {
     setCursorPreview(p => ({ ...p, visible: false }));
@@ -711,7 +741,7 @@ const ImageCanvas = memo(({
             zIndex: 4,
             opacity: showOriginal ? 0 : 1,
             pointerEvents: showOriginal ? 'none' : 'auto',
-            cursor: isGenerativeReplaceActive ? 'none' : ((isBrushActive || isAiSubjectActive) ? 'crosshair' : 'default'),
+            cursor: isGenerativeReplaceActive ? 'none' : ((isPaintActive || isAiSubjectActive) ? 'crosshair' : 'default'),
           }}
           onMouseDown={handleMouseDown}
           onMouseMove={handleMouseMove}
@@ -728,8 +758,8 @@ const ImageCanvas = memo(({
                 onUpdate={updateSubMask}
                 isSelected={subMask.id === activeMaskId}
                 onSelect={() => onSelectMask(subMask.id)}
-                onMaskMouseEnter={() => !(isBrushActive || isAiSubjectActive) && setIsMaskHovered(true)}
-                onMaskMouseLeave={() => !(isBrushActive || isAiSubjectActive) && setIsMaskHovered(false)}
+                onMaskMouseEnter={() => !(isPaintActive || isAiSubjectActive) && setIsMaskHovered(true)}
+                onMaskMouseLeave={() => !(isPaintActive || isAiSubjectActive) && setIsMaskHovered(false)}
                 adjustments={adjustments}
               />
             ))}
@@ -739,6 +769,8 @@ const ImageCanvas = memo(({
                 stroke={
                   previewLine.tool === 'eraser' ? '#f43f5e' :
                   previewLine.tool === 'generative-replace' ? '#8b5cf6' :
+                  previewLine.tool === 'dodge' ? '#facc15' :
+                  previewLine.tool === 'burn' ? '#3b82f6' :
                   '#0ea5e9'
                 }
                 strokeWidth={
@@ -758,7 +790,7 @@ const ImageCanvas = memo(({
                 }
               />
             )}
-            {(isBrushActive || isGenerativeReplaceActive) && cursorPreview.visible && (
+            {(isPaintActive || isGenerativeReplaceActive) && cursorPreview.visible && (
               <Circle
                 x={cursorPreview.x}
                 y={cursorPreview.y}
@@ -766,6 +798,7 @@ const ImageCanvas = memo(({
                 stroke={
                   isGenerativeReplaceActive ? '#8b5cf6' :
                   brushSettings.tool === 'eraser' ? '#f43f5e' :
+                  isDodgeBurnActive ? (dodgeBurnTool(brushSettings.tool) === 'burn' ? '#3b82f6' : '#facc15') :
                   '#0ea5e9'
                 }
                 strokeWidth={isGenerativeReplaceActive ? 2 : 1}
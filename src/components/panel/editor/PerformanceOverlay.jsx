@@ -0,0 +1,54 @@
+import { useRef } from 'react';
+import Draggable from 'react-draggable';
+import { Gauge } from 'lucide-react';
+import { motion } from 'framer-motion';
+
+const STAGES = [
+  { key: 'decode', label: 'Decode' },
+  { key: 'develop', label: 'Develop' },
+  { key: 'transform', label: 'Transform' },
+  { key: 'render', label: 'Render' },
+  { key: 'encode', label: 'Encode' },
+];
+
+const formatMs = (ms) => (ms >= 100 ? `${Math.round(ms)} ms` : `${ms.toFixed(1)} ms`);
+
+export default function PerformanceOverlay({ timings }) {
+  const nodeRef = useRef(null);
+  const stages = timings?.stages || {};
+
+  return (
+    <Draggable nodeRef={nodeRef} handle=".handle" bounds="parent">
+      <div
+        ref={nodeRef}
+        className="absolute top-20 right-6 w-[200px] z-50"
+      >
+        <motion.div
+          key="performance-content"
+          initial={{ opacity: 0, scale: 0.95 }}
+          animate={{ opacity: 1, scale: 1 }}
+          exit={{ opacity: 0, scale: 0.95 }}
+          transition={{ duration: 0.2, ease: 'easeOut' }}
+          className="bg-bg-secondary/80 backdrop-blur-sm rounded-lg shadow-lg text-text-secondary border border-surface/40 overflow-hidden"
+          style={{ transformOrigin: 'top right' }}
+        >
+          <div className="handle flex items-center gap-2 p-2 cursor-move">
+            <Gauge size={16} className="text-text-secondary" />
+            <h3 className="text-sm font-semibold text-text-primary [text-shadow:0_1px_3px_rgba(0,0,0,0.4)]">Performance</h3>
+          </div>
+          <div className="px-3 pb-2 space-y-1 text-xs">
+            {STAGES.filter(({ key }) => stages[key]).map(({ key, label }) => (
+              <div key={key} className="flex justify-between">
+                <span>{label}</span>
+                <span className="font-mono text-text-primary" title={`Average ${formatMs(stages[key].totalMs / stages[key].count)}, max ${formatMs(stages[key].maxMs)}`}>
+                  {formatMs(stages[key].lastMs)}
+                </span>
+              </div>
+            ))}
+            {Object.keys(stages).length === 0 && <p>Waiting for the next preview…</p>}
+          </div>
+        </motion.div>
+      </div>
+    </Draggable>
+  );
+}
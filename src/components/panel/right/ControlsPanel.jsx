@@ -107,7 +107,7 @@ export default function Controls({
         <h2 className="text-xl font-bold text-primary text-shadow-shiny">Adjustments</h2>
         <div className="flex items-center gap-1">
           <button
-            onClick={handleAutoAdjustments}
+            onClick={() => handleAutoAdjustments()}
             disabled={!selectedImage}
             className="p-2 rounded-full hover:bg-surface disabled:opacity-50 disabled:cursor-not-allowed transition-colors"
             title="Auto Adjustments"
@@ -152,6 +152,9 @@ export default function Controls({
                   setAdjustments={setAdjustments}
                   histogram={histogram}
                   theme={theme}
+                  isRaw={selectedImage?.isRaw}
+                  isHdr={selectedImage?.isHdr}
+                  imagePath={selectedImage?.path}
                 />
               </CollapsibleSection>
             </div>
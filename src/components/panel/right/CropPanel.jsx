@@ -1,6 +1,10 @@
 import { RotateCcw, X, RectangleHorizontal, RectangleVertical, FlipHorizontal, FlipVertical, RotateCw } from 'lucide-react';
 import { useState, useEffect, useCallback, useMemo } from 'react';
-import { INITIAL_ADJUSTMENTS } from '../../../utils/adjustments';
+import { invoke } from '@tauri-apps/api/core';
+import { INITIAL_ADJUSTMENTS, INITIAL_LENS_CORRECTION } from '../../../utils/adjustments';
+import Switch from '../../ui/Switch';
+import Dropdown from '../../ui/Dropdown';
+import Slider from '../../ui/Slider';
 import clsx from 'clsx';
 
 const PRESETS = [
@@ -45,6 +49,30 @@ const ToolButton = ({ icon: Icon, label, onClick, isActive = false }) => (
 export default function CropPanel({ selectedImage, adjustments, setAdjustments }) {
   const [customW, setCustomW] = useState('');
   const [customH, setCustomH] = useState('');
+  const [lensProfiles, setLensProfiles] = useState([]);
+  const [detectedLens, setDetectedLens] = useState(null);
+
+  useEffect(() => {
+    invoke('list_lens_profiles').then(setLensProfiles).catch(err => console.error("Failed to list lens profiles:", err));
+  }, []);
+
+  useEffect(() => {
+    setDetectedLens(null);
+    if (!selectedImage?.path) return;
+    invoke('detect_lens_profile', { path: selectedImage.path })
+      .then(setDetectedLens)
+      .catch(err => console.error("Failed to detect lens profile:", err));
+  }, [selectedImage?.path]);
+
+  const lensCorrection = { ...INITIAL_LENS_CORRECTION, ...(adjustments.lensCorrection || {}) };
+  const lensCorrectionEnabled = lensCorrection.enabled ?? !!selectedImage?.isRaw;
+  const updateLensCorrection = (changes) => {
+    setAdjustments(prev => ({ ...prev, lensCorrection: { ...INITIAL_LENS_CORRECTION, ...(prev.lensCorrection || {}), ...changes } }));
+  };
+  const lensOptions = [
+    { value: null, label: detectedLens ? `Automatic (${detectedLens})` : 'Automatic (no profile found)' },
+    ...lensProfiles.map(id => ({ value: id, label: id })),
+  ];
 
   const { aspectRatio, rotation = 0, flipHorizontal = false, flipVertical = false } = adjustments;
   const activePreset = PRESETS.find(p => doesRatioMatchPreset(aspectRatio, p, selectedImage));
@@ -246,6 +274,30 @@ export default function CropPanel({ selectedImage, adjustments, setAdjustments }
                 <ToolButton icon={FlipVertical} label="Flip Vert" onClick={() => setAdjustments(prev => ({ ...prev, flipVertical: !prev.flipVertical }))} isActive={flipVertical} />
               </div>
             </div>
+
+            <div className="space-y-3">
+              <p className="text-sm mb-3 font-semibold text-text-primary">Lens Correction</p>
+              <Switch label="Enable Profile Corrections" checked={lensCorrectionEnabled} onChange={(checked) => updateLensCorrection({ enabled: checked })} />
+              <div className={clsx('space-y-3', !lensCorrectionEnabled && 'opacity-50 pointer-events-none')}>
+                <Dropdown options={lensOptions} value={lensCorrection.lens} onChange={(value) => updateLensCorrection({ lens: value })} />
+                <Switch label="Distortion" checked={lensCorrection.distortion} onChange={(checked) => updateLensCorrection({ distortion: checked })} />
+                <Switch label="Vignetting" checked={lensCorrection.vignetting} onChange={(checked) => updateLensCorrection({ vignetting: checked })} />
+                <Switch label="Chromatic Aberration" checked={lensCorrection.tca} onChange={(checked) => updateLensCorrection({ tca: checked })} />
+                {lensProfiles.length === 0 && (
+                  <p className="text-xs text-text-secondary">No lensfun database found. Install lensfun or copy its XML files into the app's lensfun data folder.</p>
+                )}
+              </div>
+              <Slider
+                label="Manual Distortion"
+                value={adjustments.lensDistortion || 0}
+                onChange={(e) => setAdjustments(prev => ({ ...prev, lensDistortion: parseFloat(e.target.value) }))}
+                min="-100" max="100" step="1"
+              />
+              <div className="flex justify-between text-xs text-text-tertiary">
+                <span>Remove Pincushion</span>
+                <span>Remove Barrel</span>
+              </div>
+            </div>
           </>
         ) : (
           <p className="text-center text-text-tertiary mt-4">No image selected.</p>
@@ -8,6 +8,17 @@ const FILE_FORMATS = [
   { id: 'jpeg', name: 'JPEG', extensions: ['jpg', 'jpeg'] },
   { id: 'png', name: 'PNG', extensions: ['png'] },
   { id: 'tiff', name: 'TIFF', extensions: ['tiff'] },
+  { id: 'ptif', name: 'Pyramid TIFF', extensions: ['ptif'] },
+  { id: 'dzi', name: 'Deep Zoom', extensions: ['dzi'] },
+];
+
+const METADATA_PROFILES = [
+  { id: 'everything', name: 'All Metadata' },
+  { id: 'allExceptLocation', name: 'All Except Location' },
+  { id: 'allExceptSerialNumbers', name: 'All Except Serial Numbers' },
+  { id: 'cameraAndExposure', name: 'Camera & Exposure Info' },
+  { id: 'copyrightOnly', name: 'Copyright Only' },
+  { id: 'none', name: 'None' },
 ];
 
 const FILENAME_VARIABLES = [
@@ -38,8 +49,13 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
   const [resizeMode, setResizeMode] = useState('longEdge');
   const [resizeValue, setResizeValue] = useState(2048);
   const [dontEnlarge, setDontEnlarge] = useState(true);
-  const [keepMetadata, setKeepMetadata] = useState(true);
-  const [stripGps, setStripGps] = useState(true);
+  const [resizeFilter, setResizeFilter] = useState('lanczos');
+  const [dpi, setDpi] = useState('');
+  const [convertToCmyk, setConvertToCmyk] = useState(false);
+  const [cmykProfilePath, setCmykProfilePath] = useState('');
+  const [renderingIntent, setRenderingIntent] = useState('perceptual');
+  const [metadataProfile, setMetadataProfile] = useState('allExceptLocation');
+  const [writeXmpSidecar, setWriteXmpSidecar] = useState(false);
   const [filenameTemplate, setFilenameTemplate] = useState('{original_filename}_edited');
   const filenameInputRef = useRef(null);
 
@@ -77,6 +93,16 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
     }, 0);
   };
 
+  const handleSelectCmykProfile = async () => {
+    const profilePath = await open({
+      title: 'Select CMYK ICC Profile',
+      filters: [{ name: 'ICC Profiles', extensions: ['icc', 'icm'] }],
+    });
+    if (typeof profilePath === 'string') {
+      setCmykProfilePath(profilePath);
+    }
+  };
+
   const handleExport = async () => {
     if (numImages === 0 || isExporting) return;
 
@@ -90,9 +116,13 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
 
     const exportSettings = {
       jpegQuality: parseInt(jpegQuality, 10),
-      resize: enableResize ? { mode: resizeMode, value: parseInt(resizeValue, 10), dontEnlarge } : null,
-      keepMetadata,
-      stripGps,
+      resize: enableResize ? { mode: resizeMode, value: parseFloat(resizeValue), dontEnlarge, filter: resizeFilter } : null,
+      dpi: dpi ? parseInt(dpi, 10) : null,
+      cmyk: fileFormat === 'tiff' && convertToCmyk && cmykProfilePath
+        ? { profilePath: cmykProfilePath, intent: renderingIntent, blackPointCompensation: true }
+        : null,
+      metadataProfile,
+      writeXmpSidecar,
       filenameTemplate: finalFilenameTemplate,
     };
 
@@ -172,7 +202,7 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
                   </button>
                 ))}
               </div>
-              {fileFormat === 'jpeg' && (
+              {(fileFormat === 'jpeg' || fileFormat === 'dzi') && (
                 <div className="flex items-center gap-2">
                   <label className="text-sm w-20">Quality</label>
                   <input
@@ -187,6 +217,48 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
                   <span className="text-sm font-mono w-12 text-right">{jpegQuality}</span>
                 </div>
               )}
+              {(fileFormat === 'ptif' || fileFormat === 'dzi') && (
+                <p className="text-xs text-text-secondary">
+                  Written tile by tile for gigapixel images, with all zoom levels. Resizing, watermarks and borders are not applied.
+                </p>
+              )}
+              {fileFormat === 'tiff' && (
+                <>
+                  <Switch
+                    label="Convert to CMYK"
+                    checked={convertToCmyk}
+                    onChange={setConvertToCmyk}
+                    disabled={isExporting}
+                  />
+                  {convertToCmyk && (
+                    <div className="pl-2 border-l-2 border-surface space-y-2">
+                      <div className="flex items-center gap-2">
+                        <button
+                          onClick={handleSelectCmykProfile}
+                          disabled={isExporting}
+                          className="px-2 py-1 bg-surface text-text-secondary text-xs rounded-md hover:bg-card-active transition-colors disabled:opacity-50"
+                        >
+                          Select Profile
+                        </button>
+                        <span className="text-xs text-text-tertiary truncate" title={cmykProfilePath}>
+                          {cmykProfilePath ? cmykProfilePath.split(/[\\/]/).pop() : 'No profile selected'}
+                        </span>
+                      </div>
+                      <select
+                        value={renderingIntent}
+                        onChange={(e) => setRenderingIntent(e.target.value)}
+                        disabled={isExporting}
+                        className="w-full bg-bg-primary border border-surface rounded-md p-2 text-sm focus:ring-accent focus:border-accent"
+                      >
+                        <option value="perceptual">Perceptual</option>
+                        <option value="relativeColorimetric">Relative Colorimetric</option>
+                        <option value="saturation">Saturation</option>
+                        <option value="absoluteColorimetric">Absolute Colorimetric</option>
+                      </select>
+                    </div>
+                  )}
+                </>
+              )}
             </Section>
 
             {isBatchMode && (
@@ -233,6 +305,8 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
                       <option value="longEdge">Long Edge</option>
                       <option value="width">Width</option>
                       <option value="height">Height</option>
+                      <option value="megapixels">Megapixels</option>
+                      <option value="percent">Percent</option>
                     </select>
                     <input
                       type="number"
@@ -240,9 +314,25 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
                       onChange={(e) => setResizeValue(e.target.value)}
                       disabled={isExporting}
                       className="w-24 bg-bg-primary text-center rounded-md p-2 border border-surface focus:border-accent focus:ring-accent"
-                      min="1"
+                      min="0.1"
+                      step="any"
                     />
-                    <span className="text-sm">pixels</span>
+                    <span className="text-sm">
+                      {resizeMode === 'megapixels' ? 'MP' : resizeMode === 'percent' ? '%' : 'pixels'}
+                    </span>
+                  </div>
+                  <div className="flex items-center justify-between gap-2">
+                    <span className="text-sm">Resampling</span>
+                    <select
+                      value={resizeFilter}
+                      onChange={(e) => setResizeFilter(e.target.value)}
+                      disabled={isExporting}
+                      className="bg-bg-primary border border-surface rounded-md p-2 text-sm text-text-primary focus:ring-accent focus:border-accent"
+                    >
+                      <option value="lanczos">Lanczos (Sharpest)</option>
+                      <option value="catmullRom">Catmull-Rom</option>
+                      <option value="fast">Fast</option>
+                    </select>
                   </div>
                   <Switch
                     label="Don't Enlarge"
@@ -252,25 +342,40 @@ export default function ExportPanel({ selectedImage, adjustments, multiSelectedP
                   />
                 </div>
               )}
+              <div className="flex items-center justify-between mt-4">
+                <span className="text-sm">Output Resolution</span>
+                <div className="flex items-center gap-2">
+                  <input
+                    type="number"
+                    value={dpi}
+                    onChange={(e) => setDpi(e.target.value)}
+                    disabled={isExporting}
+                    placeholder="—"
+                    className="w-20 bg-bg-primary text-center rounded-md p-2 border border-surface focus:border-accent focus:ring-accent"
+                    min="1"
+                  />
+                  <span className="text-sm">DPI</span>
+                </div>
+              </div>
             </Section>
 
             <Section title="Metadata">
+              <select
+                value={metadataProfile}
+                onChange={(e) => setMetadataProfile(e.target.value)}
+                disabled={isExporting}
+                className="w-full bg-bg-primary border border-surface rounded-md p-2 text-sm text-text-primary focus:ring-accent focus:border-accent"
+              >
+                {METADATA_PROFILES.map(profile => (
+                  <option key={profile.id} value={profile.id}>{profile.name}</option>
+                ))}
+              </select>
               <Switch
-                label="Keep Original Metadata"
-                checked={keepMetadata}
-                onChange={setKeepMetadata}
+                label="Write XMP Sidecar"
+                checked={writeXmpSidecar}
+                onChange={setWriteXmpSidecar}
                 disabled={isExporting}
               />
-              {keepMetadata && (
-                <div className="pl-2 border-l-2 border-surface">
-                  <Switch
-                    label="Remove GPS Data"
-                    checked={stripGps}
-                    onChange={setStripGps}
-                    disabled={isExporting}
-                  />
-                </div>
-              )}
             </Section>
           </>
         ) : (
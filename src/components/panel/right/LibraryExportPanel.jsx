@@ -8,6 +8,17 @@ const FILE_FORMATS = [
   { id: 'jpeg', name: 'JPEG', extensions: ['jpg', 'jpeg'] },
   { id: 'png', name: 'PNG', extensions: ['png'] },
   { id: 'tiff', name: 'TIFF', extensions: ['tiff'] },
+  { id: 'ptif', name: 'Pyramid TIFF', extensions: ['ptif'] },
+  { id: 'dzi', name: 'Deep Zoom', extensions: ['dzi'] },
+];
+
+const METADATA_PROFILES = [
+  { id: 'everything', name: 'All Metadata' },
+  { id: 'allExceptLocation', name: 'All Except Location' },
+  { id: 'allExceptSerialNumbers', name: 'All Except Serial Numbers' },
+  { id: 'cameraAndExposure', name: 'Camera & Exposure Info' },
+  { id: 'copyrightOnly', name: 'Copyright Only' },
+  { id: 'none', name: 'None' },
 ];
 
 const FILENAME_VARIABLES = [
@@ -18,6 +29,11 @@ const FILENAME_VARIABLES = [
   '{DD}',
   '{hh}',
   '{mm}',
+  '{camera}',
+  '{lens}',
+  '{iso}',
+  '{capture_date}',
+  '{rating}',
 ];
 
 function Section({ title, children }) {
@@ -38,8 +54,10 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
   const [resizeMode, setResizeMode] = useState('longEdge');
   const [resizeValue, setResizeValue] = useState(2048);
   const [dontEnlarge, setDontEnlarge] = useState(true);
-  const [keepMetadata, setKeepMetadata] = useState(true);
-  const [stripGps, setStripGps] = useState(true);
+  const [resizeFilter, setResizeFilter] = useState('lanczos');
+  const [dpi, setDpi] = useState('');
+  const [metadataProfile, setMetadataProfile] = useState('allExceptLocation');
+  const [writeXmpSidecar, setWriteXmpSidecar] = useState(false);
   const [filenameTemplate, setFilenameTemplate] = useState('{original_filename}_edited');
   const filenameInputRef = useRef(null);
 
@@ -85,9 +103,10 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
 
     const exportSettings = {
       jpegQuality: parseInt(jpegQuality, 10),
-      resize: enableResize ? { mode: resizeMode, value: parseInt(resizeValue, 10), dontEnlarge } : null,
-      keepMetadata,
-      stripGps,
+      resize: enableResize ? { mode: resizeMode, value: parseFloat(resizeValue), dontEnlarge, filter: resizeFilter } : null,
+      dpi: dpi ? parseInt(dpi, 10) : null,
+      metadataProfile,
+      writeXmpSidecar,
       filenameTemplate: finalFilenameTemplate,
     };
 
@@ -153,7 +172,7 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
                   </button>
                 ))}
               </div>
-              {fileFormat === 'jpeg' && (
+              {(fileFormat === 'jpeg' || fileFormat === 'dzi') && (
                 <div className="flex items-center gap-2">
                   <label className="text-sm w-20">Quality</label>
                   <input
@@ -168,6 +187,11 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
                   <span className="text-sm font-mono w-12 text-right">{jpegQuality}</span>
                 </div>
               )}
+              {(fileFormat === 'ptif' || fileFormat === 'dzi') && (
+                <p className="text-xs text-text-secondary">
+                  Written tile by tile for gigapixel images, with all zoom levels. Resizing, watermarks and borders are not applied.
+                </p>
+              )}
             </Section>
 
             <Section title="File Naming">
@@ -212,6 +236,8 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
                       <option value="longEdge">Long Edge</option>
                       <option value="width">Width</option>
                       <option value="height">Height</option>
+                      <option value="megapixels">Megapixels</option>
+                      <option value="percent">Percent</option>
                     </select>
                     <input
                       type="number"
@@ -219,9 +245,25 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
                       onChange={(e) => setResizeValue(e.target.value)}
                       disabled={isExporting}
                       className="w-24 bg-bg-primary text-center rounded-md p-2 border border-surface focus:border-accent focus:ring-accent"
-                      min="1"
+                      min="0.1"
+                      step="any"
                     />
-                    <span className="text-sm">pixels</span>
+                    <span className="text-sm">
+                      {resizeMode === 'megapixels' ? 'MP' : resizeMode === 'percent' ? '%' : 'pixels'}
+                    </span>
+                  </div>
+                  <div className="flex items-center justify-between gap-2">
+                    <span className="text-sm">Resampling</span>
+                    <select
+                      value={resizeFilter}
+                      onChange={(e) => setResizeFilter(e.target.value)}
+                      disabled={isExporting}
+                      className="bg-bg-primary border border-surface rounded-md p-2 text-sm text-text-primary focus:ring-accent focus:border-accent"
+                    >
+                      <option value="lanczos">Lanczos (Sharpest)</option>
+                      <option value="catmullRom">Catmull-Rom</option>
+                      <option value="fast">Fast</option>
+                    </select>
                   </div>
                   <Switch
                     label="Don't Enlarge"
@@ -231,25 +273,40 @@ export default function LibraryExportPanel({ multiSelectedPaths, onClose, isVisi
                   />
                 </div>
               )}
+              <div className="flex items-center justify-between mt-4">
+                <span className="text-sm">Output Resolution</span>
+                <div className="flex items-center gap-2">
+                  <input
+                    type="number"
+                    value={dpi}
+                    onChange={(e) => setDpi(e.target.value)}
+                    disabled={isExporting}
+                    placeholder="—"
+                    className="w-20 bg-bg-primary text-center rounded-md p-2 border border-surface focus:border-accent focus:ring-accent"
+                    min="1"
+                  />
+                  <span className="text-sm">DPI</span>
+                </div>
+              </div>
             </Section>
 
             <Section title="Metadata">
+              <select
+                value={metadataProfile}
+                onChange={(e) => setMetadataProfile(e.target.value)}
+                disabled={isExporting}
+                className="w-full bg-bg-primary border border-surface rounded-md p-2 text-sm text-text-primary focus:ring-accent focus:border-accent"
+              >
+                {METADATA_PROFILES.map(profile => (
+                  <option key={profile.id} value={profile.id}>{profile.name}</option>
+                ))}
+              </select>
               <Switch
-                label="Keep Original Metadata"
-                checked={keepMetadata}
-                onChange={setKeepMetadata}
+                label="Write XMP Sidecar"
+                checked={writeXmpSidecar}
+                onChange={setWriteXmpSidecar}
                 disabled={isExporting}
               />
-              {keepMetadata && (
-                <div className="pl-2 border-l-2 border-surface">
-                  <Switch
-                    label="Remove GPS Data"
-                    checked={stripGps}
-                    onChange={setStripGps}
-                    disabled={isExporting}
-                  />
-                </div>
-              )}
             </Section>
           </>
         ) : (
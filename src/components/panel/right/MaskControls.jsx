@@ -3,7 +3,7 @@ import { v4 as uuidv4 } from 'uuid';
 import { motion, AnimatePresence } from 'framer-motion';
 import {
   RotateCcw, Copy, ClipboardPaste, Circle, TriangleRight, Brush, Droplet, Sparkles, User,
-  Trash2, Eye, EyeOff, Plus, Minus
+  Trash2, Eye, EyeOff, Plus, Minus, SunMoon
 } from 'lucide-react';
 
 import CollapsibleSection from '../../ui/CollapsibleSection';
@@ -30,12 +30,14 @@ const MASK_TYPES = [
 function formatMaskTypeName(type) {
   if (type === 'ai-subject') return 'AI Subject';
   if (type === 'ai-foreground') return 'AI Foreground';
+  if (type === 'dodge-burn') return 'Dodge & Burn';
   return type.charAt(0).toUpperCase() + type.slice(1);
 }
 
 const SUB_MASK_CONFIG = {
   radial: { parameters: [{ key: 'feather', label: 'Feather', min: 0, max: 100, step: 1, multiplier: 100, defaultValue: 50 }] },
   brush: { showBrushTools: true },
+  'dodge-burn': { showDodgeBurnTools: true },
   linear: { parameters: [] },
   color: { parameters: [] },
   luminance: { parameters: [] },
@@ -54,6 +56,31 @@ const BrushTools = ({ settings, onSettingsChange }) => (
   </div>
 );
 
+const DODGE_BURN_TOOLS = [
+  { tool: 'dodge', label: 'Dodge' },
+  { tool: 'burn', label: 'Burn' },
+  { tool: 'eraser', label: 'Eraser' },
+];
+
+// Dodge & burn paints its own exposure and contrast, so it has no mask adjustments to set.
+const DodgeBurnTools = ({ parameters, onParameterChange, settings, onSettingsChange }) => {
+  const activeTool = settings.tool === 'burn' || settings.tool === 'eraser' ? settings.tool : 'dodge';
+  return (
+    <div className="space-y-4 pt-4 border-t border-surface mt-4">
+      <Slider label="Exposure" value={parameters.exposure ?? 1} onChange={(e) => onParameterChange('exposure', parseFloat(e.target.value))} min="0" max="3" step="0.05" defaultValue={1} />
+      <Slider label="Contrast" value={parameters.contrast ?? 0} onChange={(e) => onParameterChange('contrast', parseFloat(e.target.value))} min="-100" max="100" step="1" defaultValue={0} />
+      <Slider label="Brush Size" value={settings.size} onChange={(e) => onSettingsChange(s => ({ ...s, size: Number(e.target.value) }))} min="1" max="200" step="1" defaultValue="100" />
+      <Slider label="Brush Feather" value={settings.feather} onChange={(e) => onSettingsChange(s => ({ ...s, feather: Number(e.target.value) }))} min="0" max="100" step="1" defaultValue="50" />
+      <Slider label="Flow" value={settings.flow} onChange={(e) => onSettingsChange(s => ({ ...s, flow: Number(e.target.value) }))} min="1" max="100" step="1" defaultValue="25" />
+      <div className="grid grid-cols-3 gap-2 pt-2">
+        {DODGE_BURN_TOOLS.map(({ tool, label }) => (
+          <button key={tool} onClick={() => onSettingsChange(s => ({ ...s, tool }))} className={`p-2 rounded-md text-sm font-medium transition-colors flex items-center justify-center gap-2 ${activeTool === tool ? 'text-primary bg-surface' : 'bg-surface text-text-secondary hover:bg-card-active'}`}>{label}</button>
+        ))}
+      </div>
+    </div>
+  );
+};
+
 export default function MaskControls({
   editingMask, activeSubMask, updateMask, updateSubMask,
   brushSettings, setBrushSettings, histogram, isGeneratingAiMask, aiModelDownloadStatus,
@@ -202,11 +229,12 @@ export default function MaskControls({
   };
 
   const isAiMask = activeSubMask && (activeSubMask.type === 'ai-subject' || activeSubMask.type === 'ai-foreground');
+  const isDodgeBurn = editingMask.subMasks.some(sm => sm.type === 'dodge-burn');
   const sectionVisibility = editingMask.adjustments.sectionVisibility || INITIAL_MASK_ADJUSTMENTS.sectionVisibility;
 
   return (
     <>
-      <div className="p-4 border-b border-surface">
+      {!isDodgeBurn && <div className="p-4 border-b border-surface">
         <p className="text-sm mb-3 font-semibold text-text-primary">Add Component</p>
         <div className="grid grid-cols-3 gap-2">
           {MASK_TYPES.map(maskType => (
@@ -222,7 +250,7 @@ export default function MaskControls({
             </button>
           ))}
         </div>
-      </div>
+      </div>}
 
       <div className="p-4 space-y-2" onClick={handleDeselectSubMask}>
         <p className="text-sm mb-3 font-semibold text-text-primary">Mask Components</p>
@@ -234,7 +262,7 @@ export default function MaskControls({
         ) : (
           <AnimatePresence>
             {editingMask.subMasks.filter(sm => sm.id !== deletingItemId).map((subMask) => {
-              const MaskIcon = MASK_TYPES.find(mt => mt.type === subMask.type)?.icon || Circle;
+              const MaskIcon = subMask.type === 'dodge-burn' ? SunMoon : (MASK_TYPES.find(mt => mt.type === subMask.type)?.icon || Circle);
               return (
                 <motion.div
                   key={subMask.id}
@@ -286,6 +314,9 @@ export default function MaskControls({
                 {subMaskConfig.showBrushTools && brushSettings && setBrushSettings && (
                   <BrushTools settings={brushSettings} onSettingsChange={setBrushSettings} />
                 )}
+                {subMaskConfig.showDodgeBurnTools && brushSettings && setBrushSettings && (
+                  <DodgeBurnTools parameters={activeSubMask.parameters} onParameterChange={handleSubMaskParameterChange} settings={brushSettings} onSettingsChange={setBrushSettings} />
+                )}
               </>
             )}
           </div>
@@ -296,12 +327,12 @@ export default function MaskControls({
           onMouseEnter={() => setIsMaskControlHovered(true)}
           onMouseLeave={() => setIsMaskControlHovered(false)}
         >
-          {Object.keys(ADJUSTMENT_SECTIONS).map(sectionName => {
+          {!isDodgeBurn && Object.keys(ADJUSTMENT_SECTIONS).map(sectionName => {
             const SectionComponent = { basic: BasicAdjustments, curves: CurveGraph, color: ColorPanel, details: DetailsPanel, effects: EffectsPanel }[sectionName];
             const title = sectionName.charAt(0).toUpperCase() + sectionName.slice(1);
             return (
               <CollapsibleSection key={sectionName} title={title} isOpen={collapsibleState[sectionName]} onToggle={() => handleToggleSection(sectionName)} onContextMenu={(e) => handleSectionContextMenu(e, sectionName)} isContentVisible={sectionVisibility[sectionName]} onToggleVisibility={() => handleToggleVisibility(sectionName)}>
-                <SectionComponent adjustments={editingMask.adjustments} setAdjustments={setMaskContainerAdjustments} histogram={histogram} isForMask={sectionName === 'effects'} />
+                <SectionComponent adjustments={editingMask.adjustments} setAdjustments={setMaskContainerAdjustments} histogram={histogram} isForMask />
               </CollapsibleSection>
             );
           })}
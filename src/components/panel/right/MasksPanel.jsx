@@ -3,7 +3,7 @@ import { v4 as uuidv4 } from 'uuid';
 import { motion, AnimatePresence } from 'framer-motion';
 import {
   Trash2, RotateCcw, ArrowLeft, Eye, EyeOff, Edit, Copy, ClipboardPaste, PlusSquare,
-  ChevronsRight, FileEdit, Sparkles, User, Brush, TriangleRight, Circle, Droplet
+  ChevronsRight, FileEdit, Sparkles, User, Brush, TriangleRight, Circle, Droplet, Sunrise, SunMoon
 } from 'lucide-react';
 import MaskControls from './MaskControls';
 import { INITIAL_MASK_ADJUSTMENTS, INITIAL_MASK_CONTAINER } from '../../../utils/adjustments';
@@ -32,7 +32,8 @@ export default function MasksPanel({
   adjustments, setAdjustments, selectedImage, onSelectMask, activeMaskId,
   activeMaskContainerId, onSelectContainer, setIsMaskControlHovered,
   brushSettings, setBrushSettings, copiedMask, setCopiedMask, histogram,
-  setCustomEscapeHandler, isGeneratingAiMask, aiModelDownloadStatus, onGenerateAiForegroundMask
+  setCustomEscapeHandler, isGeneratingAiMask, aiModelDownloadStatus, onGenerateAiForegroundMask,
+  onGenerateGraduatedSky
 }) {
   const [deletingItemId, setDeletingItemId] = useState(null);
   const [renamingContainerId, setRenamingContainerId] = useState(null);
@@ -82,6 +83,7 @@ export default function MasksPanel({
       case 'brush': return { ...common, parameters: { lines: [] } };
       case 'ai-subject': return { ...common, parameters: { startX: 0, startY: 0, endX: 0, endY: 0, maskDataBase64: null } };
       case 'ai-foreground': return { ...common, parameters: { maskDataBase64: null } };
+      case 'dodge-burn': return { ...common, parameters: { exposure: 1, contrast: 0, strokes: [] } };
       default: return { ...common, parameters: {} };
     }
   };
@@ -91,7 +93,7 @@ export default function MasksPanel({
     const newContainer = {
       ...INITIAL_MASK_CONTAINER,
       id: uuidv4(),
-      name: `Mask ${adjustments.masks.length + 1}`,
+      name: type === 'dodge-burn' ? 'Dodge & Burn' : `Mask ${adjustments.masks.length + 1}`,
       subMasks: [subMask],
     };
     setAdjustments(prev => ({ ...prev, masks: [...(prev.masks || []), newContainer] }));
@@ -102,6 +104,19 @@ export default function MasksPanel({
     }
   };
 
+  const handleAddGraduatedSky = async () => {
+    const mask = await onGenerateGraduatedSky();
+    if (!mask) return;
+    const newContainer = {
+      ...INITIAL_MASK_CONTAINER,
+      ...mask,
+      adjustments: { ...INITIAL_MASK_ADJUSTMENTS, ...mask.adjustments },
+    };
+    setAdjustments(prev => ({ ...prev, masks: [...(prev.masks || []), newContainer] }));
+    onSelectContainer(newContainer.id);
+    onSelectMask(newContainer.subMasks[0]?.id ?? null);
+  };
+
   const handleDeleteContainer = (id) => {
     setDeletingItemId(id);
     setTimeout(() => {
@@ -298,6 +313,14 @@ export default function MasksPanel({
               </button>
             ))}
           </div>
+          <button onClick={handleAddGraduatedSky} disabled={isGeneratingAiMask} className={`mt-2 w-full bg-surface text-text-primary rounded-lg p-2 flex items-center justify-center gap-2 transition-colors ${isGeneratingAiMask ? 'opacity-50 cursor-not-allowed' : 'hover:bg-card-active'}`} title="Darken the sky down to the detected horizon">
+            <Sunrise size={18} />
+            <span className="text-xs">Graduated Sky</span>
+          </button>
+          <button onClick={() => handleAddMaskContainer('dodge-burn')} disabled={isGeneratingAiMask} className={`mt-2 w-full bg-surface text-text-primary rounded-lg p-2 flex items-center justify-center gap-2 transition-colors ${isGeneratingAiMask ? 'opacity-50 cursor-not-allowed' : 'hover:bg-card-active'}`} title="Paint exposure and contrast changes directly">
+            <SunMoon size={18} />
+            <span className="text-xs">Dodge & Burn</span>
+          </button>
         </div>
         {adjustments.masks.length > 0 && (
           <div onClick={(e) => e.stopPropagation()}>
@@ -46,9 +46,12 @@ const KEY_CAMERA_SETTINGS_MAP = {
     label: 'Lens',
     format: (value) => String(value).replace(/"/g, ''),
   },
+  PictureStyle: {
+    label: 'Picture Style',
+  },
 };
 
-const KEY_SETTINGS_ORDER = ['FNumber', 'ExposureTime', 'PhotographicSensitivity', 'FocalLength', 'LensModel'];
+const KEY_SETTINGS_ORDER = ['FNumber', 'ExposureTime', 'PhotographicSensitivity', 'FocalLength', 'LensModel', 'PictureStyle'];
 
 export default function MetadataPanel({ selectedImage }) {
   const { keyCameraSettings, gpsData, otherExifEntries } = useMemo(() => {
@@ -19,6 +19,7 @@ export const useKeyboardShortcuts = ({
   setLibraryActivePath,
   setMultiSelectedPaths,
   handleRate,
+  handleToggleCullingMode,
   handleDeleteSelected,
   handleCopyAdjustments,
   handlePasteAdjustments,
@@ -29,6 +30,9 @@ export const useKeyboardShortcuts = ({
   handleBackToLibrary,
   handleToggleFullScreen,
   setShowOriginal,
+  setShowClipping,
+  setCompareMode,
+  adjustments,
   handleRightPanelSelect,
   setIsWaveformVisible,
   handleZoomChange,
@@ -69,6 +73,15 @@ export const useKeyboardShortcuts = ({
         }
         if (key === 'f' && !isCtrl) { event.preventDefault(); handleToggleFullScreen(); }
         if (key === 'b' && !isCtrl) { event.preventDefault(); setShowOriginal(prev => !prev); }
+        if (key === 'j' && !isCtrl) { event.preventDefault(); setShowClipping(prev => !prev); }
+        if (key === 'y' && !isCtrl) {
+          // Shift compares against the adjustments as they are now, for trying further edits. AI
+          // patches are part of the base image and left out, they are large to send every frame.
+          event.preventDefault();
+          const { aiPatches, ...rendered } = adjustments;
+          const snapshot = { adjustments: event.shiftKey ? rendered : null };
+          setCompareMode(prev => prev ? null : snapshot);
+        }
         if (key === 'r' && !isCtrl) { event.preventDefault(); handleRightPanelSelect('crop'); }
         if (key === 'm' && !isCtrl) { event.preventDefault(); handleRightPanelSelect('masks'); }
         if (key === 'i' && !isCtrl) { event.preventDefault(); handleRightPanelSelect('metadata'); }
@@ -113,6 +126,8 @@ export const useKeyboardShortcuts = ({
         }
       }
 
+      if (!selectedImage && key === 'c' && !isCtrl) { event.preventDefault(); handleToggleCullingMode(); }
+
       if (['0', '1', '2', '3', '4', '5'].includes(key) && !isCtrl) { event.preventDefault(); handleRate(parseInt(key, 10)); }
       if (key === 'delete') { event.preventDefault(); handleDeleteSelected(); }
 
@@ -131,5 +146,5 @@ export const useKeyboardShortcuts = ({
     return () => {
       window.removeEventListener('keydown', handleKeyDown);
     };
-  }, [ sortedImageList, selectedImage, undo, redo, isFullScreen, handleToggleFullScreen, handleBackToLibrary, handleRightPanelSelect, handleRate, handleDeleteSelected, handleCopyAdjustments, handlePasteAdjustments, multiSelectedPaths, copiedFilePaths, handlePasteFiles, libraryActivePath, handleImageSelect, zoom, handleZoomChange, customEscapeHandler, activeMaskId, aiTool, isViewLoading, activeRightPanel, canRedo, canUndo, setAiTool, setActiveMaskId, setCopiedFilePaths, setIsWaveformVisible, setLibraryActivePath, setMultiSelectedPaths, setShowOriginal ]);
+  }, [ sortedImageList, selectedImage, undo, redo, isFullScreen, handleToggleFullScreen, handleBackToLibrary, handleRightPanelSelect, handleRate, handleToggleCullingMode, handleDeleteSelected, handleCopyAdjustments, handlePasteAdjustments, multiSelectedPaths, copiedFilePaths, handlePasteFiles, libraryActivePath, handleImageSelect, zoom, handleZoomChange, customEscapeHandler, activeMaskId, aiTool, isViewLoading, activeRightPanel, canRedo, canUndo, setAiTool, setActiveMaskId, setCopiedFilePaths, setIsWaveformVisible, setLibraryActivePath, setMultiSelectedPaths, setShowClipping, setShowOriginal, setCompareMode, adjustments ]);
 };
\ No newline at end of file
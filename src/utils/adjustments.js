@@ -8,11 +8,30 @@ const INITIAL_COLOR_GRADING = {
   balance: 0,
 };
 
+const INITIAL_DEFRINGE = {
+  purpleAmount: 0,
+  purpleHue: 290,
+  purpleRange: 60,
+  greenAmount: 0,
+  greenHue: 120,
+  greenRange: 60,
+};
+
+const INITIAL_LEVELS_CHANNEL = { inBlack: 0, inWhite: 255, gamma: 1, outBlack: 0, outWhite: 255 };
+
+const INITIAL_SPLIT_TONING = {
+  shadowHue: 220,
+  shadowSaturation: 0,
+  highlightHue: 40,
+  highlightSaturation: 0,
+  balance: 0,
+};
+
 export const INITIAL_MASK_ADJUSTMENTS = {
   exposure: 0, contrast: 0, highlights: 0, shadows: 0, whites: 0, blacks: 0,
   saturation: 0, temperature: 0, tint: 0, vibrance: 0,
   sharpness: 0, lumaNoiseReduction: 0, colorNoiseReduction: 0,
-  clarity: 0, dehaze: 0, structure: 0,
+  clarity: 0, dehaze: 0, structure: 0, texture: 0,
   colorGrading: { ...INITIAL_COLOR_GRADING },
   hsl: {
     reds: { hue: 0, saturation: 0, luminance: 0 }, oranges: { hue: 0, saturation: 0, luminance: 0 },
@@ -24,6 +43,7 @@ export const INITIAL_MASK_ADJUSTMENTS = {
     luma: [{ x: 0, y: 0 }, { x: 255, y: 255 }], red: [{ x: 0, y: 0 }, { x: 255, y: 255 }],
     green: [{ x: 0, y: 0 }, { x: 255, y: 255 }], blue: [{ x: 0, y: 0 }, { x: 255, y: 255 }],
   },
+  lumaCurveMode: 'rgb',
   sectionVisibility: {
     basic: true,
     curves: true,
@@ -31,6 +51,39 @@ export const INITIAL_MASK_ADJUSTMENTS = {
     details: true,
     effects: true,
   },
+  enabledGroups: {
+    tone: true,
+    curves: true,
+    whiteBalance: true,
+    saturation: true,
+    hsl: true,
+    colorGrading: true,
+    sharpening: true,
+    noiseReduction: true,
+    presence: true,
+    vignette: true,
+    grain: true,
+  },
+};
+
+// `enabled` stays null until the user toggles it, which the backend takes as on for RAW files
+// and off for JPEGs and other formats that are usually corrected in camera.
+export const INITIAL_LENS_CORRECTION = {
+  enabled: null,
+  distortion: true,
+  vignetting: true,
+  tca: true,
+  lens: null,
+  focalLength: null,
+  aperture: null,
+};
+
+// Applied to the raw channels while developing; temperature and tint above only shift the
+// rendered colors.
+export const INITIAL_RAW_WHITE_BALANCE = {
+  mode: 'asShot',
+  temperature: 5500,
+  tint: 0,
 };
 
 export const INITIAL_MASK_CONTAINER = {
@@ -45,8 +98,9 @@ export const INITIAL_ADJUSTMENTS = {
   rating: 0,
   exposure: 0, contrast: 0, highlights: 0, shadows: 0, whites: 0, blacks: 0,
   saturation: 0, temperature: 0, tint: 0, vibrance: 0,
-  sharpness: 0, lumaNoiseReduction: 0, colorNoiseReduction: 0,
-  clarity: 0, dehaze: 0, structure: 0,
+  sharpness: 0, sharpenRadius: 1.0, sharpenDetail: 25, sharpenMasking: 0,
+  lumaNoiseReduction: 0, lumaNoiseReductionDetail: 50, colorNoiseReduction: 0, colorNoiseReductionSmoothness: 50,
+  clarity: 0, dehaze: 0, structure: 0, texture: 0,
   vignetteAmount: 0, vignetteMidpoint: 50, vignetteRoundness: 0, vignetteFeather: 50,
   grainAmount: 0, grainSize: 25, grainRoughness: 50,
   enableNegativeConversion: false,
@@ -55,6 +109,9 @@ export const INITIAL_ADJUSTMENTS = {
   negativeGreenBalance: 0,
   negativeBlueBalance: 0,
   colorGrading: { ...INITIAL_COLOR_GRADING },
+  splitToning: { ...INITIAL_SPLIT_TONING },
+  defringe: { ...INITIAL_DEFRINGE },
+  lut: { path: null, hash: null, name: null, intensity: 100 },
   hsl: {
     reds: { hue: 0, saturation: 0, luminance: 0 }, oranges: { hue: 0, saturation: 0, luminance: 0 },
     yellows: { hue: 0, saturation: 0, luminance: 0 }, greens: { hue: 0, saturation: 0, luminance: 0 },
@@ -65,7 +122,23 @@ export const INITIAL_ADJUSTMENTS = {
     luma: [{ x: 0, y: 0 }, { x: 255, y: 255 }], red: [{ x: 0, y: 0 }, { x: 255, y: 255 }],
     green: [{ x: 0, y: 0 }, { x: 255, y: 255 }], blue: [{ x: 0, y: 0 }, { x: 255, y: 255 }],
   },
-  crop: null, aspectRatio: null, rotation: 0, flipHorizontal: false, flipVertical: false,
+  lumaCurveMode: 'rgb',
+  hueCurves: { hueVsHue: [], hueVsSat: [], lumVsSat: [] },
+  calibration: { redHue: 0, redSaturation: 0, greenHue: 0, greenSaturation: 0, blueHue: 0, blueSaturation: 0 },
+  levels: {
+    luma: { ...INITIAL_LEVELS_CHANNEL }, red: { ...INITIAL_LEVELS_CHANNEL },
+    green: { ...INITIAL_LEVELS_CHANNEL }, blue: { ...INITIAL_LEVELS_CHANNEL },
+  },
+  crop: null, aspectRatio: null, rotation: 0, flipHorizontal: false, flipVertical: false, lensDistortion: 0,
+  demosaic: null,
+  toneMapper: null,
+  rawWhiteBalance: { ...INITIAL_RAW_WHITE_BALANCE },
+  rawLevels: { blackLevel: null, whiteLevel: null },
+  monochromeSensor: false,
+  falseColorSuppression: false,
+  bandingCorrection: false,
+  showFullSensorArea: false,
+  lensCorrection: { ...INITIAL_LENS_CORRECTION },
   masks: [],
   aiPatches: [],
   sectionVisibility: {
@@ -75,6 +148,25 @@ export const INITIAL_ADJUSTMENTS = {
     details: true,
     effects: true,
   },
+  enabledGroups: {
+    tone: true,
+    curves: true,
+    whiteBalance: true,
+    saturation: true,
+    hsl: true,
+    colorGrading: true,
+    splitToning: true,
+    sharpening: true,
+    noiseReduction: true,
+    defringe: true,
+    presence: true,
+    vignette: true,
+    grain: true,
+    lut: true,
+    levels: true,
+    hueCurves: true,
+    calibration: true,
+  },
 };
 
 export const normalizeLoadedAdjustments = (loadedAdjustments) => {
@@ -102,6 +194,10 @@ export const normalizeLoadedAdjustments = (loadedAdjustments) => {
           ...INITIAL_MASK_ADJUSTMENTS.sectionVisibility,
           ...(containerAdjustments.sectionVisibility || {})
         },
+        enabledGroups: {
+          ...INITIAL_MASK_ADJUSTMENTS.enabledGroups,
+          ...(containerAdjustments.enabledGroups || {})
+        },
       },
       subMasks: normalizedSubMasks,
     };
@@ -116,37 +212,63 @@ export const normalizeLoadedAdjustments = (loadedAdjustments) => {
     ...INITIAL_ADJUSTMENTS,
     ...loadedAdjustments,
     colorGrading: { ...INITIAL_ADJUSTMENTS.colorGrading, ...(loadedAdjustments.colorGrading || {}) },
+    splitToning: { ...INITIAL_ADJUSTMENTS.splitToning, ...(loadedAdjustments.splitToning || {}) },
+    defringe: { ...INITIAL_ADJUSTMENTS.defringe, ...(loadedAdjustments.defringe || {}) },
+    lut: { ...INITIAL_ADJUSTMENTS.lut, ...(loadedAdjustments.lut || {}) },
     hsl: { ...INITIAL_ADJUSTMENTS.hsl, ...(loadedAdjustments.hsl || {}) },
+    hueCurves: { ...INITIAL_ADJUSTMENTS.hueCurves, ...(loadedAdjustments.hueCurves || {}) },
+    calibration: { ...INITIAL_ADJUSTMENTS.calibration, ...(loadedAdjustments.calibration || {}) },
     curves: { ...INITIAL_ADJUSTMENTS.curves, ...(loadedAdjustments.curves || {}) },
+    levels: { ...INITIAL_ADJUSTMENTS.levels, ...(loadedAdjustments.levels || {}) },
+    rawWhiteBalance: { ...INITIAL_RAW_WHITE_BALANCE, ...(loadedAdjustments.rawWhiteBalance || {}) },
+    rawLevels: { ...INITIAL_ADJUSTMENTS.rawLevels, ...(loadedAdjustments.rawLevels || {}) },
+    lensCorrection: { ...INITIAL_LENS_CORRECTION, ...(loadedAdjustments.lensCorrection || {}) },
     masks: normalizedMasks,
     aiPatches: normalizedAiPatches,
     sectionVisibility: {
       ...INITIAL_ADJUSTMENTS.sectionVisibility,
       ...(loadedAdjustments.sectionVisibility || {})
     },
+    enabledGroups: {
+      ...INITIAL_ADJUSTMENTS.enabledGroups,
+      ...(loadedAdjustments.enabledGroups || {})
+    },
   };
 };
 
 export const COPYABLE_ADJUSTMENT_KEYS = [
-  'exposure', 'contrast', 'highlights', 'shadows', 'whites', 'blacks',
+  'exposure', 'contrast', 'highlights', 'shadows', 'whites', 'blacks', 'toneMapper',
   'saturation', 'temperature', 'tint', 'vibrance',
-  'sharpness', 'lumaNoiseReduction', 'colorNoiseReduction',
-  'clarity', 'dehaze', 'structure',
+  'sharpness', 'sharpenRadius', 'sharpenDetail', 'sharpenMasking', 'lumaNoiseReduction', 'lumaNoiseReductionDetail', 'colorNoiseReduction', 'colorNoiseReductionSmoothness', 'defringe', 'demosaic', 'rawWhiteBalance', 'rawLevels', 'monochromeSensor', 'falseColorSuppression', 'bandingCorrection', 'showFullSensorArea', 'lensCorrection', 'lensDistortion',
+  'texture', 'clarity', 'dehaze', 'structure',
   'vignetteAmount', 'vignetteMidpoint', 'vignetteRoundness', 'vignetteFeather',
   'grainAmount', 'grainSize', 'grainRoughness',
-  'enableNegativeConversion', 'filmBaseColor', 'negativeRedBalance', 'negativeGreenBalance', 'negativeBlueBalance',
-  'hsl', 'curves', 'colorGrading', 'sectionVisibility',
+  'enableNegativeConversion', 'filmBaseColor', 'negativeRedBalance', 'negativeGreenBalance', 'negativeBlueBalance', 'lut',
+  'hsl', 'hueCurves', 'calibration', 'curves', 'lumaCurveMode', 'levels', 'colorGrading', 'splitToning', 'sectionVisibility', 'enabledGroups',
 ];
 
+// Changes to these need the base image loaded again instead of only a new render.
+export const getBaseImageKey = (adjustments) => JSON.stringify({
+  demosaic: adjustments.demosaic ?? null,
+  toneMapper: adjustments.toneMapper ?? null,
+  rawWhiteBalance: adjustments.rawWhiteBalance?.mode === 'asShot' ? null : adjustments.rawWhiteBalance ?? null,
+  rawLevels: adjustments.rawLevels ?? null,
+  monochromeSensor: !!adjustments.monochromeSensor,
+  falseColorSuppression: !!adjustments.falseColorSuppression,
+  bandingCorrection: !!adjustments.bandingCorrection,
+  showFullSensorArea: !!adjustments.showFullSensorArea,
+  lensCorrection: adjustments.lensCorrection ?? null,
+});
+
 export const ADJUSTMENT_SECTIONS = {
-  basic: ['exposure', 'contrast', 'highlights', 'shadows', 'whites', 'blacks'],
-  curves: ['curves'],
-  color: ['saturation', 'temperature', 'tint', 'vibrance', 'hsl', 'colorGrading'],
-  details: ['sharpness', 'lumaNoiseReduction', 'colorNoiseReduction'],
+  basic: ['exposure', 'contrast', 'highlights', 'shadows', 'whites', 'blacks', 'toneMapper'],
+  curves: ['curves', 'lumaCurveMode', 'levels'],
+  color: ['saturation', 'temperature', 'tint', 'rawWhiteBalance', 'vibrance', 'hsl', 'hueCurves', 'colorGrading', 'splitToning', 'calibration'],
+  details: ['sharpness', 'sharpenRadius', 'sharpenDetail', 'sharpenMasking', 'lumaNoiseReduction', 'lumaNoiseReductionDetail', 'colorNoiseReduction', 'colorNoiseReductionSmoothness', 'defringe', 'demosaic', 'rawLevels', 'monochromeSensor', 'falseColorSuppression', 'bandingCorrection', 'showFullSensorArea'],
   effects: [
-    'clarity', 'dehaze', 'structure',
+    'texture', 'clarity', 'dehaze', 'structure',
     'vignetteAmount', 'vignetteMidpoint', 'vignetteRoundness', 'vignetteFeather',
     'grainAmount', 'grainSize', 'grainRoughness',
-    'enableNegativeConversion', 'filmBaseColor', 'negativeRedBalance', 'negativeGreenBalance', 'negativeBlueBalance'
+    'enableNegativeConversion', 'filmBaseColor', 'negativeRedBalance', 'negativeGreenBalance', 'negativeBlueBalance', 'lut',
   ],
 };
\ No newline at end of file